@@ -0,0 +1,13 @@
+# Sets the name of a bossbar to a string from memory
+# i8* str == %param0%0
+# i32 len == %param1%0
+# i32 bar == %param2%0
+
+function intrinsic:print_str/capture
+
+execute if score %param2%0 reg matches 0 run bossbar set wasmrunner:bar0 name {"nbt":"buffer","storage":"wasm:stdout","interpret":true}
+execute if score %param2%0 reg matches 1 run bossbar set wasmrunner:bar1 name {"nbt":"buffer","storage":"wasm:stdout","interpret":true}
+execute if score %param2%0 reg matches 2 run bossbar set wasmrunner:bar2 name {"nbt":"buffer","storage":"wasm:stdout","interpret":true}
+execute if score %param2%0 reg matches 3 run bossbar set wasmrunner:bar3 name {"nbt":"buffer","storage":"wasm:stdout","interpret":true}
+
+function intrinsic:print_str/restore
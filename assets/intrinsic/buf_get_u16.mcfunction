@@ -0,0 +1,20 @@
+# %param0%0 - The address of the buffer
+# %param1%0 - The offset of the halfword in the buffer, in bytes
+# %param0%0 - The return value
+# Clobbers %param1%0, %return%0, and %ptr
+
+scoreboard players operation %ptr reg = %param0%0 reg
+scoreboard players operation %ptr reg += %param1%0 reg
+function intrinsic:setptr
+
+# %param1%0 = the position of the halfword's low byte in the word (0 to 3)
+scoreboard players operation %param1%0 reg = %ptr reg
+scoreboard players operation %param1%0 reg %= %%4 reg
+
+execute at 44453000-0-0-0-1 store result score %param0%0 reg run data get block ~ ~ ~ RecordItem.tag.Memory 1
+
+execute if score %param1%0 reg matches 1 run scoreboard players operation %param0%0 reg /= %%256 reg
+execute if score %param1%0 reg matches 2 run scoreboard players operation %param0%0 reg /= %%65536 reg
+
+execute unless score %param1%0 reg matches 3 run scoreboard players operation %param0%0 reg %= %%65536 reg
+execute if score %param1%0 reg matches 3 run function intrinsic:buf_get_u16/crossing
@@ -0,0 +1,15 @@
+# Reads a halfword whose low byte is the last byte of the word the memory pointer is at,
+# and whose high byte is the first byte of the next word
+# %param0%0 - The word the memory pointer is at, which is replaced by the halfword
+# Clobbers %return%0 and %ptr
+
+scoreboard players operation %param0%0 reg /= %%16777216 reg
+scoreboard players operation %param0%0 reg %= %%256 reg
+
+scoreboard players add %ptr reg 1
+function intrinsic:setptr
+
+execute at 44453000-0-0-0-1 store result score %return%0 reg run data get block ~ ~ ~ RecordItem.tag.Memory 1
+scoreboard players operation %return%0 reg %= %%256 reg
+scoreboard players operation %return%0 reg *= %%256 reg
+scoreboard players operation %param0%0 reg += %return%0 reg
@@ -0,0 +1,17 @@
+# %param0%0 - The address of the buffer
+# %param1%0 - The offset of the halfword in the buffer, in bytes
+# %param2%0 - The halfword to write
+# Clobbers %param0%0, %param1%0, %param2%0, %return%0, and %ptr
+
+scoreboard players operation %ptr reg = %param0%0 reg
+scoreboard players operation %ptr reg += %param1%0 reg
+function intrinsic:setptr
+
+# %param1%0 = the position of the halfword's low byte in the word (0 to 3)
+scoreboard players operation %param1%0 reg = %ptr reg
+scoreboard players operation %param1%0 reg %= %%4 reg
+
+scoreboard players operation %param2%0 reg %= %%65536 reg
+
+execute unless score %param1%0 reg matches 3 run function intrinsic:buf_set_u16/within
+execute if score %param1%0 reg matches 3 run function intrinsic:buf_set_u16/crossing
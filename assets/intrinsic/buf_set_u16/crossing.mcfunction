@@ -0,0 +1,31 @@
+# Writes a halfword whose low byte is the last byte of the word the memory pointer is at,
+# and whose high byte is the first byte of the next word
+# %param2%0 - The halfword to write, from 0 to 65535
+
+execute at 44453000-0-0-0-1 store result score %param0%0 reg run data get block ~ ~ ~ RecordItem.tag.Memory 1
+
+# word += (new low byte - old last byte) << 24
+scoreboard players operation %return%0 reg = %param0%0 reg
+scoreboard players operation %return%0 reg /= %%16777216 reg
+scoreboard players operation %return%0 reg %= %%256 reg
+scoreboard players operation %%temp0_buf_set_u16 reg = %param2%0 reg
+scoreboard players operation %%temp0_buf_set_u16 reg %= %%256 reg
+scoreboard players operation %%temp0_buf_set_u16 reg -= %return%0 reg
+scoreboard players operation %%temp0_buf_set_u16 reg *= %%16777216 reg
+scoreboard players operation %param0%0 reg += %%temp0_buf_set_u16 reg
+
+execute at 44453000-0-0-0-1 store result block ~ ~ ~ RecordItem.tag.Memory int 1 run scoreboard players get %param0%0 reg
+
+scoreboard players add %ptr reg 1
+function intrinsic:setptr
+
+execute at 44453000-0-0-0-1 store result score %param0%0 reg run data get block ~ ~ ~ RecordItem.tag.Memory 1
+
+# next word += new high byte - old first byte
+scoreboard players operation %return%0 reg = %param0%0 reg
+scoreboard players operation %return%0 reg %= %%256 reg
+scoreboard players operation %param2%0 reg /= %%256 reg
+scoreboard players operation %param2%0 reg -= %return%0 reg
+scoreboard players operation %param0%0 reg += %param2%0 reg
+
+execute at 44453000-0-0-0-1 store result block ~ ~ ~ RecordItem.tag.Memory int 1 run scoreboard players get %param0%0 reg
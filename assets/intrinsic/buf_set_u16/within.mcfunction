@@ -0,0 +1,19 @@
+# Writes a halfword that is entirely in the word the memory pointer is at
+# %param1%0 - The position of the halfword's low byte in the word (0 to 2)
+# %param2%0 - The halfword to write, from 0 to 65535
+
+execute at 44453000-0-0-0-1 store result score %param0%0 reg run data get block ~ ~ ~ RecordItem.tag.Memory 1
+
+# %return%0 = old halfword
+scoreboard players operation %return%0 reg = %param0%0 reg
+execute if score %param1%0 reg matches 1 run scoreboard players operation %return%0 reg /= %%256 reg
+execute if score %param1%0 reg matches 2 run scoreboard players operation %return%0 reg /= %%65536 reg
+scoreboard players operation %return%0 reg %= %%65536 reg
+
+# word += (new halfword - old halfword) << (8 * %param1%0)
+scoreboard players operation %param2%0 reg -= %return%0 reg
+execute if score %param1%0 reg matches 1 run scoreboard players operation %param2%0 reg *= %%256 reg
+execute if score %param1%0 reg matches 2 run scoreboard players operation %param2%0 reg *= %%65536 reg
+scoreboard players operation %param0%0 reg += %param2%0 reg
+
+execute at 44453000-0-0-0-1 store result block ~ ~ ~ RecordItem.tag.Memory int 1 run scoreboard players get %param0%0 reg
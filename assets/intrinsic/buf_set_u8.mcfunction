@@ -0,0 +1,31 @@
+# %param0%0 - The address of the buffer
+# %param1%0 - The offset of the byte in the buffer
+# %param2%0 - The byte to write
+# Clobbers %param0%0, %param1%0, %param2%0, %return%0, and %ptr
+
+scoreboard players operation %ptr reg = %param0%0 reg
+scoreboard players operation %ptr reg += %param1%0 reg
+function intrinsic:setptr
+
+# %param1%0 = the position of the byte in the word (0 to 3)
+scoreboard players operation %param1%0 reg = %ptr reg
+scoreboard players operation %param1%0 reg %= %%4 reg
+
+execute at 44453000-0-0-0-1 store result score %param0%0 reg run data get block ~ ~ ~ RecordItem.tag.Memory 1
+
+# %return%0 = old byte
+scoreboard players operation %return%0 reg = %param0%0 reg
+execute if score %param1%0 reg matches 1 run scoreboard players operation %return%0 reg /= %%256 reg
+execute if score %param1%0 reg matches 2 run scoreboard players operation %return%0 reg /= %%65536 reg
+execute if score %param1%0 reg matches 3 run scoreboard players operation %return%0 reg /= %%16777216 reg
+scoreboard players operation %return%0 reg %= %%256 reg
+
+# word += (new byte - old byte) << (8 * %param1%0)
+scoreboard players operation %param2%0 reg %= %%256 reg
+scoreboard players operation %param2%0 reg -= %return%0 reg
+execute if score %param1%0 reg matches 1 run scoreboard players operation %param2%0 reg *= %%256 reg
+execute if score %param1%0 reg matches 2 run scoreboard players operation %param2%0 reg *= %%65536 reg
+execute if score %param1%0 reg matches 3 run scoreboard players operation %param2%0 reg *= %%16777216 reg
+scoreboard players operation %param0%0 reg += %param2%0 reg
+
+execute at 44453000-0-0-0-1 store result block ~ ~ ~ RecordItem.tag.Memory int 1 run scoreboard players get %param0%0 reg
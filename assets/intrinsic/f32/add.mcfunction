@@ -0,0 +1,12 @@
+# %param0%0 - lhs
+# %param1%0 - rhs
+# %return%0 - lhs + rhs
+
+function intrinsic:f32/unpack_lhs
+function intrinsic:f32/unpack_rhs
+
+execute store success score %f32_special reg if score %f32_exp_a reg matches 255
+execute if score %f32_exp_b reg matches 255 run scoreboard players set %f32_special reg 1
+
+execute if score %f32_special reg matches 1 run function intrinsic:f32/add_special
+execute if score %f32_special reg matches 0 run function intrinsic:f32/add_finite
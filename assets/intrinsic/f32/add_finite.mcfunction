@@ -0,0 +1,24 @@
+# Make sure lhs has the larger magnitude, so the result has its sign and exponent
+execute if score %f32_abs_a reg < %f32_abs_b reg run function intrinsic:f32/add_swap
+
+scoreboard players operation %f32_sign reg = %f32_sign_a reg
+scoreboard players operation %f32_e reg = %f32_e_a reg
+
+# m = m_a << 6
+scoreboard players operation %f32_m reg = %f32_m_a reg
+scoreboard players operation %f32_m reg *= %%64 reg
+
+# Line up rhs with lhs
+scoreboard players operation %f32_sh_val reg = %f32_m_b reg
+scoreboard players operation %f32_sh_val reg *= %%64 reg
+scoreboard players operation %f32_sh_amt reg = %f32_e_a reg
+scoreboard players operation %f32_sh_amt reg -= %f32_e_b reg
+function intrinsic:f32/shift_right_sticky
+
+execute if score %f32_sign_a reg = %f32_sign_b reg run scoreboard players operation %f32_m reg += %f32_sh_val reg
+execute unless score %f32_sign_a reg = %f32_sign_b reg run scoreboard players operation %f32_m reg -= %f32_sh_val reg
+
+# x + -x is +0
+execute if score %f32_m reg matches 0 unless score %f32_sign_a reg = %f32_sign_b reg run scoreboard players set %f32_sign reg 0
+
+function intrinsic:f32/pack
@@ -0,0 +1,10 @@
+# Infinity plus anything finite is that infinity
+execute if score %f32_exp_a reg matches 255 run scoreboard players operation %return%0 reg = %param0%0 reg
+execute unless score %f32_exp_a reg matches 255 run scoreboard players operation %return%0 reg = %param1%0 reg
+
+# Infinities with opposite signs cancel out to NaN
+execute if score %f32_exp_a reg matches 255 if score %f32_exp_b reg matches 255 unless score %f32_sign_a reg = %f32_sign_b reg run scoreboard players set %return%0 reg 2143289344
+
+# NaN plus anything is NaN
+execute if score %f32_exp_a reg matches 255 unless score %f32_m_a reg matches 8388608 run scoreboard players set %return%0 reg 2143289344
+execute if score %f32_exp_b reg matches 255 unless score %f32_m_b reg matches 8388608 run scoreboard players set %return%0 reg 2143289344
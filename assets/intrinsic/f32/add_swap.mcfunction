@@ -0,0 +1,4 @@
+scoreboard players operation %f32_sign_a reg >< %f32_sign_b reg
+scoreboard players operation %f32_abs_a reg >< %f32_abs_b reg
+scoreboard players operation %f32_m_a reg >< %f32_m_b reg
+scoreboard players operation %f32_e_a reg >< %f32_e_b reg
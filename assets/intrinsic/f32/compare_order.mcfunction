@@ -0,0 +1,19 @@
+# %param0%0 - lhs
+# %param1%0 - rhs
+# %f32_rhs_less - 1 if rhs is less than lhs, counting -0 as less than +0, otherwise 0
+# %f32_nan - 1 if either of them is NaN, otherwise 0
+
+# Flipping every bit but the sign of a negative float makes its bits order the same way as its value
+scoreboard players operation %f32_key_a reg = %param0%0 reg
+execute if score %param0%0 reg matches ..-1 run scoreboard players set %f32_key_a reg 2147483647
+execute if score %param0%0 reg matches ..-1 run scoreboard players operation %f32_key_a reg -= %param0%0 reg
+scoreboard players operation %f32_key_b reg = %param1%0 reg
+execute if score %param1%0 reg matches ..-1 run scoreboard players set %f32_key_b reg 2147483647
+execute if score %param1%0 reg matches ..-1 run scoreboard players operation %f32_key_b reg -= %param1%0 reg
+
+execute store success score %f32_rhs_less reg if score %f32_key_b reg < %f32_key_a reg
+
+execute store success score %f32_nan reg if score %param0%0 reg matches 2139095041..
+execute if score %param0%0 reg matches -8388607..-1 run scoreboard players set %f32_nan reg 1
+execute if score %param1%0 reg matches 2139095041.. run scoreboard players set %f32_nan reg 1
+execute if score %param1%0 reg matches -8388607..-1 run scoreboard players set %f32_nan reg 1
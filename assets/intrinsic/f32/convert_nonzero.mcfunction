@@ -0,0 +1,15 @@
+# Move the leading bit up to bit 55, then shift it back down to bit 29 where intrinsic:f32/pack wants it
+execute if score %f64_mh reg matches 0 run function intrinsic:f64/normalize_limb
+execute if score %f64_mh reg matches ..134217727 run function intrinsic:f64/normalize
+
+scoreboard players operation %f64_sh_h reg = %f64_mh reg
+scoreboard players operation %f64_sh_l reg = %f64_ml reg
+scoreboard players set %f64_sh_amt reg 26
+function intrinsic:f64/shift_right_sticky
+scoreboard players operation %f32_m reg = %f64_sh_h reg
+scoreboard players operation %f32_m reg *= %%268435456 reg
+scoreboard players operation %f32_m reg += %f64_sh_l reg
+
+# e = e - 1078 + 26 + 156
+scoreboard players operation %f32_e reg = %f64_e reg
+scoreboard players remove %f32_e reg 896
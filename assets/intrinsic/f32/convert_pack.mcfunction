@@ -0,0 +1,8 @@
+# Rounds the result of intrinsic:f64/convert_magnitude to a float
+
+scoreboard players operation %f32_sign reg = %f64_sign reg
+scoreboard players set %f32_m reg 0
+execute store success score %f64_nonzero reg unless score %f64_mh reg matches 0
+execute unless score %f64_ml reg matches 0 run scoreboard players set %f64_nonzero reg 1
+execute if score %f64_nonzero reg matches 1 run function intrinsic:f32/convert_nonzero
+function intrinsic:f32/pack
@@ -0,0 +1,7 @@
+# %param0%0, %param0%1 - a signed 64-bit integer
+# %return%0 - the nearest float
+
+execute store success score %f64_sign reg if score %param0%1 reg matches ..-1
+execute if score %f64_sign reg matches 1 run function intrinsic:f64/convert_negate
+function intrinsic:f64/convert_magnitude
+function intrinsic:f32/convert_pack
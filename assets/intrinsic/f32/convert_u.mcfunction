@@ -0,0 +1,6 @@
+# %param0%0, %param0%1 - a unsigned 64-bit integer
+# %return%0 - the nearest float
+
+scoreboard players set %f64_sign reg 0
+function intrinsic:f64/convert_magnitude
+function intrinsic:f32/convert_pack
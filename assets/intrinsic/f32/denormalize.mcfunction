@@ -0,0 +1,7 @@
+# m >>= 1 - e, e = 1
+scoreboard players set %f32_sh_amt reg 1
+scoreboard players operation %f32_sh_amt reg -= %f32_e reg
+scoreboard players operation %f32_sh_val reg = %f32_m reg
+function intrinsic:f32/shift_right_sticky
+scoreboard players operation %f32_m reg = %f32_sh_val reg
+scoreboard players set %f32_e reg 1
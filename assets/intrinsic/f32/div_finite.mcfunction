@@ -0,0 +1,30 @@
+execute if score %f32_m_a reg matches ..8388607 run function intrinsic:f32/normalize_lhs
+execute if score %f32_m_b reg matches ..8388607 run function intrinsic:f32/normalize_rhs
+
+# Long division, 7 bits at a time so the remainder never overflows:
+#	m = (m_a << 30) / m_b
+
+scoreboard players operation %f32_m reg = %f32_m_a reg
+scoreboard players operation %f32_m reg /= %f32_m_b reg
+scoreboard players operation %f32_rem reg = %f32_m_a reg
+scoreboard players operation %f32_rem reg %= %f32_m_b reg
+
+scoreboard players operation %f32_div_step reg = %%128 reg
+function intrinsic:f32/div_step
+function intrinsic:f32/div_step
+function intrinsic:f32/div_step
+function intrinsic:f32/div_step
+scoreboard players operation %f32_div_step reg = %%4 reg
+function intrinsic:f32/div_step
+
+# Anything left over becomes the sticky bit
+scoreboard players operation %f32_odd reg = %f32_m reg
+scoreboard players operation %f32_odd reg %= %%2 reg
+execute unless score %f32_rem reg matches 0 if score %f32_odd reg matches 0 run scoreboard players add %f32_m reg 1
+
+# e = e_a - e_b + 126
+scoreboard players operation %f32_e reg = %f32_e_a reg
+scoreboard players operation %f32_e reg -= %f32_e_b reg
+scoreboard players add %f32_e reg 126
+
+function intrinsic:f32/pack
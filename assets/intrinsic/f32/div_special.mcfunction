@@ -0,0 +1,14 @@
+# Zero divided by anything or anything divided by infinity is zero,
+# and infinity divided by anything or anything divided by zero is infinity
+scoreboard players set %return%0 reg 0
+execute if score %f32_exp_a reg matches 255 run scoreboard players set %return%0 reg 2139095040
+execute if score %f32_m_b reg matches 0 run scoreboard players set %return%0 reg 2139095040
+execute if score %f32_sign reg matches 1 run scoreboard players operation %return%0 reg += %%-2147483648 reg
+
+# 0 / 0 and inf / inf are NaN
+execute if score %f32_m_a reg matches 0 if score %f32_m_b reg matches 0 run scoreboard players set %return%0 reg 2143289344
+execute if score %f32_exp_a reg matches 255 if score %f32_exp_b reg matches 255 run scoreboard players set %return%0 reg 2143289344
+
+# NaN divided by anything is NaN
+execute if score %f32_exp_a reg matches 255 unless score %f32_m_a reg matches 8388608 run scoreboard players set %return%0 reg 2143289344
+execute if score %f32_exp_b reg matches 255 unless score %f32_m_b reg matches 8388608 run scoreboard players set %return%0 reg 2143289344
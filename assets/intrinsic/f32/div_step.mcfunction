@@ -0,0 +1,9 @@
+# m = m * step + (rem * step) / m_b
+# rem = (rem * step) % m_b
+
+scoreboard players operation %f32_rem reg *= %f32_div_step reg
+scoreboard players operation %f32_m reg *= %f32_div_step reg
+scoreboard players operation %f32_tmp reg = %f32_rem reg
+scoreboard players operation %f32_tmp reg /= %f32_m_b reg
+scoreboard players operation %f32_m reg += %f32_tmp reg
+scoreboard players operation %f32_rem reg %= %f32_m_b reg
@@ -0,0 +1,9 @@
+# %param0%0 - lhs
+# %param1%0 - rhs
+# %return%0 - the larger of lhs and rhs, or NaN if either of them is NaN
+
+function intrinsic:f32/compare_order
+
+scoreboard players operation %return%0 reg = %param1%0 reg
+execute if score %f32_rhs_less reg matches 1 run scoreboard players operation %return%0 reg = %param0%0 reg
+execute if score %f32_nan reg matches 1 run scoreboard players set %return%0 reg 2143289344
@@ -0,0 +1,10 @@
+# %param0%0 - lhs
+# %param1%0 - rhs
+# %return%0 - lhs * rhs
+
+function intrinsic:f32/unpack_lhs
+function intrinsic:f32/unpack_rhs
+function intrinsic:f32/sign_product
+
+execute if score %f32_special reg matches 1 run function intrinsic:f32/mul_special
+execute if score %f32_special reg matches 0 run function intrinsic:f32/mul_finite
@@ -0,0 +1,54 @@
+execute if score %f32_m_a reg matches ..8388607 run function intrinsic:f32/normalize_lhs
+execute if score %f32_m_b reg matches ..8388607 run function intrinsic:f32/normalize_rhs
+
+# Both significands are 24 bits, so the 48-bit product is built from 12-bit halves:
+#	p = (ah * bh << 24) + ((ah * bl + al * bh) << 12) + al * bl
+#	p = (hi << 24) + lo
+
+scoreboard players operation %f32_ah reg = %f32_m_a reg
+scoreboard players operation %f32_ah reg /= %%4096 reg
+scoreboard players operation %f32_al reg = %f32_m_a reg
+scoreboard players operation %f32_al reg %= %%4096 reg
+scoreboard players operation %f32_bh reg = %f32_m_b reg
+scoreboard players operation %f32_bh reg /= %%4096 reg
+scoreboard players operation %f32_bl reg = %f32_m_b reg
+scoreboard players operation %f32_bl reg %= %%4096 reg
+
+scoreboard players operation %f32_hi reg = %f32_ah reg
+scoreboard players operation %f32_hi reg *= %f32_bh reg
+scoreboard players operation %f32_lo reg = %f32_al reg
+scoreboard players operation %f32_lo reg *= %f32_bl reg
+scoreboard players operation %f32_mid reg = %f32_ah reg
+scoreboard players operation %f32_mid reg *= %f32_bl reg
+scoreboard players operation %f32_tmp reg = %f32_al reg
+scoreboard players operation %f32_tmp reg *= %f32_bh reg
+scoreboard players operation %f32_mid reg += %f32_tmp reg
+
+scoreboard players operation %f32_tmp reg = %f32_mid reg
+scoreboard players operation %f32_tmp reg %= %%4096 reg
+scoreboard players operation %f32_tmp reg *= %%4096 reg
+scoreboard players operation %f32_lo reg += %f32_tmp reg
+scoreboard players operation %f32_mid reg /= %%4096 reg
+scoreboard players operation %f32_hi reg += %f32_mid reg
+scoreboard players operation %f32_tmp reg = %f32_lo reg
+scoreboard players operation %f32_tmp reg /= %%16777216 reg
+scoreboard players operation %f32_hi reg += %f32_tmp reg
+scoreboard players operation %f32_lo reg %= %%16777216 reg
+
+# m = p >> 17, keeping the lost bits as a sticky bit
+scoreboard players operation %f32_m reg = %f32_hi reg
+scoreboard players operation %f32_m reg *= %%128 reg
+scoreboard players operation %f32_tmp reg = %f32_lo reg
+scoreboard players operation %f32_tmp reg /= %%131072 reg
+scoreboard players operation %f32_m reg += %f32_tmp reg
+scoreboard players operation %f32_lo reg %= %%131072 reg
+scoreboard players operation %f32_odd reg = %f32_m reg
+scoreboard players operation %f32_odd reg %= %%2 reg
+execute unless score %f32_lo reg matches 0 if score %f32_odd reg matches 0 run scoreboard players add %f32_m reg 1
+
+# e = e_a + e_b - 127
+scoreboard players operation %f32_e reg = %f32_e_a reg
+scoreboard players operation %f32_e reg += %f32_e_b reg
+scoreboard players remove %f32_e reg 127
+
+function intrinsic:f32/pack
@@ -0,0 +1,13 @@
+# Anything times zero is zero, and infinity times anything else is infinity
+scoreboard players set %return%0 reg 0
+execute if score %f32_exp_a reg matches 255 run scoreboard players set %return%0 reg 2139095040
+execute if score %f32_exp_b reg matches 255 run scoreboard players set %return%0 reg 2139095040
+execute if score %f32_sign reg matches 1 run scoreboard players operation %return%0 reg += %%-2147483648 reg
+
+# Infinity times zero is NaN
+execute if score %f32_exp_a reg matches 255 if score %f32_m_b reg matches 0 run scoreboard players set %return%0 reg 2143289344
+execute if score %f32_exp_b reg matches 255 if score %f32_m_a reg matches 0 run scoreboard players set %return%0 reg 2143289344
+
+# NaN times anything is NaN
+execute if score %f32_exp_a reg matches 255 unless score %f32_m_a reg matches 8388608 run scoreboard players set %return%0 reg 2143289344
+execute if score %f32_exp_b reg matches 255 unless score %f32_m_b reg matches 8388608 run scoreboard players set %return%0 reg 2143289344
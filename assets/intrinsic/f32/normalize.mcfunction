@@ -0,0 +1,3 @@
+scoreboard players operation %f32_m reg += %f32_m reg
+scoreboard players remove %f32_e reg 1
+execute if score %f32_m reg matches ..536870911 if score %f32_e reg matches 2.. run function intrinsic:f32/normalize
@@ -0,0 +1,4 @@
+# Moves the leading bit of a subnormal significand up to bit 23
+scoreboard players operation %f32_m_a reg += %f32_m_a reg
+scoreboard players remove %f32_e_a reg 1
+execute if score %f32_m_a reg matches ..8388607 run function intrinsic:f32/normalize_lhs
@@ -0,0 +1,4 @@
+# Moves the leading bit of a subnormal significand up to bit 23
+scoreboard players operation %f32_m_b reg += %f32_m_b reg
+scoreboard players remove %f32_e_b reg 1
+execute if score %f32_m_b reg matches ..8388607 run function intrinsic:f32/normalize_rhs
@@ -0,0 +1,10 @@
+# %f32_sign - 1 if the result is negative
+# %f32_e, %f32_m - the exact result is m * 2^(e - 156), where m must not be negative
+# %return%0 - the rounded result
+
+# A normal result has its leading bit at bit 29 of m,
+# leaving 6 extra bits below the final significand for rounding.
+
+execute if score %f32_m reg matches 0 run scoreboard players set %return%0 reg 0
+execute unless score %f32_m reg matches 0 run function intrinsic:f32/pack_nonzero
+execute if score %f32_sign reg matches 1 run scoreboard players operation %return%0 reg += %%-2147483648 reg
@@ -0,0 +1,3 @@
+# Rounding up overflowed into the next exponent, so m is exactly 1 << 24
+scoreboard players operation %f32_m reg /= %%2 reg
+scoreboard players add %f32_e reg 1
@@ -0,0 +1,5 @@
+# Replace the implicit leading bit with the exponent field
+scoreboard players operation %return%0 reg -= %%8388608 reg
+scoreboard players operation %f32_tmp reg = %f32_e reg
+scoreboard players operation %f32_tmp reg *= %%8388608 reg
+scoreboard players operation %return%0 reg += %f32_tmp reg
@@ -0,0 +1,23 @@
+# Carry out of the top
+execute if score %f32_m reg matches 1073741824.. run function intrinsic:f32/pack_shift_once
+
+# Shift the leading bit up into place, unless that would make the number subnormal
+execute if score %f32_m reg matches ..536870911 if score %f32_e reg matches 2.. run function intrinsic:f32/normalize
+
+# Too small to be a normal number
+execute if score %f32_e reg matches ..0 run function intrinsic:f32/denormalize
+
+# Round to nearest, ties to even
+scoreboard players operation %f32_round reg = %f32_m reg
+scoreboard players operation %f32_round reg %= %%64 reg
+scoreboard players operation %f32_m reg /= %%64 reg
+scoreboard players operation %f32_odd reg = %f32_m reg
+scoreboard players operation %f32_odd reg %= %%2 reg
+execute if score %f32_round reg matches 33.. run scoreboard players add %f32_m reg 1
+execute if score %f32_round reg matches 32 if score %f32_odd reg matches 1 run scoreboard players add %f32_m reg 1
+execute if score %f32_m reg matches 16777216.. run function intrinsic:f32/pack_carry
+
+# Put the fields together
+scoreboard players operation %return%0 reg = %f32_m reg
+execute if score %f32_m reg matches 8388608.. run function intrinsic:f32/pack_exponent
+execute if score %f32_e reg matches 255.. run scoreboard players set %return%0 reg 2139095040
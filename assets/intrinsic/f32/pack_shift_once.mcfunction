@@ -0,0 +1,5 @@
+scoreboard players operation %f32_sh_val reg = %f32_m reg
+scoreboard players set %f32_sh_amt reg 1
+function intrinsic:f32/shift_right_sticky
+scoreboard players operation %f32_m reg = %f32_sh_val reg
+scoreboard players add %f32_e reg 1
@@ -0,0 +1,10 @@
+# %param0%0 - the float to round
+# %f32_round_mode - 0 to round towards zero, 1 to round down, 2 to round up, 3 to round to nearest with ties to even
+# %return%0 - the float rounded to an integer
+
+function intrinsic:f32/unpack_lhs
+
+# Infinity and numbers too large to have a fraction are already integers, and NaN stays NaN
+scoreboard players operation %return%0 reg = %param0%0 reg
+execute if score %f32_exp_a reg matches 255 unless score %f32_m_a reg matches 8388608 run scoreboard players set %return%0 reg 2143289344
+execute if score %f32_e_a reg matches ..149 run function intrinsic:f32/round_fraction
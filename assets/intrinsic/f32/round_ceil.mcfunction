@@ -0,0 +1,5 @@
+# %param0%0 - the float to round
+# %return%0 - the float rounded up to an integer
+
+scoreboard players set %f32_round_mode reg 2
+function intrinsic:f32/round
@@ -0,0 +1,5 @@
+# %param0%0 - the float to round
+# %return%0 - the float rounded down to an integer
+
+scoreboard players set %f32_round_mode reg 1
+function intrinsic:f32/round
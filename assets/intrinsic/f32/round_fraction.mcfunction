@@ -0,0 +1,26 @@
+# Shift out the fraction, keeping its top bit and whether any of the rest is set in the two lowest bits:
+#	m = (m_a << 2) >> (150 - e_a)
+scoreboard players operation %f32_sh_val reg = %f32_m_a reg
+scoreboard players operation %f32_sh_val reg *= %%4 reg
+scoreboard players set %f32_sh_amt reg 150
+scoreboard players operation %f32_sh_amt reg -= %f32_e_a reg
+function intrinsic:f32/shift_right_sticky
+scoreboard players operation %f32_m reg = %f32_sh_val reg
+
+scoreboard players operation %f32_round reg = %f32_m reg
+scoreboard players operation %f32_round reg %= %%4 reg
+scoreboard players operation %f32_m reg -= %f32_round reg
+scoreboard players operation %f32_odd reg = %f32_m reg
+scoreboard players operation %f32_odd reg %= %%8 reg
+
+# Round the magnitude up if the mode calls for it
+execute if score %f32_round_mode reg matches 1 if score %f32_sign_a reg matches 1 unless score %f32_round reg matches 0 run scoreboard players add %f32_m reg 4
+execute if score %f32_round_mode reg matches 2 if score %f32_sign_a reg matches 0 unless score %f32_round reg matches 0 run scoreboard players add %f32_m reg 4
+execute if score %f32_round_mode reg matches 3 if score %f32_round reg matches 3 run scoreboard players add %f32_m reg 4
+execute if score %f32_round_mode reg matches 3 if score %f32_round reg matches 2 if score %f32_odd reg matches 4 run scoreboard players add %f32_m reg 4
+
+# The integer is m >> 2, which is exact once packed
+scoreboard players operation %f32_m reg *= %%16 reg
+scoreboard players set %f32_e reg 150
+scoreboard players operation %f32_sign reg = %f32_sign_a reg
+function intrinsic:f32/pack
@@ -0,0 +1,5 @@
+# %param0%0 - the float to round
+# %return%0 - the float rounded to the nearest integer, with ties going to the even one
+
+scoreboard players set %f32_round_mode reg 3
+function intrinsic:f32/round
@@ -0,0 +1,8 @@
+# %f32_sh_val - the value to shift right, must not be negative
+# %f32_sh_amt - how far to shift it, must not be negative (clobbered)
+
+# Any bits that are shifted out get ORed into the lowest bit,
+# so that the result still rounds the same way as the original value.
+
+execute if score %f32_sh_amt reg matches 31.. unless score %f32_sh_val reg matches 0 run scoreboard players set %f32_sh_val reg 1
+execute if score %f32_sh_amt reg matches 1..30 run function intrinsic:f32/shift_right_sticky_inner
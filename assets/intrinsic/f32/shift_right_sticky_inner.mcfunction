@@ -0,0 +1,20 @@
+# pow = 1 << amt
+
+scoreboard players set %f32_sh_pow reg 1
+execute if score %f32_sh_amt reg matches 16.. run scoreboard players operation %f32_sh_pow reg *= %%65536 reg
+execute if score %f32_sh_amt reg matches 16.. run scoreboard players remove %f32_sh_amt reg 16
+execute if score %f32_sh_amt reg matches 8.. run scoreboard players operation %f32_sh_pow reg *= %%256 reg
+execute if score %f32_sh_amt reg matches 8.. run scoreboard players remove %f32_sh_amt reg 8
+execute if score %f32_sh_amt reg matches 4.. run scoreboard players operation %f32_sh_pow reg *= %%16 reg
+execute if score %f32_sh_amt reg matches 4.. run scoreboard players remove %f32_sh_amt reg 4
+execute if score %f32_sh_amt reg matches 2.. run scoreboard players operation %f32_sh_pow reg *= %%4 reg
+execute if score %f32_sh_amt reg matches 2.. run scoreboard players remove %f32_sh_amt reg 2
+execute if score %f32_sh_amt reg matches 1 run scoreboard players operation %f32_sh_pow reg *= %%2 reg
+
+scoreboard players operation %f32_sh_rem reg = %f32_sh_val reg
+scoreboard players operation %f32_sh_rem reg %= %f32_sh_pow reg
+scoreboard players operation %f32_sh_val reg /= %f32_sh_pow reg
+
+scoreboard players operation %f32_sh_odd reg = %f32_sh_val reg
+scoreboard players operation %f32_sh_odd reg %= %%2 reg
+execute unless score %f32_sh_rem reg matches 0 if score %f32_sh_odd reg matches 0 run scoreboard players add %f32_sh_val reg 1
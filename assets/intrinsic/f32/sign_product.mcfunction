@@ -0,0 +1,9 @@
+# The sign of a product or quotient is the XOR of the signs of its operands
+scoreboard players operation %f32_sign reg = %f32_sign_a reg
+scoreboard players operation %f32_sign reg += %f32_sign_b reg
+scoreboard players operation %f32_sign reg %= %%2 reg
+
+execute store success score %f32_special reg if score %f32_exp_a reg matches 255
+execute if score %f32_exp_b reg matches 255 run scoreboard players set %f32_special reg 1
+execute if score %f32_m_a reg matches 0 run scoreboard players set %f32_special reg 1
+execute if score %f32_m_b reg matches 0 run scoreboard players set %f32_special reg 1
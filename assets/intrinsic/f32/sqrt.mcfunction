@@ -0,0 +1,11 @@
+# %param0%0 - the float to take the square root of
+# %return%0 - the square root, or NaN for numbers below zero
+
+function intrinsic:f32/unpack_lhs
+
+execute store success score %f32_special reg if score %f32_exp_a reg matches 255
+execute if score %f32_sign_a reg matches 1 run scoreboard players set %f32_special reg 1
+execute if score %f32_m_a reg matches 0 run scoreboard players set %f32_special reg 1
+
+execute if score %f32_special reg matches 1 run function intrinsic:f32/sqrt_special
+execute if score %f32_special reg matches 0 run function intrinsic:f32/sqrt_finite
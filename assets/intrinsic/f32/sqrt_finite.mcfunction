@@ -0,0 +1,30 @@
+execute if score %f32_m_a reg matches ..8388607 run function intrinsic:f32/normalize_lhs
+
+# Only an even power of two can be halved, so an odd one gives a bit to the significand
+scoreboard players operation %f32_odd reg = %f32_e_a reg
+scoreboard players operation %f32_odd reg %= %%2 reg
+execute if score %f32_odd reg matches 1 run scoreboard players operation %f32_m_a reg += %f32_m_a reg
+execute if score %f32_odd reg matches 1 run scoreboard players remove %f32_e_a reg 1
+
+# Square root one bit at a time, bringing down two bits of the radicand at each step:
+#	m = sqrt(m_a << 28)
+# m_a has at most 26 bits, so after 13 steps only zeros are left to bring down.
+
+scoreboard players set %f32_m reg 0
+scoreboard players set %f32_rem reg 0
+scoreboard players set %f32_sqrt_iter reg 27
+function intrinsic:f32/sqrt_step
+
+# Anything left over becomes the sticky bit
+scoreboard players operation %f32_odd reg = %f32_m reg
+scoreboard players operation %f32_odd reg %= %%2 reg
+execute unless score %f32_rem reg matches 0 if score %f32_odd reg matches 0 run scoreboard players add %f32_m reg 1
+
+# e = (e_a - 150) / 2 + 142
+scoreboard players operation %f32_e reg = %f32_e_a reg
+scoreboard players remove %f32_e reg 150
+scoreboard players operation %f32_e reg /= %%2 reg
+scoreboard players add %f32_e reg 142
+
+scoreboard players set %f32_sign reg 0
+function intrinsic:f32/pack
@@ -0,0 +1,8 @@
+# The square root of zero is itself, including the sign, and the square root of infinity is infinity
+scoreboard players operation %return%0 reg = %param0%0 reg
+
+# Every other negative number has no square root
+execute if score %f32_sign_a reg matches 1 unless score %f32_m_a reg matches 0 run scoreboard players set %return%0 reg 2143289344
+
+# The square root of NaN is NaN
+execute if score %f32_exp_a reg matches 255 unless score %f32_m_a reg matches 8388608 run scoreboard players set %return%0 reg 2143289344
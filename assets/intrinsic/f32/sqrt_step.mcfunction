@@ -0,0 +1,18 @@
+# rem = (rem << 2) + the top two bits of m_a
+scoreboard players operation %f32_rem reg *= %%4 reg
+scoreboard players operation %f32_tmp reg = %f32_m_a reg
+scoreboard players operation %f32_tmp reg /= %%16777216 reg
+scoreboard players operation %f32_rem reg += %f32_tmp reg
+scoreboard players operation %f32_m_a reg %= %%16777216 reg
+scoreboard players operation %f32_m_a reg *= %%4 reg
+
+# if (rem >= (m << 2) + 1) { rem -= (m << 2) + 1; m = (m << 1) + 1; } else { m <<= 1; }
+scoreboard players operation %f32_tmp reg = %f32_m reg
+scoreboard players operation %f32_tmp reg *= %%4 reg
+scoreboard players add %f32_tmp reg 1
+scoreboard players operation %f32_m reg += %f32_m reg
+execute if score %f32_rem reg >= %f32_tmp reg run scoreboard players add %f32_m reg 1
+execute if score %f32_rem reg >= %f32_tmp reg run scoreboard players operation %f32_rem reg -= %f32_tmp reg
+
+scoreboard players remove %f32_sqrt_iter reg 1
+execute if score %f32_sqrt_iter reg matches 1.. run function intrinsic:f32/sqrt_step
@@ -0,0 +1,9 @@
+# %param0%0 - the float to truncate
+# %return%0, %return%1 - the float rounded towards zero, as a 64-bit integer.
+#                        This wraps around if it does not fit, and NaN and infinity give 0.
+
+function intrinsic:f32/unpack_lhs
+
+scoreboard players set %return%0 reg 0
+scoreboard players set %return%1 reg 0
+execute unless score %f32_exp_a reg matches 255 run function intrinsic:f32/trunc_finite
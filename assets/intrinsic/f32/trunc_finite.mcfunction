@@ -0,0 +1,8 @@
+# Every float can be unpacked as a double with the same value
+scoreboard players operation %f64_sign_a reg = %f32_sign_a reg
+scoreboard players set %f64_mh_a reg 0
+scoreboard players operation %f64_ml_a reg = %f32_m_a reg
+scoreboard players operation %f64_e_a reg = %f32_e_a reg
+scoreboard players add %f64_e_a reg 925
+
+function intrinsic:f64/trunc_finite
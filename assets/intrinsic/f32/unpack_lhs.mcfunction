@@ -0,0 +1,20 @@
+# %param0%0 - the float to unpack
+# %f32_sign_a - 1 if negative, otherwise 0
+# %f32_abs_a - the float with its sign bit cleared
+# %f32_exp_a - the raw exponent field
+# %f32_m_a - the significand, including the implicit leading bit for normal numbers
+# %f32_e_a - the exponent, where subnormals get the same exponent as the smallest normal numbers,
+#            so that the value is always m * 2^(e - 150)
+
+scoreboard players operation %f32_abs_a reg = %param0%0 reg
+execute store success score %f32_sign_a reg if score %f32_abs_a reg matches ..-1
+execute if score %f32_sign_a reg matches 1 run scoreboard players operation %f32_abs_a reg += %%-2147483648 reg
+
+scoreboard players operation %f32_exp_a reg = %f32_abs_a reg
+scoreboard players operation %f32_exp_a reg /= %%8388608 reg
+scoreboard players operation %f32_m_a reg = %f32_abs_a reg
+scoreboard players operation %f32_m_a reg %= %%8388608 reg
+
+scoreboard players operation %f32_e_a reg = %f32_exp_a reg
+execute if score %f32_exp_a reg matches 0 run scoreboard players set %f32_e_a reg 1
+execute unless score %f32_exp_a reg matches 0 run scoreboard players operation %f32_m_a reg += %%8388608 reg
@@ -0,0 +1,20 @@
+# %param1%0 - the float to unpack
+# %f32_sign_b - 1 if negative, otherwise 0
+# %f32_abs_b - the float with its sign bit cleared
+# %f32_exp_b - the raw exponent field
+# %f32_m_b - the significand, including the implicit leading bit for normal numbers
+# %f32_e_b - the exponent, where subnormals get the same exponent as the smallest normal numbers,
+#            so that the value is always m * 2^(e - 150)
+
+scoreboard players operation %f32_abs_b reg = %param1%0 reg
+execute store success score %f32_sign_b reg if score %f32_abs_b reg matches ..-1
+execute if score %f32_sign_b reg matches 1 run scoreboard players operation %f32_abs_b reg += %%-2147483648 reg
+
+scoreboard players operation %f32_exp_b reg = %f32_abs_b reg
+scoreboard players operation %f32_exp_b reg /= %%8388608 reg
+scoreboard players operation %f32_m_b reg = %f32_abs_b reg
+scoreboard players operation %f32_m_b reg %= %%8388608 reg
+
+scoreboard players operation %f32_e_b reg = %f32_exp_b reg
+execute if score %f32_exp_b reg matches 0 run scoreboard players set %f32_e_b reg 1
+execute unless score %f32_exp_b reg matches 0 run scoreboard players operation %f32_m_b reg += %%8388608 reg
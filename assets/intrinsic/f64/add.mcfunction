@@ -0,0 +1,12 @@
+# %param0%0, %param0%1 - lhs
+# %param1%0, %param1%1 - rhs
+# %return%0, %return%1 - lhs + rhs
+
+function intrinsic:f64/unpack_lhs
+function intrinsic:f64/unpack_rhs
+
+execute store success score %f64_special reg if score %f64_exp_a reg matches 2047
+execute if score %f64_exp_b reg matches 2047 run scoreboard players set %f64_special reg 1
+
+execute if score %f64_special reg matches 1 run function intrinsic:f64/add_special
+execute if score %f64_special reg matches 0 run function intrinsic:f64/add_finite
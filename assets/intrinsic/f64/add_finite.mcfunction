@@ -0,0 +1,40 @@
+# Make sure lhs has the larger magnitude, so the result has its sign and exponent
+scoreboard players set %f64_swap reg 0
+execute if score %f64_e_a reg < %f64_e_b reg run scoreboard players set %f64_swap reg 1
+execute if score %f64_e_a reg = %f64_e_b reg if score %f64_mh_a reg < %f64_mh_b reg run scoreboard players set %f64_swap reg 1
+execute if score %f64_e_a reg = %f64_e_b reg if score %f64_mh_a reg = %f64_mh_b reg if score %f64_ml_a reg < %f64_ml_b reg run scoreboard players set %f64_swap reg 1
+execute if score %f64_swap reg matches 1 run function intrinsic:f64/add_swap
+
+scoreboard players operation %f64_sign reg = %f64_sign_a reg
+scoreboard players operation %f64_e reg = %f64_e_a reg
+
+# m = m_a << 3
+scoreboard players operation %f64_mh reg = %f64_mh_a reg
+scoreboard players operation %f64_ml reg = %f64_ml_a reg
+scoreboard players operation %f64_tmp reg = %f64_ml reg
+scoreboard players operation %f64_tmp reg /= %%33554432 reg
+scoreboard players operation %f64_mh reg *= %%8 reg
+scoreboard players operation %f64_mh reg += %f64_tmp reg
+scoreboard players operation %f64_ml reg %= %%33554432 reg
+scoreboard players operation %f64_ml reg *= %%8 reg
+
+# Line up rhs with lhs
+scoreboard players operation %f64_sh_h reg = %f64_mh_b reg
+scoreboard players operation %f64_sh_l reg = %f64_ml_b reg
+scoreboard players operation %f64_tmp reg = %f64_sh_l reg
+scoreboard players operation %f64_tmp reg /= %%33554432 reg
+scoreboard players operation %f64_sh_h reg *= %%8 reg
+scoreboard players operation %f64_sh_h reg += %f64_tmp reg
+scoreboard players operation %f64_sh_l reg %= %%33554432 reg
+scoreboard players operation %f64_sh_l reg *= %%8 reg
+scoreboard players operation %f64_sh_amt reg = %f64_e_a reg
+scoreboard players operation %f64_sh_amt reg -= %f64_e_b reg
+function intrinsic:f64/shift_right_sticky
+
+execute if score %f64_sign_a reg = %f64_sign_b reg run function intrinsic:f64/add_magnitudes
+execute unless score %f64_sign_a reg = %f64_sign_b reg run function intrinsic:f64/sub_magnitudes
+
+# x + -x is +0
+execute if score %f64_mh reg matches 0 if score %f64_ml reg matches 0 unless score %f64_sign_a reg = %f64_sign_b reg run scoreboard players set %f64_sign reg 0
+
+function intrinsic:f64/pack
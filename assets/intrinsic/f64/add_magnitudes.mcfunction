@@ -0,0 +1,4 @@
+scoreboard players operation %f64_mh reg += %f64_sh_h reg
+scoreboard players operation %f64_ml reg += %f64_sh_l reg
+execute if score %f64_ml reg matches 268435456.. run scoreboard players add %f64_mh reg 1
+execute if score %f64_ml reg matches 268435456.. run scoreboard players remove %f64_ml reg 268435456
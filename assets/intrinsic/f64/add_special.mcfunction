@@ -0,0 +1,10 @@
+# Infinity plus anything finite is that infinity
+execute if score %f64_exp_a reg matches 2047 run scoreboard players operation %return%0 reg = %param0%0 reg
+execute if score %f64_exp_a reg matches 2047 run scoreboard players operation %return%1 reg = %param0%1 reg
+execute unless score %f64_exp_a reg matches 2047 run scoreboard players operation %return%0 reg = %param1%0 reg
+execute unless score %f64_exp_a reg matches 2047 run scoreboard players operation %return%1 reg = %param1%1 reg
+
+# Infinities with opposite signs cancel out to NaN
+execute if score %f64_exp_a reg matches 2047 if score %f64_exp_b reg matches 2047 unless score %f64_sign_a reg = %f64_sign_b reg run function intrinsic:f64/return_nan
+
+function intrinsic:f64/propagate_nan
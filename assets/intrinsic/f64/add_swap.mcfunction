@@ -0,0 +1,4 @@
+scoreboard players operation %f64_sign_a reg >< %f64_sign_b reg
+scoreboard players operation %f64_e_a reg >< %f64_e_b reg
+scoreboard players operation %f64_mh_a reg >< %f64_mh_b reg
+scoreboard players operation %f64_ml_a reg >< %f64_ml_b reg
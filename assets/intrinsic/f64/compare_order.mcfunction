@@ -0,0 +1,33 @@
+# %param0%0, %param0%1 - lhs
+# %param1%0, %param1%1 - rhs
+# %f64_rhs_less - 1 if rhs is less than lhs, counting -0 as less than +0, otherwise 0
+# %f64_nan - 1 if either of them is NaN, otherwise 0
+
+# Flipping every bit but the sign of a negative double makes its bits order the same way as its value.
+# The low words are compared as unsigned numbers, so their top bits are flipped as well.
+scoreboard players operation %f64_key_ha reg = %param0%1 reg
+scoreboard players operation %f64_key_la reg = %param0%0 reg
+scoreboard players operation %f64_key_la reg += %%-2147483648 reg
+execute if score %param0%1 reg matches ..-1 run scoreboard players set %f64_key_ha reg 2147483647
+execute if score %param0%1 reg matches ..-1 run scoreboard players operation %f64_key_ha reg -= %param0%1 reg
+execute if score %param0%1 reg matches ..-1 run scoreboard players set %f64_key_la reg 2147483647
+execute if score %param0%1 reg matches ..-1 run scoreboard players operation %f64_key_la reg -= %param0%0 reg
+scoreboard players operation %f64_key_hb reg = %param1%1 reg
+scoreboard players operation %f64_key_lb reg = %param1%0 reg
+scoreboard players operation %f64_key_lb reg += %%-2147483648 reg
+execute if score %param1%1 reg matches ..-1 run scoreboard players set %f64_key_hb reg 2147483647
+execute if score %param1%1 reg matches ..-1 run scoreboard players operation %f64_key_hb reg -= %param1%1 reg
+execute if score %param1%1 reg matches ..-1 run scoreboard players set %f64_key_lb reg 2147483647
+execute if score %param1%1 reg matches ..-1 run scoreboard players operation %f64_key_lb reg -= %param1%0 reg
+
+execute store success score %f64_rhs_less reg if score %f64_key_hb reg < %f64_key_ha reg
+execute if score %f64_key_hb reg = %f64_key_ha reg if score %f64_key_lb reg < %f64_key_la reg run scoreboard players set %f64_rhs_less reg 1
+
+execute store success score %f64_nan reg if score %param0%1 reg matches 2146435073..
+execute if score %param0%1 reg matches -1048575..-1 run scoreboard players set %f64_nan reg 1
+execute if score %param0%1 reg matches 2146435072 unless score %param0%0 reg matches 0 run scoreboard players set %f64_nan reg 1
+execute if score %param0%1 reg matches -1048576 unless score %param0%0 reg matches 0 run scoreboard players set %f64_nan reg 1
+execute if score %param1%1 reg matches 2146435073.. run scoreboard players set %f64_nan reg 1
+execute if score %param1%1 reg matches -1048575..-1 run scoreboard players set %f64_nan reg 1
+execute if score %param1%1 reg matches 2146435072 unless score %param1%0 reg matches 0 run scoreboard players set %f64_nan reg 1
+execute if score %param1%1 reg matches -1048576 unless score %param1%0 reg matches 0 run scoreboard players set %f64_nan reg 1
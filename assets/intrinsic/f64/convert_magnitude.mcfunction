@@ -0,0 +1,23 @@
+# %param0%0, %param0%1 - an unsigned 64-bit integer
+# %f64_e, %f64_mh, %f64_ml - the same value in the form that intrinsic:f64/pack takes
+
+# Split the low word at bit 28
+scoreboard players operation %f64_ml reg = %param0%0 reg
+scoreboard players operation %f64_ml reg %= %%268435456 reg
+scoreboard players operation %f64_mh reg = %param0%0 reg
+scoreboard players operation %f64_mh reg /= %%268435456 reg
+execute if score %f64_mh reg matches ..-1 run scoreboard players add %f64_mh reg 16
+
+# The bottom 24 bits of the high word go right above that
+scoreboard players operation %f64_tmp reg = %param0%1 reg
+scoreboard players operation %f64_tmp reg %= %%16777216 reg
+scoreboard players operation %f64_tmp reg *= %%16 reg
+scoreboard players operation %f64_mh reg += %f64_tmp reg
+
+scoreboard players set %f64_e reg 1078
+
+# The top 8 bits don't fit, so shift right until they do
+scoreboard players operation %f64_top reg = %param0%1 reg
+scoreboard players operation %f64_top reg /= %%16777216 reg
+execute if score %f64_top reg matches ..-1 run scoreboard players add %f64_top reg 256
+execute unless score %f64_top reg matches 0 run function intrinsic:f64/convert_shift_top
@@ -0,0 +1,4 @@
+# -x == (-x_lo, -x_hi - 1), unless x_lo is 0
+scoreboard players operation %param0%0 reg *= %%-1 reg
+scoreboard players operation %param0%1 reg *= %%-1 reg
+execute unless score %param0%0 reg matches 0 run scoreboard players remove %param0%1 reg 1
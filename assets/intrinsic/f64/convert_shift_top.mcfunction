@@ -0,0 +1,16 @@
+# Shift right once, moving the lowest of the top bits into bit 55
+scoreboard players operation %f64_sh_h reg = %f64_mh reg
+scoreboard players operation %f64_sh_l reg = %f64_ml reg
+scoreboard players set %f64_sh_amt reg 1
+function intrinsic:f64/shift_right_sticky
+scoreboard players operation %f64_mh reg = %f64_sh_h reg
+scoreboard players operation %f64_ml reg = %f64_sh_l reg
+
+scoreboard players operation %f64_tmp reg = %f64_top reg
+scoreboard players operation %f64_tmp reg %= %%2 reg
+scoreboard players operation %f64_tmp reg *= %%134217728 reg
+scoreboard players operation %f64_mh reg += %f64_tmp reg
+scoreboard players operation %f64_top reg /= %%2 reg
+scoreboard players add %f64_e reg 1
+
+execute unless score %f64_top reg matches 0 run function intrinsic:f64/convert_shift_top
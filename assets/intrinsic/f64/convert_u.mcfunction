@@ -0,0 +1,6 @@
+# %param0%0, %param0%1 - an unsigned 64-bit integer
+# %return%0, %return%1 - the nearest double
+
+scoreboard players set %f64_sign reg 0
+function intrinsic:f64/convert_magnitude
+function intrinsic:f64/pack
@@ -0,0 +1,9 @@
+# m >>= 1 - e, e = 1
+scoreboard players set %f64_sh_amt reg 1
+scoreboard players operation %f64_sh_amt reg -= %f64_e reg
+scoreboard players operation %f64_sh_h reg = %f64_mh reg
+scoreboard players operation %f64_sh_l reg = %f64_ml reg
+function intrinsic:f64/shift_right_sticky
+scoreboard players operation %f64_mh reg = %f64_sh_h reg
+scoreboard players operation %f64_ml reg = %f64_sh_l reg
+scoreboard players set %f64_e reg 1
@@ -0,0 +1,29 @@
+execute if score %f64_mh_a reg matches ..16777215 run function intrinsic:f64/normalize_lhs
+execute if score %f64_mh_b reg matches ..16777215 run function intrinsic:f64/normalize_rhs
+
+# Long division, one bit at a time:
+#	q = (m_a << 55) / m_b
+
+scoreboard players set %f64_qh reg 0
+scoreboard players set %f64_ql reg 0
+scoreboard players operation %f64_rh reg = %f64_mh_a reg
+scoreboard players operation %f64_rl reg = %f64_ml_a reg
+
+scoreboard players set %f64_div_iter reg 56
+function intrinsic:f64/div_step
+
+# Anything left over becomes the sticky bit
+execute unless score %f64_rl reg matches 0 run scoreboard players set %f64_rh reg 1
+scoreboard players operation %f64_odd reg = %f64_ql reg
+scoreboard players operation %f64_odd reg %= %%2 reg
+execute unless score %f64_rh reg matches 0 if score %f64_odd reg matches 0 run scoreboard players add %f64_ql reg 1
+
+scoreboard players operation %f64_mh reg = %f64_qh reg
+scoreboard players operation %f64_ml reg = %f64_ql reg
+
+# e = e_a - e_b + 1023
+scoreboard players operation %f64_e reg = %f64_e_a reg
+scoreboard players operation %f64_e reg -= %f64_e_b reg
+scoreboard players add %f64_e reg 1023
+
+function intrinsic:f64/pack
@@ -0,0 +1,19 @@
+# q <<= 1
+scoreboard players operation %f64_qh reg += %f64_qh reg
+scoreboard players operation %f64_ql reg += %f64_ql reg
+execute if score %f64_ql reg matches 268435456.. run scoreboard players add %f64_qh reg 1
+execute if score %f64_ql reg matches 268435456.. run scoreboard players remove %f64_ql reg 268435456
+
+# if (r >= m_b) { r -= m_b; q |= 1; }
+execute store success score %f64_fits reg if score %f64_rh reg > %f64_mh_b reg
+execute if score %f64_rh reg = %f64_mh_b reg if score %f64_rl reg >= %f64_ml_b reg run scoreboard players set %f64_fits reg 1
+execute if score %f64_fits reg matches 1 run function intrinsic:f64/div_subtract
+
+# r <<= 1
+scoreboard players operation %f64_rh reg += %f64_rh reg
+scoreboard players operation %f64_rl reg += %f64_rl reg
+execute if score %f64_rl reg matches 268435456.. run scoreboard players add %f64_rh reg 1
+execute if score %f64_rl reg matches 268435456.. run scoreboard players remove %f64_rl reg 268435456
+
+scoreboard players remove %f64_div_iter reg 1
+execute if score %f64_div_iter reg matches 1.. run function intrinsic:f64/div_step
@@ -0,0 +1,11 @@
+# %param0%0, %param0%1 - lhs
+# %param1%0, %param1%1 - rhs
+# %return%0, %return%1 - the larger of lhs and rhs, or NaN if either of them is NaN
+
+function intrinsic:f64/compare_order
+
+scoreboard players operation %return%0 reg = %param1%0 reg
+scoreboard players operation %return%1 reg = %param1%1 reg
+execute if score %f64_rhs_less reg matches 1 run scoreboard players operation %return%0 reg = %param0%0 reg
+execute if score %f64_rhs_less reg matches 1 run scoreboard players operation %return%1 reg = %param0%1 reg
+execute if score %f64_nan reg matches 1 run function intrinsic:f64/return_nan
@@ -0,0 +1,10 @@
+# %param0%0, %param0%1 - lhs
+# %param1%0, %param1%1 - rhs
+# %return%0, %return%1 - lhs * rhs
+
+function intrinsic:f64/unpack_lhs
+function intrinsic:f64/unpack_rhs
+function intrinsic:f64/sign_product
+
+execute if score %f64_special reg matches 1 run function intrinsic:f64/mul_special
+execute if score %f64_special reg matches 0 run function intrinsic:f64/mul_finite
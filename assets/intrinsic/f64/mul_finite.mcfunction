@@ -0,0 +1,130 @@
+execute if score %f64_mh_a reg matches ..16777215 run function intrinsic:f64/normalize_lhs
+execute if score %f64_mh_b reg matches ..16777215 run function intrinsic:f64/normalize_rhs
+
+# Both significands are 53 bits, so the 106-bit product is built from 14-bit digits:
+#	p = sum of c_k << (14 * k), where c_k = sum of a_i * b_j with i + j = k
+
+scoreboard players operation %f64_a0 reg = %f64_ml_a reg
+scoreboard players operation %f64_a0 reg %= %%16384 reg
+scoreboard players operation %f64_a1 reg = %f64_ml_a reg
+scoreboard players operation %f64_a1 reg /= %%16384 reg
+scoreboard players operation %f64_a2 reg = %f64_mh_a reg
+scoreboard players operation %f64_a2 reg %= %%16384 reg
+scoreboard players operation %f64_a3 reg = %f64_mh_a reg
+scoreboard players operation %f64_a3 reg /= %%16384 reg
+scoreboard players operation %f64_b0 reg = %f64_ml_b reg
+scoreboard players operation %f64_b0 reg %= %%16384 reg
+scoreboard players operation %f64_b1 reg = %f64_ml_b reg
+scoreboard players operation %f64_b1 reg /= %%16384 reg
+scoreboard players operation %f64_b2 reg = %f64_mh_b reg
+scoreboard players operation %f64_b2 reg %= %%16384 reg
+scoreboard players operation %f64_b3 reg = %f64_mh_b reg
+scoreboard players operation %f64_b3 reg /= %%16384 reg
+
+scoreboard players operation %f64_c0 reg = %f64_a0 reg
+scoreboard players operation %f64_c0 reg *= %f64_b0 reg
+scoreboard players operation %f64_c1 reg = %f64_a0 reg
+scoreboard players operation %f64_c1 reg *= %f64_b1 reg
+scoreboard players operation %f64_tmp reg = %f64_a1 reg
+scoreboard players operation %f64_tmp reg *= %f64_b0 reg
+scoreboard players operation %f64_c1 reg += %f64_tmp reg
+scoreboard players operation %f64_c2 reg = %f64_a0 reg
+scoreboard players operation %f64_c2 reg *= %f64_b2 reg
+scoreboard players operation %f64_tmp reg = %f64_a1 reg
+scoreboard players operation %f64_tmp reg *= %f64_b1 reg
+scoreboard players operation %f64_c2 reg += %f64_tmp reg
+scoreboard players operation %f64_tmp reg = %f64_a2 reg
+scoreboard players operation %f64_tmp reg *= %f64_b0 reg
+scoreboard players operation %f64_c2 reg += %f64_tmp reg
+scoreboard players operation %f64_c3 reg = %f64_a0 reg
+scoreboard players operation %f64_c3 reg *= %f64_b3 reg
+scoreboard players operation %f64_tmp reg = %f64_a1 reg
+scoreboard players operation %f64_tmp reg *= %f64_b2 reg
+scoreboard players operation %f64_c3 reg += %f64_tmp reg
+scoreboard players operation %f64_tmp reg = %f64_a2 reg
+scoreboard players operation %f64_tmp reg *= %f64_b1 reg
+scoreboard players operation %f64_c3 reg += %f64_tmp reg
+scoreboard players operation %f64_tmp reg = %f64_a3 reg
+scoreboard players operation %f64_tmp reg *= %f64_b0 reg
+scoreboard players operation %f64_c3 reg += %f64_tmp reg
+scoreboard players operation %f64_c4 reg = %f64_a1 reg
+scoreboard players operation %f64_c4 reg *= %f64_b3 reg
+scoreboard players operation %f64_tmp reg = %f64_a2 reg
+scoreboard players operation %f64_tmp reg *= %f64_b2 reg
+scoreboard players operation %f64_c4 reg += %f64_tmp reg
+scoreboard players operation %f64_tmp reg = %f64_a3 reg
+scoreboard players operation %f64_tmp reg *= %f64_b1 reg
+scoreboard players operation %f64_c4 reg += %f64_tmp reg
+scoreboard players operation %f64_c5 reg = %f64_a2 reg
+scoreboard players operation %f64_c5 reg *= %f64_b3 reg
+scoreboard players operation %f64_tmp reg = %f64_a3 reg
+scoreboard players operation %f64_tmp reg *= %f64_b2 reg
+scoreboard players operation %f64_c5 reg += %f64_tmp reg
+scoreboard players operation %f64_c6 reg = %f64_a3 reg
+scoreboard players operation %f64_c6 reg *= %f64_b3 reg
+
+# Carry each column into the next one, so every c_k fits in 14 bits
+scoreboard players set %f64_c7 reg 0
+scoreboard players operation %f64_tmp reg = %f64_c0 reg
+scoreboard players operation %f64_tmp reg /= %%16384 reg
+scoreboard players operation %f64_c1 reg += %f64_tmp reg
+scoreboard players operation %f64_c0 reg %= %%16384 reg
+scoreboard players operation %f64_tmp reg = %f64_c1 reg
+scoreboard players operation %f64_tmp reg /= %%16384 reg
+scoreboard players operation %f64_c2 reg += %f64_tmp reg
+scoreboard players operation %f64_c1 reg %= %%16384 reg
+scoreboard players operation %f64_tmp reg = %f64_c2 reg
+scoreboard players operation %f64_tmp reg /= %%16384 reg
+scoreboard players operation %f64_c3 reg += %f64_tmp reg
+scoreboard players operation %f64_c2 reg %= %%16384 reg
+scoreboard players operation %f64_tmp reg = %f64_c3 reg
+scoreboard players operation %f64_tmp reg /= %%16384 reg
+scoreboard players operation %f64_c4 reg += %f64_tmp reg
+scoreboard players operation %f64_c3 reg %= %%16384 reg
+scoreboard players operation %f64_tmp reg = %f64_c4 reg
+scoreboard players operation %f64_tmp reg /= %%16384 reg
+scoreboard players operation %f64_c5 reg += %f64_tmp reg
+scoreboard players operation %f64_c4 reg %= %%16384 reg
+scoreboard players operation %f64_tmp reg = %f64_c5 reg
+scoreboard players operation %f64_tmp reg /= %%16384 reg
+scoreboard players operation %f64_c6 reg += %f64_tmp reg
+scoreboard players operation %f64_c5 reg %= %%16384 reg
+scoreboard players operation %f64_tmp reg = %f64_c6 reg
+scoreboard players operation %f64_tmp reg /= %%16384 reg
+scoreboard players operation %f64_c7 reg += %f64_tmp reg
+scoreboard players operation %f64_c6 reg %= %%16384 reg
+
+# m = p >> 49, keeping the lost bits as a sticky bit
+#	ml = bits 49..76 of p, mh = bits 77..105 of p
+scoreboard players operation %f64_ml reg = %f64_c3 reg
+scoreboard players operation %f64_ml reg /= %%128 reg
+scoreboard players operation %f64_tmp reg = %f64_c4 reg
+scoreboard players operation %f64_tmp reg *= %%128 reg
+scoreboard players operation %f64_ml reg += %f64_tmp reg
+scoreboard players operation %f64_tmp reg = %f64_c5 reg
+scoreboard players operation %f64_tmp reg %= %%128 reg
+scoreboard players operation %f64_tmp reg *= %%2097152 reg
+scoreboard players operation %f64_ml reg += %f64_tmp reg
+scoreboard players operation %f64_mh reg = %f64_c5 reg
+scoreboard players operation %f64_mh reg /= %%128 reg
+scoreboard players operation %f64_tmp reg = %f64_c6 reg
+scoreboard players operation %f64_tmp reg *= %%128 reg
+scoreboard players operation %f64_mh reg += %f64_tmp reg
+scoreboard players operation %f64_tmp reg = %f64_c7 reg
+scoreboard players operation %f64_tmp reg *= %%2097152 reg
+scoreboard players operation %f64_mh reg += %f64_tmp reg
+
+scoreboard players operation %f64_c3 reg %= %%128 reg
+scoreboard players operation %f64_c3 reg += %f64_c2 reg
+scoreboard players operation %f64_c3 reg += %f64_c1 reg
+scoreboard players operation %f64_c3 reg += %f64_c0 reg
+scoreboard players operation %f64_odd reg = %f64_ml reg
+scoreboard players operation %f64_odd reg %= %%2 reg
+execute unless score %f64_c3 reg matches 0 if score %f64_odd reg matches 0 run scoreboard players add %f64_ml reg 1
+
+# e = e_a + e_b - 1023
+scoreboard players operation %f64_e reg = %f64_e_a reg
+scoreboard players operation %f64_e reg += %f64_e_b reg
+scoreboard players remove %f64_e reg 1023
+
+function intrinsic:f64/pack
@@ -0,0 +1,12 @@
+# Anything times zero is zero, and infinity times anything else is infinity
+scoreboard players set %return%0 reg 0
+scoreboard players set %return%1 reg 0
+execute if score %f64_exp_a reg matches 2047 run scoreboard players set %return%1 reg 2146435072
+execute if score %f64_exp_b reg matches 2047 run scoreboard players set %return%1 reg 2146435072
+execute if score %f64_sign reg matches 1 run scoreboard players operation %return%1 reg += %%-2147483648 reg
+
+# Infinity times zero is NaN
+execute if score %f64_exp_a reg matches 2047 if score %f64_mh_b reg matches 0 if score %f64_ml_b reg matches 0 run function intrinsic:f64/return_nan
+execute if score %f64_exp_b reg matches 2047 if score %f64_mh_a reg matches 0 if score %f64_ml_a reg matches 0 run function intrinsic:f64/return_nan
+
+function intrinsic:f64/propagate_nan
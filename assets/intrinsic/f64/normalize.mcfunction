@@ -0,0 +1,6 @@
+scoreboard players operation %f64_mh reg += %f64_mh reg
+scoreboard players operation %f64_ml reg += %f64_ml reg
+execute if score %f64_ml reg matches 268435456.. run scoreboard players add %f64_mh reg 1
+execute if score %f64_ml reg matches 268435456.. run scoreboard players remove %f64_ml reg 268435456
+scoreboard players remove %f64_e reg 1
+execute if score %f64_mh reg matches ..134217727 if score %f64_e reg matches 2.. run function intrinsic:f64/normalize
@@ -0,0 +1,7 @@
+# Moves the leading bit of a subnormal significand up to bit 52
+scoreboard players operation %f64_mh_a reg += %f64_mh_a reg
+scoreboard players operation %f64_ml_a reg += %f64_ml_a reg
+execute if score %f64_ml_a reg matches 268435456.. run scoreboard players add %f64_mh_a reg 1
+execute if score %f64_ml_a reg matches 268435456.. run scoreboard players remove %f64_ml_a reg 268435456
+scoreboard players remove %f64_e_a reg 1
+execute if score %f64_mh_a reg matches ..16777215 run function intrinsic:f64/normalize_lhs
@@ -0,0 +1,4 @@
+# The high part is empty, so shift left by 28 all at once
+scoreboard players operation %f64_mh reg = %f64_ml reg
+scoreboard players set %f64_ml reg 0
+scoreboard players remove %f64_e reg 28
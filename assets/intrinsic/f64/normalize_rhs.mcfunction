@@ -0,0 +1,7 @@
+# Moves the leading bit of a subnormal significand up to bit 52
+scoreboard players operation %f64_mh_b reg += %f64_mh_b reg
+scoreboard players operation %f64_ml_b reg += %f64_ml_b reg
+execute if score %f64_ml_b reg matches 268435456.. run scoreboard players add %f64_mh_b reg 1
+execute if score %f64_ml_b reg matches 268435456.. run scoreboard players remove %f64_ml_b reg 268435456
+scoreboard players remove %f64_e_b reg 1
+execute if score %f64_mh_b reg matches ..16777215 run function intrinsic:f64/normalize_rhs
@@ -0,0 +1,14 @@
+# %f64_sign - 1 if the result is negative
+# %f64_e, %f64_mh, %f64_ml - the exact result is m * 2^(e - 1078), where m = (mh << 28) + ml,
+#                            ml is less than 2^28 and mh is less than 2^29
+# %return%0, %return%1 - the rounded result
+
+# A normal result has its leading bit at bit 55 of m,
+# leaving 3 extra bits below the final significand for rounding.
+
+scoreboard players set %return%0 reg 0
+scoreboard players set %return%1 reg 0
+execute store success score %f64_nonzero reg unless score %f64_mh reg matches 0
+execute unless score %f64_ml reg matches 0 run scoreboard players set %f64_nonzero reg 1
+execute if score %f64_nonzero reg matches 1 run function intrinsic:f64/pack_nonzero
+execute if score %f64_sign reg matches 1 run scoreboard players operation %return%1 reg += %%-2147483648 reg
@@ -0,0 +1,3 @@
+# Rounding up overflowed into the next exponent, so m is exactly 1 << 53
+scoreboard players operation %f64_mh reg /= %%2 reg
+scoreboard players add %f64_e reg 1
@@ -0,0 +1,5 @@
+# Replace the implicit leading bit with the exponent field
+scoreboard players operation %return%1 reg -= %%1048576 reg
+scoreboard players operation %f64_tmp reg = %f64_e reg
+scoreboard players operation %f64_tmp reg *= %%1048576 reg
+scoreboard players operation %return%1 reg += %f64_tmp reg
@@ -0,0 +1,34 @@
+# Carry out of the top
+execute if score %f64_mh reg matches 268435456.. run function intrinsic:f64/pack_shift_once
+
+# Shift the leading bit up into place, unless that would make the number subnormal
+execute if score %f64_mh reg matches 0 if score %f64_e reg matches 29.. run function intrinsic:f64/normalize_limb
+execute if score %f64_mh reg matches ..134217727 if score %f64_e reg matches 2.. run function intrinsic:f64/normalize
+
+# Too small to be a normal number
+execute if score %f64_e reg matches ..0 run function intrinsic:f64/denormalize
+
+# Round to nearest, ties to even
+scoreboard players operation %f64_round reg = %f64_ml reg
+scoreboard players operation %f64_round reg %= %%8 reg
+scoreboard players operation %f64_ml reg /= %%8 reg
+scoreboard players operation %f64_tmp reg = %f64_mh reg
+scoreboard players operation %f64_tmp reg %= %%8 reg
+scoreboard players operation %f64_tmp reg *= %%33554432 reg
+scoreboard players operation %f64_ml reg += %f64_tmp reg
+scoreboard players operation %f64_mh reg /= %%8 reg
+scoreboard players operation %f64_odd reg = %f64_ml reg
+scoreboard players operation %f64_odd reg %= %%2 reg
+execute if score %f64_round reg matches 5.. run function intrinsic:f64/pack_round_up
+execute if score %f64_round reg matches 4 if score %f64_odd reg matches 1 run function intrinsic:f64/pack_round_up
+execute if score %f64_mh reg matches 33554432.. run function intrinsic:f64/pack_carry
+
+# Put the fields together
+scoreboard players operation %return%1 reg = %f64_mh reg
+scoreboard players operation %return%1 reg /= %%16 reg
+scoreboard players operation %return%0 reg = %f64_mh reg
+scoreboard players operation %return%0 reg %= %%16 reg
+scoreboard players operation %return%0 reg *= %%268435456 reg
+scoreboard players operation %return%0 reg += %f64_ml reg
+execute if score %f64_mh reg matches 16777216.. run function intrinsic:f64/pack_exponent
+execute if score %f64_e reg matches 2047.. run function intrinsic:f64/return_infinity
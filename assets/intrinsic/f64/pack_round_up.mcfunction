@@ -0,0 +1,3 @@
+scoreboard players add %f64_ml reg 1
+execute if score %f64_ml reg matches 268435456 run scoreboard players add %f64_mh reg 1
+execute if score %f64_ml reg matches 268435456 run scoreboard players set %f64_ml reg 0
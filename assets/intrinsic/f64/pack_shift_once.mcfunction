@@ -0,0 +1,7 @@
+scoreboard players operation %f64_sh_h reg = %f64_mh reg
+scoreboard players operation %f64_sh_l reg = %f64_ml reg
+scoreboard players set %f64_sh_amt reg 1
+function intrinsic:f64/shift_right_sticky
+scoreboard players operation %f64_mh reg = %f64_sh_h reg
+scoreboard players operation %f64_ml reg = %f64_sh_l reg
+scoreboard players add %f64_e reg 1
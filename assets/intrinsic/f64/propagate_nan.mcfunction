@@ -0,0 +1,5 @@
+# NaN combined with anything is NaN
+execute if score %f64_exp_a reg matches 2047 unless score %f64_mh_a reg matches 16777216 run function intrinsic:f64/return_nan
+execute if score %f64_exp_a reg matches 2047 unless score %f64_ml_a reg matches 0 run function intrinsic:f64/return_nan
+execute if score %f64_exp_b reg matches 2047 unless score %f64_mh_b reg matches 16777216 run function intrinsic:f64/return_nan
+execute if score %f64_exp_b reg matches 2047 unless score %f64_ml_b reg matches 0 run function intrinsic:f64/return_nan
@@ -0,0 +1,2 @@
+scoreboard players set %return%0 reg 0
+scoreboard players set %return%1 reg 2146435072
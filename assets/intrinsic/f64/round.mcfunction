@@ -0,0 +1,12 @@
+# %param0%0, %param0%1 - the double to round
+# %f64_round_mode - 0 to round towards zero, 1 to round down, 2 to round up, 3 to round to nearest with ties to even
+# %return%0, %return%1 - the double rounded to an integer
+
+function intrinsic:f64/unpack_lhs
+
+# Infinity and numbers too large to have a fraction are already integers, and NaN stays NaN
+scoreboard players operation %return%0 reg = %param0%0 reg
+scoreboard players operation %return%1 reg = %param0%1 reg
+execute if score %f64_exp_a reg matches 2047 unless score %f64_mh_a reg matches 16777216 run function intrinsic:f64/return_nan
+execute if score %f64_exp_a reg matches 2047 unless score %f64_ml_a reg matches 0 run function intrinsic:f64/return_nan
+execute if score %f64_e_a reg matches ..1074 run function intrinsic:f64/round_fraction
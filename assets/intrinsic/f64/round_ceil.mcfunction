@@ -0,0 +1,5 @@
+# %param0%0, %param0%1 - the double to round
+# %return%0, %return%1 - the double rounded up to an integer
+
+scoreboard players set %f64_round_mode reg 2
+function intrinsic:f64/round
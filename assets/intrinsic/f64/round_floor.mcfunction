@@ -0,0 +1,5 @@
+# %param0%0, %param0%1 - the double to round
+# %return%0, %return%1 - the double rounded down to an integer
+
+scoreboard players set %f64_round_mode reg 1
+function intrinsic:f64/round
@@ -0,0 +1,38 @@
+# Shift out the fraction, keeping its top bit and whether any of the rest is set in the two lowest bits:
+#	m = (m_a << 2) >> (1075 - e_a)
+scoreboard players operation %f64_sh_h reg = %f64_mh_a reg
+scoreboard players operation %f64_sh_h reg *= %%4 reg
+scoreboard players operation %f64_sh_l reg = %f64_ml_a reg
+scoreboard players operation %f64_sh_l reg *= %%4 reg
+scoreboard players operation %f64_tmp reg = %f64_sh_l reg
+scoreboard players operation %f64_tmp reg /= %%268435456 reg
+scoreboard players operation %f64_sh_h reg += %f64_tmp reg
+scoreboard players operation %f64_sh_l reg %= %%268435456 reg
+scoreboard players set %f64_sh_amt reg 1075
+scoreboard players operation %f64_sh_amt reg -= %f64_e_a reg
+function intrinsic:f64/shift_right_sticky
+scoreboard players operation %f64_mh reg = %f64_sh_h reg
+scoreboard players operation %f64_ml reg = %f64_sh_l reg
+
+scoreboard players operation %f64_round reg = %f64_ml reg
+scoreboard players operation %f64_round reg %= %%4 reg
+scoreboard players operation %f64_ml reg -= %f64_round reg
+scoreboard players operation %f64_odd reg = %f64_ml reg
+scoreboard players operation %f64_odd reg %= %%8 reg
+
+# Round the magnitude up if the mode calls for it
+execute if score %f64_round_mode reg matches 1 if score %f64_sign_a reg matches 1 unless score %f64_round reg matches 0 run scoreboard players add %f64_ml reg 4
+execute if score %f64_round_mode reg matches 2 if score %f64_sign_a reg matches 0 unless score %f64_round reg matches 0 run scoreboard players add %f64_ml reg 4
+execute if score %f64_round_mode reg matches 3 if score %f64_round reg matches 3 run scoreboard players add %f64_ml reg 4
+execute if score %f64_round_mode reg matches 3 if score %f64_round reg matches 2 if score %f64_odd reg matches 4 run scoreboard players add %f64_ml reg 4
+execute if score %f64_ml reg matches 268435456.. run scoreboard players add %f64_mh reg 1
+execute if score %f64_ml reg matches 268435456.. run scoreboard players remove %f64_ml reg 268435456
+
+# The integer is m >> 2, which is exact once packed
+scoreboard players operation %f64_mh reg += %f64_mh reg
+scoreboard players operation %f64_ml reg += %f64_ml reg
+execute if score %f64_ml reg matches 268435456.. run scoreboard players add %f64_mh reg 1
+execute if score %f64_ml reg matches 268435456.. run scoreboard players remove %f64_ml reg 268435456
+scoreboard players set %f64_e reg 1075
+scoreboard players operation %f64_sign reg = %f64_sign_a reg
+function intrinsic:f64/pack
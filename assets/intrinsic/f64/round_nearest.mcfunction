@@ -0,0 +1,5 @@
+# %param0%0, %param0%1 - the double to round
+# %return%0, %return%1 - the double rounded to the nearest integer, with ties going to the even one
+
+scoreboard players set %f64_round_mode reg 3
+function intrinsic:f64/round
@@ -0,0 +1,5 @@
+# %param0%0, %param0%1 - the double to round
+# %return%0, %return%1 - the double rounded towards zero to an integer
+
+scoreboard players set %f64_round_mode reg 0
+function intrinsic:f64/round
@@ -0,0 +1,10 @@
+# %f64_sh_h, %f64_sh_l - the value to shift right, split into the bits above and below bit 28,
+#                        which must be less than 2^56
+# %f64_sh_amt - how far to shift it, must not be negative (clobbered)
+
+# Any bits that are shifted out get ORed into the lowest bit,
+# so that the result still rounds the same way as the original value.
+
+execute if score %f64_sh_amt reg matches 57.. run function intrinsic:f64/shift_right_sticky_all
+execute if score %f64_sh_amt reg matches 28..56 run function intrinsic:f64/shift_right_sticky_limb
+execute if score %f64_sh_amt reg matches 1..28 run function intrinsic:f64/shift_right_sticky_inner
@@ -0,0 +1,5 @@
+# Every bit is shifted out, so only the sticky bit is left
+scoreboard players operation %f64_sh_l reg += %f64_sh_h reg
+execute unless score %f64_sh_l reg matches 0 run scoreboard players set %f64_sh_l reg 1
+scoreboard players set %f64_sh_h reg 0
+scoreboard players set %f64_sh_amt reg 0
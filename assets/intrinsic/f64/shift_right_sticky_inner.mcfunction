@@ -0,0 +1,28 @@
+# pow = 1 << amt
+
+scoreboard players set %f64_sh_pow reg 1
+execute if score %f64_sh_amt reg matches 16.. run scoreboard players operation %f64_sh_pow reg *= %%65536 reg
+execute if score %f64_sh_amt reg matches 16.. run scoreboard players remove %f64_sh_amt reg 16
+execute if score %f64_sh_amt reg matches 8.. run scoreboard players operation %f64_sh_pow reg *= %%256 reg
+execute if score %f64_sh_amt reg matches 8.. run scoreboard players remove %f64_sh_amt reg 8
+execute if score %f64_sh_amt reg matches 4.. run scoreboard players operation %f64_sh_pow reg *= %%16 reg
+execute if score %f64_sh_amt reg matches 4.. run scoreboard players remove %f64_sh_amt reg 4
+execute if score %f64_sh_amt reg matches 2.. run scoreboard players operation %f64_sh_pow reg *= %%4 reg
+execute if score %f64_sh_amt reg matches 2.. run scoreboard players remove %f64_sh_amt reg 2
+execute if score %f64_sh_amt reg matches 1 run scoreboard players operation %f64_sh_pow reg *= %%2 reg
+
+# The bits shifted out of the high part move into the top of the low part
+scoreboard players operation %f64_sh_rem reg = %f64_sh_l reg
+scoreboard players operation %f64_sh_rem reg %= %f64_sh_pow reg
+scoreboard players operation %f64_sh_l reg /= %f64_sh_pow reg
+scoreboard players operation %f64_sh_tmp reg = %f64_sh_h reg
+scoreboard players operation %f64_sh_tmp reg %= %f64_sh_pow reg
+scoreboard players operation %f64_sh_up reg = %%268435456 reg
+scoreboard players operation %f64_sh_up reg /= %f64_sh_pow reg
+scoreboard players operation %f64_sh_tmp reg *= %f64_sh_up reg
+scoreboard players operation %f64_sh_l reg += %f64_sh_tmp reg
+scoreboard players operation %f64_sh_h reg /= %f64_sh_pow reg
+
+scoreboard players operation %f64_sh_odd reg = %f64_sh_l reg
+scoreboard players operation %f64_sh_odd reg %= %%2 reg
+execute unless score %f64_sh_rem reg matches 0 if score %f64_sh_odd reg matches 0 run scoreboard players add %f64_sh_l reg 1
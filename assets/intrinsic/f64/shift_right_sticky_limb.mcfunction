@@ -0,0 +1,9 @@
+# Shift right by 28 by moving the high part into the low part
+scoreboard players operation %f64_sh_rem reg = %f64_sh_l reg
+scoreboard players operation %f64_sh_l reg = %f64_sh_h reg
+scoreboard players set %f64_sh_h reg 0
+scoreboard players remove %f64_sh_amt reg 28
+
+scoreboard players operation %f64_sh_odd reg = %f64_sh_l reg
+scoreboard players operation %f64_sh_odd reg %= %%2 reg
+execute unless score %f64_sh_rem reg matches 0 if score %f64_sh_odd reg matches 0 run scoreboard players add %f64_sh_l reg 1
@@ -0,0 +1,9 @@
+# The sign of a product or quotient is the XOR of the signs of its operands
+scoreboard players operation %f64_sign reg = %f64_sign_a reg
+scoreboard players operation %f64_sign reg += %f64_sign_b reg
+scoreboard players operation %f64_sign reg %= %%2 reg
+
+execute store success score %f64_special reg if score %f64_exp_a reg matches 2047
+execute if score %f64_exp_b reg matches 2047 run scoreboard players set %f64_special reg 1
+execute if score %f64_mh_a reg matches 0 if score %f64_ml_a reg matches 0 run scoreboard players set %f64_special reg 1
+execute if score %f64_mh_b reg matches 0 if score %f64_ml_b reg matches 0 run scoreboard players set %f64_special reg 1
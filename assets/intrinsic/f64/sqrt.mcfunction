@@ -0,0 +1,11 @@
+# %param0%0, %param0%1 - the double to take the square root of
+# %return%0, %return%1 - the square root, or NaN for numbers below zero
+
+function intrinsic:f64/unpack_lhs
+
+execute store success score %f64_special reg if score %f64_exp_a reg matches 2047
+execute if score %f64_sign_a reg matches 1 run scoreboard players set %f64_special reg 1
+execute if score %f64_mh_a reg matches 0 if score %f64_ml_a reg matches 0 run scoreboard players set %f64_special reg 1
+
+execute if score %f64_special reg matches 1 run function intrinsic:f64/sqrt_special
+execute if score %f64_special reg matches 0 run function intrinsic:f64/sqrt_finite
@@ -0,0 +1,40 @@
+execute if score %f64_mh_a reg matches ..16777215 run function intrinsic:f64/normalize_lhs
+
+# Only an even power of two can be halved, so an odd one gives a bit to the significand
+scoreboard players operation %f64_odd reg = %f64_e_a reg
+scoreboard players operation %f64_odd reg %= %%2 reg
+execute if score %f64_odd reg matches 0 run scoreboard players operation %f64_mh_a reg += %f64_mh_a reg
+execute if score %f64_odd reg matches 0 run scoreboard players operation %f64_ml_a reg += %f64_ml_a reg
+execute if score %f64_odd reg matches 0 if score %f64_ml_a reg matches 268435456.. run scoreboard players add %f64_mh_a reg 1
+execute if score %f64_odd reg matches 0 if score %f64_ml_a reg matches 268435456.. run scoreboard players remove %f64_ml_a reg 268435456
+execute if score %f64_odd reg matches 0 run scoreboard players remove %f64_e_a reg 1
+
+# Square root one bit at a time, bringing down two bits of the radicand at each step:
+#	q = sqrt(m_a << 56)
+# m_a has at most 54 bits, so after 27 steps only zeros are left to bring down.
+
+scoreboard players set %f64_qh reg 0
+scoreboard players set %f64_ql reg 0
+scoreboard players set %f64_rh reg 0
+scoreboard players set %f64_rl reg 0
+
+scoreboard players set %f64_sqrt_iter reg 55
+function intrinsic:f64/sqrt_step
+
+# Anything left over becomes the sticky bit
+execute unless score %f64_rl reg matches 0 run scoreboard players set %f64_rh reg 1
+scoreboard players operation %f64_odd reg = %f64_ql reg
+scoreboard players operation %f64_odd reg %= %%2 reg
+execute unless score %f64_rh reg matches 0 if score %f64_odd reg matches 0 run scoreboard players add %f64_ql reg 1
+
+scoreboard players operation %f64_mh reg = %f64_qh reg
+scoreboard players operation %f64_ml reg = %f64_ql reg
+
+# e = (e_a - 1075) / 2 + 1050
+scoreboard players operation %f64_e reg = %f64_e_a reg
+scoreboard players remove %f64_e reg 1075
+scoreboard players operation %f64_e reg /= %%2 reg
+scoreboard players add %f64_e reg 1050
+
+scoreboard players set %f64_sign reg 0
+function intrinsic:f64/pack
@@ -0,0 +1,11 @@
+# The square root of zero is itself, including the sign, and the square root of infinity is infinity
+scoreboard players operation %return%0 reg = %param0%0 reg
+scoreboard players operation %return%1 reg = %param0%1 reg
+
+# Every other negative number has no square root
+execute if score %f64_sign_a reg matches 1 unless score %f64_mh_a reg matches 0 run function intrinsic:f64/return_nan
+execute if score %f64_sign_a reg matches 1 unless score %f64_ml_a reg matches 0 run function intrinsic:f64/return_nan
+
+# The square root of NaN is NaN
+execute if score %f64_exp_a reg matches 2047 unless score %f64_mh_a reg matches 16777216 run function intrinsic:f64/return_nan
+execute if score %f64_exp_a reg matches 2047 unless score %f64_ml_a reg matches 0 run function intrinsic:f64/return_nan
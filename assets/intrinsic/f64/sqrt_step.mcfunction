@@ -0,0 +1,44 @@
+# r = (r << 2) + the top two bits of m_a
+scoreboard players operation %f64_rh reg *= %%4 reg
+scoreboard players operation %f64_rl reg *= %%4 reg
+scoreboard players operation %f64_tmp reg = %f64_rl reg
+scoreboard players operation %f64_tmp reg /= %%268435456 reg
+scoreboard players operation %f64_rh reg += %f64_tmp reg
+scoreboard players operation %f64_rl reg %= %%268435456 reg
+scoreboard players operation %f64_tmp reg = %f64_mh_a reg
+scoreboard players operation %f64_tmp reg /= %%16777216 reg
+scoreboard players operation %f64_rl reg += %f64_tmp reg
+
+# m_a <<= 2, dropping the bits that were just brought down
+scoreboard players operation %f64_mh_a reg %= %%16777216 reg
+scoreboard players operation %f64_mh_a reg *= %%4 reg
+scoreboard players operation %f64_tmp reg = %f64_ml_a reg
+scoreboard players operation %f64_tmp reg /= %%67108864 reg
+scoreboard players operation %f64_mh_a reg += %f64_tmp reg
+scoreboard players operation %f64_ml_a reg %= %%67108864 reg
+scoreboard players operation %f64_ml_a reg *= %%4 reg
+
+# t = (q << 2) + 1
+scoreboard players operation %f64_th reg = %f64_qh reg
+scoreboard players operation %f64_th reg *= %%4 reg
+scoreboard players operation %f64_tl reg = %f64_ql reg
+scoreboard players operation %f64_tl reg *= %%4 reg
+scoreboard players operation %f64_tmp reg = %f64_tl reg
+scoreboard players operation %f64_tmp reg /= %%268435456 reg
+scoreboard players operation %f64_th reg += %f64_tmp reg
+scoreboard players operation %f64_tl reg %= %%268435456 reg
+scoreboard players add %f64_tl reg 1
+
+# q <<= 1
+scoreboard players operation %f64_qh reg += %f64_qh reg
+scoreboard players operation %f64_ql reg += %f64_ql reg
+execute if score %f64_ql reg matches 268435456.. run scoreboard players add %f64_qh reg 1
+execute if score %f64_ql reg matches 268435456.. run scoreboard players remove %f64_ql reg 268435456
+
+# if (r >= t) { r -= t; q |= 1; }
+execute store success score %f64_fits reg if score %f64_rh reg > %f64_th reg
+execute if score %f64_rh reg = %f64_th reg if score %f64_rl reg >= %f64_tl reg run scoreboard players set %f64_fits reg 1
+execute if score %f64_fits reg matches 1 run function intrinsic:f64/sqrt_subtract
+
+scoreboard players remove %f64_sqrt_iter reg 1
+execute if score %f64_sqrt_iter reg matches 1.. run function intrinsic:f64/sqrt_step
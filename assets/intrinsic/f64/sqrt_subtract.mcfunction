@@ -0,0 +1,5 @@
+scoreboard players operation %f64_rh reg -= %f64_th reg
+scoreboard players operation %f64_rl reg -= %f64_tl reg
+execute if score %f64_rl reg matches ..-1 run scoreboard players remove %f64_rh reg 1
+execute if score %f64_rl reg matches ..-1 run scoreboard players add %f64_rl reg 268435456
+scoreboard players add %f64_ql reg 1
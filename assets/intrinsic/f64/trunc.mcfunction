@@ -0,0 +1,9 @@
+# %param0%0, %param0%1 - the double to truncate
+# %return%0, %return%1 - the double rounded towards zero, as a 64-bit integer.
+#                        This wraps around if it does not fit, and NaN and infinity give 0.
+
+function intrinsic:f64/unpack_lhs
+
+scoreboard players set %return%0 reg 0
+scoreboard players set %return%1 reg 0
+execute unless score %f64_exp_a reg matches 2047 run function intrinsic:f64/trunc_finite
@@ -0,0 +1,23 @@
+# %f64_sign_a, %f64_e_a, %f64_mh_a, %f64_ml_a - the unpacked value to truncate
+
+scoreboard players operation %f64_mh reg = %f64_mh_a reg
+scoreboard players operation %f64_ml reg = %f64_ml_a reg
+scoreboard players set %f64_trunc_amt reg 1075
+scoreboard players operation %f64_trunc_amt reg -= %f64_e_a reg
+
+execute if score %f64_trunc_amt reg matches 1.. run function intrinsic:f64/trunc_shift_right
+
+# Put the words together
+scoreboard players operation %return%1 reg = %f64_mh reg
+scoreboard players operation %return%1 reg /= %%16 reg
+scoreboard players operation %return%0 reg = %f64_mh reg
+scoreboard players operation %return%0 reg %= %%16 reg
+scoreboard players operation %return%0 reg *= %%268435456 reg
+scoreboard players operation %return%0 reg += %f64_ml reg
+
+# Every bit is shifted out of a 64-bit result
+execute if score %f64_trunc_amt reg matches ..-64 run scoreboard players set %return%0 reg 0
+execute if score %f64_trunc_amt reg matches ..-64 run scoreboard players set %return%1 reg 0
+execute if score %f64_trunc_amt reg matches -63..-1 run function intrinsic:f64/trunc_shift_left
+
+execute if score %f64_sign_a reg matches 1 run function intrinsic:f64/trunc_negate
@@ -0,0 +1,4 @@
+# -x == (-x_lo, -x_hi - 1), unless x_lo is 0
+scoreboard players operation %return%0 reg *= %%-1 reg
+scoreboard players operation %return%1 reg *= %%-1 reg
+execute unless score %return%0 reg matches 0 run scoreboard players remove %return%1 reg 1
@@ -0,0 +1,8 @@
+# Shifts the result left once for each step that amt is below 0
+execute store success score %f64_carry reg if score %return%0 reg matches ..-1
+scoreboard players operation %return%0 reg += %return%0 reg
+scoreboard players operation %return%1 reg += %return%1 reg
+scoreboard players operation %return%1 reg += %f64_carry reg
+
+scoreboard players add %f64_trunc_amt reg 1
+execute if score %f64_trunc_amt reg matches ..-1 run function intrinsic:f64/trunc_shift_left
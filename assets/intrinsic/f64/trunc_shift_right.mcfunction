@@ -0,0 +1,22 @@
+# m >>= amt, rounding towards zero
+
+# This shifts m << 1 instead, so that the sticky bit ends up in the lowest bit, which is then dropped.
+scoreboard players operation %f64_sh_h reg = %f64_mh reg
+scoreboard players operation %f64_sh_h reg *= %%2 reg
+scoreboard players operation %f64_tmp reg = %f64_ml reg
+scoreboard players operation %f64_tmp reg /= %%134217728 reg
+scoreboard players operation %f64_sh_h reg += %f64_tmp reg
+scoreboard players operation %f64_sh_l reg = %f64_ml reg
+scoreboard players operation %f64_sh_l reg %= %%134217728 reg
+scoreboard players operation %f64_sh_l reg *= %%2 reg
+scoreboard players operation %f64_sh_amt reg = %f64_trunc_amt reg
+function intrinsic:f64/shift_right_sticky
+
+scoreboard players operation %f64_mh reg = %f64_sh_h reg
+scoreboard players operation %f64_mh reg /= %%2 reg
+scoreboard players operation %f64_ml reg = %f64_sh_l reg
+scoreboard players operation %f64_ml reg /= %%2 reg
+scoreboard players operation %f64_tmp reg = %f64_sh_h reg
+scoreboard players operation %f64_tmp reg %= %%2 reg
+scoreboard players operation %f64_tmp reg *= %%134217728 reg
+scoreboard players operation %f64_ml reg += %f64_tmp reg
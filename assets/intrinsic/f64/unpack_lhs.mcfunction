@@ -0,0 +1,28 @@
+# %param0%0, %param0%1 - the low and high words of the double to unpack
+# %f64_sign_a - 1 if negative, otherwise 0
+# %f64_exp_a - the raw exponent field
+# %f64_mh_a, %f64_ml_a - the significand, including the implicit leading bit for normal numbers,
+#                        split into the bits above and below bit 28
+# %f64_e_a - the exponent, where subnormals get the same exponent as the smallest normal numbers,
+#           so that the value is always m * 2^(e - 1075)
+
+scoreboard players operation %f64_mh_a reg = %param0%1 reg
+execute store success score %f64_sign_a reg if score %f64_mh_a reg matches ..-1
+execute if score %f64_sign_a reg matches 1 run scoreboard players operation %f64_mh_a reg += %%-2147483648 reg
+
+scoreboard players operation %f64_exp_a reg = %f64_mh_a reg
+scoreboard players operation %f64_exp_a reg /= %%1048576 reg
+scoreboard players operation %f64_mh_a reg %= %%1048576 reg
+
+scoreboard players operation %f64_e_a reg = %f64_exp_a reg
+execute if score %f64_exp_a reg matches 0 run scoreboard players set %f64_e_a reg 1
+execute unless score %f64_exp_a reg matches 0 run scoreboard players operation %f64_mh_a reg += %%1048576 reg
+
+# The top 4 bits of the low word belong to the high part
+scoreboard players operation %f64_mh_a reg *= %%16 reg
+scoreboard players operation %f64_tmp reg = %param0%0 reg
+scoreboard players operation %f64_tmp reg /= %%268435456 reg
+execute if score %f64_tmp reg matches ..-1 run scoreboard players add %f64_tmp reg 16
+scoreboard players operation %f64_mh_a reg += %f64_tmp reg
+scoreboard players operation %f64_ml_a reg = %param0%0 reg
+scoreboard players operation %f64_ml_a reg %= %%268435456 reg
@@ -0,0 +1,28 @@
+# %param1%0, %param1%1 - the low and high words of the double to unpack
+# %f64_sign_b - 1 if negative, otherwise 0
+# %f64_exp_b - the raw exponent field
+# %f64_mh_b, %f64_ml_b - the significand, including the implicit leading bit for normal numbers,
+#                        split into the bits above and below bit 28
+# %f64_e_b - the exponent, where subnormals get the same exponent as the smallest normal numbers,
+#           so that the value is always m * 2^(e - 1075)
+
+scoreboard players operation %f64_mh_b reg = %param1%1 reg
+execute store success score %f64_sign_b reg if score %f64_mh_b reg matches ..-1
+execute if score %f64_sign_b reg matches 1 run scoreboard players operation %f64_mh_b reg += %%-2147483648 reg
+
+scoreboard players operation %f64_exp_b reg = %f64_mh_b reg
+scoreboard players operation %f64_exp_b reg /= %%1048576 reg
+scoreboard players operation %f64_mh_b reg %= %%1048576 reg
+
+scoreboard players operation %f64_e_b reg = %f64_exp_b reg
+execute if score %f64_exp_b reg matches 0 run scoreboard players set %f64_e_b reg 1
+execute unless score %f64_exp_b reg matches 0 run scoreboard players operation %f64_mh_b reg += %%1048576 reg
+
+# The top 4 bits of the low word belong to the high part
+scoreboard players operation %f64_mh_b reg *= %%16 reg
+scoreboard players operation %f64_tmp reg = %param1%0 reg
+scoreboard players operation %f64_tmp reg /= %%268435456 reg
+execute if score %f64_tmp reg matches ..-1 run scoreboard players add %f64_tmp reg 16
+scoreboard players operation %f64_mh_b reg += %f64_tmp reg
+scoreboard players operation %f64_ml_b reg = %param1%0 reg
+scoreboard players operation %f64_ml_b reg %= %%268435456 reg
@@ -0,0 +1,18 @@
+# Implements WASI's fd_write for stdout and stderr, which are both printed like put_char
+# i32 fd       == %param0%0
+# iovec* iovs  == %param1%0
+# i32 iovs_len == %param2%0
+# i32* nwritten == %param3%0
+
+# %return%0 is set to the errno, which is EBADF (8) for any other file descriptor
+
+# !INTERPRETER: ASSERT if score %param2%0 reg matches 0..
+
+scoreboard players operation %fdw_iov reg = %param1%0 reg
+scoreboard players operation %fdw_iovs_left reg = %param2%0 reg
+scoreboard players operation %fdw_nwritten_ptr reg = %param3%0 reg
+scoreboard players set %fdw_total reg 0
+
+scoreboard players set %return%0 reg 8
+execute if score %param0%0 reg matches 1..2 if score %fdw_iovs_left reg matches 1.. run function intrinsic:fd_write/iovec
+execute if score %param0%0 reg matches 1..2 run function intrinsic:fd_write/done
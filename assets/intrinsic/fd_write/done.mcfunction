@@ -0,0 +1,8 @@
+# Stores the number of bytes written and reports success
+
+scoreboard players operation %ptr reg = %fdw_nwritten_ptr reg
+function intrinsic:setptr
+scoreboard players operation %param0%0 reg = %fdw_total reg
+function intrinsic:store_word
+
+scoreboard players set %return%0 reg 0
@@ -0,0 +1,22 @@
+# Writes the iovec at %fdw_iov, then moves on to the next one
+# struct iovec { u8* buf; u32 len; }
+
+scoreboard players operation %ptr reg = %fdw_iov reg
+function intrinsic:setptr
+function intrinsic:load_word
+scoreboard players operation %fdw_buf reg = %return%0 reg
+
+scoreboard players operation %ptr reg = %fdw_iov reg
+scoreboard players add %ptr reg 4
+function intrinsic:setptr
+function intrinsic:load_word
+scoreboard players operation %fdw_len reg = %return%0 reg
+
+scoreboard players operation %fdw_total reg += %fdw_len reg
+scoreboard players operation %pstr_ptr reg = %fdw_buf reg
+scoreboard players operation %pstr_left reg = %fdw_len reg
+execute if score %pstr_left reg matches 1.. run function intrinsic:print_str/bytes
+
+scoreboard players add %fdw_iov reg 8
+scoreboard players remove %fdw_iovs_left reg 1
+execute if score %fdw_iovs_left reg matches 1.. run function intrinsic:fd_write/iovec
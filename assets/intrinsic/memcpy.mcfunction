@@ -0,0 +1,28 @@
+# i8* dest == %param0%0
+# i8* src  == %param1%0
+# i32 len  == %param2%0
+
+# The regions may overlap, like memmove
+
+# !INTERPRETER: ASSERT if score %param2%0 reg matches 0..
+
+scoreboard players operation %mcp_dest reg = %param0%0 reg
+scoreboard players operation %mcp_src reg = %param1%0 reg
+scoreboard players operation %mcp_len reg = %param2%0 reg
+
+# Whole words can only be copied if both pointers have the same alignment
+scoreboard players operation %mcp_dest_align reg = %mcp_dest reg
+scoreboard players operation %mcp_dest_align reg %= %%4 reg
+scoreboard players operation %mcp_src_align reg = %mcp_src reg
+scoreboard players operation %mcp_src_align reg %= %%4 reg
+scoreboard players set %mcp_same_align reg 0
+execute if score %mcp_dest_align reg = %mcp_src_align reg run scoreboard players set %mcp_same_align reg 1
+
+# Copying from the end is only needed when the destination starts inside the source
+scoreboard players operation %mcp_src_end reg = %mcp_src reg
+scoreboard players operation %mcp_src_end reg += %mcp_len reg
+scoreboard players set %mcp_backward reg 0
+execute if score %mcp_dest reg > %mcp_src reg if score %mcp_dest reg < %mcp_src_end reg run scoreboard players set %mcp_backward reg 1
+
+execute if score %mcp_backward reg matches 0 run function intrinsic:memcpy/forward
+execute if score %mcp_backward reg matches 1 run function intrinsic:memcpy/backward
@@ -0,0 +1,21 @@
+# Copies %mcp_len bytes from %mcp_src to %mcp_dest, starting at the end
+
+scoreboard players operation %mcp_src reg += %mcp_len reg
+scoreboard players operation %mcp_dest reg += %mcp_len reg
+
+# Copy single bytes until the end of the destination is word-aligned, or everything if the source never will be
+scoreboard players operation %mcp_count reg = %mcp_len reg
+execute if score %mcp_same_align reg matches 1 run scoreboard players operation %mcp_count reg = %mcp_dest reg
+execute if score %mcp_same_align reg matches 1 run scoreboard players operation %mcp_count reg %= %%4 reg
+execute if score %mcp_count reg > %mcp_len reg run scoreboard players operation %mcp_count reg = %mcp_len reg
+scoreboard players operation %mcp_len reg -= %mcp_count reg
+execute if score %mcp_count reg matches 1.. run function intrinsic:memcpy/bytes_backward
+
+# Then whole words
+scoreboard players operation %mcp_count reg = %mcp_len reg
+scoreboard players operation %mcp_count reg /= %%4 reg
+execute if score %mcp_count reg matches 1.. run function intrinsic:memcpy/words_backward_begin
+
+# And finally the bytes before the first whole word
+scoreboard players operation %mcp_count reg = %mcp_len reg
+execute if score %mcp_count reg matches 1.. run function intrinsic:memcpy/bytes_backward
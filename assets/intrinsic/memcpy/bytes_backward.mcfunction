@@ -0,0 +1,16 @@
+# Copies the %mcp_count bytes before %mcp_src and %mcp_dest, one at a time
+
+scoreboard players remove %mcp_src reg 1
+scoreboard players remove %mcp_dest reg 1
+
+scoreboard players operation %ptr reg = %mcp_src reg
+function intrinsic:setptr
+function intrinsic:load_byte
+
+scoreboard players operation %param2%0 reg = %param0%0 reg
+scoreboard players operation %ptr reg = %mcp_dest reg
+function intrinsic:setptr
+function intrinsic:store_byte
+
+scoreboard players remove %mcp_count reg 1
+execute if score %mcp_count reg matches 1.. run function intrinsic:memcpy/bytes_backward
@@ -0,0 +1,11 @@
+# Copies the word at (%mcp_src_x, %mcp_src_y, %mcp_src_z) to (%mcp_dest_x, %mcp_dest_y, %mcp_dest_z)
+
+execute store result entity 44453000-0-0-0-1 Pos[0] double 1 run scoreboard players get %mcp_src_x reg
+execute store result entity 44453000-0-0-0-1 Pos[1] double 1 run scoreboard players get %mcp_src_y reg
+execute store result entity 44453000-0-0-0-1 Pos[2] double 1 run scoreboard players get %mcp_src_z reg
+execute at 44453000-0-0-0-1 store result score %mcp_word reg run data get block ~ ~ ~ RecordItem.tag.Memory 1
+
+execute store result entity 44453000-0-0-0-1 Pos[0] double 1 run scoreboard players get %mcp_dest_x reg
+execute store result entity 44453000-0-0-0-1 Pos[1] double 1 run scoreboard players get %mcp_dest_y reg
+execute store result entity 44453000-0-0-0-1 Pos[2] double 1 run scoreboard players get %mcp_dest_z reg
+execute at 44453000-0-0-0-1 store result block ~ ~ ~ RecordItem.tag.Memory int 1 run scoreboard players get %mcp_word reg
@@ -0,0 +1,19 @@
+# Copies %mcp_len bytes from %mcp_src to %mcp_dest, starting at the beginning
+
+# Copy single bytes until the destination is word-aligned, or everything if the source never will be
+scoreboard players operation %mcp_count reg = %mcp_len reg
+execute if score %mcp_same_align reg matches 1 run scoreboard players set %mcp_count reg 4
+execute if score %mcp_same_align reg matches 1 run scoreboard players operation %mcp_count reg -= %mcp_dest_align reg
+execute if score %mcp_same_align reg matches 1 run scoreboard players operation %mcp_count reg %= %%4 reg
+execute if score %mcp_count reg > %mcp_len reg run scoreboard players operation %mcp_count reg = %mcp_len reg
+scoreboard players operation %mcp_len reg -= %mcp_count reg
+execute if score %mcp_count reg matches 1.. run function intrinsic:memcpy/bytes_forward
+
+# Then whole words
+scoreboard players operation %mcp_count reg = %mcp_len reg
+scoreboard players operation %mcp_count reg /= %%4 reg
+execute if score %mcp_count reg matches 1.. run function intrinsic:memcpy/words_forward_begin
+
+# And finally the bytes after the last whole word
+scoreboard players operation %mcp_count reg = %mcp_len reg
+execute if score %mcp_count reg matches 1.. run function intrinsic:memcpy/bytes_forward
@@ -0,0 +1,19 @@
+function intrinsic:memcpy/copy_word
+
+scoreboard players remove %mcp_src_z reg 1
+execute if score %mcp_src_z reg < %%memory_z reg run scoreboard players remove %mcp_src_y reg 1
+execute if score %mcp_src_z reg < %%memory_z reg run scoreboard players operation %mcp_src_z reg += %%PAGE_SPAN_Z reg
+execute if score %mcp_src_y reg < %%memory_y reg run scoreboard players remove %mcp_src_x reg 1
+execute if score %mcp_src_y reg < %%memory_y reg run scoreboard players operation %mcp_src_y reg += %%PAGE_SPAN_Y reg
+
+scoreboard players remove %mcp_dest_z reg 1
+execute if score %mcp_dest_z reg < %%memory_z reg run scoreboard players remove %mcp_dest_y reg 1
+execute if score %mcp_dest_z reg < %%memory_z reg run scoreboard players operation %mcp_dest_z reg += %%PAGE_SPAN_Z reg
+execute if score %mcp_dest_y reg < %%memory_y reg run scoreboard players remove %mcp_dest_x reg 1
+execute if score %mcp_dest_y reg < %%memory_y reg run scoreboard players operation %mcp_dest_y reg += %%PAGE_SPAN_Y reg
+
+scoreboard players remove %mcp_src reg 4
+scoreboard players remove %mcp_dest reg 4
+scoreboard players remove %mcp_len reg 4
+scoreboard players remove %mcp_count reg 1
+execute if score %mcp_count reg matches 1.. run function intrinsic:memcpy/words_backward
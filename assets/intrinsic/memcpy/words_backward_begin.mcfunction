@@ -0,0 +1,21 @@
+# Copies the %mcp_count words before %mcp_src and %mcp_dest, keeping track of the block positions
+# of both pointers so the memory pointer doesn't have to be recomputed for every word
+
+scoreboard players operation %ptr reg = %mcp_src reg
+scoreboard players remove %ptr reg 4
+function intrinsic:setptr
+scoreboard players operation %mcp_src_x reg = %%ptr reg
+scoreboard players operation %mcp_src_y reg = %y reg
+scoreboard players operation %mcp_src_z reg = %z reg
+
+scoreboard players operation %ptr reg = %mcp_dest reg
+scoreboard players remove %ptr reg 4
+function intrinsic:setptr
+scoreboard players operation %mcp_dest_x reg = %%ptr reg
+scoreboard players operation %mcp_dest_y reg = %y reg
+scoreboard players operation %mcp_dest_z reg = %z reg
+
+scoreboard players operation %%memory_z_end reg = %%memory_z reg
+scoreboard players operation %%memory_z_end reg += %%PAGE_SPAN_Z reg
+
+function intrinsic:memcpy/words_backward
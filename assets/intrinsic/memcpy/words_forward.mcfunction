@@ -0,0 +1,19 @@
+function intrinsic:memcpy/copy_word
+
+scoreboard players add %mcp_src_z reg 1
+execute if score %mcp_src_z reg = %%memory_z_end reg run scoreboard players add %mcp_src_y reg 1
+execute if score %mcp_src_z reg = %%memory_z_end reg run scoreboard players operation %mcp_src_z reg = %%memory_z reg
+execute if score %mcp_src_y reg = %%memory_y_end reg run scoreboard players add %mcp_src_x reg 1
+execute if score %mcp_src_y reg = %%memory_y_end reg run scoreboard players operation %mcp_src_y reg = %%memory_y reg
+
+scoreboard players add %mcp_dest_z reg 1
+execute if score %mcp_dest_z reg = %%memory_z_end reg run scoreboard players add %mcp_dest_y reg 1
+execute if score %mcp_dest_z reg = %%memory_z_end reg run scoreboard players operation %mcp_dest_z reg = %%memory_z reg
+execute if score %mcp_dest_y reg = %%memory_y_end reg run scoreboard players add %mcp_dest_x reg 1
+execute if score %mcp_dest_y reg = %%memory_y_end reg run scoreboard players operation %mcp_dest_y reg = %%memory_y reg
+
+scoreboard players add %mcp_src reg 4
+scoreboard players add %mcp_dest reg 4
+scoreboard players remove %mcp_len reg 4
+scoreboard players remove %mcp_count reg 1
+execute if score %mcp_count reg matches 1.. run function intrinsic:memcpy/words_forward
@@ -47,14 +47,14 @@
 execute at @s store result block ~ ~ ~ RecordItem.tag.Memory int 1 run scoreboard players get %mst_value_word reg
 
 scoreboard players add %mst_z reg 1
-execute if score %mst_z reg = %%PAGE_SPAN_Z reg run scoreboard players add %mst_y reg 1
-execute if score %mst_y reg = %%PAGE_SPAN_Y reg run scoreboard players add %mst_x reg 1
+execute if score %mst_z reg = %%memory_z_end reg run scoreboard players add %mst_y reg 1
+execute if score %mst_y reg = %%memory_y_end reg run scoreboard players add %mst_x reg 1
 
-execute if score %mst_y reg = %%PAGE_SPAN_Y reg store result entity @s Pos[0] double 1 run scoreboard players get %mst_x reg
-execute if score %mst_y reg = %%PAGE_SPAN_Y reg run scoreboard players set %mst_y reg 0
+execute if score %mst_y reg = %%memory_y_end reg store result entity @s Pos[0] double 1 run scoreboard players get %mst_x reg
+execute if score %mst_y reg = %%memory_y_end reg run scoreboard players operation %mst_y reg = %%memory_y reg
 
-execute if score %mst_z reg = %%PAGE_SPAN_Z reg store result entity @s Pos[1] double 1 run scoreboard players get %mst_y reg
-execute if score %mst_z reg = %%PAGE_SPAN_Z reg run scoreboard players set %mst_z reg 0
+execute if score %mst_z reg = %%memory_z_end reg store result entity @s Pos[1] double 1 run scoreboard players get %mst_y reg
+execute if score %mst_z reg = %%memory_z_end reg run scoreboard players operation %mst_z reg = %%memory_z reg
 
 execute store result entity @s Pos[2] double 1 run scoreboard players get %mst_z reg
 
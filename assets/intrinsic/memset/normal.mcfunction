@@ -16,6 +16,8 @@ function intrinsic:setptr
 scoreboard players operation %mst_x reg = %%ptr reg
 scoreboard players operation %mst_y reg = %y reg
 scoreboard players operation %mst_z reg = %z reg
+scoreboard players operation %%memory_z_end reg = %%memory_z reg
+scoreboard players operation %%memory_z_end reg += %%PAGE_SPAN_Z reg
 execute if score %mst_length reg matches 4.. run function intrinsic:memset/body_words
 
 # The pointer is already set properly by body_words,
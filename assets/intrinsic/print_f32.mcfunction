@@ -0,0 +1,24 @@
+# Prints a float as a chat message
+# %param0%0 - the float
+
+# Anything from mc_putc that hasn't been printed yet goes on its own line
+execute store result score %pflt_exp reg run data get storage wasm:stdout buffer
+execute if score %pflt_exp reg matches 1.. run function intrinsic:put_char/flush
+
+# The float is widened to a double, which is always exact, and printed the same way
+scoreboard players set %pflt_lo reg 0
+scoreboard players set %pflt_hi reg 0
+scoreboard players operation %pflt_sign reg = %param0%0 reg
+scoreboard players operation %pflt_mag reg = %param0%0 reg
+execute if score %pflt_mag reg matches ..-1 run scoreboard players operation %pflt_mag reg += %%-2147483648 reg
+scoreboard players operation %pflt_exp reg = %pflt_mag reg
+scoreboard players operation %pflt_exp reg /= %%8388608 reg
+scoreboard players operation %pflt_mant reg = %pflt_mag reg
+scoreboard players operation %pflt_mant reg %= %%8388608 reg
+
+execute if score %pflt_exp reg matches 1.. run function intrinsic:print_float/widen
+execute if score %pflt_exp reg matches 0 unless score %pflt_mant reg matches 0 run function intrinsic:print_float/widen_subnormal
+execute if score %pflt_sign reg matches ..-1 run scoreboard players operation %pflt_hi reg += %%-2147483648 reg
+
+function intrinsic:print_float/value
+function intrinsic:print_long/done
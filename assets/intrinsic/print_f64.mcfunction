@@ -0,0 +1,12 @@
+# Prints a double as a chat message
+# %param0%0, %param0%1 - the double
+
+# Anything from mc_putc that hasn't been printed yet goes on its own line
+execute store result score %pflt_exp reg run data get storage wasm:stdout buffer
+execute if score %pflt_exp reg matches 1.. run function intrinsic:put_char/flush
+
+scoreboard players operation %pflt_lo reg = %param0%0 reg
+scoreboard players operation %pflt_hi reg = %param0%1 reg
+
+function intrinsic:print_float/value
+function intrinsic:print_long/done
@@ -0,0 +1,6 @@
+# 'e'
+scoreboard players set %param0%0 reg 101
+function intrinsic:put_char
+
+scoreboard players operation %pfmt_arg reg = %pflt_exp reg
+function intrinsic:print_fmt/signed
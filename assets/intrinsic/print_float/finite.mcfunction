@@ -0,0 +1,52 @@
+# Prints the positive, finite double in %pflt_lo, %pflt_hi
+
+# Since the number is positive, comparing the high word of its bits is enough to compare it to a power of two
+scoreboard players set %pflt_exp reg 0
+# 2^63 and up doesn't fit in the integer part
+execute if score %pflt_hi reg matches 1138753536.. run function intrinsic:print_float/scale_down
+# Below 2^-14 would print as mostly zeros, but zero itself is printed as-is
+execute if score %pflt_hi reg matches 1..1058013183 run function intrinsic:print_float/scale_up
+execute if score %pflt_hi reg matches 0 unless score %pflt_lo reg matches 0 run function intrinsic:print_float/scale_up
+
+# Integer part
+scoreboard players operation %param0%0 reg = %pflt_lo reg
+scoreboard players operation %param0%1 reg = %pflt_hi reg
+function intrinsic:f64/trunc
+scoreboard players operation %plong_lo reg = %return%0 reg
+scoreboard players operation %plong_hi reg = %return%1 reg
+function intrinsic:print_long/unsigned
+
+# Fractional part: trunc((value - int) * 10^6)
+scoreboard players operation %param0%0 reg = %plong_lo reg
+scoreboard players operation %param0%1 reg = %plong_hi reg
+function intrinsic:f64/convert_u
+scoreboard players operation %param1%0 reg = %return%0 reg
+scoreboard players operation %param1%1 reg = %return%1 reg
+scoreboard players operation %param1%1 reg += %%-2147483648 reg
+scoreboard players operation %param0%0 reg = %pflt_lo reg
+scoreboard players operation %param0%1 reg = %pflt_hi reg
+function intrinsic:f64/add
+
+scoreboard players operation %param0%0 reg = %return%0 reg
+scoreboard players operation %param0%1 reg = %return%1 reg
+# 10^6
+scoreboard players set %param1%0 reg 0
+scoreboard players set %param1%1 reg 1093567616
+function intrinsic:f64/mul
+
+scoreboard players operation %param0%0 reg = %return%0 reg
+scoreboard players operation %param0%1 reg = %return%1 reg
+function intrinsic:f64/trunc
+scoreboard players operation %pfmt_rem reg = %return%0 reg
+
+# '.'
+scoreboard players set %param0%0 reg 46
+function intrinsic:put_char
+
+# Leading zeros are kept
+scoreboard players set %pfmt_base reg 10
+scoreboard players set %pfmt_place reg 100000
+scoreboard players set %pfmt_started reg 1
+function intrinsic:print_fmt/digits
+
+execute unless score %pflt_exp reg matches 0 run function intrinsic:print_float/exponent
@@ -0,0 +1,9 @@
+# 'i'
+scoreboard players set %param0%0 reg 105
+function intrinsic:put_char
+# 'n'
+scoreboard players set %param0%0 reg 110
+function intrinsic:put_char
+# 'f'
+scoreboard players set %param0%0 reg 102
+function intrinsic:put_char
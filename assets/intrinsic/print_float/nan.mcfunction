@@ -0,0 +1,9 @@
+# 'n'
+scoreboard players set %param0%0 reg 110
+function intrinsic:put_char
+# 'a'
+scoreboard players set %param0%0 reg 97
+function intrinsic:put_char
+# 'n'
+scoreboard players set %param0%0 reg 110
+function intrinsic:put_char
@@ -0,0 +1,13 @@
+# Multiplies %pflt_lo, %pflt_hi by 10 until it's at least 1, counting the multiplications in %pflt_exp
+
+scoreboard players operation %param0%0 reg = %pflt_lo reg
+scoreboard players operation %param0%1 reg = %pflt_hi reg
+# 10.0
+scoreboard players set %param1%0 reg 0
+scoreboard players set %param1%1 reg 1076101120
+function intrinsic:f64/mul
+scoreboard players operation %pflt_lo reg = %return%0 reg
+scoreboard players operation %pflt_hi reg = %return%1 reg
+scoreboard players remove %pflt_exp reg 1
+
+execute if score %pflt_hi reg matches ..1072693247 run function intrinsic:print_float/scale_up
@@ -0,0 +1,8 @@
+# Prints infinity or NaN, which have the largest exponent in %pflt_hi
+
+# Infinity has a zero mantissa
+scoreboard players operation %pflt_mant reg = %pflt_hi reg
+scoreboard players remove %pflt_mant reg 2146435072
+execute if score %pflt_mant reg matches 0 if score %pflt_lo reg matches 0 run function intrinsic:print_float/inf
+execute if score %pflt_mant reg matches 0 unless score %pflt_lo reg matches 0 run function intrinsic:print_float/nan
+execute unless score %pflt_mant reg matches 0 run function intrinsic:print_float/nan
@@ -0,0 +1,12 @@
+# Prints the double in %pflt_lo, %pflt_hi with six digits after the decimal point, rounded towards zero.
+# Numbers of 2^63 or more and nonzero numbers below 2^-14 are scaled to between 1 and 10 first,
+# and the power of ten they were scaled by is printed after an 'e'
+
+# '-'
+execute if score %pflt_hi reg matches ..-1 run scoreboard players set %param0%0 reg 45
+execute if score %pflt_hi reg matches ..-1 run function intrinsic:put_char
+execute if score %pflt_hi reg matches ..-1 run scoreboard players operation %pflt_hi reg += %%-2147483648 reg
+
+# Infinity and NaN have the largest exponent
+execute if score %pflt_hi reg matches 2146435072.. run function intrinsic:print_float/special
+execute if score %pflt_hi reg matches ..2146435071 run function intrinsic:print_float/finite
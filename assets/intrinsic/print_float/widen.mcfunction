@@ -0,0 +1,15 @@
+# Widens a normal float, infinity, or NaN in %pflt_exp and %pflt_mant to a double in %pflt_lo, %pflt_hi
+
+# The exponent bias goes from 127 to 1023, except that infinity and NaN always have the largest exponent
+scoreboard players add %pflt_exp reg 896
+execute if score %pflt_exp reg matches 1151 run scoreboard players set %pflt_exp reg 2047
+scoreboard players operation %pflt_hi reg = %pflt_exp reg
+scoreboard players operation %pflt_hi reg *= %%1048576 reg
+
+# The top 20 bits of the mantissa go in the high word, and the other 3 at the top of the low word
+scoreboard players operation %pflt_lo reg = %pflt_mant reg
+scoreboard players operation %pflt_lo reg /= %%8 reg
+scoreboard players operation %pflt_hi reg += %pflt_lo reg
+scoreboard players operation %pflt_lo reg = %pflt_mant reg
+scoreboard players operation %pflt_lo reg %= %%8 reg
+scoreboard players operation %pflt_lo reg *= %%536870912 reg
@@ -0,0 +1,16 @@
+# Widens a subnormal float in %pflt_mant to a double in %pflt_lo, %pflt_hi.
+# Its value is mant * 2^-149, which is normal as a double
+
+scoreboard players operation %param0%0 reg = %pflt_mant reg
+scoreboard players set %param0%1 reg 0
+function intrinsic:f64/convert_u
+
+scoreboard players operation %param0%0 reg = %return%0 reg
+scoreboard players operation %param0%1 reg = %return%1 reg
+# 2^-149
+scoreboard players set %param1%0 reg 0
+scoreboard players set %param1%1 reg 916455424
+function intrinsic:f64/mul
+
+scoreboard players operation %pflt_lo reg = %return%0 reg
+scoreboard players operation %pflt_hi reg = %return%1 reg
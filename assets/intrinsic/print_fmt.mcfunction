@@ -0,0 +1,13 @@
+# Prints a printf-style format string from memory as a chat message
+# i8* fmt   == %param0%0
+# i32* args == %param1%0
+
+# Supports %d, %u, %x, %c, %s, and %%, and each argument is one word, like a va_list
+
+scoreboard players operation %pfmt_ptr reg = %param0%0 reg
+scoreboard players operation %pfmt_args reg = %param1%0 reg
+function intrinsic:print_fmt/char
+
+# Newlines already flush the buffer, so this only prints what's left after the last one
+execute store result score %pfmt_c reg run data get storage wasm:stdout buffer
+execute if score %pfmt_c reg matches 1.. run function intrinsic:put_char/flush
@@ -0,0 +1,13 @@
+# Prints the next character of the format string, or the conversion it starts
+
+scoreboard players operation %ptr reg = %pfmt_ptr reg
+function intrinsic:setptr
+function intrinsic:load_byte
+scoreboard players operation %pfmt_c reg = %param0%0 reg
+scoreboard players add %pfmt_ptr reg 1
+
+# '%'
+execute if score %pfmt_c reg matches 37 run function intrinsic:print_fmt/conversion
+execute unless score %pfmt_c reg matches 0 unless score %pfmt_c reg matches 37 run function intrinsic:put_char
+
+execute unless score %pfmt_c reg matches 0 run function intrinsic:print_fmt/char
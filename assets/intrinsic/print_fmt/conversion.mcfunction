@@ -0,0 +1,30 @@
+# Prints the conversion after a '%'
+
+scoreboard players operation %ptr reg = %pfmt_ptr reg
+function intrinsic:setptr
+function intrinsic:load_byte
+scoreboard players operation %pfmt_conv reg = %param0%0 reg
+scoreboard players add %pfmt_ptr reg 1
+
+# A '%' at the end of the string ends it
+execute if score %pfmt_conv reg matches 0 run scoreboard players set %pfmt_c reg 0
+
+# 'c' and 'd'
+execute if score %pfmt_conv reg matches 99..100 run function intrinsic:print_fmt/next_arg
+# 's'
+execute if score %pfmt_conv reg matches 115 run function intrinsic:print_fmt/next_arg
+# 'u'
+execute if score %pfmt_conv reg matches 117 run function intrinsic:print_fmt/next_arg
+# 'x'
+execute if score %pfmt_conv reg matches 120 run function intrinsic:print_fmt/next_arg
+
+execute if score %pfmt_conv reg matches 99 run scoreboard players operation %param0%0 reg = %pfmt_arg reg
+execute if score %pfmt_conv reg matches 99 run function intrinsic:put_char
+execute if score %pfmt_conv reg matches 100 run function intrinsic:print_fmt/signed
+execute if score %pfmt_conv reg matches 115 run function intrinsic:print_fmt/string
+execute if score %pfmt_conv reg matches 117 run function intrinsic:print_fmt/unsigned
+execute if score %pfmt_conv reg matches 120 run function intrinsic:print_fmt/hex
+
+# '%%' prints a single '%', and anything else is printed as-is
+execute if score %pfmt_conv reg matches 37 run function intrinsic:put_char
+execute unless score %pfmt_conv reg matches 0 unless score %pfmt_conv reg matches 37 unless score %pfmt_conv reg matches 99..100 unless score %pfmt_conv reg matches 115 unless score %pfmt_conv reg matches 117 unless score %pfmt_conv reg matches 120 run function intrinsic:print_fmt/unknown
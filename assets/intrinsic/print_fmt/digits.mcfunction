@@ -0,0 +1,13 @@
+# Prints the digit of %pfmt_rem at %pfmt_place and the ones after it, skipping leading zeros
+
+scoreboard players operation %pfmt_digit reg = %pfmt_rem reg
+scoreboard players operation %pfmt_digit reg /= %pfmt_place reg
+scoreboard players operation %pfmt_rem reg %= %pfmt_place reg
+
+execute if score %pfmt_digit reg matches 1.. run scoreboard players set %pfmt_started reg 1
+# Zero still prints a single digit
+execute if score %pfmt_place reg matches 1 run scoreboard players set %pfmt_started reg 1
+execute if score %pfmt_started reg matches 1 run function intrinsic:print_fmt/put_digit
+
+scoreboard players operation %pfmt_place reg /= %pfmt_base reg
+execute if score %pfmt_place reg matches 1.. run function intrinsic:print_fmt/digits
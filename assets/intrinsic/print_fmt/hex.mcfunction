@@ -0,0 +1,11 @@
+# Prints %pfmt_arg as an unsigned lowercase hexadecimal number
+
+scoreboard players set %pfmt_base reg 16
+scoreboard players operation %pfmt_place reg = %%268435456 reg
+scoreboard players set %pfmt_started reg 0
+scoreboard players operation %pfmt_rem reg = %pfmt_arg reg
+
+# Numbers of 2^31 or more look negative, so the top bit is moved into the first digit by hand
+execute if score %pfmt_rem reg matches ..-1 run function intrinsic:print_fmt/hex_high
+
+function intrinsic:print_fmt/digits
@@ -0,0 +1,10 @@
+scoreboard players operation %pfmt_rem reg += %%-2147483648 reg
+
+scoreboard players operation %pfmt_digit reg = %pfmt_rem reg
+scoreboard players operation %pfmt_digit reg /= %pfmt_place reg
+scoreboard players operation %pfmt_rem reg %= %pfmt_place reg
+scoreboard players add %pfmt_digit reg 8
+
+scoreboard players set %pfmt_started reg 1
+function intrinsic:print_fmt/put_digit
+scoreboard players operation %pfmt_place reg /= %pfmt_base reg
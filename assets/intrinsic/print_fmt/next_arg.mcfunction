@@ -0,0 +1,7 @@
+# Loads the next argument into %pfmt_arg
+
+scoreboard players operation %ptr reg = %pfmt_args reg
+function intrinsic:setptr
+function intrinsic:load_word
+scoreboard players operation %pfmt_arg reg = %return%0 reg
+scoreboard players add %pfmt_args reg 4
@@ -0,0 +1,6 @@
+# Prints %pfmt_digit as '0'-'9' or 'a'-'f'
+
+scoreboard players operation %param0%0 reg = %pfmt_digit reg
+execute if score %pfmt_digit reg matches 10.. run scoreboard players add %param0%0 reg 39
+scoreboard players add %param0%0 reg 48
+function intrinsic:put_char
@@ -0,0 +1,9 @@
+# Prints %pfmt_arg as a signed decimal number
+
+# '-'
+execute if score %pfmt_arg reg matches ..-1 run scoreboard players set %param0%0 reg 45
+execute if score %pfmt_arg reg matches ..-1 run function intrinsic:put_char
+# The most negative number stays negative, which is then printed correctly as unsigned
+execute if score %pfmt_arg reg matches ..-1 run scoreboard players operation %pfmt_arg reg *= %%-1 reg
+
+function intrinsic:print_fmt/unsigned
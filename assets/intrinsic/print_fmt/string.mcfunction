@@ -0,0 +1,4 @@
+# Prints the null-terminated string at %pfmt_arg
+
+scoreboard players operation %pfmt_str reg = %pfmt_arg reg
+function intrinsic:print_fmt/string_char
@@ -0,0 +1,8 @@
+scoreboard players operation %ptr reg = %pfmt_str reg
+function intrinsic:setptr
+function intrinsic:load_byte
+scoreboard players operation %pfmt_str_c reg = %param0%0 reg
+scoreboard players add %pfmt_str reg 1
+
+execute unless score %pfmt_str_c reg matches 0 run function intrinsic:put_char
+execute unless score %pfmt_str_c reg matches 0 run function intrinsic:print_fmt/string_char
@@ -0,0 +1,6 @@
+# Prints an unsupported conversion as-is
+
+scoreboard players set %param0%0 reg 37
+function intrinsic:put_char
+scoreboard players operation %param0%0 reg = %pfmt_conv reg
+function intrinsic:put_char
@@ -0,0 +1,12 @@
+# Prints %pfmt_arg as an unsigned decimal number
+
+scoreboard players set %pfmt_base reg 10
+scoreboard players set %pfmt_place reg 1000000000
+scoreboard players set %pfmt_started reg 0
+scoreboard players operation %pfmt_rem reg = %pfmt_arg reg
+
+# Numbers of 2^31 or more look negative, so their first digit is found separately:
+# rem = arg - 2^31, then 2^31 = 2 * 10^9 + 147483648 is added back in pieces that don't overflow
+execute if score %pfmt_rem reg matches ..-1 run function intrinsic:print_fmt/unsigned_high
+
+function intrinsic:print_fmt/digits
@@ -0,0 +1,14 @@
+scoreboard players operation %pfmt_rem reg += %%-2147483648 reg
+
+scoreboard players operation %pfmt_digit reg = %pfmt_rem reg
+scoreboard players operation %pfmt_digit reg /= %pfmt_place reg
+scoreboard players operation %pfmt_rem reg %= %pfmt_place reg
+
+scoreboard players add %pfmt_digit reg 2
+scoreboard players add %pfmt_rem reg 147483648
+execute if score %pfmt_rem reg matches 1000000000.. run scoreboard players add %pfmt_digit reg 1
+execute if score %pfmt_rem reg matches 1000000000.. run scoreboard players remove %pfmt_rem reg 1000000000
+
+scoreboard players set %pfmt_started reg 1
+function intrinsic:print_fmt/put_digit
+scoreboard players operation %pfmt_place reg /= %pfmt_base reg
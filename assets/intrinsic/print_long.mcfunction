@@ -0,0 +1,18 @@
+# Prints a 64-bit integer as a chat message
+# %param0%0, %param0%1 - the integer
+
+# Anything from mc_putc that hasn't been printed yet goes on its own line
+execute store result score %plong_len reg run data get storage wasm:stdout buffer
+execute if score %plong_len reg matches 1.. run function intrinsic:put_char/flush
+
+scoreboard players operation %plong_lo reg = %param0%0 reg
+scoreboard players operation %plong_hi reg = %param0%1 reg
+
+# '-'
+execute if score %plong_hi reg matches ..-1 run scoreboard players set %param0%0 reg 45
+execute if score %plong_hi reg matches ..-1 run function intrinsic:put_char
+# The most negative number stays negative, which is then printed correctly as unsigned
+execute if score %plong_hi reg matches ..-1 run function intrinsic:print_long/negate
+
+function intrinsic:print_long/unsigned
+function intrinsic:print_long/done
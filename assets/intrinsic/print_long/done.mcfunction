@@ -0,0 +1,4 @@
+# Prints the number in the stdout buffer the same way `print` does
+
+tellraw @a [{"text":"Printed "},{"nbt":"buffer","storage":"wasm:stdout","interpret":true}]
+data modify storage wasm:stdout buffer set value []
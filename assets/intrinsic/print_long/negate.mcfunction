@@ -0,0 +1,7 @@
+# Negates %plong_lo, %plong_hi: -x == ~x + 1, and ~hi == -hi - 1 while ~lo + 1 == -lo
+
+scoreboard players operation %plong_hi reg *= %%-1 reg
+scoreboard players remove %plong_hi reg 1
+scoreboard players operation %plong_lo reg *= %%-1 reg
+# Adding one only carries into the high word if the low word was zero
+execute if score %plong_lo reg matches 0 run scoreboard players add %plong_hi reg 1
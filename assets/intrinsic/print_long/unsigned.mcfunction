@@ -0,0 +1,34 @@
+# Prints %plong_lo, %plong_hi as an unsigned decimal number,
+# split into groups of nine digits so each one fits in a score: (top * 10^9 + middle) * 10^9 + bottom
+
+scoreboard players operation %udiv_lhs_lo reg = %plong_lo reg
+scoreboard players operation %udiv_lhs_hi reg = %plong_hi reg
+scoreboard players set %udiv_rhs_lo reg 1000000000
+scoreboard players set %udiv_rhs_hi reg 0
+function intrinsic:i64_udivrem/main
+scoreboard players operation %plong_bottom reg = %udiv_r_lo reg
+
+scoreboard players operation %udiv_lhs_lo reg = %udiv_q_lo reg
+scoreboard players operation %udiv_lhs_hi reg = %udiv_q_hi reg
+scoreboard players set %udiv_rhs_lo reg 1000000000
+scoreboard players set %udiv_rhs_hi reg 0
+function intrinsic:i64_udivrem/main
+scoreboard players operation %plong_middle reg = %udiv_r_lo reg
+scoreboard players operation %plong_top reg = %udiv_q_lo reg
+
+scoreboard players set %pfmt_base reg 10
+scoreboard players set %pfmt_started reg 0
+
+# The top group is at most 18
+scoreboard players operation %pfmt_rem reg = %plong_top reg
+scoreboard players set %pfmt_place reg 10
+execute if score %plong_top reg matches 1.. run function intrinsic:print_fmt/digits
+
+scoreboard players operation %pfmt_rem reg = %plong_middle reg
+scoreboard players set %pfmt_place reg 100000000
+execute if score %pfmt_started reg matches 1 run function intrinsic:print_fmt/digits
+execute if score %pfmt_started reg matches 0 if score %plong_middle reg matches 1.. run function intrinsic:print_fmt/digits
+
+scoreboard players operation %pfmt_rem reg = %plong_bottom reg
+scoreboard players set %pfmt_place reg 100000000
+function intrinsic:print_fmt/digits
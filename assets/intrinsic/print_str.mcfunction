@@ -0,0 +1,13 @@
+# Prints a string from memory as a chat message
+# i8* str == %param0%0
+# i32 len == %param1%0
+
+# !INTERPRETER: ASSERT if score %param1%0 reg matches 0..
+
+scoreboard players operation %pstr_ptr reg = %param0%0 reg
+scoreboard players operation %pstr_left reg = %param1%0 reg
+execute if score %pstr_left reg matches 1.. run function intrinsic:print_str/bytes
+
+# Newlines already flush the buffer, so this only prints what's left after the last one
+execute store result score %pstr_left reg run data get storage wasm:stdout buffer
+execute if score %pstr_left reg matches 1.. run function intrinsic:put_char/flush
@@ -0,0 +1,10 @@
+# Adds %pstr_left bytes starting at %pstr_ptr to the stdout buffer
+
+scoreboard players operation %ptr reg = %pstr_ptr reg
+function intrinsic:setptr
+function intrinsic:load_byte
+function intrinsic:put_char
+
+scoreboard players add %pstr_ptr reg 1
+scoreboard players remove %pstr_left reg 1
+execute if score %pstr_left reg matches 1.. run function intrinsic:print_str/bytes
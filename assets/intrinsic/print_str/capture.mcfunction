@@ -0,0 +1,13 @@
+# Puts a string from memory in the stdout buffer on its own so it can be used in a text component,
+# setting aside anything from mc_putc that hasn't been printed yet until print_str/restore
+# i8* str == %param0%0
+# i32 len == %param1%0
+
+# !INTERPRETER: ASSERT if score %param1%0 reg matches 0..
+
+data modify storage wasm:scratch stdout set from storage wasm:stdout buffer
+data modify storage wasm:stdout buffer set value []
+
+scoreboard players operation %pstr_ptr reg = %param0%0 reg
+scoreboard players operation %pstr_left reg = %param1%0 reg
+execute if score %pstr_left reg matches 1.. run function intrinsic:print_str/bytes
@@ -0,0 +1,3 @@
+# Puts back what was in the stdout buffer before print_str/capture
+
+data modify storage wasm:stdout buffer set from storage wasm:scratch stdout
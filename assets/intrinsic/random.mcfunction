@@ -0,0 +1,21 @@
+# Returns a random number from 0 up to but not including the bound,
+# built from the top halves of two steps of a linear congruential generator
+# i32 bound == %param0%0
+# i32 result == %return%0
+
+# !INTERPRETER: ASSERT if score %param0%0 reg matches 1..
+
+scoreboard players operation %%rng_state reg *= %%rng_mul reg
+scoreboard players operation %%rng_state reg += %%rng_inc reg
+scoreboard players operation %return%0 reg = %%rng_state reg
+scoreboard players operation %return%0 reg /= %%65536 reg
+scoreboard players operation %return%0 reg *= %%65536 reg
+
+scoreboard players operation %%rng_state reg *= %%rng_mul reg
+scoreboard players operation %%rng_state reg += %%rng_inc reg
+scoreboard players operation %rng_lo reg = %%rng_state reg
+scoreboard players operation %rng_lo reg /= %%65536 reg
+scoreboard players operation %rng_lo reg %= %%65536 reg
+
+scoreboard players operation %return%0 reg += %rng_lo reg
+scoreboard players operation %return%0 reg %= %param0%0 reg
@@ -0,0 +1,9 @@
+# Moves the turtle forward from the center of its block, one block at a time,
+# until it's in a block that isn't air or it has moved %param0%0 blocks.
+# The turtle is left at the corner of the block it stopped in, like its other positions.
+# %param0%0 - the furthest number of blocks to move
+
+scoreboard players operation %ray_left reg = %param0%0 reg
+execute as 44453000-0-0-0-2 at @s run tp @s ~0.5 ~0.5 ~0.5
+execute if score %ray_left reg matches 1.. run function intrinsic:raycast/step
+execute as 44453000-0-0-0-2 at @s align xyz run tp @s ~ ~ ~
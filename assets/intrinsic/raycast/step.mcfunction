@@ -0,0 +1,5 @@
+# Each step is a whole block, so blocks that the ray only clips the corner of can be missed
+
+execute as 44453000-0-0-0-2 at @s run tp @s ^ ^ ^1
+scoreboard players remove %ray_left reg 1
+execute if score %ray_left reg matches 1.. at 44453000-0-0-0-2 if block ~ ~ ~ minecraft:air run function intrinsic:raycast/step
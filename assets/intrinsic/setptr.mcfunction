@@ -2,10 +2,13 @@ scoreboard players operation %%ptr reg = %ptr reg
 scoreboard players operation %%ptr reg /= %%4 reg
 scoreboard players operation %z reg = %%ptr reg 
 scoreboard players operation %z reg %= %%PAGE_SPAN_Z reg
+scoreboard players operation %z reg += %%memory_z reg
 scoreboard players operation %%ptr reg /= %%PAGE_SPAN_Z reg
 scoreboard players operation %y reg = %%ptr reg
 scoreboard players operation %y reg %= %%PAGE_SPAN_Y reg
 scoreboard players operation %%ptr reg /= %%PAGE_SPAN_Y reg
+scoreboard players operation %%ptr reg += %%memory_x reg
+scoreboard players operation %y reg += %%memory_y reg
 execute store result storage wasm:scratch Pos[0] double 1 run scoreboard players get %%ptr reg
 execute store result storage wasm:scratch Pos[1] double 1 run scoreboard players get %y reg
 execute store result storage wasm:scratch Pos[2] double 1 run scoreboard players get %z reg
@@ -0,0 +1,9 @@
+# Moves the turtle %param0%0 blocks in the direction it faces, or backwards if it's negative.
+# Steps are taken from the center of the block so rounding errors in the rotation can't move it into the wrong block.
+# %param0%0 - the number of blocks to move
+
+scoreboard players operation %fwd_left reg = %param0%0 reg
+execute as 44453000-0-0-0-2 at @s run tp @s ~0.5 ~0.5 ~0.5
+execute if score %fwd_left reg matches 1.. run function intrinsic:turtle_forward/step
+execute if score %fwd_left reg matches ..-1 run function intrinsic:turtle_forward/step_back
+execute as 44453000-0-0-0-2 at @s align xyz run tp @s ~ ~ ~
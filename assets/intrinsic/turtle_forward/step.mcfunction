@@ -0,0 +1,3 @@
+execute as 44453000-0-0-0-2 at @s run tp @s ^ ^ ^1
+scoreboard players remove %fwd_left reg 1
+execute if score %fwd_left reg matches 1.. run function intrinsic:turtle_forward/step
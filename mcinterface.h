@@ -6,7 +6,15 @@ extern "C" {
 #endif
 
 extern void print(int value);
+extern void print_long(long long value);
+// Floats are printed with six digits after the decimal point,
+// or in scientific notation if they are very large or very small, e.g. `1.500000e20`.
+extern void print_float(float value);
+extern void print_double(double value);
 
+// A header generated with `--block-consts` replaces these when included first.
+#ifndef MCINTERFACE_BLOCKS_DEFINED
+#define MCINTERFACE_BLOCKS_DEFINED
 enum Block {
     AIR,
     COBBLESTONE,
@@ -23,10 +31,20 @@ enum Block {
     OAK_LOG_BLOCK,
     OAK_LEAVES_BLOCK,
 };
+#endif
 
 // ptr must be aligned to 32 bytes
 extern void store_8(int *ptr, int value);
 
+// Typed array accessors for byte and halfword buffers, like framebuffers and tilemaps.
+// These are cheaper than normal loads and stores, and ptr doesn't need to be aligned.
+// They're cheaper still when the compiler can tell where in a word the element is,
+// like when ptr is aligned to 4 bytes and idx is a constant.
+extern int buf_get_u8(const unsigned char *ptr, int idx);
+extern void buf_set_u8(unsigned char *ptr, int idx, int value);
+extern int buf_get_u16(const unsigned short *ptr, int idx);
+extern void buf_set_u16(unsigned short *ptr, int idx, int value);
+
 extern void turtle_x(int value);
 extern void turtle_y(int value);
 extern void turtle_z(int value);
@@ -40,6 +58,10 @@ extern void turtle_fill(enum Block block, int x_span, int y_span, int z_span);
 // Sets the block at the turtle's position.
 extern void turtle_set(enum Block block);
 
+// Like `turtle_set`, but also gives the block entity data as SNBT, e.g. `turtle_set_block_nbt(CHEST, "{Items:[]}", 10)`.
+// The data must be a string literal, since it is copied into the datapack when compiling.
+extern void turtle_set_block_nbt(enum Block block, const char *nbt, int len);
+
 // Returns the block at the turtle's position
 extern enum Block turtle_get(void);
 
@@ -50,11 +72,155 @@ inline int turtle_check(enum Block block) {
 
 extern int turtle_get_char(void);
 
+// Sets the direction the turtle faces, like a player's rotation:
+// yaw is 0 for south (+z) and 90 for west (-x), and pitch is -90 for up and 90 for down.
+extern void turtle_face(int yaw, int pitch);
+
+// Moves the turtle the given number of blocks in the direction it faces, or backwards if negative.
+// Facing straight along an axis moves it exactly that many blocks along the axis.
+extern void turtle_forward(int blocks);
+
+// Turns the turtle 90 degrees to its left or right, keeping its pitch
+extern void turtle_turn_left(void);
+extern void turtle_turn_right(void);
+
+// Moves the turtle forward one block at a time, starting from the block after its current one,
+// until it is in a block that isn't air or it has moved `max_dist` blocks.
+// Returns the block the turtle stopped in, like `turtle_get`.
+// Blocks that the ray only clips the corner of can be missed.
+extern enum Block turtle_raycast(int max_dist);
+
+// Returns the light level from 0 to 15 at the turtle's position.
+extern int turtle_light_level(void);
+
+// Returns 1 if the block at the turtle's position is air.
+extern int turtle_is_air(void);
+
+// Returns 1 if the block at the turtle's position is neither air nor water or lava.
+extern int turtle_is_solid(void);
+
+enum Sound {
+    SOUND_NOTE_HARP,
+    SOUND_NOTE_BASS,
+    SOUND_NOTE_BELL,
+    SOUND_STONE_BREAK,
+    SOUND_CLICK,
+    SOUND_EXPERIENCE_ORB,
+    SOUND_ITEM_PICKUP,
+    SOUND_LEVEL_UP,
+    SOUND_EXPLOSION,
+    SOUND_HURT,
+};
+
+// Plays a sound at the turtle's position for all players.
+// The volume and pitch are given in hundredths, e.g. `mc_playsound(SOUND_CLICK, 100, 150)` plays at a pitch of 1.5,
+// and they have to be constants.
+extern void mc_playsound(enum Sound sound, int volume, int pitch);
+
+enum Particle {
+    PARTICLE_FLAME,
+    PARTICLE_SMOKE,
+    PARTICLE_CLOUD,
+    PARTICLE_HEART,
+    PARTICLE_HAPPY_VILLAGER,
+    PARTICLE_CRIT,
+    PARTICLE_NOTE,
+    PARTICLE_END_ROD,
+    PARTICLE_EXPLOSION,
+};
+
+// Shows particles around the center of the turtle's block. The count has to be a constant.
+extern void mc_particle(enum Particle particle, int count);
+
+// There are 4 bossbars, numbered 0 to 3, which are hidden until they are created.
+// Creating a bossbar that already exists resets it, and it's shown to all players.
+extern void mc_bossbar_create(int bar);
+extern void mc_bossbar_value(int bar, int value);
+extern void mc_bossbar_max(int bar, int max);
+// Sets the bossbar's name to a string, which can't contain any newlines
+extern void mc_bossbar_name(int bar, const char *name, int len);
+
+// Returns the position of the player nearest to the turtle, rounded down to a block.
+// If there are no players, this returns 0.
+extern int nearest_player_x(void);
+extern int nearest_player_y(void);
+extern int nearest_player_z(void);
+
+enum Selector {
+    // All players
+    PLAYERS,
+    // All entities, including players
+    ENTITIES,
+    // All entities except players
+    NON_PLAYERS,
+    // Dropped items
+    ITEMS,
+    // Players standing in the turtle's block
+    PLAYERS_AT_TURTLE,
+};
+
+// Returns the number of entities that match the selector
+extern int entity_count(enum Selector selector);
+
+// Returns the number of ticks the world has existed for, which increases by 20 every second
+extern long long mc_gametime(void);
+
+// Returns a random number from 0 up to but not including `bound`, which must be positive
+extern int mc_random(int bound);
+
 // Pauses execution and continues it on the next tick
 extern void mc_sleep();
 
+// Pauses execution and continues it after the given number of ticks, e.g. `mc_sleep_ticks(20)` waits for a second.
+// The number of ticks must be a constant of at least 1.
+extern void mc_sleep_ticks(int ticks);
+
 extern void mc_putc(int ch);
 
+// Prints a string as a chat message, which is much faster than printing it a character at a time.
+// Any characters from `mc_putc` that haven't been printed yet are printed first, on the same line.
+extern void print_str(const char *str, int len);
+
+// Prints a formatted chat message using a routine built into the datapack, which is much smaller than a full printf.
+// Supports `%d`, `%u`, `%x`, `%c`, `%s`, and `%%`, without any flags, widths, or precisions.
+extern void print_fmt(const char *fmt, __builtin_va_list args);
+
+static inline void mc_printf(const char *fmt, ...) {
+    __builtin_va_list args;
+    __builtin_va_start(args, fmt);
+    print_fmt(fmt, args);
+    __builtin_va_end(args);
+}
+
+// Shows a string in large text in the middle of the screen of all players
+extern void mc_title(const char *str, int len);
+// Shows a string above the hotbar of all players
+extern void mc_actionbar(const char *str, int len);
+
+// Runs a Minecraft command, e.g. `mc_command("time set day", 12)` (a leading slash is optional).
+// The command must be a string literal, since it is copied into the datapack when compiling.
+extern void mc_command(const char *cmd, int len);
+
+// Convenience wrapper that takes the length of the string literal automatically
+#define MC_COMMAND(cmd) mc_command(cmd, sizeof(cmd) - 1)
+
+// Like `mc_command`, but returns the command's result, e.g. `mc_command_result("time query daytime", 18)`.
+// Commands that fail return 0.
+extern int mc_command_result(const char *cmd, int len);
+
+// Like `mc_command`, but returns 1 if the command succeeded and 0 otherwise.
+extern int mc_command_success(const char *cmd, int len);
+
+#define MC_COMMAND_RESULT(cmd) mc_command_result(cmd, sizeof(cmd) - 1)
+#define MC_COMMAND_SUCCESS(cmd) mc_command_success(cmd, sizeof(cmd) - 1)
+
+// Copies the memory region with the given ID (set with `--snapshot-region START:LENGTH`) into storage.
+// The whole region is copied at once, so execution is never paused in the middle of a snapshot.
+extern void memory_snapshot(int region_id);
+
+// Copies a region previously saved with `memory_snapshot` back into memory.
+extern void memory_restore(int region_id);
+
 #ifdef __cplusplus
 }
 #endif
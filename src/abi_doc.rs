@@ -0,0 +1,407 @@
+//! Generates a description of the interface a compiled datapack exposes to other datapacks:
+//! which functions to call, which scoreboard holders and storage they communicate through,
+//! and where linear memory lives in the world.
+
+use std::fmt::Write;
+
+use wasmparser::ValType;
+
+use crate::{CompileContext, wasm_file::WasmFile, ssa::SsaProgram, lir::{DoubleRegister, Register}, pack_emitter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbiDocFormat {
+	Markdown,
+	Json,
+}
+
+impl AbiDocFormat {
+	/// Picks the format based on the extension of the output file, defaulting to Markdown.
+	pub fn from_path(path: &std::path::Path) -> Self {
+		match path.extension().and_then(|e| e.to_str()) {
+			Some("json") => AbiDocFormat::Json,
+			_ => AbiDocFormat::Markdown,
+		}
+	}
+}
+
+struct AbiFunction {
+	name: String,
+	function: String,
+	params: Vec<(ValType, Vec<String>)>,
+	returns: Vec<(ValType, Vec<String>)>,
+	/// The `wasmrunner:call/*` function, which uses holders in the call objective instead
+	call_function: String,
+	call_params: Vec<(ValType, Vec<String>)>,
+	call_returns: Vec<(ValType, Vec<String>)>,
+}
+
+struct AbiImport {
+	module: String,
+	field: String,
+	mapped_to: Option<String>,
+	/// The call function of another datapack that calls to the import run
+	pack_call: Option<String>,
+	params: Vec<ValType>,
+	returns: Vec<ValType>,
+}
+
+struct AbiGlobal {
+	index: usize,
+	ty: ValType,
+	/// The `MODULE.FIELD` name of an imported global
+	import: Option<String>,
+	holders: Vec<String>,
+}
+
+struct AbiMemory {
+	size: usize,
+	maximum: Option<usize>,
+	first_block: (i32, i32, i32),
+	last_block: (i32, i32, i32),
+}
+
+struct AbiDoc {
+	objective: String,
+	call_objective: String,
+	/// The NBT path of the word stored in each memory jukebox
+	memory_path: &'static str,
+	storage: Vec<(&'static str, &'static str)>,
+	functions: Vec<(String, &'static str)>,
+	exports: Vec<AbiFunction>,
+	imports: Vec<AbiImport>,
+	globals: Vec<AbiGlobal>,
+	memory: Vec<AbiMemory>,
+	snapshot_regions: Vec<(u32, u32)>,
+}
+
+fn holder_names(reg: DoubleRegister, ty: ValType) -> Vec<String> {
+	let holder = |r: Register| r.scoreboard_pair().0.to_string();
+	match ty {
+		ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => vec![holder(reg.lo())],
+		ValType::I64 | ValType::F64 => vec![holder(reg.lo()), holder(reg.hi())],
+		_ => panic!("values of type {ty:?} have no holders"),
+	}
+}
+
+fn collect(ctx: &CompileContext, wasm_file: &WasmFile, program: &SsaProgram) -> AbiDoc {
+	let objective = crate::lir::OBJECTIVE_NAME.to_string();
+
+	let storage = vec![
+		("wasm:datastack", "Spilled values, saved across calls"),
+		("wasm:localstack", "Locals of functions currently on the call stack"),
+		("wasm:returnstack", "Return addresses; exports push a sentinel before running"),
+		("wasm:scratch", "Temporary data, not preserved between commands"),
+		("wasm:stdout", "Buffered output of the print intrinsics"),
+	];
+
+	let init_desc = if program.start.is_some() {
+		"Resets all state, initializes memory, and runs the start function, must be run once before calling any export"
+	} else {
+		"Resets all state and initializes memory, must be run once before calling any export"
+	};
+	let mut functions = vec![
+		("wasmrunner:init".to_string(), init_desc),
+	];
+	if ctx.controls {
+		functions.push(("wasmrunner:controls".to_string(), "Shows the control panel in chat"));
+	}
+	for memory_idx in 0..program.memory.len() {
+		functions.push((pack_emitter::get_memory_grow_func(memory_idx as u32), "Grows memory by the number of pages in the first parameter, returning the old size or -1"));
+	}
+	if !ctx.snapshot_regions.is_empty() {
+		functions.push(("wasmrunner:__memory_snapshot".to_string(), "Saves the snapshot region selected by the first parameter"));
+		functions.push(("wasmrunner:__memory_restore".to_string(), "Restores the snapshot region selected by the first parameter"));
+	}
+	if pack_emitter::has_player_input(ctx, &program.exports) {
+		functions.push(("wasmrunner:__player_events".to_string(), "Gives new players an ID in the `wasm_player_id` objective and calls the player event and trigger exports, started by init and rescheduled every tick"));
+	}
+	if ctx.framebuffer.is_some() {
+		functions.push(("wasmrunner:__framebuffer".to_string(), "Redraws the changed parts of the framebuffer wall, started by init and rescheduled automatically"));
+	}
+	for (name, _) in pack_emitter::get_tick_exports(ctx, &program.exports, |id| !program.get_func(id.func as u32).params.is_empty()) {
+		functions.push((format!("wasmrunner:__tick_{name}"), "Calls the export with the same name on a loop, started by init and rescheduled automatically"));
+	}
+
+	let mut exports = program.exports.iter().map(|(name, id)| {
+		let func = program.get_func(id.func as u32);
+		AbiFunction {
+			name: name.clone(),
+			function: format!("wasmrunner:{name}"),
+			params: func.params.iter().enumerate().map(|(i, ty)| (*ty, holder_names(DoubleRegister::param(i as u32), *ty))).collect(),
+			returns: func.returns.iter().enumerate().map(|(i, ty)| (*ty, holder_names(DoubleRegister::return_reg(i as u32), *ty))).collect(),
+			call_function: format!("wasmrunner:call/{name}"),
+			call_params: func.params.iter().enumerate().map(|(i, ty)| (*ty, pack_emitter::get_call_holders("arg", i, *ty))).collect(),
+			call_returns: func.returns.iter().enumerate().map(|(i, ty)| (*ty, pack_emitter::get_call_holders("ret", i, *ty))).collect(),
+		}
+	}).collect::<Vec<_>>();
+	exports.sort_by(|a, b| a.name.cmp(&b.name));
+
+	let imports = wasm_file.imports.func_imports().iter().enumerate().map(|(idx, import)| {
+		let ty = wasm_file.func_type(idx);
+		let mapped = ctx.map_import(import.module, import.field);
+		let mapped_to = if mapped != (import.module, import.field) {
+			Some(format!("{}.{}", mapped.0, mapped.1))
+		} else {
+			None
+		};
+
+		let pack_call = ctx.pack_imports.get(mapped.0).map(|namespace| format!("{namespace}:call/{}", mapped.1));
+
+		AbiImport {
+			module: import.module.to_owned(),
+			field: import.field.to_owned(),
+			mapped_to,
+			pack_call,
+			params: ty.params.to_vec(),
+			returns: ty.returns.to_vec(),
+		}
+	}).collect();
+
+	let global_imports = wasm_file.imports.global_imports();
+	let globals = program.globals.iter().enumerate().map(|(index, value)| {
+		let ty = value.ty();
+		let import = global_imports.get(index).map(|import| format!("{}.{}", import.module, import.field));
+		AbiGlobal { index, ty, import, holders: holder_names(DoubleRegister::global(index as u32), ty) }
+	}).collect();
+
+	let memory = program.memory.iter().enumerate().map(|(memory_idx, mem)| {
+		let size = mem.data.len();
+		let last_word = size.saturating_sub(4) as i32;
+		AbiMemory {
+			size,
+			maximum: mem.maximum,
+			first_block: pack_emitter::get_memory_address_pos(ctx.layout.memory_origin, memory_idx as u32, 0),
+			last_block: pack_emitter::get_memory_address_pos(ctx.layout.memory_origin, memory_idx as u32, last_word),
+		}
+	}).collect();
+
+	let snapshot_regions = ctx.snapshot_regions.iter().map(|r| (r.start, r.length)).collect();
+
+	let call_objective = pack_emitter::CALL_OBJECTIVE.to_string();
+
+	let memory_path = if ctx.mc_version.has_item_components() {
+		r#"RecordItem.components."minecraft:custom_data".Memory"#
+	} else {
+		"RecordItem.tag.Memory"
+	};
+
+	AbiDoc { objective, call_objective, memory_path, storage, functions, exports, imports, globals, memory, snapshot_regions }
+}
+
+fn type_list(tys: &[ValType]) -> String {
+	let tys = tys.iter().map(|ty| format!("{ty:?}").to_lowercase()).collect::<Vec<_>>();
+	format!("({})", tys.join(", "))
+}
+
+fn render_markdown(doc: &AbiDoc) -> String {
+	let mut s = String::new();
+
+	writeln!(s, "# Datapack ABI").unwrap();
+	writeln!(s).unwrap();
+	writeln!(s, "All values are stored as scores in the `{}` objective. 64-bit values are split into a low and high 32-bit half.", doc.objective).unwrap();
+	writeln!(s).unwrap();
+
+	writeln!(s, "## Calling convention").unwrap();
+	writeln!(s).unwrap();
+	writeln!(s, "1. Set each parameter's score holders.").unwrap();
+	writeln!(s, "2. Run the export's function.").unwrap();
+	writeln!(s, "3. Once the program has finished, read each result's score holders.").unwrap();
+	writeln!(s).unwrap();
+	writeln!(s, "Long-running exports may continue over several ticks using `schedule`, so results are not necessarily available right after the function returns.").unwrap();
+	writeln!(s).unwrap();
+	writeln!(s, "Each export also has a call function, which takes its parameters from and writes its results to score holders in the `{}` objective that don't change between compilations.", doc.call_objective).unwrap();
+	writeln!(s, "Call functions are the stable interface for other datapacks: their names, holders, and objective stay the same between versions of wasmcraft, and other programs compiled by it call them through `--import-pack`.").unwrap();
+	writeln!(s).unwrap();
+
+	writeln!(s, "## Functions").unwrap();
+	writeln!(s).unwrap();
+	for (function, desc) in doc.functions.iter() {
+		writeln!(s, "- `{function}`: {desc}").unwrap();
+	}
+	writeln!(s).unwrap();
+
+	writeln!(s, "## Exports").unwrap();
+	writeln!(s).unwrap();
+	for export in doc.exports.iter() {
+		writeln!(s, "### `{}`", export.name).unwrap();
+		writeln!(s).unwrap();
+		writeln!(s, "Function: `{}`", export.function).unwrap();
+		writeln!(s).unwrap();
+		for (kind, values) in [("Parameter", &export.params), ("Result", &export.returns)] {
+			for (i, (ty, holders)) in values.iter().enumerate() {
+				let holders = holders.iter().map(|h| format!("`{h}`")).collect::<Vec<_>>().join(", ");
+				writeln!(s, "- {kind} {i} ({}): {holders}", format!("{ty:?}").to_lowercase()).unwrap();
+			}
+		}
+		writeln!(s).unwrap();
+		writeln!(s, "Call function: `{}`", export.call_function).unwrap();
+		writeln!(s).unwrap();
+		for (kind, values) in [("Parameter", &export.call_params), ("Result", &export.call_returns)] {
+			for (i, (ty, holders)) in values.iter().enumerate() {
+				let holders = holders.iter().map(|h| format!("`{h} {}`", doc.call_objective)).collect::<Vec<_>>().join(", ");
+				writeln!(s, "- {kind} {i} ({}): {holders}", format!("{ty:?}").to_lowercase()).unwrap();
+			}
+		}
+		writeln!(s).unwrap();
+	}
+
+	if !doc.imports.is_empty() {
+		writeln!(s, "## Imports").unwrap();
+		writeln!(s).unwrap();
+		for import in doc.imports.iter() {
+			write!(s, "- `{}.{}` {} -> {}", import.module, import.field, type_list(&import.params), type_list(&import.returns)).unwrap();
+			if let Some(mapped_to) = &import.mapped_to {
+				write!(s, ", mapped to `{mapped_to}`").unwrap();
+			}
+			if let Some(pack_call) = &import.pack_call {
+				write!(s, ", calls `{pack_call}` in another datapack").unwrap();
+			}
+			writeln!(s).unwrap();
+		}
+		writeln!(s).unwrap();
+	}
+
+	if !doc.globals.is_empty() {
+		writeln!(s, "## Globals").unwrap();
+		writeln!(s).unwrap();
+		for global in doc.globals.iter() {
+			let holders = global.holders.iter().map(|h| format!("`{h}`")).collect::<Vec<_>>().join(", ");
+			write!(s, "- Global {} ({})", global.index, format!("{:?}", global.ty).to_lowercase()).unwrap();
+			if let Some(import) = &global.import {
+				write!(s, ", imported as `{import}`").unwrap();
+			}
+			writeln!(s, ": {holders}").unwrap();
+		}
+		writeln!(s).unwrap();
+	}
+
+	writeln!(s, "## Storage").unwrap();
+	writeln!(s).unwrap();
+	for (storage, desc) in doc.storage.iter() {
+		writeln!(s, "- `{storage}`: {desc}").unwrap();
+	}
+	writeln!(s).unwrap();
+
+	if !doc.memory.is_empty() {
+		writeln!(s, "## Memory").unwrap();
+		writeln!(s).unwrap();
+		writeln!(s, "Each 4-byte little-endian word is stored in the `{}` field of a jukebox.", doc.memory_path).unwrap();
+		writeln!(s).unwrap();
+		for (i, mem) in doc.memory.iter().enumerate() {
+			let (x0, y0, z0) = mem.first_block;
+			let (x1, y1, z1) = mem.last_block;
+			write!(s, "- Memory {i}: {} bytes", mem.size).unwrap();
+			if let Some(maximum) = mem.maximum {
+				write!(s, " (at most {maximum} pages)").unwrap();
+			}
+			writeln!(s, ", from {x0} {y0} {z0} to {x1} {y1} {z1}").unwrap();
+		}
+		writeln!(s).unwrap();
+	}
+
+	if !doc.snapshot_regions.is_empty() {
+		writeln!(s, "## Snapshot regions").unwrap();
+		writeln!(s).unwrap();
+		for (id, (start, length)) in doc.snapshot_regions.iter().enumerate() {
+			writeln!(s, "- Region {id}: {length} bytes starting at {start}").unwrap();
+		}
+		writeln!(s).unwrap();
+	}
+
+	s
+}
+
+fn json_str(s: &str) -> String {
+	let mut result = String::from("\"");
+	for c in s.chars() {
+		match c {
+			'"' => result.push_str("\\\""),
+			'\\' => result.push_str("\\\\"),
+			c if (c as u32) < 0x20 => write!(result, "\\u{:04x}", c as u32).unwrap(),
+			c => result.push(c),
+		}
+	}
+	result.push('"');
+	result
+}
+
+fn json_list<T, F: Fn(&T) -> String>(items: &[T], f: F) -> String {
+	format!("[{}]", items.iter().map(f).collect::<Vec<_>>().join(","))
+}
+
+fn json_ty(ty: &ValType) -> String {
+	json_str(&format!("{ty:?}").to_lowercase())
+}
+
+fn json_values(values: &[(ValType, Vec<String>)]) -> String {
+	json_list(values, |(ty, holders)| {
+		format!(r#"{{"type":{},"holders":{}}}"#, json_ty(ty), json_list(holders, |h| json_str(h)))
+	})
+}
+
+fn render_json(doc: &AbiDoc) -> String {
+	let functions = json_list(&doc.functions, |(function, desc)| {
+		format!(r#"{{"function":{},"description":{}}}"#, json_str(function), json_str(desc))
+	});
+
+	let exports = json_list(&doc.exports, |export| {
+		format!(
+			r#"{{"name":{},"function":{},"params":{},"returns":{},"call":{{"function":{},"params":{},"returns":{}}}}}"#,
+			json_str(&export.name), json_str(&export.function), json_values(&export.params), json_values(&export.returns),
+			json_str(&export.call_function), json_values(&export.call_params), json_values(&export.call_returns)
+		)
+	});
+
+	let imports = json_list(&doc.imports, |import| {
+		let mapped_to = import.mapped_to.as_deref().map(json_str).unwrap_or_else(|| "null".to_string());
+		let pack_call = import.pack_call.as_deref().map(json_str).unwrap_or_else(|| "null".to_string());
+		format!(
+			r#"{{"module":{},"field":{},"mapped_to":{},"pack_call":{},"params":{},"returns":{}}}"#,
+			json_str(&import.module), json_str(&import.field), mapped_to, pack_call, json_list(&import.params, json_ty), json_list(&import.returns, json_ty)
+		)
+	});
+
+	let globals = json_list(&doc.globals, |global| {
+		let import = global.import.as_deref().map(json_str).unwrap_or_else(|| "null".to_string());
+		format!(
+			r#"{{"index":{},"type":{},"import":{},"holders":{}}}"#,
+			global.index, json_ty(&global.ty), import, json_list(&global.holders, |h| json_str(h))
+		)
+	});
+
+	let storage = json_list(&doc.storage, |(storage, desc)| {
+		format!(r#"{{"storage":{},"description":{}}}"#, json_str(storage), json_str(desc))
+	});
+
+	let memory = json_list(&doc.memory, |mem| {
+		let maximum = mem.maximum.map(|m| m.to_string()).unwrap_or_else(|| "null".to_string());
+		let (x0, y0, z0) = mem.first_block;
+		let (x1, y1, z1) = mem.last_block;
+		format!(r#"{{"size":{},"maximum_pages":{maximum},"first_block":[{x0},{y0},{z0}],"last_block":[{x1},{y1},{z1}]}}"#, mem.size)
+	});
+
+	let snapshot_regions = json_list(&doc.snapshot_regions, |(start, length)| {
+		format!(r#"{{"start":{start},"length":{length}}}"#)
+	});
+
+	format!(
+		r#"{{"objective":{},"call_objective":{},"functions":{functions},"exports":{exports},"imports":{imports},"globals":{globals},"storage":{storage},"memory":{memory},"snapshot_regions":{snapshot_regions}}}"#,
+		json_str(&doc.objective), json_str(&doc.call_objective)
+	) + "\n"
+}
+
+/// Describes the interface of the compiled program, for use by other datapacks.
+pub fn generate(ctx: &CompileContext, wasm_file: &WasmFile, program: &SsaProgram, format: AbiDocFormat) -> String {
+	let doc = collect(ctx, wasm_file, program);
+
+	let rendered = match format {
+		AbiDocFormat::Markdown => render_markdown(&doc),
+		AbiDocFormat::Json => render_json(&doc),
+	};
+
+	// Describes the names the datapack is written with, not the ones it was compiled with
+	match ctx.pack_namespace() {
+		Some(namespace) => namespace.apply(&rendered),
+		None => rendered,
+	}
+}
@@ -0,0 +1,160 @@
+//! Generates block ID constants for guest programs from the block palette,
+//! and checks the palette against a block registry report from Minecraft's data generator.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockConstsFormat {
+	C,
+	Rust,
+}
+
+impl BlockConstsFormat {
+	/// Picks the format based on the extension of the output file, defaulting to a C header.
+	pub fn from_path(path: &std::path::Path) -> Self {
+		match path.extension().and_then(|e| e.to_str()) {
+			Some("rs") => BlockConstsFormat::Rust,
+			_ => BlockConstsFormat::C,
+		}
+	}
+}
+
+/// The blocks in a `blocks.json` report, with the values each of their properties can take.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockRegistry {
+	blocks: BTreeMap<String, BTreeMap<String, Vec<String>>>,
+}
+
+impl BlockRegistry {
+	/// Parses the report generated by `java -DbundlerMainClass=net.minecraft.data.Main -jar server.jar --reports`.
+	pub fn parse(contents: &str) -> Result<Self, String> {
+		let report: serde_json::Value = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+		let report = report.as_object().ok_or("expected an object of blocks")?;
+		if report.is_empty() {
+			return Err("it does not contain any blocks".to_string());
+		}
+
+		let mut blocks = BTreeMap::new();
+		for (name, block) in report.iter() {
+			let properties = match block.get("properties") {
+				Some(properties) => serde_json::from_value(properties.clone()).map_err(|e| format!("invalid properties for {name:?}: {e}"))?,
+				None => BTreeMap::new(),
+			};
+			blocks.insert(name.clone(), properties);
+		}
+
+		Ok(BlockRegistry { blocks })
+	}
+
+	/// Checks that a block state like `minecraft:oak_stairs[facing=east]` names a block in the registry
+	/// and only gives it properties it has.
+	pub fn check_state(&self, state: &str) -> Result<(), String> {
+		let (name, props) = split_state(state);
+
+		let name = if name.contains(':') { name.to_string() } else { format!("minecraft:{name}") };
+		let properties = self.blocks.get(&name).ok_or_else(|| format!("unknown block {name:?}"))?;
+
+		for (key, value) in props {
+			match properties.get(key) {
+				Some(values) if values.iter().any(|v| v == value) => {}
+				Some(values) => return Err(format!("{value:?} is not a value of {key:?} for {name:?}, expected one of {values:?}")),
+				None => return Err(format!("{name:?} has no property {key:?}")),
+			}
+		}
+
+		Ok(())
+	}
+}
+
+/// Splits a block state into its block name and properties, ignoring any block entity data.
+fn split_state(state: &str) -> (&str, Vec<(&str, &str)>) {
+	let state = state.split('{').next().unwrap();
+
+	match state.split_once('[') {
+		Some((name, props)) => {
+			let props = props.trim_end_matches(']').split(',')
+				.filter(|p| !p.trim().is_empty())
+				.map(|p| {
+					let (key, value) = p.split_once('=').unwrap_or((p, ""));
+					(key.trim(), value.trim())
+				})
+				.collect();
+			(name, props)
+		}
+		None => (state, Vec::new()),
+	}
+}
+
+/// Turns a block state into a constant name, e.g. `minecraft:oak_stairs[facing=east]` into `OAK_STAIRS_FACING_EAST`.
+/// Blocks outside of the `minecraft` namespace keep their namespace as a prefix.
+fn const_name(state: &str) -> String {
+	let (name, props) = split_state(state);
+	let name = name.strip_prefix("minecraft:").unwrap_or(name);
+
+	let mut result = String::new();
+	for part in std::iter::once(name).chain(props.iter().flat_map(|(k, v)| [*k, *v])) {
+		if !result.is_empty() {
+			result.push('_');
+		}
+		result.extend(part.chars().map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' }));
+	}
+
+	if result.starts_with(|c: char| c.is_ascii_digit()) {
+		result.insert_str(0, "BLOCK_");
+	}
+
+	result
+}
+
+/// Returns a constant name for each block in the palette,
+/// adding the block ID to names that would otherwise be used more than once.
+fn const_names(palette: &[String]) -> Vec<String> {
+	let names = palette.iter().map(|state| const_name(state)).collect::<Vec<_>>();
+
+	names.iter().enumerate().map(|(idx, name)| {
+		if names.iter().filter(|n| *n == name).count() > 1 {
+			format!("{name}_{idx}")
+		} else {
+			name.clone()
+		}
+	}).collect()
+}
+
+fn render_c(palette: &[String], names: &[String]) -> String {
+	let mut s = String::new();
+	writeln!(s, "// Generated by wasmcraft from the block palette, do not edit.").unwrap();
+	writeln!(s, "// Include this before `mcinterface.h` to replace its `Block` enum.").unwrap();
+	writeln!(s, "#ifndef MCINTERFACE_BLOCKS_DEFINED").unwrap();
+	writeln!(s, "#define MCINTERFACE_BLOCKS_DEFINED").unwrap();
+	writeln!(s).unwrap();
+	writeln!(s, "enum Block {{").unwrap();
+	for (idx, (state, name)) in palette.iter().zip(names).enumerate() {
+		writeln!(s, "    {name} = {idx}, // {state}").unwrap();
+	}
+	writeln!(s, "}};").unwrap();
+	writeln!(s).unwrap();
+	writeln!(s, "#endif").unwrap();
+	s
+}
+
+fn render_rust(palette: &[String], names: &[String]) -> String {
+	let mut s = String::new();
+	writeln!(s, "//! Generated by wasmcraft from the block palette, do not edit.").unwrap();
+	writeln!(s).unwrap();
+	for (idx, (state, name)) in palette.iter().zip(names).enumerate() {
+		writeln!(s, "/// `{state}`").unwrap();
+		writeln!(s, "pub const {name}: i32 = {idx};").unwrap();
+	}
+	s
+}
+
+/// Lists the ID of each block in the palette as constants the program can use with the turtle intrinsics.
+pub fn generate(palette: &[String], format: BlockConstsFormat) -> String {
+	let names = const_names(palette);
+
+	match format {
+		BlockConstsFormat::C => render_c(palette, &names),
+		BlockConstsFormat::Rust => render_rust(palette, &names),
+	}
+}
@@ -1,18 +1,21 @@
+use std::collections::{BTreeMap, HashMap, btree_map};
+
 use crate::ssa::BlockId;
 
 
 /// Only allows block IDs from within a single function.
 ///
-/// Stores values as a Vec<Option<T>>, because BlockIds are usually densely packed.
+/// Stores values in a BTreeMap keyed by block index, so that lookups and finding vacant IDs
+/// don't have to step over the gaps left behind when blocks are removed.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct LocalBlockMap<T> {
 	func_id: Option<usize>,
-	data: Vec<Option<T>>,
+	data: BTreeMap<usize, T>,
 }
 
 impl<T> LocalBlockMap<T> {
 	pub fn new(func_id: usize) -> Self {
-		LocalBlockMap { func_id: Some(func_id), data: Vec::new() }
+		LocalBlockMap { func_id: Some(func_id), data: BTreeMap::new() }
 	}
 
 	pub fn func_id(&self) -> usize {
@@ -20,11 +23,11 @@ impl<T> LocalBlockMap<T> {
 	}
 
 	pub fn len(&self) -> usize {
-		self.data.iter().filter(|d| d.is_some()).count()
+		self.data.len()
 	}
 
 	pub fn is_empty(&self) -> bool {
-		self.len() == 0
+		self.data.is_empty()
 	}
 
 	fn check_func_id(&mut self, func_id: usize) {
@@ -38,43 +41,64 @@ impl<T> LocalBlockMap<T> {
 	pub fn insert(&mut self, k: BlockId, v: T) -> Option<T> {
 		self.check_func_id(k.func);
 
-		if k.block >= self.data.len() {
-			self.data.resize_with(k.block + 1, Default::default);
-		}
-
-		std::mem::replace(&mut self.data[k.block], Some(v))
+		self.data.insert(k.block, v)
 	}
 
 	pub fn get(&self, k: BlockId) -> Option<&T> {
 		if let Some(f) = self.func_id { assert_eq!(k.func, f); }
 
-		self.data.get(k.block).and_then(|v| v.as_ref())
+		self.data.get(&k.block)
 	}
 
 	pub fn get_mut(&mut self, k: BlockId) -> Option<&mut T> {
 		if let Some(f) = self.func_id { assert_eq!(k.func, f); }
 
-		self.data.get_mut(k.block).and_then(|v| v.as_mut())
+		self.data.get_mut(&k.block)
 	}
 
 	pub fn remove(&mut self, k: BlockId) -> Option<T> {
 		if let Some(f) = self.func_id { assert_eq!(k.func, f); }
 
-		if let Some(entry) = self.data.get_mut(k.block) {
-			entry.take()
-		} else {
-			None
+		self.data.remove(&k.block)
+	}
+
+	/// Moves all values to the lowest block IDs, keeping them in the same order,
+	/// so that the IDs are densely packed again after blocks are removed.
+	/// Returns a mapping from each old ID to its new ID.
+	pub fn renumber(&mut self) -> HashMap<BlockId, BlockId> {
+		let mut mapping = HashMap::new();
+		let mut data = BTreeMap::new();
+		for (idx, value) in std::mem::take(&mut self.data) {
+			let func = self.func_id.expect("a LocalBlockMap with values must have a function ID");
+			let new_idx = data.len();
+			mapping.insert(BlockId { func, block: idx }, BlockId { func, block: new_idx });
+			data.insert(new_idx, value);
 		}
+
+		self.data = data;
+		mapping
 	}
 
-	pub fn entry(&mut self, k: BlockId) -> &mut Option<T> {
-		if let Some(f) = self.func_id { assert_eq!(k.func, f); }
+	/// Returns the lowest block ID that doesn't have a value, starting from the given block index.
+	/// Only the values in the run of used IDs starting there are looked at.
+	pub fn first_vacant_from(&self, start: usize) -> BlockId {
+		let func = self.func_id.unwrap();
 
-		if k.block >= self.data.len() {
-			self.data.resize_with(k.block + 1, Default::default);
+		let mut block = start;
+		for &used in self.data.range(start..).map(|(idx, _)| idx) {
+			if used != block {
+				break;
+			}
+			block += 1;
 		}
 
-		&mut self.data[k.block]
+		BlockId { func, block }
+	}
+
+	pub fn entry(&mut self, k: BlockId) -> btree_map::Entry<'_, usize, T> {
+		self.check_func_id(k.func);
+
+		self.data.entry(k.block)
 	}
 
 	pub fn contains_key(&self, k: BlockId) -> bool {
@@ -84,19 +108,13 @@ impl<T> LocalBlockMap<T> {
 	pub fn iter(&self) -> impl Iterator<Item=(BlockId, &T)> + '_ {
 		let func = self.func_id.unwrap_or(usize::MAX);
 
-		self.data.iter().enumerate().flat_map(move |(idx, v)| {
-			let block_id = BlockId { func, block: idx };
-			v.as_ref().map(|v| (block_id, v))
-		})
+		self.data.iter().map(move |(&block, v)| (BlockId { func, block }, v))
 	}
 
 	pub fn iter_mut(&mut self) -> impl Iterator<Item=(BlockId, &mut T)> {
 		let func = self.func_id.unwrap_or(usize::MAX);
 
-		self.data.iter_mut().enumerate().flat_map(move |(idx, v)| {
-			let block_id = BlockId { func, block: idx };
-			v.as_mut().map(|v| (block_id, v))
-		})
+		self.data.iter_mut().map(move |(&block, v)| (BlockId { func, block }, v))
 	}
 
 	pub fn keys(&self) -> impl Iterator<Item=BlockId> + '_ {
@@ -104,35 +122,18 @@ impl<T> LocalBlockMap<T> {
 	}
 
 	pub fn values(&self) -> impl Iterator<Item=&T> {
-		self.iter().map(|(_k, v)| v)
-	}
-
-	/// Includes keys that don't have a corresponding value in the map.
-	pub fn iter_all_keys(&self) -> impl Iterator<Item=BlockId> {
-		let func = self.func_id.unwrap();
-
-		(0..self.data.len()).map(move |i| {
-			BlockId { func, block: i }
-		})
+		self.data.values()
 	}
 }
 
-pub struct LocalBlockMapIntoIter<T>(LocalBlockMap<T>, usize);
+pub struct LocalBlockMapIntoIter<T>(Option<usize>, btree_map::IntoIter<usize, T>);
 
 impl<V> Iterator for LocalBlockMapIntoIter<V> {
     type Item = (BlockId, V);
 
     fn next(&mut self) -> Option<Self::Item> {
-		while self.1 < self.0.data.len() {
-			let old_idx = self.1;
-			self.1 += 1;
-
-			if let Some(value) = self.0.data[old_idx].take() {
-				return Some((BlockId { func: self.0.func_id.unwrap(), block: old_idx }, value))
-			}
-		}
-
-		None
+		let (block, value) = self.1.next()?;
+		Some((BlockId { func: self.0.unwrap(), block }, value))
     }
 }
 
@@ -142,13 +143,13 @@ impl<V> IntoIterator for LocalBlockMap<V> {
     type IntoIter = LocalBlockMapIntoIter<V>;
 
     fn into_iter(self) -> Self::IntoIter {
-		LocalBlockMapIntoIter(self, 0)
+		LocalBlockMapIntoIter(self.func_id, self.data.into_iter())
     }
 }
 
 impl<V> FromIterator<(BlockId, V)> for LocalBlockMap<V> {
     fn from_iter<T: IntoIterator<Item = (BlockId, V)>>(iter: T) -> Self {
-		let mut data = Vec::new();
+		let mut data = BTreeMap::new();
 
 		let mut func_id = None;
 
@@ -159,14 +160,75 @@ impl<V> FromIterator<(BlockId, V)> for LocalBlockMap<V> {
 				func_id = Some(block_id.func);
 			}
 
-			if block_id.block >= data.len() {
-				data.resize_with(block_id.block + 1, || None);
-			}
-
-			assert!(data[block_id.block].is_none());
-			data[block_id.block] = Some(block);
+			assert!(data.insert(block_id.block, block).is_none());
 		}
 
 		LocalBlockMap { func_id, data }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn renumber_compacts() {
+		let mut map = LocalBlockMap::new(3);
+		for block in [0, 2, 5, 6] {
+			map.insert(BlockId { func: 3, block }, block);
+		}
+		map.remove(BlockId { func: 3, block: 6 });
+
+		let mapping = map.renumber();
+
+		assert_eq!(mapping.len(), 3);
+		assert_eq!(mapping[&BlockId { func: 3, block: 0 }], BlockId { func: 3, block: 0 });
+		assert_eq!(mapping[&BlockId { func: 3, block: 2 }], BlockId { func: 3, block: 1 });
+		assert_eq!(mapping[&BlockId { func: 3, block: 5 }], BlockId { func: 3, block: 2 });
+		assert_eq!(map.iter().map(|(k, v)| (k.block, *v)).collect::<Vec<_>>(), vec![(0, 0), (1, 2), (2, 5)]);
+		assert_eq!(map.first_vacant_from(0), BlockId { func: 3, block: 3 });
+	}
+	#[test]
+	fn renumber_sparse() {
+		let mut map = LocalBlockMap::new(1);
+		for block in [0, 1000, 70000, 70001] {
+			map.insert(BlockId { func: 1, block }, block);
+		}
+		map.remove(BlockId { func: 1, block: 1000 });
+
+		let mapping = map.renumber();
+
+		assert_eq!(mapping[&BlockId { func: 1, block: 70000 }], BlockId { func: 1, block: 1 });
+		assert_eq!(mapping[&BlockId { func: 1, block: 70001 }], BlockId { func: 1, block: 2 });
+		assert!(!mapping.contains_key(&BlockId { func: 1, block: 1000 }));
+		assert_eq!(map.keys().map(|k| k.block).collect::<Vec<_>>(), vec![0, 1, 2]);
+		assert_eq!(map.len(), 3);
+		assert_eq!(map.get(BlockId { func: 1, block: 2 }), Some(&70001));
+		assert_eq!(map.first_vacant_from(0), BlockId { func: 1, block: 3 });
+	}
+
+	#[test]
+	fn renumber_empty() {
+		let mut map = LocalBlockMap::<u32>::new(2);
+		map.insert(BlockId { func: 2, block: 4 }, 4);
+		map.remove(BlockId { func: 2, block: 4 });
+		assert!(map.renumber().is_empty());
+		assert!(map.is_empty());
+
+		let mut map = std::iter::empty::<(BlockId, u32)>().collect::<LocalBlockMap<_>>();
+		assert!(map.renumber().is_empty());
+	}
+
+	#[test]
+	fn first_vacant_skips_used_runs() {
+		let mut map = LocalBlockMap::new(0);
+		for block in [0, 1, 2, 5, 100_000] {
+			map.insert(BlockId { func: 0, block }, ());
+		}
+
+		assert_eq!(map.first_vacant_from(0), BlockId { func: 0, block: 3 });
+		assert_eq!(map.first_vacant_from(4), BlockId { func: 0, block: 4 });
+		assert_eq!(map.first_vacant_from(5), BlockId { func: 0, block: 6 });
+		assert_eq!(map.first_vacant_from(100_000), BlockId { func: 0, block: 100_001 });
+	}
+}
@@ -0,0 +1,122 @@
+//! Assumptions about how expensive the generated commands are to run,
+//! used to decide how many commands can run before yielding to the next tick.
+
+/// Built-in cost models, selectable with `--cost-model`.
+pub const PROFILES: &[(&str, &str)] = &[
+	("default", "Leaves plenty of headroom below the vanilla command limit"),
+	("vanilla", "Runs as close to the default maxCommandChainLength of 65536 as is safe"),
+	("server", "Yields often to keep ticks short on multiplayer servers"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostModel {
+	/// How many commands may run in a single tick before the program yields
+	pub max_cmds_per_tick: usize,
+	/// Extra commands assumed to run for every function call, in addition to the call itself
+	pub call_overhead: usize,
+	/// Extra commands run by a call to an intrinsic, matched by the first name contained in the command
+	pub intrinsic_costs: Vec<(String, usize)>,
+}
+
+impl Default for CostModel {
+	fn default() -> Self {
+		CostModel::from_profile("default").unwrap()
+	}
+}
+
+impl CostModel {
+	pub fn from_profile(name: &str) -> Option<Self> {
+		let max_cmds_per_tick = match name {
+			"default" => 30_000,
+			"vanilla" => 60_000,
+			"server" => 10_000,
+			_ => return None,
+		};
+
+		let intrinsic_costs = [
+			("intrinsic:and", 200),
+			("intrinsic:or", 200),
+			("intrinsic:xor", 200),
+			("intrinsic:shl_64", 700),
+			("intrinsic:i64_sdivrem/rem", 4_000),
+			("intrinsic:i64_sdivrem/div", 3_500),
+			("intrinsic:i64_udivrem", 3_500),
+			("intrinsic:f32/add", 110),
+			("intrinsic:f32/mul", 190),
+			("intrinsic:f32/div", 220),
+			("intrinsic:f64/add", 160),
+			("intrinsic:f64/mul", 240),
+			("intrinsic:f64/div", 1_150),
+			("intrinsic:f32/min", 20),
+			("intrinsic:f32/max", 20),
+			("intrinsic:f64/min", 35),
+			("intrinsic:f64/max", 35),
+			("intrinsic:f32/sqrt", 470),
+			("intrinsic:f64/sqrt", 2_100),
+			("intrinsic:f32/round", 180),
+			("intrinsic:f64/round", 350),
+			("intrinsic:f32/trunc", 150),
+			("intrinsic:f64/trunc", 150),
+			("intrinsic:f32/convert", 330),
+			("intrinsic:f64/convert", 300),
+		].into_iter().map(|(name, cost)| (name.to_string(), cost)).collect();
+
+		Some(CostModel { max_cmds_per_tick, call_overhead: 0, intrinsic_costs })
+	}
+
+	/// Overrides parts of this cost model with the ones given in a TOML file, for example:
+	///
+	/// ```toml
+	/// max_cmds_per_tick = 50000
+	/// call_overhead = 1
+	///
+	/// [intrinsic_costs]
+	/// "intrinsic:xor" = 150
+	/// ```
+	pub fn apply_toml(&mut self, contents: &str) -> Result<(), String> {
+		let value = contents.parse::<toml::Value>().map_err(|e| e.to_string())?;
+		let table = value.as_table().ok_or("expected a table")?;
+
+		let as_usize = |key: &str, value: &toml::Value| {
+			value.as_integer()
+				.and_then(|v| usize::try_from(v).ok())
+				.ok_or_else(|| format!("`{key}` must be a non-negative integer"))
+		};
+
+		for (key, value) in table.iter() {
+			match key.as_str() {
+				"max_cmds_per_tick" => self.max_cmds_per_tick = as_usize(key, value)?,
+				"call_overhead" => self.call_overhead = as_usize(key, value)?,
+				"intrinsic_costs" => {
+					let costs = value.as_table().ok_or("`intrinsic_costs` must be a table")?;
+					for (name, cost) in costs.iter() {
+						let cost = as_usize(name, cost)?;
+						match self.intrinsic_costs.iter_mut().find(|(n, _)| n == name) {
+							Some(existing) => existing.1 = cost,
+							None => self.intrinsic_costs.push((name.clone(), cost)),
+						}
+					}
+				}
+				_ => return Err(format!("unknown cost model key `{key}`")),
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Estimates how many commands will be run by the given commands, including any intrinsics they call.
+	pub fn cmd_count(&self, code: &[String]) -> usize {
+		let mut num_cmds = code.len();
+		for c in code.iter() {
+			if let Some((_, cost)) = self.intrinsic_costs.iter().find(|(name, _)| c.contains(name.as_str())) {
+				num_cmds += cost;
+			}
+			// TODO: memset? bitwise operations?
+
+			if c.contains("function ") {
+				num_cmds += self.call_overhead;
+			}
+		}
+		num_cmds
+	}
+}
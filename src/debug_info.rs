@@ -0,0 +1,488 @@
+//! A map from the functions of an emitted datapack back to the wasm code they were compiled from,
+//! so what a program does in game can be traced back to its source.
+//!
+//! Every line of a block's function comes from the same wasm operators, which are given as ranges of offsets
+//! into the code section, the same addresses DWARF uses. If the module has a `.debug_line` section,
+//! the source lines those ranges were compiled from are given too.
+
+use std::{collections::{BTreeSet, HashMap}, ops::Range};
+
+use datapack_common::functions::Function;
+
+use crate::{lir::LirProgram, mc_version::McVersion, pack_emitter, wasm_file::WasmFile};
+
+const DW_LNS_COPY: u8 = 1;
+const DW_LNS_ADVANCE_PC: u8 = 2;
+const DW_LNS_ADVANCE_LINE: u8 = 3;
+const DW_LNS_SET_FILE: u8 = 4;
+const DW_LNS_CONST_ADD_PC: u8 = 8;
+const DW_LNS_FIXED_ADVANCE_PC: u8 = 9;
+
+const DW_LNE_END_SEQUENCE: u8 = 1;
+const DW_LNE_SET_ADDRESS: u8 = 2;
+
+const DW_LNCT_PATH: u64 = 1;
+const DW_LNCT_DIRECTORY_INDEX: u64 = 2;
+
+const DW_FORM_DATA2: u64 = 0x05;
+const DW_FORM_DATA4: u64 = 0x06;
+const DW_FORM_DATA8: u64 = 0x07;
+const DW_FORM_STRING: u64 = 0x08;
+const DW_FORM_BLOCK: u64 = 0x09;
+const DW_FORM_DATA1: u64 = 0x0B;
+const DW_FORM_STRP: u64 = 0x0E;
+const DW_FORM_UDATA: u64 = 0x0F;
+const DW_FORM_DATA16: u64 = 0x1E;
+const DW_FORM_LINE_STRP: u64 = 0x1F;
+
+pub struct DebugInfo {
+	/// Every function emitted for a block of wasm code, in the order of the blocks.
+	pub functions: Vec<FunctionDebugInfo>,
+}
+
+pub struct FunctionDebugInfo {
+	/// The ID of the datapack function.
+	pub id: String,
+	/// How many lines the function is written as, all of which come from the code below.
+	pub lines: usize,
+	pub wasm_function: usize,
+	pub name: Option<String>,
+	pub block: usize,
+	/// Offsets into the code section of the operators the block was compiled from.
+	pub code_ranges: Vec<Range<usize>>,
+	/// The source lines of those operators, if the module has a line table.
+	pub source: Vec<SourceLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct SourceLine {
+	pub file: String,
+	pub line: u64,
+}
+
+impl DebugInfo {
+	pub fn to_json(&self) -> String {
+		let json = serde_json::json!({
+			"functions": self.functions.iter().map(|func| serde_json::json!({
+				"function": func.id,
+				"lines": func.lines,
+				"wasm_function": func.wasm_function,
+				"name": func.name,
+				"block": func.block,
+				"code_ranges": func.code_ranges.iter().map(|range| serde_json::json!({
+					"start": range.start,
+					"end": range.end,
+				})).collect::<Vec<_>>(),
+				"source": func.source.iter().map(|source| serde_json::json!({
+					"file": source.file,
+					"line": source.line,
+				})).collect::<Vec<_>>(),
+			})).collect::<Vec<_>>(),
+		});
+		serde_json::to_string_pretty(&json).unwrap()
+	}
+}
+
+/// Finds the wasm code each function of a datapack was compiled from, and its source lines if there's a line table.
+pub fn compute(datapack: &[Function], program: &LirProgram, line_table: Option<&LineTable>, version: McVersion) -> DebugInfo {
+	let blocks = program.code.iter()
+		.flat_map(|func| func.code_ranges.iter())
+		.map(|(block_id, ranges)| (pack_emitter::get_mc_id(&program.func_names, *block_id), (*block_id, ranges)))
+		.collect::<HashMap<_, _>>();
+
+	let mut functions = datapack.iter().filter_map(|func| {
+		let id = func.id.to_string();
+		let (block_id, ranges) = blocks.get(&id)?;
+		Some(FunctionDebugInfo {
+			lines: pack_emitter::written_lines(func, version)?.len(),
+			id,
+			wasm_function: block_id.func,
+			name: program.func_names.get(&block_id.func).cloned(),
+			block: block_id.block,
+			code_ranges: ranges.to_vec(),
+			source: line_table.map(|table| table.lookup(ranges)).unwrap_or_default(),
+		})
+	}).collect::<Vec<_>>();
+	functions.sort_by_key(|func| (func.wasm_function, func.block));
+
+	DebugInfo { functions }
+}
+
+/// Reads the line table of a module, if it has one.
+/// A line table that can't be read is left out with a warning, since the offsets are still useful without it.
+pub fn read_line_table(wasm_file: &WasmFile) -> Option<LineTable> {
+	let debug_line = wasm_file.debug_sections.get(".debug_line")?;
+	let debug_line_str = wasm_file.debug_sections.get(".debug_line_str").copied().unwrap_or_default();
+	let debug_str = wasm_file.debug_sections.get(".debug_str").copied().unwrap_or_default();
+
+	match LineTable::parse(debug_line, debug_line_str, debug_str) {
+		Ok(table) => Some(table),
+		Err(err) => {
+			println!("warning: couldn't read the line table in .debug_line: {err}");
+			None
+		}
+	}
+}
+
+/// The rows of a DWARF line table, which give the source line of each code address.
+pub struct LineTable {
+	files: Vec<String>,
+	/// The addresses each source line covers, sorted by where they start.
+	entries: Vec<LineEntry>,
+	/// The furthest any entry up to each one ends, so the entries around an address can be found by searching.
+	max_ends: Vec<u64>,
+}
+
+struct LineEntry {
+	addresses: Range<u64>,
+	file: usize,
+	line: u64,
+}
+
+impl LineTable {
+	/// Parses a `.debug_line` section of DWARF version 2 to 5,
+	/// along with the string sections that version 5 file names can be kept in.
+	pub fn parse(debug_line: &[u8], debug_line_str: &[u8], debug_str: &[u8]) -> Result<Self, String> {
+		let strings = Strings { debug_line_str, debug_str };
+
+		let mut files = Vec::new();
+		let mut entries = Vec::new();
+
+		let mut reader = Reader { data: debug_line, pos: 0 };
+		while reader.pos < debug_line.len() {
+			let mut unit_length = reader.u32()? as u64;
+			let is_64 = unit_length == 0xFFFF_FFFF;
+			if is_64 {
+				unit_length = reader.u64()?;
+			}
+			let unit_end = reader.pos.checked_add(unit_length as usize).filter(|end| *end <= debug_line.len())
+				.ok_or_else(|| format!("unit at {:#X} goes past the end of the section", reader.pos))?;
+
+			let file_base = files.len();
+			let program = parse_unit_header(&mut reader, is_64, &strings, &mut files)?;
+			reader.pos = program.start;
+			run_line_program(&mut reader, unit_end, &program, file_base, files.len(), &mut entries)?;
+			reader.pos = unit_end;
+		}
+
+		entries.sort_by_key(|entry| entry.addresses.start);
+		let max_ends = entries.iter().scan(0, |max_end, entry| {
+			*max_end = entry.addresses.end.max(*max_end);
+			Some(*max_end)
+		}).collect();
+
+		Ok(LineTable { files, entries, max_ends })
+	}
+
+	/// The source lines of the code at any of the given addresses.
+	pub fn lookup(&self, ranges: &[Range<usize>]) -> Vec<SourceLine> {
+		let mut lines = BTreeSet::new();
+		for range in ranges.iter() {
+			let (start, end) = (range.start as u64, range.end as u64);
+			let first = self.max_ends.partition_point(|max_end| *max_end <= start);
+			for entry in self.entries[first..].iter().take_while(|entry| entry.addresses.start < end) {
+				if entry.addresses.end > start {
+					lines.insert(SourceLine { file: self.files[entry.file].clone(), line: entry.line });
+				}
+			}
+		}
+		lines.into_iter().collect()
+	}
+}
+
+/// What's needed from the header of a unit to run its line number program.
+struct LineProgram<'a> {
+	start: usize,
+	address_size: u8,
+	min_instruction_length: u64,
+	line_base: i64,
+	line_range: u64,
+	opcode_base: u8,
+	standard_opcode_lengths: &'a [u8],
+	/// Whether file indices start at 0, like they do from version 5 on
+	zero_based_files: bool,
+}
+
+/// Reads the header of a unit of the line table up to its line number program, adding the unit's files to `files`.
+fn parse_unit_header<'a>(reader: &mut Reader<'a>, is_64: bool, strings: &Strings, files: &mut Vec<String>) -> Result<LineProgram<'a>, String> {
+	let version = reader.u16()?;
+	if !(2..=5).contains(&version) {
+		return Err(format!("unsupported line table version {version}"));
+	}
+
+	let address_size = if version >= 5 {
+		let address_size = reader.u8()?;
+		let _segment_selector_size = reader.u8()?;
+		address_size
+	} else {
+		4
+	};
+
+	let header_length = reader.offset(is_64)?;
+	let start = reader.pos + header_length as usize;
+
+	let min_instruction_length = reader.u8()? as u64;
+	if version >= 4 {
+		let _max_ops_per_instruction = reader.u8()?;
+	}
+	let _default_is_stmt = reader.u8()?;
+	let line_base = reader.u8()? as i8 as i64;
+	let line_range = reader.u8()? as u64;
+	if line_range == 0 {
+		return Err("line range is 0".to_string());
+	}
+	let opcode_base = reader.u8()?;
+	let standard_opcode_lengths = reader.take(opcode_base.saturating_sub(1) as usize)?;
+
+	if version >= 5 {
+		let dirs = read_entry_list(reader, is_64, strings)?;
+		for (path, dir) in read_entry_list(reader, is_64, strings)? {
+			let dir = dirs.get(dir as usize).map_or("", |(dir, _)| dir.as_str());
+			files.push(join_path(dir, &path));
+		}
+	} else {
+		// Directory 0 is where the unit was compiled, which isn't in the table before version 5
+		let mut dirs = vec![String::new()];
+		loop {
+			let dir = reader.cstr()?;
+			if dir.is_empty() {
+				break;
+			}
+			dirs.push(dir);
+		}
+
+		loop {
+			let path = reader.cstr()?;
+			if path.is_empty() {
+				break;
+			}
+			let dir = reader.uleb()?;
+			let _mtime = reader.uleb()?;
+			let _length = reader.uleb()?;
+			files.push(join_path(dirs.get(dir as usize).map_or("", String::as_str), &path));
+		}
+	}
+
+	Ok(LineProgram {
+		start,
+		address_size,
+		min_instruction_length,
+		line_base,
+		line_range,
+		opcode_base,
+		standard_opcode_lengths,
+		zero_based_files: version >= 5,
+	})
+}
+
+/// Reads a list of directory or file entries from a version 5 header, as each one's path and directory index.
+fn read_entry_list(reader: &mut Reader, is_64: bool, strings: &Strings) -> Result<Vec<(String, u64)>, String> {
+	let format_count = reader.u8()?;
+	let formats = (0..format_count).map(|_| Ok((reader.uleb()?, reader.uleb()?))).collect::<Result<Vec<_>, String>>()?;
+
+	let count = reader.uleb()?;
+	(0..count).map(|_| {
+		let mut path = String::new();
+		let mut dir = 0;
+		for &(content_type, form) in formats.iter() {
+			match (content_type, read_form(reader, form, is_64, strings)?) {
+				(DW_LNCT_PATH, FormValue::String(value)) => path = value,
+				(DW_LNCT_DIRECTORY_INDEX, FormValue::Unsigned(value)) => dir = value,
+				_ => {}
+			}
+		}
+		Ok((path, dir))
+	}).collect()
+}
+
+enum FormValue {
+	String(String),
+	Unsigned(u64),
+	Other,
+}
+
+fn read_form(reader: &mut Reader, form: u64, is_64: bool, strings: &Strings) -> Result<FormValue, String> {
+	Ok(match form {
+		DW_FORM_STRING => FormValue::String(reader.cstr()?),
+		DW_FORM_LINE_STRP => FormValue::String(strings.read(strings.debug_line_str, reader.offset(is_64)?, ".debug_line_str")?),
+		DW_FORM_STRP => FormValue::String(strings.read(strings.debug_str, reader.offset(is_64)?, ".debug_str")?),
+		DW_FORM_DATA1 => FormValue::Unsigned(reader.u8()? as u64),
+		DW_FORM_DATA2 => FormValue::Unsigned(reader.u16()? as u64),
+		DW_FORM_DATA4 => FormValue::Unsigned(reader.u32()? as u64),
+		DW_FORM_DATA8 => FormValue::Unsigned(reader.u64()?),
+		DW_FORM_UDATA => FormValue::Unsigned(reader.uleb()?),
+		DW_FORM_DATA16 => {
+			reader.take(16)?;
+			FormValue::Other
+		}
+		DW_FORM_BLOCK => {
+			let len = reader.uleb()?;
+			reader.take(len as usize)?;
+			FormValue::Other
+		}
+		_ => return Err(format!("unsupported form {form:#X} in file entry")),
+	})
+}
+
+/// Runs a unit's line number program, adding the addresses each line covers to `entries`.
+/// The unit's files are `file_base..file_end` in the table.
+fn run_line_program(reader: &mut Reader, unit_end: usize, program: &LineProgram, file_base: usize, file_end: usize, entries: &mut Vec<LineEntry>) -> Result<(), String> {
+	let file_index = |file: u64| {
+		let file = if program.zero_based_files { Some(file) } else { file.checked_sub(1) }?;
+		Some(file_base + file as usize).filter(|file| *file < file_end)
+	};
+
+	// The rows of the current sequence, as their address and the file and line from there on
+	let mut rows: Vec<(u64, Option<(usize, u64)>)> = Vec::new();
+	let mut address = 0;
+	let mut file = 1;
+	let mut line = 1;
+
+	while reader.pos < unit_end {
+		let opcode = reader.u8()?;
+		match opcode {
+			0 => {
+				let len = reader.uleb()? as usize;
+				match reader.take(len)?.split_first() {
+					Some((&DW_LNE_END_SEQUENCE, _)) => {
+						rows.push((address, None));
+						for pair in rows.windows(2) {
+							if let ((start, Some((file, line))), (end, _)) = (pair[0], pair[1]) {
+								if start < end && line != 0 {
+									entries.push(LineEntry { addresses: start..end, file, line });
+								}
+							}
+						}
+						rows.clear();
+						address = 0;
+						file = 1;
+						line = 1;
+					}
+					Some((&DW_LNE_SET_ADDRESS, bytes)) => {
+						let size = (program.address_size as usize).min(bytes.len()).min(8);
+						address = bytes[..size].iter().rev().fold(0, |acc, byte| (acc << 8) | *byte as u64);
+					}
+					_ => {}
+				}
+			}
+			op if op >= program.opcode_base => {
+				let adjusted = (op - program.opcode_base) as u64;
+				address += (adjusted / program.line_range) * program.min_instruction_length;
+				line += program.line_base + (adjusted % program.line_range) as i64;
+				rows.push((address, file_index(file).map(|file| (file, line.max(0) as u64))));
+			}
+			DW_LNS_COPY => rows.push((address, file_index(file).map(|file| (file, line.max(0) as u64)))),
+			DW_LNS_ADVANCE_PC => address += reader.uleb()? * program.min_instruction_length,
+			DW_LNS_ADVANCE_LINE => line += reader.sleb()?,
+			DW_LNS_SET_FILE => file = reader.uleb()?,
+			DW_LNS_CONST_ADD_PC => address += ((255 - program.opcode_base) as u64 / program.line_range) * program.min_instruction_length,
+			DW_LNS_FIXED_ADVANCE_PC => address += reader.u16()? as u64,
+			// Everything else only changes state that isn't kept, so its operands are skipped
+			op => {
+				for _ in 0..program.standard_opcode_lengths[op as usize - 1] {
+					reader.uleb()?;
+				}
+			}
+		}
+	}
+
+	Ok(())
+}
+
+fn join_path(dir: &str, path: &str) -> String {
+	if dir.is_empty() || path.starts_with('/') {
+		path.to_string()
+	} else {
+		format!("{}/{}", dir.trim_end_matches('/'), path)
+	}
+}
+
+struct Strings<'a> {
+	debug_line_str: &'a [u8],
+	debug_str: &'a [u8],
+}
+
+impl Strings<'_> {
+	fn read(&self, section: &[u8], offset: u64, name: &str) -> Result<String, String> {
+		if offset as usize >= section.len() {
+			return Err(format!("string at {offset:#X} is past the end of {name}"));
+		}
+		Reader { data: section, pos: offset as usize }.cstr()
+	}
+}
+
+struct Reader<'a> {
+	data: &'a [u8],
+	pos: usize,
+}
+
+impl<'a> Reader<'a> {
+	fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+		let bytes = self.data.get(self.pos..).and_then(|rest| rest.get(..len))
+			.ok_or_else(|| format!("unexpected end of section at {:#X}", self.pos))?;
+		self.pos += len;
+		Ok(bytes)
+	}
+
+	fn u8(&mut self) -> Result<u8, String> {
+		Ok(self.take(1)?[0])
+	}
+
+	fn u16(&mut self) -> Result<u16, String> {
+		Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+	}
+
+	fn u32(&mut self) -> Result<u32, String> {
+		Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+	}
+
+	fn u64(&mut self) -> Result<u64, String> {
+		Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+	}
+
+	/// An offset into a section, which is 8 bytes in 64-bit DWARF.
+	fn offset(&mut self, is_64: bool) -> Result<u64, String> {
+		if is_64 { self.u64() } else { self.u32().map(|offset| offset as u64) }
+	}
+
+	fn uleb(&mut self) -> Result<u64, String> {
+		let mut result = 0;
+		let mut shift = 0;
+		loop {
+			let byte = self.u8()?;
+			if shift < 64 {
+				result |= ((byte & 0x7F) as u64) << shift;
+			}
+			shift += 7;
+			if byte & 0x80 == 0 {
+				return Ok(result);
+			}
+		}
+	}
+
+	fn sleb(&mut self) -> Result<i64, String> {
+		let mut result = 0;
+		let mut shift = 0;
+		loop {
+			let byte = self.u8()?;
+			if shift < 64 {
+				result |= ((byte & 0x7F) as i64) << shift;
+			}
+			shift += 7;
+			if byte & 0x80 == 0 {
+				if shift < 64 && byte & 0x40 != 0 {
+					result |= -1 << shift;
+				}
+				return Ok(result);
+			}
+		}
+	}
+
+	fn cstr(&mut self) -> Result<String, String> {
+		let len = self.data.get(self.pos..).and_then(|rest| rest.iter().position(|byte| *byte == 0))
+			.ok_or_else(|| format!("unterminated string at {:#X}", self.pos))?;
+		let s = String::from_utf8_lossy(self.take(len)?).into_owned();
+		self.pos += 1;
+		Ok(s)
+	}
+}
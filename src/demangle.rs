@@ -0,0 +1,123 @@
+//! Demangles the symbol names found in the wasm `name` section,
+//! so dumps and emitted function names show `my_crate::render` instead of `_ZN8my_crate6render17h0123456789abcdefE`.
+//!
+//! Only the common Itanium-style `_ZN...E` nested names used by C++ and the legacy Rust mangling are handled.
+//! C++ parameter types are dropped, and anything that can't be demangled is left as-is.
+
+/// Returns the demangled form of a symbol, or the symbol itself if it isn't mangled in a supported way.
+pub fn demangle(symbol: &str) -> String {
+	try_demangle(symbol).unwrap_or_else(|| symbol.to_string())
+}
+
+fn try_demangle(symbol: &str) -> Option<String> {
+	let rest = symbol.strip_prefix("_Z")?;
+
+	let mut segments = Vec::new();
+	if let Some(mut rest) = rest.strip_prefix('N') {
+		// Qualifiers on member functions, e.g. `_ZNK3Foo3barEv`
+		rest = rest.trim_start_matches(['K', 'V', 'r']);
+
+		while !rest.starts_with('E') {
+			let (segment, tail) = read_source_name(rest)?;
+			segments.push(segment);
+			rest = tail;
+		}
+	} else {
+		let (segment, _) = read_source_name(rest)?;
+		segments.push(segment);
+	}
+
+	// Rust appends a segment with a hash of the crate, e.g. `h0123456789abcdef`
+	if segments.len() > 1 && is_rust_hash(segments.last().unwrap()) {
+		segments.pop();
+	}
+
+	let segments = segments.into_iter().map(unescape_rust_segment).collect::<Option<Vec<_>>>()?;
+	Some(segments.join("::"))
+}
+
+/// Reads an identifier prefixed by its length in decimal, returning it and the remaining input.
+fn read_source_name(s: &str) -> Option<(&str, &str)> {
+	let digits = s.bytes().take_while(u8::is_ascii_digit).count();
+	let len = s[..digits].parse::<usize>().ok()?;
+	let rest = &s[digits..];
+	if len == 0 || rest.len() < len || !rest.is_char_boundary(len) {
+		return None;
+	}
+
+	Some(rest.split_at(len))
+}
+
+fn is_rust_hash(segment: &str) -> bool {
+	segment.len() == 17 && segment.starts_with('h') && segment[1..].bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Undoes the escapes the legacy Rust mangling uses for characters that aren't allowed in symbols,
+/// e.g. `_$LT$impl$u20$Foo$GT$` becomes `<impl Foo>`.
+fn unescape_rust_segment(segment: &str) -> Option<String> {
+	// Segments starting with an escape get an extra underscore in front
+	let segment = segment.strip_prefix('_').filter(|s| s.starts_with('$')).unwrap_or(segment);
+
+	let mut result = String::new();
+	let mut rest = segment;
+	while !rest.is_empty() {
+		if let Some(tail) = rest.strip_prefix('$') {
+			let end = tail.find('$')?;
+			let escape = &tail[..end];
+			let c = match escape {
+				"SP" => '@',
+				"BP" => '*',
+				"RF" => '&',
+				"LT" => '<',
+				"GT" => '>',
+				"LP" => '(',
+				"RP" => ')',
+				"C" => ',',
+				_ => {
+					let hex = escape.strip_prefix('u')?;
+					char::from_u32(u32::from_str_radix(hex, 16).ok()?)?
+				}
+			};
+			result.push(c);
+			rest = &tail[end + 1..];
+		} else if let Some(tail) = rest.strip_prefix("..") {
+			result.push_str("::");
+			rest = tail;
+		} else {
+			let c = rest.chars().next().unwrap();
+			result.push(c);
+			rest = &rest[c.len_utf8()..];
+		}
+	}
+
+	Some(result)
+}
+
+#[cfg(test)]
+mod test {
+	use super::demangle;
+
+	#[test]
+	fn rust_symbols() {
+		assert_eq!(demangle("_ZN8my_crate6render17h0123456789abcdefE"), "my_crate::render");
+		assert_eq!(demangle("_ZN4core3fmt5write17h9f5e4c1b2a3d4e5fE"), "core::fmt::write");
+		assert_eq!(
+			demangle("_ZN60_$LT$alloc..string..String$u20$as$u20$core..fmt..Display$GT$3fmt17h0000000000000000E"),
+			"<alloc::string::String as core::fmt::Display>::fmt"
+		);
+	}
+
+	#[test]
+	fn cpp_symbols() {
+		assert_eq!(demangle("_ZN3foo3barEv"), "foo::bar");
+		assert_eq!(demangle("_ZNK3Foo4sizeEv"), "Foo::size");
+		assert_eq!(demangle("_Z6squarei"), "square");
+	}
+
+	#[test]
+	fn unmangled_names_are_unchanged() {
+		assert_eq!(demangle("main"), "main");
+		assert_eq!(demangle("__original_main"), "__original_main");
+		assert_eq!(demangle("_ZN99tooshortE"), "_ZN99tooshortE");
+	}
+}
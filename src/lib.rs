@@ -1,9 +1,10 @@
 #![allow(dead_code)]
 
-use std::time::SystemTime;
+use std::{time::SystemTime, collections::HashMap};
 use command_parser::CommandParse;
 use datapack_common::functions::{command_components::NbtPath, Function};
 use clap::{Parser, clap_derive::ValueEnum};
+use pack_emitter::memory_backend::{self, MemoryBackend};
 use lir::LirProgram;
 use ssa::SsaProgram;
 use wasm_file::WasmFile;
@@ -12,10 +13,19 @@ use crate::{validator::wasm_to_ssa, ssa::lir_emitter};
 
 pub mod wasm_file;
 pub mod validator;
+pub mod linker;
 pub mod ssa;
 pub mod lir;
 pub mod pack_emitter;
 pub mod block_id_map;
+pub mod remarks;
+pub mod abi_doc;
+pub mod pack_stats;
+pub mod debug_info;
+pub mod block_consts;
+pub mod mc_version;
+pub mod cost_model;
+pub mod demangle;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CodegenStage {
@@ -26,6 +36,9 @@ enum CodegenStage {
 
 const CODEGEN_STAGE: CodegenStage = CodegenStage::Datapack;
 
+/// Every entry a table can grow to needs its own scoreboard holder, so growth is capped by default.
+const DEFAULT_MAX_TABLE_SIZE: u32 = 1024;
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 enum RegAllocMode {
     /// No-op regalloc. Faster compilation, slower output.
@@ -43,6 +56,12 @@ pub struct Args {
     #[clap(value_parser, value_hint = clap::ValueHint::FilePath)]
     input: std::path::PathBuf,
 
+	/// Another WebAssembly module to link into the program, named after its file without the extension.
+	/// Imports from a module with that name are resolved to its exports, sharing the memories and globals it exports.
+	/// Can be passed multiple times.
+	#[clap(long = "link", value_parser, value_hint = clap::ValueHint::FilePath)]
+	linked_modules: Vec<std::path::PathBuf>,
+
     /// Run the generated output under the interpreter.
     #[clap(short = 'r', long, action)]
     run_output: bool,
@@ -84,16 +103,633 @@ pub struct Args {
     #[clap(short = 'o', value_parser, value_hint = clap::ValueHint::DirPath)]
     output: std::path::PathBuf,
 
+	/// Path to a world save to install the datapack into.
+	/// The datapack is written to the world's `datapacks` folder, named after the output path.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::DirPath)]
+	install: Option<std::path::PathBuf>,
+
+	/// Replace the markers used as pointers whenever the datapack is loaded,
+	/// so a program that's already running keeps working after `/reload`.
+	#[clap(long, action)]
+	refresh_markers: bool,
+
+	/// Copy the scores of a paused program into storage every time it yields to a later tick,
+	/// and put them back when the datapack is loaded if they were lost, so the program continues where it left off.
+	/// Needs `--jump-mode bounce`.
+	#[clap(long, action)]
+	persist_state: bool,
+
+	/// Only rewrite the files of the datapack whose contents changed, and set up the new version's constants
+	/// whenever it's loaded, so `/reload` picks up a recompiled program without `wasmrunner:init`
+	/// and a running program keeps its memory, globals, and call stack.
+	#[clap(long, action)]
+	hot_reload: bool,
+
+	/// Write the datapack as a zip archive at the output path instead of a folder.
+	/// `.zip` is added to the output path if it has no extension.
+	#[clap(long, action)]
+	zip: bool,
+
+	/// Split the datapack into several datapacks with at most N functions each, for hosts that limit how many
+	/// functions a datapack may have. The others are written next to the output path with their number added,
+	/// like `nameofdatapack_2`, and have to be enabled too.
+	#[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+	max_pack_functions: Option<u32>,
+
 	/// Flags to be passed to the GUI simulator.
 	/// Only used with the GUI feature and the --run-output flag.
 	#[clap(long, default_value_t)]
 	sim_flags: String,
+
+	/// A region of linear memory that can be saved and restored using the
+	/// `memory_snapshot` and `memory_restore` intrinsics, given as `START:LENGTH` in bytes.
+	/// Can be passed multiple times, the region ID is the order the regions were given in.
+	#[clap(long = "snapshot-region", value_parser = parse_snapshot_region)]
+	snapshot_regions: Vec<SnapshotRegion>,
+
+	/// Trap on signed overflow of 32-bit add, sub, and mul instructions, saying where in the wasm code it happened.
+	/// Overflow is allowed by WebAssembly, but usually points at a bug when numbers go wrong in game.
+	#[clap(long, action)]
+	overflow_checks: bool,
+
+	/// What integer division and remainder do when dividing by zero, see `DivByZero`.
+	#[clap(long, value_enum, default_value = "fast")]
+	div_by_zero: DivByZero,
+
+	/// Don't check that `call_indirect` finds a function of the right type before calling it,
+	/// which makes the datapack smaller, but calls through a bad table entry do nothing instead of trapping.
+	#[clap(long, action)]
+	no_call_indirect_checks: bool,
+
+	/// Trap once calls are nested this deep, before the stack of local frames grows too deep for Minecraft to store.
+	/// 0 turns the check off, which makes every call a little cheaper.
+	#[clap(long, default_value_t = pack_emitter::DEFAULT_MAX_CALL_DEPTH)]
+	max_call_depth: u32,
+
+	/// Treats calls to one import as calls to another, given as `MODULE.FIELD=MODULE.FIELD`.
+	/// For example, `--import-map env.print_i32=env.print` uses the built-in print for `print_i32`.
+	#[clap(long = "import-map", value_parser = parse_import_map_entry)]
+	import_map: Vec<(String, String)>,
+
+	/// Path to a TOML file containing import mappings,
+	/// where each key is the original `MODULE.FIELD` and each value is the replacement.
+	/// Mappings given with --import-map take priority.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::FilePath)]
+	import_map_file: Option<std::path::PathBuf>,
+
+	/// Generate a `controls` function that shows a clickable menu in chat
+	/// for starting, stopping, and resetting the program.
+	#[clap(long, action)]
+	controls: bool,
+
+	/// Initialize memory on the first call to an export instead of in the init function,
+	/// spreading the work over multiple ticks. Useful for programs with large amounts of data.
+	#[clap(long, action)]
+	lazy_memory_init: bool,
+
+	/// A binary file to copy into linear memory at startup, given as `FILE@ADDRESS`.
+	/// The address can be decimal or hexadecimal with a `0x` prefix.
+	/// Can be passed multiple times, later files overwrite earlier ones and the wasm data segments.
+	#[clap(long = "data", value_parser = parse_data_file, value_hint = clap::ValueHint::FilePath)]
+	data_files: Vec<DataFile>,
+
+	/// Path to write optimization remarks to,
+	/// describing what each optimization pass did or why it couldn't do more.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::FilePath)]
+	remarks: Option<std::path::PathBuf>,
+
+	/// Path to write a description of the compiled datapack's interface to,
+	/// listing the functions, scoreboard holders, storage, and memory layout other datapacks can use.
+	/// Written as JSON if the path ends in `.json`, and as Markdown otherwise.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::FilePath)]
+	abi_doc: Option<std::path::PathBuf>,
+
+	/// Write the datapack's functions, storage, and objectives under this namespace instead of `wasmrunner`,
+	/// so it can be installed in the same world as other datapacks compiled by wasmcraft.
+	/// Its exports are then called with `NAMESPACE:call/EXPORT`.
+	#[clap(long, value_parser = parse_namespace)]
+	namespace: Option<String>,
+
+	/// Treats the imports from a module as calls to the exports of another datapack compiled by wasmcraft,
+	/// given as `MODULE=NAMESPACE` with the namespace that datapack was compiled with.
+	/// For example, `--import-pack mathlib=mathlib` makes calls to `mathlib.add` run `mathlib:call/add`.
+	#[clap(long = "import-pack", value_parser = parse_import_pack)]
+	import_packs: Vec<(String, String)>,
+
+	/// Shorten the names of internal score holders and objectives in the written datapack,
+	/// and write what each short name stands for to this path as JSON.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::FilePath)]
+	short_names: Option<std::path::PathBuf>,
+
+	/// Path to write statistics about the datapack's size to as JSON,
+	/// in addition to the summary that is always printed.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::FilePath)]
+	stats: Option<std::path::PathBuf>,
+
+	/// Path to write the wasm code each function of the datapack was compiled from to as JSON,
+	/// along with its source lines if the module has DWARF line info.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::FilePath)]
+	debug_info: Option<std::path::PathBuf>,
+
+	/// Count how often each function runs in a score, or each block with `--profile=blocks`,
+	/// and add `wasmrunner:profile_dump` to print the highest counts in game and `wasmrunner:profile_reset` to start over.
+	#[clap(long, value_enum, min_values = 0, require_equals = true, default_missing_value = "functions")]
+	profile: Option<ProfileMode>,
+
+	/// Also write `wasmrunner:init` and the exports as chains of command blocks, for worlds without datapacks.
+	/// Writes a `setblock` command for each block to this path, one per line.
+	/// Needs a Minecraft version without function macros, 1.20.1 or older.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::FilePath)]
+	command_blocks: Option<std::path::PathBuf>,
+
+	/// Position of the first command block chain, given as `X,Y,Z`.
+	/// Each chain runs along the positive x axis, and the next chain is placed 2 blocks further along the z axis.
+	#[clap(long, value_parser = parse_block_pos, default_value = "0,0,-8")]
+	command_blocks_origin: (i32, i32, i32),
+
+	/// Make recursive functions yield a tick after this many recursive calls,
+	/// instead of trying to run deep recursion within a single tick.
+	#[clap(long, value_parser = clap::value_parser!(u32).range(1..))]
+	recursion_yield_depth: Option<u32>,
+
+	/// Split functions with more commands than this into parts that are run on consecutive ticks,
+	/// which should be at most the world's `maxCommandChainLength` gamerule.
+	#[clap(long, value_parser = clap::value_parser!(u32).range(2..), default_value_t = pack_emitter::DEFAULT_MAX_COMMAND_CHAIN_LENGTH)]
+	max_command_chain_length: u32,
+
+	/// Which built-in cost model to use when deciding how many commands can run per tick.
+	/// One of `default`, `vanilla`, or `server`.
+	#[clap(long, value_parser = parse_cost_model_profile, default_value = "default")]
+	cost_model: String,
+
+	/// Path to a TOML file overriding parts of the cost model,
+	/// with the keys `max_cmds_per_tick`, `call_overhead`, and an `intrinsic_costs` table.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::FilePath)]
+	cost_model_file: Option<std::path::PathBuf>,
+
+	/// How many commands may run per tick before a loop yields to the next tick, overriding the cost model's.
+	/// Smaller budgets lag the game less and larger ones finish sooner. 0 never yields in loops.
+	#[clap(long, value_parser = clap::value_parser!(u32).range(..=i32::MAX as i64))]
+	tick_budget: Option<u32>,
+
+	/// How blocks jump to the blocks after them, see `JumpMode`.
+	#[clap(long, value_enum, default_value = "direct")]
+	jump_mode: JumpMode,
+
+	/// Limit each call to an export to 1/N of the per-tick command budget, given as `EXPORT=N`, e.g. `--time-slice draw_frame=4`,
+	/// so it yields sooner and spreads over more ticks. The export isn't cut into exactly N parts.
+	/// Calls made while the previous one is still running are dropped and counted in `%%frames_dropped reg`.
+	#[clap(long = "time-slice", value_parser = parse_time_slice)]
+	time_slices: Vec<(String, u32)>,
+
+	/// Call an export on a loop, given as `EXPORT` to call it every tick or `EXPORT=N` to call it every N ticks,
+	/// e.g. `--tick slow_update=20`. Exports named `tick` or `tick_N` without parameters are called this way without being given.
+	#[clap(long = "tick", value_parser = parse_tick_export)]
+	tick_exports: Vec<(String, u32)>,
+
+	/// The most pages `memory.grow` may grow linear memory to.
+	/// The memory's own maximum still applies if it is lower.
+	#[clap(long, value_parser = clap::value_parser!(u32).range(..=65536))]
+	max_memory_pages: Option<u32>,
+
+	/// The most entries `table.grow` may grow a table to.
+	/// The table's own maximum still applies if it is lower.
+	#[clap(long, default_value_t = DEFAULT_MAX_TABLE_SIZE)]
+	max_table_size: u32,
+
+	/// An argument returned by WASI's `args_get`, baked into the datapack.
+	/// Can be passed multiple times, the first one is usually the program name.
+	#[clap(long = "wasi-arg")]
+	wasi_args: Vec<String>,
+
+	/// An environment variable returned by WASI's `environ_get`, baked into the datapack, given as `KEY=VALUE`.
+	#[clap(long = "wasi-env", value_parser = parse_wasi_env_var)]
+	wasi_env: Vec<(String, String)>,
+
+	/// Path to a TOML file containing environment variables for WASI's `environ_get`,
+	/// where each key is a variable name and each value is its value.
+	/// Variables given with --wasi-env take priority.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::FilePath)]
+	wasi_env_file: Option<std::path::PathBuf>,
+
+	/// A region of linear memory mirrored to a wall of concrete blocks, given as `START:WIDTHxHEIGHT`,
+	/// with one byte per pixel in rows from top to bottom. The low 4 bits of each byte select the color.
+	/// The start and width must be multiples of 4.
+	#[clap(long, value_parser = parse_framebuffer)]
+	framebuffer: Option<Framebuffer>,
+
+	/// Position of the top-left pixel of the framebuffer wall, given as `X,Y,Z`.
+	/// The wall extends along the positive x axis and downward.
+	#[clap(long, value_parser = parse_block_pos, default_value = "0,128,-16")]
+	framebuffer_origin: (i32, i32, i32),
+
+	/// How many ticks to wait between updates of the framebuffer wall.
+	#[clap(long, value_parser = clap::value_parser!(u32).range(1..), default_value_t = 1)]
+	framebuffer_interval: u32,
+
+	/// Let players call an export with `/trigger`, given as `EXPORT` or `EXPORT=OBJECTIVE`.
+	/// The export is called on the next tick with the value the player set, and the player's ID as a second parameter.
+	/// The trigger objective has the same name as the export unless another one is given.
+	#[clap(long = "trigger", value_parser = parse_trigger)]
+	triggers: Vec<(String, String)>,
+
+	/// Path to a JSON file containing an array of block states, e.g. `["minecraft:air", "minecraft:oak_stairs[facing=east]"]`,
+	/// used for the block IDs of the turtle intrinsics instead of the ones in `mcinterface.h`.
+	/// Each block's ID is its index in the array.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::FilePath)]
+	block_palette: Option<std::path::PathBuf>,
+
+	/// Path to the `blocks.json` report from Minecraft's data generator,
+	/// used to check that every block in the palette and its properties exist in that version.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::FilePath)]
+	block_registry: Option<std::path::PathBuf>,
+
+	/// Path to write a constant for each block ID in the palette to, for programs to use with the turtle intrinsics.
+	/// Written as a Rust module if the path ends in `.rs`, and as a C header otherwise.
+	#[clap(long, value_parser, value_hint = clap::ValueHint::FilePath)]
+	block_consts: Option<std::path::PathBuf>,
+
+	/// The Y coordinates blocks can be placed at in the world the datapack runs in, given as `MIN..MAX` with MAX excluded,
+	/// e.g. `-64..320` for worlds from 1.18 onwards. Only used to check that everything the program places fits.
+	#[clap(long, value_parser = parse_height_range, default_value = "0..256")]
+	world_height: (i32, i32),
+
+	/// Position of the corner of the block-backed memory with the lowest coordinates, given as `X,Y,Z`.
+	/// Memory extends along the positive x and z axes and is 256 blocks tall.
+	#[clap(long, value_parser = parse_block_pos, default_value = "0,0,0")]
+	memory_origin: (i32, i32, i32),
+
+	/// Where linear memory is kept.
+	#[clap(long, value_enum, default_value = "jukebox")]
+	memory_backend: MemoryBackendKind,
+
+	/// Position the turtle starts at, given as `X,Y,Z`.
+	#[clap(long, value_parser = parse_block_pos, default_value = "0,0,8")]
+	turtle_origin: (i32, i32, i32),
+
+	/// The Minecraft version to write the datapack for, from 1.17 to 1.21.4.
+	/// This decides the pack format, the names of the datapack's folders, and which newer commands can be used.
+	#[clap(long, value_parser = mc_version::McVersion::parse, default_value = "1.19.4")]
+	mc_version: mc_version::McVersion,
+
+	/// Which edition of Minecraft to write the program for.
+	/// Bedrock Edition behavior packs are experimental, and need a `--mc-version` without function macros.
+	#[clap(long, value_enum, default_value = "java")]
+	edition: Edition,
+
+	/// The oldest Bedrock Edition engine version the behavior pack can be loaded in, given as `X.Y.Z`.
+	/// `execute` commands need 1.19.50 or newer. Only used with `--edition bedrock`.
+	#[clap(long, value_parser = parse_engine_version, default_value = "1.19.50")]
+	bedrock_engine_version: (u32, u32, u32),
+
+	/// Leave out `scoreboard players operation` in the behavior pack, for Bedrock servers that don't have it.
+	/// Only used with `--edition bedrock`.
+	#[clap(long, action)]
+	bedrock_no_score_operations: bool,
+}
+
+/// An external binary file that is placed in linear memory at startup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataFile {
+	pub path: std::path::PathBuf,
+	/// Address of the first byte of the file in memory
+	pub address: u32,
+}
+
+fn parse_data_file(s: &str) -> Result<DataFile, String> {
+	let (path, address) = s.rsplit_once('@').ok_or_else(|| format!("expected FILE@ADDRESS, got {s:?}"))?;
+
+	let address = if let Some(hex) = address.strip_prefix("0x") {
+		u32::from_str_radix(hex, 16)
+	} else {
+		address.parse::<u32>()
+	}.map_err(|e| e.to_string())?;
+
+	Ok(DataFile { path: path.into(), address })
+}
+
+/// A word-aligned range of linear memory that can be copied to and from data storage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotRegion {
+	/// Address of the first byte in the region
+	pub start: u32,
+	/// Length of the region in bytes
+	pub length: u32,
+}
+
+fn parse_snapshot_region(s: &str) -> Result<SnapshotRegion, String> {
+	let (start, length) = s.split_once(':').ok_or_else(|| format!("expected START:LENGTH, got {s:?}"))?;
+
+	let start = start.parse::<u32>().map_err(|e| e.to_string())?;
+	let length = length.parse::<u32>().map_err(|e| e.to_string())?;
+
+	if start % 4 != 0 || length % 4 != 0 {
+		return Err(format!("snapshot region {s:?} must be word-aligned"));
+	}
+
+	Ok(SnapshotRegion { start, length })
+}
+
+/// A region of linear memory shown on a wall of blocks, one byte per pixel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Framebuffer {
+	/// Address of the top-left pixel
+	pub start: u32,
+	pub width: u32,
+	pub height: u32,
+	/// Position of the top-left pixel in the world
+	pub origin: (i32, i32, i32),
+	/// Ticks between updates
+	pub interval: u32,
+}
+
+fn parse_framebuffer(s: &str) -> Result<Framebuffer, String> {
+	let err = || format!("expected START:WIDTHxHEIGHT, got {s:?}");
+	let (start, size) = s.split_once(':').ok_or_else(err)?;
+	let (width, height) = size.split_once('x').ok_or_else(err)?;
+
+	let start = start.parse::<u32>().map_err(|e| e.to_string())?;
+	let width = width.parse::<u32>().map_err(|e| e.to_string())?;
+	let height = height.parse::<u32>().map_err(|e| e.to_string())?;
+
+	if start % 4 != 0 || width % 4 != 0 {
+		return Err(format!("framebuffer {s:?} must start on a word and have a width that is a multiple of 4"));
+	}
+	if width == 0 || height == 0 {
+		return Err(format!("framebuffer {s:?} must not be empty"));
+	}
+
+	Ok(Framebuffer { start, width, height, origin: (0, 0, 0), interval: 1 })
+}
+
+fn parse_block_pos(s: &str) -> Result<(i32, i32, i32), String> {
+	let coords = s.split(',').map(|c| c.trim().parse::<i32>().map_err(|e| e.to_string())).collect::<Result<Vec<_>, _>>()?;
+	match coords[..] {
+		[x, y, z] => Ok((x, y, z)),
+		_ => Err(format!("expected X,Y,Z, got {s:?}")),
+	}
+}
+
+fn parse_engine_version(s: &str) -> Result<(u32, u32, u32), String> {
+	let parts = s.split('.').map(|c| c.trim().parse::<u32>().map_err(|e| e.to_string())).collect::<Result<Vec<_>, _>>()?;
+	match parts[..] {
+		[major, minor, patch] => Ok((major, minor, patch)),
+		_ => Err(format!("expected a version like 1.19.50, got {s:?}")),
+	}
+}
+
+fn parse_height_range(s: &str) -> Result<(i32, i32), String> {
+	let (min, max) = s.split_once("..").ok_or_else(|| format!("expected MIN..MAX, got {s:?}"))?;
+	let min = min.trim().parse::<i32>().map_err(|e| e.to_string())?;
+	let max = max.trim().parse::<i32>().map_err(|e| e.to_string())?;
+	if min >= max {
+		return Err(format!("height range {s:?} must not be empty"));
+	}
+	Ok((min, max))
+}
+
+/// Where linear memory is kept, see `pack_emitter::memory_backend` for how each is implemented.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum MemoryBackendKind {
+	/// In the records of jukeboxes placed in the world. Fastest, and the interpreter can run it.
+	Jukebox,
+	/// In int arrays in data storage, accessed with function macros. Slower, but doesn't modify the world
+	/// and initializing memory takes far fewer commands. Needs 1.20.2 or newer.
+	Storage,
+	/// In one score per word, accessed with function macros. Like storage, but memory never needs to be filled in,
+	/// since missing scores are read as zero. Needs 1.20.2 or newer.
+	Scoreboard,
+}
+
+impl MemoryBackendKind {
+	pub fn backend(self) -> &'static dyn MemoryBackend {
+		match self {
+			MemoryBackendKind::Jukebox => &memory_backend::JukeboxMemory,
+			MemoryBackendKind::Storage => &memory_backend::StorageMemory,
+			MemoryBackendKind::Scoreboard => &memory_backend::ScoreboardMemory,
+		}
+	}
+}
+
+/// Where the blocks used by the program are placed in the world.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorldLayout {
+	/// The lowest Y coordinate blocks can be placed at, and the one above the highest
+	pub height: (i32, i32),
+	/// Position of the corner of the block-backed memory with the lowest coordinates
+	pub memory_origin: (i32, i32, i32),
+	/// Whether memory is kept in jukeboxes starting at `memory_origin` or somewhere that isn't in the world
+	pub memory_backend: MemoryBackendKind,
+	/// Where the turtle starts
+	pub turtle_origin: (i32, i32, i32),
+}
+
+impl Default for WorldLayout {
+	fn default() -> Self {
+		WorldLayout { height: (0, 256), memory_origin: (0, 0, 0), memory_backend: MemoryBackendKind::Jukebox, turtle_origin: (0, 0, 8) }
+	}
+}
+
+impl WorldLayout {
+	/// Panics if memory, the turtle, or the framebuffer wall would be placed outside of the world's height range.
+	fn check(&self, framebuffer: Option<&Framebuffer>) {
+		let (min_y, max_y) = self.height;
+		let check_range = |what: &str, low: i32, high: i32| {
+			if low < min_y || high >= max_y {
+				panic!("{what} would be placed from y={low} to y={high}, which is outside of the world height range {min_y}..{max_y}");
+			}
+		};
+
+		if self.memory_backend.backend().places_blocks() {
+			let memory_y = self.memory_origin.1;
+			check_range("memory", memory_y, memory_y + pack_emitter::PAGE_SPAN_Y - 1);
+		}
+		check_range("the turtle", self.turtle_origin.1, self.turtle_origin.1);
+		if let Some(fb) = framebuffer {
+			check_range("the framebuffer", fb.origin.1 - fb.height as i32 + 1, fb.origin.1);
+		}
+	}
+}
+
+fn parse_trigger(s: &str) -> Result<(String, String), String> {
+	let (export, objective) = s.split_once('=').unwrap_or((s, s));
+	if objective.is_empty() || objective.len() > 16 {
+		return Err(format!("trigger objective {objective:?} must be 1 to 16 characters long"));
+	}
+	if !objective.chars().all(|c| c.is_ascii_alphanumeric() || "_-.+".contains(c)) {
+		return Err(format!("trigger objective {objective:?} may only contain letters, digits, and `_-.+`"));
+	}
+	Ok((export.to_string(), objective.to_string()))
+}
+
+fn check_import_name(s: &str) -> Result<(), String> {
+	match s.split_once('.') {
+		Some((module, field)) if !module.is_empty() && !field.is_empty() => Ok(()),
+		_ => Err(format!("expected MODULE.FIELD, got {s:?}")),
+	}
+}
+
+fn parse_import_map_entry(s: &str) -> Result<(String, String), String> {
+	let (from, to) = s.split_once('=').ok_or_else(|| format!("expected FROM=TO, got {s:?}"))?;
+	check_import_name(from)?;
+	check_import_name(to)?;
+	Ok((from.to_string(), to.to_string()))
+}
+
+fn parse_namespace(s: &str) -> Result<String, String> {
+	pack_emitter::namespace::check_namespace(s)?;
+	Ok(s.to_string())
+}
+
+fn parse_import_pack(s: &str) -> Result<(String, String), String> {
+	let (module, namespace) = s.split_once('=').ok_or_else(|| format!("expected MODULE=NAMESPACE, got {s:?}"))?;
+	if module.is_empty() {
+		return Err(format!("expected MODULE=NAMESPACE, got {s:?}"));
+	}
+	pack_emitter::namespace::check_namespace(namespace)?;
+	if namespace == pack_emitter::namespace::DEFAULT_NAMESPACE {
+		return Err(format!("datapacks called by other datapacks need their own namespace, compile the one for `{module}` with --namespace"));
+	}
+	Ok((module.to_string(), namespace.to_string()))
+}
+
+fn check_wasi_env_name(name: &str) -> Result<(), String> {
+	if name.is_empty() || name.contains(['=', '\0']) {
+		Err(format!("invalid environment variable name {name:?}"))
+	} else {
+		Ok(())
+	}
+}
+
+fn parse_wasi_env_var(s: &str) -> Result<(String, String), String> {
+	let (name, value) = s.split_once('=').ok_or_else(|| format!("expected KEY=VALUE, got {s:?}"))?;
+	check_wasi_env_name(name)?;
+	Ok((name.to_string(), value.to_string()))
+}
+
+fn parse_time_slice(s: &str) -> Result<(String, u32), String> {
+	let (name, slices) = s.split_once('=').ok_or_else(|| format!("expected EXPORT=N, got {s:?}"))?;
+	let slices = slices.parse::<u32>().map_err(|e| format!("invalid slice count {slices:?}: {e}"))?;
+	if slices == 0 {
+		return Err("slice count must be at least 1".to_string());
+	}
+	Ok((name.to_string(), slices))
+}
+
+fn parse_tick_export(s: &str) -> Result<(String, u32), String> {
+	let (name, interval) = match s.split_once('=') {
+		Some((name, interval)) => {
+			let interval = interval.parse::<u32>().map_err(|e| format!("invalid tick interval {interval:?}: {e}"))?;
+			(name, interval)
+		}
+		None => (s, 1),
+	};
+	if interval == 0 {
+		return Err("tick interval must be at least 1".to_string());
+	}
+	Ok((name.to_string(), interval))
+}
+
+fn parse_cost_model_profile(s: &str) -> Result<String, String> {
+	if cost_model::CostModel::from_profile(s).is_some() {
+		Ok(s.to_string())
+	} else {
+		let names = cost_model::PROFILES.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+		Err(format!("unknown cost model, expected one of: {}", names.join(", ")))
+	}
+}
+
+fn load_cost_model(profile: &str, path: Option<&std::path::Path>) -> cost_model::CostModel {
+	let mut model = cost_model::CostModel::from_profile(profile).unwrap();
+	if let Some(path) = path {
+		let contents = std::fs::read_to_string(path).unwrap_or_else(|err| panic!("failed to read cost model {:?}: {}", path, err));
+		model.apply_toml(&contents).unwrap_or_else(|err| panic!("invalid cost model {:?}: {}", path, err));
+	}
+	model
+}
+
+fn load_import_map_file(path: &std::path::Path) -> HashMap<String, String> {
+	let contents = std::fs::read_to_string(path).unwrap_or_else(|err| panic!("failed to read import map {:?}: {}", path, err));
+	let map: HashMap<String, String> = toml::from_str(&contents).unwrap_or_else(|err| panic!("invalid import map {:?}: {}", path, err));
+	for (from, to) in map.iter() {
+		if let Err(err) = check_import_name(from).and_then(|()| check_import_name(to)) {
+			panic!("invalid import map {:?}: {}", path, err);
+		}
+	}
+	map
+}
+
+fn load_block_palette(path: &std::path::Path) -> Vec<String> {
+	let contents = std::fs::read_to_string(path).unwrap_or_else(|err| panic!("failed to read block palette {:?}: {}", path, err));
+	let palette: Vec<String> = serde_json::from_str(&contents).unwrap_or_else(|err| panic!("invalid block palette {:?}: {}", path, err));
+	if palette.is_empty() {
+		panic!("invalid block palette {:?}: it must contain at least one block", path);
+	}
+	if let Some(block) = palette.iter().find(|block| block.is_empty() || block.contains(char::is_whitespace)) {
+		panic!("invalid block palette {:?}: {:?} is not a block state", path, block);
+	}
+	palette
+}
+
+fn check_block_registry(path: &std::path::Path, palette: &[String]) {
+	let contents = std::fs::read_to_string(path).unwrap_or_else(|err| panic!("failed to read block registry {:?}: {}", path, err));
+	let registry = block_consts::BlockRegistry::parse(&contents).unwrap_or_else(|err| panic!("invalid block registry {:?}: {}", path, err));
+	for block in palette {
+		if let Err(err) = registry.check_state(block) {
+			panic!("block {:?} in the palette is not in the block registry {:?}: {}", block, path, err);
+		}
+	}
+}
+
+/// The name other modules import from a module by, which is its file name without the extension.
+fn module_name(path: &std::path::Path) -> String {
+	path.file_stem().unwrap_or_else(|| panic!("module path {:?} has no file name", path)).to_string_lossy().into_owned()
+}
+
+/// The path to write a pack named after `output` to within the world save at `world`,
+/// which is the `datapacks` folder for Java Edition and the `behavior_packs` folder for Bedrock Edition.
+fn install_path(world: &std::path::Path, output: &std::path::Path, edition: Edition) -> std::path::PathBuf {
+	if !world.join("level.dat").is_file() {
+		panic!("{:?} is not a world save, it has no level.dat", world);
+	}
+	let name = output.file_name().unwrap_or_else(|| panic!("output path {:?} has no name to install the datapack as", output));
+	let folder = match edition {
+		Edition::Java => "datapacks",
+		Edition::Bedrock => "behavior_packs",
+	};
+	world.join(folder).join(name)
+}
+
+fn load_wasi_env_file(path: &std::path::Path) -> Vec<(String, String)> {
+	let contents = std::fs::read_to_string(path).unwrap_or_else(|err| panic!("failed to read environment {:?}: {}", path, err));
+	let env: std::collections::BTreeMap<String, String> = toml::from_str(&contents).unwrap_or_else(|err| panic!("invalid environment {:?}: {}", path, err));
+	for name in env.keys() {
+		if let Err(err) = check_wasi_env_name(name) {
+			panic!("invalid environment {:?}: {}", path, err);
+		}
+	}
+	env.into_iter().collect()
+}
+
+/// Combines environment variables into the `KEY=VALUE` strings WASI programs expect.
+/// Later variables replace earlier ones with the same name.
+fn make_wasi_env(vars: impl IntoIterator<Item = (String, String)>) -> Vec<String> {
+	let mut env: Vec<(String, String)> = Vec::new();
+	for (name, value) in vars {
+		match env.iter_mut().find(|(n, _)| *n == name) {
+			Some(existing) => existing.1 = value,
+			None => env.push((name, value)),
+		}
+	}
+	env.into_iter().map(|(name, value)| format!("{name}={value}")).collect()
 }
 
 /// A more-parsed form of the command line arguments
 pub struct CompileContext {
     /// Path to the binary WebAssembly file.
 	input: std::path::PathBuf,
+	/// Paths to other modules to link with it
+	linked_modules: Vec<std::path::PathBuf>,
     /// Path to place the output datapack.
     /// Any files previously in this directory will be deleted.
     output: std::path::PathBuf,
@@ -102,6 +738,10 @@ pub struct CompileContext {
     run_output: bool,
 	/// Save the datapack to disk.
 	persist_output: bool,
+	/// Save the datapack as a zip archive instead of a folder.
+	zip_output: bool,
+	/// Split the saved datapack into datapacks with at most this many functions.
+	max_pack_functions: Option<usize>,
 
     /// Which form of register allocation to use.
     regalloc: RegAllocMode,
@@ -119,6 +759,138 @@ pub struct CompileContext {
 
 	/// Flags to be passed to the GUI simulator.
 	sim_flags: Vec<String>,
+
+	/// Regions of memory usable with `memory_snapshot` and `memory_restore`
+	snapshot_regions: Vec<SnapshotRegion>,
+
+	/// Trap on signed overflow of 32-bit arithmetic
+	overflow_checks: bool,
+
+	/// Whether divisions by zero trap or give a defined result
+	div_by_zero: DivByZero,
+
+	/// Trap when `call_indirect` finds no function or one of another type
+	call_indirect_checks: bool,
+
+	/// Trap when calls are nested deeper than this
+	max_call_depth: Option<u32>,
+
+	/// Maps an import's original `module.field` name to the name it should be treated as
+	import_map: HashMap<String, String>,
+
+	/// Generate the in-game control panel
+	controls: bool,
+
+	/// Replace the pointer markers when the datapack is loaded
+	refresh_markers: bool,
+
+	/// Checkpoint the program's scores when it yields and restore them when the datapack is loaded
+	persist_state: bool,
+
+	/// Only rewrite changed files and set up constants when the datapack is loaded
+	hot_reload: bool,
+
+	/// Defer memory initialization until the first export call
+	lazy_memory_init: bool,
+
+	/// External files to place in memory, in addition to the wasm data segments
+	data_files: Vec<DataFile>,
+
+	/// Where to write optimization remarks, if anywhere
+	remarks_path: Option<std::path::PathBuf>,
+
+	/// The optimization remarks emitted while compiling, which are only kept if they are written somewhere
+	remarks: remarks::Remarks,
+
+	/// Where to write the ABI documentation, if anywhere
+	abi_doc: Option<std::path::PathBuf>,
+
+	/// The namespace the datapack is written under
+	namespace: String,
+
+	/// Modules whose imports are calls to another datapack, and that datapack's namespace
+	pack_imports: HashMap<String, String>,
+
+	/// Where to write the mapping of short names, if names should be shortened
+	short_names: Option<std::path::PathBuf>,
+
+	/// Where to write the datapack statistics, if anywhere
+	stats: Option<std::path::PathBuf>,
+
+	/// Where to write the map from functions back to wasm code, if anywhere
+	debug_info: Option<std::path::PathBuf>,
+
+	/// What to count how often it runs, if anything
+	profile: Option<ProfileMode>,
+
+	/// Where to write the command block chains, if anywhere
+	command_blocks: Option<std::path::PathBuf>,
+
+	/// Where the first command block chain is placed
+	command_blocks_origin: (i32, i32, i32),
+
+	/// How many calls to recursive functions are allowed before yielding, if they should yield at all
+	recursion_yield_depth: Option<u32>,
+
+	/// The most commands a single function can have before it is split into parts
+	max_command_chain_length: usize,
+
+	/// Assumptions about how expensive the generated commands are
+	cost_model: cost_model::CostModel,
+
+	/// How many commands may run per tick before loops yield, or `None` if loops never yield
+	tick_budget: Option<usize>,
+
+	/// How blocks jump to the blocks after them
+	jump_mode: JumpMode,
+
+	/// How many ticks each of these exports should be spread over
+	time_slices: HashMap<String, u32>,
+
+	/// Exports given with `--tick`, and how many ticks apart they are called
+	tick_exports: Vec<(String, u32)>,
+
+	/// Limit on the number of memory pages, in addition to the memory's own maximum
+	max_memory_pages: Option<u32>,
+
+	/// Limit on the number of entries in tables that are grown, in addition to the table's own maximum
+	max_table_size: u32,
+
+	/// Arguments returned by WASI's `args_get`
+	wasi_args: Vec<String>,
+
+	/// Environment variables returned by WASI's `environ_get`, as `KEY=VALUE`
+	wasi_env: Vec<String>,
+
+	/// The memory region mirrored to a wall of blocks, if any
+	framebuffer: Option<Framebuffer>,
+
+	/// Exports players can call with `/trigger`, and the trigger objective for each
+	triggers: Vec<(String, String)>,
+
+	/// The block state for each block ID used by the turtle intrinsics
+	block_palette: Vec<String>,
+
+	/// Path to write block ID constants to, if any
+	block_consts: Option<std::path::PathBuf>,
+
+	/// Where memory and the turtle are placed in the world
+	layout: WorldLayout,
+
+	/// The Minecraft version the datapack is written for
+	mc_version: mc_version::McVersion,
+
+	/// The edition the program is written for
+	edition: Edition,
+
+	/// The oldest Bedrock engine version the behavior pack is written for
+	bedrock_engine_version: (u32, u32, u32),
+
+	/// Whether the behavior pack can use `scoreboard players operation`
+	bedrock_score_operations: bool,
+
+	/// Additional imports registered through the library API
+	imports: validator::ImportRegistry,
 }
 
 impl CompileContext {
@@ -150,6 +922,100 @@ impl CompileContext {
 		let do_const_prop = args.do_const_prop.unwrap_or(default_const_prop);
 		let do_dead_code_elim = args.do_dead_code_elim.unwrap_or(default_dead_code_elim);
 
+		let output = match &args.install {
+			Some(world) => install_path(world, &args.output, args.edition),
+			None => args.output,
+		};
+
+		let mut import_map = args.import_map_file.as_deref().map(load_import_map_file).unwrap_or_default();
+		import_map.extend(args.import_map);
+
+		let wasi_env_file = args.wasi_env_file.as_deref().map(load_wasi_env_file).unwrap_or_default();
+		let wasi_env = make_wasi_env(wasi_env_file.into_iter().chain(args.wasi_env));
+
+		let framebuffer = args.framebuffer.map(|fb| Framebuffer {
+			origin: args.framebuffer_origin,
+			interval: args.framebuffer_interval,
+			..fb
+		});
+
+		let layout = WorldLayout {
+			height: args.world_height,
+			memory_origin: args.memory_origin,
+			memory_backend: args.memory_backend,
+			turtle_origin: args.turtle_origin,
+		};
+		layout.check(framebuffer.as_ref());
+
+		let memory_backend = layout.memory_backend.backend();
+		if let Err(err) = memory_backend.check_version(args.mc_version) {
+			panic!("{err}");
+		}
+		if args.command_blocks.is_some() && args.mc_version.has_macros() {
+			panic!("command blocks can't use function macros, so --command-blocks needs --mc-version 1.20.1 or older");
+		}
+		if args.edition == Edition::Bedrock && args.mc_version.has_macros() {
+			panic!("Bedrock Edition has no function macros, so --edition bedrock needs --mc-version 1.20.1 or older");
+		}
+		// Only in bounce mode is the block a paused program continues at kept in a score instead of a pending `schedule`
+		if args.persist_state && args.jump_mode != JumpMode::Bounce {
+			panic!("--persist-state needs --jump-mode bounce, so the block a paused program continues at can be checkpointed");
+		}
+		if args.persist_state && args.edition == Edition::Bedrock {
+			panic!("Bedrock Edition has no command storage, so --persist-state can only be used with --edition java");
+		}
+		if args.hot_reload && args.zip {
+			panic!("--hot-reload only rewrites the files that changed in a datapack folder, so it can't be used with --zip");
+		}
+		// Every behavior pack needs its own UUID in its manifest
+		if args.max_pack_functions.is_some() && args.edition == Edition::Bedrock {
+			panic!("--max-pack-functions can only be used with --edition java");
+		}
+		if args.hot_reload && args.edition == Edition::Bedrock {
+			panic!("behavior packs have no load functions to set up the new version, so --hot-reload can only be used with --edition java");
+		}
+		// Short names depend on how often each name is used, so they change from one version of the program to the next
+		if args.hot_reload && args.short_names.is_some() {
+			panic!("--short-names renames the running program's scores whenever it changes, so it can't be used with --hot-reload");
+		}
+		let namespace = args.namespace.unwrap_or_else(|| pack_emitter::namespace::DEFAULT_NAMESPACE.to_string());
+		if args.edition == Edition::Bedrock && namespace != pack_emitter::namespace::DEFAULT_NAMESPACE {
+			panic!("--namespace can only be used with --edition java");
+		}
+		if let Some((module, _)) = args.import_packs.iter().find(|(_, other)| *other == namespace) {
+			panic!("`{module}` is imported from this datapack's own namespace, give it another one with --namespace");
+		}
+		// Objectives like `NAMESPACE_player_id` have to fit in 16 characters before 1.18.2
+		if args.mc_version < mc_version::McVersion::new(18, 2) && namespace.len() > 6 {
+			panic!("objective names are limited to 16 characters before 1.18.2, so --namespace can be at most 6 characters long");
+		}
+		// Whether they fit in the module's memory is checked once it is read
+		for region in args.snapshot_regions.iter() {
+			if region.length == 0 {
+				panic!("snapshot region {}:{} is empty", region.start, region.length);
+			}
+			if region.start as u64 + region.length as u64 > 1 << 32 {
+				panic!("snapshot region {}:{} goes past the end of the 32-bit address space", region.start, region.length);
+			}
+		}
+		if args.run_output && !memory_backend.can_interpret() {
+			panic!("the interpreter can only run programs using the jukebox memory backend");
+		}
+
+		let block_palette = args.block_palette.as_deref().map(load_block_palette).unwrap_or_else(pack_emitter::default_block_palette);
+		if let Some(path) = &args.block_registry {
+			check_block_registry(path, &block_palette);
+		}
+
+		// Scores can't go above i32::MAX, so that is as close to no limit as `%%max_commands` gets
+		let mut cost_model = load_cost_model(&args.cost_model, args.cost_model_file.as_deref());
+		let tick_budget = match args.tick_budget {
+			Some(0) => None,
+			Some(budget) => Some(budget as usize),
+			None => Some(cost_model.max_cmds_per_tick),
+		};
+		cost_model.max_cmds_per_tick = tick_budget.unwrap_or(i32::MAX as usize);
+
 		let sim_flags = if args.sim_flags.is_empty() {
 			Vec::new()
 		} else {
@@ -157,11 +1023,58 @@ impl CompileContext {
 		};
 
 		CompileContext {
-			input: args.input, output: args.output,
+			input: args.input, output,
+			linked_modules: args.linked_modules,
 			run_output: args.run_output, persist_output: !args.no_persist_output,
+			zip_output: args.zip,
+			max_pack_functions: args.max_pack_functions.map(|max| max as usize),
 			regalloc, do_const_prop, do_dead_code_elim,
 			dump_wasm: args.dump_wasm, dump_lir: args.dump_lir, dump_datapack: args.dump_datapack,
 			sim_flags,
+			snapshot_regions: args.snapshot_regions,
+			overflow_checks: args.overflow_checks,
+			div_by_zero: args.div_by_zero,
+			call_indirect_checks: !args.no_call_indirect_checks,
+			max_call_depth: (args.max_call_depth != 0).then(|| args.max_call_depth),
+			import_map,
+			controls: args.controls,
+			refresh_markers: args.refresh_markers,
+			persist_state: args.persist_state,
+			hot_reload: args.hot_reload,
+			lazy_memory_init: args.lazy_memory_init,
+			data_files: args.data_files,
+			remarks: remarks::Remarks::new(args.remarks.is_some()),
+			remarks_path: args.remarks,
+			abi_doc: args.abi_doc,
+			namespace,
+			pack_imports: args.import_packs.into_iter().collect(),
+			short_names: args.short_names,
+			stats: args.stats,
+			debug_info: args.debug_info,
+			profile: args.profile,
+			command_blocks: args.command_blocks,
+			command_blocks_origin: args.command_blocks_origin,
+			recursion_yield_depth: args.recursion_yield_depth,
+			max_command_chain_length: args.max_command_chain_length as usize,
+			cost_model,
+			tick_budget,
+			jump_mode: args.jump_mode,
+			time_slices: args.time_slices.into_iter().collect(),
+			tick_exports: args.tick_exports,
+			max_memory_pages: args.max_memory_pages,
+			max_table_size: args.max_table_size,
+			wasi_args: args.wasi_args,
+			wasi_env,
+			framebuffer,
+			triggers: args.triggers,
+			block_palette,
+			block_consts: args.block_consts,
+			layout,
+			mc_version: args.mc_version,
+			edition: args.edition,
+			bedrock_engine_version: args.bedrock_engine_version,
+			bedrock_score_operations: !args.bedrock_no_score_operations,
+			imports: Default::default(),
 		}
 	}
 
@@ -191,12 +1104,126 @@ impl CompileContext {
 
 		CompileContext {
 			input: Default::default(), output: Default::default(),
+			linked_modules: Vec::new(),
 			run_output: true, persist_output: true,
+			zip_output: false,
+			max_pack_functions: None,
 			regalloc, do_const_prop, do_dead_code_elim,
 			dump_wasm: false, dump_lir: false, dump_datapack: false,
 			sim_flags: Vec::new(),
+			snapshot_regions: Vec::new(),
+			overflow_checks: false,
+			div_by_zero: DivByZero::Fast,
+			call_indirect_checks: true,
+			max_call_depth: Some(pack_emitter::DEFAULT_MAX_CALL_DEPTH),
+			import_map: HashMap::new(),
+			controls: false,
+			refresh_markers: false,
+			persist_state: false,
+			hot_reload: false,
+			lazy_memory_init: false,
+			data_files: Vec::new(),
+			remarks_path: None,
+			remarks: remarks::Remarks::default(),
+			abi_doc: None,
+			namespace: pack_emitter::namespace::DEFAULT_NAMESPACE.to_string(),
+			pack_imports: HashMap::new(),
+			short_names: None,
+			stats: None,
+			debug_info: None,
+			profile: None,
+			command_blocks: None,
+			command_blocks_origin: (0, 0, -8),
+			recursion_yield_depth: None,
+			max_command_chain_length: pack_emitter::DEFAULT_MAX_COMMAND_CHAIN_LENGTH as usize,
+			cost_model: cost_model::CostModel::default(),
+			tick_budget: Some(cost_model::CostModel::default().max_cmds_per_tick),
+			jump_mode: JumpMode::Direct,
+			time_slices: HashMap::new(),
+			tick_exports: Vec::new(),
+			max_memory_pages: None,
+			max_table_size: DEFAULT_MAX_TABLE_SIZE,
+			wasi_args: Vec::new(),
+			wasi_env: Vec::new(),
+			framebuffer: None,
+			triggers: Vec::new(),
+			block_palette: pack_emitter::default_block_palette(),
+			block_consts: None,
+			layout: WorldLayout::default(),
+			mc_version: mc_version::McVersion::default(),
+			edition: Edition::Java,
+			bedrock_engine_version: pack_emitter::bedrock::NEW_EXECUTE_ENGINE_VERSION,
+			bedrock_score_operations: true,
+			imports: Default::default(),
+		}
+
+	}
+
+	/// Declares an import that isn't one of the built-in intrinsics, and how calls to it are lowered into SSA.
+	/// Registered imports take priority over built-in ones, and are looked up after applying the import map.
+	pub fn register_import(&mut self, module: &str, field: &str, lowering: impl Fn(validator::ImportCall) + Send + Sync + 'static) {
+		self.imports.register(module, field, lowering);
+	}
+
+	/// Returns the module and field an import should be treated as, after applying the import map.
+	pub fn map_import<'a>(&'a self, module: &'a str, field: &'a str) -> (&'a str, &'a str) {
+		match self.import_map.get(&format!("{module}.{field}")) {
+			Some(mapped) => mapped.split_once('.').unwrap(),
+			None => (module, field),
 		}
+	}
+
+	pub fn jump_mode(&self) -> JumpMode {
+		self.jump_mode
+	}
+
+	/// Changes how jumps between blocks are written for programs lowered to LIR after this.
+	pub fn set_jump_mode(&mut self, jump_mode: JumpMode) {
+		self.jump_mode = jump_mode;
+	}
 
+	/// The optimization remarks emitted so far, see `--remarks`.
+	pub fn remarks(&self) -> &remarks::Remarks {
+		&self.remarks
+	}
+
+	/// The path the datapack is written to, which is in the world's `datapacks` folder when installing it.
+	pub fn output(&self) -> &std::path::Path {
+		&self.output
+	}
+
+	/// The datapack the program is written as with `--edition java`.
+	pub fn datapack(&self) -> pack_emitter::Datapack {
+		let mut pack = pack_emitter::Datapack::new(self.mc_version);
+		pack.short_names = self.short_names.is_some();
+		pack.namespace = self.namespace.clone();
+		pack
+	}
+
+	/// How many commands `--run-output` lets the datapack run in a tick before stopping it.
+	/// The program yields once its cost model says it ran `max_cmds_per_tick` commands,
+	/// and the game stops function chains longer than `maxCommandChainLength`, so the larger of the two is allowed.
+	pub fn run_output_tick_limit(&self) -> usize {
+		self.cost_model.max_cmds_per_tick.max(self.max_command_chain_length)
+	}
+
+	/// The names the datapack is moved to when it's written, if it isn't written under the default namespace.
+	pub fn pack_namespace(&self) -> Option<pack_emitter::namespace::PackNamespace> {
+		if self.namespace == pack_emitter::namespace::DEFAULT_NAMESPACE {
+			None
+		} else {
+			Some(pack_emitter::namespace::PackNamespace::new(&self.namespace))
+		}
+	}
+
+	/// The behavior pack the program is written as with `--edition bedrock`, named after the output path.
+	pub fn behavior_pack(&self) -> pack_emitter::bedrock::BehaviorPack {
+		let name = self.output.file_stem().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| "wasmcraft".to_string());
+		let mut pack = pack_emitter::bedrock::BehaviorPack::new(&name, self.mc_version);
+		pack.min_engine_version = self.bedrock_engine_version;
+		pack.score_operations = self.bedrock_score_operations;
+		pack.short_names = self.short_names.is_some();
+		pack
 	}
 
 	pub fn compute_wasm_file<'a>(&self, bytes: &'a [u8]) -> WasmFile<'a> {
@@ -221,13 +1248,42 @@ impl CompileContext {
 		wasm_to_ssa(self, wasm_file)
 	}
 
+	/// Links modules into one program, resolving the imports each module has from the others by name.
+	/// The first module's functions keep their indices.
+	pub fn compute_linked_ssa(&self, modules: &[(&str, &WasmFile)]) -> SsaProgram {
+		linker::link_to_ssa(self, modules)
+	}
+
 	pub fn compute_lir(&self, ssa_program: SsaProgram) -> LirProgram {
 		lir_emitter::convert(self, ssa_program)
 	}
 
 	pub fn compute_datapack(&self, lir_program: &LirProgram) -> Vec<Function> {
-		pack_emitter::emit_program(lir_program)
+		pack_emitter::emit_program(self, lir_program)
 	}
+
+	/// Lays out `wasmrunner:init` and each export as a chain of command blocks.
+	pub fn compute_command_blocks(&self, lir_program: &LirProgram, datapack: &[Function]) -> pack_emitter::command_blocks::CommandBlockScript {
+		let mut exports = lir_program.exports.keys().map(|name| format!("wasmrunner:{name}")).collect::<Vec<_>>();
+		exports.sort();
+		let entries = std::iter::once("wasmrunner:init".to_string()).chain(exports).collect::<Vec<_>>();
+		let mut script = pack_emitter::command_blocks::build(datapack, &entries, self.mc_version, self.command_blocks_origin, self.max_command_chain_length);
+		if let Some(namespace) = self.pack_namespace() {
+			for cmd in script.commands.iter_mut() {
+				*cmd = namespace.apply(cmd);
+			}
+		}
+		script
+	}
+
+	pub fn compute_block_consts(&self, format: block_consts::BlockConstsFormat) -> String {
+		block_consts::generate(&self.block_palette, format)
+	}
+}
+
+/// The export `--run-output` calls once the program is initialized
+pub fn find_run_entry(exports: &HashMap<String, ssa::BlockId>) -> Result<ssa::BlockId, String> {
+	exports.get("_start").copied().ok_or_else(|| "--run-output calls the `_start` export, but the program doesn't export a function named `_start`".to_string())
 }
 
 /// The main entry point for Wasmcraft.
@@ -236,23 +1292,61 @@ impl CompileContext {
 pub fn run(args: Args) {
 	let ctx = CompileContext::new(args);
 
+	if let Some(path) = &ctx.block_consts {
+		let consts = ctx.compute_block_consts(block_consts::BlockConstsFormat::from_path(path));
+		std::fs::write(path, consts).unwrap();
+	}
+
 	let start = SystemTime::now();
 
 	let bytes = std::fs::read(&ctx.input).unwrap();
+	let linked_bytes = ctx.linked_modules.iter()
+		.map(|path| std::fs::read(path).unwrap_or_else(|err| panic!("failed to read module {:?}: {}", path, err)))
+		.collect::<Vec<_>>();
 	let file = ctx.compute_wasm_file(&bytes);
-	let ssa_program = ctx.compute_ssa(&file);
+	let linked_files = linked_bytes.iter().map(|bytes| ctx.compute_wasm_file(bytes)).collect::<Vec<_>>();
+	let ssa_program = if linked_files.is_empty() {
+		ctx.compute_ssa(&file)
+	} else {
+		let names = std::iter::once(&ctx.input).chain(ctx.linked_modules.iter()).map(|path| module_name(path)).collect::<Vec<_>>();
+		let modules = names.iter().map(String::as_str).zip(std::iter::once(&file).chain(linked_files.iter())).collect::<Vec<_>>();
+		ctx.compute_linked_ssa(&modules)
+	};
+
+	let run_entry = if ctx.run_output {
+		Some(find_run_entry(&ssa_program.exports).unwrap_or_else(|err| panic!("{err}")))
+	} else {
+		None
+	};
+
+	let default_origin = WorldLayout::default().memory_origin;
+	if ctx.pack_namespace().is_some() && !ssa_program.memory.is_empty() && ctx.layout.memory_backend == MemoryBackendKind::Jukebox && ctx.layout.memory_origin == default_origin {
+		println!("warning: --namespace is given, but memory is kept at the default --memory-origin, where other datapacks keep theirs too");
+	}
+
+	if let Some(path) = &ctx.abi_doc {
+		let doc = abi_doc::generate(&ctx, &file, &ssa_program, abi_doc::AbiDocFormat::from_path(path));
+		std::fs::write(path, doc).unwrap();
+	}
 
 	if CODEGEN_STAGE == CodegenStage::Ssa {
 		println!("Finished in {}s", SystemTime::now().duration_since(start).unwrap().as_secs_f64());
 
-		if ctx.run_output {
-			let start_idx = file.exports.find_func("_start").unwrap();
+		if let Some(run_entry) = run_entry {
+			let start_idx = run_entry.func;
 
 			let func = ssa_program.code.iter().find(|f| f.func_id() as usize == start_idx).unwrap();
 			dbg!(func.code.len());
 
+			let start_func = ssa_program.start;
+
 			let mut interp = ssa::interp::SsaInterpreter::new(ssa_program);
 
+			if let Some(start_func) = start_func {
+				interp.call(start_func.func, vec![]);
+				interp.run_until_halted();
+			}
+
 			interp.call(start_idx, vec![]);
 
 			interp.run_until_halted();
@@ -263,11 +1357,31 @@ pub fn run(args: Args) {
 
 	let lir_program = ctx.compute_lir(ssa_program);
 
+	if let Some(path) = &ctx.remarks_path {
+		ctx.remarks.write_to(path).unwrap();
+	}
+
 	if CODEGEN_STAGE == CodegenStage::Lir {
 		println!("Finished in {}s", SystemTime::now().duration_since(start).unwrap().as_secs_f64());
 
-		if ctx.run_output {
-			todo!("run the LIR interpreter");
+		if let Some(run_entry) = run_entry {
+			let start_idx = run_entry.func;
+
+			let start_func = lir_program.start;
+			let checkpoint_cmds = ctx.persist_state.then(|| pack_emitter::checkpoint_cmd_count(&ctx, &lir_program));
+
+			let mut interp = lir::interp::LirInterpreter::with_cost_model(lir_program, ctx.cost_model.clone(), checkpoint_cmds);
+
+			if let Some(start_func) = start_func {
+				interp.call(start_func.func, &[]);
+				interp.run_until_halted();
+			}
+
+			interp.call(start_idx, &[]);
+
+			interp.run_until_halted();
+
+			print!("{}", interp.command_counts());
 		}
 
 		return;
@@ -275,6 +1389,34 @@ pub fn run(args: Args) {
 
 	let datapack = ctx.compute_datapack(&lir_program);
 
+	let stats = pack_stats::compute(&datapack, &lir_program.func_names, ctx.mc_version);
+	print!("{}", stats.summary());
+	if let Some(path) = &ctx.stats {
+		std::fs::write(path, stats.to_json()).unwrap();
+	}
+
+	if let Some(path) = &ctx.debug_info {
+		let line_table = debug_info::read_line_table(&file);
+		let mut info = debug_info::compute(&datapack, &lir_program, line_table.as_ref(), ctx.mc_version);
+		if let Some(namespace) = ctx.pack_namespace() {
+			for func in info.functions.iter_mut() {
+				func.id = namespace.apply(&func.id);
+			}
+		}
+		std::fs::write(path, info.to_json()).unwrap();
+	}
+
+	if let Some(path) = &ctx.command_blocks {
+		let script = ctx.compute_command_blocks(&lir_program, &datapack);
+		for (entry, reason) in script.skipped.iter() {
+			println!("warning: {entry} can't be written as command blocks: {reason}");
+		}
+		for (entry, len, (x, y, z)) in script.chains.iter() {
+			println!("Wrote {entry} as {len} command blocks starting at {x} {y} {z}");
+		}
+		std::fs::write(path, script.to_script()).unwrap();
+	}
+
 	drop(lir_program);
 
 	println!("Finished in {}s", SystemTime::now().duration_since(start).unwrap().as_secs_f64());
@@ -291,7 +1433,49 @@ pub fn run(args: Args) {
 
 	if ctx.persist_output {
 		let start = SystemTime::now();
-		pack_emitter::persist_program(std::path::Path::new(&ctx.output), &datapack);
+		let java_pack = ctx.datapack();
+		let behavior_pack = ctx.behavior_pack();
+		let pack: &dyn pack_emitter::PackBackend = match ctx.edition {
+			Edition::Java => &java_pack,
+			Edition::Bedrock => {
+				let (cmds, funcs) = behavior_pack.unsupported_commands(&datapack);
+				if cmds > 0 {
+					println!("warning: {cmds} commands in {funcs} functions have no Bedrock Edition equivalent and were written as comments");
+				}
+				&behavior_pack
+			}
+		};
+		let mut output = ctx.output.clone();
+		if ctx.zip_output && output.extension().is_none() {
+			output.set_extension(if ctx.edition == Edition::Bedrock { "mcpack" } else { "zip" });
+		}
+
+		println!("Writing output");
+		let (files, names) = pack.files(&datapack);
+		let shards = match ctx.max_pack_functions {
+			Some(max_functions) => pack_emitter::shards::split_files(files, max_functions),
+			None => vec![files],
+		};
+		for (idx, files) in shards.iter().enumerate() {
+			let path = pack_emitter::shards::shard_path(&output, idx);
+			if ctx.zip_output {
+				pack_emitter::write_pack_zip(&path, files);
+			} else if ctx.hot_reload {
+				pack_emitter::update_pack_files(pack, &path, files);
+			} else {
+				pack_emitter::write_pack_files(pack, &path, files);
+			}
+		}
+		if shards.len() > 1 {
+			println!("Split the datapack into {} datapacks, which all have to be enabled", shards.len());
+		}
+		let leftover = pack_emitter::shards::shard_path(&output, shards.len());
+		if ctx.max_pack_functions.is_some() && leftover.exists() {
+			println!("warning: {leftover:?} is left over from a build with more datapacks, remove it so its functions don't replace these");
+		}
+		if let Some(path) = &ctx.short_names {
+			std::fs::write(path, names.to_json()).unwrap();
+		}
 		println!("Finished export in {}s", SystemTime::now().duration_since(start).unwrap().as_secs_f64());
 	}
 
@@ -300,7 +1484,7 @@ pub fn run(args: Args) {
 	}
 }
 
-fn run_datapack_output(_ctx: &CompileContext, datapack: Vec<Function>) {
+fn run_datapack_output(ctx: &CompileContext, datapack: Vec<Function>) {
 	let indiv_time = vec![0; datapack.len()];
 	let intrin_cum_times = vec![0; datapack.len()];
 	let intrin_visited = vec![false; datapack.len()];
@@ -309,7 +1493,7 @@ fn run_datapack_output(_ctx: &CompileContext, datapack: Vec<Function>) {
 	let mut interp = datapack_vm::Interpreter::new(datapack, 0);
 
 	interp.max_total_commands = 1_500_000_000;
-	interp.max_tick_commands = 60_000_000;
+	interp.max_tick_commands = ctx.run_output_tick_limit();
 
 	let (_, func_name) = datapack_common::functions::command_components::FunctionIdent::parse_from_command("wasmrunner:init").unwrap();
 
@@ -318,8 +1502,6 @@ fn run_datapack_output(_ctx: &CompileContext, datapack: Vec<Function>) {
 
 	interp.run_to_end().unwrap();
 
-	interp.max_tick_commands = 500_000;
-
 	let (_, func_name) = datapack_common::functions::command_components::FunctionIdent::parse_from_command("wasmrunner:_start").unwrap();
 
 	let interp_idx = interp.get_func_idx(&func_name);
@@ -327,7 +1509,7 @@ fn run_datapack_output(_ctx: &CompileContext, datapack: Vec<Function>) {
 
 	#[cfg(feature = "gui")]
 	{
-		let vm_cfg = datapack_vm::gui::Cfg::new(&_ctx.sim_flags);
+		let vm_cfg = datapack_vm::gui::Cfg::new(&ctx.sim_flags);
 		let vm_cfg = vm_cfg.unwrap_or_else(|_| {
 			eprintln!("invalid simulator flags passed!");
 			datapack_vm::gui::print_usage();
@@ -456,11 +1638,38 @@ fn run_interp(
 
 // TODO: Test mixed-tick tables
 
-#[derive(Debug, PartialEq, Eq)]
-pub enum JumpMode {
-	Direct,
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum Edition {
+	/// A datapack for Minecraft: Java Edition.
+	Java,
+	/// An experimental behavior pack for Minecraft: Bedrock Edition. Bedrock has no `data`, `execute store`,
+	/// or marker entities, so most memory accesses can't be written, and commands without an equivalent become comments.
+	Bedrock,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum DivByZero {
+	/// Division by zero gives -1, all bits set, and remainder by zero gives the dividend, without checking the divisor first.
+	Fast,
+	/// Division and remainder by zero trap, like WebAssembly requires, at the cost of a few more commands each.
+	Trap,
 }
 
-pub fn jump_mode() -> JumpMode {
-	JumpMode::Direct
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum ProfileMode {
+	/// Count the calls to each function.
+	Functions,
+	/// Count how often each block runs, which points at the loops inside a function that take the most time.
+	Blocks,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum JumpMode {
+	/// Blocks call the blocks they jump to, so the function call stack grows with every jump
+	/// until the program returns or yields.
+	Direct,
+	/// Loop back edges and calls that may yield return to a central dispatcher with the block to continue at,
+	/// which checks the command budget before running it. Keeps the call stack short and yields between blocks,
+	/// at the cost of a few more commands per jump. Better suited to servers with small command limits.
+	Bounce,
 }
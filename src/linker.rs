@@ -0,0 +1,289 @@
+//! Links several WebAssembly modules into one SSA program, so projects split across modules
+//! don't need an external linker.
+//!
+//! An import is provided by another module if its module name is that module's name and the module exports
+//! something of the same kind under the import's field name. Calls to provided functions become direct calls,
+//! and provided globals and memories are shared with the module exporting them, so a memory exported by one module
+//! and imported by the others is merged into one. Memories that aren't shared stay separate memories of the program.
+//! Imports no module provides are handled the same as for a single module.
+
+use std::collections::{HashMap, HashSet};
+
+use wasmparser::ExternalKind;
+
+use crate::{CompileContext, wasm_file::WasmFile, validator, ssa::{BlockId, SsaBasicBlock, SsaFunction, SsaInstr, SsaProgram, SsaTerminator}};
+
+/// Converts and links modules into a single program. The first module keeps its function indices,
+/// and the others follow it in order.
+pub fn link_to_ssa(ctx: &CompileContext, modules: &[(&str, &WasmFile)]) -> SsaProgram {
+	for (idx, (name, _)) in modules.iter().enumerate() {
+		if modules[..idx].iter().any(|(other, _)| other == name) {
+			panic!("two modules are named `{name}`, rename one of the files");
+		}
+	}
+
+	let mut func_base = Vec::new();
+	let mut next_func = 0;
+	for (_, file) in modules.iter() {
+		func_base.push(next_func);
+		next_func += file.functions.functions.len();
+	}
+
+	let func_maps = modules.iter().enumerate().map(|(module, (_, file))| {
+		(0..file.functions.functions.len()).map(|func| {
+			let (provider, provided) = resolve(modules, module, ExternalKind::Func, func as u32);
+			if (provider, provided as usize) != (module, func) && file.func_type(func) != modules[provider].1.func_type(provided as usize) {
+				let import = file.func_import(func);
+				panic!("`{}.{}` is imported by `{}` with a different type than `{}` exports it with", import.module, import.field, modules[module].0, import.module);
+			}
+			func_base[provider] + provided as usize
+		}).collect::<Vec<_>>()
+	}).collect::<Vec<_>>();
+
+	let mut programs = modules.iter().enumerate().map(|(module, (_, file))| {
+		let linked_funcs = file.imports.func_imports().iter().enumerate()
+			.filter(|(func, _)| func_maps[module][*func] != func_base[module] + func)
+			.map(|(func, _)| func)
+			.collect::<HashSet<_>>();
+		validator::module_to_ssa(ctx, file, &linked_funcs)
+	}).collect::<Vec<_>>();
+
+	let mut linked = SsaProgram {
+		local_types: HashMap::new(),
+		globals: Vec::new(),
+		memory: Vec::new(),
+		tables: Vec::new(),
+		elem_segments: Vec::new(),
+		code: Vec::new(),
+		exports: HashMap::new(),
+		start: None,
+		func_names: HashMap::new(),
+		snapshot_regions: Vec::new(),
+		div_by_zero: ctx.div_by_zero,
+	};
+
+	// Globals and memories only get a place in the program if no other module provides them
+	let global_maps = place_shared(modules, ExternalKind::Global, |module| programs[module].globals.len(), |module, global| {
+		linked.globals.push(programs[module].globals[global]);
+		linked.globals.len() - 1
+	});
+
+	let mut shared_memory = Vec::new();
+	let memory_maps = place_shared(modules, ExternalKind::Memory, |module| programs[module].memory.len(), |module, memory| {
+		shared_memory.push((module, memory));
+		shared_memory.len() - 1
+	});
+
+	let mut memories = programs.iter_mut()
+		.map(|program| std::mem::take(&mut program.memory).into_iter().map(Some).collect::<Vec<_>>())
+		.collect::<Vec<_>>();
+	linked.memory = shared_memory.iter().map(|&(module, memory)| memories[module][memory].take().unwrap()).collect();
+
+	// What's left is provided by other modules, which get the data these modules initialize it with
+	for (module, module_memories) in memories.into_iter().enumerate() {
+		for (memory, imported) in module_memories.into_iter().enumerate() {
+			if let Some(imported) = imported {
+				let target = &mut linked.memory[memory_maps[module][memory] as usize];
+				for range in imported.imported.iter().flatten() {
+					if range.end > target.data.len() {
+						panic!("`{}` initializes memory up to {:#X}, but the memory it imports is only {} bytes", modules[module].0, range.end, target.data.len());
+					}
+					target.store(range.start, imported.load(range.start, range.len()));
+					target.mark_initialized(range.clone());
+				}
+			}
+		}
+	}
+
+	let mut starts = Vec::new();
+	for (module, program) in programs.into_iter().enumerate() {
+		let relocation = Relocation {
+			funcs: &func_maps[module],
+			globals: &global_maps[module],
+			memories: &memory_maps[module],
+			table_base: linked.tables.len() as u32,
+			segment_base: linked.elem_segments.len() as u32,
+		};
+
+		for func in program.code.into_iter() {
+			linked.code.push(relocation.apply_func(func));
+		}
+
+		for mut table in program.tables.into_iter() {
+			for element in table.elements.iter_mut().flatten() {
+				*element = relocation.funcs[*element];
+			}
+			if let Some(funcs) = &mut table.runtime_funcs {
+				for func in funcs.iter_mut() {
+					*func = relocation.funcs[*func];
+				}
+			}
+			linked.tables.push(table);
+		}
+
+		for segment in program.elem_segments.into_iter() {
+			linked.elem_segments.push(segment.into_iter().map(|func| func.map(|func| relocation.funcs[func])).collect());
+		}
+
+		for (func, types) in program.local_types.into_iter() {
+			linked.local_types.insert(relocation.funcs[func], types);
+		}
+		// Imports keep the name of the function they are linked to
+		for (func, name) in program.func_names.into_iter() {
+			if relocation.funcs[func] == func_base[module] + func {
+				linked.func_names.insert(relocation.funcs[func], name);
+			}
+		}
+
+		for (name, id) in program.exports.into_iter() {
+			let id = BlockId { func: relocation.funcs[id.func], block: id.block };
+			match linked.exports.get(&name) {
+				Some(existing) if *existing != id => println!("warning: `{}` exports `{name}` too, only the first module's export is kept", modules[module].0),
+				Some(_) => {}
+				None => { linked.exports.insert(name, id); }
+			}
+		}
+
+		starts.extend(program.start.map(|start| BlockId { func: relocation.funcs[start.func], block: start.block }));
+	}
+
+	linked.start = link_starts(&mut linked, starts, next_func);
+
+	validator::finish_ssa(ctx, linked)
+}
+
+/// Follows an import to the module that defines what it refers to, giving that module and its index there.
+/// Anything that isn't an import, or is an import no module provides, resolves to itself.
+fn resolve(modules: &[(&str, &WasmFile)], module: usize, kind: ExternalKind, index: u32) -> (usize, u32) {
+	let mut current = (module, index);
+	for _ in 0..=modules.len() {
+		let (module, index) = current;
+		let (import_module, field) = match import_of(modules[module].1, kind, index) {
+			Some(import) => import,
+			None => return current,
+		};
+
+		let provided = modules.iter().enumerate()
+			.filter(|(_, (name, _))| *name == import_module)
+			.find_map(|(provider, (_, file))| {
+				let export = file.exports.exports.iter().find(|export| export.name == field && export.kind == kind)?;
+				Some((provider, export.index))
+			});
+		match provided {
+			Some(provided) => current = provided,
+			None => return current,
+		}
+	}
+
+	panic!("`{}` imports {:?} {index} from a cycle of modules that never define it", modules[module].0, kind)
+}
+
+/// The module and field an index refers to, if it refers to an import.
+fn import_of<'a>(file: &WasmFile<'a>, kind: ExternalKind, index: u32) -> Option<(&'a str, &'a str)> {
+	let index = index as usize;
+	match kind {
+		ExternalKind::Func => file.imports.func_imports().get(index).map(|import| (import.module, import.field)),
+		ExternalKind::Global => file.imports.global_imports().get(index).map(|import| (import.module, import.field)),
+		ExternalKind::Memory => file.imports.memory_imports().get(index).map(|import| (import.module, import.field)),
+		_ => None,
+	}
+}
+
+/// Gives everything of a kind that isn't provided by another module a place in the program with `place`,
+/// and returns where each module's indices end up.
+fn place_shared(modules: &[(&str, &WasmFile)], kind: ExternalKind, count: impl Fn(usize) -> usize, mut place: impl FnMut(usize, usize) -> usize) -> Vec<Vec<u32>> {
+	let mut placed = HashMap::new();
+	for module in 0..modules.len() {
+		for index in 0..count(module) {
+			if resolve(modules, module, kind, index as u32) == (module, index as u32) {
+				placed.insert((module, index as u32), place(module, index) as u32);
+			}
+		}
+	}
+
+	(0..modules.len()).map(|module| {
+		(0..count(module)).map(|index| placed[&resolve(modules, module, kind, index as u32)]).collect()
+	}).collect()
+}
+
+/// Where one module's indices end up in the linked program.
+struct Relocation<'a> {
+	funcs: &'a [usize],
+	globals: &'a [u32],
+	memories: &'a [u32],
+	table_base: u32,
+	segment_base: u32,
+}
+
+impl Relocation<'_> {
+	fn apply_func(&self, func: SsaFunction) -> SsaFunction {
+		let func_id = self.funcs[func.func_id() as usize];
+		let SsaFunction { code, params, returns, code_ranges: _ } = func;
+		let code = code.into_iter().map(|(id, mut block)| {
+			for instr in block.body.iter_mut() {
+				self.apply(instr);
+			}
+			for target in block.term.targets_mut() {
+				target.label.func = func_id;
+			}
+			(BlockId { func: func_id, block: id.block }, block)
+		});
+		SsaFunction::new(code, params, returns)
+	}
+
+	fn apply(&self, instr: &mut SsaInstr) {
+		match instr {
+			SsaInstr::Load64(memarg, _, _) |
+			SsaInstr::Load32S(memarg, _, _) |
+			SsaInstr::Load32U(memarg, _, _) |
+			SsaInstr::Load16S(memarg, _, _) |
+			SsaInstr::Load16U(memarg, _, _) |
+			SsaInstr::Load8S(memarg, _, _) |
+			SsaInstr::Load8U(memarg, _, _) |
+			SsaInstr::Store64(memarg, _, _) |
+			SsaInstr::Store32(memarg, _, _) |
+			SsaInstr::Store16(memarg, _, _) |
+			SsaInstr::Store8(memarg, _, _) => memarg.memory = self.memories[memarg.memory as usize],
+			SsaInstr::Memset { memory, .. } |
+			SsaInstr::Memcpy { memory, .. } |
+			SsaInstr::MemoryGrow { memory, .. } |
+			SsaInstr::MemorySize(memory, _) => *memory = self.memories[*memory as usize],
+			SsaInstr::GlobalSet(global, _) |
+			SsaInstr::GlobalGet(_, global) => *global = self.globals[*global as usize],
+			SsaInstr::Call { function_index, .. } |
+			SsaInstr::RefFunc(_, function_index) => *function_index = self.funcs[*function_index as usize] as u32,
+			SsaInstr::CallIndirect { table_index, .. } |
+			SsaInstr::TableGet { table_index, .. } |
+			SsaInstr::TableSet { table_index, .. } |
+			SsaInstr::TableGrow { table_index, .. } |
+			SsaInstr::TableSize { table_index, .. } => *table_index += self.table_base,
+			SsaInstr::TableInit { table_index, segment, .. } => {
+				*table_index += self.table_base;
+				*segment += self.segment_base;
+			}
+			SsaInstr::ElemDrop(segment) => *segment += self.segment_base,
+			_ => {}
+		}
+	}
+}
+
+/// The start function of the linked program. If more than one module has a start function,
+/// a new function calling them last module first is added, so the modules given after the one
+/// importing from them are started before it.
+fn link_starts(program: &mut SsaProgram, starts: Vec<BlockId>, func: usize) -> Option<BlockId> {
+	if starts.len() <= 1 {
+		return starts.first().copied();
+	}
+
+	let body = starts.iter().rev().map(|start| SsaInstr::Call {
+		function_index: start.func as u32,
+		params: Vec::new(),
+		returns: Vec::new(),
+	}).collect();
+
+	let id = BlockId { func, block: 0 };
+	let block = SsaBasicBlock { params: Vec::new(), body, term: SsaTerminator::Return(Vec::new()) };
+	program.code.push(SsaFunction::new([(id, block)], Box::new([]), Box::new([])));
+	program.local_types.insert(func, Vec::new());
+	Some(id)
+}
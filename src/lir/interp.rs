@@ -1,8 +1,8 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
 use wasmparser::ValType;
 
-use crate::{ssa::{interp::TypedValue, BlockId, Memory, Table, lir_emitter::RegisterWithInfo}, jump_mode, JumpMode, lir::LirInstr};
+use crate::{ssa::{interp::{TypedValue, div_by_zero_result}, BlockId, Memory, Table, TrapKind, format_float, lir_emitter::RegisterWithInfo}, lir::LirInstr, pack_emitter, cost_model::CostModel, SnapshotRegion, DivByZero};
 
 use super::{Register, LirBasicBlock, DoubleRegister, LirProgram, LirTerminator, Condition, Half};
 
@@ -46,9 +46,9 @@ impl LocalStack {
 	pub fn push(&mut self, frame: &[ValType]) {
 		let frame_vals = frame.iter().map(|ty| {
 			match ty {
-				ValType::I32 => TypedValue::I32(0),
-				ValType::I64 => TypedValue::I64(0),
-				_ => todo!(),
+				ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => TypedValue::I32(0),
+				ValType::I64 | ValType::F64 => TypedValue::I64(0),
+				ty => panic!("locals of type {ty:?} aren't supported"),
 			}
 		}).collect();
 
@@ -196,6 +196,52 @@ impl ReturnStack {
 	}
 }
 
+/// Estimated number of commands the generated datapack would run,
+/// based on the code emitted for each instruction that was executed.
+#[derive(Debug, Default, Clone)]
+pub struct CommandCounts {
+	/// Total commands run in each function, indexed by function ID
+	pub per_func: HashMap<usize, usize>,
+	/// Commands run in each tick that has finished
+	pub per_tick: Vec<usize>,
+	/// Commands run so far in the current tick
+	pub current_tick: usize,
+}
+
+impl CommandCounts {
+	fn record(&mut self, func: usize, cmds: usize) {
+		*self.per_func.entry(func).or_default() += cmds;
+		self.current_tick += cmds;
+	}
+
+	fn end_tick(&mut self) {
+		self.per_tick.push(self.current_tick);
+		self.current_tick = 0;
+	}
+
+	pub fn total(&self) -> usize {
+		self.per_func.values().sum()
+	}
+}
+
+impl fmt::Display for CommandCounts {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "Estimated commands run: {}", self.total())?;
+
+		let ticks = self.per_tick.iter().copied().chain(Some(self.current_tick)).collect::<Vec<_>>();
+		let max_tick = ticks.iter().copied().max().unwrap_or(0);
+		writeln!(f, "Ticks: {}, most commands in one tick: {}", ticks.len(), max_tick)?;
+
+		let mut funcs = self.per_func.iter().collect::<Vec<_>>();
+		funcs.sort_by_key(|(func, cmds)| (std::cmp::Reverse(**cmds), **func));
+		for (func, cmds) in funcs.iter().take(20) {
+			writeln!(f, "\tfunction {func}: {cmds}")?;
+		}
+
+		Ok(())
+	}
+}
+
 pub struct LirInterpreter {
 	globals: GlobalList,
 	data_stack: Vec<i32>,
@@ -205,14 +251,35 @@ pub struct LirInterpreter {
 	registers: RegContext,
 	memory: Vec<Memory>,
 	tables: Vec<Table>,
+	elem_segments: Vec<Vec<Option<usize>>>,
+	/// Each snapshot region and the bytes last copied from it, which start out as zeros like in the datapack
+	snapshots: Vec<(SnapshotRegion, Vec<u8>)>,
 	returns: HashMap<usize, Box<[ValType]>>,
 	code: HashMap<BlockId, LirBasicBlock>,
 	scheduled: Option<BlockId>,
+	/// The estimated cost of each instruction in a block, followed by the cost of its terminator
+	costs: HashMap<BlockId, (Vec<usize>, usize)>,
+	command_counts: CommandCounts,
+	/// Calls to recursive functions since the last forced yield
+	recursion_depth: u32,
+	cost_model: CostModel,
+	div_by_zero: DivByZero,
 }
 
 impl LirInterpreter {
 	pub fn new(program: LirProgram) -> Self {
-		let LirProgram { globals, memory, tables, code, constants: _, exports: _ /* TODO: */ } = program;
+		Self::with_cost_model(program, CostModel::default(), None)
+	}
+
+	/// Creates an interpreter that estimates command counts using the given cost model.
+	/// `checkpoint_cmds` is how many commands each checkpoint runs, if state is persisted.
+	pub fn with_cost_model(program: LirProgram, cost_model: CostModel, checkpoint_cmds: Option<usize>) -> Self {
+		let costs = program.code.iter().flat_map(|func| func.code.iter()).map(|(id, block)| {
+			let instr_costs = block.body.iter().map(|instr| pack_emitter::estimate_instr_cost(instr, &program, &cost_model)).collect();
+			(*id, (instr_costs, pack_emitter::estimate_term_cost(&block.term, program.jump_mode, checkpoint_cmds, &cost_model)))
+		}).collect();
+
+		let LirProgram { globals, memory, tables, elem_segments, code, constants: _, exports: _ /* TODO: */, start: _, func_names: _, snapshot_regions, block_palette: _, layout: _, jump_mode: _, div_by_zero } = program;
 
 		let globals = GlobalList::new(globals);
 
@@ -232,12 +299,23 @@ impl LirInterpreter {
 			registers: RegContext::default(),
 			memory,
 			tables,
+			elem_segments,
+			snapshots: snapshot_regions.into_iter().map(|region| (region, vec![0; region.length as usize])).collect(),
 			returns,
 			code,
 			scheduled: None,
+			costs,
+			command_counts: CommandCounts::default(),
+			recursion_depth: 0,
+			cost_model,
+			div_by_zero,
 		}
 	}
 
+	pub fn command_counts(&self) -> &CommandCounts {
+		&self.command_counts
+	}
+
 	pub fn call(&mut self, func: usize, params: &[TypedValue]) {
 		for (idx, param) in params.iter().enumerate() {
 			match param {
@@ -321,6 +399,72 @@ impl LirInterpreter {
 			regs.set_64(dst, f(lhs, rhs));
 		}
 
+		/// Applies a float operation to the bits of two floats.
+		/// NaN results are canonicalized, like they are by the softfloat intrinsics.
+		fn f32_binop(lhs: i32, rhs: i32, f: impl FnOnce(f32, f32) -> f32) -> i32 {
+			let result = f(f32::from_bits(lhs as u32), f32::from_bits(rhs as u32));
+			if result.is_nan() {
+				0x7FC0_0000
+			} else {
+				result.to_bits() as i32
+			}
+		}
+
+		/// Applies a double operation to the bits of two doubles.
+		/// NaN results are canonicalized, like they are by the softfloat intrinsics.
+		fn f64_binop(lhs: i64, rhs: i64, f: impl FnOnce(f64, f64) -> f64) -> i64 {
+			let result = f(f64::from_bits(lhs as u64), f64::from_bits(rhs as u64));
+			if result.is_nan() {
+				0x7FF8_0000_0000_0000
+			} else {
+				result.to_bits() as i64
+			}
+		}
+
+		/// Unlike `f64::min`, NaN wins and -0 is less than +0
+		fn float_min(a: f64, b: f64) -> f64 {
+			if a.is_nan() || b.is_nan() {
+				f64::NAN
+			} else if a == b {
+				if a.is_sign_negative() { a } else { b }
+			} else {
+				a.min(b)
+			}
+		}
+
+		/// Unlike `f64::max`, NaN wins and +0 is greater than -0
+		fn float_max(a: f64, b: f64) -> f64 {
+			if a.is_nan() || b.is_nan() {
+				f64::NAN
+			} else if a == b {
+				if a.is_sign_positive() { a } else { b }
+			} else {
+				a.max(b)
+			}
+		}
+
+		/// Applies a float operation to the bits of a float.
+		/// NaN results are canonicalized, like they are by the softfloat intrinsics.
+		fn f32_unaryop(val: i32, f: impl FnOnce(f32) -> f32) -> i32 {
+			f32_binop(val, 0, |a, _| f(a))
+		}
+
+		/// Applies a double operation to the bits of a double.
+		/// NaN results are canonicalized, like they are by the softfloat intrinsics.
+		fn f64_unaryop(val: i64, f: impl FnOnce(f64) -> f64) -> i64 {
+			f64_binop(val, 0, |a, _| f(a))
+		}
+
+		/// Rounds towards zero and wraps around, like the softfloat intrinsics.
+		/// NaN and infinity give 0.
+		fn trunc_to_64(val: f64) -> i64 {
+			if val.is_finite() {
+				val.trunc() as i128 as i64
+			} else {
+				0
+			}
+		}
+
 		fn do_compareop64<F>(dst: Register, lhs: DoubleRegister, rhs: DoubleRegister, regs: &mut RegContext, f: F)
 			where F: FnOnce(i64, i64) -> bool
 		{
@@ -329,9 +473,9 @@ impl LirInterpreter {
 			regs.set(dst, f(lhs, rhs) as i32);
 		}
 
-		fn do_store(src: Register, addr: RegisterWithInfo, bytes: usize, regs: &mut RegContext, mem: &mut [Memory])
+		fn do_store(mem: u32, src: Register, addr: RegisterWithInfo, bytes: usize, regs: &mut RegContext, memory: &mut [Memory])
 		{
-			let mem = &mut mem[0];
+			let mem = &mut memory[mem as usize];
 
 			let src = src.eval(regs);
 			let addr = addr.eval(regs);
@@ -341,11 +485,11 @@ impl LirInterpreter {
 			mem.data[addr as usize..][..bytes].copy_from_slice(data);
 		}
 
-		fn do_load(dst: Register, addr: RegisterWithInfo, bytes: usize, regs: &mut RegContext, mem: &mut [Memory]) {
+		fn do_load(mem: u32, dst: Register, addr: RegisterWithInfo, bytes: usize, regs: &mut RegContext, memory: &mut [Memory]) {
 			let addr = addr.eval(regs);
 
 			let mut data = [0; 4];
-			data[..bytes].copy_from_slice(&mem[0].data[addr as usize..][..bytes]);
+			data[..bytes].copy_from_slice(&memory[mem as usize].data[addr as usize..][..bytes]);
 			let data = i32::from_le_bytes(data);
 
 			regs.set(dst, data);
@@ -364,10 +508,10 @@ impl LirInterpreter {
 			&LirInstr::Add(lhs, rhs) => do_assignop(lhs, rhs, &mut self.registers, |a, b| a.wrapping_add(b)),
 			&LirInstr::Sub(lhs, rhs) => do_assignop(lhs, rhs, &mut self.registers, |a, b| a.wrapping_sub(b)),
 			&LirInstr::Mul(lhs, rhs) => do_assignop(lhs, rhs, &mut self.registers, |a, b| a.wrapping_mul(b)),
-			&LirInstr::DivS(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| a.wrapping_div(b)),
-			&LirInstr::DivU(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| (a as u32).wrapping_div(b as u32) as i32),
-			&LirInstr::RemS(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| a.wrapping_rem(b)),
-			&LirInstr::RemU(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| (a as u32).wrapping_rem(b as u32) as i32),
+			&LirInstr::DivS(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| if b == 0 { div_by_zero_result(self.div_by_zero, -1) } else { a.wrapping_div(b) }),
+			&LirInstr::DivU(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| if b == 0 { div_by_zero_result(self.div_by_zero, -1) } else { (a as u32).wrapping_div(b as u32) as i32 }),
+			&LirInstr::RemS(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| if b == 0 { div_by_zero_result(self.div_by_zero, a) } else { a.wrapping_rem(b) }),
+			&LirInstr::RemU(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| if b == 0 { div_by_zero_result(self.div_by_zero, a) } else { (a as u32).wrapping_rem(b as u32) as i32 }),
 
 			&LirInstr::MulTo64 (dst, lhs, rhs) => {
 				let l = self.registers.get(lhs);
@@ -378,10 +522,10 @@ impl LirInterpreter {
 
 			&LirInstr::Add64 (dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| a.wrapping_add(b)),
 			&LirInstr::Sub64 (dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| a.wrapping_sub(b)),
-			&LirInstr::DivS64(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| a.wrapping_div(b)),
-			&LirInstr::DivU64(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| (a as u64).wrapping_div(b as u64) as i64),
-			&LirInstr::RemS64(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| a.wrapping_rem(b)),
-			&LirInstr::RemU64(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| (a as u64).wrapping_rem(b as u64) as i64),
+			&LirInstr::DivS64(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| if b == 0 { div_by_zero_result(self.div_by_zero, -1) } else { a.wrapping_div(b) }),
+			&LirInstr::DivU64(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| if b == 0 { div_by_zero_result(self.div_by_zero, -1) } else { (a as u64).wrapping_div(b as u64) as i64 }),
+			&LirInstr::RemS64(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| if b == 0 { div_by_zero_result(self.div_by_zero, a) } else { a.wrapping_rem(b) }),
+			&LirInstr::RemU64(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| if b == 0 { div_by_zero_result(self.div_by_zero, a) } else { (a as u64).wrapping_rem(b as u64) as i64 }),
 			&LirInstr::Shl (dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| a << b.rem_euclid(32)),
 			&LirInstr::ShrS(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| a >> b.rem_euclid(32)),
 			&LirInstr::ShrU(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| (a as u32 >> b.rem_euclid(32)) as i32),
@@ -392,6 +536,83 @@ impl LirInterpreter {
 			&LirInstr::ShrU64(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| ((a as u64) >> b.rem_euclid(64)) as i64),
 			&LirInstr::Rotl64(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| a.rotate_left(b.rem_euclid(64) as u32)),
 			&LirInstr::Rotr64(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| a.rotate_right(b.rem_euclid(64) as u32)),
+			&LirInstr::F32Add(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| f32_binop(a, b, |a, b| a + b)),
+			&LirInstr::F32Sub(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| f32_binop(a, b, |a, b| a - b)),
+			&LirInstr::F32Mul(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| f32_binop(a, b, |a, b| a * b)),
+			&LirInstr::F32Div(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| f32_binop(a, b, |a, b| a / b)),
+			&LirInstr::F32Min(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| f32_binop(a, b, |a, b| float_min(a as f64, b as f64) as f32)),
+			&LirInstr::F32Max(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| f32_binop(a, b, |a, b| float_max(a as f64, b as f64) as f32)),
+			&LirInstr::F64Add(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| f64_binop(a, b, |a, b| a + b)),
+			&LirInstr::F64Sub(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| f64_binop(a, b, |a, b| a - b)),
+			&LirInstr::F64Mul(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| f64_binop(a, b, |a, b| a * b)),
+			&LirInstr::F64Div(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| f64_binop(a, b, |a, b| a / b)),
+			&LirInstr::F64Min(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| f64_binop(a, b, float_min)),
+			&LirInstr::F64Max(dst, lhs, rhs) => do_binaryop64(dst, lhs, rhs, &mut self.registers, |a, b| f64_binop(a, b, float_max)),
+			&LirInstr::F32Sqrt(dst, src) => {
+				let val = self.registers.get(src);
+				self.registers.set(dst, f32_unaryop(val, f32::sqrt));
+			}
+			&LirInstr::F64Sqrt(dst, src) => {
+				let val = self.registers.get_64(src);
+				self.registers.set_64(dst, f64_unaryop(val, f64::sqrt));
+			}
+			&LirInstr::F32Floor(dst, src) => {
+				let val = self.registers.get(src);
+				self.registers.set(dst, f32_unaryop(val, f32::floor));
+			}
+			&LirInstr::F32Ceil(dst, src) => {
+				let val = self.registers.get(src);
+				self.registers.set(dst, f32_unaryop(val, f32::ceil));
+			}
+			&LirInstr::F32Trunc(dst, src) => {
+				let val = self.registers.get(src);
+				self.registers.set(dst, f32_unaryop(val, f32::trunc));
+			}
+			&LirInstr::F32Nearest(dst, src) => {
+				let val = self.registers.get(src);
+				self.registers.set(dst, f32_unaryop(val, f32::round_ties_even));
+			}
+			&LirInstr::F64Floor(dst, src) => {
+				let val = self.registers.get_64(src);
+				self.registers.set_64(dst, f64_unaryop(val, f64::floor));
+			}
+			&LirInstr::F64Ceil(dst, src) => {
+				let val = self.registers.get_64(src);
+				self.registers.set_64(dst, f64_unaryop(val, f64::ceil));
+			}
+			&LirInstr::F64Trunc(dst, src) => {
+				let val = self.registers.get_64(src);
+				self.registers.set_64(dst, f64_unaryop(val, f64::trunc));
+			}
+			&LirInstr::F64Nearest(dst, src) => {
+				let val = self.registers.get_64(src);
+				self.registers.set_64(dst, f64_unaryop(val, f64::round_ties_even));
+			}
+			&LirInstr::F32TruncTo64(dst, src) => {
+				let val = f32::from_bits(self.registers.get(src) as u32);
+				self.registers.set_64(dst, trunc_to_64(val as f64));
+			}
+			&LirInstr::F64TruncTo64(dst, src) => {
+				let val = f64::from_bits(self.registers.get_64(src) as u64);
+				self.registers.set_64(dst, trunc_to_64(val));
+			}
+			&LirInstr::F32FromS64(dst, src) => {
+				let val = self.registers.get_64(src);
+				self.registers.set(dst, (val as f32).to_bits() as i32);
+			}
+			&LirInstr::F32FromU64(dst, src) => {
+				let val = self.registers.get_64(src) as u64;
+				self.registers.set(dst, (val as f32).to_bits() as i32);
+			}
+			&LirInstr::F64FromS64(dst, src) => {
+				let val = self.registers.get_64(src);
+				self.registers.set_64(dst, (val as f64).to_bits() as i64);
+			}
+			&LirInstr::F64FromU64(dst, src) => {
+				let val = self.registers.get_64(src) as u64;
+				self.registers.set_64(dst, (val as f64).to_bits() as i64);
+			}
+
 			&LirInstr::Xor(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| a ^ b),
 			&LirInstr::And(dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| a & b),
 			&LirInstr::Or (dst, lhs, rhs) => do_binaryop(dst, lhs, rhs, &mut self.registers, |a, b| a | b),
@@ -471,26 +692,44 @@ impl LirInterpreter {
 			&LirInstr::GlobalSet(dst, dst_half, src) => self.globals.set(dst, dst_half, self.registers.get(src)),
 			&LirInstr::GlobalGet(dst, src, src_half) => self.registers.set(dst, self.globals.get(src, src_half)),
 
-			&LirInstr::Store32(src, addr) => do_store(src, addr, 4, &mut self.registers, &mut self.memory),
-			&LirInstr::Store16(src, addr) => do_store(src, addr, 2, &mut self.registers, &mut self.memory),
-			&LirInstr::Store8(src, addr) => do_store(src, addr, 1, &mut self.registers, &mut self.memory),
+			&LirInstr::Store32(mem, src, addr) => do_store(mem, src, addr, 4, &mut self.registers, &mut self.memory),
+			&LirInstr::Store16(mem, src, addr) => do_store(mem, src, addr, 2, &mut self.registers, &mut self.memory),
+			&LirInstr::Store8(mem, src, addr) => do_store(mem, src, addr, 1, &mut self.registers, &mut self.memory),
 
-			&LirInstr::Load64(dst, addr) => {
+			&LirInstr::Load64(mem, dst, addr) => {
 				let addr = addr.eval(&mut self.registers);
 
+				let memory = &self.memory[mem as usize];
 				let mut data_lo = [0; 4];
 				let mut data_hi = [0; 4];
-				data_lo.copy_from_slice(&self.memory[0].data[addr as usize..][..4]);
-				data_hi.copy_from_slice(&self.memory[0].data[addr as usize + 4..][..4]);
+				data_lo.copy_from_slice(&memory.data[addr as usize..][..4]);
+				data_hi.copy_from_slice(&memory.data[addr as usize + 4..][..4]);
 				let data_lo = i32::from_le_bytes(data_lo);
 				let data_hi = i32::from_le_bytes(data_hi);
 
 				self.registers.set(dst.lo(), data_lo);
 				self.registers.set(dst.hi(), data_hi);
 			}
-			&LirInstr::Load32(dst, addr) => do_load(dst, addr, 4, &mut self.registers, &mut self.memory),
-			&LirInstr::Load16(dst, addr) => do_load(dst, addr, 2, &mut self.registers, &mut self.memory),
-			&LirInstr::Load8 (dst, addr) => do_load(dst, addr, 1, &mut self.registers, &mut self.memory),
+			&LirInstr::Load32(mem, dst, addr) => do_load(mem, dst, addr, 4, &mut self.registers, &mut self.memory),
+			&LirInstr::Load16(mem, dst, addr) => do_load(mem, dst, addr, 2, &mut self.registers, &mut self.memory),
+			&LirInstr::Load8 (mem, dst, addr) => do_load(mem, dst, addr, 1, &mut self.registers, &mut self.memory),
+
+			&LirInstr::BufLoad8(dst, ptr, idx) => {
+				let addr = self.registers.get(ptr.0).wrapping_add(self.registers.get(idx.0));
+				do_load(0, dst, Register::const_val(addr).into(), 1, &mut self.registers, &mut self.memory)
+			}
+			&LirInstr::BufLoad16(dst, ptr, idx) => {
+				let addr = self.registers.get(ptr.0).wrapping_add(self.registers.get(idx.0).wrapping_mul(2));
+				do_load(0, dst, Register::const_val(addr).into(), 2, &mut self.registers, &mut self.memory)
+			}
+			&LirInstr::BufStore8(src, ptr, idx) => {
+				let addr = self.registers.get(ptr.0).wrapping_add(self.registers.get(idx.0));
+				do_store(0, src, Register::const_val(addr).into(), 1, &mut self.registers, &mut self.memory)
+			}
+			&LirInstr::BufStore16(src, ptr, idx) => {
+				let addr = self.registers.get(ptr.0).wrapping_add(self.registers.get(idx.0).wrapping_mul(2));
+				do_store(0, src, Register::const_val(addr).into(), 2, &mut self.registers, &mut self.memory)
+			}
 
 			&LirInstr::Select { dst, true_reg, false_reg, cond } => {
 				let cond = self.registers.get(cond);
@@ -513,9 +752,10 @@ impl LirInterpreter {
 			}
 			LirInstr::CallIndirect { table, table_entry } => {
 				let table_entry = self.registers.get(*table_entry);
-				let table_entry = usize::try_from(table_entry).unwrap();
 
-				let function_index = table[table_entry].unwrap();
+				let function_index = usize::try_from(table_entry).ok()
+					.and_then(|idx| table.get(idx).copied().flatten())
+					.unwrap_or_else(|| panic!("trap: {} or {} at {table_entry}", TrapKind::UninitializedElement, TrapKind::IndirectCallTypeMismatch));
 
 				let block = BlockId { func: function_index, block: 0 };
 
@@ -525,6 +765,24 @@ impl LirInterpreter {
 
 				self.call_stack.0.push(Pc { block, instr: 0 });
 			}
+			LirInstr::CallIndirectCheck { table_entry, index, null, mismatched, len } => {
+				let table_entry = self.registers.get(*table_entry);
+				let index = self.registers.get(*index);
+
+				if let Some(len) = *len {
+					if usize::try_from(table_entry).map_or(true, |entry| entry >= len) {
+						panic!("trap: {} at index {index}", TrapKind::TableOutOfBounds);
+					}
+				}
+
+				let table_entry = table_entry as usize;
+				if null.contains(&table_entry) {
+					panic!("trap: {} at index {index}", TrapKind::UninitializedElement);
+				}
+				if mismatched.contains(&table_entry) {
+					panic!("trap: {} at index {index}", TrapKind::IndirectCallTypeMismatch);
+				}
+			}
 
 			LirInstr::Push(src) => for &src in src.iter() { self.data_stack.push(self.registers.get(src)) },
 			LirInstr::Pop(dst) => for &dst in dst.iter().rev() { self.registers.set(dst, self.data_stack.pop().unwrap()) },
@@ -552,23 +810,187 @@ impl LirInterpreter {
 			LirInstr::PopReturnAddr => {
 				self.return_stack.pop().unwrap();
 			}
+			&LirInstr::RecursionYieldCheck(depth) => {
+				self.recursion_depth += 1;
+				if self.recursion_depth >= depth {
+					self.recursion_depth = 0;
+					self.command_counts.current_tick = self.command_counts.current_tick.max(self.cost_model.max_cmds_per_tick);
+				}
+			}
+
+			&LirInstr::Memset { memory, dest, value, length, result } => {
+				let dest_val = self.registers.get(dest);
+				let value = self.registers.get(value);
+				let length = self.registers.get(length);
+
+				let memory = &mut self.memory[memory as usize];
+
+				// Both are unsigned, and out-of-bounds fills trap even if the length is zero
+				let (dest, length) = (dest_val as u32 as usize, length as u32 as usize);
+				if dest + length > memory.data.len() {
+					panic!("out of bounds memory fill of {length} bytes at {dest}");
+				}
+
+				memory.data[dest..][..length].fill(value as u8);
+
+				self.registers.set(result, dest_val);
+			}
+			&LirInstr::Memcpy { memory, dest, src, length } => {
+				let dest = self.registers.get(dest);
+				let src = self.registers.get(src);
+				let length = self.registers.get(length);
 
-			LirInstr::Memset { .. } => todo!(),
+				let memory = &mut self.memory[memory as usize];
+
+				// All unsigned, and out-of-bounds copies trap even if the length is zero
+				let (dest, src, length) = (dest as u32 as usize, src as u32 as usize, length as u32 as usize);
+				if dest.max(src) + length > memory.data.len() {
+					panic!("out of bounds memory copy of {length} bytes from {src} to {dest}");
+				}
+
+				memory.data.copy_within(src..src + length, dest);
+			}
+
+			&LirInstr::MemoryGrow { memory, pages, result } => {
+				let pages = self.registers.get(pages);
+
+				let old_pages = self.memory[memory as usize].grow(pages as u32 as usize).map_or(-1, |p| p as i32);
+
+				self.registers.set(result, old_pages);
+			}
+			&LirInstr::MemorySize(memory, dst) => {
+				self.registers.set(dst, self.memory[memory as usize].num_pages() as i32);
+			}
+			&LirInstr::TableGet { table, dst, index } => {
+				let index = self.registers.get(index) as u32 as usize;
+
+				let elements = &self.tables[table as usize].elements;
+				let elem = *elements.get(index).unwrap_or_else(|| panic!("out of bounds table access at {index}"));
+
+				self.registers.set(dst, elem.map_or(0, |f| f as i32 + 1));
+			}
+			&LirInstr::TableSet { table, index, src } => {
+				let index = self.registers.get(index) as u32 as usize;
+				let value = self.registers.get(src);
+
+				let elements = &mut self.tables[table as usize].elements;
+				let elem = elements.get_mut(index).unwrap_or_else(|| panic!("out of bounds table access at {index}"));
+
+				*elem = if value == 0 { None } else { Some(value as usize - 1) };
+			}
+			&LirInstr::TableGrow { table, init, count, result } => {
+				let init = self.registers.get(init);
+				let count = self.registers.get(count) as u32 as usize;
+
+				let init = if init == 0 { None } else { Some(init as usize - 1) };
+				let old_size = self.tables[table as usize].grow(count, init).map_or(-1, |s| s as i32);
+
+				self.registers.set(result, old_size);
+			}
+			&LirInstr::TableSize { table, dst } => {
+				self.registers.set(dst, self.tables[table as usize].elements.len() as i32);
+			}
+			&LirInstr::TableInit { table, segment, dst, src, length } => {
+				let dst = self.registers.get(dst) as u32 as usize;
+				let src = self.registers.get(src) as u32 as usize;
+				let length = self.registers.get(length) as u32 as usize;
+
+				let segment = &self.elem_segments[segment as usize];
+				let elements = &mut self.tables[table as usize].elements;
+				if src + length > segment.len() || dst + length > elements.len() {
+					panic!("out of bounds table access initializing {length} entries from {src} to {dst}");
+				}
+
+				elements[dst..][..length].copy_from_slice(&segment[src..][..length]);
+			}
+			&LirInstr::ElemDrop(segment) => {
+				self.elem_segments[segment as usize].clear();
+			}
 
 			LirInstr::TurtleSetX(_) |
 			LirInstr::TurtleSetY(_) |
 			LirInstr::TurtleSetZ(_) |
 			LirInstr::TurtleSetBlock(_) |
+			LirInstr::TurtleSetBlockNbt { .. } |
 			LirInstr::TurtleFillBlock { .. } |
 			LirInstr::TurtleCopyRegion { .. } |
 			LirInstr::TurtlePasteRegionMasked { .. } |
 			LirInstr::TurtleGetBlock(_) |
 			LirInstr::TurtleCopy |
 			LirInstr::TurtlePaste |
+			LirInstr::TurtleFace { .. } |
+			LirInstr::TurtleForward(_) |
+			LirInstr::TurtleTurn { .. } |
+			LirInstr::TurtleRaycast { .. } |
+			LirInstr::TurtleLightLevel(_) |
+			LirInstr::TurtleCheckBlock { .. } |
+			LirInstr::PlaySound { .. } |
+			LirInstr::Particle { .. } |
+			LirInstr::BossbarCreate(_) |
+			LirInstr::BossbarSetValue(_, _) |
+			LirInstr::BossbarSetMax(_, _) |
+			LirInstr::BossbarSetName(_, _, _) |
+			LirInstr::NearestPlayerPos { .. } |
+			LirInstr::EntityCount { .. } |
+			LirInstr::GameTime(_) |
+			LirInstr::Random { .. } |
 			LirInstr::PrintInt(_) |
-			LirInstr::PutChar(_) => panic!("attempt to use minecraft IO from LIR interpreter"),
+			LirInstr::PutChar(_) |
+			LirInstr::RawCommand(_) |
+			LirInstr::RawCommandStore { .. } => panic!("attempt to use minecraft IO from LIR interpreter"),
+			LirInstr::PackCall { namespace, export, .. } => panic!("attempt to call `{namespace}:call/{export}` in another datapack from LIR interpreter"),
+			&LirInstr::PrintLong(reg) => {
+				println!("{:?}", self.registers.get_64(reg));
+			}
+			&LirInstr::PrintF32(reg) => {
+				let value = f32::from_bits(self.registers.get(reg) as u32);
+				println!("{}", format_float(value as f64));
+			}
+			&LirInstr::PrintF64(reg) => {
+				let value = f64::from_bits(self.registers.get_64(reg) as u64);
+				println!("{}", format_float(value));
+			}
+			&LirInstr::PrintStr(ptr, len) |
+			&LirInstr::Title { ptr, len, .. } => {
+				let ptr = self.registers.get(ptr);
+				let len = self.registers.get(len);
+
+				let bytes = self.memory[0].load(ptr as u32 as usize, len as u32 as usize);
+				println!("{}", String::from_utf8_lossy(bytes).trim_end_matches('\n'));
+			}
+			&LirInstr::PrintFmt(fmt, args) => {
+				let fmt = self.registers.get(fmt);
+				let args = self.registers.get(args);
+
+				let output = self.memory[0].print_fmt(fmt, args);
+				println!("{}", String::from_utf8_lossy(&output).trim_end_matches('\n'));
+			}
+			&LirInstr::WasiFdWrite { fd, iovs, iovs_len, nwritten, result } => {
+				let fd = self.registers.get(fd);
+				let iovs = self.registers.get(iovs);
+				let iovs_len = self.registers.get(iovs_len);
+				let nwritten = self.registers.get(nwritten);
+
+				let (output, errno) = self.memory[0].wasi_fd_write(fd, iovs, iovs_len, nwritten);
+				print!("{}", String::from_utf8_lossy(&output));
+
+				self.registers.set(result, errno);
+			}
+			&LirInstr::MemorySnapshot(id) => {
+				let id = self.registers.get(id);
+				// Like in the datapack, IDs without a region do nothing
+				if let Some((region, saved)) = self.snapshots.get_mut(id as u32 as usize) {
+					saved.copy_from_slice(self.memory[0].load(region.start as usize, region.length as usize));
+				}
+			}
+			&LirInstr::MemoryRestore(id) => {
+				let id = self.registers.get(id);
+				if let Some((region, saved)) = self.snapshots.get(id as u32 as usize) {
+					self.memory[0].store(region.start as usize, saved);
+				}
+			}
 			LirInstr::WasiProcExit(_) => todo!(),
-			LirInstr::Todo(msg) => todo!("{}", msg),
+			LirInstr::Todo(msg) => panic!("trap: {}", TrapKind::Todo(msg)),
 		}
 
 		if incr_pc {
@@ -587,61 +1009,62 @@ impl LirInterpreter {
 
 		let block = self.code.get(&pc.block).unwrap();
 
+		let (instr_costs, term_cost) = self.costs.get(&pc.block).unwrap();
+		let cost = instr_costs.get(pc.instr).copied().unwrap_or(*term_cost);
+		self.command_counts.record(pc.block.func, cost);
+
 		if pc.instr == block.body.len() {
 			match &block.term {
 				LirTerminator::ScheduleJump(block_id, _delay) => {
 					assert!(self.scheduled.is_none());
 
 					self.scheduled = Some(*block_id);
+					self.command_counts.end_tick();
 
 					self.call_stack.incr(&self.code);
 
 					None
 				}
+				// In bounce mode, the dispatcher checks the command count at the same jumps direct mode does,
+				// so both modes run the same way here
 				LirTerminator::Jump(target) => {
-					if jump_mode() == JumpMode::Direct {
-						self.call_stack.incr(&self.code);
-
-						self.call_stack.push(Pc { block: target.label, instr: 0 });
+					self.call_stack.incr(&self.code);
 
-						None
-					} else {
-						todo!()
+					if target.cmd_check && self.command_counts.current_tick >= self.cost_model.max_cmds_per_tick {
+						self.command_counts.end_tick();
 					}
+
+					self.call_stack.push(Pc { block: target.label, instr: 0 });
+
+					None
 				}
 				LirTerminator::JumpIf { true_label, false_label, cond } => {
-					if jump_mode() == JumpMode::Direct {
-						self.call_stack.incr(&self.code);
+					self.call_stack.incr(&self.code);
 
-						let cond = self.registers.get(*cond);
+					let cond = self.registers.get(*cond);
 
-						if cond != 0 {
-							self.call_stack.push(Pc { block: true_label.label, instr: 0 });
-						} else {
-							self.call_stack.push(Pc { block: false_label.label, instr: 0 });
-						}
+					let target = if cond != 0 { true_label } else { false_label };
 
-						None
-					} else {
-						todo!()
+					if target.cmd_check && self.command_counts.current_tick >= self.cost_model.max_cmds_per_tick {
+						self.command_counts.end_tick();
 					}
+
+					self.call_stack.push(Pc { block: target.label, instr: 0 });
+
+					None
 				}
 				LirTerminator::JumpTable { arms, default, cond } => {
-					if jump_mode() == JumpMode::Direct {
-						self.call_stack.incr(&self.code);
-
-						let cond = self.registers.get(*cond);
+					self.call_stack.incr(&self.code);
 
-						if cond < 0 || cond as usize >= arms.len() {
-							self.call_stack.push(Pc { block: default.unwrap(), instr: 0 });
-						} else {
-							self.call_stack.push(Pc { block: arms[cond as usize].unwrap(), instr: 0 });
-						}
+					let cond = self.registers.get(*cond);
 
-						None
+					if cond < 0 || cond as usize >= arms.len() {
+						self.call_stack.push(Pc { block: default.unwrap(), instr: 0 });
 					} else {
-						todo!()
+						self.call_stack.push(Pc { block: arms[cond as usize].unwrap(), instr: 0 });
 					}
+
+					None
 				}
 				LirTerminator::Return => {
 					let returns = self.returns.get(&pc.block.func).unwrap();
@@ -651,13 +1074,13 @@ impl LirInterpreter {
 					if self.call_stack.is_empty() {
 						let return_vals = returns.iter().enumerate().map(|(idx, return_ty)| {
 							match *return_ty {
-								ValType::I32 => {
+								ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 									TypedValue::I32(self.registers.get(Register::return_lo(idx as u32)))
 								}
-								ValType::I64 => {
+								ValType::I64 | ValType::F64 => {
 									TypedValue::I64(self.registers.get_64(DoubleRegister::return_reg(idx as u32)))
 								}
-								_ => todo!(),
+								ty => panic!("return values of type {ty:?} aren't supported"),
 							}
 						}).collect();
 
@@ -678,13 +1101,13 @@ impl LirInterpreter {
 
 						let return_vals = returns.iter().enumerate().map(|(idx, return_ty)| {
 							match *return_ty {
-								ValType::I32 => {
+								ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 									TypedValue::I32(self.registers.get(Register::return_lo(idx as u32)))
 								}
-								ValType::I64 => {
+								ValType::I64 | ValType::F64 => {
 									TypedValue::I64(self.registers.get_64(DoubleRegister::return_reg(idx as u32)))
 								}
-								_ => todo!(),
+								ty => panic!("return values of type {ty:?} aren't supported"),
 							}
 						}).collect();
 
@@ -699,6 +1122,7 @@ impl LirInterpreter {
 						None
 					}
 				}
+				LirTerminator::Trap(kind) => panic!("trap: {kind} in {:?}", pc.block),
 			}
 		} else {
 			let instr = block.body[pc.instr].clone();
@@ -1,11 +1,11 @@
 pub mod interp;
 
-use std::{ops::RangeInclusive, fmt, collections::{HashSet, HashMap}};
+use std::{ops::{Range, RangeInclusive}, fmt, collections::{BTreeSet, HashMap}};
 
 use datapack_common::functions::command_components::{ScoreHolder, Objective};
 use wasmparser::ValType;
 
-use crate::ssa::{BlockId, Memory, interp::TypedValue, Table, lir_emitter::RegisterWithInfo};
+use crate::ssa::{BlockId, Memory, interp::TypedValue, Table, TrapKind, lir_emitter::RegisterWithInfo};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Half {
@@ -33,6 +33,8 @@ pub enum DoubleRegister {
 	Global(u32),
 	CondTaken,
 	SleepNeeded,
+	/// Values kept by `--overflow-checks` while checking a single instruction
+	OverflowCheck(u32),
 }
 
 impl DoubleRegister {
@@ -124,6 +126,21 @@ impl Register {
 		DoubleRegister::SleepNeeded.lo()
 	}
 
+	/// The left operand of an instruction checked for overflow, from before it ran
+	pub fn overflow_lhs() -> Register {
+		DoubleRegister::OverflowCheck(0).lo()
+	}
+
+	/// The right operand of an instruction checked for overflow
+	pub fn overflow_rhs() -> Register {
+		DoubleRegister::OverflowCheck(1).lo()
+	}
+
+	/// The product of a checked multiplication divided by its left operand
+	pub fn overflow_quot() -> Register {
+		DoubleRegister::OverflowCheck(2).lo()
+	}
+
 	pub fn const_val(v: i32) -> Register {
 		DoubleRegister::Const(v as i64).lo()
 	}
@@ -148,7 +165,7 @@ impl Register {
 	}
 }
 
-const OBJECTIVE_NAME: &str = "reg";
+pub const OBJECTIVE_NAME: &str = "reg";
 
 impl fmt::Display for Register {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -163,6 +180,7 @@ impl fmt::Display for Register {
 			DoubleRegister::Const(val) => write!(f,"%const%{}", val as i32)?,
 			DoubleRegister::CondTaken => write!(f, "%condtaken")?,
 			DoubleRegister::SleepNeeded => write!(f, "%sleepneeded")?,
+			DoubleRegister::OverflowCheck(reg) => write!(f, "%overflow%{reg}")?,
 		}
 
 		write!(f, " {OBJECTIVE_NAME}")
@@ -227,6 +245,40 @@ pub enum LirInstr {
 	Rotl64(DoubleRegister, DoubleRegister, DoubleRegister),
 	Rotr64(DoubleRegister, DoubleRegister, DoubleRegister),
 
+	F32Add(Register, Register, Register),
+	F32Sub(Register, Register, Register),
+	F32Mul(Register, Register, Register),
+	F32Div(Register, Register, Register),
+	F32Min(Register, Register, Register),
+	F32Max(Register, Register, Register),
+
+	F64Add(DoubleRegister, DoubleRegister, DoubleRegister),
+	F64Sub(DoubleRegister, DoubleRegister, DoubleRegister),
+	F64Mul(DoubleRegister, DoubleRegister, DoubleRegister),
+	F64Div(DoubleRegister, DoubleRegister, DoubleRegister),
+	F64Min(DoubleRegister, DoubleRegister, DoubleRegister),
+	F64Max(DoubleRegister, DoubleRegister, DoubleRegister),
+
+	// dst, src
+	F32Sqrt(Register, Register),
+	F32Floor(Register, Register),
+	F32Ceil(Register, Register),
+	F32Trunc(Register, Register),
+	F32Nearest(Register, Register),
+	F64Sqrt(DoubleRegister, DoubleRegister),
+	F64Floor(DoubleRegister, DoubleRegister),
+	F64Ceil(DoubleRegister, DoubleRegister),
+	F64Trunc(DoubleRegister, DoubleRegister),
+	F64Nearest(DoubleRegister, DoubleRegister),
+
+	// dst, src (truncation rounds towards zero and wraps around to fit in 64 bits)
+	F32TruncTo64(DoubleRegister, Register),
+	F64TruncTo64(DoubleRegister, DoubleRegister),
+	F32FromS64(Register, DoubleRegister),
+	F32FromU64(Register, DoubleRegister),
+	F64FromS64(DoubleRegister, DoubleRegister),
+	F64FromU64(DoubleRegister, DoubleRegister),
+
 	Xor(Register, RegisterWithInfo, RegisterWithInfo),
 	And(Register, RegisterWithInfo, RegisterWithInfo),
 	Or(Register, RegisterWithInfo, RegisterWithInfo),
@@ -277,16 +329,16 @@ pub enum LirInstr {
 	GlobalSet(u32, Half, Register),
 	GlobalGet(Register, u32, Half),
 
-	// src, addr
-	Store32(Register, RegisterWithInfo),
-	Store16(Register, RegisterWithInfo),
-	Store8(Register, RegisterWithInfo),
+	// memory, src, addr
+	Store32(u32, Register, RegisterWithInfo),
+	Store16(u32, Register, RegisterWithInfo),
+	Store8(u32, Register, RegisterWithInfo),
 
-	// dst, addr
-	Load64(DoubleRegister, RegisterWithInfo),
-	Load32(Register, RegisterWithInfo),
-	Load16(Register, RegisterWithInfo),
-	Load8(Register, RegisterWithInfo),
+	// memory, dst, addr
+	Load64(u32, DoubleRegister, RegisterWithInfo),
+	Load32(u32, Register, RegisterWithInfo),
+	Load16(u32, Register, RegisterWithInfo),
+	Load8(u32, Register, RegisterWithInfo),
 
 	/// arg, old width (assumes high bits are zero)
 	SignExtend(Register, u32),
@@ -295,6 +347,10 @@ pub enum LirInstr {
 
 	Call { func: u32 },
 	CallIndirect { table: Vec<Option<usize>>, table_entry: Register },
+	/// Traps if the `call_indirect` after it can't call the function at `index` in its table.
+	/// `null` and `mismatched` are the values of `table_entry` it dispatches on that have no function or one of another type,
+	/// and `len` is the length of the table if `table_entry` is the index itself rather than a function reference.
+	CallIndirectCheck { table_entry: Register, index: Register, null: Vec<usize>, mismatched: Vec<usize>, len: Option<usize> },
 
 	Push(Vec<Register>),
 	Pop(Vec<Register>),
@@ -304,25 +360,112 @@ pub enum LirInstr {
 	PushLocalFrame(Vec<ValType>),
 	PopLocalFrame(Vec<ValType>),
 
-	Memset { dest: Register, value: Register, length: Register, result: Register },
+	Memset { memory: u32, dest: Register, value: Register, length: Register, result: Register },
+	/// Like `memmove`, the regions may overlap
+	Memcpy { memory: u32, dest: Register, src: Register, length: Register },
+
+	/// Returns the old size in pages, or -1 if the memory can't grow that much
+	MemoryGrow { memory: u32, pages: Register, result: Register },
+	/// Returns the current size in pages
+	MemorySize(u32, Register),
+
+	/// Loads the function reference in an entry of a table that is written at runtime
+	TableGet { table: u32, dst: Register, index: Register },
+	/// Stores a function reference in an entry of a table that is written at runtime
+	TableSet { table: u32, index: Register, src: Register },
+	/// Returns the old size, or -1 if the table can't grow that much
+	TableGrow { table: u32, init: Register, count: Register, result: Register },
+	TableSize { table: u32, dst: Register },
+	TableInit { table: u32, segment: u32, dst: Register, src: Register, length: Register },
+	ElemDrop(u32),
+
+	// dst/src, base pointer, element index
+	BufLoad8(Register, RegisterWithInfo, RegisterWithInfo),
+	BufLoad16(Register, RegisterWithInfo, RegisterWithInfo),
+	BufStore8(Register, RegisterWithInfo, RegisterWithInfo),
+	BufStore16(Register, RegisterWithInfo, RegisterWithInfo),
 
 	TurtleSetX(Register),
 	TurtleSetY(Register),
 	TurtleSetZ(Register),
 	TurtleSetBlock(Register),
+	TurtleSetBlockNbt { block: Register, nbt: String },
 	TurtleFillBlock { block: Register, x_span: Register, y_span: Register, z_span: Register },
 	TurtleCopyRegion { x_span: Register, y_span: Register, z_span: Register },
 	TurtlePasteRegionMasked { x_span: Register, y_span: Register, z_span: Register },
 	TurtleGetBlock(Register),
 	TurtleCopy,
 	TurtlePaste,
+	/// Sets the direction the turtle faces, in degrees
+	TurtleFace { yaw: Register, pitch: Register },
+	/// Moves the turtle a number of blocks in the direction it faces, or backwards if negative
+	TurtleForward(Register),
+	/// Turns the turtle around the vertical axis, clockwise when seen from above for positive degrees
+	TurtleTurn { degrees: i32 },
+	/// Moves the turtle forward one block at a time until it's in a block that isn't air or it has moved `max_dist` blocks,
+	/// then gets the block there
+	TurtleRaycast { dst: Register, max_dist: Register },
+	/// The light level from 0 to 15 at the turtle's position
+	TurtleLightLevel(Register),
+	/// 1 if the block at the turtle's position is air, or if `solid` is set, 1 if it's neither air nor a fluid
+	TurtleCheckBlock { dst: Register, solid: bool },
+	/// Plays one of the sounds in `mcinterface.h` at the turtle, with the volume and pitch in hundredths
+	PlaySound { sound: Register, volume: i32, pitch: i32 },
+	/// Shows one of the particles in `mcinterface.h` at the turtle
+	Particle { particle: Register, count: i32 },
+	/// Creates a bossbar, or resets it if it already exists, and shows it to all players
+	BossbarCreate(Register),
+	/// Sets the value of a bossbar: bossbar, value
+	BossbarSetValue(Register, Register),
+	/// Sets the maximum value of a bossbar: bossbar, max
+	BossbarSetMax(Register, Register),
+	/// Sets the name of a bossbar to a string from the first memory: bossbar, pointer, length
+	BossbarSetName(Register, Register, Register),
+	/// One coordinate of the player nearest to the turtle, with 0, 1, and 2 for x, y, and z
+	NearestPlayerPos { dst: Register, axis: u8 },
+	/// The number of entities matching one of the selectors in `mcinterface.h`
+	EntityCount { dst: Register, selector: Register },
+	/// The number of ticks the world has existed for
+	GameTime(DoubleRegister),
+	/// A random number from 0 up to but not including a positive bound
+	Random { dst: Register, bound: Register },
 	PrintInt(Register),
+	/// Prints an i64 as a chat message
+	PrintLong(DoubleRegister),
+	/// Prints the bits of an f32 as a chat message
+	PrintF32(Register),
+	/// Prints the bits of an f64 as a chat message
+	PrintF64(DoubleRegister),
 	PutChar(Register),
+	/// Prints a string from the first memory as a chat message: pointer, length
+	PrintStr(Register, Register),
+	/// Prints a printf-style format string from the first memory as a chat message: format pointer, argument pointer
+	PrintFmt(Register, Register),
+	/// Shows a string from the first memory as a title, or above the hotbar if `actionbar` is set
+	Title { ptr: Register, len: Register, actionbar: bool },
 	WasiProcExit(Register),
+	/// Writes the iovecs in the first memory to stdout if the file descriptor is 1 or 2
+	WasiFdWrite { fd: Register, iovs: Register, iovs_len: Register, nwritten: Register, result: Register },
+	/// A Minecraft command to run as-is
+	RawCommand(String),
+	/// Runs a Minecraft command as-is, storing its result, or 1 if it succeeded and 0 otherwise
+	RawCommandStore { dst: Register, cmd: String, success: bool },
+	/// Calls an export of another datapack through a stub, which takes the arguments from the parameter registers
+	/// and leaves the results in the return registers
+	PackCall { namespace: String, export: String, params: Vec<ValType>, returns: Vec<ValType> },
+	/// Copies the snapshot region with the given ID from memory into data storage
+	MemorySnapshot(Register),
+	/// Copies the snapshot region with the given ID from data storage back into memory
+	MemoryRestore(Register),
+	/// An instruction that isn't supported yet, which traps with `TrapKind::Todo` if it's run
 	Todo(&'static str),
 
 	PushReturnAddr(BlockId),
 	PopReturnAddr,
+
+	/// Counts a call to a recursive function, and makes the next command check yield
+	/// once this many calls have been made since the last forced yield
+	RecursionYieldCheck(u32),
 }
 
 impl LirInstr {
@@ -333,6 +476,210 @@ impl LirInstr {
 	pub fn unless_cond_taken(self) -> LirInstr {
 		self.if_cond(Condition::eq_zero(Register::cond_taken()))
 	}
+
+	/// Every register the instruction reads or writes, including both halves of double registers and the constants.
+	/// Calls only name their target, so the parameter and return registers they use aren't included.
+	pub fn registers(&self) -> Vec<Register> {
+		fn double(reg: DoubleRegister) -> [Register; 2] {
+			[reg.lo(), reg.hi()]
+		}
+
+		fn global(idx: u32, half: Half) -> Register {
+			match half {
+				Half::Lo => Register::global_lo(idx),
+				Half::Hi => Register::global_hi(idx),
+			}
+		}
+
+		match self {
+			&LirInstr::Set(a, _) |
+			&LirInstr::Trunc(a, _) |
+			&LirInstr::SignExtend(a, _) |
+			&LirInstr::SignExtend8(a) |
+			&LirInstr::SignExtend16(a) |
+			&LirInstr::LocalSet(_, _, a) |
+			&LirInstr::LocalGet(a, _, _) |
+			&LirInstr::MemorySize(_, a) |
+			&LirInstr::TableSize { dst: a, .. } |
+			&LirInstr::CallIndirect { table_entry: a, .. } |
+			&LirInstr::TurtleSetX(a) |
+			&LirInstr::TurtleSetY(a) |
+			&LirInstr::TurtleSetZ(a) |
+			&LirInstr::TurtleSetBlock(a) |
+			&LirInstr::TurtleSetBlockNbt { block: a, .. } |
+			&LirInstr::TurtleGetBlock(a) |
+			&LirInstr::TurtleForward(a) |
+			&LirInstr::TurtleLightLevel(a) |
+			&LirInstr::TurtleCheckBlock { dst: a, .. } |
+			&LirInstr::PlaySound { sound: a, .. } |
+			&LirInstr::Particle { particle: a, .. } |
+			&LirInstr::BossbarCreate(a) |
+			&LirInstr::NearestPlayerPos { dst: a, .. } |
+			&LirInstr::PrintInt(a) |
+			&LirInstr::PrintF32(a) |
+			&LirInstr::PutChar(a) |
+			&LirInstr::WasiProcExit(a) |
+			&LirInstr::RawCommandStore { dst: a, .. } |
+			&LirInstr::MemorySnapshot(a) |
+			&LirInstr::MemoryRestore(a) => vec![a],
+
+			&LirInstr::Assign(a, b) |
+			&LirInstr::Add(a, b) |
+			&LirInstr::Sub(a, b) |
+			&LirInstr::Mul(a, b) |
+			&LirInstr::F32Sqrt(a, b) |
+			&LirInstr::F32Floor(a, b) |
+			&LirInstr::F32Ceil(a, b) |
+			&LirInstr::F32Trunc(a, b) |
+			&LirInstr::F32Nearest(a, b) |
+			&LirInstr::PopcntAdd(a, b) |
+			&LirInstr::Ctz(a, b) |
+			&LirInstr::Clz(a, b) |
+			&LirInstr::Eqz(a, b) |
+			&LirInstr::Store32(_, a, RegisterWithInfo(b, _)) |
+			&LirInstr::Store16(_, a, RegisterWithInfo(b, _)) |
+			&LirInstr::Store8(_, a, RegisterWithInfo(b, _)) |
+			&LirInstr::Load32(_, a, RegisterWithInfo(b, _)) |
+			&LirInstr::Load16(_, a, RegisterWithInfo(b, _)) |
+			&LirInstr::Load8(_, a, RegisterWithInfo(b, _)) |
+			&LirInstr::MemoryGrow { pages: a, result: b, .. } |
+			&LirInstr::TableGet { dst: a, index: b, .. } |
+			&LirInstr::TableSet { index: a, src: b, .. } |
+			&LirInstr::CallIndirectCheck { table_entry: a, index: b, .. } |
+			&LirInstr::TurtleFace { yaw: a, pitch: b } |
+			&LirInstr::TurtleRaycast { dst: a, max_dist: b } |
+			&LirInstr::BossbarSetValue(a, b) |
+			&LirInstr::BossbarSetMax(a, b) |
+			&LirInstr::EntityCount { dst: a, selector: b } |
+			&LirInstr::Random { dst: a, bound: b } |
+			&LirInstr::PrintStr(a, b) |
+			&LirInstr::PrintFmt(a, b) |
+			&LirInstr::Title { ptr: a, len: b, .. } => vec![a, b],
+
+			&LirInstr::DivS(a, b, c) |
+			&LirInstr::DivU(a, b, c) |
+			&LirInstr::RemS(a, b, c) |
+			&LirInstr::RemU(a, b, c) |
+			&LirInstr::Shl(a, b, c) |
+			&LirInstr::ShrS(a, b, c) |
+			&LirInstr::ShrU(a, b, c) |
+			&LirInstr::Rotl(a, b, c) |
+			&LirInstr::Rotr(a, b, c) |
+			&LirInstr::F32Add(a, b, c) |
+			&LirInstr::F32Sub(a, b, c) |
+			&LirInstr::F32Mul(a, b, c) |
+			&LirInstr::F32Div(a, b, c) |
+			&LirInstr::F32Min(a, b, c) |
+			&LirInstr::F32Max(a, b, c) |
+			&LirInstr::Xor(a, RegisterWithInfo(b, _), RegisterWithInfo(c, _)) |
+			&LirInstr::And(a, RegisterWithInfo(b, _), RegisterWithInfo(c, _)) |
+			&LirInstr::Or(a, RegisterWithInfo(b, _), RegisterWithInfo(c, _)) |
+			&LirInstr::GtS(a, b, c) |
+			&LirInstr::GtU(a, b, c) |
+			&LirInstr::GeS(a, b, c) |
+			&LirInstr::GeU(a, b, c) |
+			&LirInstr::LtS(a, b, c) |
+			&LirInstr::LtU(a, b, c) |
+			&LirInstr::LeS(a, b, c) |
+			&LirInstr::LeU(a, b, c) |
+			&LirInstr::Eq(a, b, c) |
+			&LirInstr::Ne(a, b, c) |
+			&LirInstr::Memcpy { dest: a, src: b, length: c, .. } |
+			&LirInstr::TableGrow { init: a, count: b, result: c, .. } |
+			&LirInstr::TableInit { dst: a, src: b, length: c, .. } |
+			&LirInstr::BufLoad8(a, RegisterWithInfo(b, _), RegisterWithInfo(c, _)) |
+			&LirInstr::BufLoad16(a, RegisterWithInfo(b, _), RegisterWithInfo(c, _)) |
+			&LirInstr::BufStore8(a, RegisterWithInfo(b, _), RegisterWithInfo(c, _)) |
+			&LirInstr::BufStore16(a, RegisterWithInfo(b, _), RegisterWithInfo(c, _)) |
+			&LirInstr::TurtleCopyRegion { x_span: a, y_span: b, z_span: c } |
+			&LirInstr::TurtlePasteRegionMasked { x_span: a, y_span: b, z_span: c } |
+			&LirInstr::BossbarSetName(a, b, c) => vec![a, b, c],
+
+			&LirInstr::Select { dst: a, true_reg: b, false_reg: c, cond: d } |
+			&LirInstr::Memset { dest: a, value: b, length: c, result: d, .. } |
+			&LirInstr::TurtleFillBlock { block: a, x_span: b, y_span: c, z_span: d } => vec![a, b, c, d],
+
+			&LirInstr::WasiFdWrite { fd, iovs, iovs_len, nwritten, result } => vec![fd, iovs, iovs_len, nwritten, result],
+
+			&LirInstr::SignExtend32(a) |
+			&LirInstr::GameTime(a) |
+			&LirInstr::PrintLong(a) |
+			&LirInstr::PrintF64(a) => double(a).to_vec(),
+
+			&LirInstr::F64Sqrt(a, b) |
+			&LirInstr::F64Floor(a, b) |
+			&LirInstr::F64Ceil(a, b) |
+			&LirInstr::F64Trunc(a, b) |
+			&LirInstr::F64Nearest(a, b) |
+			&LirInstr::F64TruncTo64(a, b) |
+			&LirInstr::F64FromS64(a, b) |
+			&LirInstr::F64FromU64(a, b) |
+			&LirInstr::Ctz64(a, b) |
+			&LirInstr::Clz64(a, b) => [double(a), double(b)].concat(),
+
+			&LirInstr::Load64(_, a, RegisterWithInfo(b, _)) => [&double(a)[..], &[b]].concat(),
+			&LirInstr::F32TruncTo64(a, b) => [&double(a)[..], &[b]].concat(),
+			&LirInstr::F32FromS64(a, b) |
+			&LirInstr::F32FromU64(a, b) |
+			&LirInstr::Eqz64(a, b) => [&[a][..], &double(b)].concat(),
+			&LirInstr::MulTo64(a, b, c) => [&double(a)[..], &[b, c]].concat(),
+
+			&LirInstr::Add64(a, b, c) |
+			&LirInstr::Sub64(a, b, c) |
+			&LirInstr::DivS64(a, b, c) |
+			&LirInstr::DivU64(a, b, c) |
+			&LirInstr::RemS64(a, b, c) |
+			&LirInstr::RemU64(a, b, c) |
+			&LirInstr::Shl64(a, b, c) |
+			&LirInstr::ShrS64(a, b, c) |
+			&LirInstr::ShrU64(a, b, c) |
+			&LirInstr::Rotl64(a, b, c) |
+			&LirInstr::Rotr64(a, b, c) |
+			&LirInstr::F64Add(a, b, c) |
+			&LirInstr::F64Sub(a, b, c) |
+			&LirInstr::F64Mul(a, b, c) |
+			&LirInstr::F64Div(a, b, c) |
+			&LirInstr::F64Min(a, b, c) |
+			&LirInstr::F64Max(a, b, c) => [double(a), double(b), double(c)].concat(),
+
+			&LirInstr::GtS64(a, b, c) |
+			&LirInstr::GtU64(a, b, c) |
+			&LirInstr::GeS64(a, b, c) |
+			&LirInstr::GeU64(a, b, c) |
+			&LirInstr::LtS64(a, b, c) |
+			&LirInstr::LtU64(a, b, c) |
+			&LirInstr::LeS64(a, b, c) |
+			&LirInstr::LeU64(a, b, c) |
+			&LirInstr::Eq64(a, b, c) |
+			&LirInstr::Ne64(a, b, c) => [&[a][..], &double(b), &double(c)].concat(),
+
+			&LirInstr::GlobalSet(idx, half, a) |
+			&LirInstr::GlobalGet(a, idx, half) => vec![a, global(idx, half)],
+
+			LirInstr::Push(regs) |
+			LirInstr::Pop(regs) => regs.clone(),
+
+			LirInstr::IfCond { cond: Condition::Matches(reg, _) | Condition::NotMatches(reg, _), instr } => {
+				let mut regs = instr.registers();
+				regs.push(*reg);
+				regs
+			}
+
+			LirInstr::Call { .. } |
+			LirInstr::PackCall { .. } |
+			LirInstr::PushLocalFrame(_) |
+			LirInstr::PopLocalFrame(_) |
+			LirInstr::ElemDrop(_) |
+			LirInstr::TurtleCopy |
+			LirInstr::TurtlePaste |
+			LirInstr::TurtleTurn { .. } |
+			LirInstr::RawCommand(_) |
+			LirInstr::Todo(_) |
+			LirInstr::PushReturnAddr(_) |
+			LirInstr::PopReturnAddr |
+			LirInstr::RecursionYieldCheck(_) => Vec::new(),
+		}
+	}
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -351,6 +698,8 @@ pub enum LirTerminator {
 	JumpTable { arms: Vec<Option<BlockId>>, default: Option<BlockId>, cond: Register },
 	Return,
 	ReturnToSaved,
+	/// Stops the program, reporting why
+	Trap(TrapKind),
 }
 
 pub struct LirBasicBlock {
@@ -360,7 +709,11 @@ pub struct LirBasicBlock {
 
 pub struct LirFunction {
 	pub code: Vec<(BlockId, LirBasicBlock)>,
+	pub params: Box<[ValType]>,
 	pub returns: Box<[ValType]>,
+	/// The wasm code each block was lowered from, as offsets into the code section.
+	/// Blocks split off while lowering share the ranges of the SSA block they came from.
+	pub code_ranges: HashMap<BlockId, Vec<Range<usize>>>,
 }
 
 impl LirFunction {
@@ -373,9 +726,24 @@ pub struct LirProgram {
 	pub globals: Vec<TypedValue>,
 	pub memory: Vec<Memory>,
 	pub tables: Vec<Table>,
+	pub elem_segments: Vec<Vec<Option<usize>>>,
 	pub code: Vec<LirFunction>,
-	pub constants: HashSet<i32>,
+	pub constants: BTreeSet<i32>,
 	pub exports: HashMap<String, BlockId>,
+	/// The function from the start section, which is run once the program is initialized.
+	pub start: Option<BlockId>,
+	/// Names of functions from the wasm `name` section, used to make the output easier to debug.
+	pub func_names: HashMap<usize, String>,
+	/// The regions of the first memory that `MemorySnapshot` and `MemoryRestore` copy, indexed by their ID.
+	pub snapshot_regions: Vec<crate::SnapshotRegion>,
+	/// The block state for each block ID used by the turtle intrinsics.
+	pub block_palette: Vec<String>,
+	/// Where memory and the turtle are placed in the world.
+	pub layout: crate::WorldLayout,
+	/// How the pack emitter writes jumps between blocks. The LIR itself is the same in every mode.
+	pub jump_mode: crate::JumpMode,
+	/// What integer division and remainder by zero do, see `SsaProgram::div_by_zero`.
+	pub div_by_zero: crate::DivByZero,
 }
 
 impl LirProgram {
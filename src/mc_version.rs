@@ -0,0 +1,109 @@
+//! The Minecraft versions datapacks can be written for, and what changed between them.
+
+use std::fmt;
+
+/// A release of Minecraft: Java Edition, like `1.20.4`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct McVersion {
+	pub minor: u32,
+	pub patch: u32,
+}
+
+impl McVersion {
+	/// The oldest version supported, which is the first one with marker entities
+	pub const OLDEST: McVersion = McVersion::new(17, 0);
+
+	/// The newest version supported
+	pub const NEWEST: McVersion = McVersion::new(21, 4);
+
+	pub const fn new(minor: u32, patch: u32) -> Self {
+		McVersion { minor, patch }
+	}
+
+	/// Parses a version like `1.20` or `1.20.4`.
+	pub fn parse(s: &str) -> Result<Self, String> {
+		let err = || format!("expected a version like 1.20.4, got {s:?}");
+
+		let rest = s.strip_prefix("1.").ok_or_else(err)?;
+		let (minor, patch) = rest.split_once('.').unwrap_or((rest, "0"));
+		let minor = minor.parse::<u32>().map_err(|_| err())?;
+		let patch = patch.parse::<u32>().map_err(|_| err())?;
+
+		let version = McVersion::new(minor, patch);
+		if version < McVersion::OLDEST || version > McVersion::NEWEST {
+			return Err(format!("unsupported version {version}, expected {} to {}", McVersion::OLDEST, McVersion::NEWEST));
+		}
+
+		Ok(version)
+	}
+
+	/// The `pack_format` in `pack.mcmeta` that this version expects.
+	pub fn pack_format(self) -> u32 {
+		match (self.minor, self.patch) {
+			(17, _) => 7,
+			(18, 0..=1) => 8,
+			(18, _) => 9,
+			(19, 0..=3) => 10,
+			(19, _) => 12,
+			(20, 0..=1) => 15,
+			(20, 2) => 18,
+			(20, 3..=4) => 26,
+			(20, _) => 41,
+			(21, 0..=1) => 48,
+			(21, 2..=3) => 57,
+			(21, _) => 61,
+			_ => unreachable!("{self}"),
+		}
+	}
+
+	/// The folder in a namespace that functions are placed in, which lost its plural in 1.21.
+	pub fn function_folder(self) -> &'static str {
+		if self >= McVersion::new(21, 0) { "function" } else { "functions" }
+	}
+
+	/// The folder in a namespace that predicates are placed in, which lost its plural in 1.21.
+	pub fn predicate_folder(self) -> &'static str {
+		if self >= McVersion::new(21, 0) { "predicate" } else { "predicates" }
+	}
+
+	/// The folder in a namespace that function tags are placed in, which lost its plural in 1.21.
+	pub fn function_tag_folder(self) -> &'static str {
+		if self >= McVersion::new(21, 0) { "tags/function" } else { "tags/functions" }
+	}
+
+	/// Function macros and `function ... with` were added in 1.20.2.
+	pub fn has_macros(self) -> bool {
+		self >= McVersion::new(20, 2)
+	}
+
+	/// `return run` was added in 1.20.2.
+	pub fn has_return_run(self) -> bool {
+		self >= McVersion::new(20, 2)
+	}
+
+	/// The `random` command was added in 1.20.2.
+	pub fn has_random_command(self) -> bool {
+		self >= McVersion::new(20, 2)
+	}
+
+	/// Items store their data in components instead of a `tag` compound since 1.20.5.
+	pub fn has_item_components(self) -> bool {
+		self >= McVersion::new(20, 5)
+	}
+}
+
+impl Default for McVersion {
+	fn default() -> Self {
+		McVersion::new(19, 4)
+	}
+}
+
+impl fmt::Display for McVersion {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.patch == 0 {
+			write!(f, "1.{}", self.minor)
+		} else {
+			write!(f, "1.{}.{}", self.minor, self.patch)
+		}
+	}
+}
@@ -0,0 +1,235 @@
+//! An experimental backend writing the program as a Bedrock Edition behavior pack instead of a datapack.
+//!
+//! Bedrock's commands are close to Java Edition's, but not the same: functions are named by their path
+//! instead of `namespace:path`, `tellraw` takes a `rawtext` list, and there is no `data`, `execute store`,
+//! `return`, `schedule function`, predicates, or marker entities.
+//! Commands that can't be translated are written as comments, so the rest of the pack still loads,
+//! and are counted so they can be reported.
+
+use datapack_common::functions::Function;
+
+use super::{PackBackend, short_names::ShortNames};
+use crate::mc_version::McVersion;
+
+/// The engine version where `execute` got the same subcommand syntax as Java Edition.
+pub const NEW_EXECUTE_ENGINE_VERSION: (u32, u32, u32) = (1, 19, 50);
+
+/// The prefix of the comments that commands with no Bedrock equivalent are written as.
+pub static UNSUPPORTED_LINE_PREFIX: &str = "# unsupported on Bedrock: ";
+
+/// `execute if` and `execute unless` conditions Bedrock has.
+static CONDITIONS: [&str; 4] = ["block", "blocks", "entity", "score"];
+
+/// Selector arguments Bedrock doesn't have.
+static UNSUPPORTED_SELECTOR_ARGS: [&str; 4] = ["sort=", "nbt=", "predicate=", "type=minecraft:marker"];
+
+pub struct BehaviorPack {
+	pub name: String,
+	pub description: String,
+	/// The version of Minecraft: Java Edition whose lines are translated, which can't have function macros.
+	pub version: McVersion,
+	/// The oldest Bedrock engine version the pack can be loaded in, written to its manifest.
+	pub min_engine_version: (u32, u32, u32),
+	/// Whether the engine or server the pack is used with has `scoreboard players operation`,
+	/// which some third-party servers leave out.
+	pub score_operations: bool,
+	/// Whether internal holders and objectives are written with short names.
+	pub short_names: bool,
+}
+
+impl BehaviorPack {
+	pub fn new(name: &str, version: McVersion) -> Self {
+		BehaviorPack {
+			name: name.to_string(),
+			description: "Autogenerated by wasmcraft".to_string(),
+			version,
+			min_engine_version: NEW_EXECUTE_ENGINE_VERSION,
+			score_operations: true,
+			short_names: false,
+		}
+	}
+
+	/// The contents of `manifest.json`. The UUIDs are derived from the pack's name,
+	/// so writing the pack again replaces the old one in a world instead of adding a second copy.
+	fn manifest_contents(&self) -> String {
+		let (major, minor, patch) = self.min_engine_version;
+		let json = serde_json::json!({
+			"format_version": 2,
+			"header": {
+				"name": self.name,
+				"description": self.description,
+				"uuid": name_uuid(&self.name, "header"),
+				"version": [1, 0, 0],
+				"min_engine_version": [major, minor, patch],
+			},
+			"modules": [{
+				"type": "data",
+				"uuid": name_uuid(&self.name, "data"),
+				"version": [1, 0, 0],
+			}],
+		});
+		serde_json::to_string_pretty(&json).unwrap()
+	}
+
+	/// The path of a function relative to the root of the behavior pack.
+	pub fn function_path(&self, namespace: &str, name: &str) -> String {
+		format!("functions/{namespace}/{name}.mcfunction")
+	}
+
+	/// Translates a command to Bedrock's dialect, or returns `None` if Bedrock has no equivalent.
+	pub fn translate(&self, cmd: &str) -> Option<String> {
+		if cmd.is_empty() || cmd.starts_with('#') {
+			return Some(cmd.to_string());
+		}
+
+		let (head, rest) = cmd.split_once(' ').unwrap_or((cmd, ""));
+		match head {
+			"function" => Some(format!("function {}", function_name(rest)?)),
+			"execute" => self.translate_execute(rest),
+			"scoreboard" if rest.starts_with("players operation ") && !self.score_operations => None,
+			"scoreboard" | "say" => Some(cmd.to_string()),
+			"tellraw" => translate_tellraw(rest),
+			"setblock" | "fill" if !cmd.contains(|c: char| c == '[' || c == '{') => Some(cmd.to_string()),
+			"kill" | "tag" | "tp" if rest.starts_with('@') => translate_selectors(cmd),
+			_ => None,
+		}
+	}
+
+	fn translate_execute(&self, rest: &str) -> Option<String> {
+		if self.min_engine_version < NEW_EXECUTE_ENGINE_VERSION {
+			return None;
+		}
+
+		let (subcommands, run) = match rest.split_once(" run ") {
+			Some((subcommands, run)) => (subcommands, Some(run)),
+			None => (rest, None),
+		};
+
+		let tokens = subcommands.split(' ').collect::<Vec<_>>();
+		for (idx, token) in tokens.iter().enumerate() {
+			match *token {
+				"store" | "on" | "summon" => return None,
+				"if" | "unless" if !CONDITIONS.contains(tokens.get(idx + 1)?) => return None,
+				_ => {}
+			}
+		}
+		let subcommands = translate_selectors(subcommands)?;
+
+		match run {
+			Some(run) => Some(format!("execute {subcommands} run {}", self.translate(run)?)),
+			None => Some(format!("execute {subcommands}")),
+		}
+	}
+
+	/// The lines written for a function, with the number of them that couldn't be translated,
+	/// or `None` if the function isn't written.
+	pub fn written_lines(&self, func: &Function) -> Option<(Vec<String>, usize)> {
+		let mut unsupported = 0;
+		let lines = super::written_lines(func, self.version)?.into_iter().map(|line| {
+			// Without the new `execute`, the commands after a trap can't be stopped, so they are written ungated
+			let line = if self.min_engine_version < NEW_EXECUTE_ENGINE_VERSION { super::strip_trap_gate(line) } else { line };
+			self.translate(&line).unwrap_or_else(|| {
+				unsupported += 1;
+				format!("{UNSUPPORTED_LINE_PREFIX}{line}")
+			})
+		}).collect();
+		Some((lines, unsupported))
+	}
+
+	/// The number of commands in a datapack that can't be translated, and the functions they are in.
+	pub fn unsupported_commands(&self, funcs: &[Function]) -> (usize, usize) {
+		funcs.iter()
+			.filter_map(|func| Some(self.written_lines(func)?.1))
+			.filter(|count| *count > 0)
+			.fold((0, 0), |(total, in_funcs), count| (total + count, in_funcs + 1))
+	}
+}
+
+impl PackBackend for BehaviorPack {
+	fn files(&self, funcs: &[Function]) -> (Vec<(String, String)>, ShortNames) {
+		let mut files = vec![("manifest.json".to_string(), self.manifest_contents())];
+		for func in funcs.iter() {
+			if let Some((lines, _)) = self.written_lines(func) {
+				files.push((self.function_path(&func.id.namespace, &func.id.path), lines.join("\n")));
+			}
+		}
+
+		let names = if self.short_names {
+			super::shorten_functions(&mut files)
+		} else {
+			ShortNames::default()
+		};
+
+		(files, names)
+	}
+
+	fn content_folder(&self) -> &'static str {
+		"functions"
+	}
+}
+
+/// The name of a function in a behavior pack, which is its path under `functions`.
+fn function_name(id: &str) -> Option<String> {
+	if id.contains(' ') {
+		return None;
+	}
+
+	let (namespace, path) = id.split_once(':')?;
+	Some(format!("{namespace}/{path}"))
+}
+
+/// Rewrites the selectors in a command for Bedrock, where `limit` is called `c`.
+fn translate_selectors(cmd: &str) -> Option<String> {
+	if UNSUPPORTED_SELECTOR_ARGS.iter().any(|arg| cmd.contains(arg)) {
+		return None;
+	}
+	Some(cmd.replace("limit=", "c="))
+}
+
+/// Rewrites `tellraw` with a Java text component to take a `rawtext` list,
+/// which only has the plain text, score, and translated components.
+fn translate_tellraw(rest: &str) -> Option<String> {
+	let (target, text) = rest.split_once(' ')?;
+	let components = match serde_json::from_str::<serde_json::Value>(text).ok()? {
+		serde_json::Value::Array(components) => components,
+		component => vec![component],
+	};
+
+	let mut rawtext = Vec::new();
+	for component in components {
+		let mut raw = serde_json::Map::new();
+		match component {
+			serde_json::Value::String(text) => {
+				raw.insert("text".to_string(), text.into());
+			}
+			serde_json::Value::Object(component) => {
+				for key in ["text", "score", "translate", "with"] {
+					if let Some(value) = component.get(key) {
+						raw.insert(key.to_string(), value.clone());
+					}
+				}
+				if raw.is_empty() {
+					return None;
+				}
+			}
+			_ => return None,
+		}
+		rawtext.push(serde_json::Value::Object(raw));
+	}
+
+	Some(format!("tellraw {target} {}", serde_json::json!({ "rawtext": rawtext })))
+}
+
+/// A UUID made from a hash of the pack's name and what it identifies.
+fn name_uuid(name: &str, kind: &str) -> String {
+	// FNV-1a, since the standard library's hasher isn't guaranteed to be the same between releases
+	let hash = |seed: u64| format!("{name}/{kind}").bytes().fold(0xcbf29ce484222325 ^ seed, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3));
+	let bits = (hash(0) as u128) << 64 | hash(1) as u128;
+
+	// Marked as a version 4 variant 1 UUID, like a random one would be
+	let bits = (bits & !(0xf << 76)) | (0x4 << 76);
+	let bits = (bits & !(0x3 << 62)) | (0x2 << 62);
+
+	let hex = format!("{bits:032x}");
+	format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
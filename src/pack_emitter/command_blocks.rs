@@ -0,0 +1,153 @@
+//! Writes entry points of a program as chains of command blocks, for worlds where datapacks can't be used.
+//!
+//! Command blocks can't call functions without a datapack, so every function an entry point calls is inlined into its chain.
+//! Conditional calls are inlined behind a guard score holding whether the call's conditions passed,
+//! since the called function can change what the conditions test.
+//! Entry points that loop, recurse, wait for a later tick, or rely on function macros can't be inlined and are left out.
+
+use std::{collections::HashMap, fmt::Write};
+
+use datapack_common::functions::Function;
+
+use crate::mc_version::McVersion;
+
+/// The objective the guard holders are in, which is created by `wasmrunner:init`.
+static GUARD_OBJECTIVE: &str = "reg";
+
+/// Subcommands that change where or as whom a function runs, or use its result, which inlining would lose.
+static CONTEXT_SUBCOMMANDS: [&str; 11] = ["as", "at", "positioned", "rotated", "facing", "align", "anchored", "in", "on", "summon", "store"];
+
+pub struct CommandBlockScript {
+	/// A `setblock` command for each command block, in the order they should be placed.
+	pub commands: Vec<String>,
+	/// The entry points that were placed, with their chain's length and the position of the block that starts them.
+	pub chains: Vec<(String, usize, (i32, i32, i32))>,
+	/// The entry points that couldn't be placed, with the reason why.
+	pub skipped: Vec<(String, String)>,
+}
+
+impl CommandBlockScript {
+	pub fn to_script(&self) -> String {
+		let mut s = String::new();
+		for cmd in self.commands.iter() {
+			writeln!(s, "{cmd}").unwrap();
+		}
+		s
+	}
+}
+
+/// Builds a chain of command blocks for each entry point, one row apart along the z axis starting at `origin`.
+/// Each chain runs along the positive x axis and starts with an impulse block, which needs to be powered to run it.
+pub fn build(datapack: &[Function], entries: &[String], version: McVersion, origin: (i32, i32, i32), max_len: usize) -> CommandBlockScript {
+	let funcs = datapack.iter()
+		.filter_map(|func| Some((func.id.to_string(), super::written_lines(func, version)?)))
+		.map(|(id, lines)| (id, lines.into_iter().filter(|line| !line.is_empty() && !line.starts_with('#')).collect::<Vec<_>>()))
+		.collect::<HashMap<_, _>>();
+
+	let mut script = CommandBlockScript { commands: Vec::new(), chains: Vec::new(), skipped: Vec::new() };
+
+	for entry in entries.iter() {
+		let mut inliner = Inliner { funcs: &funcs, max_len, stack: Vec::new(), guards: 0, code: Vec::new() };
+		if let Err(reason) = inliner.inline(entry, &[]) {
+			script.skipped.push((entry.clone(), reason));
+			continue;
+		}
+
+		let (x, y, z) = origin;
+		let z = z + 2 * script.chains.len() as i32;
+		for (idx, cmd) in inliner.code.iter().enumerate() {
+			let cmd = cmd.replace('\\', "\\\\").replace('"', "\\\"");
+			let block = if idx == 0 {
+				format!("command_block[facing=east]{{Command:\"{cmd}\"}}")
+			} else {
+				format!("chain_command_block[facing=east]{{auto:1b,Command:\"{cmd}\"}}")
+			};
+			script.commands.push(format!("setblock {} {y} {z} {block}", x + idx as i32));
+		}
+		script.chains.push((entry.clone(), inliner.code.len(), (x, y, z)));
+	}
+
+	script
+}
+
+struct Inliner<'a> {
+	funcs: &'a HashMap<String, Vec<String>>,
+	max_len: usize,
+	/// The functions currently being inlined, to catch recursion
+	stack: Vec<&'a str>,
+	guards: usize,
+	code: Vec<String>,
+}
+
+impl<'a> Inliner<'a> {
+	/// Inlines a function, running each of its commands only if all of `guards` are 1.
+	fn inline(&mut self, id: &str, guards: &[String]) -> Result<(), String> {
+		let (id, lines) = self.funcs.get_key_value(id).ok_or_else(|| format!("calls {id}, which doesn't exist or only works with function macros"))?;
+		if self.stack.contains(&id.as_str()) {
+			return Err(format!("{id} loops or is recursive"));
+		}
+		self.stack.push(id);
+
+		for line in lines.iter() {
+			if line.contains("schedule ") {
+				return Err(format!("{id} continues on a later tick"));
+			}
+
+			match split_call(line) {
+				Some((None, callee)) => self.inline(callee, guards)?,
+				Some((Some(conds), callee)) => {
+					if conds.split(' ').any(|token| CONTEXT_SUBCOMMANDS.contains(&token)) {
+						return Err(format!("{id} calls {callee} with a different context"));
+					}
+
+					let guard = format!("%%inline_guard{} {GUARD_OBJECTIVE}", self.guards);
+					self.guards += 1;
+					self.push(guards, format!("scoreboard players set {guard} 0"))?;
+					self.push(guards, format!("execute store success score {guard} {conds}"))?;
+
+					let mut inner = guards.to_vec();
+					inner.push(guard);
+					self.inline(callee, &inner)?;
+				}
+				None => self.push(guards, line.clone())?,
+			}
+		}
+
+		self.stack.pop();
+		Ok(())
+	}
+
+	fn push(&mut self, guards: &[String], line: String) -> Result<(), String> {
+		if self.code.len() == self.max_len {
+			return Err(format!("needs more than {} command blocks", self.max_len));
+		}
+
+		if guards.is_empty() {
+			self.code.push(line);
+		} else {
+			let conds = guards.iter().map(|guard| format!("if score {guard} matches 1")).collect::<Vec<_>>().join(" ");
+			match line.strip_prefix("execute ") {
+				Some(rest) => self.code.push(format!("execute {conds} {rest}")),
+				None => self.code.push(format!("execute {conds} run {line}")),
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Splits a command calling a function into the `execute` subcommands it's called with, if any, and the function.
+fn split_call(line: &str) -> Option<(Option<&str>, &str)> {
+	let (conds, callee) = match line.strip_prefix("function ") {
+		Some(callee) => (None, callee),
+		None => {
+			let (prefix, callee) = line.rsplit_once(" run function ")?;
+			(Some(prefix.strip_prefix("execute ")?), callee)
+		}
+	};
+
+	if callee.contains(' ') {
+		None
+	} else {
+		Some((conds, callee))
+	}
+}
@@ -0,0 +1,290 @@
+//! Shared by the memory backends that keep memory outside of the world and access it with function macros.
+//!
+//! Code is generated for jukeboxes as usual and then rewritten:
+//! accesses to a block at a known position become accesses to the word it holds,
+//! and accesses relative to the memory pointer call the backend's `load_word` and `store_word` macro functions
+//! with the memory, page and word `intrinsic:setptr` stored in `wasm:scratch mem`.
+
+use std::collections::BTreeSet;
+
+use datapack_common::functions::Function;
+
+use super::{parse_function, macro_line, MEM_PTR_UUID, PAGE_SPAN_X, PAGE_SPAN_Y, PAGE_SPAN_Z, MEMORY_PAGE_BLOCKS};
+
+/// Marks a line written by a macro memory backend, which is written as it is.
+/// These are comments until then, since the command parser doesn't understand calls with macro arguments.
+pub(super) static MEMORY_LINE_PREFIX: &str = "# !MEMORY: ";
+
+pub(super) fn memory_line(line: &str) -> String {
+	format!("{MEMORY_LINE_PREFIX}{line}")
+}
+
+/// Intrinsics that are replaced along with the functions they call,
+/// because they move the memory pointer through the world themselves.
+static REPLACED_INTRINSICS: [&str; 4] = ["intrinsic:setptr", "intrinsic:doubleword/load_aligned", "intrinsic:memcpy", "intrinsic:memset"];
+
+/// The path of a jukebox's word, which is rewritten.
+static JUKEBOX_WORD_PATH: &str = "RecordItem.tag.Memory";
+
+/// A word of memory, either at a known address or given by the arguments of a macro function.
+pub(super) struct Word {
+	pub memory: String,
+	pub page: String,
+	pub word: String,
+}
+
+impl Word {
+	fn known(memory: i32, page: i32, word: i32) -> Self {
+		Word { memory: memory.to_string(), page: page.to_string(), word: word.to_string() }
+	}
+
+	/// The word given by the `memory`, `page` and `word` macro arguments.
+	fn from_macro() -> Self {
+		Word { memory: "$(memory)".to_string(), page: "$(page)".to_string(), word: "$(word)".to_string() }
+	}
+}
+
+/// How a macro memory backend keeps each word.
+pub(super) trait WordStore {
+	/// The folder in the `intrinsic` namespace its macro functions are placed in.
+	fn name(&self) -> &'static str;
+
+	/// A command whose result is the word.
+	fn get(&self, word: &Word) -> String;
+
+	/// The start of an `execute` command storing its result in the word, up to and including `run`.
+	fn store_result(&self, word: &Word) -> String;
+
+	/// A command setting the word to a constant.
+	fn set(&self, word: &Word, value: i32) -> String;
+}
+
+/// Where a jukebox accessed by a command is.
+enum Location {
+	Known(Word),
+	/// At the memory pointer, which `intrinsic:setptr` has stored the word of
+	Pointer,
+}
+
+/// The word held by the jukebox at a position, or `None` if it isn't part of memory.
+fn known_word(origin: (i32, i32, i32), (x, y, z): (i32, i32, i32)) -> Option<Word> {
+	let (ox, oy, oz) = origin;
+	let (x, y, z) = (x - ox, y - oy, z - oz);
+	if x < 0 || !(0..PAGE_SPAN_Y).contains(&y) || z < 0 {
+		return None;
+	}
+
+	let memory = z / PAGE_SPAN_Z;
+	let z = z % PAGE_SPAN_Z;
+	let page = x / PAGE_SPAN_X;
+	let word = ((x % PAGE_SPAN_X) * PAGE_SPAN_Y + y) * PAGE_SPAN_Z + z;
+	Some(Word::known(memory, page, word))
+}
+
+/// Finds the `block X Y Z RecordItem.tag.Memory` a command accesses,
+/// returning the command before and after it along with where the block is.
+fn find_block(cmd: &str, origin: (i32, i32, i32)) -> Option<(&str, &str, Location)> {
+	let start = cmd.find("block ")?;
+	let after = &cmd[start + "block ".len()..];
+	let parts = after.splitn(4, ' ').collect::<Vec<_>>();
+	let (x, y, z, path) = match parts.as_slice() {
+		[x, y, z, path] => (x, y, z, path),
+		_ => return None,
+	};
+	let rest = path.strip_prefix(JUKEBOX_WORD_PATH)?;
+
+	let location = if (*x, *y, *z) == ("~", "~", "~") {
+		Location::Pointer
+	} else {
+		Location::Known(known_word(origin, (x.parse().ok()?, y.parse().ok()?, z.parse().ok()?))?)
+	};
+
+	Some((&cmd[..start], rest, location))
+}
+
+/// Rewrites a command that accesses memory in jukeboxes, or returns `None` if it doesn't access memory in a way that can be rewritten.
+fn adapt_command(cmd: &str, origin: (i32, i32, i32), store: &dyn WordStore) -> Option<Vec<String>> {
+	let (before, after, location) = find_block(cmd, origin)?;
+
+	// Accesses at the memory pointer are run at it, which isn't needed anymore
+	let ptr_prefix = format!("execute at {MEM_PTR_UUID} ");
+	let before = match location {
+		Location::Pointer => before.replacen(&ptr_prefix, "", 1),
+		Location::Known(_) => before.to_string(),
+	};
+	let name = store.name();
+
+	let line = if let (Some(before), Some(Ok(_))) = (before.strip_suffix("data get "), after.strip_prefix(' ').map(str::parse::<f64>)) {
+		// `... run data get block X Y Z RecordItem.tag.Memory 1`
+		let get = match &location {
+			Location::Known(word) => store.get(word),
+			Location::Pointer => format!("function intrinsic:{name}/load_word with storage wasm:scratch mem"),
+		};
+		format!("{before}{get}")
+	} else if let (Some(prefix), Some((_, value))) = (before.strip_suffix("execute store result "), after.strip_prefix(" int ").and_then(|a| a.split_once(" run "))) {
+		// `execute store result block X Y Z RecordItem.tag.Memory int 1 run ...`
+		match &location {
+			Location::Known(word) => format!("{prefix}{} {value}", store.store_result(word)),
+			Location::Pointer => return Some(vec![
+				memory_line(&format!("{prefix}execute store result storage wasm:scratch mem.value int 1 run {value}")),
+				memory_line(&format!("{prefix}function intrinsic:{name}/store_word with storage wasm:scratch mem")),
+			]),
+		}
+	} else if let (Location::Known(word), "data modify ") = (&location, before.as_str()) {
+		if let Some(value) = after.strip_prefix(" set value ") {
+			store.set(word, value.parse().ok()?)
+		} else {
+			let source = after.strip_prefix(" set from storage ")?;
+			format!("{} data get storage {source} 1", store.store_result(word))
+		}
+	} else if let (Location::Known(word), Some(dest), "") = (&location, before.strip_prefix("data modify storage ").and_then(|b| b.strip_suffix(" set from ")), after) {
+		format!("execute store result storage {dest} int 1 run {}", store.get(word))
+	} else {
+		return None;
+	};
+
+	Some(vec![memory_line(&line)])
+}
+
+/// Creates the intrinsics that replace the ones in `REPLACED_INTRINSICS`, and the macro functions they use.
+/// The replacements are written for jukeboxes like the rest, so they're rewritten along with everything else.
+fn create_intrinsics(origin: (i32, i32, i32), store: &dyn WordStore) -> Vec<Function> {
+	let oz = origin.2;
+	let name = store.name();
+
+	let setptr = vec![
+		"scoreboard players operation %%ptr reg = %ptr reg".to_string(),
+		"scoreboard players operation %%ptr reg /= %%4 reg".to_string(),
+		"scoreboard players operation %%ptr_page reg = %%ptr reg".to_string(),
+		format!("scoreboard players operation %%ptr_page reg /= %%{MEMORY_PAGE_BLOCKS} reg"),
+		format!("scoreboard players operation %%ptr reg %= %%{MEMORY_PAGE_BLOCKS} reg"),
+		"execute store result storage wasm:scratch mem.page int 1 run scoreboard players get %%ptr_page reg".to_string(),
+		"execute store result storage wasm:scratch mem.word int 1 run scoreboard players get %%ptr reg".to_string(),
+		// Memories other than the first are selected by moving the z coordinate, like with jukeboxes
+		"scoreboard players operation %%ptr_memory reg = %%memory_z reg".to_string(),
+		format!("scoreboard players remove %%ptr_memory reg {oz}"),
+		"scoreboard players operation %%ptr_memory reg /= %%PAGE_SPAN_Z reg".to_string(),
+		"execute store result storage wasm:scratch mem.memory int 1 run scoreboard players get %%ptr_memory reg".to_string(),
+	];
+
+	let load_doubleword = vec![
+		"function intrinsic:setptr".to_string(),
+		format!("execute at {MEM_PTR_UUID} store result score %return%0%lo reg run data get block ~ ~ ~ {JUKEBOX_WORD_PATH} 1"),
+		"scoreboard players add %ptr reg 4".to_string(),
+		"function intrinsic:setptr".to_string(),
+		format!("execute at {MEM_PTR_UUID} store result score %return%0%hi reg run data get block ~ ~ ~ {JUKEBOX_WORD_PATH} 1"),
+		"scoreboard players remove %ptr reg 4".to_string(),
+	];
+
+	// Bytes are set one at a time, which is slow but doesn't depend on where memory is
+	let memset = [
+		"scoreboard players operation %mst_result reg = %param0%0 reg",
+		"scoreboard players operation %mst_dest reg = %param0%0 reg",
+		"scoreboard players operation %mst_value reg = %param1%0 reg",
+		"scoreboard players operation %mst_length reg = %param2%0 reg",
+		"execute if score %mst_length reg matches 1.. run function intrinsic:memset/each_byte",
+		"scoreboard players operation %return%0 reg = %mst_result reg",
+	];
+	let memset_bytes = [
+		"scoreboard players operation %ptr reg = %mst_dest reg",
+		"scoreboard players operation %param2%0 reg = %mst_value reg",
+		"function intrinsic:setptr",
+		"function intrinsic:store_byte",
+		"scoreboard players add %mst_dest reg 1",
+		"scoreboard players remove %mst_length reg 1",
+		"execute if score %mst_length reg matches 1.. run function intrinsic:memset/each_byte",
+	];
+
+	// Overlapping copies to a higher address start from the end, so bytes are read before they're overwritten
+	let memcpy = [
+		"scoreboard players operation %mcp_dest reg = %param0%0 reg",
+		"scoreboard players operation %mcp_src reg = %param1%0 reg",
+		"scoreboard players operation %mcp_len reg = %param2%0 reg",
+		"scoreboard players operation %mcp_src_end reg = %mcp_src reg",
+		"scoreboard players operation %mcp_src_end reg += %mcp_len reg",
+		"scoreboard players set %mcp_backward reg 0",
+		"execute if score %mcp_dest reg > %mcp_src reg if score %mcp_dest reg < %mcp_src_end reg run scoreboard players set %mcp_backward reg 1",
+		"execute if score %mcp_backward reg matches 1 run scoreboard players operation %mcp_dest reg += %mcp_len reg",
+		"execute if score %mcp_backward reg matches 1 run scoreboard players operation %mcp_src reg += %mcp_len reg",
+		"execute if score %mcp_backward reg matches 1 run scoreboard players remove %mcp_dest reg 1",
+		"execute if score %mcp_backward reg matches 1 run scoreboard players remove %mcp_src reg 1",
+		"execute if score %mcp_len reg matches 1.. run function intrinsic:memcpy/each_byte",
+	];
+	let memcpy_bytes = [
+		"scoreboard players operation %ptr reg = %mcp_src reg",
+		"function intrinsic:setptr",
+		"function intrinsic:load_byte",
+		"scoreboard players operation %param2%0 reg = %param0%0 reg",
+		"scoreboard players operation %ptr reg = %mcp_dest reg",
+		"function intrinsic:setptr",
+		"function intrinsic:store_byte",
+		"execute if score %mcp_backward reg matches 0 run scoreboard players add %mcp_src reg 1",
+		"execute if score %mcp_backward reg matches 0 run scoreboard players add %mcp_dest reg 1",
+		"execute if score %mcp_backward reg matches 1 run scoreboard players remove %mcp_src reg 1",
+		"execute if score %mcp_backward reg matches 1 run scoreboard players remove %mcp_dest reg 1",
+		"scoreboard players remove %mcp_len reg 1",
+		"execute if score %mcp_len reg matches 1.. run function intrinsic:memcpy/each_byte",
+	];
+
+	let word = Word::from_macro();
+	vec![
+		parse_function("intrinsic:setptr", setptr),
+		parse_function("intrinsic:doubleword/load_aligned", load_doubleword),
+		parse_function("intrinsic:memset", memset),
+		parse_function("intrinsic:memset/each_byte", memset_bytes),
+		parse_function("intrinsic:memcpy", memcpy),
+		parse_function("intrinsic:memcpy/each_byte", memcpy_bytes),
+		parse_function(&format!("intrinsic:{name}/load_word"), [macro_line(&format!("$return run {}", store.get(&word)))]),
+		parse_function(&format!("intrinsic:{name}/store_word"), [macro_line(&format!("${} data get storage wasm:scratch mem.value 1", store.store_result(&word)))]),
+	]
+}
+
+fn is_replaced(id: &str) -> bool {
+	REPLACED_INTRINSICS.iter().any(|replaced| id == *replaced || id.starts_with(&format!("{replaced}/")))
+}
+
+/// Rewrites a datapack generated for jukebox memory to keep memory where the backend does instead.
+/// Panics if a function that can't be rewritten is used.
+pub(super) fn rewrite_jukebox_accesses(funcs: Vec<Function>, origin: (i32, i32, i32), store: &dyn WordStore) -> Vec<Function> {
+	let funcs = funcs.into_iter()
+		.filter(|func| !is_replaced(&func.id.to_string()))
+		.chain(create_intrinsics(origin, store));
+
+	let mut result = Vec::new();
+	let mut unsupported = BTreeSet::new();
+	for func in funcs {
+		let id = func.id.to_string();
+		let mut cmds = Vec::new();
+		let mut supported = true;
+		for cmd in func.cmds.iter().map(|cmd| cmd.to_string()) {
+			if cmd.starts_with('#') {
+				cmds.push(cmd);
+			} else if let Some(lines) = adapt_command(&cmd, origin, store) {
+				cmds.extend(lines);
+			} else {
+				supported &= !cmd.contains("RecordItem") && !cmd.contains("minecraft:jukebox");
+				cmds.push(cmd);
+			}
+		}
+
+		if supported {
+			result.push(parse_function(&id, cmds));
+		} else {
+			unsupported.insert(id);
+		}
+	}
+
+	for func in result.iter() {
+		for cmd in func.cmds.iter() {
+			let cmd = cmd.to_string();
+			for id in unsupported.iter() {
+				let call = format!("function {id}");
+				if cmd.ends_with(&call) || cmd.contains(&format!("{call} ")) {
+					panic!("{} uses {id}, which can't keep memory outside of the world", func.id);
+				}
+			}
+		}
+	}
+
+	result
+}
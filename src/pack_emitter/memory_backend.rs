@@ -0,0 +1,208 @@
+//! The ways linear memory can be kept, chosen with `--memory-backend`.
+//!
+//! A backend decides how memory is cleared and grown and how loads and stores are lowered,
+//! and can rewrite the finished datapack, so new backends only need an implementation of `MemoryBackend`
+//! and a variant of `MemoryBackendKind` that returns it.
+
+use std::collections::BTreeSet;
+
+use datapack_common::functions::Function;
+
+use crate::{lir::LirInstr, ssa::Memory, mc_version::McVersion, WorldLayout};
+
+use super::{emit_jukebox_access, macro_memory::{self, memory_line, Word, WordStore}, MEM_PTR_UUID, PAGE_SPAN_X, PAGE_SPAN_Y, PAGE_SPAN_Z, MEMORY_PAGE_BLOCKS};
+
+/// How linear memory is kept and accessed.
+pub trait MemoryBackend: Sync {
+	/// Returns why the backend can't be used with a version, if it can't.
+	fn check_version(&self, _version: McVersion) -> Result<(), String> {
+		Ok(())
+	}
+
+	/// Whether `--run-output` can run programs using this backend.
+	fn can_interpret(&self) -> bool {
+		false
+	}
+
+	/// Whether memory is placed in the world, so it has to fit in its height range.
+	fn places_blocks(&self) -> bool {
+		false
+	}
+
+	/// Emits the commands run once before any memory is initialized.
+	fn emit_setup(&self, _memory: &[Memory], _code: &mut Vec<String>) {}
+
+	/// Emits the commands replacing a memory with the given number of zeroed pages.
+	fn emit_clear(&self, memory_idx: u32, num_pages: usize, layout: &WorldLayout, code: &mut Vec<String>);
+
+	/// Emits the commands adding a zeroed page to the end of a memory, whose current size in pages is in `pages_var`.
+	fn emit_grow_page(&self, memory_idx: u32, pages_var: &str, layout: &WorldLayout, code: &mut Vec<String>);
+
+	/// Emits one of the `Load*` or `Store*` instructions.
+	fn emit_access(&self, instr: &LirInstr, layout: &WorldLayout, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>);
+
+	/// Rewrites the finished datapack, after every function and intrinsic has been added.
+	fn finish(&self, funcs: Vec<Function>, _layout: &WorldLayout) -> Vec<Function> {
+		funcs
+	}
+}
+
+/// The most blocks a single `fill` command can change.
+const MAX_FILL_BLOCKS: usize = 32768;
+
+/// Keeps each word in the record of a jukebox, starting at the memory origin.
+pub struct JukeboxMemory;
+
+impl MemoryBackend for JukeboxMemory {
+	fn can_interpret(&self) -> bool {
+		true
+	}
+
+	fn places_blocks(&self) -> bool {
+		true
+	}
+
+	fn emit_clear(&self, memory_idx: u32, num_pages: usize, layout: &WorldLayout, code: &mut Vec<String>) {
+		// Web assembly page size is 64KiB
+		// Thus a 2x256x32 area where each block is an i32
+		// makes up exactly one page,
+		// and a single fill command can fill up to 32768 blocks, which is two pages
+		let pages_per_fill = MAX_FILL_BLOCKS / MEMORY_PAGE_BLOCKS;
+
+		let (ox, y_begin, oz) = layout.memory_origin;
+		let z_begin = oz + memory_idx as i32 * PAGE_SPAN_Z;
+		for first_page in (0..num_pages).step_by(pages_per_fill) {
+			let pages = (num_pages - first_page).min(pages_per_fill);
+			let x_begin = ox + first_page as i32 * PAGE_SPAN_X;
+			let x_end = x_begin + pages as i32 * PAGE_SPAN_X - 1;
+			let y_end = y_begin + PAGE_SPAN_Y - 1;
+			let z_end = z_begin + PAGE_SPAN_Z - 1;
+			code.push(format!("fill {x_begin} {y_begin} {z_begin} {x_end} {y_end} {z_end} minecraft:air replace"));
+			code.push(format!("fill {x_begin} {y_begin} {z_begin} {x_end} {y_end} {z_end} minecraft:jukebox{{RecordItem:{{id:\"minecraft:stone\",Count:1b,tag:{{Memory:0}}}}}} replace"));
+		}
+	}
+
+	fn emit_grow_page(&self, memory_idx: u32, pages_var: &str, layout: &WorldLayout, code: &mut Vec<String>) {
+		let (ox, oy, oz) = layout.memory_origin;
+		let z_begin = oz + memory_idx as i32 * PAGE_SPAN_Z;
+		let (x_end, y_end, z_end) = (ox + PAGE_SPAN_X - 1, PAGE_SPAN_Y - 1, PAGE_SPAN_Z - 1);
+		code.push(format!("data modify storage wasm:scratch Pos set value [0d, {oy}d, {z_begin}d]"));
+		code.push(format!("execute store result storage wasm:scratch Pos[0] double {PAGE_SPAN_X} run scoreboard players get {pages_var}"));
+		code.push(format!("data modify entity {MEM_PTR_UUID} Pos set from storage wasm:scratch Pos"));
+		code.push(format!("execute at {MEM_PTR_UUID} run fill ~{ox} ~ ~ ~{x_end} ~{y_end} ~{z_end} minecraft:air replace"));
+		code.push(format!("execute at {MEM_PTR_UUID} run fill ~{ox} ~ ~ ~{x_end} ~{y_end} ~{z_end} minecraft:jukebox{{RecordItem:{{id:\"minecraft:stone\",Count:1b,tag:{{Memory:0}}}}}} replace"));
+	}
+
+	fn emit_access(&self, instr: &LirInstr, layout: &WorldLayout, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
+		emit_jukebox_access(instr, layout, false, code, const_pool);
+	}
+}
+
+fn check_has_macros(name: &str, version: McVersion) -> Result<(), String> {
+	if version.has_macros() {
+		Ok(())
+	} else {
+		Err(format!("the {name} memory backend needs function macros, which Minecraft {version} doesn't have, use --mc-version 1.20.2 or newer"))
+	}
+}
+
+/// The data storage `StorageMemory` keeps memory in.
+static MEMORY_STORAGE: &str = "wasm:memory";
+
+/// Keeps each memory in `wasm:memory m<MEMORY>` as a list of pages,
+/// each an int array of the little-endian words in that page.
+pub struct StorageMemory;
+
+impl WordStore for StorageMemory {
+	fn name(&self) -> &'static str {
+		"storage"
+	}
+
+	fn get(&self, word: &Word) -> String {
+		format!("data get storage {MEMORY_STORAGE} m{}[{}][{}] 1", word.memory, word.page, word.word)
+	}
+
+	fn store_result(&self, word: &Word) -> String {
+		format!("execute store result storage {MEMORY_STORAGE} m{}[{}][{}] int 1 run", word.memory, word.page, word.word)
+	}
+
+	fn set(&self, word: &Word, value: i32) -> String {
+		format!("data modify storage {MEMORY_STORAGE} m{}[{}][{}] set value {value}", word.memory, word.page, word.word)
+	}
+}
+
+impl MemoryBackend for StorageMemory {
+	fn check_version(&self, version: McVersion) -> Result<(), String> {
+		check_has_macros("storage", version)
+	}
+
+	/// Sets up the empty page that pages are copied from when memory is cleared or grows.
+	fn emit_setup(&self, memory: &[Memory], code: &mut Vec<String>) {
+		if !memory.is_empty() {
+			let zeros = vec!["0"; MEMORY_PAGE_BLOCKS].join(",");
+			code.push(memory_line(&format!("data modify storage {MEMORY_STORAGE} zero_page set value [I;{zeros}]")));
+		}
+	}
+
+	fn emit_clear(&self, memory_idx: u32, num_pages: usize, _layout: &WorldLayout, code: &mut Vec<String>) {
+		code.push(memory_line(&format!("data modify storage {MEMORY_STORAGE} m{memory_idx} set value []")));
+		for _ in 0..num_pages {
+			code.push(memory_line(&format!("data modify storage {MEMORY_STORAGE} m{memory_idx} append from storage {MEMORY_STORAGE} zero_page")));
+		}
+	}
+
+	fn emit_grow_page(&self, memory_idx: u32, _pages_var: &str, _layout: &WorldLayout, code: &mut Vec<String>) {
+		code.push(memory_line(&format!("data modify storage {MEMORY_STORAGE} m{memory_idx} append from storage {MEMORY_STORAGE} zero_page")));
+	}
+
+	fn emit_access(&self, instr: &LirInstr, layout: &WorldLayout, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
+		emit_jukebox_access(instr, layout, true, code, const_pool);
+	}
+
+	fn finish(&self, funcs: Vec<Function>, layout: &WorldLayout) -> Vec<Function> {
+		macro_memory::rewrite_jukebox_accesses(funcs, layout.memory_origin, self)
+	}
+}
+
+/// Keeps each word in the score of `%p<PAGE>w<WORD>` in the `wasm_memory<MEMORY>` objective.
+/// Missing scores are read as zero, so memory is cleared by recreating the objective and grows without any commands.
+pub struct ScoreboardMemory;
+
+impl WordStore for ScoreboardMemory {
+	fn name(&self) -> &'static str {
+		"scoreboard"
+	}
+
+	fn get(&self, word: &Word) -> String {
+		format!("scoreboard players get %p{}w{} wasm_memory{}", word.page, word.word, word.memory)
+	}
+
+	fn store_result(&self, word: &Word) -> String {
+		format!("execute store result score %p{}w{} wasm_memory{} run", word.page, word.word, word.memory)
+	}
+
+	fn set(&self, word: &Word, value: i32) -> String {
+		format!("scoreboard players set %p{}w{} wasm_memory{} {value}", word.page, word.word, word.memory)
+	}
+}
+
+impl MemoryBackend for ScoreboardMemory {
+	fn check_version(&self, version: McVersion) -> Result<(), String> {
+		check_has_macros("scoreboard", version)
+	}
+
+	fn emit_clear(&self, memory_idx: u32, _num_pages: usize, _layout: &WorldLayout, code: &mut Vec<String>) {
+		code.push(format!("scoreboard objectives remove wasm_memory{memory_idx}"));
+		code.push(format!("scoreboard objectives add wasm_memory{memory_idx} dummy"));
+	}
+
+	fn emit_grow_page(&self, _memory_idx: u32, _pages_var: &str, _layout: &WorldLayout, _code: &mut Vec<String>) {}
+
+	fn emit_access(&self, instr: &LirInstr, layout: &WorldLayout, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
+		emit_jukebox_access(instr, layout, true, code, const_pool);
+	}
+
+	fn finish(&self, funcs: Vec<Function>, layout: &WorldLayout) -> Vec<Function> {
+		macro_memory::rewrite_jukebox_accesses(funcs, layout.memory_origin, self)
+	}
+}
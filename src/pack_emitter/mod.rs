@@ -1,12 +1,23 @@
-use std::{collections::{HashSet, HashMap}, path::Path, ops::Range};
-use std::ops::Deref;
+use std::{collections::{HashSet, HashMap, BTreeSet}, path::Path, ops::Range};
+use std::{io::Write, ops::Deref};
 
 use command_parser::parse_command;
 use datapack_common::functions::{Function, Command, command_components::{FunctionIdent, Uuid, SNbt}};
 use rust_embed::RustEmbed;
 use wasmparser::ValType;
 
-use crate::{lir::{LirProgram, LirFunction, LirBasicBlock, LirInstr, Register, LirTerminator, Condition, Half, DoubleRegister}, ssa::{BlockId, Memory, interp::TypedValue, const_prop::{StaticValue, BitMask}, lir_emitter::RegisterWithInfo}, jump_mode, JumpMode};
+pub mod bedrock;
+pub mod command_blocks;
+pub mod memory_backend;
+pub mod namespace;
+pub mod shards;
+pub mod short_names;
+mod macro_memory;
+
+use crate::{lir::{LirProgram, LirFunction, LirBasicBlock, LirInstr, Register, LirTerminator, Condition, Half, DoubleRegister, OBJECTIVE_NAME}, ssa::{BlockId, Memory, Table, TrapKind, interp::TypedValue, const_prop::{StaticValue, BitMask}, lir_emitter::RegisterWithInfo}, JumpMode, ProfileMode, DivByZero, CompileContext, SnapshotRegion, Framebuffer, WorldLayout, cost_model::CostModel, mc_version::McVersion};
+
+use namespace::PackNamespace;
+use short_names::ShortNames;
 
 // "44453000-0-0-0-1"
 pub static MEM_PTR_UUID: Uuid = Uuid([0x44453000, 0, 0, 1]);
@@ -17,9 +28,105 @@ pub static TURTLE_UUID: Uuid = Uuid([0x44453000, 0, 0, 2]);
 /// This variable keeps track of how many commands have been run so far
 pub static CMDS_RUN_VAR: &str = "%%commands_run reg";
 
-/// This variable is the maximum number of commands allowed to run in a single tick
+/// This variable is the maximum number of commands allowed to run in a single tick,
+/// initialized from the cost model
 pub static MAX_CMDS_VAR: &str = "%%max_commands reg";
 
+/// Counts calls to recursive functions since the last forced yield.
+/// Only used when recursive calls yield periodically.
+pub static RECURSION_DEPTH_VAR: &str = "%%recursion_depth reg";
+
+/// 1 while a time-sliced export is still running, so new calls to it are dropped.
+pub static TIME_SLICE_BUSY_VAR: &str = "%%time_slice_busy reg";
+
+/// How many calls to time-sliced exports were dropped because the previous call hadn't finished.
+pub static FRAMES_DROPPED_VAR: &str = "%%frames_dropped reg";
+
+/// The command limit in effect before a time-sliced export lowered it.
+static SAVED_MAX_CMDS_VAR: &str = "%%saved_max_commands reg";
+
+/// In bounce mode, the address of the block `wasmrunner:__bounce` runs next, or -1 if there is none.
+/// The block's name is kept in `wasm:bounce next` for dispatching with macros.
+static BOUNCE_NEXT_VAR: &str = "%%bounce_next reg";
+
+/// In bounce mode, the address of the block `wasmrunner:__bounce_resume` continues at once a yield is over.
+/// The block's name is kept in `wasm:bounce resume`.
+static BOUNCE_RESUME_VAR: &str = "%%bounce_resume reg";
+
+/// Incremented by every checkpoint, and stored with it in `wasm:checkpoint id`, so restoring can tell whether the scores
+/// are still the ones that were checkpointed. Only used with `--persist-state`.
+static CHECKPOINT_ID_VAR: &str = "%%checkpoint_id reg";
+
+/// The ID of the checkpoint in storage, while the datapack is being loaded.
+static SAVED_CHECKPOINT_ID_VAR: &str = "%%saved_checkpoint_id reg";
+
+/// The highest count `wasmrunner:__profile_dump_round` hasn't printed yet.
+static PROFILE_MAX_VAR: &str = "%%profile_max reg";
+
+/// How many of the highest counts `wasmrunner:profile_dump` prints.
+const PROFILE_DUMP_TOP: usize = 10;
+
+/// The code of the trap that stopped the program, or 0 if it hasn't trapped since an export was last called.
+pub static TRAP_VAR: &str = "%%trap reg";
+
+/// How many local frames are on the stack, which is checked against `--max-call-depth` before each call pushes another.
+static CALL_DEPTH_VAR: &str = "%%call_depth reg";
+
+/// The deepest calls can be nested by default. Each local frame nests the stack in storage one level deeper,
+/// and Minecraft can't store NBT nested more than 512 levels deep.
+pub const DEFAULT_MAX_CALL_DEPTH: u32 = 500;
+
+/// The return address pushed by time-sliced exports, so returning from them can be detected.
+const TIME_SLICE_SENTINEL: i32 = -2;
+
+/// The objective `wasmrunner:call/*` functions read arguments from and write results to.
+pub static CALL_OBJECTIVE: &str = "wasm_call";
+
+/// 0 if memory has not been initialized yet, 1 if it is being initialized, and 2 once it is ready.
+/// Only used when memory is initialized lazily.
+pub static MEM_INIT_STATE_VAR: &str = "%%memory_init_state reg";
+
+/// The current size of the first memory in pages, increased by `memory.grow`.
+pub static MEM_PAGES_VAR: &str = "%%memory_pages reg";
+
+/// The size in pages `wasmrunner:__memory_grow` is growing memory to.
+static MEM_GROW_END_VAR: &str = "%%memory_grow_end reg";
+
+/// A word of imported memory that only some bytes of are initialized, while they are replaced.
+static MEM_INIT_WORD_VAR: &str = "%%memory_init_word reg";
+
+/// The bytes of `MEM_INIT_WORD_VAR` below the ones being replaced, or the new bytes while they are added.
+static MEM_INIT_PART_VAR: &str = "%%memory_init_part reg";
+
+/// The z coordinate of the memory the intrinsics are accessing, added by `intrinsic:setptr`.
+/// This is the z coordinate of the memory origin except while accessing a memory other than the first.
+static MEM_Z_VAR: &str = "%%memory_z reg";
+
+/// The x and y coordinates of the memory origin, added by `intrinsic:setptr`.
+static MEM_X_VAR: &str = "%%memory_x reg";
+static MEM_Y_VAR: &str = "%%memory_y reg";
+
+/// The y coordinate just above the top of memory, where the intrinsics that walk through memory wrap around.
+static MEM_Y_END_VAR: &str = "%%memory_y_end reg";
+
+/// Holds the entry index during `table.get`, in case the destination is the same register.
+static TABLE_INDEX_VAR: &str = "%%table_index reg";
+
+/// The size `table.grow` is growing a table to.
+static TABLE_GROW_END_VAR: &str = "%%table_grow_end reg";
+
+/// 1 if the `table.grow` in progress fits in the table, 0 otherwise.
+static TABLE_GROW_OK_VAR: &str = "%%table_grow_ok reg";
+
+/// The end of the table or segment range `table.init` is checking.
+static TABLE_INIT_END_VAR: &str = "%%table_init_end reg";
+
+/// 1 if both ranges of the `table.init` in progress are in bounds, 0 otherwise.
+static TABLE_INIT_OK_VAR: &str = "%%table_init_ok reg";
+
+/// The function reference `table.init` is copying into the table.
+static TABLE_INIT_VALUE_VAR: &str = "%%table_init_value reg";
+
 fn parse_function<C, T>(id: &str, code: C) -> Function
 	where
 		C: IntoIterator<Item=T>,
@@ -35,11 +142,16 @@ fn create_scoreboard_init(code: &mut Vec<String>) {
 	code.push("# Set up scoreboard".to_string());
 	code.push("scoreboard objectives remove reg".to_string());
 	code.push("scoreboard objectives add reg dummy".to_string());
+	code.push(format!("scoreboard objectives remove {CALL_OBJECTIVE}"));
+	code.push(format!("scoreboard objectives add {CALL_OBJECTIVE} dummy"));
 }
 
-fn create_cmd_count_init(code: &mut Vec<String>) {
+fn create_cmd_count_init(cost_model: &CostModel, code: &mut Vec<String>) {
 	code.push(format!("scoreboard players set {CMDS_RUN_VAR} 0"));
-	code.push(format!("scoreboard players set {MAX_CMDS_VAR} 30000"));
+	code.push(format!("scoreboard players set {MAX_CMDS_VAR} {}", cost_model.max_cmds_per_tick));
+	code.push(format!("scoreboard players set {RECURSION_DEPTH_VAR} 0"));
+	code.push(format!("scoreboard players set {TIME_SLICE_BUSY_VAR} 0"));
+	code.push(format!("scoreboard players set {FRAMES_DROPPED_VAR} 0"));
 }
 
 fn create_stack_init(code: &mut Vec<String>) {
@@ -47,13 +159,14 @@ fn create_stack_init(code: &mut Vec<String>) {
 	code.push("data modify storage wasm:localstack stack set value {}".to_string());
 	code.push("data modify storage wasm:returnstack stack set value {}".to_string());
 	code.push("data modify storage wasm:scratch stack set value {}".to_string());
+	code.push(format!("scoreboard players set {CALL_DEPTH_VAR} 0"));
 }
 
 fn create_stdout_init(code: &mut Vec<String>) {
 	code.push("data modify storage wasm:stdout buffer set value []".to_string());
 }
 
-fn create_pointers_init(code: &mut Vec<String>) {
+fn create_pointers_init(layout: &WorldLayout, code: &mut Vec<String>) {
 	code.push("# Remove old armor stand pointers".to_string());
 	code.push(format!("kill {MEM_PTR_UUID}"));
 	code.push(format!("kill {TURTLE_UUID}"));
@@ -61,14 +174,215 @@ fn create_pointers_init(code: &mut Vec<String>) {
 	let mem_ptr_uuid = SNbt::from(MEM_PTR_UUID);
 	let turtle_uuid = SNbt::from(TURTLE_UUID);
 
+	let (tx, ty, tz) = layout.turtle_origin;
+	let (mx, my, mz) = layout.memory_origin;
+
 	code.push("# Add armor stand pointers".to_string());
-	code.push(format!("summon minecraft:marker 0 0 8 {{UUID:{turtle_uuid}}}"));
-	code.push(format!("summon minecraft:marker 0 0 -2 {{UUID:{mem_ptr_uuid}}}"));
+	code.push(format!("summon minecraft:marker {tx} {ty} {tz} {{UUID:{turtle_uuid}}}"));
+	code.push(format!("summon minecraft:marker {mx} {my} {} {{UUID:{mem_ptr_uuid}}}", mz - 2));
+
+	code.push(format!("scoreboard players set {MEM_X_VAR} {mx}"));
+	code.push(format!("scoreboard players set {MEM_Y_VAR} {my}"));
+	code.push(format!("scoreboard players set {MEM_Y_END_VAR} {}", my + PAGE_SPAN_Y));
 
 	code.push("data modify storage wasm:scratch Pos set value [0d, 0d, 0d]".to_string());
 }
 
-fn create_constants_init(constants: &HashSet<i32>, code: &mut Vec<String>) {
+/// Functions that the `minecraft:load` function tag runs in order whenever the datapack is loaded, if they're in the datapack.
+/// The constants of a hot-reloaded datapack are set last, since restoring a checkpoint puts back the old ones.
+static LOAD_FUNCS: &[&str] = &["wasmrunner:__refresh_markers", "wasmrunner:__restore_state", "wasmrunner:__hot_reload"];
+
+/// Creates `wasmrunner:__refresh_markers`, which replaces the markers used as pointers
+/// so a running program keeps working after the datapack is updated and reloaded.
+fn create_refresh_markers_func(layout: &WorldLayout) -> Function {
+	let mut code = Vec::new();
+	create_pointers_init(layout, &mut code);
+	parse_function("wasmrunner:__refresh_markers", code)
+}
+
+/// Whether a block counts how often it runs: every block when profiling blocks,
+/// and the entry block of each function when profiling functions.
+fn is_profiled(block_id: BlockId, mode: ProfileMode) -> bool {
+	mode == ProfileMode::Blocks || block_id.block == 0
+}
+
+/// Creates `wasmrunner:profile_reset`, which sets every count to 0, and `wasmrunner:profile_dump`,
+/// which prints the blocks with the highest `PROFILE_DUMP_TOP` counts, highest first.
+/// Each round of `wasmrunner:__profile_dump_round` finds the highest count that hasn't been printed yet,
+/// and prints every block with that count.
+fn create_profile_funcs(program: &LirProgram, mode: ProfileMode) -> Vec<Function> {
+	let blocks = program.all_block_ids().filter(|&id| is_profiled(id, mode)).collect::<Vec<_>>();
+
+	let reset = blocks.iter().map(|&id| format!("scoreboard players set {} 0", get_profile_var(id)));
+
+	let what = match mode {
+		ProfileMode::Functions => "Calls per function",
+		ProfileMode::Blocks => "Runs per block",
+	};
+	let mut dump = vec![format!(r#"tellraw @a [{{"text":"{what}:"}}]"#)];
+	dump.extend(blocks.iter().map(|&id| format!("scoreboard players operation {} = {}", get_profile_rank_var(id), get_profile_var(id))));
+	dump.extend(std::iter::repeat("function wasmrunner:__profile_dump_round".to_string()).take(PROFILE_DUMP_TOP));
+
+	let mut round = vec![format!("scoreboard players set {PROFILE_MAX_VAR} 0")];
+	round.extend(blocks.iter().map(|&id| format!("scoreboard players operation {PROFILE_MAX_VAR} > {}", get_profile_rank_var(id))));
+	for &id in blocks.iter() {
+		let found = format!("execute if score {PROFILE_MAX_VAR} matches 1.. if score {} = {PROFILE_MAX_VAR} run", get_profile_rank_var(id));
+		let holder = get_profile_var(id);
+		let holder = holder.split_once(' ').unwrap().0;
+		round.push(format!(r#"{found} tellraw @a [{{"text":"{}: "}},{{"score":{{"name":"{holder}","objective":"reg"}}}}]"#, get_mc_id(&program.func_names, id)));
+	}
+	round.extend(blocks.iter().map(|&id| {
+		let rank = get_profile_rank_var(id);
+		format!("execute if score {PROFILE_MAX_VAR} matches 1.. if score {rank} = {PROFILE_MAX_VAR} run scoreboard players set {rank} -1")
+	}));
+
+	vec![
+		parse_function("wasmrunner:profile_reset", reset),
+		parse_function("wasmrunner:profile_dump", dump),
+		parse_function("wasmrunner:__profile_dump_round", round),
+	]
+}
+
+/// Creates `wasmrunner:__hot_reload`, which sets up the constants and return addresses of this version of the program
+/// without touching memory, globals, or the stacks, so a program initialized by an older version keeps running.
+fn create_hot_reload_func(program: &LirProgram) -> Function {
+	let mut code = Vec::new();
+	create_constants_init(&mut code);
+	create_return_addrs_init(program.all_block_ids(), &mut code);
+	parse_function("wasmrunner:__hot_reload", code)
+}
+
+/// Finds every holder of the holder objective that a paused program may still need once it continues:
+/// the registers its code uses, its globals, and the state the datapack keeps for it.
+/// The `%const%` holders and the return addresses are left out, since restoring sets them up again,
+/// and so are the temporaries of the intrinsics, which are never used across a yield.
+fn find_state_holders(ctx: &CompileContext, program: &LirProgram) -> BTreeSet<String> {
+	let mut regs = Vec::new();
+	for func in program.code.iter() {
+		for (idx, ty) in func.params.iter().enumerate() {
+			regs.extend(type_registers(DoubleRegister::param(idx as u32), *ty));
+		}
+		for (idx, ty) in func.returns.iter().enumerate() {
+			regs.extend(type_registers(DoubleRegister::return_reg(idx as u32), *ty));
+		}
+
+		for (_, block) in func.code.iter() {
+			for instr in block.body.iter() {
+				regs.extend(instr.registers());
+				if let LirInstr::PackCall { params, returns, .. } = instr {
+					for (idx, ty) in params.iter().enumerate() {
+						regs.extend(type_registers(DoubleRegister::param(idx as u32), *ty));
+					}
+					for (idx, ty) in returns.iter().enumerate() {
+						regs.extend(type_registers(DoubleRegister::return_reg(idx as u32), *ty));
+					}
+				}
+			}
+
+			if let &LirTerminator::JumpIf { cond, .. } | &LirTerminator::JumpTable { cond, .. } = &block.term {
+				regs.push(cond);
+			}
+		}
+	}
+	for (idx, global) in program.globals.iter().enumerate() {
+		regs.push(Register::global_lo(idx as u32));
+		if let TypedValue::I64(_) = global {
+			regs.push(Register::global_hi(idx as u32));
+		}
+	}
+
+	let mut vars = vec![
+		CMDS_RUN_VAR.to_string(),
+		MAX_CMDS_VAR.to_string(),
+		SAVED_MAX_CMDS_VAR.to_string(),
+		RECURSION_DEPTH_VAR.to_string(),
+		TIME_SLICE_BUSY_VAR.to_string(),
+		FRAMES_DROPPED_VAR.to_string(),
+		BOUNCE_NEXT_VAR.to_string(),
+		BOUNCE_RESUME_VAR.to_string(),
+		TRAP_VAR.to_string(),
+		CALL_DEPTH_VAR.to_string(),
+		MEM_X_VAR.to_string(),
+		MEM_Y_VAR.to_string(),
+		MEM_Y_END_VAR.to_string(),
+		MEM_Z_VAR.to_string(),
+	];
+	vars.extend(regs.into_iter().filter(|reg| reg.get_const().is_none()).map(|reg| reg.to_string()));
+	vars.extend((0..program.memory.len()).map(|idx| get_memory_pages_var(idx as u32)));
+	for (table_idx, table) in program.tables.iter().enumerate() {
+		if table.runtime_funcs.is_some() {
+			vars.push(get_table_size_var(table_idx as u32));
+			vars.extend((0..table.capacity()).map(|idx| get_table_entry_var(table_idx as u32, idx)));
+		}
+	}
+	vars.extend((0..program.elem_segments.len()).map(|idx| get_elem_len_var(idx as u32)));
+	if ctx.lazy_memory_init {
+		vars.push(MEM_INIT_STATE_VAR.to_string());
+	}
+	if has_player_input(ctx, &program.exports) {
+		vars.push(LAST_PLAYER_ID_VAR.to_string());
+	}
+	if uses_random(program) {
+		vars.extend(["%%rng_state reg", "%%rng_mul reg", "%%rng_inc reg"].map(str::to_string));
+	}
+
+	vars.into_iter().map(|var| var.split_once(' ').unwrap().0.to_string()).collect()
+}
+
+/// The registers a value of the given type takes up.
+fn type_registers(reg: DoubleRegister, ty: ValType) -> Vec<Register> {
+	match ty {
+		ValType::I64 | ValType::F64 => vec![reg.lo(), reg.hi()],
+		_ => vec![reg.lo()],
+	}
+}
+
+/// How many commands `wasmrunner:__checkpoint` runs.
+pub fn checkpoint_cmd_count(ctx: &CompileContext, program: &LirProgram) -> usize {
+	2 + find_state_holders(ctx, program).len()
+}
+
+/// Creates `wasmrunner:__checkpoint`, which copies every holder the program uses into `wasm:checkpoint scores`,
+/// and `wasmrunner:__restore_state`, which the datapack runs when it's loaded.
+/// If the scores no longer match the checkpoint, it puts them back and sets up the constants and return addresses again,
+/// and then continues the paused program, which replaces the pending `schedule` if that survived.
+///
+/// A checkpoint is taken whenever a paused program yields in bounce mode, which is the only time a program is paused
+/// between ticks, and removed once it continues, so there is only a checkpoint while a program is paused.
+/// The stacks of return addresses and locals are already kept in storage.
+fn create_checkpoint_funcs(ctx: &CompileContext, program: &LirProgram) -> Vec<Function> {
+	let holders = find_state_holders(ctx, program);
+
+	let mut checkpoint = vec![
+		format!("scoreboard players add {CHECKPOINT_ID_VAR} 1"),
+		format!("execute store result storage wasm:checkpoint id int 1 run scoreboard players get {CHECKPOINT_ID_VAR}"),
+	];
+	let mut restore = Vec::new();
+	for holder in holders.iter() {
+		checkpoint.push(format!("execute store result storage wasm:checkpoint scores.{holder} int 1 run scoreboard players get {holder} {OBJECTIVE_NAME}"));
+		restore.push(format!("execute store result score {holder} {OBJECTIVE_NAME} run data get storage wasm:checkpoint scores.{holder}"));
+	}
+	restore.push(format!("scoreboard players operation {CHECKPOINT_ID_VAR} = {SAVED_CHECKPOINT_ID_VAR}"));
+	create_constants_init(&mut restore);
+	create_return_addrs_init(program.all_block_ids(), &mut restore);
+
+	// The objectives are gone too if the scores were lost by removing them
+	let load = vec![
+		format!("scoreboard objectives add {OBJECTIVE_NAME} dummy"),
+		format!("scoreboard objectives add {CALL_OBJECTIVE} dummy"),
+		format!("execute store result score {SAVED_CHECKPOINT_ID_VAR} run data get storage wasm:checkpoint id"),
+		format!("execute if data storage wasm:checkpoint scores unless score {CHECKPOINT_ID_VAR} = {SAVED_CHECKPOINT_ID_VAR} run function wasmrunner:__restore_scores"),
+		"execute if data storage wasm:checkpoint scores run schedule function wasmrunner:__bounce_resume 1".to_string(),
+	];
+
+	vec![
+		parse_function("wasmrunner:__checkpoint", checkpoint),
+		parse_function("wasmrunner:__restore_scores", restore),
+		parse_function("wasmrunner:__restore_state", load),
+	]
+}
+
+fn create_constants_init(code: &mut Vec<String>) {
 	let old_style = [-1];
 
 	code.push(format!("scoreboard players set %%PAGE_SPAN_Z reg {PAGE_SPAN_Z}"));
@@ -83,50 +397,202 @@ fn create_constants_init(constants: &HashSet<i32>, code: &mut Vec<String>) {
 		code.push(format!("scoreboard players set %%{v} reg {v}"));
 	}
 
-	for v in constants.iter() {
-		code.push(format!("scoreboard players set %const%{v} reg {v}"));
-	}
+	code.push(format!("function {CONSTANTS_INIT_FUNC}"));
+}
 
-	for v in [0] {
-		code.push(format!("scoreboard players set %const%{v} reg {v}"));
+/// Sets up the `%const%` holders of the constant pool, which is called by `wasmrunner:init`.
+static CONSTANTS_INIT_FUNC: &str = "wasmrunner:__init_constants";
+
+/// Finds every constant whose `%const%` holder is read by the given functions.
+/// Constants that were only used by instructions that take literals directly never appear,
+/// so they aren't set up at all.
+fn find_used_constants(funcs: &[Function]) -> BTreeSet<i32> {
+	let mut used = BTreeSet::new();
+	for cmd in funcs.iter().flat_map(|func| func.cmds.iter()) {
+		let cmd = cmd.to_string();
+		for (idx, _) in cmd.match_indices("%const%") {
+			let rest = &cmd[idx + "%const%".len()..];
+			let end = rest.char_indices()
+				.find(|&(i, c)| !(c.is_ascii_digit() || (i == 0 && c == '-')))
+				.map_or(rest.len(), |(i, _)| i);
+			if let Ok(v) = rest[..end].parse() {
+				used.insert(v);
+			}
+		}
 	}
+	used
 }
 
-fn create_memory_init(memory: &[Memory], code: &mut Vec<String>) {
-	assert!(memory.len() <= 1);
+fn create_constants_init_func(constants: &BTreeSet<i32>) -> Function {
+	let code = constants.iter().map(|v| format!("scoreboard players set %const%{v} reg {v}"));
+	parse_function(CONSTANTS_INIT_FUNC, code)
+}
+
+fn create_memory_init(memory: &[Memory], layout: &WorldLayout, code: &mut Vec<String>) {
+	let origin = layout.memory_origin;
+	let backend = layout.memory_backend.backend();
+	backend.emit_setup(memory, code);
 
 	for (memory_idx, memory) in memory.iter().enumerate() {
-		assert_eq!(memory_idx, 0);
+		let memory_idx = memory_idx as u32;
 		assert_eq!(memory.data.len() % MEMORY_PAGE_SIZE, 0);
-		let num_pages = memory.data.len() / MEMORY_PAGE_SIZE;
-		for x_offset in 0..num_pages {
-			let x_begin = x_offset as i32 * PAGE_SPAN_X;
-			let x_end = x_begin + PAGE_SPAN_X - 1;
-			let y_end = PAGE_SPAN_Y - 1;
-			let z_end = PAGE_SPAN_Z - 1;
-			// Web assembly page size is 64KiB
-			// Thus an 8x256x8 area where each block is an i32
-			// makes up exactly one page
 
-			// Also note that a single fill command can only fill 32768 blocks,
-			// so we'll just do it one at a time for safety
-			code.push(format!("fill {x_begin} 0 0 {x_end} {y_end} {z_end} minecraft:air replace"));
-			code.push(format!("fill {x_begin} 0 0 {x_end} {y_end} {z_end} minecraft:jukebox{{RecordItem:{{id:\"minecraft:stone\",Count:1b,tag:{{Memory:0}}}}}} replace"));
+		if memory.imported.is_some() {
+			// The program exporting this memory clears it, so only write the parts this program owns
+			for (addr, mask) in memory.initialized_words() {
+				let data = i32::from_le_bytes(memory.data[addr..][..4].try_into().unwrap());
+				let (x, y, z) = get_memory_address_pos(origin, memory_idx, addr as i32);
+				if mask == 0b1111 {
+					code.push(format!("data modify block {x} {y} {z} RecordItem.tag.Memory set value {data}"))
+				} else {
+					code.push(format!("execute store result score {MEM_INIT_WORD_VAR} run data get block {x} {y} {z} RecordItem.tag.Memory 1"));
+					replace_word_bytes(data, mask, code);
+					code.push(format!("execute store result block {x} {y} {z} RecordItem.tag.Memory int 1 run scoreboard players get {MEM_INIT_WORD_VAR}"));
+				}
+			}
+			continue;
 		}
 
+		let num_pages = memory.data.len() / MEMORY_PAGE_SIZE;
+		backend.emit_clear(memory_idx, num_pages, layout, code);
+
 		for (word_idx, d) in memory.data.chunks_exact(4).enumerate() {
 			let mut data = [0; 4];
 			data.copy_from_slice(d);
 			let data = i32::from_le_bytes(data);
 
 			if data != 0 {
-				let (x, y, z) = get_address_pos(word_idx as i32 * 4);
+				let (x, y, z) = get_memory_address_pos(origin, memory_idx, word_idx as i32 * 4);
 				code.push(format!("data modify block {x} {y} {z} RecordItem.tag.Memory set value {data}"))
 			}
 		}
 	}
 }
 
+/// Replaces the bytes of `MEM_INIT_WORD_VAR` set in `mask` with the same bytes of `data`, one run of consecutive bytes at a time.
+/// Scoreboard division rounds down, so the bytes above a run are `word / 2^(8 * end)` and the ones below it are `word % 2^(8 * start)`,
+/// which works for negative words too.
+fn replace_word_bytes(data: i32, mask: u8, code: &mut Vec<String>) {
+	let mut byte = 0;
+	while byte < 4 {
+		if mask & (1 << byte) == 0 {
+			byte += 1;
+			continue;
+		}
+		let start = byte;
+		while byte < 4 && mask & (1 << byte) != 0 {
+			byte += 1;
+		}
+		let end = byte;
+
+		if start > 0 {
+			code.push(format!("scoreboard players operation {MEM_INIT_PART_VAR} = {MEM_INIT_WORD_VAR}"));
+			code.push(format!("scoreboard players operation {MEM_INIT_PART_VAR} %= %%{} reg", 1 << (8 * start)));
+		}
+		if end < 4 {
+			code.push(format!("scoreboard players operation {MEM_INIT_WORD_VAR} /= %%{} reg", 1 << (8 * end)));
+			code.push(format!("scoreboard players operation {MEM_INIT_WORD_VAR} *= %%{} reg", 1 << (8 * end)));
+		} else {
+			code.push(format!("scoreboard players set {MEM_INIT_WORD_VAR} 0"));
+		}
+		if start > 0 {
+			code.push(format!("scoreboard players operation {MEM_INIT_WORD_VAR} += {MEM_INIT_PART_VAR}"));
+		}
+
+		let run_mask = ((1_u64 << (8 * end)) - (1_u64 << (8 * start))) as u32;
+		let new_bytes = (data as u32 & run_mask) as i32;
+		if new_bytes != 0 {
+			code.push(format!("scoreboard players set {MEM_INIT_PART_VAR} {new_bytes}"));
+			code.push(format!("scoreboard players operation {MEM_INIT_WORD_VAR} += {MEM_INIT_PART_VAR}"));
+		}
+	}
+}
+
+fn create_snapshot_init(regions: &[SnapshotRegion], code: &mut Vec<String>) {
+	code.push("data modify storage wasm:snapshot regions set value []".to_string());
+	for region in regions.iter() {
+		let arr = create_zeroed_array(region.length as usize / 4);
+		code.push(format!("data modify storage wasm:snapshot regions append value {arr}"));
+	}
+}
+
+/// The maximum number of memory initialization commands that are run in a single tick
+/// when initializing memory lazily.
+const LAZY_MEM_INIT_CHUNK_SIZE: usize = 10_000;
+
+/// Creates the functions used to initialize memory on the first call to an export,
+/// spread out over multiple ticks with a bossbar showing the progress.
+/// The memory is fully initialized once `MEM_INIT_STATE_VAR` is 2,
+/// and the start function is run right after that.
+fn create_lazy_memory_init_funcs(memory: &[Memory], layout: &WorldLayout, start: Option<BlockId>, func_names: &HashMap<usize, String>, jump_mode: JumpMode) -> Vec<Function> {
+	let mut init_code = Vec::new();
+	create_memory_init(memory, layout, &mut init_code);
+
+	let chunks = init_code.chunks(LAZY_MEM_INIT_CHUNK_SIZE).collect::<Vec<_>>();
+
+	let mut funcs = Vec::new();
+
+	let begin_code = vec![
+		format!("scoreboard players set {MEM_INIT_STATE_VAR} 1"),
+		r#"bossbar add wasmrunner:memory_init {"text":"Initializing memory"}"#.to_string(),
+		format!("bossbar set wasmrunner:memory_init max {}", chunks.len().max(1)),
+		"bossbar set wasmrunner:memory_init players @a".to_string(),
+		"schedule function wasmrunner:__memory_init_0 1".to_string(),
+	];
+	funcs.push(parse_function("wasmrunner:__memory_init_begin", begin_code));
+
+	funcs.push(parse_function("wasmrunner:__memory_init_start", [
+		format!("execute if score {MEM_INIT_STATE_VAR} matches 0 run function wasmrunner:__memory_init_begin"),
+	]));
+
+	for idx in 0..chunks.len().max(1) {
+		let mut code = chunks.get(idx).map(|c| c.to_vec()).unwrap_or_default();
+		code.push(format!("bossbar set wasmrunner:memory_init value {}", idx + 1));
+		if idx + 1 < chunks.len() {
+			code.push(format!("schedule function wasmrunner:__memory_init_{} 1", idx + 1));
+		} else {
+			code.push("bossbar remove wasmrunner:memory_init".to_string());
+			code.push(format!("scoreboard players set {MEM_INIT_STATE_VAR} 2"));
+			if let Some(start) = start {
+				code.extend(make_top_level_call(func_names, start, jump_mode));
+			}
+		}
+		funcs.push(parse_function(&format!("wasmrunner:__memory_init_{idx}"), code));
+	}
+
+	funcs
+}
+
+/// Creates the function returned by `get_memory_grow_func`, which takes the number of pages to add in `%param0%0`
+/// and returns the old size in pages in `%return%0`, or -1 if memory can't grow that much.
+/// New pages are zeroed one at a time by a separate `_page` function.
+fn create_memory_grow_funcs(memory_idx: u32, memory: &Memory, layout: &WorldLayout) -> Vec<Function> {
+	let max_pages = memory.max_pages();
+	let pages_var = get_memory_pages_var(memory_idx);
+	let grow_func = get_memory_grow_func(memory_idx);
+	let page_func = format!("{grow_func}_page");
+
+	let grow_code = vec![
+		format!("scoreboard players operation %return%0 reg = {pages_var}"),
+		format!("scoreboard players operation {MEM_GROW_END_VAR} = {pages_var}"),
+		format!("scoreboard players operation {MEM_GROW_END_VAR} += %param0%0 reg"),
+		// The page count is unsigned, and checking it first also catches the end overflowing
+		format!("execute unless score %param0%0 reg matches 0..{max_pages} run scoreboard players set %return%0 reg -1"),
+		format!("execute unless score {MEM_GROW_END_VAR} matches ..{max_pages} run scoreboard players set %return%0 reg -1"),
+		format!("execute unless score %return%0 reg matches -1 if score {pages_var} < {MEM_GROW_END_VAR} run function {page_func}"),
+	];
+
+	let mut page_code = Vec::new();
+	layout.memory_backend.backend().emit_grow_page(memory_idx, &pages_var, layout, &mut page_code);
+	page_code.push(format!("scoreboard players add {pages_var} 1"));
+	page_code.push(format!("execute if score {pages_var} < {MEM_GROW_END_VAR} run function {page_func}"));
+
+	vec![
+		parse_function(&grow_func, grow_code),
+		parse_function(&page_func, page_code),
+	]
+}
+
 fn create_globals_init(globals: &[TypedValue], code: &mut Vec<String>) {
 	for (idx, val) in globals.iter().enumerate() {
 		match val {
@@ -145,6 +611,94 @@ fn create_globals_init(globals: &[TypedValue], code: &mut Vec<String>) {
 	}
 }
 
+/// Tables that are written at runtime store each entry as a function reference in its own holder,
+/// including the entries they can grow into.
+fn create_tables_init(tables: &[Table], code: &mut Vec<String>) {
+	for (table_idx, table) in tables.iter().enumerate() {
+		if table.runtime_funcs.is_none() {
+			continue;
+		}
+
+		for idx in 0..table.capacity() {
+			let entry_var = get_table_entry_var(table_idx as u32, idx);
+			let func_ref = table.elements.get(idx).copied().flatten().map_or(0, |f| f as i32 + 1);
+			code.push(format!("scoreboard players set {entry_var} {func_ref}"));
+		}
+
+		let size_var = get_table_size_var(table_idx as u32);
+		code.push(format!("scoreboard players set {size_var} {}", table.elements.len()));
+	}
+}
+
+fn create_elem_segments_init(elem_segments: &[Vec<Option<usize>>], code: &mut Vec<String>) {
+	for (segment_idx, segment) in elem_segments.iter().enumerate() {
+		let len_var = get_elem_len_var(segment_idx as u32);
+		code.push(format!("scoreboard players set {len_var} {}", segment.len()));
+	}
+}
+
+/// Creates `wasmrunner:__table_init_{table}_{segment}` for every table written at runtime and passive element segment,
+/// which take the destination, source, and length in `%param0%0` through `%param2%0`,
+/// and copy one entry at a time. Out-of-bounds ranges leave `TABLE_INIT_OK_VAR` at 0 for the caller to trap.
+fn create_table_init_funcs(tables: &[Table], elem_segments: &[Vec<Option<usize>>]) -> Vec<Function> {
+	let mut funcs = Vec::new();
+
+	for (table_idx, table) in tables.iter().enumerate() {
+		if table.runtime_funcs.is_none() {
+			continue;
+		}
+
+		let table_idx = table_idx as u32;
+		let size_var = get_table_size_var(table_idx);
+
+		for (segment_idx, segment) in elem_segments.iter().enumerate() {
+			if segment.is_empty() {
+				continue;
+			}
+
+			let len_var = get_elem_len_var(segment_idx as u32);
+			let func_id = format!("wasmrunner:__table_init_{table_idx}_{segment_idx}");
+
+			// Everything is unsigned, and out-of-bounds ranges trap even if the length is zero
+			let init_code = vec![
+				format!("scoreboard players set {TABLE_INIT_OK_VAR} 1"),
+				format!("execute unless score %param0%0 reg matches 0.. run scoreboard players set {TABLE_INIT_OK_VAR} 0"),
+				format!("execute unless score %param1%0 reg matches 0.. run scoreboard players set {TABLE_INIT_OK_VAR} 0"),
+				format!("execute unless score %param2%0 reg matches 0.. run scoreboard players set {TABLE_INIT_OK_VAR} 0"),
+				format!("scoreboard players operation {TABLE_INIT_END_VAR} = %param0%0 reg"),
+				format!("scoreboard players operation {TABLE_INIT_END_VAR} += %param2%0 reg"),
+				format!("execute unless score {TABLE_INIT_END_VAR} matches 0.. run scoreboard players set {TABLE_INIT_OK_VAR} 0"),
+				format!("execute if score {TABLE_INIT_END_VAR} > {size_var} run scoreboard players set {TABLE_INIT_OK_VAR} 0"),
+				format!("scoreboard players operation {TABLE_INIT_END_VAR} = %param1%0 reg"),
+				format!("scoreboard players operation {TABLE_INIT_END_VAR} += %param2%0 reg"),
+				format!("execute unless score {TABLE_INIT_END_VAR} matches 0.. run scoreboard players set {TABLE_INIT_OK_VAR} 0"),
+				format!("execute if score {TABLE_INIT_END_VAR} > {len_var} run scoreboard players set {TABLE_INIT_OK_VAR} 0"),
+				format!("execute if score {TABLE_INIT_OK_VAR} matches 1 if score %param2%0 reg matches 1.. run function {func_id}_step"),
+			];
+
+			let mut step_code = vec![format!("scoreboard players set {TABLE_INIT_VALUE_VAR} 0")];
+			for (idx, elem) in segment.iter().enumerate() {
+				if let Some(func_idx) = elem {
+					step_code.push(format!("execute if score %param1%0 reg matches {idx} run scoreboard players set {TABLE_INIT_VALUE_VAR} {}", func_idx + 1));
+				}
+			}
+			for idx in 0..table.capacity() {
+				let entry_var = get_table_entry_var(table_idx, idx);
+				step_code.push(format!("execute if score %param0%0 reg matches {idx} run scoreboard players operation {entry_var} = {TABLE_INIT_VALUE_VAR}"));
+			}
+			step_code.push("scoreboard players add %param0%0 reg 1".to_string());
+			step_code.push("scoreboard players add %param1%0 reg 1".to_string());
+			step_code.push("scoreboard players remove %param2%0 reg 1".to_string());
+			step_code.push(format!("execute if score %param2%0 reg matches 1.. run function {func_id}_step"));
+
+			funcs.push(parse_function(&func_id, init_code));
+			funcs.push(parse_function(&format!("{func_id}_step"), step_code));
+		}
+	}
+
+	funcs
+}
+
 fn create_return_addrs_init<I>(blocks: I, code: &mut Vec<String>)
 	where I: Iterator<Item=BlockId>
 {
@@ -162,125 +716,691 @@ fn create_return_addrs_init<I>(blocks: I, code: &mut Vec<String>)
 // reset data stack
 // initialize constants
 // initialize "return address" constants
-fn create_init_func(program: &LirProgram, constants: &HashSet<i32>) -> Function {
+fn create_init_func(ctx: &CompileContext, program: &LirProgram) -> Function {
 	let mut code = Vec::new();
 
 	create_scoreboard_init(&mut code);
-	create_cmd_count_init(&mut code);
+	create_cmd_count_init(&ctx.cost_model, &mut code);
+	if program.jump_mode == JumpMode::Bounce {
+		code.push(format!("scoreboard players set {BOUNCE_NEXT_VAR} -1"));
+	}
+	code.push(format!("scoreboard players set {TRAP_VAR} 0"));
 	create_stack_init(&mut code);
+	if ctx.persist_state {
+		code.push("data remove storage wasm:checkpoint scores".to_string());
+	}
 	create_stdout_init(&mut code);
-	create_pointers_init(&mut code);
-	create_constants_init(constants, &mut code);
-	create_memory_init(&program.memory, &mut code);
+	create_pointers_init(&program.layout, &mut code);
+	create_constants_init(&mut code);
+	if uses_random(program) {
+		create_random_init(program.layout.memory_origin, &mut code);
+	}
+	if ctx.lazy_memory_init {
+		code.push(format!("scoreboard players set {MEM_INIT_STATE_VAR} 0"));
+	} else {
+		create_memory_init(&program.memory, &program.layout, &mut code);
+	}
+	for (memory_idx, memory) in program.memory.iter().enumerate() {
+		code.push(format!("scoreboard players set {} {}", get_memory_pages_var(memory_idx as u32), memory.num_pages()));
+	}
+	code.push(format!("scoreboard players set {MEM_Z_VAR} {}", program.layout.memory_origin.2));
+	create_snapshot_init(&ctx.snapshot_regions, &mut code);
 	create_globals_init(&program.globals, &mut code);
+	create_tables_init(&program.tables, &mut code);
+	create_elem_segments_init(&program.elem_segments, &mut code);
 	create_return_addrs_init(program.all_block_ids(), &mut code);
+	if ctx.profile.is_some() {
+		code.push("function wasmrunner:profile_reset".to_string());
+	}
+	if let Some(fb) = &ctx.framebuffer {
+		create_framebuffer_init(fb, &mut code);
+	}
+	if has_player_input(ctx, &program.exports) {
+		create_player_events_init(ctx, &program.exports, &mut code);
+	}
+	create_tick_init(&get_lir_tick_exports(ctx, program), &mut code);
+	if !ctx.lazy_memory_init {
+		if let Some(start) = program.start {
+			code.extend(make_top_level_call(&program.func_names, start, program.jump_mode));
+		}
+	}
 
 	parse_function("wasmrunner:init", code)
 }
 
-fn create_return_to_saved_func(program: &LirProgram) -> Vec<Function> {
-	let reg = Register::temp_lo(0);
+fn uses_random(program: &LirProgram) -> bool {
+	program.code.iter()
+		.flat_map(|func| func.code.iter())
+		.any(|(_, block)| block.body.iter().any(|instr| matches!(instr, LirInstr::Random { .. })))
+}
 
-	let mut code = Vec::new();
+/// Sets up the generator used by `intrinsic:random`,
+/// seeded from the randomly chosen UUID of a temporary marker.
+fn create_random_init(origin: (i32, i32, i32), code: &mut Vec<String>) {
+	// The same constants as many C standard libraries
+	code.push("scoreboard players set %%rng_mul reg 1103515245".to_string());
+	code.push("scoreboard players set %%rng_inc reg 12345".to_string());
+	let (x, y, z) = origin;
+	code.push(format!(r#"summon minecraft:marker {x} {y} {z} {{Tags:["wasm_rng_seed"]}}"#));
+	code.push("execute store result score %%rng_state reg run data get entity @e[type=minecraft:marker,tag=wasm_rng_seed,limit=1] UUID[0]".to_string());
+	code.push("kill @e[type=minecraft:marker,tag=wasm_rng_seed]".to_string());
+}
 
-	code.push(format!("scoreboard players set {reg} -1"));
-	let cond_taken = Register::cond_taken();
-	code.push(format!("scoreboard players set {cond_taken} 0"));
-	code.push("data modify storage wasm:scratch stack.data set from storage wasm:returnstack stack.data".to_string());
-	code.push(format!("execute store result score {reg} run data get storage wasm:returnstack stack.data.ptr 1"));
-	code.push("data modify storage wasm:returnstack stack set from storage wasm:returnstack stack.tail".to_string());
+/// Creates `wasmrunner:__memory_snapshot` and `wasmrunner:__memory_restore`,
+/// which take the region ID in `%param0%0` and copy the entire region in a single function call,
+/// so a snapshot can never be observed half-written.
+fn create_snapshot_funcs(regions: &[SnapshotRegion], origin: (i32, i32, i32)) -> Vec<Function> {
+	let mut funcs = Vec::new();
 
-	let blocks = program.all_block_ids().enumerate().collect::<Vec<_>>();
+	let mut snapshot_code = Vec::new();
+	let mut restore_code = Vec::new();
 
-	let mut funcs = Vec::new();
-	create_nested_return_func(reg, &blocks, &mut funcs);
+	for (region_id, region) in regions.iter().enumerate() {
+		let mut save = Vec::new();
+		let mut load = Vec::new();
 
-	let func_name = format!("wasmrunner:__return_to_saved_{}", funcs.len() - 1);
+		for word_idx in 0..region.length / 4 {
+			let (x, y, z) = get_address_pos(origin, (region.start + word_idx * 4) as i32);
+			save.push(format!("data modify storage wasm:snapshot regions[{region_id}][{word_idx}] set from block {x} {y} {z} RecordItem.tag.Memory"));
+			load.push(format!("data modify block {x} {y} {z} RecordItem.tag.Memory set from storage wasm:snapshot regions[{region_id}][{word_idx}]"));
+		}
 
-	code.push(format!("function {func_name}"));
-	code.push(format!("scoreboard players set {cond_taken} 1"));
+		snapshot_code.push(format!("execute if score %param0%0 reg matches {region_id} run function wasmrunner:__memory_snapshot_{region_id}"));
+		restore_code.push(format!("execute if score %param0%0 reg matches {region_id} run function wasmrunner:__memory_restore_{region_id}"));
+
+		funcs.push(parse_function(&format!("wasmrunner:__memory_snapshot_{region_id}"), save));
+		funcs.push(parse_function(&format!("wasmrunner:__memory_restore_{region_id}"), load));
+	}
+
+	funcs.push(parse_function("wasmrunner:__memory_snapshot", snapshot_code));
+	funcs.push(parse_function("wasmrunner:__memory_restore", restore_code));
 
-	let func = parse_function("wasmrunner:__return_to_saved", &code);
-	funcs.push(func);
 	funcs
 }
 
-fn create_nested_return_func(cond: Register, values: &[(usize, BlockId)], funcs: &mut Vec<Function>) {
-	let cond_taken = Register::cond_taken();
+/// The concrete colors used by the framebuffer, indexed by the low 4 bits of each pixel.
+static FRAMEBUFFER_COLORS: [&str; 16] = [
+	"white", "orange", "magenta", "light_blue", "yellow", "lime", "pink", "gray",
+	"light_gray", "cyan", "purple", "blue", "brown", "green", "red", "black",
+];
 
-	let mut code = Vec::new();
+/// The last value of each framebuffer word that was drawn, so unchanged words can be skipped.
+fn get_framebuffer_word_var(word_idx: u32) -> String {
+	format!("%%framebuffer_{word_idx} reg")
+}
 
-	match values[..] {
-		[] => panic!("nested return func was empty"),
-		[(addr, block_id)] => {
-			let func = get_mc_id(block_id);
-			code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches {addr} run function {func}"));
-		}
-		[(addr0, block_id0), (addr1, block_id1)] => {
-			let func0 = get_mc_id(block_id0);
-			code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches {addr0} run function {func0}"));
-			let func1 = get_mc_id(block_id1);
-			code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches {addr1} run function {func1}"));
-		}
-		_ => {
-			create_nested_return_func(cond, &values[..values.len() / 2], funcs);
-			let func_name_lesser = format!("wasmrunner:__return_to_saved_{}", funcs.len() - 1);
+/// Returns the world position of a framebuffer pixel.
+fn get_framebuffer_pixel_pos(fb: &Framebuffer, x: u32, y: u32) -> (i32, i32, i32) {
+	let (ox, oy, oz) = fb.origin;
+	(ox + x as i32, oy - y as i32, oz)
+}
+
+/// Clears the framebuffer wall to color 0 and starts updating it.
+fn create_framebuffer_init(fb: &Framebuffer, code: &mut Vec<String>) {
+	let color = FRAMEBUFFER_COLORS[0];
+	// Filling a row at a time keeps each fill under the block limit
+	for y in 0..fb.height {
+		let (x0, y0, z0) = get_framebuffer_pixel_pos(fb, 0, y);
+		let (x1, y1, z1) = get_framebuffer_pixel_pos(fb, fb.width - 1, y);
+		code.push(format!("fill {x0} {y0} {z0} {x1} {y1} {z1} minecraft:{color}_concrete"));
+	}
 
-			create_nested_return_func(cond, &values[values.len() / 2..], funcs);
-			let func_name_greater = format!("wasmrunner:__return_to_saved_{}", funcs.len() - 1);
+	for word_idx in 0..fb.width * fb.height / 4 {
+		code.push(format!("scoreboard players set {} 0", get_framebuffer_word_var(word_idx)));
+	}
 
-			let (addr_mid, _block_id_mid) = values[values.len() / 2];
+	code.push(format!("schedule function wasmrunner:__framebuffer {}", fb.interval));
+}
 
-			code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches ..{} run function {func_name_lesser}", addr_mid - 1));
-			code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches {}.. run function {func_name_greater}", addr_mid));
+/// Creates `wasmrunner:__framebuffer`, which redraws every word of the framebuffer that changed since it last ran
+/// and then schedules itself again, and the `wasmrunner:__framebuffer_{word}` functions that draw a single word.
+fn create_framebuffer_funcs(fb: &Framebuffer, origin: (i32, i32, i32)) -> Vec<Function> {
+	let mut funcs = Vec::new();
+	let mut code = Vec::new();
+
+	for word_idx in 0..fb.width * fb.height / 4 {
+		let word_var = get_framebuffer_word_var(word_idx);
+		let (mx, my, mz) = get_address_pos(origin, (fb.start + word_idx * 4) as i32);
+		code.push(format!("execute store result score %fb_word reg run data get block {mx} {my} {mz} RecordItem.tag.Memory 1"));
+		code.push(format!("execute unless score %fb_word reg = {word_var} run function wasmrunner:__framebuffer_{word_idx}"));
+
+		let mut draw = vec![format!("scoreboard players operation {word_var} = %fb_word reg")];
+		for byte in 0..4 {
+			let pixel = word_idx * 4 + byte;
+			let (x, y, z) = get_framebuffer_pixel_pos(fb, pixel % fb.width, pixel / fb.width);
+
+			// Bytes are taken off the bottom of the word, which is little-endian like the rest of memory
+			draw.push("scoreboard players operation %fb_pixel reg = %fb_word reg".to_string());
+			draw.push("scoreboard players operation %fb_pixel reg %= %%256 reg".to_string());
+			if byte != 3 {
+				draw.push("scoreboard players operation %fb_word reg -= %fb_pixel reg".to_string());
+				draw.push("scoreboard players operation %fb_word reg /= %%256 reg".to_string());
+			}
+			draw.push("scoreboard players operation %fb_pixel reg %= %%16 reg".to_string());
+			for (color_idx, color) in FRAMEBUFFER_COLORS.iter().enumerate() {
+				draw.push(format!("execute if score %fb_pixel reg matches {color_idx} run setblock {x} {y} {z} minecraft:{color}_concrete"));
+			}
 		}
+		funcs.push(parse_function(&format!("wasmrunner:__framebuffer_{word_idx}"), draw));
 	}
 
-	let func_name = format!("wasmrunner:__return_to_saved_{}", funcs.len());
-	let func = parse_function(&func_name, &code);
-	funcs.push(func);
+	code.push(format!("schedule function wasmrunner:__framebuffer {}", fb.interval));
+	funcs.push(parse_function("wasmrunner:__framebuffer", code));
+
+	funcs
 }
 
-fn push_return_addr(addr: BlockId, code: &mut Vec<String>) {
-	let addr_str = get_mc_id(addr);
-	let addr_var = get_block_addr_var(addr);
+/// Exports with these names are called whenever a player does something, with the player's ID as the parameter.
+/// Each event is counted in its own objective, given with the criterion that counts it.
+static PLAYER_EVENTS: [(&str, &str, &str); 4] = [
+	("on_player_use", "wasm_use", "minecraft.used:minecraft.carrot_on_a_stick"),
+	("on_player_jump", "wasm_jump", "minecraft.custom:minecraft.jump"),
+	("on_player_death", "wasm_death", "deathCount"),
+	("on_player_join", "wasm_join", "minecraft.custom:minecraft.leave_game"),
+];
 
-	code.push("data modify storage wasm:scratch stack.data set value {}".to_string());
-	code.push(format!("data modify storage wasm:scratch stack.data.'{addr_str}' set value 1"));
-	code.push(format!("execute store result storage wasm:scratch stack.data.ptr int 1 run scoreboard players get {}", addr_var));
-	code.push("data modify storage wasm:scratch stack.tail set from storage wasm:returnstack stack".to_string());
-	code.push("data modify storage wasm:returnstack stack set from storage wasm:scratch stack".to_string());
+/// The objective holding the ID given to each player, starting at 1.
+pub static PLAYER_ID_OBJECTIVE: &str = "wasm_player_id";
+
+/// The ID that was given to the most recent new player.
+static LAST_PLAYER_ID_VAR: &str = "%%last_player_id reg";
+
+/// Returns the player events the program has exports for, as the export name and the objective counting it.
+pub fn get_player_events(exports: &HashMap<String, BlockId>) -> Vec<(&'static str, &'static str)> {
+	PLAYER_EVENTS.iter()
+		.filter(|(name, _, _)| exports.contains_key(*name))
+		.map(|&(name, objective, _)| (name, objective))
+		.collect()
 }
 
-fn pop_return_addr(code: &mut Vec<String>) {
-	code.push("data modify storage wasm:returnstack stack set from storage wasm:returnstack stack.tail".to_string());
+/// Returns true if the program reacts to players, either with player event exports or exports called with `/trigger`.
+pub fn has_player_input(ctx: &CompileContext, exports: &HashMap<String, BlockId>) -> bool {
+	!get_player_events(exports).is_empty() || !ctx.triggers.is_empty()
 }
 
-fn push_data(regs: &[Register], code: &mut Vec<String>) {
-	let arr = create_array_with_consts(regs);
-	code.push(format!("data modify storage wasm:scratch stack.data set value {arr}"));
-	for (idx, reg) in regs.iter().enumerate() {
-		if reg.get_const().is_some() { continue; }
-		code.push(format!("execute store result storage wasm:scratch stack.data[{idx}] int 1 run scoreboard players get {reg}"));
+fn create_player_events_init(ctx: &CompileContext, exports: &HashMap<String, BlockId>, code: &mut Vec<String>) {
+	code.push(format!("scoreboard objectives remove {PLAYER_ID_OBJECTIVE}"));
+	code.push(format!("scoreboard objectives add {PLAYER_ID_OBJECTIVE} dummy"));
+	code.push(format!("scoreboard players set {LAST_PLAYER_ID_VAR} 0"));
+
+	for (name, objective, criterion) in PLAYER_EVENTS.iter() {
+		if exports.contains_key(*name) {
+			code.push(format!("scoreboard objectives remove {objective}"));
+			code.push(format!("scoreboard objectives add {objective} {criterion}"));
+		}
 	}
-	code.push("data modify storage wasm:scratch stack.tail set from storage wasm:datastack stack".to_string());
-	code.push("data modify storage wasm:datastack stack set from storage wasm:scratch stack".to_string());
-}
 
-fn pop_data(regs: &[Register], code: &mut Vec<String>) {
-	for (idx, reg) in regs.iter().enumerate() {
-		code.push(format!("execute store result score {reg} run data get storage wasm:datastack stack.data[{idx}] 1"));
+	for (_, objective) in ctx.triggers.iter() {
+		code.push(format!("scoreboard objectives remove {objective}"));
+		code.push(format!("scoreboard objectives add {objective} trigger"));
 	}
-	code.push("data modify storage wasm:datastack stack set from storage wasm:datastack stack.tail".to_string());
+
+	code.push("schedule function wasmrunner:__player_events 1".to_string());
 }
 
-fn create_array_with_consts(regs: &[Register]) -> String {
-	let mut arr = '['.to_string();
-	for (i, r) in regs.iter().enumerate() {
-		if let Some(r) = r.get_const() {
-			arr.push_str(&r.to_string())
-		} else {
-			arr.push('0');
+/// Creates `wasmrunner:__player_events`, which runs every tick to give new players an ID
+/// and call the exports for anything players did or triggered since the last tick.
+fn create_player_events_funcs(ctx: &CompileContext, exports: &HashMap<String, BlockId>) -> Vec<Function> {
+	let events = get_player_events(exports);
+
+	let mut new_player = vec![
+		format!("scoreboard players add {LAST_PLAYER_ID_VAR} 1"),
+		format!("scoreboard players operation @s {PLAYER_ID_OBJECTIVE} = {LAST_PLAYER_ID_VAR}"),
+	];
+	// Leaving the game is only counted after the first time a player joined, so that one is counted here instead
+	if let Some((_, objective)) = events.iter().find(|(name, _)| *name == "on_player_join") {
+		new_player.push(format!("scoreboard players set @s {objective} 1"));
+	}
+
+	let mut code = vec![format!("execute as @a unless score @s {PLAYER_ID_OBJECTIVE} matches 1.. run function wasmrunner:__new_player")];
+	let mut funcs = vec![parse_function("wasmrunner:__new_player", new_player)];
+
+	for (name, objective) in events {
+		code.push(format!("execute as @a if score @s {objective} matches 1.. run function wasmrunner:__{name}"));
+
+		let call = [
+			format!("scoreboard players set @s {objective} 0"),
+			format!("scoreboard players operation {} = @s {PLAYER_ID_OBJECTIVE}", Register::param_lo(0)),
+			format!("function wasmrunner:{name}"),
+		];
+		funcs.push(parse_function(&format!("wasmrunner:__{name}"), call));
+	}
+
+	for (name, objective) in ctx.triggers.iter() {
+		if !exports.contains_key(name) {
+			panic!("--trigger given for {name:?}, which is not an export");
+		}
+
+		// Triggers can only be used once until they are enabled again
+		code.push(format!("scoreboard players enable @a {objective}"));
+		// Players that never used the trigger have no score at all, which `unless score` would match
+		code.push(format!("execute as @a unless score @s {objective} matches 0 if score @s {objective} matches -2147483648.. run function wasmrunner:__trigger_{name}"));
+
+		let call = [
+			format!("scoreboard players operation {} = @s {objective}", Register::param_lo(0)),
+			format!("scoreboard players operation {} = @s {PLAYER_ID_OBJECTIVE}", Register::param_lo(1)),
+			format!("scoreboard players set @s {objective} 0"),
+			format!("function wasmrunner:{name}"),
+		];
+		funcs.push(parse_function(&format!("wasmrunner:__trigger_{name}"), call));
+	}
+
+	code.push("schedule function wasmrunner:__player_events 1".to_string());
+	funcs.push(parse_function("wasmrunner:__player_events", code));
+
+	funcs
+}
+
+/// Returns how many ticks apart an export is called if its name is `tick` or `tick_N`.
+fn get_tick_interval_from_name(name: &str) -> Option<u32> {
+	if name == "tick" {
+		return Some(1);
+	}
+	name.strip_prefix("tick_")?.parse::<u32>().ok().filter(|&interval| interval > 0)
+}
+
+/// Returns the exports that are called on a loop and how many ticks apart they are called, sorted by name.
+/// These are the ones given with `--tick`, and the ones named `tick` or `tick_N` that don't take any parameters.
+pub fn get_tick_exports(ctx: &CompileContext, exports: &HashMap<String, BlockId>, takes_params: impl Fn(BlockId) -> bool) -> Vec<(String, u32)> {
+	let mut result = Vec::new();
+
+	for (name, interval) in ctx.tick_exports.iter() {
+		let id = *exports.get(name).unwrap_or_else(|| panic!("--tick given for {name:?}, which is not an export"));
+		if takes_params(id) {
+			panic!("--tick given for {name:?}, which takes parameters");
+		}
+		result.push((name.clone(), *interval));
+	}
+
+	for (name, &id) in exports.iter() {
+		if result.iter().any(|(n, _)| n == name) || takes_params(id) {
+			continue;
+		}
+		if let Some(interval) = get_tick_interval_from_name(name) {
+			result.push((name.clone(), interval));
+		}
+	}
+
+	result.sort();
+	result
+}
+
+fn create_tick_init(ticks: &[(String, u32)], code: &mut Vec<String>) {
+	for (name, _) in ticks.iter() {
+		code.push(format!("schedule function wasmrunner:__tick_{name} 1"));
+	}
+}
+
+/// Creates a `wasmrunner:__tick_{name}` function for each export called on a loop,
+/// which schedules itself again before calling the export so the loop continues even if the export doesn't finish.
+fn create_tick_funcs(ticks: &[(String, u32)]) -> Vec<Function> {
+	ticks.iter().map(|(name, interval)| {
+		let code = [
+			format!("schedule function wasmrunner:__tick_{name} {interval}"),
+			format!("function wasmrunner:{name}"),
+		];
+		parse_function(&format!("wasmrunner:__tick_{name}"), code)
+	}).collect()
+}
+
+/// Returns every block that may be run using `schedule` instead of being called directly,
+/// which in bounce mode are the blocks run by `wasmrunner:__bounce` instead.
+fn scheduled_blocks(program: &LirProgram) -> Vec<BlockId> {
+	let mut blocks = Vec::new();
+	for func in program.code.iter() {
+		for (_, block) in func.code.iter() {
+			match &block.term {
+				&LirTerminator::ScheduleJump(target, _) => blocks.push(target),
+				LirTerminator::Jump(target) if target.cmd_check => blocks.push(target.label),
+				LirTerminator::JumpIf { true_label, false_label, .. } => {
+					if true_label.cmd_check {
+						blocks.push(true_label.label);
+					}
+					if false_label.cmd_check {
+						blocks.push(false_label.label);
+					}
+				}
+				_ => {}
+			}
+		}
+	}
+	blocks.sort();
+	blocks.dedup();
+	blocks
+}
+
+fn make_button(text: &str, color: &str, command: &str) -> String {
+	format!(r#"{{"text":"[{text}]","color":"{color}","clickEvent":{{"action":"run_command","value":"/{command}"}}}}"#)
+}
+
+/// Creates `wasmrunner:controls`, which shows a clickable menu in chat for operating the program,
+/// along with the `wasmrunner:__stop`, `wasmrunner:__run_tests`, and `wasmrunner:__status` functions it uses.
+fn create_controls_funcs(program: &LirProgram, ticks: &[(String, u32)]) -> Vec<Function> {
+	let mut exports = program.exports.keys().collect::<Vec<_>>();
+	exports.sort();
+
+	let mut funcs = Vec::new();
+
+	// Stopping is done by cancelling anything that would resume the program on a later tick
+	let scheduled = match program.jump_mode {
+		JumpMode::Direct => scheduled_blocks(program).into_iter().map(|id| get_mc_id(&program.func_names, id)).collect(),
+		JumpMode::Bounce => vec!["wasmrunner:__bounce_resume".to_string()],
+	};
+	let stop_code = scheduled.into_iter()
+		.map(|id| format!("schedule clear {id}"))
+		.chain(ticks.iter().map(|(name, _)| format!("schedule clear wasmrunner:__tick_{name}")))
+		.chain(std::iter::once(r#"tellraw @a [{"text":"Program stopped"}]"#.to_string()));
+	funcs.push(parse_function("wasmrunner:__stop", stop_code));
+
+	let tests = exports.iter().filter(|name| name.starts_with("test")).collect::<Vec<_>>();
+	let test_code = tests.iter().flat_map(|name| [
+		format!(r#"tellraw @a [{{"text":"Running {name}"}}]"#),
+		format!("function wasmrunner:{name}"),
+	]);
+	funcs.push(parse_function("wasmrunner:__run_tests", test_code));
+
+	let status_code = [
+		format!(r#"tellraw @a [{{"text":"Commands run this tick: "}},{{"score":{{"name":"{}","objective":"reg"}}}}]"#, CMDS_RUN_VAR.split_once(' ').unwrap().0),
+		format!(r#"tellraw @a [{{"text":"Command limit per tick: "}},{{"score":{{"name":"{}","objective":"reg"}}}}]"#, MAX_CMDS_VAR.split_once(' ').unwrap().0),
+		format!(r#"tellraw @a [{{"text":"Waiting for next tick: "}},{{"score":{{"name":"{}","objective":"reg"}}}}]"#, Register::sleep_needed().to_string().split_once(' ').unwrap().0),
+	];
+	funcs.push(parse_function("wasmrunner:__status", status_code));
+
+	let mut buttons = vec![
+		make_button("Reset", "yellow", "function wasmrunner:init"),
+		make_button("Stop", "red", "function wasmrunner:__stop"),
+	];
+	if !tests.is_empty() {
+		buttons.push(make_button("Run tests", "aqua", "function wasmrunner:__run_tests"));
+	}
+	buttons.push(make_button("Status", "gray", "function wasmrunner:__status"));
+
+	let mut code = vec![
+		r#"tellraw @s [{"text":"Wasmcraft controls","bold":true}]"#.to_string(),
+		format!(r#"tellraw @s [{}]"#, buttons.join(r#",{"text":" "},"#)),
+	];
+	for name in exports.iter().filter(|name| !name.starts_with("test")) {
+		let label = if name.as_str() == "_start" { "Start".to_string() } else { format!("Run {name}") };
+		code.push(format!(r#"tellraw @s [{}]"#, make_button(&label, "green", &format!("function wasmrunner:{name}"))));
+	}
+	funcs.push(parse_function("wasmrunner:controls", code));
+
+	funcs
+}
+
+/// Marks a line that is only written for versions with function macros, replacing the other lines of its function.
+/// Macro lines are comments otherwise, so the interpreter, which doesn't support macros, runs the other lines instead.
+static MACRO_LINE_PREFIX: &str = "# !MACRO: ";
+
+fn macro_line(line: &str) -> String {
+	format!("{MACRO_LINE_PREFIX}{line}")
+}
+
+/// Marks a line that is only written for versions with `return run`, and dropped otherwise.
+/// Return lines are placed before the lines they stand in for and return from the function, so those never run.
+static RETURN_LINE_PREFIX: &str = "# !RETURN: ";
+
+fn return_line(line: &str) -> String {
+	format!("{RETURN_LINE_PREFIX}{line}")
+}
+
+/// Creates the functions used for dynamic jumps with macros:
+/// `wasmrunner:__call_target`, which calls the function named by its `target` argument,
+/// `wasmrunner:__jump_table_lookup`, which sets `jump.target` to the entry of `jump.table` at its `idx` argument,
+/// and `wasmrunner:__bad_jump_table`, which is called for jump table entries that don't exist.
+fn create_macro_dispatch_funcs() -> Vec<Function> {
+	vec![
+		parse_function("wasmrunner:__call_target", [macro_line("$function $(target)")]),
+		parse_function("wasmrunner:__jump_table_lookup", [macro_line("$data modify storage wasm:scratch jump.target set from storage wasm:scratch jump.table[$(idx)]")]),
+		parse_function("wasmrunner:__bad_jump_table", [macro_line(r#"tellraw @a [{"text":"BAD JUMP TABLE"}]"#)]),
+	]
+}
+
+fn get_jump_table_func(block_id: BlockId) -> String {
+	format!("wasmrunner:__jump_table_{}_{}", block_id.func, block_id.block)
+}
+
+/// The commands that jump to the arm selected by `cond`, or to the default if there isn't one.
+fn jump_table_code(arms: &[Option<BlockId>], default: Option<BlockId>, cond: Register, func_names: &HashMap<usize, String>) -> Vec<String> {
+	let cond_taken = Register::cond_taken();
+
+	let mut code = vec![format!("scoreboard players set {cond_taken} 0")];
+
+	for (idx, arm) in arms.iter().enumerate() {
+		if let Some(arm) = arm {
+			let arm_func = get_mc_id(func_names, *arm);
+			code.push(format!("execute if score {cond_taken} matches 0 run execute if score {cond} matches {idx} run function {arm_func}"));
+		}
+	}
+
+	if let Some(default) = default {
+		let default_func = get_mc_id(func_names, default);
+
+		code.push(format!("execute if score {cond_taken} matches 0 run function {default_func}"));
+	} else {
+		code.push(format!("# !INTERPRETER: ASSERT unless score {cond_taken} matches 0"));
+		code.push(format!("execute if score {cond_taken} matches 0 run tellraw @a [{{\"text\":\"BAD JUMP TABLE\"}}]"));
+	}
+
+	code
+}
+
+/// Creates the function a block ending in a jump table calls to jump to the selected arm.
+/// With macros, the arm is looked up in a list of function names instead of being compared against each index.
+fn create_jump_table_func(block_id: BlockId, arms: &[Option<BlockId>], default: Option<BlockId>, cond: Register, func_names: &HashMap<usize, String>) -> Function {
+	let mut code = gate_after_traps(jump_table_code(arms, default, cond, func_names));
+
+	let default_func = default.map(|default| get_mc_id(func_names, default)).unwrap_or_else(|| "wasmrunner:__bad_jump_table".to_string());
+	let table = arms.iter()
+		.map(|arm| format!("\"{}\"", arm.map(|arm| get_mc_id(func_names, arm)).unwrap_or_else(|| default_func.clone())))
+		.collect::<Vec<_>>();
+
+	let mut macro_code = vec![format!("data modify storage wasm:scratch jump.target set value \"{default_func}\"")];
+	if !arms.is_empty() {
+		macro_code.push(format!("data modify storage wasm:scratch jump.table set value [{}]", table.join(",")));
+		macro_code.push(format!("execute store result storage wasm:scratch jump.idx int 1 run scoreboard players get {cond}"));
+		macro_code.push(format!("execute if score {cond} matches 0..{} run function wasmrunner:__jump_table_lookup with storage wasm:scratch jump", arms.len() - 1));
+	}
+	macro_code.push("function wasmrunner:__call_target with storage wasm:scratch jump".to_string());
+	code.extend(macro_code.iter().map(|line| macro_line(line)));
+
+	parse_function(&get_jump_table_func(block_id), code)
+}
+
+fn create_return_to_saved_func(program: &LirProgram, time_sliced: bool) -> Vec<Function> {
+	let reg = Register::temp_lo(0);
+
+	let mut code = Vec::new();
+
+	code.push(format!("scoreboard players set {reg} -1"));
+	let cond_taken = Register::cond_taken();
+	code.push(format!("scoreboard players set {cond_taken} 0"));
+	code.push("data modify storage wasm:scratch stack.data set from storage wasm:returnstack stack.data".to_string());
+	code.push(format!("execute store result score {reg} run data get storage wasm:returnstack stack.data.ptr 1"));
+	code.push("data modify storage wasm:returnstack stack set from storage wasm:returnstack stack.tail".to_string());
+
+	if time_sliced {
+		code.push(format!("execute if score {reg} matches {TIME_SLICE_SENTINEL} run function wasmrunner:__time_slice_done"));
+	}
+
+	let blocks = program.all_block_ids().enumerate().collect::<Vec<_>>();
+
+	let mut funcs = Vec::new();
+	create_nested_return_func(&program.func_names, "wasmrunner:__return_to_saved", reg, &blocks, &mut funcs);
+
+	let func_name = format!("wasmrunner:__return_to_saved_{}", funcs.len() - 1);
+
+	code.push(format!("function {func_name}"));
+	code.push(format!("scoreboard players set {cond_taken} 1"));
+
+	// With macros, the block is called by the name stored with its return address instead of searching for it
+	let mut macro_code = code[..code.len() - 2].to_vec();
+	macro_code.push(format!("execute if score {reg} matches 0.. run function wasmrunner:__call_target with storage wasm:scratch stack.data"));
+	macro_code.push(format!("scoreboard players set {cond_taken} 1"));
+	code.extend(macro_code.iter().map(|line| macro_line(line)));
+
+	let func = parse_function("wasmrunner:__return_to_saved", &code);
+	funcs.push(func);
+	funcs
+}
+
+fn create_nested_return_func(func_names: &HashMap<usize, String>, prefix: &str, cond: Register, values: &[(usize, BlockId)], funcs: &mut Vec<Function>) {
+	let cond_taken = Register::cond_taken();
+
+	let mut code = Vec::new();
+
+	match values[..] {
+		[] => panic!("nested return func was empty"),
+		[(addr, block_id)] => {
+			let func = get_mc_id(func_names, block_id);
+			code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches {addr} run function {func}"));
+		}
+		[(addr0, block_id0), (addr1, block_id1)] => {
+			let func0 = get_mc_id(func_names, block_id0);
+			code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches {addr0} run function {func0}"));
+			let func1 = get_mc_id(func_names, block_id1);
+			code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches {addr1} run function {func1}"));
+		}
+		_ => {
+			create_nested_return_func(func_names, prefix, cond, &values[..values.len() / 2], funcs);
+			let func_name_lesser = format!("{prefix}_{}", funcs.len() - 1);
+
+			create_nested_return_func(func_names, prefix, cond, &values[values.len() / 2..], funcs);
+			let func_name_greater = format!("{prefix}_{}", funcs.len() - 1);
+
+			let (addr_mid, _block_id_mid) = values[values.len() / 2];
+
+			code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches ..{} run function {func_name_lesser}", addr_mid - 1));
+			code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches {}.. run function {func_name_greater}", addr_mid));
+		}
+	}
+
+	let func_name = format!("{prefix}_{}", funcs.len());
+	let func = parse_function(&func_name, &code);
+	funcs.push(func);
+}
+
+/// In bounce mode, jumps that check the command count set the block to continue at and return
+/// all the way to `wasmrunner:__bounce`, instead of calling the block themselves.
+fn bounce_to(func_names: &HashMap<usize, String>, target: BlockId) -> Vec<String> {
+	vec![
+		format!("scoreboard players operation {BOUNCE_NEXT_VAR} = {}", get_block_addr_var(target)),
+		format!("data modify storage wasm:bounce next.target set value \"{}\"", get_mc_id(func_names, target)),
+	]
+}
+
+/// Continues at the block set by `bounce_to` after `delay` ticks, instead of right away.
+fn bounce_yield(delay: u32) -> Vec<String> {
+	vec![
+		format!("scoreboard players operation {BOUNCE_RESUME_VAR} = {BOUNCE_NEXT_VAR}"),
+		"data modify storage wasm:bounce resume set from storage wasm:bounce next".to_string(),
+		format!("scoreboard players set {BOUNCE_NEXT_VAR} -1"),
+		format!("schedule function wasmrunner:__bounce_resume {delay}"),
+	]
+}
+
+/// Creates the dispatcher used in bounce mode: `wasmrunner:__bounce`, which runs blocks for as long as
+/// they bounce to another one and yields once too many commands were run, `wasmrunner:__bounce_step`,
+/// which runs a single block, and `wasmrunner:__bounce_resume`, which continues after a yield.
+fn create_bounce_funcs(program: &LirProgram, persist_state: bool) -> Vec<Function> {
+	let reg = Register::temp_lo(0);
+	let sleep_reg = Register::sleep_needed();
+	let cond_taken = Register::cond_taken();
+
+	let mut funcs = Vec::new();
+
+	let mut code = vec![
+		format!("execute store success score {sleep_reg} if score {CMDS_RUN_VAR} >= {MAX_CMDS_VAR}"),
+		format!("execute if score {sleep_reg} matches 1 run scoreboard players set {CMDS_RUN_VAR} 0"),
+	];
+	code.extend(bounce_yield(1).iter().map(|line| format!("execute if score {sleep_reg} matches 1 run {line}")));
+	if persist_state {
+		code.push(format!("execute if score {sleep_reg} matches 1 run function wasmrunner:__checkpoint"));
+	}
+	code.push(return_line(&format!("execute if score {sleep_reg} matches 0 run return run function wasmrunner:__bounce_step")));
+	code.push(format!("execute if score {sleep_reg} matches 0 run function wasmrunner:__bounce_step"));
+	funcs.push(parse_function("wasmrunner:__bounce", code));
+
+	let mut code = vec![
+		format!("scoreboard players operation {reg} = {BOUNCE_NEXT_VAR}"),
+		format!("scoreboard players set {BOUNCE_NEXT_VAR} -1"),
+		format!("scoreboard players set {cond_taken} 0"),
+	];
+
+	// Only blocks that are bounced to need to be found
+	let targets = scheduled_blocks(program);
+	let blocks = program.all_block_ids().enumerate().filter(|(_, id)| targets.contains(id)).collect::<Vec<_>>();
+	let mut macro_code = code.clone();
+	if !blocks.is_empty() {
+		create_nested_return_func(&program.func_names, "wasmrunner:__bounce_to", reg, &blocks, &mut funcs);
+		code.push(format!("function wasmrunner:__bounce_to_{}", funcs.len() - 1));
+	}
+	macro_code.push(format!("execute unless score {reg} matches -1 run function wasmrunner:__call_target with storage wasm:bounce next"));
+
+	// Every version with macros also has `return run`
+	let bounce_again = format!("execute unless score {BOUNCE_NEXT_VAR} matches -1 run return run function wasmrunner:__bounce");
+	code.push(format!("scoreboard players set {cond_taken} 1"));
+	code.push(return_line(&bounce_again));
+	code.push(format!("execute unless score {BOUNCE_NEXT_VAR} matches -1 run function wasmrunner:__bounce"));
+	macro_code.push(format!("scoreboard players set {cond_taken} 1"));
+	macro_code.push(bounce_again);
+	code.extend(macro_code.iter().map(|line| macro_line(line)));
+	funcs.push(parse_function("wasmrunner:__bounce_step", code));
+
+	let mut code = vec![
+		format!("scoreboard players operation {BOUNCE_NEXT_VAR} = {BOUNCE_RESUME_VAR}"),
+		"data modify storage wasm:bounce next set from storage wasm:bounce resume".to_string(),
+	];
+	if persist_state {
+		// The program is no longer paused, and takes another checkpoint if it yields again
+		code.push("data remove storage wasm:checkpoint scores".to_string());
+	}
+	code.push("function wasmrunner:__bounce".to_string());
+	funcs.push(parse_function("wasmrunner:__bounce_resume", code));
+
+	funcs
+}
+
+fn push_return_addr(func_names: &HashMap<usize, String>, addr: BlockId, code: &mut Vec<String>) {
+	let addr_str = get_mc_id(func_names, addr);
+	let addr_var = get_block_addr_var(addr);
+
+	code.push("data modify storage wasm:scratch stack.data set value {}".to_string());
+	code.push(format!("data modify storage wasm:scratch stack.data.target set value \"{addr_str}\""));
+	code.push(format!("execute store result storage wasm:scratch stack.data.ptr int 1 run scoreboard players get {}", addr_var));
+	code.push("data modify storage wasm:scratch stack.tail set from storage wasm:returnstack stack".to_string());
+	code.push("data modify storage wasm:returnstack stack set from storage wasm:scratch stack".to_string());
+}
+
+fn pop_return_addr(code: &mut Vec<String>) {
+	code.push("data modify storage wasm:returnstack stack set from storage wasm:returnstack stack.tail".to_string());
+}
+
+fn push_data(regs: &[Register], code: &mut Vec<String>) {
+	let arr = create_array_with_consts(regs);
+	code.push(format!("data modify storage wasm:scratch stack.data set value {arr}"));
+	for (idx, reg) in regs.iter().enumerate() {
+		if reg.get_const().is_some() { continue; }
+		code.push(format!("execute store result storage wasm:scratch stack.data[{idx}] int 1 run scoreboard players get {reg}"));
+	}
+	code.push("data modify storage wasm:scratch stack.tail set from storage wasm:datastack stack".to_string());
+	code.push("data modify storage wasm:datastack stack set from storage wasm:scratch stack".to_string());
+}
+
+fn pop_data(regs: &[Register], code: &mut Vec<String>) {
+	for (idx, reg) in regs.iter().enumerate() {
+		code.push(format!("execute store result score {reg} run data get storage wasm:datastack stack.data[{idx}] 1"));
+	}
+	code.push("data modify storage wasm:datastack stack set from storage wasm:datastack stack.tail".to_string());
+}
+
+fn create_array_with_consts(regs: &[Register]) -> String {
+	let mut arr = '['.to_string();
+	for (i, r) in regs.iter().enumerate() {
+		if let Some(r) = r.get_const() {
+			arr.push_str(&r.to_string())
+		} else {
+			arr.push('0');
 		}
 		if i != regs.len() - 1 {
 			arr.push_str(", ");
@@ -326,8 +1446,25 @@ fn local_get(dst: Register, src: u32, half: Half, code: &mut Vec<String>) {
 	code.push(format!("execute store result score {dst} run data get storage wasm:localstack stack.data[{idx}]"));
 }
 
-pub fn get_mc_id(block_id: BlockId) -> String {
-	format!("wasmrunner:wasm_{}_{}", block_id.func, block_id.block)
+/// The most characters of a function's name that are included in the names of its blocks.
+const MAX_FUNC_NAME_LEN: usize = 64;
+
+/// Turns a name from the wasm `name` section into something that can be part of a function ID,
+/// e.g. `my_crate::render` becomes `my_crate__render`.
+fn sanitize_func_name(name: &str) -> String {
+	name.chars()
+		.map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+		.take(MAX_FUNC_NAME_LEN)
+		.collect()
+}
+
+/// Returns the ID of the datapack function a block is emitted as,
+/// which includes the name of its wasm function if the module has one.
+pub fn get_mc_id(func_names: &HashMap<usize, String>, block_id: BlockId) -> String {
+	match func_names.get(&block_id.func) {
+		Some(name) => format!("wasmrunner:wasm_{}_{}_{}", block_id.func, sanitize_func_name(name), block_id.block),
+		None => format!("wasmrunner:wasm_{}_{}", block_id.func, block_id.block),
+	}
 }
 
 /// To avoid needing to do fixups, each block has its "address"
@@ -336,12 +1473,41 @@ pub fn get_block_addr_var(block_id: BlockId) -> String {
 	format!("%%returnaddr_wasm_{}_{} reg", block_id.func, block_id.block)
 }
 
-fn mem_store_unaligned_32(src: Register, addr: i32, offset: i32, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+/// Counts how often a block runs with `--profile`.
+pub fn get_profile_var(block_id: BlockId) -> String {
+	format!("%profile%{}%{} reg", block_id.func, block_id.block)
+}
+
+/// A copy of a block's count, which `wasmrunner:profile_dump` sets to -1 once it has printed the count.
+fn get_profile_rank_var(block_id: BlockId) -> String {
+	format!("%profile_rank%{}%{} reg", block_id.func, block_id.block)
+}
+
+pub fn get_table_entry_var(table: u32, idx: usize) -> String {
+	format!("%%table_{table}_{idx} reg")
+}
+
+pub fn get_table_size_var(table: u32) -> String {
+	format!("%%table_{table}_size reg")
+}
+
+/// The length of an element segment, which becomes 0 once it is dropped.
+pub fn get_elem_len_var(segment: u32) -> String {
+	format!("%%elem_{segment}_len reg")
+}
+
+fn emit_table_bounds_check(table: u32, index: &str, block_id: BlockId, func_names: &HashMap<usize, String>, code: &mut Vec<String>) {
+	let size_var = get_table_size_var(table);
+	emit_trap(TrapKind::TableOutOfBounds, block_id, func_names, Some(&format!("unless score {index} matches 0..")), code);
+	emit_trap(TrapKind::TableOutOfBounds, block_id, func_names, Some(&format!("if score {index} >= {size_var}")), code);
+}
+
+fn mem_store_unaligned_32(src: Register, addr: i32, offset: i32, origin: (i32, i32, i32), code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	assert!(offset > 0);
 	assert!(offset < 4);
 
-	let (x0, y0, z0) = get_address_pos(addr - offset);
-	let (x1, y1, z1) = get_address_pos(addr - offset + 4);
+	let (x0, y0, z0) = get_address_pos(origin, addr - offset);
+	let (x1, y1, z1) = get_address_pos(origin, addr - offset + 4);
 
 	let tmp1 = Register::temp_lo(100_000);
 	let tmp2 = Register::temp_lo(100_001);
@@ -368,7 +1534,7 @@ fn mem_store_unaligned_32(src: Register, addr: i32, offset: i32, code: &mut Vec<
 }
 
 //noinspection RsConstantConditionIf
-fn mem_store_32(src: Register, addr: RegisterWithInfo, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+fn mem_store_32(src: Register, addr: RegisterWithInfo, origin: (i32, i32, i32), code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	if INSERT_MEM_PRINTS {
 		code.push(tellraw_mem_store(32, src, addr.0));
 	}
@@ -376,10 +1542,10 @@ fn mem_store_32(src: Register, addr: RegisterWithInfo, code: &mut Vec<String>, c
 	if let Some(addr) = addr.get_const() {
 		match addr % 4 {
 			0 => {
-				let (x, y, z) = get_address_pos(addr);
+				let (x, y, z) = get_address_pos(origin, addr);
 				code.push(format!("execute store result block {x} {y} {z} RecordItem.tag.Memory int 1 run scoreboard players get {src}"));
 			}
-			offset@(1 | 2 | 3) => { mem_store_unaligned_32(src, addr, offset, code, const_pool); }
+			offset@(1 | 2 | 3) => { mem_store_unaligned_32(src, addr, offset, origin, code, const_pool); }
 			_ => unreachable!(),
 		}
 	} else if ENABLE_MEM_OPTS && addr.1 != StaticValue::unknown() && addr.1 != (BitMask { set_bits: 0, clr_bits: 1 }.into()) {
@@ -431,7 +1597,7 @@ fn mem_store_32(src: Register, addr: RegisterWithInfo, code: &mut Vec<String>, c
 }
 
 //noinspection RsConstantConditionIf
-fn mem_store_16(src: Register, addr: RegisterWithInfo, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+fn mem_store_16(src: Register, addr: RegisterWithInfo, origin: (i32, i32, i32), code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	if INSERT_MEM_PRINTS {
 		code.push(tellraw_mem_store(16, src, addr.0));
 	}
@@ -439,7 +1605,7 @@ fn mem_store_16(src: Register, addr: RegisterWithInfo, code: &mut Vec<String>, c
 	if let Some(addr) = addr.get_const() {
 		match addr % 4 {
 			0 => {
-				let (x, y, z) = get_address_pos(addr);
+				let (x, y, z) = get_address_pos(origin, addr);
 				code.push(format!("scoreboard players operation %param2%0 reg = {src}"));
 				code.push("scoreboard players operation %param2%0 reg %= %%65536 reg".to_string());
 				code.push(format!("execute store result score %param0%0 reg run data get block {x} {y} {z} RecordItem.tag.Memory 1"));
@@ -452,7 +1618,7 @@ fn mem_store_16(src: Register, addr: RegisterWithInfo, code: &mut Vec<String>, c
 				code.push(format!("execute store result block {x} {y} {z} RecordItem.tag.Memory int 1 run scoreboard players get %return%0 reg"));
 			}
 			1 => {
-				let (x, y, z) = get_address_pos(addr - 1);
+				let (x, y, z) = get_address_pos(origin, addr - 1);
 
 				let tmp1 = Register::temp_lo(4321);
 				let tmp2 = Register::temp_lo(4322);
@@ -465,7 +1631,7 @@ fn mem_store_16(src: Register, addr: RegisterWithInfo, code: &mut Vec<String>, c
 				code.push(format!("execute store result block {x} {y} {z} RecordItem.tag.Memory int 1 run scoreboard players get {tmp1}"));
 			}
 			2 => {
-				let (x, y, z) = get_address_pos(addr - 2);
+				let (x, y, z) = get_address_pos(origin, addr - 2);
 
 				code.push(format!("execute store result score %param0%0 reg run data get block {x} {y} {z} RecordItem.tag.Memory 1"));
 				code.push("scoreboard players operation %param0%0 reg %= %%65536 reg".to_string());
@@ -493,7 +1659,7 @@ fn mem_store_16(src: Register, addr: RegisterWithInfo, code: &mut Vec<String>, c
 }
 
 //noinspection RsConstantConditionIf
-fn mem_store_8 (src: Register, addr: RegisterWithInfo, code: &mut Vec<String>) {
+fn mem_store_8 (src: Register, addr: RegisterWithInfo, origin: (i32, i32, i32), code: &mut Vec<String>) {
 	if INSERT_MEM_PRINTS {
 		code.push(tellraw_mem_store(8, src, addr.0));
 	}
@@ -501,7 +1667,7 @@ fn mem_store_8 (src: Register, addr: RegisterWithInfo, code: &mut Vec<String>) {
 	if let Some(addr) = addr.get_const() {
 		match addr % 4 {
 			0 => {
-				let (x, y, z) = get_address_pos(addr);
+				let (x, y, z) = get_address_pos(origin, addr);
 				code.push(format!("scoreboard players operation %param2%0 reg = {src}"));
 				code.push("scoreboard players operation %param2%0 reg %= %%256 reg".to_string());
 				code.push(format!("execute store result score %param0%0 reg run data get block {x} {y} {z} RecordItem.tag.Memory 1"));
@@ -514,7 +1680,7 @@ fn mem_store_8 (src: Register, addr: RegisterWithInfo, code: &mut Vec<String>) {
 				code.push(format!("execute store result block {x} {y} {z} RecordItem.tag.Memory int 1 run scoreboard players get %return%0 reg"));
 			}
 			1 => {
-				let (x, y, z) = get_address_pos(addr - 1);
+				let (x, y, z) = get_address_pos(origin, addr - 1);
 				code.push(format!("scoreboard players operation %param2%0 reg = {src}"));
 				code.push("scoreboard players operation %param2%0 reg %= %%256 reg".to_string());
 				code.push(format!("execute store result score %param0%0 reg run data get block {x} {y} {z} RecordItem.tag.Memory 1"));
@@ -530,7 +1696,7 @@ fn mem_store_8 (src: Register, addr: RegisterWithInfo, code: &mut Vec<String>) {
 				code.push(format!("execute store result block {x} {y} {z} RecordItem.tag.Memory int 1 run scoreboard players get %return%0 reg"));
 			}
 			2 => {
-				let (x, y, z) = get_address_pos(addr - 2);
+				let (x, y, z) = get_address_pos(origin, addr - 2);
 				code.push(format!("scoreboard players operation %param2%0 reg = {src}"));
 				code.push("scoreboard players operation %param2%0 reg %= %%256 reg".to_string());
 				code.push(format!("execute store result score %param0%0 reg run data get block {x} {y} {z} RecordItem.tag.Memory 1"));
@@ -546,7 +1712,7 @@ fn mem_store_8 (src: Register, addr: RegisterWithInfo, code: &mut Vec<String>) {
 				code.push(format!("execute store result block {x} {y} {z} RecordItem.tag.Memory int 1 run scoreboard players get %return%0 reg"));
 			}
 			3 => {
-				let (x, y, z) = get_address_pos(addr - 3);
+				let (x, y, z) = get_address_pos(origin, addr - 3);
 				code.push(format!("scoreboard players operation %param2%0 reg = {src}"));
 				code.push("scoreboard players operation %param2%0 reg %= %%256 reg".to_string());
 				code.push(format!("execute store result score %param0%0 reg run data get block {x} {y} {z} RecordItem.tag.Memory 1"));
@@ -590,14 +1756,17 @@ fn mem_store_8 (src: Register, addr: RegisterWithInfo, code: &mut Vec<String>) {
 }
 
 const PAGE_SPAN_Z: i32 = 32;
-const PAGE_SPAN_Y: i32 = 256;
+/// How many blocks tall memory is
+pub const PAGE_SPAN_Y: i32 = 256;
 const PAGE_SPAN_X: i32 = 2;
 
 const MEMORY_PAGE_SIZE: usize = 65536;
 
 const MEMORY_PAGE_BLOCKS: usize = MEMORY_PAGE_SIZE / 4;
 
-fn get_address_pos(addr: i32) -> (i32, i32, i32) {
+/// Returns the world position of the block holding the word at an address in the first memory,
+/// where `origin` is the corner of memory with the lowest coordinates.
+pub fn get_address_pos(origin: (i32, i32, i32), addr: i32) -> (i32, i32, i32) {
 	assert!(addr >= 0);
 	assert_eq!(addr % 4, 0);
 
@@ -609,7 +1778,87 @@ fn get_address_pos(addr: i32) -> (i32, i32, i32) {
 	word_addr /= PAGE_SPAN_Y;
 	let x = word_addr;
 
-	(x, y, z)
+	let (ox, oy, oz) = origin;
+	(ox + x, oy + y, oz + z)
+}
+
+/// Like `get_address_pos`, but for an address in the given memory.
+/// Each memory has its own region, placed next to the previous one along the z axis.
+pub fn get_memory_address_pos(origin: (i32, i32, i32), memory: u32, addr: i32) -> (i32, i32, i32) {
+	let (x, y, z) = get_address_pos(origin, addr);
+	(x, y, z + memory as i32 * PAGE_SPAN_Z)
+}
+
+/// The current size of a memory in pages.
+pub fn get_memory_pages_var(memory: u32) -> String {
+	if memory == 0 {
+		MEM_PAGES_VAR.to_string()
+	} else {
+		format!("%%memory_{memory}_pages reg")
+	}
+}
+
+/// The function implementing `memory.grow` for a memory.
+pub fn get_memory_grow_func(memory: u32) -> String {
+	if memory == 0 {
+		"wasmrunner:__memory_grow".to_string()
+	} else {
+		format!("wasmrunner:__memory_{memory}_grow")
+	}
+}
+
+/// Sets `MEM_Z_VAR` around code calling the intrinsics, so they access the given memory.
+fn with_memory_z(memory: u32, code: &mut Vec<String>, f: impl FnOnce(&mut Vec<String>)) {
+	if memory == 0 {
+		f(code);
+		return;
+	}
+
+	code.push(format!("scoreboard players add {MEM_Z_VAR} {}", memory as i32 * PAGE_SPAN_Z));
+	f(code);
+	code.push(format!("scoreboard players remove {MEM_Z_VAR} {}", memory as i32 * PAGE_SPAN_Z));
+}
+
+/// Emits a load or store using jukeboxes.
+/// Partly known addresses are accessed relative to the memory pointer's position unless `known_addresses_only` is set,
+/// so backends that rewrite these commands only have to handle `intrinsic:setptr` and fully known addresses.
+pub(super) fn emit_jukebox_access(instr: &LirInstr, layout: &WorldLayout, known_addresses_only: bool, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
+	match instr {
+		&LirInstr::Store32(mem, src, addr) => emit_in_memory(mem, addr, known_addresses_only, code, |addr, code| mem_store_32(src, addr, layout.memory_origin, code, const_pool)),
+		&LirInstr::Store16(mem, src, addr) => emit_in_memory(mem, addr, known_addresses_only, code, |addr, code| mem_store_16(src, addr, layout.memory_origin, code, const_pool)),
+		&LirInstr::Store8 (mem, src, addr) => emit_in_memory(mem, addr, known_addresses_only, code, |addr, code| mem_store_8 (src, addr, layout.memory_origin, code)),
+		&LirInstr::Load64(mem, dst, addr) => emit_in_memory(mem, addr, known_addresses_only, code, |addr, code| mem_load_64(dst, addr, code)),
+		&LirInstr::Load32(mem, dst, addr) => emit_in_memory(mem, addr, known_addresses_only, code, |addr, code| mem_load_32(dst, addr, layout.memory_origin, code, const_pool)),
+		&LirInstr::Load16(mem, dst, addr) => emit_in_memory(mem, addr, known_addresses_only, code, |addr, code| mem_load_16(dst, addr, layout.memory_origin, code)),
+		&LirInstr::Load8 (mem, dst, addr) => emit_in_memory(mem, addr, known_addresses_only, code, |addr, code| mem_load_8 (dst, addr, layout.memory_origin, code)),
+		_ => unreachable!("{instr:?} doesn't access memory"),
+	}
+}
+
+/// Emits a load or store, going through the intrinsics for memories other than the first
+/// so the memory's z offset is added.
+fn emit_in_memory(memory: u32, addr: RegisterWithInfo, known_addresses_only: bool, code: &mut Vec<String>, f: impl FnOnce(RegisterWithInfo, &mut Vec<String>)) {
+	if memory == 0 {
+		// Partly known addresses are accessed relative to the memory pointer's position in the world
+		let addr = if known_addresses_only && addr.get_const().is_none() {
+			RegisterWithInfo::new(addr.0)
+		} else {
+			addr
+		};
+		f(addr, code);
+		return;
+	}
+
+	// Known addresses would skip the intrinsics, so hide what is known about them
+	let addr = if let Some(addr) = addr.get_const() {
+		let tmp = Register::temp_lo(4330);
+		code.push(format!("scoreboard players set {tmp} {addr}"));
+		RegisterWithInfo::new(tmp)
+	} else {
+		RegisterWithInfo::new(addr.0)
+	};
+
+	with_memory_z(memory, code, |code| f(addr, code));
 }
 
 const INSERT_MEM_PRINTS: bool = false;
@@ -650,12 +1899,12 @@ fn tellraw_mem_load(size: u32, dst: Register, addr: Register) -> String {
 	s
 }
 
-fn mem_load_unaligned_32(dst: Register, addr: i32, offset: i32, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+fn mem_load_unaligned_32(dst: Register, addr: i32, offset: i32, origin: (i32, i32, i32), code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	assert!(offset > 0);
 	assert!(offset < 4);
 
-	let (x0, y0, z0) = get_address_pos(addr - offset);
-	let (x1, y1, z1) = get_address_pos(addr - offset + 4);
+	let (x0, y0, z0) = get_address_pos(origin, addr - offset);
+	let (x1, y1, z1) = get_address_pos(origin, addr - offset + 4);
 
 	let tmp1 = Register::temp_lo(100_000);
 
@@ -731,15 +1980,15 @@ fn known_bits(value: StaticValue) -> u64 {
 }
 
 //noinspection RsConstantConditionIf
-fn mem_load_32(dst: Register, addr: RegisterWithInfo, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+fn mem_load_32(dst: Register, addr: RegisterWithInfo, origin: (i32, i32, i32), code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	if let Some(addr) = addr.get_const() {
 		match addr % 4 {
 			0 => {
-				let (x, y, z) = get_address_pos(addr);
+				let (x, y, z) = get_address_pos(origin, addr);
 
 				code.push(format!("execute store result score {dst} run data get block {x} {y} {z} RecordItem.tag.Memory 1"));
 			}
-			offset@(1 | 2 | 3) => mem_load_unaligned_32(dst, addr, offset, code, const_pool),
+			offset@(1 | 2 | 3) => mem_load_unaligned_32(dst, addr, offset, origin, code, const_pool),
 			_ => unreachable!(),
 		}
 	} else if ENABLE_MEM_OPTS && addr.1 != StaticValue::unknown() && addr.1 != (BitMask { set_bits: 0, clr_bits: 1 }.into()) {
@@ -797,25 +2046,25 @@ fn mem_load_32(dst: Register, addr: RegisterWithInfo, code: &mut Vec<String>, co
 }
 
 //noinspection RsConstantConditionIf
-fn mem_load_16(dst: Register, addr: RegisterWithInfo, code: &mut Vec<String>) {
+fn mem_load_16(dst: Register, addr: RegisterWithInfo, origin: (i32, i32, i32), code: &mut Vec<String>) {
 	if let Some(addr) = addr.get_const() {
 		match addr % 4 {
 			0 => {
-				let (x, y, z) = get_address_pos(addr);
+				let (x, y, z) = get_address_pos(origin, addr);
 
 				code.push(format!("execute store result score {dst} run data get block {x} {y} {z} RecordItem.tag.Memory 1"));
 				code.push(format!("scoreboard players operation {dst} %= %%65536 reg"));
 
 			}
 			1 => {
-				let (x, y, z) = get_address_pos(addr - 1);
+				let (x, y, z) = get_address_pos(origin, addr - 1);
 
 				code.push(format!("execute store result score {dst} run data get block {x} {y} {z} RecordItem.tag.Memory 1"));
 				code.push(format!("scoreboard players operation {dst} %= %%16777216 reg"));
 				code.push(format!("scoreboard players operation {dst} /= %%256 reg"));
 			}
 			2 => {
-				let (x, y, z) = get_address_pos(addr - 2);
+				let (x, y, z) = get_address_pos(origin, addr - 2);
 
 				let tmp1 = Register::temp_lo(100_001);
 
@@ -826,9 +2075,9 @@ fn mem_load_16(dst: Register, addr: RegisterWithInfo, code: &mut Vec<String>) {
 				code.push(format!("execute if score {tmp1} matches 1 run scoreboard players add {dst} 32768"));
 			}
 			3 => {
-				mem_load_8(dst, Register::const_val(addr).into(), code);
+				mem_load_8(dst, Register::const_val(addr).into(), origin, code);
 
-				let (x1, y1, z1) = get_address_pos(addr + 1);
+				let (x1, y1, z1) = get_address_pos(origin, addr + 1);
 
 				let tmp1 = Register::temp_lo(100_001);
 
@@ -883,13 +2132,59 @@ fn mem_load_16(dst: Register, addr: RegisterWithInfo, code: &mut Vec<String>) {
 
 const ENABLE_MEM_OPTS: bool = false;
 
-//noinspection RsConstantConditionIf
-fn mem_load_8 (dst: Register, addr: RegisterWithInfo, code: &mut Vec<String>) {
-	if let Some(addr) = addr.get_const() {
-		assert!(addr >= 0);
-		let addr_offset = addr % 4;
-		let aligned_addr = addr - addr_offset;
-		let (x, y, z) = get_address_pos(aligned_addr);
+/// The position in its word of the first byte a buffer intrinsic accesses,
+/// if the low bits of both the base and the element index are known,
+/// like when the base is word-aligned and the index is a constant.
+fn buf_word_pos(ptr: RegisterWithInfo, idx: RegisterWithInfo, elem_size: i32) -> Option<i32> {
+	fn low_bits(reg: RegisterWithInfo) -> Option<i32> {
+		if let Some(c) = reg.get_const() {
+			Some(c & 3)
+		} else if known_bits(reg.1) & 3 == 3 {
+			Some(reg.1.into_mask().set_bits as i32 & 3)
+		} else {
+			None
+		}
+	}
+
+	Some((low_bits(ptr)? + low_bits(idx)? * elem_size) & 3)
+}
+
+/// Moves the memory pointer to the word holding element `idx` of the buffer at `ptr`, leaving its address in `%ptr`.
+fn buf_set_ptr(ptr: RegisterWithInfo, idx: RegisterWithInfo, elem_size: i32, code: &mut Vec<String>) {
+	code.push(format!("scoreboard players operation %ptr reg = {}", ptr.0));
+	for _ in 0..elem_size {
+		code.push(format!("scoreboard players operation %ptr reg += {}", idx.0));
+	}
+	code.push("function intrinsic:setptr".to_string());
+}
+
+/// Replaces the byte or halfword at `pos` in the word the memory pointer is at with `%param2%0`,
+/// which must already be less than `size`. Clobbers `%param0%0` and `%return%0`.
+fn buf_replace_in_word(pos: i32, size: i32, code: &mut Vec<String>) {
+	code.push(format!("execute at {MEM_PTR_UUID} store result score %param0%0 reg run data get block ~ ~ ~ RecordItem.tag.Memory 1"));
+
+	// word += (new value - old value) << (8 * pos)
+	code.push("scoreboard players operation %return%0 reg = %param0%0 reg".to_string());
+	if pos != 0 {
+		code.push(format!("scoreboard players operation %return%0 reg /= %%{} reg", 1 << (pos * 8)));
+	}
+	code.push(format!("scoreboard players operation %return%0 reg %= %%{size} reg"));
+	code.push("scoreboard players operation %param2%0 reg -= %return%0 reg".to_string());
+	if pos != 0 {
+		code.push(format!("scoreboard players operation %param2%0 reg *= %%{} reg", 1 << (pos * 8)));
+	}
+	code.push("scoreboard players operation %param0%0 reg += %param2%0 reg".to_string());
+
+	code.push(format!("execute at {MEM_PTR_UUID} store result block ~ ~ ~ RecordItem.tag.Memory int 1 run scoreboard players get %param0%0 reg"));
+}
+
+//noinspection RsConstantConditionIf
+fn mem_load_8 (dst: Register, addr: RegisterWithInfo, origin: (i32, i32, i32), code: &mut Vec<String>) {
+	if let Some(addr) = addr.get_const() {
+		assert!(addr >= 0);
+		let addr_offset = addr % 4;
+		let aligned_addr = addr - addr_offset;
+		let (x, y, z) = get_address_pos(origin, aligned_addr);
 
 		code.push(format!("execute store result score {dst} run data get block {x} {y} {z} RecordItem.tag.Memory 1"));
 
@@ -969,7 +2264,7 @@ fn unsigned_less_than(dst: Register, lhs: Register, rhs: Register, code: &mut Ve
 	}
 }
 
-fn unsigned_less_than_eq(dst: Register, lhs: Register, rhs: Register, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+fn unsigned_less_than_eq(dst: Register, lhs: Register, rhs: Register, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	// FIXME: Add a test for this (previously I had r_value - 1, which was a bug)
 
 	if let Some(r) = rhs.get_const() {
@@ -1002,7 +2297,7 @@ fn unsigned_less_than_eq(dst: Register, lhs: Register, rhs: Register, code: &mut
 	}
 }
 
-fn unsigned_greater_than_eq(dst: Register, lhs: Register, rhs: Register, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+fn unsigned_greater_than_eq(dst: Register, lhs: Register, rhs: Register, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	unsigned_less_than_eq(dst, rhs, lhs, code, const_pool); /* swapped */
 }
 
@@ -1062,10 +2357,14 @@ fn signed_div(dst: Register, mut lhs: Register, mut rhs: Register, code: &mut Ve
 
 		code.push(format!("execute if score {lhs} matches ..-1 if score {rhs} matches 0.. unless score {rem} matches 0..0 run scoreboard players add {dst} 1"));
 		code.push(format!("execute if score {rhs} matches ..-1 if score {lhs} matches 0.. unless score {rem} matches 0..0 run scoreboard players add {dst} 1"));
+
+		// Scoreboard division by zero leaves the score unchanged, so give it a defined result,
+		// which also makes the remainder computed from it the dividend
+		code.push(format!("execute if score {rhs} matches 0 run scoreboard players set {dst} -1"));
 	}
 }
 
-fn unsigned_div(dst: Register, mut lhs: Register, mut rhs: Register, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+fn unsigned_div(dst: Register, mut lhs: Register, mut rhs: Register, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	// TODO: Find a better way to pick these registers
 	let d1 = Register::temp_lo(30);
 	let r1 = Register::temp_lo(31);
@@ -1136,9 +2435,12 @@ fn unsigned_div(dst: Register, mut lhs: Register, mut rhs: Register, code: &mut
 	code.push(format!("scoreboard players operation {d1} += {d3}"));
 
 	code.push(format!("execute if score {lhs} matches ..-1 if score {rhs} matches 0.. run scoreboard players operation {dst} = {d1}"));
+
+	// Same as for signed division
+	code.push(format!("execute if score {rhs} matches 0 run scoreboard players set {dst} -1"));
 }
 
-fn unsigned_rem(dst: Register, lhs: Register, rhs: Register, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+fn unsigned_rem(dst: Register, lhs: Register, rhs: Register, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	assert_ne!(lhs, dst);
 	assert_ne!(rhs, dst);
 
@@ -1345,7 +2647,9 @@ fn signed_greater_than_eq_64(dst: Register, lhs: DoubleRegister, rhs: DoubleRegi
 	signed_less_than_eq_64(dst, rhs, lhs, code)
 }
 
-static BLOCKS: [&str; 15] = [
+/// The block states used for the turtle's block IDs unless a palette is given,
+/// in the same order as `enum Block` in `mcinterface.h`
+pub static DEFAULT_BLOCK_PALETTE: [&str; 15] = [
 	"minecraft:air",
 	"minecraft:cobblestone",
 	"minecraft:granite",
@@ -1363,15 +2667,20 @@ static BLOCKS: [&str; 15] = [
 	"minecraft:coal_block",
 ];
 
-fn turtle_set_block(reg: Register, code: &mut Vec<String>) {
-	for (idx, block) in BLOCKS.iter().enumerate() {
+pub fn default_block_palette() -> Vec<String> {
+	DEFAULT_BLOCK_PALETTE.iter().map(|block| block.to_string()).collect()
+}
+
+/// Sets the block at the turtle's position, where `nbt` is either empty or the block entity data to give it.
+fn turtle_set_block(reg: Register, palette: &[String], nbt: &str, code: &mut Vec<String>) {
+	for (idx, block) in palette.iter().enumerate() {
 		// TODO: Replace or destroy?
-		code.push(format!("execute at {TURTLE_UUID} if score {reg} matches {idx} run setblock ~ ~ ~ {block} replace"));
+		code.push(format!("execute at {TURTLE_UUID} if score {reg} matches {idx} run setblock ~ ~ ~ {block}{nbt} replace"));
 	}
 
 	let reg_name = reg.scoreboard_pair().0;
 
-	let mut s = format!("execute unless score {reg} matches 0..{} run ", BLOCKS.len() - 1);
+	let mut s = format!("execute unless score {reg} matches 0..{} run ", palette.len() - 1);
 	s.push_str(r#"tellraw @a [{"text":"Attempt to set invalid block"},{"score":{"name":""#);
 	s.push_str(reg_name.as_ref());
 	s.push_str(r#"","objective":"reg"}}]"#);
@@ -1379,13 +2688,13 @@ fn turtle_set_block(reg: Register, code: &mut Vec<String>) {
 
 }
 
-fn turtle_fill_block(block: Register, x_span: Register, y_span: Register, z_span: Register, code: &mut Vec<String>) {
+fn turtle_fill_block(block: Register, x_span: Register, y_span: Register, z_span: Register, palette: &[String], code: &mut Vec<String>) {
 	if let (Some(x_span), Some(y_span), Some(z_span)) = (x_span.get_const(), y_span.get_const(), z_span.get_const()) {
 		if let Some(block) = block.get_const() {
-			let block = BLOCKS[block as usize];
+			let block = &palette[block as usize];
 			code.push(format!("execute at {TURTLE_UUID} run fill ~ ~ ~ ~{x_span} ~{y_span} ~{z_span} {block} replace"));
 		} else {
-			for (idx, block_name) in BLOCKS.iter().enumerate() {
+			for (idx, block_name) in palette.iter().enumerate() {
 				// TODO: Replace or destroy?
 				code.push(format!("execute at {TURTLE_UUID} if score {block} matches {idx} run fill ~ ~ ~ ~{x_span} ~{y_span} ~{z_span} {block_name} replace"));
 			}
@@ -1411,20 +2720,28 @@ fn turtle_fill_block(block: Register, x_span: Register, y_span: Register, z_span
 
 }
 
-fn turtle_paste_region_masked(x_span: Register, y_span: Register, z_span: Register, code: &mut Vec<String>) {
+fn turtle_paste_region_masked(x_span: Register, y_span: Register, z_span: Register, origin: (i32, i32, i32), code: &mut Vec<String>) {
 	if let (Some(x_span), Some(y_span), Some(z_span)) = (x_span.get_const(), y_span.get_const(), z_span.get_const()) {
-		let x_end = x_span;
-		let y_end = y_span;
-		let z_end = -1 + z_span;
-		code.push(format!("execute at {TURTLE_UUID} run clone 0 0 -1 {x_end} {y_end} {z_end} ~ ~ ~ masked"));
+		let (x, y, z) = get_clipboard_pos(origin);
+		let x_end = x + x_span;
+		let y_end = y + y_span;
+		let z_end = z + z_span;
+		code.push(format!("execute at {TURTLE_UUID} run clone {x} {y} {z} {x_end} {y_end} {z_end} ~ ~ ~ masked"));
 	} else {
 		todo!()
 	}
 }
 
-fn turtle_copy_region(x_span: Register, y_span: Register, z_span: Register, code: &mut Vec<String>) {
+/// Where `turtle_copy_region` copies blocks to, just in front of memory
+fn get_clipboard_pos(origin: (i32, i32, i32)) -> (i32, i32, i32) {
+	let (x, y, z) = origin;
+	(x, y, z - 1)
+}
+
+fn turtle_copy_region(x_span: Register, y_span: Register, z_span: Register, origin: (i32, i32, i32), code: &mut Vec<String>) {
 	if let (Some(x_span), Some(y_span), Some(z_span)) = (x_span.get_const(), y_span.get_const(), z_span.get_const()) {
-		code.push(format!("execute at {TURTLE_UUID} run clone ~ ~ ~ ~{x_span} ~{y_span} ~{z_span} 0 0 -1"));
+		let (x, y, z) = get_clipboard_pos(origin);
+		code.push(format!("execute at {TURTLE_UUID} run clone ~ ~ ~ ~{x_span} ~{y_span} ~{z_span} {x} {y} {z}"));
 	} else {
 		todo!()
 	}
@@ -1448,13 +2765,142 @@ fn turtle_copy_region(x_span: Register, y_span: Register, z_span: Register, code
 
 
 
-fn turtle_get_block(reg: Register, code: &mut Vec<String>) {
+fn turtle_get_block(reg: Register, palette: &[String], code: &mut Vec<String>) {
 	code.push(format!("scoreboard players set {reg} 0"));
-	for (idx, block) in BLOCKS.iter().enumerate() {
+	for (idx, block) in palette.iter().enumerate() {
 		code.push(format!("execute at {TURTLE_UUID} run execute if block ~ ~ ~ {block} run scoreboard players set {reg} {idx}"));
 	}
 }
 
+/// The blocks that count as air for `turtle_is_air`, followed by the fluids that `turtle_is_solid` also excludes
+static NON_SOLID_BLOCKS: [&str; 5] = [
+	"minecraft:air",
+	"minecraft:cave_air",
+	"minecraft:void_air",
+	"minecraft:water",
+	"minecraft:lava",
+];
+
+fn turtle_check_block(dst: Register, solid: bool, code: &mut Vec<String>) {
+	let (blocks, found, not_found) = if solid {
+		(&NON_SOLID_BLOCKS[..], 0, 1)
+	} else {
+		(&NON_SOLID_BLOCKS[..3], 1, 0)
+	};
+
+	code.push(format!("scoreboard players set {dst} {not_found}"));
+	for block in blocks {
+		code.push(format!("execute at {TURTLE_UUID} if block ~ ~ ~ {block} run scoreboard players set {dst} {found}"));
+	}
+}
+
+/// There's no command for getting the light level, so there's a predicate for each level that is checked in turn
+fn turtle_light_level(dst: Register, code: &mut Vec<String>) {
+	code.push(format!("scoreboard players set {dst} 0"));
+	for level in 1..=15 {
+		code.push(format!("execute at {TURTLE_UUID} if predicate wasmrunner:light/{level} run scoreboard players set {dst} {level}"));
+	}
+}
+
+fn light_level_predicate(level: u32) -> String {
+	format!(r#"{{"condition":"minecraft:location_check","predicate":{{"light":{{"light":{level}}}}}}}"#)
+}
+
+/// The sounds that can be passed to `mc_playsound`, in the same order as `enum Sound` in `mcinterface.h`
+static SOUNDS: [&str; 10] = [
+	"minecraft:block.note_block.harp",
+	"minecraft:block.note_block.bass",
+	"minecraft:block.note_block.bell",
+	"minecraft:block.stone.break",
+	"minecraft:block.lever.click",
+	"minecraft:entity.experience_orb.pickup",
+	"minecraft:entity.item.pickup",
+	"minecraft:entity.player.levelup",
+	"minecraft:entity.generic.explode",
+	"minecraft:entity.player.hurt",
+];
+
+/// The particles that can be passed to `mc_particle`, in the same order as `enum Particle` in `mcinterface.h`
+static PARTICLES: [&str; 9] = [
+	"minecraft:flame",
+	"minecraft:smoke",
+	"minecraft:cloud",
+	"minecraft:heart",
+	"minecraft:happy_villager",
+	"minecraft:crit",
+	"minecraft:note",
+	"minecraft:end_rod",
+	"minecraft:explosion",
+];
+
+/// Formats a value given in hundredths as a decimal number
+fn hundredths(value: i32) -> String {
+	format!("{}.{:02}", value / 100, value % 100)
+}
+
+fn play_sound(sound: Register, volume: i32, pitch: i32, code: &mut Vec<String>) {
+	let (volume, pitch) = (hundredths(volume), hundredths(pitch));
+	if let Some(sound) = sound.get_const() {
+		let sound = SOUNDS[sound as usize];
+		code.push(format!("execute at {TURTLE_UUID} run playsound {sound} master @a ~ ~ ~ {volume} {pitch}"));
+	} else {
+		for (idx, sound_name) in SOUNDS.iter().enumerate() {
+			code.push(format!("execute at {TURTLE_UUID} if score {sound} matches {idx} run playsound {sound_name} master @a ~ ~ ~ {volume} {pitch}"));
+		}
+	}
+}
+
+fn show_particle(particle: Register, count: i32, code: &mut Vec<String>) {
+	// Spread out a little around the center of the turtle's block
+	if let Some(particle) = particle.get_const() {
+		let particle = PARTICLES[particle as usize];
+		code.push(format!("execute at {TURTLE_UUID} run particle {particle} ~0.5 ~0.5 ~0.5 0.25 0.25 0.25 0 {count}"));
+	} else {
+		for (idx, particle_name) in PARTICLES.iter().enumerate() {
+			code.push(format!("execute at {TURTLE_UUID} if score {particle} matches {idx} run particle {particle_name} ~0.5 ~0.5 ~0.5 0.25 0.25 0.25 0 {count}"));
+		}
+	}
+}
+
+/// The number of bossbars programs can use, which are named `wasmrunner:bar0` and so on
+const BOSSBAR_COUNT: i32 = 4;
+
+/// Pushes a command for the bossbar with the index in `bar`, given its ID.
+/// If the index isn't constant, there is one command per bossbar.
+fn for_each_bossbar(bar: Register, code: &mut Vec<String>, cmd: impl Fn(&str) -> String) {
+	if let Some(bar) = bar.get_const() {
+		assert!((0..BOSSBAR_COUNT).contains(&bar), "there is no bossbar {bar}");
+		code.push(cmd(&format!("wasmrunner:bar{bar}")));
+	} else {
+		for idx in 0..BOSSBAR_COUNT {
+			let cmd = cmd(&format!("wasmrunner:bar{idx}"));
+			code.push(format!("execute if score {bar} matches {idx} run {cmd}"));
+		}
+	}
+}
+
+/// The selectors that can be passed to `entity_count`, in the same order as `enum Selector` in `mcinterface.h`.
+/// The markers used by the turtle and memory pointer are never counted.
+static SELECTORS: [&str; 5] = [
+	"@a",
+	"@e[type=!minecraft:marker]",
+	"@e[type=!minecraft:marker,type=!minecraft:player]",
+	"@e[type=minecraft:item]",
+	"@a[dx=0,dy=0,dz=0]",
+];
+
+fn entity_count(dst: Register, selector: Register, code: &mut Vec<String>) {
+	if let Some(selector) = selector.get_const() {
+		let selector = SELECTORS[selector as usize];
+		code.push(format!("execute at {TURTLE_UUID} store result score {dst} if entity {selector}"));
+	} else {
+		code.push(format!("scoreboard players set {dst} 0"));
+		for (idx, selector_str) in SELECTORS.iter().enumerate() {
+			code.push(format!("execute at {TURTLE_UUID} if score {selector} matches {idx} store result score {dst} if entity {selector_str}"));
+		}
+	}
+}
+
 fn get_all_bit_runs(mut value: i32) -> impl Iterator<Item=Range<u32>> {
 	std::iter::from_fn(move || {
 		let run = get_first_bit_run(value)?;
@@ -1510,7 +2956,7 @@ fn emit_constant_or(dst: Register, lhs: RegisterWithInfo, rhs: i32, code: &mut V
 	code.push(format!("scoreboard players operation {dst} = %return%0 reg"));
 }
 
-fn emit_constant_and(dst: Register, lhs: RegisterWithInfo, rhs: i32, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+fn emit_constant_and(dst: Register, lhs: RegisterWithInfo, rhs: i32, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	if rhs == 0 {
 		code.push(format!("scoreboard players set {dst} 0"));
 	} else if rhs == -1 {
@@ -1552,7 +2998,7 @@ fn emit_constant_and(dst: Register, lhs: RegisterWithInfo, rhs: i32, code: &mut
 }
 
 // Returns the remaining mask that must be handled, if successful.
-fn emit_xor_low_bits(reg: Register, rhs: i32, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) -> Result<i32, ()> {
+fn emit_xor_low_bits(reg: Register, rhs: i32, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) -> Result<i32, ()> {
 	if rhs.trailing_ones() <= 1 || rhs.trailing_ones() >= 31 {
 		return Err(())
 	}
@@ -1581,7 +3027,7 @@ fn emit_xor_low_bits(reg: Register, rhs: i32, code: &mut Vec<String>, const_pool
 	Ok(rhs & !(lo_bits_mod - 1))
 }
 
-fn emit_constant_xor(dst: Register, lhs: RegisterWithInfo, mut rhs: i32, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+fn emit_constant_xor(dst: Register, lhs: RegisterWithInfo, mut rhs: i32, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	let old_code_len = code.len();
 	let old_rhs = rhs;
 
@@ -1649,7 +3095,7 @@ fn emit_constant_xor(dst: Register, lhs: RegisterWithInfo, mut rhs: i32, code: &
 }
 
 
-fn emit_constant_shru(dst: Register, lhs: Register, rhs: i32, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+fn emit_constant_shru(dst: Register, lhs: Register, rhs: i32, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	let rhs = rhs.rem_euclid(32);
 
 	if rhs == 31 {
@@ -1682,15 +3128,231 @@ fn emit_constant_shru(dst: Register, lhs: Register, rhs: i32, code: &mut Vec<Str
 	}
 }
 
+fn emit_constant_shl(dst: Register, lhs: Register, rhs: i32, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
+	let rhs = rhs.rem_euclid(32);
+
+	if dst != lhs {
+		code.push(format!("scoreboard players operation {dst} = {lhs}"));
+	}
+
+	if rhs != 0 {
+		let m = (1_u32 << rhs) as i32;
+		const_pool.insert(m);
+		let m = Register::const_val(m);
+		code.push(format!("scoreboard players operation {dst} *= {m}"));
+	}
+}
+
+fn emit_constant_shrs(dst: Register, lhs: Register, rhs: i32, code: &mut Vec<String>) {
+	let rhs = rhs.rem_euclid(32);
+
+	if dst != lhs {
+		code.push(format!("scoreboard players operation {dst} = {lhs}"));
+	}
+
+	if rhs == 31 {
+		code.push(format!("execute store success score {dst} if score {dst} matches ..-1"));
+		code.push(format!("scoreboard players operation {dst} *= %%-1 reg"));
+	} else if rhs != 0 {
+		code.push(format!("scoreboard players operation {dst} /= %%{} reg", 1 << rhs));
+	}
+}
+
+/// The bits shifted out of one end come back in at the other, so they can be added to the shifted value.
+fn emit_constant_rotl(dst: Register, lhs: Register, rhs: i32, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
+	let rhs = rhs.rem_euclid(32);
+
+	if rhs == 0 {
+		if dst != lhs {
+			code.push(format!("scoreboard players operation {dst} = {lhs}"));
+		}
+		return;
+	}
+
+	let wrapped = Register::temp_lo(790);
+	emit_constant_shru(wrapped, lhs, 32 - rhs, code, const_pool);
+	emit_constant_shl(dst, lhs, rhs, code, const_pool);
+	code.push(format!("scoreboard players operation {dst} += {wrapped}"));
+}
+
+/// Shifts each half by the count, carrying the bits that cross from the low half into the high half.
+fn emit_constant_shl_64(dst: DoubleRegister, lhs: DoubleRegister, rhs: i32, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
+	let rhs = rhs.rem_euclid(64);
+	let (dst_lo, dst_hi) = dst.split_lo_hi();
+	let (lhs_lo, lhs_hi) = lhs.split_lo_hi();
+
+	if rhs >= 32 {
+		emit_constant_shl(dst_hi, lhs_lo, rhs - 32, code, const_pool);
+		code.push(format!("scoreboard players set {dst_lo} 0"));
+	} else if rhs > 0 {
+		let carry = Register::temp_lo(790);
+		emit_constant_shru(carry, lhs_lo, 32 - rhs, code, const_pool);
+		emit_constant_shl(dst_hi, lhs_hi, rhs, code, const_pool);
+		code.push(format!("scoreboard players operation {dst_hi} += {carry}"));
+		emit_constant_shl(dst_lo, lhs_lo, rhs, code, const_pool);
+	} else if dst != lhs {
+		code.push(format!("scoreboard players operation {dst_lo} = {lhs_lo}"));
+		code.push(format!("scoreboard players operation {dst_hi} = {lhs_hi}"));
+	}
+}
+
+/// Shifts each half by the count, carrying the bits that cross from the high half into the low half.
+fn emit_constant_shr_64(dst: DoubleRegister, lhs: DoubleRegister, rhs: i32, signed: bool, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
+	let rhs = rhs.rem_euclid(64);
+	let (dst_lo, dst_hi) = dst.split_lo_hi();
+	let (lhs_lo, lhs_hi) = lhs.split_lo_hi();
+
+	if rhs >= 32 {
+		if signed {
+			emit_constant_shrs(dst_lo, lhs_hi, rhs - 32, code);
+			emit_constant_shrs(dst_hi, lhs_hi, 31, code);
+		} else {
+			emit_constant_shru(dst_lo, lhs_hi, rhs - 32, code, const_pool);
+			code.push(format!("scoreboard players set {dst_hi} 0"));
+		}
+	} else if rhs > 0 {
+		let carry = Register::temp_lo(790);
+		emit_constant_shl(carry, lhs_hi, 32 - rhs, code, const_pool);
+		emit_constant_shru(dst_lo, lhs_lo, rhs, code, const_pool);
+		code.push(format!("scoreboard players operation {dst_lo} += {carry}"));
+		if signed {
+			emit_constant_shrs(dst_hi, lhs_hi, rhs, code);
+		} else {
+			emit_constant_shru(dst_hi, lhs_hi, rhs, code, const_pool);
+		}
+	} else if dst != lhs {
+		code.push(format!("scoreboard players operation {dst_lo} = {lhs_lo}"));
+		code.push(format!("scoreboard players operation {dst_hi} = {lhs_hi}"));
+	}
+}
+
+/// Rotating by 32 or more swaps the halves first, then each half is rotated into the other like `emit_constant_rotl`.
+fn emit_constant_rotl_64(dst: DoubleRegister, lhs: DoubleRegister, rhs: i32, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
+	let rhs = rhs.rem_euclid(64);
+	let (dst_lo, dst_hi) = dst.split_lo_hi();
+	let (lhs_lo, lhs_hi) = lhs.split_lo_hi();
+
+	if rhs == 0 {
+		if dst != lhs {
+			code.push(format!("scoreboard players operation {dst_lo} = {lhs_lo}"));
+			code.push(format!("scoreboard players operation {dst_hi} = {lhs_hi}"));
+		}
+		return;
+	}
+
+	// The halves that end up in the high and low half before the bits crossing between them are added
+	let (high, low) = if rhs < 32 { (lhs_hi, lhs_lo) } else { (lhs_lo, lhs_hi) };
+	let rhs = rhs % 32;
+
+	let new_hi = Register::temp_lo(791);
+	let new_lo = Register::temp_lo(792);
+	if rhs == 0 {
+		code.push(format!("scoreboard players operation {new_hi} = {high}"));
+		code.push(format!("scoreboard players operation {new_lo} = {low}"));
+	} else {
+		let wrapped = Register::temp_lo(790);
+		emit_constant_shl(new_hi, high, rhs, code, const_pool);
+		emit_constant_shru(wrapped, low, 32 - rhs, code, const_pool);
+		code.push(format!("scoreboard players operation {new_hi} += {wrapped}"));
+		emit_constant_shl(new_lo, low, rhs, code, const_pool);
+		emit_constant_shru(wrapped, high, 32 - rhs, code, const_pool);
+		code.push(format!("scoreboard players operation {new_lo} += {wrapped}"));
+	}
+	code.push(format!("scoreboard players operation {dst_lo} = {new_lo}"));
+	code.push(format!("scoreboard players operation {dst_hi} = {new_hi}"));
+}
+
+
+/// Calls one of the softfloat intrinsics in `intrinsic:f32/`.
+/// If `negate_rhs` is set, the sign of rhs is flipped first.
+fn f32_binop(name: &str, dst: Register, lhs: Register, rhs: Register, negate_rhs: bool, code: &mut Vec<String>) {
+	code.push(format!("scoreboard players operation %param0%0 reg = {lhs}"));
+	code.push(format!("scoreboard players operation %param1%0 reg = {rhs}"));
+	if negate_rhs {
+		code.push("scoreboard players operation %param1%0 reg += %%-2147483648 reg".to_string());
+	}
+	code.push(format!("function intrinsic:f32/{name}"));
+	code.push(format!("scoreboard players operation {dst} = %return%0 reg"));
+}
+
+/// Calls one of the softfloat intrinsics in `intrinsic:f64/`.
+/// If `negate_rhs` is set, the sign of rhs is flipped first.
+fn f64_binop(name: &str, dst: DoubleRegister, lhs: DoubleRegister, rhs: DoubleRegister, negate_rhs: bool, code: &mut Vec<String>) {
+	let (lhs_lo, lhs_hi) = lhs.split_lo_hi();
+	let (rhs_lo, rhs_hi) = rhs.split_lo_hi();
+	let (dst_lo, dst_hi) = dst.split_lo_hi();
+
+	code.push(format!("scoreboard players operation %param0%0 reg = {lhs_lo}"));
+	code.push(format!("scoreboard players operation %param0%1 reg = {lhs_hi}"));
+	code.push(format!("scoreboard players operation %param1%0 reg = {rhs_lo}"));
+	code.push(format!("scoreboard players operation %param1%1 reg = {rhs_hi}"));
+	if negate_rhs {
+		code.push("scoreboard players operation %param1%1 reg += %%-2147483648 reg".to_string());
+	}
+	code.push(format!("function intrinsic:f64/{name}"));
+	code.push(format!("scoreboard players operation {dst_lo} = %return%0 reg"));
+	code.push(format!("scoreboard players operation {dst_hi} = %return%1 reg"));
+}
+
+/// Calls one of the single-operand softfloat intrinsics in `intrinsic:f32/`.
+fn f32_unaryop(name: &str, dst: Register, src: Register, code: &mut Vec<String>) {
+	code.push(format!("scoreboard players operation %param0%0 reg = {src}"));
+	code.push(format!("function intrinsic:f32/{name}"));
+	code.push(format!("scoreboard players operation {dst} = %return%0 reg"));
+}
+
+/// Calls one of the single-operand softfloat intrinsics in `intrinsic:f64/`.
+fn f64_unaryop(name: &str, dst: DoubleRegister, src: DoubleRegister, code: &mut Vec<String>) {
+	let (src_lo, src_hi) = src.split_lo_hi();
+	let (dst_lo, dst_hi) = dst.split_lo_hi();
+
+	code.push(format!("scoreboard players operation %param0%0 reg = {src_lo}"));
+	code.push(format!("scoreboard players operation %param0%1 reg = {src_hi}"));
+	code.push(format!("function intrinsic:f64/{name}"));
+	code.push(format!("scoreboard players operation {dst_lo} = %return%0 reg"));
+	code.push(format!("scoreboard players operation {dst_hi} = %return%1 reg"));
+}
+
+/// Calls one of the integer to float conversion intrinsics in `intrinsic:f32/`.
+fn f32_from_64(name: &str, dst: Register, src: DoubleRegister, code: &mut Vec<String>) {
+	let (src_lo, src_hi) = src.split_lo_hi();
+
+	code.push(format!("scoreboard players operation %param0%0 reg = {src_lo}"));
+	code.push(format!("scoreboard players operation %param0%1 reg = {src_hi}"));
+	code.push(format!("function intrinsic:f32/{name}"));
+	code.push(format!("scoreboard players operation {dst} = %return%0 reg"));
+}
+
+/// Calls one of the integer to float conversion intrinsics in `intrinsic:f64/`.
+fn f64_from_64(name: &str, dst: DoubleRegister, src: DoubleRegister, code: &mut Vec<String>) {
+	let (src_lo, src_hi) = src.split_lo_hi();
+	let (dst_lo, dst_hi) = dst.split_lo_hi();
 
+	code.push(format!("scoreboard players operation %param0%0 reg = {src_lo}"));
+	code.push(format!("scoreboard players operation %param0%1 reg = {src_hi}"));
+	code.push(format!("function intrinsic:f64/{name}"));
+	code.push(format!("scoreboard players operation {dst_lo} = %return%0 reg"));
+	code.push(format!("scoreboard players operation {dst_hi} = %return%1 reg"));
+}
 
-fn emit_instr(instr: &LirInstr, parent: &LirProgram, code: &mut Vec<String>, const_pool: &mut HashSet<i32>) {
+fn emit_instr(instr: &LirInstr, block_id: BlockId, parent: &LirProgram, code: &mut Vec<String>, const_pool: &mut BTreeSet<i32>) {
 	match instr {
-		&LirInstr::Assign(dst, src) => if dst != src { code.push(format!("scoreboard players operation {dst} = {src}")); },
+		&LirInstr::Assign(dst, src) => if let Some(src) = src.get_const() {
+			code.push(format!("scoreboard players set {dst} {src}"));
+		} else if dst != src {
+			code.push(format!("scoreboard players operation {dst} = {src}"));
+		},
 		&LirInstr::Set(dst, src) => code.push(format!("scoreboard players set {dst} {src}")),
-		&LirInstr::Add(dst, src) => code.push(format!("scoreboard players operation {dst} += {src}")),
-		&LirInstr::Sub(dst, src) => code.push(format!("scoreboard players operation {dst} -= {src}")),
+		&LirInstr::Add(dst, src) => emit_add_const(dst, src, 1, code),
+		&LirInstr::Sub(dst, src) => emit_add_const(dst, src, -1, code),
 		&LirInstr::Mul(dst, src) => code.push(format!("scoreboard players operation {dst} *= {src}")),
+		// A constant zero divisor gives the result documented for `DivByZero::Fast`, or traps beforehand with `DivByZero::Trap`
+		&LirInstr::DivS(dst, _, rhs) | &LirInstr::DivU(dst, _, rhs) if rhs.get_const() == Some(0) => {
+			code.push(format!("scoreboard players set {dst} -1"));
+		}
+		&LirInstr::RemS(dst, lhs, rhs) | &LirInstr::RemU(dst, lhs, rhs) if rhs.get_const() == Some(0) => {
+			code.push(format!("scoreboard players operation {dst} = {lhs}"));
+		}
 		&LirInstr::DivS(dst, lhs, rhs) => signed_div(dst, lhs, rhs, code),
 		&LirInstr::DivU(dst, lhs, rhs) => unsigned_div(dst, lhs, rhs, code, const_pool),
 		&LirInstr::RemS(dst, lhs, rhs) => signed_rem(dst, lhs, rhs, code),
@@ -1740,6 +3402,9 @@ fn emit_instr(instr: &LirInstr, parent: &LirProgram, code: &mut Vec<String>, con
 			code.push(format!("scoreboard players operation %sdiv_rhs_lo reg = {rhs_lo}"));
 			code.push(format!("scoreboard players operation %sdiv_rhs_hi reg = {rhs_hi}"));
 			code.push("function intrinsic:i64_sdivrem/div".to_string());
+			// Unsigned division by zero gives all bits set, but the sign of the dividend is applied to it afterwards
+			code.push("execute if score %sdiv_rhs_lo reg matches 0 if score %sdiv_rhs_hi reg matches 0 run scoreboard players set %sdiv_q_lo reg -1".to_string());
+			code.push("execute if score %sdiv_rhs_lo reg matches 0 if score %sdiv_rhs_hi reg matches 0 run scoreboard players set %sdiv_q_hi reg -1".to_string());
 			code.push(format!("scoreboard players operation {dst_lo} = %sdiv_q_lo reg"));
 			code.push(format!("scoreboard players operation {dst_hi} = %sdiv_q_hi reg"));
 		}
@@ -1788,18 +3453,7 @@ fn emit_instr(instr: &LirInstr, parent: &LirProgram, code: &mut Vec<String>, con
 
 		&LirInstr::Shl(dst, lhs, rhs) => {
 			if let Some(rhs) = rhs.get_const() {
-				let rhs = rhs.rem_euclid(32);
-
-				if dst != lhs {
-					code.push(format!("scoreboard players operation {dst} = {lhs}"));
-				}
-
-				if rhs != 0 {
-					let m = (1_u32 << rhs) as i32;
-					const_pool.insert(m);
-					let m = Register::const_val(m);
-					code.push(format!("scoreboard players operation {dst} *= {m}"));
-				}
+				emit_constant_shl(dst, lhs, rhs, code, const_pool);
 			} else {
 				code.push(format!("scoreboard players operation %param0%0 reg = {lhs}"));
 				code.push(format!("scoreboard players operation %param1%0 reg = {rhs}"));
@@ -1809,29 +3463,23 @@ fn emit_instr(instr: &LirInstr, parent: &LirProgram, code: &mut Vec<String>, con
 			}
 		}
 		&LirInstr::ShrS(dst, lhs, rhs) => {
-			if dst != lhs {
-				code.push(format!("scoreboard players operation {dst} = {lhs}"));
-			}
-
 			if let Some(rhs) = rhs.get_const() {
-				let rhs = rhs.rem_euclid(32);
-
-				if rhs == 31 {
-					code.push(format!("execute store success score {dst} if score {dst} matches ..-1"));
-					code.push(format!("scoreboard players operation {dst} *= %%-1 reg"));
-				} else if rhs != 0 {
-					code.push(format!("scoreboard players operation {dst} /= %%{} reg", 1 << rhs));
-				}
+				emit_constant_shrs(dst, lhs, rhs, code);
 			} else {
-				assert_ne!(rhs, dst);
+				// The count is masked in a copy, since the register it's in may still be used, or be `dst`
+				let count = "%param1%0 reg";
+				code.push(format!("scoreboard players operation {count} = {rhs}"));
+				code.push(format!("scoreboard players operation {count} %= %%32 reg"));
 
-				code.push(format!("scoreboard players operation {rhs} %= %%32 reg"));
+				if dst != lhs {
+					code.push(format!("scoreboard players operation {dst} = {lhs}"));
+				}
 
 				for i in 1..31 {
-					code.push(format!("execute if score {rhs} matches {i} run scoreboard players operation {dst} /= %%{} reg", 1 << i))
+					code.push(format!("execute if score {count} matches {i} run scoreboard players operation {dst} /= %%{} reg", 1 << i))
 				}
-				code.push(format!("execute if score {rhs} matches 31 run execute store success score {dst} if score {dst} matches ..-1"));
-				code.push(format!("execute if score {rhs} matches 31 run scoreboard players operation {dst} *= %%-1 reg"));
+				code.push(format!("execute if score {count} matches 31 run execute store success score {dst} if score {dst} matches ..-1"));
+				code.push(format!("execute if score {count} matches 31 run scoreboard players operation {dst} *= %%-1 reg"));
 			}
 		}
 		&LirInstr::ShrU(dst, lhs, rhs) => {
@@ -1846,31 +3494,44 @@ fn emit_instr(instr: &LirInstr, parent: &LirProgram, code: &mut Vec<String>, con
 			}
 		}
 		&LirInstr::Rotl(dst, lhs, rhs) => {
-			code.push(format!("scoreboard players operation %param0%0 reg = {lhs}"));
-			code.push(format!("scoreboard players operation %param1%0 reg = {rhs}"));
-			code.push("scoreboard players operation %param1%0 reg %= %%32 reg".to_string());
-			code.push("function intrinsic:rotl".to_string());
-			code.push(format!("scoreboard players operation {dst} = %param0%0 reg"));
+			if let Some(rhs) = rhs.get_const() {
+				emit_constant_rotl(dst, lhs, rhs, code, const_pool);
+			} else {
+				code.push(format!("scoreboard players operation %param0%0 reg = {lhs}"));
+				code.push(format!("scoreboard players operation %param1%0 reg = {rhs}"));
+				code.push("scoreboard players operation %param1%0 reg %= %%32 reg".to_string());
+				code.push("function intrinsic:rotl".to_string());
+				code.push(format!("scoreboard players operation {dst} = %param0%0 reg"));
+			}
 		}
 		&LirInstr::Rotr(dst, lhs, rhs) => {
-			code.push(format!("scoreboard players operation %param0%0 reg = {lhs}"));
-			code.push(format!("scoreboard players operation %param1%0 reg = {rhs}"));
-			code.push("scoreboard players operation %param1%0 reg %= %%32 reg".to_string());
-			code.push("function intrinsic:rotr".to_string());
-			code.push(format!("scoreboard players operation {dst} = %param0%0 reg"));
+			if let Some(rhs) = rhs.get_const() {
+				emit_constant_rotl(dst, lhs, 32 - rhs.rem_euclid(32), code, const_pool);
+			} else {
+				code.push(format!("scoreboard players operation %param0%0 reg = {lhs}"));
+				code.push(format!("scoreboard players operation %param1%0 reg = {rhs}"));
+				code.push("scoreboard players operation %param1%0 reg %= %%32 reg".to_string());
+				code.push("function intrinsic:rotr".to_string());
+				code.push(format!("scoreboard players operation {dst} = %param0%0 reg"));
+			}
 		}
 
 		&LirInstr::Shl64(dst, lhs, rhs) => {
 			let (dst_lo, dst_hi) = dst.split_lo_hi();
 			let (lhs_lo, lhs_hi) = lhs.split_lo_hi();
 			let rhs_lo = rhs.lo();
-			code.push(format!("scoreboard players operation %param0%0 reg = {lhs_lo}"));
-			code.push(format!("scoreboard players operation %param0%1 reg = {lhs_hi}"));
-			code.push(format!("scoreboard players operation %param1%0 reg = {rhs_lo}"));
-			code.push("scoreboard players operation %param1%0 reg %= %%64 reg".to_string());
-			code.push("function intrinsic:shl_64".to_string());
-			code.push(format!("scoreboard players operation {dst_lo} = %param0%0 reg"));
-			code.push(format!("scoreboard players operation {dst_hi} = %param0%1 reg"));
+
+			if let Some(rhs) = rhs_lo.get_const() {
+				emit_constant_shl_64(dst, lhs, rhs, code, const_pool);
+			} else {
+				code.push(format!("scoreboard players operation %param0%0 reg = {lhs_lo}"));
+				code.push(format!("scoreboard players operation %param0%1 reg = {lhs_hi}"));
+				code.push(format!("scoreboard players operation %param1%0 reg = {rhs_lo}"));
+				code.push("scoreboard players operation %param1%0 reg %= %%64 reg".to_string());
+				code.push("function intrinsic:shl_64".to_string());
+				code.push(format!("scoreboard players operation {dst_lo} = %param0%0 reg"));
+				code.push(format!("scoreboard players operation {dst_hi} = %param0%1 reg"));
+			}
 		}
 
 		&LirInstr::ShrS64(dst, lhs, rhs) => {
@@ -1878,54 +3539,115 @@ fn emit_instr(instr: &LirInstr, parent: &LirProgram, code: &mut Vec<String>, con
 			let (lhs_lo, lhs_hi) = lhs.split_lo_hi();
 			let rhs_lo = rhs.lo();
 
-			code.push(format!("scoreboard players operation %param0%0 reg = {lhs_lo}"));
-			code.push(format!("scoreboard players operation %param0%1 reg = {lhs_hi}"));
-			code.push(format!("scoreboard players operation %param1%0 reg = {rhs_lo}"));
-			code.push("scoreboard players operation %param1%0 reg %= %%64 reg".to_string());
-			code.push("function intrinsic:ashr_i64".to_string());
-			code.push(format!("scoreboard players operation {dst_lo} = %param0%0 reg"));
-			code.push(format!("scoreboard players operation {dst_hi} = %param0%1 reg"));
+			if let Some(rhs) = rhs_lo.get_const() {
+				emit_constant_shr_64(dst, lhs, rhs, true, code, const_pool);
+			} else {
+				code.push(format!("scoreboard players operation %param0%0 reg = {lhs_lo}"));
+				code.push(format!("scoreboard players operation %param0%1 reg = {lhs_hi}"));
+				code.push(format!("scoreboard players operation %param1%0 reg = {rhs_lo}"));
+				code.push("scoreboard players operation %param1%0 reg %= %%64 reg".to_string());
+				code.push("function intrinsic:ashr_i64".to_string());
+				code.push(format!("scoreboard players operation {dst_lo} = %param0%0 reg"));
+				code.push(format!("scoreboard players operation {dst_hi} = %param0%1 reg"));
+			}
 		}
 		&LirInstr::ShrU64(dst, lhs, rhs) => {
 			let (dst_lo, dst_hi) = dst.split_lo_hi();
 			let (lhs_lo, lhs_hi) = lhs.split_lo_hi();
 			let rhs_lo = rhs.lo();
 
-			code.push(format!("scoreboard players operation %param0%0 reg = {lhs_lo}"));
-			code.push(format!("scoreboard players operation %param0%1 reg = {lhs_hi}"));
-			code.push(format!("scoreboard players operation %param1%0 reg = {rhs_lo}"));
-			code.push("scoreboard players operation %param1%0 reg %= %%64 reg".to_string());
-			code.push("function intrinsic:lshr_i64".to_string());
-			code.push(format!("scoreboard players operation {dst_lo} = %param0%0 reg"));
-			code.push(format!("scoreboard players operation {dst_hi} = %param0%1 reg"));
+			if let Some(rhs) = rhs_lo.get_const() {
+				emit_constant_shr_64(dst, lhs, rhs, false, code, const_pool);
+			} else {
+				code.push(format!("scoreboard players operation %param0%0 reg = {lhs_lo}"));
+				code.push(format!("scoreboard players operation %param0%1 reg = {lhs_hi}"));
+				code.push(format!("scoreboard players operation %param1%0 reg = {rhs_lo}"));
+				code.push("scoreboard players operation %param1%0 reg %= %%64 reg".to_string());
+				code.push("function intrinsic:lshr_i64".to_string());
+				code.push(format!("scoreboard players operation {dst_lo} = %param0%0 reg"));
+				code.push(format!("scoreboard players operation {dst_hi} = %param0%1 reg"));
+			}
 		}
 		&LirInstr::Rotl64(dst, lhs, rhs) => {
 			let (dst_lo, dst_hi) = dst.split_lo_hi();
 			let (lhs_lo, lhs_hi) = lhs.split_lo_hi();
 			let rhs_lo = rhs.lo();
 
-			code.push(format!("scoreboard players operation %param0%0 reg = {lhs_lo}"));
-			code.push(format!("scoreboard players operation %param0%1 reg = {lhs_hi}"));
-			code.push(format!("scoreboard players operation %param1%0 reg = {rhs_lo}"));
-			code.push("scoreboard players operation %param1%0 reg %= %%64 reg".to_string());
-			code.push("function intrinsic:rotl_64".to_string());
-			code.push(format!("scoreboard players operation {dst_lo} = %param0%0 reg"));
-			code.push(format!("scoreboard players operation {dst_hi} = %param0%1 reg"));
+			if let Some(rhs) = rhs_lo.get_const() {
+				emit_constant_rotl_64(dst, lhs, rhs, code, const_pool);
+			} else {
+				code.push(format!("scoreboard players operation %param0%0 reg = {lhs_lo}"));
+				code.push(format!("scoreboard players operation %param0%1 reg = {lhs_hi}"));
+				code.push(format!("scoreboard players operation %param1%0 reg = {rhs_lo}"));
+				code.push("scoreboard players operation %param1%0 reg %= %%64 reg".to_string());
+				code.push("function intrinsic:rotl_64".to_string());
+				code.push(format!("scoreboard players operation {dst_lo} = %param0%0 reg"));
+				code.push(format!("scoreboard players operation {dst_hi} = %param0%1 reg"));
+			}
 		}
 		&LirInstr::Rotr64(dst, lhs, rhs) => {
 			let (dst_lo, dst_hi) = dst.split_lo_hi();
 			let (lhs_lo, lhs_hi) = lhs.split_lo_hi();
 			let rhs_lo = rhs.lo();
 
-			code.push(format!("scoreboard players operation %param0%0 reg = {lhs_lo}"));
-			code.push(format!("scoreboard players operation %param0%1 reg = {lhs_hi}"));
-			code.push(format!("scoreboard players operation %param1%0 reg = {rhs_lo}"));
-			code.push("scoreboard players operation %param1%0 reg %= %%64 reg".to_string());
-			code.push("function intrinsic:rotr_64".to_string());
-			code.push(format!("scoreboard players operation {dst_lo} = %param0%0 reg"));
-			code.push(format!("scoreboard players operation {dst_hi} = %param0%1 reg"));
+			if let Some(rhs) = rhs_lo.get_const() {
+				emit_constant_rotl_64(dst, lhs, 64 - rhs.rem_euclid(64), code, const_pool);
+			} else {
+				code.push(format!("scoreboard players operation %param0%0 reg = {lhs_lo}"));
+				code.push(format!("scoreboard players operation %param0%1 reg = {lhs_hi}"));
+				code.push(format!("scoreboard players operation %param1%0 reg = {rhs_lo}"));
+				code.push("scoreboard players operation %param1%0 reg %= %%64 reg".to_string());
+				code.push("function intrinsic:rotr_64".to_string());
+				code.push(format!("scoreboard players operation {dst_lo} = %param0%0 reg"));
+				code.push(format!("scoreboard players operation {dst_hi} = %param0%1 reg"));
+			}
+		}
+
+		&LirInstr::F32Add(dst, lhs, rhs) => f32_binop("add", dst, lhs, rhs, false, code),
+		// lhs - rhs = lhs + -rhs
+		&LirInstr::F32Sub(dst, lhs, rhs) => f32_binop("add", dst, lhs, rhs, true, code),
+		&LirInstr::F32Mul(dst, lhs, rhs) => f32_binop("mul", dst, lhs, rhs, false, code),
+		&LirInstr::F32Div(dst, lhs, rhs) => f32_binop("div", dst, lhs, rhs, false, code),
+		&LirInstr::F32Min(dst, lhs, rhs) => f32_binop("min", dst, lhs, rhs, false, code),
+		&LirInstr::F32Max(dst, lhs, rhs) => f32_binop("max", dst, lhs, rhs, false, code),
+		&LirInstr::F64Add(dst, lhs, rhs) => f64_binop("add", dst, lhs, rhs, false, code),
+		&LirInstr::F64Sub(dst, lhs, rhs) => f64_binop("add", dst, lhs, rhs, true, code),
+		&LirInstr::F64Mul(dst, lhs, rhs) => f64_binop("mul", dst, lhs, rhs, false, code),
+		&LirInstr::F64Div(dst, lhs, rhs) => f64_binop("div", dst, lhs, rhs, false, code),
+		&LirInstr::F64Min(dst, lhs, rhs) => f64_binop("min", dst, lhs, rhs, false, code),
+		&LirInstr::F64Max(dst, lhs, rhs) => f64_binop("max", dst, lhs, rhs, false, code),
+		&LirInstr::F32Sqrt(dst, src) => f32_unaryop("sqrt", dst, src, code),
+		&LirInstr::F64Sqrt(dst, src) => f64_unaryop("sqrt", dst, src, code),
+		&LirInstr::F32Floor(dst, src) => f32_unaryop("round_floor", dst, src, code),
+		&LirInstr::F32Ceil(dst, src) => f32_unaryop("round_ceil", dst, src, code),
+		&LirInstr::F32Trunc(dst, src) => f32_unaryop("round_trunc", dst, src, code),
+		&LirInstr::F32Nearest(dst, src) => f32_unaryop("round_nearest", dst, src, code),
+		&LirInstr::F64Floor(dst, src) => f64_unaryop("round_floor", dst, src, code),
+		&LirInstr::F64Ceil(dst, src) => f64_unaryop("round_ceil", dst, src, code),
+		&LirInstr::F64Trunc(dst, src) => f64_unaryop("round_trunc", dst, src, code),
+		&LirInstr::F64Nearest(dst, src) => f64_unaryop("round_nearest", dst, src, code),
+		&LirInstr::F32TruncTo64(dst, src) => {
+			let (dst_lo, dst_hi) = dst.split_lo_hi();
+
+			code.push(format!("scoreboard players operation %param0%0 reg = {src}"));
+			code.push("function intrinsic:f32/trunc".to_string());
+			code.push(format!("scoreboard players operation {dst_lo} = %return%0 reg"));
+			code.push(format!("scoreboard players operation {dst_hi} = %return%1 reg"));
 		}
+		&LirInstr::F64TruncTo64(dst, src) => {
+			let (src_lo, src_hi) = src.split_lo_hi();
+			let (dst_lo, dst_hi) = dst.split_lo_hi();
 
+			code.push(format!("scoreboard players operation %param0%0 reg = {src_lo}"));
+			code.push(format!("scoreboard players operation %param0%1 reg = {src_hi}"));
+			code.push("function intrinsic:f64/trunc".to_string());
+			code.push(format!("scoreboard players operation {dst_lo} = %return%0 reg"));
+			code.push(format!("scoreboard players operation {dst_hi} = %return%1 reg"));
+		}
+		&LirInstr::F32FromS64(dst, src) => f32_from_64("convert_s", dst, src, code),
+		&LirInstr::F32FromU64(dst, src) => f32_from_64("convert_u", dst, src, code),
+		&LirInstr::F64FromS64(dst, src) => f64_from_64("convert_s", dst, src, code),
+		&LirInstr::F64FromU64(dst, src) => f64_from_64("convert_u", dst, src, code),
 		&LirInstr::Xor(dst, lhs, rhs) => {
 			if let Some(rhs) = rhs.get_const() {
 				emit_constant_xor(dst, lhs, rhs, code, const_pool);
@@ -2083,13 +3805,10 @@ fn emit_instr(instr: &LirInstr, parent: &LirProgram, code: &mut Vec<String>, con
 			let reg = if half == Half::Hi { Register::global_hi(src) } else { Register::global_lo(src) };
 			code.push(format!("scoreboard players operation {dst} = {reg}"));
 		},
-		&LirInstr::Store32(src, addr) => mem_store_32(src, addr, code, const_pool),
-		&LirInstr::Store16(src, addr) => mem_store_16(src, addr, code, const_pool),
-		&LirInstr::Store8 (src, addr) => mem_store_8 (src, addr, code),
-		&LirInstr::Load64(dst, addr) => mem_load_64(dst, addr, code),
-		&LirInstr::Load32(dst, addr) => mem_load_32(dst, addr, code, const_pool),
-		&LirInstr::Load16(dst, addr) => mem_load_16(dst, addr, code),
-		&LirInstr::Load8 (dst, addr) => mem_load_8 (dst, addr, code),
+		LirInstr::Store32(..) | LirInstr::Store16(..) | LirInstr::Store8(..) |
+		LirInstr::Load64(..) | LirInstr::Load32(..) | LirInstr::Load16(..) | LirInstr::Load8(..) => {
+			parent.layout.memory_backend.backend().emit_access(instr, &parent.layout, code, const_pool);
+		}
 		&LirInstr::SignExtend(_, _) => todo!(),
 		&LirInstr::Select { dst, true_reg, false_reg, cond } => {
 			if dst == true_reg {
@@ -2102,37 +3821,43 @@ fn emit_instr(instr: &LirInstr, parent: &LirProgram, code: &mut Vec<String>, con
 			}
 		}
 		&LirInstr::Call { func } => {
-			if jump_mode() == JumpMode::Direct {
-				let func_id = get_mc_id(BlockId { func: func as usize, block: 0 });
-				code.push(format!("function {func_id}"));
-			} else {
-				todo!()
-			}
+			let func_id = get_mc_id(&parent.func_names, BlockId { func: func as usize, block: 0 });
+			code.push(format!("function {func_id}"));
+			code.push(trap_guard());
 		}
 		LirInstr::CallIndirect { table, table_entry } => {
-			if jump_mode() == JumpMode::Direct {
-				let cond_taken = Register::cond_taken();
+			let cond_taken = Register::cond_taken();
 
-				code.push(format!("scoreboard players set {cond_taken} 0"));
+			code.push(format!("scoreboard players set {cond_taken} 0"));
 
-				for (idx, arm) in table.iter().enumerate() {
-					if let Some(arm) = arm {
-						let arm_func = get_mc_id(BlockId { func: *arm, block: 0 });
-						code.push(format!("execute if score {cond_taken} matches 0 run execute if score {table_entry} matches {idx} run function {arm_func}"));
-					}
+			for (idx, arm) in table.iter().enumerate() {
+				if let Some(arm) = arm {
+					let arm_func = get_mc_id(&parent.func_names, BlockId { func: *arm, block: 0 });
+					code.push(format!("execute if score {cond_taken} matches 0 run execute if score {table_entry} matches {idx} run function {arm_func}"));
 				}
+			}
 
-				code.push(format!("# !INTERPRETER: ASSERT unless score {cond_taken} matches 0"));
-				code.push(format!("execute if score {cond_taken} matches 0 run tellraw @a [{{\"text\":\"BAD CALL INDIRECT\"}}]"));
-			} else {
-				todo!()
+			// Bad entries were trapped on by `CallIndirectCheck` beforehand, if they are checked
+			code.push(format!("# !INTERPRETER: ASSERT unless score {cond_taken} matches 0"));
+			code.push(trap_guard());
+		}
+		LirInstr::CallIndirectCheck { table_entry, index, null, mismatched, len } => {
+			if let Some(len) = *len {
+				let cond = (len > 0).then(|| format!("unless score {table_entry} matches 0..{}", len - 1));
+				emit_trap_at_index(TrapKind::TableOutOfBounds, *index, block_id, &parent.func_names, cond.as_deref(), code);
+			}
+			for (kind, values) in [(TrapKind::UninitializedElement, null), (TrapKind::IndirectCallTypeMismatch, mismatched)] {
+				for (start, end) in get_ranges(values) {
+					let cond = format!("if score {table_entry} matches {start}..{end}");
+					emit_trap_at_index(kind, *index, block_id, &parent.func_names, Some(&cond), code);
+				}
 			}
 		}
 		LirInstr::Push(reg) => push_data(reg, code),
 		LirInstr::Pop(reg) => pop_data(reg, code),
 		LirInstr::IfCond { cond, instr } => {
 			let mut child = Vec::new();
-			emit_instr(instr, parent, &mut child, const_pool);
+			emit_instr(instr, block_id, parent, &mut child, const_pool);
 
 			let prefix;
 			match cond {
@@ -2154,40 +3879,270 @@ fn emit_instr(instr: &LirInstr, parent: &LirProgram, code: &mut Vec<String>, con
 		LirInstr::PopLocalFrame(ty) => pop_local_frame(ty, code),
 
 		&LirInstr::PushReturnAddr(block_id) => {
-			push_return_addr(block_id, code);
+			push_return_addr(&parent.func_names, block_id, code);
 		}
 		&LirInstr::PopReturnAddr => {
 			pop_return_addr(code);
 		}
+		&LirInstr::RecursionYieldCheck(depth) => {
+			// Pretend the command limit was reached so the following command check yields
+			code.push(format!("scoreboard players add {RECURSION_DEPTH_VAR} 1"));
+			code.push(format!("execute if score {RECURSION_DEPTH_VAR} matches {depth}.. run scoreboard players operation {CMDS_RUN_VAR} > {MAX_CMDS_VAR}"));
+			code.push(format!("execute if score {RECURSION_DEPTH_VAR} matches {depth}.. run scoreboard players set {RECURSION_DEPTH_VAR} 0"));
+		}
 
-		LirInstr::Memset { dest, value, length, result } => {
+		// The buffer intrinsics find the word and the position in it from the base and the offset themselves,
+		// so they're only called when that position isn't known
+		&LirInstr::BufLoad8(dst, ptr, idx) => {
+			if let Some(pos) = buf_word_pos(ptr, idx, 1) {
+				buf_set_ptr(ptr, idx, 1, code);
+				code.push(format!("execute at {MEM_PTR_UUID} store result score {dst} run data get block ~ ~ ~ RecordItem.tag.Memory 1"));
+				if pos != 0 {
+					code.push(format!("scoreboard players operation {dst} /= %%{} reg", 1 << (pos * 8)));
+				}
+				code.push(format!("scoreboard players operation {dst} %= %%256 reg"));
+			} else {
+				code.push(format!("scoreboard players operation %param0%0 reg = {}", ptr.0));
+				code.push(format!("scoreboard players operation %param1%0 reg = {}", idx.0));
+				code.push("function intrinsic:buf_get_u8".to_string());
+				code.push(format!("scoreboard players operation {dst} = %param0%0 reg"));
+			}
+		}
+		&LirInstr::BufLoad16(dst, ptr, idx) => {
+			match buf_word_pos(ptr, idx, 2) {
+				Some(3) => {
+					buf_set_ptr(ptr, idx, 2, code);
+					code.push(format!("execute at {MEM_PTR_UUID} store result score %param0%0 reg run data get block ~ ~ ~ RecordItem.tag.Memory 1"));
+					code.push("function intrinsic:buf_get_u16/crossing".to_string());
+					code.push(format!("scoreboard players operation {dst} = %param0%0 reg"));
+				}
+				Some(pos) => {
+					buf_set_ptr(ptr, idx, 2, code);
+					code.push(format!("execute at {MEM_PTR_UUID} store result score {dst} run data get block ~ ~ ~ RecordItem.tag.Memory 1"));
+					if pos != 0 {
+						code.push(format!("scoreboard players operation {dst} /= %%{} reg", 1 << (pos * 8)));
+					}
+					code.push(format!("scoreboard players operation {dst} %= %%65536 reg"));
+				}
+				None => {
+					code.push(format!("scoreboard players operation %param0%0 reg = {}", ptr.0));
+					code.push(format!("scoreboard players operation %param1%0 reg = {}", idx.0));
+					code.push(format!("scoreboard players operation %param1%0 reg += {}", idx.0));
+					code.push("function intrinsic:buf_get_u16".to_string());
+					code.push(format!("scoreboard players operation {dst} = %param0%0 reg"));
+				}
+			}
+		}
+		&LirInstr::BufStore8(src, ptr, idx) => {
+			if let Some(pos) = buf_word_pos(ptr, idx, 1) {
+				code.push(format!("scoreboard players operation %param2%0 reg = {src}"));
+				code.push("scoreboard players operation %param2%0 reg %= %%256 reg".to_string());
+				buf_set_ptr(ptr, idx, 1, code);
+				buf_replace_in_word(pos, 256, code);
+			} else {
+				code.push(format!("scoreboard players operation %param0%0 reg = {}", ptr.0));
+				code.push(format!("scoreboard players operation %param1%0 reg = {}", idx.0));
+				code.push(format!("scoreboard players operation %param2%0 reg = {src}"));
+				code.push("function intrinsic:buf_set_u8".to_string());
+			}
+		}
+		&LirInstr::BufStore16(src, ptr, idx) => {
+			match buf_word_pos(ptr, idx, 2) {
+				Some(3) => {
+					code.push(format!("scoreboard players operation %param2%0 reg = {src}"));
+					code.push("scoreboard players operation %param2%0 reg %= %%65536 reg".to_string());
+					buf_set_ptr(ptr, idx, 2, code);
+					code.push("function intrinsic:buf_set_u16/crossing".to_string());
+				}
+				Some(pos) => {
+					code.push(format!("scoreboard players operation %param2%0 reg = {src}"));
+					code.push("scoreboard players operation %param2%0 reg %= %%65536 reg".to_string());
+					buf_set_ptr(ptr, idx, 2, code);
+					buf_replace_in_word(pos, 65536, code);
+				}
+				None => {
+					code.push(format!("scoreboard players operation %param0%0 reg = {}", ptr.0));
+					code.push(format!("scoreboard players operation %param1%0 reg = {}", idx.0));
+					code.push(format!("scoreboard players operation %param1%0 reg += {}", idx.0));
+					code.push(format!("scoreboard players operation %param2%0 reg = {src}"));
+					code.push("function intrinsic:buf_set_u16".to_string());
+				}
+			}
+		}
+		LirInstr::Memset { memory, dest, value, length, result } => {
 			code.push(format!("scoreboard players operation %param0%0 reg = {dest}"));
 			code.push(format!("scoreboard players operation %param1%0 reg = {value}"));
+			// Only the low byte of the value is used
+			code.push("scoreboard players operation %param1%0 reg %= %%256 reg".to_string());
 			code.push(format!("scoreboard players operation %param2%0 reg = {length}"));
-			code.push("function intrinsic:memset".to_string());
+			with_memory_z(*memory, code, |code| code.push("function intrinsic:memset".to_string()));
 			code.push(format!("scoreboard players operation {result} = %return%0 reg"));
 		}
-
-		LirInstr::TurtleSetX(x) => {
-			code.push(format!("execute store result entity {TURTLE_UUID} Pos[0] double 1 run scoreboard players get {x}"));
+		LirInstr::Memcpy { memory, dest, src, length } => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {dest}"));
+			code.push(format!("scoreboard players operation %param1%0 reg = {src}"));
+			code.push(format!("scoreboard players operation %param2%0 reg = {length}"));
+			with_memory_z(*memory, code, |code| code.push("function intrinsic:memcpy".to_string()));
 		}
-		LirInstr::TurtleSetY(y) => {
-			code.push(format!("execute store result entity {TURTLE_UUID} Pos[1] double 1 run scoreboard players get {y}"));
+		LirInstr::MemoryGrow { memory, pages, result } => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {pages}"));
+			code.push(format!("function {}", get_memory_grow_func(*memory)));
+			code.push(format!("scoreboard players operation {result} = %return%0 reg"));
 		}
-		LirInstr::TurtleSetZ(z) => {
-			code.push(format!("execute store result entity {TURTLE_UUID} Pos[2] double 1 run scoreboard players get {z}"));
+		LirInstr::MemorySize(memory, dst) => {
+			code.push(format!("scoreboard players operation {dst} = {}", get_memory_pages_var(*memory)));
 		}
-		&LirInstr::TurtleSetBlock(r) => turtle_set_block(r, code),
-		&LirInstr::TurtleFillBlock { block, x_span, y_span, z_span } => turtle_fill_block(block, x_span, y_span, z_span, code),
-		LirInstr::TurtleGetBlock(r) => turtle_get_block(*r, code),
-		&LirInstr::TurtleCopyRegion { x_span, y_span, z_span } => turtle_copy_region(x_span, y_span, z_span, code),
-		&LirInstr::TurtlePasteRegionMasked { x_span, y_span, z_span } => turtle_paste_region_masked(x_span, y_span, z_span, code),
+		&LirInstr::TableGet { table, dst, index } => {
+			let capacity = parent.tables[table as usize].capacity();
+
+			code.push(format!("scoreboard players operation {TABLE_INDEX_VAR} = {index}"));
+			code.push(format!("scoreboard players set {dst} 0"));
+			for idx in 0..capacity {
+				let entry_var = get_table_entry_var(table, idx);
+				code.push(format!("execute if score {TABLE_INDEX_VAR} matches {idx} run scoreboard players operation {dst} = {entry_var}"));
+			}
+
+			emit_table_bounds_check(table, TABLE_INDEX_VAR, block_id, &parent.func_names, code);
+		}
+		&LirInstr::TableSet { table, index, src } => {
+			let table_info = &parent.tables[table as usize];
+			let size_var = get_table_size_var(table);
+
+			for idx in 0..table_info.capacity() {
+				let entry_var = get_table_entry_var(table, idx);
+				if idx < table_info.elements.len() {
+					code.push(format!("execute if score {index} matches {idx} run scoreboard players operation {entry_var} = {src}"));
+				} else {
+					// Entries past the initial size may not exist yet
+					code.push(format!("execute if score {index} matches {idx} if score {size_var} matches {}.. run scoreboard players operation {entry_var} = {src}", idx + 1));
+				}
+			}
+
+			emit_table_bounds_check(table, &index.to_string(), block_id, &parent.func_names, code);
+		}
+		&LirInstr::TableGrow { table, init, count, result } => {
+			let capacity = parent.tables[table as usize].capacity();
+			let size_var = get_table_size_var(table);
+
+			code.push(format!("execute store success score {TABLE_GROW_OK_VAR} if score {count} matches 0..{capacity}"));
+			code.push(format!("scoreboard players operation {TABLE_GROW_END_VAR} = {size_var}"));
+			code.push(format!("scoreboard players operation {TABLE_GROW_END_VAR} += {count}"));
+			code.push(format!("execute if score {TABLE_GROW_END_VAR} matches {}.. run scoreboard players set {TABLE_GROW_OK_VAR} 0", capacity + 1));
+
+			for idx in 0..capacity {
+				let entry_var = get_table_entry_var(table, idx);
+				code.push(format!("execute if score {TABLE_GROW_OK_VAR} matches 1 if score {size_var} matches ..{idx} if score {TABLE_GROW_END_VAR} matches {}.. run scoreboard players operation {entry_var} = {init}", idx + 1));
+			}
+
+			code.push(format!("scoreboard players operation {result} = {size_var}"));
+			code.push(format!("execute if score {TABLE_GROW_OK_VAR} matches 0 run scoreboard players set {result} -1"));
+			code.push(format!("execute if score {TABLE_GROW_OK_VAR} matches 1 run scoreboard players operation {size_var} = {TABLE_GROW_END_VAR}"));
+		}
+		&LirInstr::TableSize { table, dst } => {
+			let size_var = get_table_size_var(table);
+			code.push(format!("scoreboard players operation {dst} = {size_var}"));
+		}
+		&LirInstr::TableInit { table, segment, dst, src, length } => {
+			if parent.elem_segments[segment as usize].is_empty() {
+				// Only the bounds check is left, since nothing can be copied out of an empty segment
+				let size_var = get_table_size_var(table);
+				let conds = [
+					format!("unless score {src} matches 0"),
+					format!("unless score {length} matches 0"),
+					format!("unless score {dst} matches 0.."),
+					format!("if score {dst} > {size_var}"),
+				];
+				for cond in conds {
+					emit_trap(TrapKind::TableOutOfBounds, block_id, &parent.func_names, Some(&cond), code);
+				}
+			} else {
+				code.push(format!("scoreboard players operation %param0%0 reg = {dst}"));
+				code.push(format!("scoreboard players operation %param1%0 reg = {src}"));
+				code.push(format!("scoreboard players operation %param2%0 reg = {length}"));
+				code.push(format!("function wasmrunner:__table_init_{table}_{segment}"));
+				emit_trap(TrapKind::TableOutOfBounds, block_id, &parent.func_names, Some(&format!("if score {TABLE_INIT_OK_VAR} matches 0")), code);
+			}
+		}
+		&LirInstr::ElemDrop(segment) => {
+			let len_var = get_elem_len_var(segment);
+			code.push(format!("scoreboard players set {len_var} 0"));
+		}
+
+		LirInstr::TurtleSetX(x) => {
+			code.push(format!("execute store result entity {TURTLE_UUID} Pos[0] double 1 run scoreboard players get {x}"));
+		}
+		LirInstr::TurtleSetY(y) => {
+			code.push(format!("execute store result entity {TURTLE_UUID} Pos[1] double 1 run scoreboard players get {y}"));
+		}
+		LirInstr::TurtleSetZ(z) => {
+			code.push(format!("execute store result entity {TURTLE_UUID} Pos[2] double 1 run scoreboard players get {z}"));
+		}
+		&LirInstr::TurtleSetBlock(r) => turtle_set_block(r, &parent.block_palette, "", code),
+		LirInstr::TurtleSetBlockNbt { block, nbt } => turtle_set_block(*block, &parent.block_palette, nbt, code),
+		&LirInstr::TurtleFillBlock { block, x_span, y_span, z_span } => turtle_fill_block(block, x_span, y_span, z_span, &parent.block_palette, code),
+		LirInstr::TurtleGetBlock(r) => turtle_get_block(*r, &parent.block_palette, code),
+		&LirInstr::TurtleCopyRegion { x_span, y_span, z_span } => turtle_copy_region(x_span, y_span, z_span, parent.layout.memory_origin, code),
+		&LirInstr::TurtlePasteRegionMasked { x_span, y_span, z_span } => turtle_paste_region_masked(x_span, y_span, z_span, parent.layout.memory_origin, code),
 		LirInstr::TurtleCopy => {
 			code.push(format!("execute at {TURTLE_UUID} run clone ~ ~ ~ ~ ~ ~ -1 -1 -1"));
 		}
 		LirInstr::TurtlePaste => {
 			code.push(format!("execute at {TURTLE_UUID} run clone -1 -1 -1 -1 -1 -1 ~ ~ ~"));
 		}
+		&LirInstr::TurtleFace { yaw, pitch } => {
+			code.push(format!("execute store result entity {TURTLE_UUID} Rotation[0] float 1 run scoreboard players get {yaw}"));
+			code.push(format!("execute store result entity {TURTLE_UUID} Rotation[1] float 1 run scoreboard players get {pitch}"));
+		}
+		&LirInstr::TurtleForward(blocks) => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {blocks}"));
+			code.push("function intrinsic:turtle_forward".to_string());
+		}
+		&LirInstr::TurtleTurn { degrees } => {
+			code.push(format!("execute store result score %turtle_yaw reg run data get entity {TURTLE_UUID} Rotation[0]"));
+			code.push(format!("scoreboard players add %turtle_yaw reg {degrees}"));
+			code.push(format!("execute store result entity {TURTLE_UUID} Rotation[0] float 1 run scoreboard players get %turtle_yaw reg"));
+		}
+		&LirInstr::TurtleRaycast { dst, max_dist } => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {max_dist}"));
+			code.push("function intrinsic:raycast".to_string());
+			turtle_get_block(dst, &parent.block_palette, code);
+		}
+		&LirInstr::TurtleLightLevel(dst) => turtle_light_level(dst, code),
+		&LirInstr::TurtleCheckBlock { dst, solid } => turtle_check_block(dst, solid, code),
+		&LirInstr::PlaySound { sound, volume, pitch } => play_sound(sound, volume, pitch, code),
+		&LirInstr::Particle { particle, count } => show_particle(particle, count, code),
+		&LirInstr::BossbarCreate(bar) => {
+			for_each_bossbar(bar, code, |id| format!("bossbar remove {id}"));
+			for_each_bossbar(bar, code, |id| format!(r#"bossbar add {id} {{"text":""}}"#));
+			for_each_bossbar(bar, code, |id| format!("bossbar set {id} players @a"));
+		}
+		&LirInstr::BossbarSetValue(bar, value) => {
+			for_each_bossbar(bar, code, |id| format!("execute store result bossbar {id} value run scoreboard players get {value}"));
+		}
+		&LirInstr::BossbarSetMax(bar, max) => {
+			for_each_bossbar(bar, code, |id| format!("execute store result bossbar {id} max run scoreboard players get {max}"));
+		}
+		&LirInstr::BossbarSetName(bar, ptr, len) => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {ptr}"));
+			code.push(format!("scoreboard players operation %param1%0 reg = {len}"));
+			code.push(format!("scoreboard players operation %param2%0 reg = {bar}"));
+			code.push("function intrinsic:bossbar_name".to_string());
+		}
+		&LirInstr::NearestPlayerPos { dst, axis } => {
+			code.push(format!("execute at {TURTLE_UUID} store result score {dst} run data get entity @p Pos[{axis}]"));
+		}
+		&LirInstr::EntityCount { dst, selector } => entity_count(dst, selector, code),
+		&LirInstr::GameTime(dst) => {
+			// `time query` gives an int, which only wraps around after a world has existed for a few years
+			let (lo, hi) = dst.split_lo_hi();
+			code.push(format!("execute store result score {lo} run time query gametime"));
+			code.push(format!("scoreboard players set {hi} 0"));
+		}
+		&LirInstr::Random { dst, bound } => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {bound}"));
+			code.push("function intrinsic:random".to_string());
+			code.push(format!("scoreboard players operation {dst} = %return%0 reg"));
+		}
 		LirInstr::PrintInt(i) => {
 			let mut s = String::new();
 			s.push_str(r#"tellraw @a [{"text":"Printed "},{"score":{"name":""#);
@@ -2197,10 +4152,52 @@ fn emit_instr(instr: &LirInstr, parent: &LirProgram, code: &mut Vec<String>, con
 			s.push_str(r#"","objective":"reg"}}]"#);
 			code.push(s);
 		}
+		LirInstr::PrintLong(i) => {
+			let (lo, hi) = i.split_lo_hi();
+			code.push(format!("scoreboard players operation %param0%0 reg = {lo}"));
+			code.push(format!("scoreboard players operation %param0%1 reg = {hi}"));
+			code.push("function intrinsic:print_long".to_string());
+		}
+		LirInstr::PrintF32(f) => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {f}"));
+			code.push("function intrinsic:print_f32".to_string());
+		}
+		LirInstr::PrintF64(f) => {
+			let (lo, hi) = f.split_lo_hi();
+			code.push(format!("scoreboard players operation %param0%0 reg = {lo}"));
+			code.push(format!("scoreboard players operation %param0%1 reg = {hi}"));
+			code.push("function intrinsic:print_f64".to_string());
+		}
 		LirInstr::PutChar(i) => {
 			code.push(format!("scoreboard players operation %param0%0 reg = {i}"));
 			code.push("function intrinsic:put_char".to_string());
 		}
+		LirInstr::PrintStr(ptr, len) => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {ptr}"));
+			code.push(format!("scoreboard players operation %param1%0 reg = {len}"));
+			code.push("function intrinsic:print_str".to_string());
+		}
+		&LirInstr::Title { ptr, len, actionbar } => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {ptr}"));
+			code.push(format!("scoreboard players operation %param1%0 reg = {len}"));
+			code.push("function intrinsic:print_str/capture".to_string());
+			let kind = if actionbar { "actionbar" } else { "title" };
+			code.push(format!(r#"title @a {kind} {{"nbt":"buffer","storage":"wasm:stdout","interpret":true}}"#));
+			code.push("function intrinsic:print_str/restore".to_string());
+		}
+		LirInstr::PrintFmt(fmt, args) => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {fmt}"));
+			code.push(format!("scoreboard players operation %param1%0 reg = {args}"));
+			code.push("function intrinsic:print_fmt".to_string());
+		}
+		LirInstr::MemorySnapshot(i) => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {i}"));
+			code.push("function wasmrunner:__memory_snapshot".to_string());
+		}
+		LirInstr::MemoryRestore(i) => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {i}"));
+			code.push("function wasmrunner:__memory_restore".to_string());
+		}
 		LirInstr::WasiProcExit(i) => {
 			let mut s = String::new();
 			s.push_str(r#"tellraw @a [{"text":"Program finished with exit code "},{"score":{"name":""#);
@@ -2210,12 +4207,26 @@ fn emit_instr(instr: &LirInstr, parent: &LirProgram, code: &mut Vec<String>, con
 			s.push_str(r#"","objective":"reg"}}]"#);
 			code.push(s);
 		}
-		LirInstr::Todo(msg) => {
-			let mut s = String::new();
-			s.push_str(r#"tellraw @a [{"text":"TODO: "#);
-			s.push_str(*msg);
-			s.push_str(r#""}]"#);
-			code.push(s);
+		LirInstr::WasiFdWrite { fd, iovs, iovs_len, nwritten, result } => {
+			code.push(format!("scoreboard players operation %param0%0 reg = {fd}"));
+			code.push(format!("scoreboard players operation %param1%0 reg = {iovs}"));
+			code.push(format!("scoreboard players operation %param2%0 reg = {iovs_len}"));
+			code.push(format!("scoreboard players operation %param3%0 reg = {nwritten}"));
+			code.push("function intrinsic:fd_write".to_string());
+			code.push(format!("scoreboard players operation {result} = %return%0 reg"));
+		}
+		LirInstr::RawCommand(cmd) => {
+			code.push(cmd.clone());
+		}
+		LirInstr::RawCommandStore { dst, cmd, success } => {
+			let kind = if *success { "success" } else { "result" };
+			code.push(format!("execute store {kind} score {dst} run {cmd}"));
+		}
+		LirInstr::PackCall { namespace, export, .. } => {
+			code.push(format!("function {}", get_pack_call_stub(namespace, export)));
+		}
+		&LirInstr::Todo(msg) => {
+			emit_trap(TrapKind::Todo(msg), block_id, &parent.func_names, None, code);
 		}
 	}
 }
@@ -2224,158 +4235,438 @@ fn emit_instr(instr: &LirInstr, parent: &LirProgram, code: &mut Vec<String>, con
 
 // jump to a split block: push return address
 
-fn emit_block(block_id: BlockId, block: &LirBasicBlock, parent: &LirProgram, const_pool: &mut HashSet<i32>) -> Function {
-	let mut code: Vec<String> = Vec::new();
+/// Adds `sign * src` to `dst`, using `scoreboard players add` or `remove` when `src` is a constant
+/// so its holder isn't needed.
+fn emit_add_const(dst: Register, src: Register, sign: i32, code: &mut Vec<String>) {
+	match src.get_const().and_then(|c| c.checked_mul(sign)) {
+		Some(c) if c >= 0 => code.push(format!("scoreboard players add {dst} {c}")),
+		Some(c) if c != i32::MIN => code.push(format!("scoreboard players remove {dst} {}", -c)),
+		_ => {
+			let op = if sign > 0 { "+=" } else { "-=" };
+			code.push(format!("scoreboard players operation {dst} {op} {src}"));
+		}
+	}
+}
 
-	/*if block_id.block == 0 {
-		code.push(format!("tellraw @a [{{\"text\":\"{block_id:?}\"}}]"));
-	}*/
+/// Saves the operands of an instruction that may overflow, so that they can be checked after it runs.
+fn emit_overflow_check_pre(instr: &LirInstr, code: &mut Vec<String>) {
+	let (dst, src) = match instr {
+		&LirInstr::Add(dst, src) |
+		&LirInstr::Sub(dst, src) |
+		&LirInstr::Mul(dst, src) => (dst, src),
+		_ => return,
+	};
+
+	code.push(format!("scoreboard players operation {} = {dst}", Register::overflow_lhs()));
+	code.push(format!("scoreboard players operation {} = {src}", Register::overflow_rhs()));
+}
 
-	for instr in block.body.iter() {
-		emit_instr(instr, parent, &mut code, const_pool);
+/// Traps when the result of an instruction differs from the result it would have with unbounded integers,
+/// giving the offsets of the wasm code the block was lowered from.
+fn emit_overflow_check_post(instr: &LirInstr, block_id: BlockId, func_names: &HashMap<usize, String>, code_ranges: Option<&[Range<usize>]>, code: &mut Vec<String>) {
+	let lhs = Register::overflow_lhs();
+	let rhs = Register::overflow_rhs();
+
+	let (op, conds) = match instr {
+		&LirInstr::Add(dst, _) => {
+			("i32.add", vec![
+				format!("if score {lhs} matches 0.. if score {rhs} matches 0.. if score {dst} matches ..-1"),
+				format!("if score {lhs} matches ..-1 if score {rhs} matches ..-1 if score {dst} matches 0.."),
+			])
+		}
+		&LirInstr::Sub(dst, _) => {
+			("i32.sub", vec![
+				format!("if score {lhs} matches 0.. if score {rhs} matches ..-1 if score {dst} matches ..-1"),
+				format!("if score {lhs} matches ..-1 if score {rhs} matches 0.. if score {dst} matches 0.."),
+			])
+		}
+		&LirInstr::Mul(dst, _) => {
+			// If no overflow happened, dividing the product by one operand gives back the other one
+			let quot = Register::overflow_quot();
+			code.push(format!("scoreboard players operation {quot} = {dst}"));
+			code.push(format!("execute unless score {lhs} matches 0 run scoreboard players operation {quot} /= {lhs}"));
+
+			("i32.mul", vec![
+				format!("unless score {lhs} matches 0 unless score {quot} = {rhs}"),
+				// i32::MIN / -1 also wraps, so the check above misses this case
+				format!("if score {lhs} matches -1 if score {rhs} matches -2147483648"),
+			])
+		}
+		_ => return,
+	};
+
+	let mut location = describe_location(func_names, block_id);
+	if let Some(ranges) = code_ranges {
+		let ranges = ranges.iter().map(|range| format!("{:#X}..{:#X}", range.start, range.end)).collect::<Vec<_>>();
+		location.push_str(&format!(", code offsets {}", ranges.join(" ")));
 	}
+	let message = format!(r#"{{"text":"Trap: {} in {op} ({location})","color":"red"}}"#, escape_trap_kind(TrapKind::IntegerOverflow));
+	for cond in conds {
+		emit_trap_message(TrapKind::IntegerOverflow, &message, Some(&cond), code);
+	}
+}
 
-	// FIXME: This doesn't include called functions, the terminator, or count `execute` commands properly.
-	let mut num_cmds = code.len();
-	for c in code.iter() {
-		if c.contains("intrinsic:and") || c.contains("intrinsic:or") || c.contains("intrinsic:xor") {
-			num_cmds += 200;
-		} else if c.contains("intrinsic:shl_64") {
-			num_cmds += 700;
-		} else if c.contains("intrinsic:i64_sdivrem/rem")  {
-			num_cmds += 4_000;
-		} else if c.contains("intrinsic:i64_sdivrem/div") || c.contains("intrinsic:i64_udivrem") {
-			num_cmds += 3_500;
-		} else {
-			// TODO: memset? bitwise operations?
+/// Traps before an integer division or remainder if its divisor is zero.
+fn emit_div_by_zero_check(instr: &LirInstr, block_id: BlockId, func_names: &HashMap<usize, String>, code: &mut Vec<String>) {
+	let (lo, hi) = match *instr {
+		LirInstr::DivS(_, _, rhs) | LirInstr::DivU(_, _, rhs) | LirInstr::RemS(_, _, rhs) | LirInstr::RemU(_, _, rhs) => (rhs, None),
+		LirInstr::DivS64(_, _, rhs) | LirInstr::DivU64(_, _, rhs) | LirInstr::RemS64(_, _, rhs) | LirInstr::RemU64(_, _, rhs) => (rhs.lo(), Some(rhs.hi())),
+		_ => return,
+	};
+
+	let mut conds = Vec::new();
+	for half in std::iter::once(lo).chain(hi) {
+		match half.get_const() {
+			Some(0) => {}
+			Some(_) => return,
+			None => conds.push(format!("if score {half} matches 0")),
 		}
 	}
-	code.push(format!("scoreboard players add {CMDS_RUN_VAR} {num_cmds}"));
 
-	match &block.term {
+	if conds.is_empty() {
+		emit_trap(TrapKind::DivByZero, block_id, func_names, None, code);
+	} else {
+		emit_trap(TrapKind::DivByZero, block_id, func_names, Some(&conds.join(" ")), code);
+	}
+}
+
+/// Counts the local frames pushed and popped, and traps before a call if there are already `max_depth` of them,
+/// since the function it calls would push another one. Return addresses are only pushed by calls that push a local frame too,
+/// so their stack is never deeper. The check is made by the caller, so a function that never calls doesn't need gates after it.
+fn emit_call_depth_check(instr: &LirInstr, max_depth: u32, block_id: BlockId, func_names: &HashMap<usize, String>, code: &mut Vec<String>) {
+	match instr {
+		LirInstr::Call { .. } | LirInstr::CallIndirect { .. } => {
+			let location = describe_location(func_names, block_id);
+			let kind = TrapKind::CallStackExhausted;
+			let message = format!(r#"{{"text":"Trap: {} after {max_depth} nested calls ({location})","color":"red"}}"#, escape_trap_kind(kind));
+			emit_trap_message(kind, &message, Some(&format!("if score {CALL_DEPTH_VAR} matches {max_depth}..")), code);
+		}
+		LirInstr::PushLocalFrame(_) => {
+			code.push(format!("scoreboard players add {CALL_DEPTH_VAR} 1"));
+		}
+		LirInstr::PopLocalFrame(_) => {
+			code.push(format!("scoreboard players remove {CALL_DEPTH_VAR} 1"));
+		}
+		_ => {}
+	}
+}
+
+/// Describes where a block is in the wasm program for messages printed in chat, escaped for a JSON string.
+fn describe_location(func_names: &HashMap<usize, String>, block_id: BlockId) -> String {
+	let BlockId { func, block } = block_id;
+	let func = match func_names.get(&func) {
+		Some(name) => format!("{func} {}", name.replace('\\', "\\\\").replace('"', "\\\"")),
+		None => func.to_string(),
+	};
+	format!("wasm function {func}, block {block}")
+}
+
+/// Returns from the current block if the program has trapped, which for versions with `return run`
+/// also returns from every block that called it, so the program stops.
+fn trap_guard() -> String {
+	return_line(&format!("execute if score {TRAP_VAR} matches 1.. run return 0"))
+}
+
+/// The prefixes of the functions that run blocks of the program, which may trap.
+static PROGRAM_FUNCS: &[&str] = &[
+	"function wasmrunner:wasm_",
+	"function wasmrunner:__jump_table_",
+	"function wasmrunner:__call_target",
+	"function wasmrunner:__return_to_saved",
+];
+
+/// The `execute` subcommands every command that may run after a trap starts with, see `gate_after_traps`.
+fn trap_gate() -> String {
+	format!("execute unless score {TRAP_VAR} matches 1.. run ")
+}
+
+/// Versions without `return run` can't leave a function early, so once a command may have trapped,
+/// the commands after it only run if the program hasn't. A command may have trapped if it runs
+/// another block of the program or checks `trap_guard`.
+/// Versions with `return run` leave out the gates again when the pack is written, since `trap_guard` stops them instead.
+fn gate_after_traps(code: Vec<String>) -> Vec<String> {
+	let gate = trap_gate();
+	let mut may_have_trapped = false;
+	code.into_iter().map(|cmd| {
+		let cmd = if may_have_trapped && !cmd.starts_with('#') { format!("{gate}{cmd}") } else { cmd };
+		let runs_block = cmd.split(" run ").any(|part| PROGRAM_FUNCS.iter().any(|func| part.starts_with(func)));
+		may_have_trapped |= runs_block || cmd == trap_guard();
+		cmd
+	}).collect()
+}
+
+/// Removes the gate added by `gate_after_traps` from a command, if it has one.
+pub(crate) fn strip_trap_gate(cmd: String) -> String {
+	match cmd.strip_prefix(&trap_gate()) {
+		Some(cmd) => cmd.to_string(),
+		None => cmd,
+	}
+}
+
+/// Traps if the given `execute` condition is met, or always if there is none:
+/// prints why and where in chat, stores the trap's code in `TRAP_VAR`, and stops the program.
+fn emit_trap(kind: TrapKind, block_id: BlockId, func_names: &HashMap<usize, String>, cond: Option<&str>, code: &mut Vec<String>) {
+	let location = describe_location(func_names, block_id);
+	let message = format!(r#"{{"text":"Trap: {} ({location})","color":"red"}}"#, escape_trap_kind(kind));
+	emit_trap_message(kind, &message, cond, code);
+}
+
+/// Traps like `emit_trap`, also printing the table index that was accessed.
+fn emit_trap_at_index(kind: TrapKind, index: Register, block_id: BlockId, func_names: &HashMap<usize, String>, cond: Option<&str>, code: &mut Vec<String>) {
+	let location = describe_location(func_names, block_id);
+	let index = index.to_string();
+	let holder = index.split_once(' ').unwrap().0;
+	let message = format!(
+		r#"{{"text":"Trap: {} at index ","color":"red"}},{{"score":{{"name":"{holder}","objective":"reg"}}}},{{"text":" ({location})"}}"#,
+		escape_trap_kind(kind),
+	);
+	emit_trap_message(kind, &message, cond, code);
+}
+
+fn escape_trap_kind(kind: TrapKind) -> String {
+	kind.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn emit_trap_message(kind: TrapKind, message: &str, cond: Option<&str>, code: &mut Vec<String>) {
+	let prefix = cond.map(|cond| format!("execute {cond} run ")).unwrap_or_default();
+
+	code.push(format!("{prefix}tellraw @a [{message}]"));
+	code.push(format!("{prefix}scoreboard players set {TRAP_VAR} {}", kind.code()));
+	code.push(format!("{prefix}function wasmrunner:__trap"));
+	code.push(trap_guard());
+}
+
+/// Groups sorted values into runs of consecutive values, given as their first and last value.
+fn get_ranges(values: &[usize]) -> Vec<(usize, usize)> {
+	let mut ranges: Vec<(usize, usize)> = Vec::new();
+	for &value in values.iter() {
+		match ranges.last_mut() {
+			Some((_, end)) if *end + 1 == value => *end = value,
+			_ => ranges.push((value, value)),
+		}
+	}
+	ranges
+}
+
+/// Creates `wasmrunner:__trap`, which cleans up after a trap so nothing the program left scheduled keeps running it.
+fn create_trap_func(jump_mode: JumpMode, time_sliced: bool) -> Function {
+	let mut code = Vec::new();
+	if jump_mode == JumpMode::Bounce {
+		code.push(format!("scoreboard players set {BOUNCE_NEXT_VAR} -1"));
+	}
+	if time_sliced {
+		code.push(format!("execute if score {TIME_SLICE_BUSY_VAR} matches 1 run function wasmrunner:__time_slice_done"));
+	}
+	// The functions the trap stops never pop their local frames
+	code.push("data modify storage wasm:localstack stack set value {}".to_string());
+	code.push(format!("scoreboard players set {CALL_DEPTH_VAR} 0"));
+	parse_function("wasmrunner:__trap", code)
+}
+
+/// Estimates how many commands running the given code will take, including the intrinsics it calls.
+/// Estimates how many commands running an instruction will take in the generated datapack.
+pub fn estimate_instr_cost(instr: &LirInstr, parent: &LirProgram, cost_model: &CostModel) -> usize {
+	let mut code = Vec::new();
+	// Function names don't change how many commands are run
+	emit_instr(instr, BlockId { func: 0, block: 0 }, parent, &mut code, &mut BTreeSet::new());
+	cost_model.cmd_count(&code)
+}
+
+/// Estimates how many commands running a terminator will take in the generated datapack,
+/// including the bookkeeping done at the end of every block.
+/// With `--persist-state`, `checkpoint_cmds` is how many commands each checkpoint runs, from `checkpoint_cmd_count`.
+pub fn estimate_term_cost(term: &LirTerminator, jump_mode: JumpMode, checkpoint_cmds: Option<usize>, cost_model: &CostModel) -> usize {
+	let mut code = Vec::new();
+	// Function names don't change how many commands are run
+	let block_id = BlockId { func: 0, block: 0 };
+	emit_terminator(term, block_id, &HashMap::new(), jump_mode, checkpoint_cmds.is_some(), &mut code);
+	if let LirTerminator::JumpTable { arms, default, cond } = term {
+		code.extend(jump_table_code(arms, *default, *cond, &HashMap::new()));
+	}
+	// The checkpoint copies every holder, which the call to it doesn't show
+	let checkpoints = code.iter().filter(|cmd| cmd.ends_with("function wasmrunner:__checkpoint")).count();
+	cost_model.cmd_count(&code) + checkpoints * checkpoint_cmds.unwrap_or(0) + 2
+}
+
+fn emit_terminator(term: &LirTerminator, block_id: BlockId, func_names: &HashMap<usize, String>, jump_mode: JumpMode, persist_state: bool, code: &mut Vec<String>) {
+	match term {
 		&LirTerminator::Jump(target) => {
-			if jump_mode() == JumpMode::Direct {
-				let target_mc_id = get_mc_id(target.label);
-				if !target.cmd_check {
-					code.push(format!("function {target_mc_id}"));
-				} else {
-					// The return address was pushed beforehand in the LIR emitter, so we don't have to handle that here.
+			let target_mc_id = get_mc_id(func_names, target.label);
+			if !target.cmd_check {
+				code.push(format!("function {target_mc_id}"));
+			} else {
+				match jump_mode {
+					JumpMode::Direct => {
+						// The return address was pushed beforehand in the LIR emitter, so we don't have to handle that here.
 
-					let sleep_reg = Register::sleep_needed();
+						let sleep_reg = Register::sleep_needed();
 
-					code.push(format!("execute store success score {sleep_reg} if score {CMDS_RUN_VAR} >= {MAX_CMDS_VAR}"));
-					code.push(format!("execute if score {sleep_reg} matches 1 run scoreboard players set {CMDS_RUN_VAR} 0"));
+						code.push(format!("execute store success score {sleep_reg} if score {CMDS_RUN_VAR} >= {MAX_CMDS_VAR}"));
+						code.push(format!("execute if score {sleep_reg} matches 1 run scoreboard players set {CMDS_RUN_VAR} 0"));
 
-					code.push(format!("execute if score {sleep_reg} matches 1 run schedule function {target_mc_id} 1"));
-					code.push(format!("execute if score {sleep_reg} matches 0 run function {target_mc_id}"));
+						code.push(format!("execute if score {sleep_reg} matches 1 run schedule function {target_mc_id} 1"));
+						code.push(format!("execute if score {sleep_reg} matches 0 run function {target_mc_id}"));
+					}
+					JumpMode::Bounce => {
+						code.extend(bounce_to(func_names, target.label));
+					}
 				}
-			} else {
-				todo!()
 			}
 		}
 		&LirTerminator::ScheduleJump(target, delay) => {
-			if jump_mode() == JumpMode::Direct {
-				// TODO: add the "append" keyword
-				code.push(format!("schedule function {} {delay}", get_mc_id(target)));
-				code.push(format!("scoreboard players set {CMDS_RUN_VAR} 0"));
-			} else {
-				todo!()
-			}
-		}
-		&LirTerminator::JumpIf { true_label, false_label, cond } => {
-			if jump_mode() == JumpMode::Direct {
-				let true_func = get_mc_id(true_label.label);
-				let false_func = get_mc_id(false_label.label);
-
-				let cond_taken = Register::cond_taken();
-				let sleep_reg = Register::sleep_needed();
-
-				if true_label.cmd_check || false_label.cmd_check {
-					code.push(format!("execute store success score {sleep_reg} if score {CMDS_RUN_VAR} >= {MAX_CMDS_VAR}"));
-					code.push(format!("execute if score {sleep_reg} matches 1 run scoreboard players set {CMDS_RUN_VAR} 0"))
+			match jump_mode {
+				JumpMode::Direct => {
+					// TODO: add the "append" keyword
+					code.push(format!("schedule function {} {delay}", get_mc_id(func_names, target)));
 				}
-
-				code.push(format!("scoreboard players set {cond_taken} 0"));
-				if !true_label.cmd_check && !false_label.cmd_check {
-					code.push(format!("execute unless score {cond} matches 0 run function {}", true_func));
-					code.push(format!("execute if score {cond_taken} matches 0 run function {}", false_func));
-				} else if true_label.cmd_check && !false_label.cmd_check {
-					code.push(format!("execute if score {sleep_reg} matches 1 unless score {cond} matches 0 run schedule function {} 1", true_func));
-					code.push(format!("execute if score {sleep_reg} matches 1 unless score {cond} matches 0 run scoreboard players set {cond_taken} 1"));
-
-					code.push(format!("execute if score {cond_taken} matches 0 unless score {cond} matches 0 run function {}", true_func));
-					code.push(format!("execute if score {cond_taken} matches 0 run function {}", false_func));
-				} else if !true_label.cmd_check && false_label.cmd_check {
-					code.push(format!("execute if score {sleep_reg} matches 1 if score {cond} matches 0 run schedule function {} 1", false_func));
-					code.push(format!("execute if score {sleep_reg} matches 1 if score {cond} matches 0 run scoreboard players set {cond_taken} 1"));
-
-					code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches 0 run function {}", false_func));
-					code.push(format!("execute if score {cond_taken} matches 0 run function {}", true_func));
-				} else if true_label.cmd_check && false_label.cmd_check {
-					code.push(format!("execute if score {sleep_reg} matches 1 unless score {cond} matches 0 run schedule function {} 1", true_func));
-					code.push(format!("execute if score {sleep_reg} matches 1 if score {cond} matches 0 run schedule function {} 1", false_func));
-					code.push(format!("execute if score {sleep_reg} matches 1 run scoreboard players set {cond_taken} 1"));
-
-					code.push(format!("execute if score {cond_taken} matches 0 unless score {cond} matches 0 run function {}", true_func));
-					code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches 0 run function {}", false_func));
+				JumpMode::Bounce => {
+					code.extend(bounce_to(func_names, target));
+					code.extend(bounce_yield(delay));
+					if persist_state {
+						code.push("function wasmrunner:__checkpoint".to_string());
+					}
 				}
-			} else {
-				todo!()
 			}
+			code.push(format!("scoreboard players set {CMDS_RUN_VAR} 0"));
 		}
-		LirTerminator::JumpTable { arms, default, cond } => {
-			if jump_mode() == JumpMode::Direct {
-				let cond_taken = Register::cond_taken();
+		&LirTerminator::JumpIf { true_label, false_label, cond } if jump_mode == JumpMode::Bounce => {
+			let cond_taken = Register::cond_taken();
 
-				code.push(format!("scoreboard players set {cond_taken} 0"));
+			code.push(format!("scoreboard players set {cond_taken} 0"));
 
-				for (idx, arm) in arms.iter().enumerate() {
-					if let Some(arm) = arm {
-						let arm_func = get_mc_id(*arm);
-						code.push(format!("execute if score {cond_taken} matches 0 run execute if score {cond} matches {idx} run function {arm_func}"));
+			// Jumping to either label sets `cond_taken`, so the false label doesn't need to check the condition again
+			for (label, test) in [(true_label, format!("unless score {cond} matches 0")), (false_label, format!("if score {cond_taken} matches 0"))] {
+				if label.cmd_check {
+					for line in bounce_to(func_names, label.label) {
+						code.push(format!("execute {test} run {line}"));
 					}
+					code.push(format!("execute {test} run scoreboard players set {cond_taken} 1"));
+				} else {
+					code.push(format!("execute {test} run function {}", get_mc_id(func_names, label.label)));
 				}
+			}
+		}
+		&LirTerminator::JumpIf { true_label, false_label, cond } => {
+			let true_func = get_mc_id(func_names, true_label.label);
+			let false_func = get_mc_id(func_names, false_label.label);
 
-				if let Some(default) = *default {
-					let default_func = get_mc_id(default);
+			let cond_taken = Register::cond_taken();
+			let sleep_reg = Register::sleep_needed();
 
-					code.push(format!("execute if score {cond_taken} matches 0 run function {default_func}"));
-				} else {
-					code.push(format!("# !INTERPRETER: ASSERT unless score {cond_taken} matches 0"));
-					code.push(format!("execute if score {cond_taken} matches 0 run tellraw @a [{{\"text\":\"BAD JUMP TABLE\"}}]"));
-				}
-			} else {
-				todo!()
+			if true_label.cmd_check || false_label.cmd_check {
+				code.push(format!("execute store success score {sleep_reg} if score {CMDS_RUN_VAR} >= {MAX_CMDS_VAR}"));
+				code.push(format!("execute if score {sleep_reg} matches 1 run scoreboard players set {CMDS_RUN_VAR} 0"))
 			}
+
+			code.push(format!("scoreboard players set {cond_taken} 0"));
+			if !true_label.cmd_check && !false_label.cmd_check {
+				code.push(format!("execute unless score {cond} matches 0 run function {}", true_func));
+				code.push(format!("execute if score {cond_taken} matches 0 run function {}", false_func));
+			} else if true_label.cmd_check && !false_label.cmd_check {
+				code.push(format!("execute if score {sleep_reg} matches 1 unless score {cond} matches 0 run schedule function {} 1", true_func));
+				code.push(format!("execute if score {sleep_reg} matches 1 unless score {cond} matches 0 run scoreboard players set {cond_taken} 1"));
+
+				code.push(format!("execute if score {cond_taken} matches 0 unless score {cond} matches 0 run function {}", true_func));
+				code.push(format!("execute if score {cond_taken} matches 0 run function {}", false_func));
+			} else if !true_label.cmd_check && false_label.cmd_check {
+				code.push(format!("execute if score {sleep_reg} matches 1 if score {cond} matches 0 run schedule function {} 1", false_func));
+				code.push(format!("execute if score {sleep_reg} matches 1 if score {cond} matches 0 run scoreboard players set {cond_taken} 1"));
+
+				code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches 0 run function {}", false_func));
+				code.push(format!("execute if score {cond_taken} matches 0 run function {}", true_func));
+			} else if true_label.cmd_check && false_label.cmd_check {
+				code.push(format!("execute if score {sleep_reg} matches 1 unless score {cond} matches 0 run schedule function {} 1", true_func));
+				code.push(format!("execute if score {sleep_reg} matches 1 if score {cond} matches 0 run schedule function {} 1", false_func));
+				code.push(format!("execute if score {sleep_reg} matches 1 run scoreboard players set {cond_taken} 1"));
+
+				code.push(format!("execute if score {cond_taken} matches 0 unless score {cond} matches 0 run function {}", true_func));
+				code.push(format!("execute if score {cond_taken} matches 0 if score {cond} matches 0 run function {}", false_func));
+			}
+		}
+		LirTerminator::JumpTable { .. } => {
+			code.push(format!("function {}", get_jump_table_func(block_id)));
 		}
 		LirTerminator::Return => {
-			if jump_mode() == JumpMode::Direct {
-				// Do nothing
-			} else {
-				todo!()
-			}
+			// Do nothing
 		}
 		LirTerminator::ReturnToSaved => {
-			if jump_mode() == JumpMode::Direct {
-				code.push("function wasmrunner:__return_to_saved".to_string());
-			} else {
-				todo!()
-			}
+			code.push("function wasmrunner:__return_to_saved".to_string());
 		}
+		&LirTerminator::Trap(kind) => {
+			emit_trap(kind, block_id, func_names, None, code);
+		}
+	}
+}
+
+/// Jumps that don't need to check the command count tail call their target with `return run`,
+/// which leaves the rest of the block unrun without setting and checking `cond_taken`.
+fn emit_return_run_terminator(term: &LirTerminator, func_names: &HashMap<usize, String>, code: &mut Vec<String>) {
+	match term {
+		&LirTerminator::Jump(target) if !target.cmd_check => {
+			code.push(return_line(&format!("return run function {}", get_mc_id(func_names, target.label))));
+		}
+		&LirTerminator::JumpIf { true_label, false_label, cond } if !true_label.cmd_check && !false_label.cmd_check => {
+			code.push(return_line(&format!("execute unless score {cond} matches 0 run return run function {}", get_mc_id(func_names, true_label.label))));
+			code.push(return_line(&format!("return run function {}", get_mc_id(func_names, false_label.label))));
+		}
+		_ => {}
 	}
+}
+
+fn emit_block(ctx: &CompileContext, block_id: BlockId, block: &LirBasicBlock, func: &LirFunction, parent: &LirProgram, const_pool: &mut BTreeSet<i32>) -> Function {
+	let mut code: Vec<String> = Vec::new();
+
+	/*if block_id.block == 0 {
+		code.push(format!("tellraw @a [{{\"text\":\"{block_id:?}\"}}]"));
+	}*/
 
-	if jump_mode() == JumpMode::Direct {
-		code.push(format!("scoreboard players set {} 1", Register::cond_taken()));
+	if ctx.profile.map_or(false, |mode| is_profiled(block_id, mode)) {
+		code.push(format!("scoreboard players add {} 1", get_profile_var(block_id)));
 	}
 
-	let block_id_str = get_mc_id(block_id);
-	parse_function(&block_id_str, &code)
+	for instr in block.body.iter() {
+		if ctx.div_by_zero == DivByZero::Trap {
+			emit_div_by_zero_check(instr, block_id, &parent.func_names, &mut code);
+		}
+
+		if ctx.overflow_checks {
+			emit_overflow_check_pre(instr, &mut code);
+		}
+
+		if let Some(max_depth) = ctx.max_call_depth {
+			emit_call_depth_check(instr, max_depth, block_id, &parent.func_names, &mut code);
+		}
+
+		emit_instr(instr, block_id, parent, &mut code, const_pool);
+
+		if ctx.overflow_checks {
+			emit_overflow_check_post(instr, block_id, &parent.func_names, func.code_ranges.get(&block_id).map(Vec::as_slice), &mut code);
+		}
+	}
+
+	// FIXME: This doesn't include called functions, the terminator, or count `execute` commands properly.
+	let num_cmds = ctx.cost_model.cmd_count(&code);
+	code.push(format!("scoreboard players add {CMDS_RUN_VAR} {num_cmds}"));
+
+	emit_return_run_terminator(&block.term, &parent.func_names, &mut code);
+
+	emit_terminator(&block.term, block_id, &parent.func_names, parent.jump_mode, ctx.persist_state, &mut code);
+
+	code.push(format!("scoreboard players set {} 1", Register::cond_taken()));
+	// `return run function` only returns if the function it runs does
+	code.push(return_line("return 1"));
+
+	let block_id_str = get_mc_id(&parent.func_names, block_id);
+	parse_function(&block_id_str, gate_after_traps(code))
 }
 
-fn emit_function(func: &LirFunction, parent: &LirProgram, const_pool: &mut HashSet<i32>) -> Vec<Function> {
+fn emit_function(ctx: &CompileContext, func: &LirFunction, parent: &LirProgram, const_pool: &mut BTreeSet<i32>) -> Vec<Function> {
 	let mut result = Vec::new();
 	for (block_id, block) in func.code.iter() {
-		result.push(emit_block(*block_id, block, parent, const_pool));
+		result.push(emit_block(ctx, *block_id, block, func, parent, const_pool));
+		if let LirTerminator::JumpTable { arms, default, cond } = &block.term {
+			result.push(create_jump_table_func(*block_id, arms, *default, *cond, &parent.func_names));
+		}
 	}
 	result
 }
@@ -2404,59 +4695,432 @@ pub fn load_intrinsics() -> Vec<Function> {
 	result
 }
 
+/// Calls a function from outside of the program,
+/// with a return address that doesn't belong to any block so execution stops once it returns.
+fn make_top_level_call(func_names: &HashMap<usize, String>, id: BlockId, jump_mode: JumpMode) -> Vec<String> {
+	let func_id = get_mc_id(func_names, id);
+	let mut code = vec![
+		// Every call starts over, like calling a wasm instance again after it trapped
+		format!("scoreboard players set {TRAP_VAR} 0"),
+		"data modify storage wasm:scratch stack.data set value {ptr:-1}".to_string(),
+		"data modify storage wasm:scratch stack.tail set from storage wasm:returnstack stack".to_string(),
+		"data modify storage wasm:returnstack stack set from storage wasm:scratch stack".to_string(),
+		format!("function {func_id}"),
+	];
+	code.extend(bounce_dispatch(jump_mode));
+	code
+}
+
+/// In bounce mode, runs the block the function that was just called bounced to, if it did.
+fn bounce_dispatch(jump_mode: JumpMode) -> Option<String> {
+	match jump_mode {
+		JumpMode::Direct => None,
+		JumpMode::Bounce => Some(format!("execute unless score {BOUNCE_NEXT_VAR} matches -1 run function wasmrunner:__bounce")),
+	}
+}
+
 // make_export_func returns a datapack function
-pub fn make_export_func(name: &str, id: BlockId) -> Function {
+pub fn make_export_func(func_names: &HashMap<usize, String>, name: &str, id: BlockId, params: &[ValType], jump_mode: JumpMode, lazy_memory_init: bool, time_sliced: bool) -> Function {
 	let wrapper_name = format!("wasmrunner:{name}");
 	let wrapper_id = wrapper_name.parse().unwrap();
 
-	let func_id = get_mc_id(id);
-	let cmds: Vec<Command> = vec![
-		"data modify storage wasm:scratch stack.data set value {ptr:-1}".to_string().parse().unwrap(),
-		"data modify storage wasm:scratch stack.tail set from storage wasm:returnstack stack".parse().unwrap(),
-		"data modify storage wasm:returnstack stack set from storage wasm:scratch stack".parse().unwrap(),
-		format!("function {func_id}").parse().unwrap(),
-	];
+	let mut cmds = if time_sliced {
+		vec![
+			format!("execute if score {TIME_SLICE_BUSY_VAR} matches 1 run scoreboard players add {FRAMES_DROPPED_VAR} 1"),
+			format!("execute unless score {TIME_SLICE_BUSY_VAR} matches 1 run function wasmrunner:__time_slice_{name}"),
+		]
+	} else {
+		make_top_level_call(func_names, id, jump_mode)
+	};
+
+	if lazy_memory_init {
+		// Keep retrying every tick until memory is ready.
+		// The parameters are saved for the retry, since the start function or other code
+		// may overwrite the parameter registers in between.
+		let mut waiting = vec!["function wasmrunner:__memory_init_start".to_string()];
+		for (reg, holder) in get_lazy_retry_holders(name, params) {
+			waiting.push(format!("scoreboard players operation {holder} = {reg}"));
+		}
+		waiting.push(format!("schedule function {} 1", get_lazy_retry_func(name)));
+
+		cmds = waiting.into_iter()
+			.map(|c| format!("execute unless score {MEM_INIT_STATE_VAR} matches 2 run {c}"))
+			.chain(cmds.into_iter().map(|c| format!("execute if score {MEM_INIT_STATE_VAR} matches 2 run {c}")))
+			.collect();
+	}
+
+	let cmds: Vec<Command> = cmds.into_iter().map(|c| c.parse().unwrap()).collect();
+
+	Function { id: wrapper_id, cmds }
+}
+
+fn get_lazy_retry_func(name: &str) -> String {
+	format!("wasmrunner:__lazy_retry_{name}")
+}
+
+/// The parameter registers of an export, and the holders they are saved in while it waits for memory to be initialized
+fn get_lazy_retry_holders(name: &str, params: &[ValType]) -> Vec<(Register, String)> {
+	params.iter().enumerate().flat_map(|(idx, ty)| {
+		let reg = DoubleRegister::param(idx as u32);
+		get_call_holders("arg", idx, *ty).into_iter().zip([reg.lo(), reg.hi()]).map(|(holder, half)| {
+			(half, format!("%%lazy_{name}_{holder} {OBJECTIVE_NAME}"))
+		}).collect::<Vec<_>>()
+	}).collect()
+}
+
+/// Creates the function an export that is waiting for memory to be initialized is retried with on a later tick,
+/// which restores the parameters it was called with
+fn make_lazy_retry_func(name: &str, params: &[ValType]) -> Function {
+	let mut code = get_lazy_retry_holders(name, params).into_iter()
+		.map(|(reg, holder)| format!("scoreboard players operation {reg} = {holder}"))
+		.collect::<Vec<_>>();
+	code.push(format!("function wasmrunner:{name}"));
+
+	parse_function(&get_lazy_retry_func(name), code)
+}
+
+/// Starts a call to a time-sliced export, which only gets `1/slices` of the per-tick command budget
+/// until it returns to the sentinel address.
+/// This limits the budget rather than cutting the export into `slices` parts: it yields at the same points as
+/// any other multi-tick code once its share is used up, so how many ticks a call takes depends on how much work it does.
+fn make_tick_budget_func(func_names: &HashMap<usize, String>, name: &str, id: BlockId, slices: u32, jump_mode: JumpMode, cost_model: &CostModel) -> Function {
+	let func_id = get_mc_id(func_names, id);
+	let budget = (cost_model.max_cmds_per_tick / slices as usize).max(1);
+
+	let mut code = vec![
+		format!("scoreboard players set {TRAP_VAR} 0"),
+		format!("scoreboard players set {TIME_SLICE_BUSY_VAR} 1"),
+		format!("scoreboard players operation {SAVED_MAX_CMDS_VAR} = {MAX_CMDS_VAR}"),
+		format!("scoreboard players set {MAX_CMDS_VAR} {budget}"),
+		format!("data modify storage wasm:scratch stack.data set value {{ptr:{TIME_SLICE_SENTINEL}}}"),
+		"data modify storage wasm:scratch stack.tail set from storage wasm:returnstack stack".to_string(),
+		"data modify storage wasm:returnstack stack set from storage wasm:scratch stack".to_string(),
+		format!("function {func_id}"),
+	];
+	code.extend(bounce_dispatch(jump_mode));
+
+	parse_function(&format!("wasmrunner:__time_slice_{name}"), code)
+}
+
+fn create_time_slice_done_func() -> Function {
+	let code = [
+		format!("scoreboard players set {TIME_SLICE_BUSY_VAR} 0"),
+		format!("scoreboard players operation {MAX_CMDS_VAR} = {SAVED_MAX_CMDS_VAR}"),
+	];
+
+	parse_function("wasmrunner:__time_slice_done", code)
+}
+
+/// Returns the holders in the call objective for the argument or result at `idx`, where `kind` is `arg` or `ret`.
+/// 64-bit values also use a holder suffixed with `_hi` for their high half.
+pub fn get_call_holders(kind: &str, idx: usize, ty: ValType) -> Vec<String> {
+	match ty {
+		ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => vec![format!("{kind}{idx}")],
+		ValType::I64 | ValType::F64 => vec![format!("{kind}{idx}"), format!("{kind}{idx}_hi")],
+		_ => panic!("exports can't pass values of type {ty:?} through the call objective"),
+	}
+}
+
+/// Creates `wasmrunner:call/{name}`, which calls an export with the arguments in the call objective
+/// and writes its results back to it, so other datapacks don't depend on the registers the program uses.
+fn make_call_func(name: &str, func: &LirFunction) -> Function {
+	let mut code = Vec::new();
+
+	for (idx, ty) in func.params.iter().enumerate() {
+		let reg = DoubleRegister::param(idx as u32);
+		for (holder, half) in get_call_holders("arg", idx, *ty).iter().zip([reg.lo(), reg.hi()]) {
+			code.push(format!("scoreboard players operation {half} = {holder} {CALL_OBJECTIVE}"));
+		}
+	}
+
+	code.push(format!("function wasmrunner:{name}"));
+
+	for (idx, ty) in func.returns.iter().enumerate() {
+		let reg = DoubleRegister::return_reg(idx as u32);
+		for (holder, half) in get_call_holders("ret", idx, *ty).iter().zip([reg.lo(), reg.hi()]) {
+			code.push(format!("scoreboard players operation {holder} {CALL_OBJECTIVE} = {half}"));
+		}
+	}
+
+	parse_function(&format!("wasmrunner:call/{name}"), code)
+}
+
+/// The function that calls an export of another datapack, like `wasmrunner:__pack_call/lib/add` for `lib:call/add`.
+/// Only these stubs name the other datapack's functions.
+pub fn get_pack_call_stub(namespace: &str, export: &str) -> String {
+	format!("wasmrunner:__pack_call/{namespace}/{export}")
+}
+
+/// Creates the stub calling `{namespace}:call/{export}` in another datapack, the other side of `make_call_func`.
+/// It takes the arguments from the parameter registers and leaves the results in the return registers, like a call to any other function.
+fn make_pack_call_stub(namespace: &str, export: &str, params: &[ValType], returns: &[ValType]) -> Function {
+	let mut code = Vec::new();
+
+	for (idx, ty) in params.iter().enumerate() {
+		let reg = DoubleRegister::param(idx as u32);
+		for (holder, half) in get_call_holders("arg", idx, *ty).iter().zip([reg.lo(), reg.hi()]) {
+			code.push(format!("scoreboard players operation {holder} {CALL_OBJECTIVE} = {half}"));
+		}
+	}
+
+	code.push(format!("function {namespace}:call/{export}"));
+
+	for (idx, ty) in returns.iter().enumerate() {
+		let reg = DoubleRegister::return_reg(idx as u32);
+		for (holder, half) in get_call_holders("ret", idx, *ty).iter().zip([reg.lo(), reg.hi()]) {
+			code.push(format!("scoreboard players operation {half} = {holder} {CALL_OBJECTIVE}"));
+		}
+	}
+
+	parse_function(&get_pack_call_stub(namespace, export), code)
+}
+
+/// Creates a stub for each export of another datapack the program calls.
+fn create_pack_call_stubs(program: &LirProgram) -> Vec<Function> {
+	let mut stubs = HashMap::new();
+	for func in program.code.iter() {
+		for (_, block) in func.code.iter() {
+			for instr in block.body.iter() {
+				if let LirInstr::PackCall { namespace, export, params, returns } = instr {
+					stubs.entry((namespace, export)).or_insert((params, returns));
+				}
+			}
+		}
+	}
+
+	// Sorted so the functions are always written in the same order
+	let mut stubs = stubs.into_iter().collect::<Vec<_>>();
+	stubs.sort_by(|(a, _), (b, _)| a.cmp(b));
+	stubs.into_iter().map(|((namespace, export), (params, returns))| make_pack_call_stub(namespace, export, params, returns)).collect()
+}
+
+fn get_lir_tick_exports(ctx: &CompileContext, program: &LirProgram) -> Vec<(String, u32)> {
+	get_tick_exports(ctx, &program.exports, |id| !program.code[id.func].params.is_empty())
+}
+
+/// Returns true if the function may yield and continue on a later tick.
+fn is_multi_tick(func: &LirFunction) -> bool {
+	func.code.iter().any(|(_, block)| matches!(block.term, LirTerminator::ReturnToSaved))
+}
+
+/// Returns how many ticks each export should be spread over.
+/// Exports that always finish within a single tick can't be split, so they are left out.
+fn get_time_sliced_exports(ctx: &CompileContext, program: &LirProgram) -> HashMap<String, u32> {
+	let mut result = HashMap::new();
+	for (name, &slices) in ctx.time_slices.iter() {
+		let id = *program.exports.get(name).unwrap_or_else(|| panic!("--time-slice given for {name:?}, which is not an export"));
+
+		if is_multi_tick(&program.code[id.func]) {
+			result.insert(name.clone(), slices);
+		} else {
+			println!("warning: export {name:?} always finishes in one tick and will not be time-sliced");
+		}
+	}
+	result
+}
+
+pub fn add_export_funcs(program: &LirProgram, time_slices: &HashMap<String, u32>, cost_model: &CostModel, lazy_memory_init: bool, code: &mut Vec<Function>) {
+	let func_names = &program.func_names;
+
+	// Sorted so the functions are always written in the same order
+	let mut exports = program.exports.iter().collect::<Vec<_>>();
+	exports.sort();
+	for (name, id) in exports {
+		let slices = time_slices.get(name);
+		let func = &program.code[id.func];
+		code.push(make_export_func(func_names, name, *id, &func.params, program.jump_mode, lazy_memory_init, slices.is_some()));
+		if lazy_memory_init {
+			code.push(make_lazy_retry_func(name, &func.params));
+		}
+		if let Some(&slices) = slices {
+			code.push(make_tick_budget_func(func_names, name, *id, slices, program.jump_mode, cost_model));
+		}
+
+		code.push(make_call_func(name, func));
+		if !func.returns.is_empty() && (is_multi_tick(func) || lazy_memory_init) {
+			println!("warning: export {name:?} may not finish in one tick, so `wasmrunner:call/{name}` may write its results before they are ready");
+		}
+	}
+
+	if !time_slices.is_empty() {
+		code.push(create_time_slice_done_func());
+	}
+}
+
+static INSERT_FUNC_PRINTS: bool = false;
+
+/// Converts an LIR program to a list of Minecraft datapack functions.
+pub fn emit_program(ctx: &CompileContext, lir_program: &LirProgram) -> Vec<Function> {
+	let mut result = Vec::new();
+	let mut constants = lir_program.constants.clone();
+	for func in lir_program.code.iter() {
+		result.extend(emit_function(ctx, func, lir_program, &mut constants));
+	}
+
+	if INSERT_FUNC_PRINTS {
+		for r in result.iter_mut() {
+			let id = r.id.to_string();
+			let s = format!(r#"tellraw @a [{{"text":"Entered {id}"}}]"#);
+			r.cmds.insert(0, s.parse().unwrap());
+		}
+	}
+
+	let init_func = create_init_func(ctx, lir_program);
+	result.push(init_func);
+
+	result.extend(create_snapshot_funcs(&ctx.snapshot_regions, lir_program.layout.memory_origin));
+
+	if let Some(fb) = &ctx.framebuffer {
+		result.extend(create_framebuffer_funcs(fb, lir_program.layout.memory_origin));
+	}
+
+	if has_player_input(ctx, &lir_program.exports) {
+		result.extend(create_player_events_funcs(ctx, &lir_program.exports));
+	}
+
+	for (memory_idx, memory) in lir_program.memory.iter().enumerate() {
+		result.extend(create_memory_grow_funcs(memory_idx as u32, memory, &lir_program.layout));
+	}
+
+	result.extend(create_table_init_funcs(&lir_program.tables, &lir_program.elem_segments));
+
+	let ticks = get_lir_tick_exports(ctx, lir_program);
+	for (name, _) in ticks.iter() {
+		if is_multi_tick(&lir_program.code[lir_program.exports[name].func]) {
+			println!("warning: export {name:?} may not finish before it is called again by `wasmrunner:__tick_{name}`");
+		}
+	}
+	result.extend(create_tick_funcs(&ticks));
+
+	let time_slices = get_time_sliced_exports(ctx, lir_program);
+
+	let return_to_saved = create_return_to_saved_func(lir_program, !time_slices.is_empty());
+	result.extend(return_to_saved);
+	result.extend(create_macro_dispatch_funcs());
+	if lir_program.jump_mode == JumpMode::Bounce {
+		result.extend(create_bounce_funcs(lir_program, ctx.persist_state));
+	}
+	result.push(create_trap_func(lir_program.jump_mode, !time_slices.is_empty()));
+
+	add_export_funcs(lir_program, &time_slices, &ctx.cost_model, ctx.lazy_memory_init, &mut result);
+	result.extend(create_pack_call_stubs(lir_program));
+
+	if ctx.lazy_memory_init {
+		result.extend(create_lazy_memory_init_funcs(&lir_program.memory, &lir_program.layout, lir_program.start, &lir_program.func_names, lir_program.jump_mode));
+	}
+
+	if ctx.controls {
+		result.extend(create_controls_funcs(lir_program, &ticks));
+	}
+
+	if ctx.refresh_markers {
+		result.push(create_refresh_markers_func(&lir_program.layout));
+	}
+
+	if ctx.hot_reload {
+		result.push(create_hot_reload_func(lir_program));
+	}
 
-	Function { id: wrapper_id, cmds }
-}
+	if let Some(mode) = ctx.profile {
+		result.extend(create_profile_funcs(lir_program, mode));
+	}
 
-pub fn add_export_funcs(exports: &HashMap<String, BlockId>, code: &mut Vec<Function>) {
-	code.extend(exports.iter().map(|(name, id)| make_export_func(name, *id)));
-}
+	result.extend(load_intrinsics());
 
-static INSERT_FUNC_PRINTS: bool = false;
+	let mut result = lir_program.layout.memory_backend.backend().finish(result, &lir_program.layout);
 
-/// Converts an LIR program to a list of Minecraft datapack functions.
-pub fn emit_program(lir_program: &LirProgram) -> Vec<Function> {
-	let mut result = Vec::new();
-	let mut constants = lir_program.constants.clone();
-	for func in lir_program.code.iter() {
-		result.extend(emit_function(func, lir_program, &mut constants));
+	// Only the constants that are still read once everything is emitted need a holder
+	result.push(create_constants_init_func(&find_used_constants(&result)));
+
+	if ctx.persist_state {
+		let checkpoint_funcs = create_checkpoint_funcs(ctx, lir_program);
+		result.extend(checkpoint_funcs);
 	}
 
-	if INSERT_FUNC_PRINTS {
-		for r in result.iter_mut() {
-			let id = r.id.to_string();
-			let s = format!(r#"tellraw @a [{{"text":"Entered {id}"}}]"#);
-			r.cmds.insert(0, s.parse().unwrap());
+	split_long_functions(result, ctx.max_command_chain_length, lir_program)
+}
+
+/// The default value of the `maxCommandChainLength` gamerule.
+pub const DEFAULT_MAX_COMMAND_CHAIN_LENGTH: u32 = 65536;
+
+/// Splits each function with more than `max_len` commands into parts, since Minecraft stops running
+/// a function partway through once it has run `maxCommandChainLength` commands.
+/// The limit counts every command run since the function was started, including those of the functions it calls,
+/// so a part can only get a fresh count by being scheduled for the next tick.
+/// That is only done for functions nothing calls, like `wasmrunner:init`, since whatever called a function
+/// would otherwise carry on before the rest of it has run. The parts of any other function call each other in turn,
+/// which keeps each file short but may still be cut short, so those are warned about.
+/// Functions with macro lines are left whole, because which of their lines are written depends on the version.
+/// A `return` in one part still skips the rest of the function, since the call to the next part comes after it.
+fn split_long_functions(funcs: Vec<Function>, max_len: usize, program: &LirProgram) -> Vec<Function> {
+	let mut called = program.all_block_ids().map(|id| get_mc_id(&program.func_names, id)).collect::<HashSet<_>>();
+	for cmd in funcs.iter().flat_map(|func| func.cmds.iter()) {
+		let cmd = cmd.to_string();
+		let words = cmd.split_whitespace().collect::<Vec<_>>();
+		for (idx, pair) in words.windows(2).enumerate() {
+			if pair[0] == "function" && (idx == 0 || words[idx - 1] != "schedule") {
+				called.insert(pair[1].to_string());
+			}
 		}
 	}
 
-	let init_func = create_init_func(lir_program, &constants);
-	result.push(init_func);
+	let mut result = Vec::with_capacity(funcs.len());
+	for func in funcs {
+		if func.cmds.len() <= max_len {
+			result.push(func);
+			continue;
+		}
 
-	let return_to_saved = create_return_to_saved_func(lir_program);
-	result.extend(return_to_saved);
+		let id = func.id.to_string();
+		if func.cmds.iter().any(|cmd| cmd.to_string().starts_with(MACRO_LINE_PREFIX)) {
+			println!("warning: {id} has {} commands but can't be split because it has macro lines", func.cmds.len());
+			result.push(func);
+			continue;
+		}
 
-	add_export_funcs(&lir_program.exports, &mut result);
+		let is_called = called.contains(&id);
+		if is_called {
+			println!("warning: {id} has {} commands and is called by other functions, so it is split into parts that call each other and may still be stopped by maxCommandChainLength", func.cmds.len());
+		}
 
-	result.extend(load_intrinsics());
+		let part_id = |idx: usize| if idx == 0 { id.clone() } else { format!("{id}__part{idx}") };
 
+		let cmds = func.cmds.iter().map(ToString::to_string).collect::<Vec<_>>();
+		let parts = cmds.chunks(max_len - 1).collect::<Vec<_>>();
+		for (idx, part) in parts.iter().enumerate() {
+			let mut code = part.to_vec();
+			if idx + 1 < parts.len() {
+				if is_called {
+					code.push(format!("function {}", part_id(idx + 1)));
+				} else {
+					code.push(format!("schedule function {} 1", part_id(idx + 1)));
+				}
+			}
+			result.push(parse_function(&part_id(idx), code));
+		}
+	}
 	result
 }
 
+/// A kind of pack the program's functions can be written as,
+/// which decides where each file goes and how its commands are written.
+pub trait PackBackend {
+    /// Every file of the pack as a path relative to its root and its contents, including its metadata,
+    /// and the short names used in them if the pack has any.
+    fn files(&self, funcs: &[Function]) -> (Vec<(String, String)>, ShortNames);
+
+    /// The folder holding everything written for the program,
+    /// whose leftover files are removed when the pack is written again.
+    fn content_folder(&self) -> &'static str;
+}
+
 pub struct Datapack {
     pub description: String,
+    pub version: McVersion,
+    /// Whether internal holders and objectives are written with short names.
+    pub short_names: bool,
+    /// The namespace the functions, storage, and objectives are moved to, see `namespace`.
+    pub namespace: String,
 }
 
 impl Datapack {
@@ -2470,53 +5134,109 @@ impl Datapack {
     }
     */
 
-    pub fn new() -> Self {
+    pub fn new(version: McVersion) -> Self {
         Self {
             description: "Autogenerated by wasmcraft".to_string(),
+            version,
+            short_names: false,
+            namespace: namespace::DEFAULT_NAMESPACE.to_string(),
         }
     }
 
+    /// The contents of `pack.mcmeta`.
+    fn mcmeta_contents(&self) -> String {
+        format!(r#"
+            {{ "pack": {{
+                "pack_format": {},
+                "description": "foo"
+            }} }}
+        "#, self.version.pack_format())
+    }
+
+    /// The path of a function relative to the root of the datapack.
+    pub fn function_path(&self, namespace: &str, name: &str) -> String {
+        format!("data/{}/{}/{}.mcfunction", namespace, self.version.function_folder(), name)
+    }
+
+    /// The path of a function tag relative to the root of the datapack.
+    pub fn function_tag_path(&self, namespace: &str, name: &str) -> String {
+        format!("data/{}/{}/{}.json", namespace, self.version.function_tag_folder(), name)
+    }
+
+    /// The path of a predicate relative to the root of the datapack.
+    pub fn predicate_path(&self, namespace: &str, name: &str) -> String {
+        format!("data/{}/{}/{}.json", namespace, self.version.predicate_folder(), name)
+    }
+
     /// Creates a datapack with the given root directory, erasing the previous contents of the folder.
     pub fn save(&self, output_folder: &Path) -> Result<(), std::io::Error> {
 		if !output_folder.exists() {
-			std::fs::create_dir(&output_folder)?;
+			std::fs::create_dir_all(output_folder)?;
 		}
 
-        let mcmeta_contents = r#"
-            { "pack": {
-                "pack_format": 5,
-                "description": "foo"
-            } }
-        "#;
-
         std::fs::write(
             output_folder.join("pack.mcmeta"),
-            mcmeta_contents.to_string(),
+            self.mcmeta_contents(),
         )?;
 
         Ok(())
     }
 
-    pub fn write_function(&self, output_folder: &Path, namespace: &str, mut name: &str, contents: &str) -> std::io::Result<std::path::PathBuf> {
-        let prefix = if let Some((a, b)) = name.split_once('/') {
-            name = b;
-            a
-        } else {
-            ""
-        };
+    pub fn write_function(&self, output_folder: &Path, namespace: &str, name: &str, contents: &str) -> std::io::Result<std::path::PathBuf> {
+        self.write_file(output_folder, &self.function_path(namespace, name), contents)
+    }
+
+    pub fn write_predicate(&self, output_folder: &Path, namespace: &str, name: &str, contents: &str) -> std::io::Result<std::path::PathBuf> {
+        self.write_file(output_folder, &self.predicate_path(namespace, name), contents)
+    }
 
-        let func_folder = output_folder.join(format!("data/{}/functions/{}", namespace, prefix));
-        std::fs::create_dir_all(&func_folder)?;
+    fn write_file(&self, output_folder: &Path, path: &str, contents: &str) -> std::io::Result<std::path::PathBuf> {
+        write_pack_file(output_folder, path, contents)
+    }
 
-        let func_name = format!("{}.mcfunction", name);
+    /// Writes the datapack as a zip archive containing `pack.mcmeta` and the given files,
+    /// which are given as paths relative to the root of the datapack.
+    pub fn save_zip(&self, zip_path: &Path, files: &[(String, String)]) -> zip::result::ZipResult<()> {
+        let mcmeta = ("pack.mcmeta".to_string(), self.mcmeta_contents());
+        write_zip(zip_path, std::iter::once(&mcmeta).chain(files.iter()))
+    }
+}
 
-        let file_path = func_folder.join(Path::new(&func_name));
-        std::fs::write(file_path.deref(), contents)?;
+impl PackBackend for Datapack {
+    fn files(&self, funcs: &[Function]) -> (Vec<(String, String)>, ShortNames) {
+        let (files, names) = datapack_files(self, funcs);
+        let mcmeta = ("pack.mcmeta".to_string(), self.mcmeta_contents());
+        (std::iter::once(mcmeta).chain(files).collect(), names)
+    }
 
-        Ok(file_path.canonicalize().unwrap())
+    fn content_folder(&self) -> &'static str {
+        "data"
     }
 }
 
+/// Writes a file of a pack, given as a path relative to its root, and returns where it was written.
+fn write_pack_file(output_folder: &Path, path: &str, contents: &str) -> std::io::Result<std::path::PathBuf> {
+	let file_path = output_folder.join(path);
+	std::fs::create_dir_all(file_path.parent().unwrap())?;
+	std::fs::write(file_path.deref(), contents)?;
+
+	Ok(file_path.canonicalize().unwrap())
+}
+
+/// Writes a zip archive containing the given files, which are given as paths relative to the root of the pack.
+fn write_zip<'a>(zip_path: &Path, files: impl Iterator<Item=&'a (String, String)>) -> zip::result::ZipResult<()> {
+	let mut zip = zip::ZipWriter::new(std::fs::File::create(zip_path)?);
+	let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+	for (path, contents) in files {
+		zip.start_file(path.as_str(), options)?;
+		zip.write_all(contents.as_bytes())?;
+	}
+
+	zip.finish()?;
+	Ok(())
+}
+
 fn remove_old_in_directory(written_paths: &HashSet<std::path::PathBuf>, folder_path: std::path::PathBuf) {
 	for entry in std::fs::read_dir(folder_path).unwrap() {
 		let entry = entry.unwrap();
@@ -2530,23 +5250,211 @@ fn remove_old_in_directory(written_paths: &HashSet<std::path::PathBuf>, folder_p
 	}
 }
 
-pub fn persist_program(folder_path: &Path, funcs: &[Function]) {
-	let datapack = Datapack::new();
+/// `intrinsic:random` for versions with the `random` command, which uses the world's random number generator instead.
+/// The range is as wide as the command allows.
+static RANDOM_COMMAND_INTRINSIC: [&str; 2] = [
+	"execute store result score %return%0 reg run random value 0..2147483645",
+	"scoreboard players operation %return%0 reg %= %param0%0 reg",
+];
+
+/// Moves the memory word stored in a jukebox's record from the item's `tag` to its custom data component.
+fn use_item_components(cmd: &str) -> String {
+	let cmd = cmd.replace("RecordItem.tag.Memory", r#"RecordItem.components."minecraft:custom_data".Memory"#);
+
+	let old_item = "Count:1b,tag:{Memory:";
+	let mut result = String::new();
+	let mut rest = cmd.as_str();
+	while let Some(idx) = rest.find(old_item) {
+		let value_start = idx + old_item.len();
+		let value_end = value_start + rest[value_start..].find('}').unwrap();
+		result.push_str(&rest[..idx]);
+		result.push_str(r#"count:1,components:{"minecraft:custom_data":{Memory:"#);
+		result.push_str(&rest[value_start..value_end]);
+		result.push('}');
+		rest = &rest[value_end..];
+	}
+	result.push_str(rest);
+	result
+}
+
+/// Picks which lines of a function to write: only its macro lines for versions with function macros if it has any,
+/// and the other lines otherwise. Return lines are kept in place for versions with `return run`, which also drop trap gates,
+/// and memory lines are always kept in place, since the backends writing them are only used with versions that have macros.
+/// Returns `None` if the function is only used with macros and the version doesn't have them.
+fn select_lines_for_version(cmds: Vec<String>, version: McVersion) -> Option<Vec<String>> {
+	let cmds = cmds.into_iter().filter_map(|cmd| {
+		if let Some(line) = cmd.strip_prefix(macro_memory::MEMORY_LINE_PREFIX) {
+			return Some(line.to_string());
+		}
+		match cmd.strip_prefix(RETURN_LINE_PREFIX) {
+			Some(line) if version.has_return_run() => Some(line.to_string()),
+			Some(_) => None,
+			None if version.has_return_run() => Some(strip_trap_gate(cmd)),
+			None => Some(cmd),
+		}
+	});
+
+	let (macro_lines, other_lines): (Vec<_>, Vec<_>) = cmds.into_iter().partition(|cmd| cmd.starts_with(MACRO_LINE_PREFIX));
+
+	if macro_lines.is_empty() {
+		Some(other_lines)
+	} else if version.has_macros() {
+		Some(macro_lines.iter().map(|line| line[MACRO_LINE_PREFIX.len()..].to_string()).collect())
+	} else if other_lines.is_empty() {
+		None
+	} else {
+		Some(other_lines)
+	}
+}
+
+/// Adjusts a command for the version the datapack is written for.
+/// Commands are generated for the oldest supported version, which is also what the interpreter understands.
+fn adapt_command(cmd: String, version: McVersion) -> String {
+	if version.has_item_components() {
+		use_item_components(&cmd)
+	} else {
+		cmd
+	}
+}
+
+/// The lines written for a function for a version, or `None` if the function isn't written for it.
+pub fn written_lines(func: &Function, version: McVersion) -> Option<Vec<String>> {
+	if version.has_random_command() && func.id.to_string() == "intrinsic:random" {
+		return Some(RANDOM_COMMAND_INTRINSIC.iter().map(|cmd| cmd.to_string()).collect());
+	}
 
-	println!("Writing output");
-	datapack.save(folder_path).unwrap();
+	let cmds = select_lines_for_version(func.cmds.iter().map(ToString::to_string).collect(), version)?;
+	Some(cmds.into_iter().map(|cmd| adapt_command(cmd, version)).collect())
+}
 
-	let mut written_paths = HashSet::<std::path::PathBuf>::new();
+/// The files of the datapack other than `pack.mcmeta`, as paths relative to its root and their contents,
+/// and the short names used in them if the datapack has any.
+fn datapack_files(datapack: &Datapack, funcs: &[Function]) -> (Vec<(String, String)>, ShortNames) {
+	let version = datapack.version;
+
+	let mut files = Vec::new();
 	for func in funcs.iter() {
-		let contents = func.cmds.iter().map(ToString::to_string).collect::<Vec<_>>();
+		let contents = match written_lines(func, version) {
+			Some(lines) => lines,
+			None => continue,
+		};
 		let contents = contents.join("\n")
 			.replace("\\u{fffd}", "�"); // TODO: FIXME: HACK: THIS IS AN AWFUL HACK
-		let written_path = datapack.write_function(folder_path, &func.id.namespace, &func.id.path, &contents).unwrap();
+		files.push((datapack.function_path(&func.id.namespace, &func.id.path), contents));
+	}
+
+	// Only `turtle_light_level` checks these predicates
+	if files.iter().any(|(_, contents)| contents.contains("predicate wasmrunner:light/")) {
+		for level in 1..=15 {
+			files.push((datapack.predicate_path("wasmrunner", &format!("light/{level}")), light_level_predicate(level)));
+		}
+	}
+
+	let load_funcs = LOAD_FUNCS.iter()
+		.filter(|id| funcs.iter().any(|func| func.id.to_string() == **id))
+		.map(|id| format!("\"{id}\""))
+		.collect::<Vec<_>>();
+	if !load_funcs.is_empty() {
+		files.push((datapack.function_tag_path("minecraft", "load"), format!(r#"{{"values":[{}]}}"#, load_funcs.join(","))));
+	}
+
+	let mut names = ShortNames::default();
+	if datapack.short_names {
+		names = shorten_functions(&mut files);
+	}
+
+	if datapack.namespace != namespace::DEFAULT_NAMESPACE {
+		let namespace = PackNamespace::new(&datapack.namespace);
+		for (path, contents) in files.iter_mut() {
+			*path = namespace.rename_path(path);
+			*contents = namespace.apply(contents);
+		}
+	}
+
+	(files, names)
+}
+
+/// Replaces the internal names in the functions among a pack's files with short ones, and returns the names used.
+fn shorten_functions(files: &mut [(String, String)]) -> ShortNames {
+	let is_function = |path: &str| path.ends_with(".mcfunction");
+	let names = ShortNames::assign(files.iter().filter(|(path, _)| is_function(path)).map(|(_, contents)| contents.as_str()));
+	names.apply(files.iter_mut().filter(|(path, _)| is_function(path)).map(|(_, contents)| contents));
+	names
+}
+
+pub fn persist_program(folder_path: &Path, funcs: &[Function], version: McVersion) {
+	write_datapack(&Datapack::new(version), folder_path, funcs);
+}
+
+/// Writes the pack to a folder, removing any functions left over from before,
+/// and returns the short names used in it.
+pub fn write_datapack(pack: &dyn PackBackend, folder_path: &Path, funcs: &[Function]) -> ShortNames {
+	println!("Writing output");
+	let (files, names) = pack.files(funcs);
+	write_pack_files(pack, folder_path, &files);
+
+	names
+}
+
+/// Writes the files of a pack to a folder, and removes the files a previous version of the pack left in its content folder.
+pub fn write_pack_files(pack: &dyn PackBackend, folder_path: &Path, files: &[(String, String)]) {
+	let mut written_paths = HashSet::<std::path::PathBuf>::new();
+	for (path, contents) in files.iter() {
+		let written_path = write_pack_file(folder_path, path, contents).unwrap();
+		written_paths.insert(written_path);
+	}
+
+	println!("Removing old functions");
+	remove_old_in_directory(&written_paths, folder_path.join(pack.content_folder()));
+}
+
+/// Writes the datapack like `write_datapack`, but leaves the files whose contents didn't change alone,
+/// so reloading a datapack that's already in use only replaces the functions that changed.
+pub fn update_datapack(pack: &dyn PackBackend, folder_path: &Path, funcs: &[Function]) -> ShortNames {
+	println!("Writing changed output");
+	let (files, names) = pack.files(funcs);
+	update_pack_files(pack, folder_path, &files);
+
+	names
+}
+
+/// Writes the files of a pack like `write_pack_files`, but only the ones whose contents changed.
+pub fn update_pack_files(pack: &dyn PackBackend, folder_path: &Path, files: &[(String, String)]) {
+	let mut written_paths = HashSet::<std::path::PathBuf>::new();
+	let mut changed = 0;
+	for (path, contents) in files.iter() {
+		let file_path = folder_path.join(path);
+		let written_path = if std::fs::read_to_string(&file_path).ok().as_deref() == Some(contents.as_str()) {
+			file_path.canonicalize().unwrap()
+		} else {
+			changed += 1;
+			write_pack_file(folder_path, path, contents).unwrap()
+		};
 		written_paths.insert(written_path);
 	}
+	println!("Rewrote {changed} of {} files", files.len());
 
 	println!("Removing old functions");
-	remove_old_in_directory(&written_paths, folder_path.join("data"));
+	remove_old_in_directory(&written_paths, folder_path.join(pack.content_folder()));
+}
+
+/// Writes the datapack as a zip archive instead of a folder, replacing the archive if it already exists.
+pub fn persist_program_zip(zip_path: &Path, funcs: &[Function], version: McVersion) {
+	write_datapack_zip(&Datapack::new(version), zip_path, funcs);
+}
+
+/// Writes the datapack as a zip archive like `persist_program_zip`, and returns the short names used in it.
+pub fn write_datapack_zip(pack: &dyn PackBackend, zip_path: &Path, funcs: &[Function]) -> ShortNames {
+	println!("Writing output");
+	let (files, names) = pack.files(funcs);
+	write_pack_zip(zip_path, &files);
+
+	names
+}
+
+/// Writes the files of a pack as a zip archive, replacing the archive if it already exists.
+pub fn write_pack_zip(zip_path: &Path, files: &[(String, String)]) {
+	write_zip(zip_path, files.iter()).unwrap();
 }
 
 #[cfg(test)]
@@ -2559,7 +5467,7 @@ mod test {
 	fn test_constant_func<I, E, R, F>(lhs_vals: I, rhs: i32, emitter: E, ex: F)
 		where
 			I: Iterator<Item=i32>,
-			E: FnOnce(Register, R, i32, &mut Vec<String>, &mut HashSet<i32>),
+			E: FnOnce(Register, R, i32, &mut Vec<String>, &mut BTreeSet<i32>),
 			F: Fn(i32, i32) -> i32,
 			R: From<Register>,
 	{
@@ -2567,7 +5475,7 @@ mod test {
 		let lhs = Register::param_lo(0);
 
 		let mut code = Vec::new();
-		let mut const_pool = HashSet::new();
+		let mut const_pool = BTreeSet::new();
 		emitter(dst, lhs.into(), rhs, &mut code, &mut const_pool);
 
 		let func = parse_function("wasmrunner:test_constant", &code);
@@ -2709,7 +5617,7 @@ mod test {
 
 		let mut code = Vec::new();
 
-		let const_pool = HashSet::new();
+		let const_pool = BTreeSet::new();
 
 		let addr = Register::work_lo(0, 0);
 
@@ -2757,6 +5665,10 @@ mod test {
 		let obj = Objective::new("reg".to_string()).unwrap();
 		interp.set_named_score(&holder_z, &obj, PAGE_SPAN_Z);
 		interp.set_named_score(&holder_y, &obj, PAGE_SPAN_Y);
+		interp.set_named_score(&ScoreHolder::new("%%memory_x".to_string()).unwrap(), &obj, 0);
+		interp.set_named_score(&ScoreHolder::new("%%memory_y".to_string()).unwrap(), &obj, 0);
+		interp.set_named_score(&ScoreHolder::new("%%memory_y_end".to_string()).unwrap(), &obj, PAGE_SPAN_Y);
+		interp.set_named_score(&ScoreHolder::new("%%memory_z".to_string()).unwrap(), &obj, 0);
 
 		for c in const_pool {
 			let (holder, obj) = Register::const_val(c).scoreboard_pair();
@@ -2764,7 +5676,7 @@ mod test {
 		}
 
 		for (addr, value) in memory.iter().copied().enumerate() {
-			let (x, y, z) = get_address_pos(addr as i32 * 4);
+			let (x, y, z) = get_address_pos((0, 0, 0), addr as i32 * 4);
 			let cmd = format!("setblock {x} {y} {z} minecraft:jukebox{{RecordItem:{{id:\"minecraft:stone\",Count:1b,tag:{{Memory:{value}}}}}}}").parse::<Command>().unwrap();
 			interp.execute_cmd(&cmd).unwrap();
 		}
@@ -2826,7 +5738,7 @@ mod test {
 
 		let addr = Register::const_val(offset);
 
-		mem_load_16(dst, addr.into(), &mut code);
+		mem_load_16(dst, addr.into(), (0, 0, 0), &mut code);
 
 		let func = parse_function("wasmrunner:test_constant_memory_load", &code);
 		let func_id = func.id.clone();
@@ -2847,7 +5759,7 @@ mod test {
 		}
 
 		for (addr, value) in memory.iter().copied().enumerate() {
-			let (x, y, z) = get_address_pos(addr as i32 * 4);
+			let (x, y, z) = get_address_pos((0, 0, 0), addr as i32 * 4);
 			let cmd = format!("setblock {x} {y} {z} minecraft:jukebox{{RecordItem:{{id:\"minecraft:stone\",Count:1b,tag:{{Memory:{value}}}}}}}").parse::<Command>().unwrap();
 			interp.execute_cmd(&cmd).unwrap();
 		}
@@ -2891,9 +5803,9 @@ mod test {
 		let src = Register::work_lo(0, 0);
 
 		let mut code = Vec::new();
-		let mut const_pool = HashSet::new();
+		let mut const_pool = BTreeSet::new();
 
-		mem_store_unaligned_32(src, offset, offset, &mut code, &mut const_pool);
+		mem_store_unaligned_32(src, offset, offset, (0, 0, 0), &mut code, &mut const_pool);
 
 		let func = parse_function("wasmrunner:test_constant_memory_store", &code);
 		let func_id = func.id.clone();
@@ -2908,8 +5820,8 @@ mod test {
 			interp.set_named_score(&holder, &obj, c);
 		}
 
-		let (x0, y0, z0) = get_address_pos(0);
-		let (x1, y1, z1) = get_address_pos(4);
+		let (x0, y0, z0) = get_address_pos((0, 0, 0), 0);
+		let (x1, y1, z1) = get_address_pos((0, 0, 0), 4);
 
 		let v0 = before as i32;
 		let v1 = (before >> 32) as i32;
@@ -3040,11 +5952,15 @@ mod test {
 		let obj = Objective::new("reg".to_string()).unwrap();
 		interp.set_named_score(&holder_z, &obj, PAGE_SPAN_Z);
 		interp.set_named_score(&holder_y, &obj, PAGE_SPAN_Y);
+		interp.set_named_score(&ScoreHolder::new("%%memory_x".to_string()).unwrap(), &obj, 0);
+		interp.set_named_score(&ScoreHolder::new("%%memory_y".to_string()).unwrap(), &obj, 0);
+		interp.set_named_score(&ScoreHolder::new("%%memory_y_end".to_string()).unwrap(), &obj, PAGE_SPAN_Y);
+		interp.set_named_score(&ScoreHolder::new("%%memory_z".to_string()).unwrap(), &obj, 0);
 
 		//interp.scoreboard.0.insert(Objective::new("reg".to_string()).unwrap(), Default::default());
 
 		for i in 0..MEMORY_PAGE_BLOCKS * 3 {
-			let pos = get_address_pos(i as i32 * 4);
+			let pos = get_address_pos((0, 0, 0), i as i32 * 4);
 			let v0 = 0x55_55_55_55;
 			interp.set_block_raw(pos, datapack_vm::interpreter::Block::Jukebox(v0));
 		}
@@ -3056,7 +5972,7 @@ mod test {
 		let mut bytes = Vec::new();
 
 		for i in 0..MEMORY_PAGE_BLOCKS * 3 {
-			let pos = get_address_pos(i as i32 * 4);
+			let pos = get_address_pos((0, 0, 0), i as i32 * 4);
 			let block = interp.get_block(pos);
 
 			if let Some(datapack_vm::interpreter::Block::Jukebox(m0)) = block {
@@ -3102,4 +6018,399 @@ mod test {
 			panic!("actual and expected values differed");
 		}*/
 	}
+
+	/// An interpreter with every intrinsic loaded and the constants they use set
+	fn intrinsic_interp() -> Interpreter {
+		intrinsic_interp_with(Vec::new())
+	}
+
+	fn intrinsic_interp_with(extra: Vec<Function>) -> Interpreter {
+		let mut program = load_intrinsics();
+		program.extend(extra);
+		let mut interp = Interpreter::new(program, 0);
+
+		let obj = Objective::new("reg".to_string()).unwrap();
+		for i in 0..32 {
+			let c: i32 = 1 << i;
+			interp.set_named_score(&ScoreHolder::new(format!("%%{c}")).unwrap(), &obj, c);
+		}
+		interp.set_named_score(&ScoreHolder::new("%%-1".to_string()).unwrap(), &obj, -1);
+
+		interp
+	}
+
+	fn run_intrinsic(interp: &mut Interpreter, name: &str) {
+		let (_, id) = FunctionIdent::parse_from_command(name).unwrap();
+		let idx = interp.get_func_idx(&id);
+		interp.set_pos(idx);
+		interp.run_to_end().unwrap();
+	}
+
+	/// The intrinsics take their parameters in `%param0%0` and so on, rather than in `Register`s
+	fn set_score(interp: &mut Interpreter, holder: &str, value: i32) {
+		let obj = Objective::new("reg".to_string()).unwrap();
+		interp.set_named_score(&ScoreHolder::new(holder.to_string()).unwrap(), &obj, value);
+	}
+
+	fn get_score(interp: &Interpreter, holder: &str) -> i32 {
+		let obj = Objective::new("reg".to_string()).unwrap();
+		interp.get_named_score(&ScoreHolder::new(holder.to_string()).unwrap(), &obj).unwrap()
+	}
+
+	/// ±0, NaNs, ±inf, subnormals, the largest finite values (which overflow when doubled),
+	/// and values just above 1 whose sums round to even
+	const F32_VALUES: [u32; 24] = [
+		0x0000_0000, 0x8000_0000,
+		0x7FC0_0000, 0xFFC0_0000, 0x7F80_0001,
+		0x7F80_0000, 0xFF80_0000,
+		0x0000_0001, 0x8000_0001, 0x007F_FFFF, 0x0080_0000, 0x0040_0000,
+		0x7F7F_FFFF, 0xFF7F_FFFF,
+		0x3F80_0000, 0xBF80_0000, 0x3F80_0001, 0x3380_0000, 0x3400_0000,
+		0x3F00_0000, 0x3FC0_0000, 0x4000_0000, 0x4049_0FDB, 0x4B00_0001,
+	];
+
+	/// The same kinds of values as `F32_VALUES`
+	const F64_VALUES: [u64; 22] = [
+		0x0000_0000_0000_0000, 0x8000_0000_0000_0000,
+		0x7FF8_0000_0000_0000, 0xFFF8_0000_0000_0000, 0x7FF0_0000_0000_0001,
+		0x7FF0_0000_0000_0000, 0xFFF0_0000_0000_0000,
+		0x0000_0000_0000_0001, 0x8000_0000_0000_0001, 0x000F_FFFF_FFFF_FFFF, 0x0010_0000_0000_0000,
+		0x7FEF_FFFF_FFFF_FFFF, 0xFFEF_FFFF_FFFF_FFFF,
+		0x3FF0_0000_0000_0000, 0xBFF0_0000_0000_0000, 0x3FF0_0000_0000_0001, 0x3CA0_0000_0000_0000, 0x3CB0_0000_0000_0000,
+		0x3FE0_0000_0000_0000, 0x4000_0000_0000_0000, 0x4009_21FB_5444_2D18, 0x4330_0000_0000_0001,
+	];
+
+	/// The intrinsics always return the canonical NaN
+	fn f32_bits(val: f32) -> u32 {
+		if val.is_nan() { 0x7FC0_0000 } else { val.to_bits() }
+	}
+
+	/// The intrinsics always return the canonical NaN
+	fn f64_bits(val: f64) -> u64 {
+		if val.is_nan() { 0x7FF8_0000_0000_0000 } else { val.to_bits() }
+	}
+
+	/// Sets the low half of a 64-bit value in `{prefix}%0` and the high half in `{prefix}%1`
+	fn set_score_64(interp: &mut Interpreter, prefix: &str, value: u64) {
+		set_score(interp, &format!("{prefix}%0"), value as i32);
+		set_score(interp, &format!("{prefix}%1"), (value >> 32) as i32);
+	}
+
+	fn get_score_64(interp: &Interpreter, prefix: &str) -> u64 {
+		(get_score(interp, &format!("{prefix}%0")) as u32 as u64) | ((get_score(interp, &format!("{prefix}%1")) as u32 as u64) << 32)
+	}
+
+	fn f32_binop_tester(interp: &mut Interpreter, name: &str, negate_rhs: bool, ex: impl Fn(f32, f32) -> f32) {
+		for lhs in F32_VALUES {
+			for rhs in F32_VALUES {
+				set_score(interp, "%param0%0", lhs as i32);
+				let rhs_bits = if negate_rhs { rhs ^ 0x8000_0000 } else { rhs };
+				set_score(interp, "%param1%0", rhs_bits as i32);
+				run_intrinsic(interp, name);
+
+				let actual = get_score(interp, "%return%0") as u32;
+				let expected = f32_bits(ex(f32::from_bits(lhs), f32::from_bits(rhs)));
+				assert_eq!(actual, expected, "{name} {lhs:#010X} {rhs:#010X}: got {actual:#010X}, expected {expected:#010X}");
+			}
+		}
+	}
+
+	#[test]
+	fn f32_arithmetic() {
+		let mut interp = intrinsic_interp();
+
+		f32_binop_tester(&mut interp, "intrinsic:f32/add", false, |a, b| a + b);
+		// Subtraction is addition with the sign of the rhs flipped
+		f32_binop_tester(&mut interp, "intrinsic:f32/add", true, |a, b| a - b);
+		f32_binop_tester(&mut interp, "intrinsic:f32/mul", false, |a, b| a * b);
+		f32_binop_tester(&mut interp, "intrinsic:f32/div", false, |a, b| a / b);
+	}
+
+	#[test]
+	fn f32_add_ties() {
+		let mut interp = intrinsic_interp();
+
+		// 1 + 2^-24 is exactly halfway between 1 and its successor, so it rounds to even (down),
+		// while (1 + 2^-23) + 2^-24 rounds to even (up)
+		for (lhs, rhs, expected) in [(0x3F80_0000, 0x3380_0000, 0x3F80_0000), (0x3F80_0001, 0x3380_0000, 0x3F80_0002), (0x7F7F_FFFF, 0x7F7F_FFFF, 0x7F80_0000)] {
+			set_score(&mut interp, "%param0%0", lhs);
+			set_score(&mut interp, "%param1%0", rhs);
+			run_intrinsic(&mut interp, "intrinsic:f32/add");
+			assert_eq!(get_score(&interp, "%return%0"), expected);
+			assert_eq!(expected as u32, (f32::from_bits(lhs as u32) + f32::from_bits(rhs as u32)).to_bits());
+		}
+	}
+
+	fn f64_binop_tester(interp: &mut Interpreter, name: &str, negate_rhs: bool, ex: impl Fn(f64, f64) -> f64) {
+		for lhs in F64_VALUES {
+			for rhs in F64_VALUES {
+				set_score_64(interp, "%param0", lhs);
+				set_score_64(interp, "%param1", if negate_rhs { rhs ^ (1 << 63) } else { rhs });
+				run_intrinsic(interp, name);
+
+				let actual = get_score_64(interp, "%return");
+				let expected = f64_bits(ex(f64::from_bits(lhs), f64::from_bits(rhs)));
+				assert_eq!(actual, expected, "{name} {lhs:#018X} {rhs:#018X}: got {actual:#018X}, expected {expected:#018X}");
+			}
+		}
+	}
+
+	#[test]
+	fn f64_arithmetic() {
+		let mut interp = intrinsic_interp();
+
+		f64_binop_tester(&mut interp, "intrinsic:f64/add", false, |a, b| a + b);
+		f64_binop_tester(&mut interp, "intrinsic:f64/add", true, |a, b| a - b);
+		f64_binop_tester(&mut interp, "intrinsic:f64/mul", false, |a, b| a * b);
+		f64_binop_tester(&mut interp, "intrinsic:f64/div", false, |a, b| a / b);
+	}
+
+	/// Integers whose conversions round to even, and the extremes of both signednesses
+	const I64_VALUES: [i64; 12] = [
+		0, 1, -1, i64::MIN, i64::MAX, u32::MAX as i64, 1 << 31,
+		(1 << 24) + 1, (1 << 24) + 3, (1 << 53) + 1, (1 << 53) + 3, -((1 << 53) + 1),
+	];
+
+	#[test]
+	fn float_sqrt() {
+		let mut interp = intrinsic_interp();
+
+		// 4, 2 and 0.25, whose square roots are exact
+		for val in F32_VALUES.into_iter().chain([0x4080_0000, 0x4000_0000, 0x3E80_0000]) {
+			set_score(&mut interp, "%param0%0", val as i32);
+			run_intrinsic(&mut interp, "intrinsic:f32/sqrt");
+			assert_eq!(get_score(&interp, "%return%0") as u32, f32_bits(f32::from_bits(val).sqrt()), "f32 sqrt {val:#010X}");
+		}
+
+		for val in F64_VALUES.into_iter().chain([0x4010_0000_0000_0000, 0x4000_0000_0000_0000, 0x3FD0_0000_0000_0000]) {
+			set_score_64(&mut interp, "%param0", val);
+			run_intrinsic(&mut interp, "intrinsic:f64/sqrt");
+			assert_eq!(get_score_64(&interp, "%return"), f64_bits(f64::from_bits(val).sqrt()), "f64 sqrt {val:#018X}");
+		}
+	}
+
+	/// Unlike `f64::min`, NaN wins and -0 is less than +0
+	fn wasm_min(a: f64, b: f64) -> f64 {
+		if a.is_nan() || b.is_nan() {
+			f64::NAN
+		} else if a == b {
+			if a.is_sign_negative() { a } else { b }
+		} else {
+			a.min(b)
+		}
+	}
+
+	/// Unlike `f64::max`, NaN wins and +0 is greater than -0
+	fn wasm_max(a: f64, b: f64) -> f64 {
+		if a.is_nan() || b.is_nan() {
+			f64::NAN
+		} else if a == b {
+			if a.is_sign_positive() { a } else { b }
+		} else {
+			a.max(b)
+		}
+	}
+
+	#[test]
+	fn float_min_max() {
+		let mut interp = intrinsic_interp();
+
+		f32_binop_tester(&mut interp, "intrinsic:f32/min", false, |a, b| wasm_min(a as f64, b as f64) as f32);
+		f32_binop_tester(&mut interp, "intrinsic:f32/max", false, |a, b| wasm_max(a as f64, b as f64) as f32);
+		f64_binop_tester(&mut interp, "intrinsic:f64/min", false, wasm_min);
+		f64_binop_tester(&mut interp, "intrinsic:f64/max", false, wasm_max);
+	}
+
+	#[test]
+	fn float_convert() {
+		let mut interp = intrinsic_interp();
+
+		for val in I64_VALUES {
+			set_score_64(&mut interp, "%param0", val as u64);
+			run_intrinsic(&mut interp, "intrinsic:f32/convert_s");
+			assert_eq!(get_score(&interp, "%return%0") as u32, (val as f32).to_bits(), "f32 convert_s {val}");
+
+			set_score_64(&mut interp, "%param0", val as u64);
+			run_intrinsic(&mut interp, "intrinsic:f32/convert_u");
+			assert_eq!(get_score(&interp, "%return%0") as u32, (val as u64 as f32).to_bits(), "f32 convert_u {val}");
+
+			set_score_64(&mut interp, "%param0", val as u64);
+			run_intrinsic(&mut interp, "intrinsic:f64/convert_s");
+			assert_eq!(get_score_64(&interp, "%return"), (val as f64).to_bits(), "f64 convert_s {val}");
+
+			set_score_64(&mut interp, "%param0", val as u64);
+			run_intrinsic(&mut interp, "intrinsic:f64/convert_u");
+			assert_eq!(get_score_64(&interp, "%return"), (val as u64 as f64).to_bits(), "f64 convert_u {val}");
+		}
+	}
+
+	/// Rounds towards zero and wraps around, and NaN and infinity give 0
+	fn trunc_to_64(val: f64) -> u64 {
+		if val.is_finite() { val.trunc() as i128 as u64 } else { 0 }
+	}
+
+	#[test]
+	fn float_trunc() {
+		let mut interp = intrinsic_interp();
+
+		// -2.5, 2^63 and 2^64, which wrap around
+		for val in F32_VALUES.into_iter().chain([0xC020_0000, 0x5F00_0000, 0x5F80_0000]) {
+			set_score(&mut interp, "%param0%0", val as i32);
+			run_intrinsic(&mut interp, "intrinsic:f32/trunc");
+			assert_eq!(get_score_64(&interp, "%return"), trunc_to_64(f32::from_bits(val) as f64), "f32 trunc {val:#010X}");
+		}
+
+		for val in F64_VALUES.into_iter().chain([0xC004_0000_0000_0000, 0x43E0_0000_0000_0000, 0x43F0_0000_0000_0001]) {
+			set_score_64(&mut interp, "%param0", val);
+			run_intrinsic(&mut interp, "intrinsic:f64/trunc");
+			assert_eq!(get_score_64(&interp, "%return"), trunc_to_64(f64::from_bits(val)), "f64 trunc {val:#018X}");
+		}
+	}
+
+	#[test]
+	fn float_rounding() {
+		let mut interp = intrinsic_interp();
+
+		let f32_ops: [(&str, fn(f32) -> f32); 4] = [("floor", f32::floor), ("ceil", f32::ceil), ("trunc", f32::trunc), ("nearest", f32::round_ties_even)];
+		let f64_ops: [(&str, fn(f64) -> f64); 4] = [("floor", f64::floor), ("ceil", f64::ceil), ("trunc", f64::trunc), ("nearest", f64::round_ties_even)];
+
+		// The extra values are ties on both sides of zero, the largest values below 0.5, and the largest ones with a fraction
+		for (op, ex) in f32_ops {
+			for val in F32_VALUES.into_iter().chain([0x4020_0000, 0xBF00_0000, 0xC020_0000, 0x4AFF_FFFF, 0x3EFF_FFFF]) {
+				set_score(&mut interp, "%param0%0", val as i32);
+				run_intrinsic(&mut interp, &format!("intrinsic:f32/round_{op}"));
+				assert_eq!(get_score(&interp, "%return%0") as u32, f32_bits(ex(f32::from_bits(val))), "f32 {op} {val:#010X}");
+			}
+		}
+
+		for (op, ex) in f64_ops {
+			for val in F64_VALUES.into_iter().chain([0x4004_0000_0000_0000, 0xBFE0_0000_0000_0000, 0xC004_0000_0000_0000, 0x3FF8_0000_0000_0000, 0x432F_FFFF_FFFF_FFFF, 0x3FDF_FFFF_FFFF_FFFF]) {
+				set_score_64(&mut interp, "%param0", val);
+				run_intrinsic(&mut interp, &format!("intrinsic:f64/round_{op}"));
+				assert_eq!(get_score_64(&interp, "%return"), f64_bits(ex(f64::from_bits(val))), "f64 {op} {val:#018X}");
+			}
+		}
+	}
+
+	#[test]
+	fn test_memcpy() {
+		let cases = [
+			// dest, src, len
+			// Both aligned, so everything is copied a word at a time
+			(16, 0, 32),
+			// Never aligned the same way, so everything is copied a byte at a time
+			(17, 2, 13),
+			// Bytes until both are aligned, then words, then the leftover bytes
+			(21, 1, 30),
+			// Overlapping with the destination after the source, so the copy goes backward
+			(6, 2, 27),
+			(5, 2, 20),
+			// Overlapping with the destination before the source
+			(2, 6, 27),
+			(8, 8, 12),
+			(40, 8, 0),
+			// Crossing into the next row, column, and page
+			(0, 256, 200),
+			(32760, 100, 16),
+			(65500, 1000, 100),
+			(65540, 65500, 80),
+			// Right up to the end of memory
+			(131064, 131000, 8),
+		];
+
+		for (dest, src, len) in cases {
+			memcpy_tester(dest, src, len);
+		}
+	}
+
+	/// Copies within two pages of memory holding a pattern, and compares every byte against `copy_within`
+	fn memcpy_tester(dest: usize, src: usize, len: usize) {
+		let mut expected = (0..MEMORY_PAGE_SIZE * 2).map(|i| (i * 37 + 11) as u8).collect::<Vec<_>>();
+		let mut interp = memory_interp(&expected);
+
+		set_score(&mut interp, "%param0%0", dest as i32);
+		set_score(&mut interp, "%param1%0", src as i32);
+		set_score(&mut interp, "%param2%0", len as i32);
+		run_intrinsic(&mut interp, "intrinsic:memcpy");
+
+		expected.copy_within(src..src + len, dest);
+		check_memory(&interp, &expected, &format!("copy({dest}, {src}, {len})"));
+	}
+
+	#[test]
+	fn test_buf_intrinsics() {
+		// Every position in a word, with the halfwords at 3 crossing into the next word,
+		// from an aligned base and from an unaligned one
+		for base in [8, 13] {
+			for offset in 0..8 {
+				buf_tester("u8", base, offset, 0xA5);
+				buf_tester("u16", base, offset, 0xBEEF);
+			}
+		}
+
+		// Only the low bits of the value are written
+		buf_tester("u8", 8, 2, -1);
+		buf_tester("u16", 8, 3, 0x1234_5678);
+	}
+
+	/// Reads and then writes one element of a buffer in memory holding a pattern with the sign bits of the words set,
+	/// and compares the value read and every byte of memory against doing the same on a `Vec`
+	fn buf_tester(ty: &str, base: usize, offset: usize, value: i32) {
+		let size = if ty == "u8" { 1 } else { 2 };
+		let mut expected = (0..64).map(|i| (i * 37 + 11) as u8 | 0x80).collect::<Vec<_>>();
+		let mut interp = memory_interp(&expected);
+
+		let addr = base + offset;
+		let mut read = [0; 4];
+		read[..size].copy_from_slice(&expected[addr..addr + size]);
+
+		set_score(&mut interp, "%param0%0", base as i32);
+		set_score(&mut interp, "%param1%0", offset as i32);
+		run_intrinsic(&mut interp, &format!("intrinsic:buf_get_{ty}"));
+		assert_eq!(get_score(&interp, "%param0%0"), i32::from_le_bytes(read), "buf_get_{ty}({base}, {offset})");
+
+		set_score(&mut interp, "%param0%0", base as i32);
+		set_score(&mut interp, "%param1%0", offset as i32);
+		set_score(&mut interp, "%param2%0", value);
+		run_intrinsic(&mut interp, &format!("intrinsic:buf_set_{ty}"));
+
+		expected[addr..addr + size].copy_from_slice(&value.to_le_bytes()[..size]);
+		check_memory(&interp, &expected, &format!("buf_set_{ty}({base}, {offset}, {value:#X})"));
+	}
+
+	/// An interpreter with the intrinsics and the memory pointer, and one memory starting at the origin holding `memory`
+	fn memory_interp(memory: &[u8]) -> Interpreter {
+		let uuid_nbt = SNbt::from(MEM_PTR_UUID);
+		let init = parse_function("wasmrunner:test_init_memory", [
+			format!("summon minecraft:marker 0 0 0 {{UUID:{uuid_nbt}}}"),
+			"data modify storage wasm:scratch Pos set value [0d, 0d, 0d]".to_string(),
+		]);
+
+		let mut interp = intrinsic_interp_with(vec![init]);
+		run_intrinsic(&mut interp, "wasmrunner:test_init_memory");
+
+		set_score(&mut interp, "%%PAGE_SPAN_Z", PAGE_SPAN_Z);
+		set_score(&mut interp, "%%PAGE_SPAN_Y", PAGE_SPAN_Y);
+		set_score(&mut interp, "%%memory_x", 0);
+		set_score(&mut interp, "%%memory_y", 0);
+		set_score(&mut interp, "%%memory_y_end", PAGE_SPAN_Y);
+		set_score(&mut interp, "%%memory_z", 0);
+
+		for (i, word) in memory.chunks_exact(4).enumerate() {
+			let pos = get_address_pos((0, 0, 0), i as i32 * 4);
+			interp.set_block_raw(pos, datapack_vm::interpreter::Block::Jukebox(i32::from_le_bytes(word.try_into().unwrap())));
+		}
+
+		interp
+	}
+
+	fn check_memory(interp: &Interpreter, expected: &[u8], what: &str) {
+		for (i, word) in expected.chunks_exact(4).enumerate() {
+			let pos = get_address_pos((0, 0, 0), i as i32 * 4);
+			let Some(datapack_vm::interpreter::Block::Jukebox(actual)) = interp.get_block(pos) else {
+				panic!("no memory at {pos:?}");
+			};
+			assert_eq!(actual.to_le_bytes(), word, "{what}, address {:#X}", i * 4);
+		}
+	}
 }
@@ -0,0 +1,105 @@
+//! Moving a datapack's names into its own namespace when it's written, chosen with `--namespace`,
+//! so it can be installed in the same world as other programs compiled by wasmcraft and call their exports.
+//!
+//! Like short names, names are replaced in the text of each written file after it has been adapted for its version,
+//! so the simulator and everything before writing keep using the default names.
+//! The call objective is left alone, since it's how packs pass arguments and results to each other.
+
+use super::CALL_OBJECTIVE;
+use crate::lir::OBJECTIVE_NAME;
+
+/// The namespace of a datapack's functions when it isn't given another one.
+pub static DEFAULT_NAMESPACE: &str = "wasmrunner";
+
+/// The namespace of the intrinsic functions every datapack has.
+static INTRINSIC_NAMESPACE: &str = "intrinsic";
+
+/// The namespace of the storage every datapack uses.
+static STORAGE_NAMESPACE: &str = "wasm";
+
+/// The prefix of the objectives and entity tags every datapack uses besides the holder objective.
+static OBJECTIVE_PREFIX: &str = "wasm_";
+
+/// Namespaces that a datapack can't be moved to, since they are used for something else already.
+static RESERVED_NAMESPACES: [&str; 3] = ["minecraft", "intrinsic", "wasm"];
+
+pub struct PackNamespace {
+	namespace: String,
+}
+
+impl PackNamespace {
+	pub fn new(namespace: &str) -> Self {
+		PackNamespace { namespace: namespace.to_string() }
+	}
+
+	/// The namespace the intrinsic functions are moved to.
+	fn intrinsic_namespace(&self) -> String {
+		format!("{}_{INTRINSIC_NAMESPACE}", self.namespace)
+	}
+
+	/// The path of a file in the pack once it has been moved.
+	pub fn rename_path(&self, path: &str) -> String {
+		let rest = path.strip_prefix("data/").and_then(|rest| rest.split_once('/'));
+		match rest {
+			Some((namespace, rest)) if namespace == DEFAULT_NAMESPACE => format!("data/{}/{rest}", self.namespace),
+			Some((namespace, rest)) if namespace == INTRINSIC_NAMESPACE => format!("data/{}/{rest}", self.intrinsic_namespace()),
+			_ => path.to_string(),
+		}
+	}
+
+	/// Replaces every moved name in the contents of a file.
+	pub fn apply(&self, contents: &str) -> String {
+		let mut result = String::with_capacity(contents.len());
+		let mut word_start = 0;
+		for (idx, c) in contents.char_indices().chain(std::iter::once((contents.len(), ' '))) {
+			if !is_word_char(c) {
+				let word = &contents[word_start..idx];
+				match self.rename_word(word) {
+					Some(renamed) => result.push_str(&renamed),
+					None => result.push_str(word),
+				}
+				if idx < contents.len() {
+					result.push(c);
+				}
+				word_start = idx + c.len_utf8();
+			}
+		}
+		result
+	}
+
+	fn rename_word(&self, word: &str) -> Option<String> {
+		let (namespace, path) = match word.split_once(':') {
+			Some(split) => split,
+			None if word == OBJECTIVE_NAME => return Some(self.namespace.clone()),
+			None if word != CALL_OBJECTIVE => {
+				let rest = word.strip_prefix(OBJECTIVE_PREFIX)?;
+				return Some(format!("{}_{rest}", self.namespace));
+			}
+			None => return None,
+		};
+
+		if namespace == DEFAULT_NAMESPACE || namespace == STORAGE_NAMESPACE {
+			Some(format!("{}:{path}", self.namespace))
+		} else if namespace == INTRINSIC_NAMESPACE {
+			Some(format!("{}:{path}", self.intrinsic_namespace()))
+		} else {
+			None
+		}
+	}
+}
+
+/// Whether a character can be part of a name, which includes the characters of function paths and macro arguments.
+fn is_word_char(c: char) -> bool {
+	c.is_ascii_alphanumeric() || "_:./-%$()".contains(c)
+}
+
+/// Checks that a namespace can be used for a datapack's functions, storage, and holder objective.
+pub fn check_namespace(namespace: &str) -> Result<(), String> {
+	if namespace.is_empty() || !namespace.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+		return Err(format!("a namespace can only have lowercase letters, digits, and underscores, got {namespace:?}"));
+	}
+	if RESERVED_NAMESPACES.contains(&namespace) {
+		return Err(format!("the namespace {namespace:?} is already used by every datapack"));
+	}
+	Ok(())
+}
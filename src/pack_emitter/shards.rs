@@ -0,0 +1,45 @@
+//! Splitting a datapack into several datapacks when it's written, chosen with `--max-pack-functions`,
+//! for hosts that limit how many functions a single datapack may have.
+//!
+//! Every shard is a complete datapack with its own `pack.mcmeta`, and all of them add their functions to the same
+//! namespaces, so calls from one shard to another work without being changed once every shard is enabled.
+//! The files that aren't functions, like function tags and predicates, are kept in the first shard.
+//! Names are shortened and moved to another namespace before splitting, so they agree between shards.
+
+use std::path::{Path, PathBuf};
+
+/// Splits the files of a pack into shards that each have at most `max_functions` functions.
+pub fn split_files(files: Vec<(String, String)>, max_functions: usize) -> Vec<Vec<(String, String)>> {
+	let (funcs, others): (Vec<_>, Vec<_>) = files.into_iter().partition(|(path, _)| path.ends_with(".mcfunction"));
+	let metadata = others.iter().find(|(path, _)| path == "pack.mcmeta").cloned();
+
+	let mut funcs = funcs.into_iter();
+	let mut first = others;
+	first.extend(funcs.by_ref().take(max_functions));
+
+	let mut shards = vec![first];
+	loop {
+		let shard = funcs.by_ref().take(max_functions).collect::<Vec<_>>();
+		if shard.is_empty() {
+			break;
+		}
+		shards.push(metadata.iter().cloned().chain(shard).collect());
+	}
+	shards
+}
+
+/// Where a shard is written: the first at the output path, and the others next to it with their number added to its name,
+/// e.g. `nameofdatapack_2`.
+pub fn shard_path(output: &Path, idx: usize) -> PathBuf {
+	if idx == 0 {
+		return output.to_path_buf();
+	}
+
+	let mut name = output.file_stem().unwrap_or_else(|| panic!("output path {:?} has no name to number the datapacks with", output)).to_os_string();
+	name.push(format!("_{}", idx + 1));
+	if let Some(extension) = output.extension() {
+		name.push(".");
+		name.push(extension);
+	}
+	output.with_file_name(name)
+}
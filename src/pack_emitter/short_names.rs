@@ -0,0 +1,146 @@
+//! Shortening the names of internal score holders and objectives when the datapack is written, chosen with `--short-names`.
+//!
+//! Names are replaced in the text of each written function, after it has been adapted for its version,
+//! so the simulator and everything before writing keep using the descriptive names.
+
+use std::collections::{HashMap, HashSet};
+
+use super::{CMDS_RUN_VAR, MAX_CMDS_VAR, RECURSION_DEPTH_VAR, TIME_SLICE_BUSY_VAR, FRAMES_DROPPED_VAR, MEM_INIT_STATE_VAR, MEM_PAGES_VAR, TRAP_VAR, PLAYER_EVENTS};
+
+/// Holders other datapacks and players are told to use, which keep their names.
+static KEPT_HOLDERS: [&str; 8] = [
+	CMDS_RUN_VAR,
+	MAX_CMDS_VAR,
+	RECURSION_DEPTH_VAR,
+	TIME_SLICE_BUSY_VAR,
+	FRAMES_DROPPED_VAR,
+	MEM_INIT_STATE_VAR,
+	MEM_PAGES_VAR,
+	TRAP_VAR,
+];
+
+/// The objective the renamed holders are in.
+static HOLDER_OBJECTIVE: &str = "reg";
+
+/// What each short name in a written datapack stands for.
+#[derive(Debug, Default)]
+pub struct ShortNames {
+	/// Each renamed holder's short name and original name, most used first.
+	holders: Vec<(String, String)>,
+	/// Each renamed objective's short name and original name, most used first.
+	objectives: Vec<(String, String)>,
+}
+
+impl ShortNames {
+	/// Picks short names for the internal holders and objectives in the contents of the given functions.
+	/// The most used names get the shortest replacements, and no replacement is a name already used anywhere in them.
+	pub(super) fn assign<'a>(functions: impl Iterator<Item = &'a str>) -> Self {
+		let kept = KEPT_HOLDERS.iter().map(|var| var.strip_suffix(" reg").unwrap()).collect::<HashSet<_>>();
+		let renamed_objectives = PLAYER_EVENTS.iter().map(|(_, objective, _)| *objective).collect::<HashSet<_>>();
+
+		let mut used = HashSet::new();
+		let mut holders = HashSet::new();
+		let mut counts = HashMap::new();
+		for contents in functions {
+			let tokens = tokens(contents).collect::<Vec<_>>();
+			for (idx, token) in tokens.iter().enumerate() {
+				used.insert(*token);
+				*counts.entry(*token).or_insert(0usize) += 1;
+				if is_holder(token) && !kept.contains(token) && tokens.get(idx + 1) == Some(&HOLDER_OBJECTIVE) {
+					holders.insert(*token);
+				}
+			}
+		}
+		let objectives = renamed_objectives.into_iter().filter(|objective| used.contains(objective)).collect::<HashSet<_>>();
+
+		let holders = assign_names(holders, "%", &counts, &used);
+		let objectives = assign_names(objectives, "w", &counts, &used);
+
+		ShortNames { holders, objectives }
+	}
+
+	/// Replaces every renamed holder and objective in the contents of the given functions.
+	pub(super) fn apply<'a>(&self, functions: impl Iterator<Item = &'a mut String>) {
+		let names = self.holders.iter().chain(self.objectives.iter())
+			.map(|(short, long)| (long.as_str(), short.as_str()))
+			.collect::<HashMap<_, _>>();
+
+		for contents in functions {
+			*contents = replace_names(contents, &names);
+		}
+	}
+
+	/// The mapping file, a JSON object from each short holder and objective name to the name it replaces.
+	pub fn to_json(&self) -> String {
+		let to_map = |names: &[(String, String)]| names.iter()
+			.map(|(short, long)| (short.clone(), serde_json::Value::String(long.clone())))
+			.collect::<serde_json::Map<_, _>>();
+
+		let json = serde_json::json!({
+			"holders": to_map(&self.holders),
+			"objectives": to_map(&self.objectives),
+		});
+		serde_json::to_string_pretty(&json).unwrap()
+	}
+}
+
+/// Gives each name a short name made of `prefix` and a base 36 number, skipping any that are already used.
+fn assign_names(names: HashSet<&str>, prefix: &str, counts: &HashMap<&str, usize>, used: &HashSet<&str>) -> Vec<(String, String)> {
+	let mut names = names.into_iter().collect::<Vec<_>>();
+	names.sort_by(|a, b| counts[b].cmp(&counts[a]).then(a.cmp(b)));
+
+	let mut next_name = 0;
+	names.into_iter().map(|name| {
+		let short = loop {
+			let short = format!("{prefix}{}", to_base36(next_name));
+			next_name += 1;
+			if !used.contains(short.as_str()) {
+				break short;
+			}
+		};
+		(short, name.to_string())
+	}).collect()
+}
+
+fn replace_names(contents: &str, names: &HashMap<&str, &str>) -> String {
+	let mut result = String::with_capacity(contents.len());
+	let mut token_start = 0;
+	for (idx, c) in contents.char_indices().chain(std::iter::once((contents.len(), ' '))) {
+		if is_separator(c) {
+			let token = &contents[token_start..idx];
+			result.push_str(names.get(token).copied().unwrap_or(token));
+			if idx < contents.len() {
+				result.push(c);
+			}
+			token_start = idx + c.len_utf8();
+		}
+	}
+	result
+}
+
+fn to_base36(mut n: u32) -> String {
+	let mut digits = Vec::new();
+	loop {
+		digits.push(std::char::from_digit(n % 36, 36).unwrap());
+		n /= 36;
+		if n == 0 {
+			break;
+		}
+	}
+	digits.iter().rev().collect()
+}
+
+/// Whether a character ends a name, which includes the quotes around names in JSON text components.
+fn is_separator(c: char) -> bool {
+	c.is_whitespace() || c == '"'
+}
+
+fn tokens(contents: &str) -> impl Iterator<Item = &str> {
+	contents.split(is_separator).filter(|token| !token.is_empty())
+}
+
+/// Whether a token is a fake player name written out in full, which excludes operators like `%=`
+/// and names put together by function macros.
+fn is_holder(token: &str) -> bool {
+	token.len() >= 2 && token.starts_with('%') && token.chars().all(|c| c.is_ascii_alphanumeric() || "%_.-".contains(c))
+}
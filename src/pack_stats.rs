@@ -0,0 +1,137 @@
+//! Statistics about the size of an emitted datapack, so it's clear which parts of a program
+//! turn into the most commands.
+//!
+//! Everything is counted from the lines written for the target version,
+//! so macro and fallback variants of a function aren't both counted.
+
+use std::{collections::{BTreeMap, BTreeSet, HashMap}, fmt::Write};
+
+use datapack_common::functions::Function;
+
+use crate::{mc_version::McVersion, pack_emitter};
+
+/// How many of the largest functions are printed.
+const PRINTED_FUNCTIONS: usize = 10;
+
+pub struct PackStats {
+	/// Every written function and its number of commands, largest first.
+	pub functions: Vec<(String, usize)>,
+	/// The commands emitted for each wasm function, largest first.
+	pub wasm_functions: Vec<WasmFunctionStats>,
+	/// Each objective the datapack adds, and the score holders used in it.
+	pub objectives: BTreeMap<String, BTreeSet<String>>,
+}
+
+pub struct WasmFunctionStats {
+	pub index: usize,
+	pub name: Option<String>,
+	/// How many functions its blocks were emitted as.
+	pub blocks: usize,
+	pub commands: usize,
+}
+
+impl PackStats {
+	pub fn total_commands(&self) -> usize {
+		self.functions.iter().map(|(_, commands)| commands).sum()
+	}
+
+	pub fn total_holders(&self) -> usize {
+		self.objectives.values().map(BTreeSet::len).sum()
+	}
+
+	/// A summary of the statistics for printing, listing only the largest functions.
+	pub fn summary(&self) -> String {
+		let mut s = String::new();
+		writeln!(s, "Datapack has {} functions with {} commands", self.functions.len(), self.total_commands()).unwrap();
+		writeln!(s, "Uses {} score holders in {} objectives", self.total_holders(), self.objectives.len()).unwrap();
+		for (objective, holders) in self.objectives.iter() {
+			writeln!(s, "\t{objective}: {} holders", holders.len()).unwrap();
+		}
+
+		writeln!(s, "Largest wasm functions:").unwrap();
+		for func in self.wasm_functions.iter().take(PRINTED_FUNCTIONS) {
+			let name = func.name.as_ref().map(|name| format!(" ({name})")).unwrap_or_default();
+			writeln!(s, "\t{} commands in {} blocks: func {}{name}", func.commands, func.blocks, func.index).unwrap();
+		}
+
+		writeln!(s, "Largest functions:").unwrap();
+		for (id, commands) in self.functions.iter().take(PRINTED_FUNCTIONS) {
+			writeln!(s, "\t{commands} commands: {id}").unwrap();
+		}
+		s
+	}
+
+	pub fn to_json(&self) -> String {
+		let json = serde_json::json!({
+			"commands": self.total_commands(),
+			"functions": self.functions.iter().map(|(id, commands)| serde_json::json!({
+				"function": id,
+				"commands": commands,
+			})).collect::<Vec<_>>(),
+			"wasm_functions": self.wasm_functions.iter().map(|func| serde_json::json!({
+				"index": func.index,
+				"name": func.name,
+				"blocks": func.blocks,
+				"commands": func.commands,
+			})).collect::<Vec<_>>(),
+			"objectives": self.objectives.iter().map(|(objective, holders)| serde_json::json!({
+				"objective": objective,
+				"holders": holders,
+			})).collect::<Vec<_>>(),
+		});
+		serde_json::to_string_pretty(&json).unwrap()
+	}
+}
+
+/// Counts the commands written for each function of a datapack, and the objectives and holders they use.
+pub fn compute(datapack: &[Function], func_names: &HashMap<usize, String>, version: McVersion) -> PackStats {
+	let written = datapack.iter()
+		.filter_map(|func| Some((func.id.to_string(), pack_emitter::written_lines(func, version)?)))
+		.map(|(id, lines)| (id, lines.into_iter().filter(|line| !line.is_empty() && !line.starts_with('#')).collect::<Vec<_>>()))
+		.collect::<Vec<_>>();
+
+	let mut functions = written.iter().map(|(id, cmds)| (id.clone(), cmds.len())).collect::<Vec<_>>();
+	functions.sort_by(|(a_id, a), (b_id, b)| b.cmp(a).then(a_id.cmp(b_id)));
+
+	let mut wasm_functions = BTreeMap::<usize, WasmFunctionStats>::new();
+	for (id, cmds) in written.iter() {
+		if let Some(index) = wasm_function_index(id) {
+			let stats = wasm_functions.entry(index).or_insert_with(|| WasmFunctionStats { index, name: func_names.get(&index).cloned(), blocks: 0, commands: 0 });
+			stats.blocks += 1;
+			stats.commands += cmds.len();
+		}
+	}
+	let mut wasm_functions = wasm_functions.into_values().collect::<Vec<_>>();
+	wasm_functions.sort_by(|a, b| b.commands.cmp(&a.commands).then(a.index.cmp(&b.index)));
+
+	let mut objectives = BTreeMap::<String, BTreeSet<String>>::new();
+	for cmd in written.iter().flat_map(|(_, cmds)| cmds.iter()) {
+		if let Some(objective) = cmd.strip_prefix("scoreboard objectives add ").and_then(|rest| rest.split(' ').next()) {
+			objectives.entry(objective.to_string()).or_default();
+		}
+	}
+	for cmd in written.iter().flat_map(|(_, cmds)| cmds.iter()) {
+		let tokens = cmd.split(' ').collect::<Vec<_>>();
+		for pair in tokens.windows(2) {
+			if let Some(holders) = objectives.get_mut(pair[1]) {
+				if is_holder(pair[0]) {
+					holders.insert(pair[0].to_string());
+				}
+			}
+		}
+	}
+
+	PackStats { functions, wasm_functions, objectives }
+}
+
+/// The wasm function a datapack function was emitted for, if it was emitted for one.
+fn wasm_function_index(id: &str) -> Option<usize> {
+	let rest = id.strip_prefix("wasmrunner:wasm_")?;
+	rest[..rest.find('_')?].parse().ok()
+}
+
+/// Whether a token can be the score holder before an objective's name,
+/// which leaves out the other words objective names follow and holders put together by function macros.
+fn is_holder(token: &str) -> bool {
+	!matches!(token, "add" | "remove" | "sidebar" | "list") && !token.contains("$(")
+}
@@ -0,0 +1,63 @@
+//! Optimization remarks, which describe what the optimization passes did
+//! or why they weren't able to do better.
+//!
+//! Each `CompileContext` has its own collector, which passes running in parallel share,
+//! and which only keeps remarks when `--remarks` is given.
+
+use std::{sync::Mutex, path::Path, fmt::Write};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remark {
+	/// The name of the pass that emitted this remark
+	pub pass: &'static str,
+	/// The function the remark applies to
+	pub func: usize,
+	/// The block the remark applies to, if it isn't about the whole function
+	pub block: Option<usize>,
+	pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Remarks {
+	enabled: bool,
+	remarks: Mutex<Vec<Remark>>,
+}
+
+impl Remarks {
+	pub fn new(enabled: bool) -> Self {
+		Remarks { enabled, remarks: Mutex::new(Vec::new()) }
+	}
+
+	pub fn is_enabled(&self) -> bool {
+		self.enabled
+	}
+
+	/// Records a remark. The message is only built if remarks are enabled.
+	pub fn emit<F: FnOnce() -> String>(&self, pass: &'static str, func: usize, block: Option<usize>, message: F) {
+		if self.enabled {
+			let remark = Remark { pass, func, block, message: message() };
+			self.remarks.lock().unwrap().push(remark);
+		}
+	}
+
+	/// Removes and returns all remarks recorded so far, ordered by function and then by block,
+	/// with the ones about a whole function first.
+	/// Remarks about the same block keep the order the passes emitted them in.
+	pub fn take(&self) -> Vec<Remark> {
+		let mut remarks = std::mem::take(&mut *self.remarks.lock().unwrap());
+		remarks.sort_by_key(|remark| (remark.func, remark.block));
+		remarks
+	}
+
+	/// Writes all remarks recorded so far to a file, one per line.
+	pub fn write_to(&self, path: &Path) -> std::io::Result<()> {
+		let mut contents = String::new();
+		for remark in self.take() {
+			match remark.block {
+				Some(block) => writeln!(contents, "func {} block {}: [{}] {}", remark.func, block, remark.pass, remark.message).unwrap(),
+				None => writeln!(contents, "func {}: [{}] {}", remark.func, remark.pass, remark.message).unwrap(),
+			}
+		}
+		std::fs::write(path, contents)
+	}
+}
@@ -19,26 +19,39 @@ pub struct CallGraph {
 
 	// The keys are function IDs
 	is_single_tick: HashMap<u32, bool>,
+
+	// Functions that can (directly or indirectly) call themselves.
+	// Only computed when recursive calls should yield.
+	recursive: HashSet<u32>,
+
+	recursion_yield_depth: Option<u32>,
 }
 
 impl CallGraph {
-	pub fn new(program: &SsaProgram) -> Self {
+	/// If `recursion_yield_depth` is given, recursive functions are treated as multi-tick
+	/// so that calls to them can yield every that many calls.
+	pub fn new(program: &SsaProgram, recursion_yield_depth: Option<u32>) -> Self {
 		println!("doing direct calls!");
 
 		let direct_calls = get_direct_calls(program);
 
+		let recursive = if recursion_yield_depth.is_some() {
+			get_recursive_funcs(&direct_calls)
+		} else {
+			HashSet::new()
+		};
+
 		println!("did direct calls, doing single tick!");
 
-		let is_single_tick = get_single_tick_funcs(program);
+		let is_single_tick = get_single_tick_funcs(program, &recursive);
 
 		println!("did single tick, doing tables!");
 
-		// FIXME: This becomes invalid once table-modifying instructions are added
 		let mut table_info = HashMap::new();
 		for (table_idx, table) in program.tables.iter().enumerate() {
 			let mut is_only_single_tick = true;
 			let mut is_only_multi_tick = true;
-			for elem in table.elements.iter().copied().flatten() {
+			for elem in table.call_targets().into_iter().flatten() {
 				let target_is_single_tick = *is_single_tick.get(&(elem as u32)).unwrap();
 				if target_is_single_tick {
 					is_only_multi_tick = false;
@@ -50,7 +63,7 @@ impl CallGraph {
 			table_info.insert(table_idx as u32, TableInfo { is_only_single_tick, is_only_multi_tick });
 		}
 
-		CallGraph { direct_calls, table_info, is_single_tick }
+		CallGraph { direct_calls, table_info, is_single_tick, recursive, recursion_yield_depth }
 	}
 
 	pub fn may_call(&self, caller: u32, callee: u32) -> bool {
@@ -63,6 +76,20 @@ impl CallGraph {
 		true
 	}
 
+	pub fn is_recursive(&self, func: u32) -> bool {
+		self.recursive.contains(&func)
+	}
+
+	/// Returns how many calls to this function are allowed before yielding a tick,
+	/// or None if calls to it never need to yield.
+	pub fn recursion_yield_depth(&self, func: u32) -> Option<u32> {
+		if self.is_recursive(func) {
+			self.recursion_yield_depth
+		} else {
+			None
+		}
+	}
+
 	pub fn is_single_tick(&self, func: u32) -> bool {
 		*self.is_single_tick.get(&func).unwrap()
 	}
@@ -155,16 +182,42 @@ fn contains_back_edge(func: &SsaFunction) -> bool {
 	false
 }
 
-fn get_single_tick_funcs(program: &SsaProgram) -> HashMap<u32, bool> {
+fn get_recursive_funcs(direct_calls: &HashMap<u32, HashSet<u32>>) -> HashSet<u32> {
+	let mut recursive = HashSet::new();
+
+	for &func in direct_calls.keys() {
+		let mut visited = HashSet::new();
+		let mut to_visit = direct_calls.get(&func).unwrap().iter().copied().collect::<Vec<_>>();
+		while let Some(node) = to_visit.pop() {
+			if node == func {
+				recursive.insert(func);
+				break;
+			}
+
+			if !visited.insert(node) {
+				continue;
+			}
+
+			if let Some(callees) = direct_calls.get(&node) {
+				to_visit.extend(callees.iter().copied());
+			}
+		}
+	}
+
+	recursive
+}
+
+fn get_single_tick_funcs(program: &SsaProgram, recursive: &HashSet<u32>) -> HashMap<u32, bool> {
 	// A function is single tick iff:
 	// - It contains no scheduled jumps,
-	// - it contains no back edges, and
+	// - it contains no back edges,
+	// - it isn't recursive (only when recursive calls should yield), and
 	// - all of its callees are single tick
 
 	let mut is_single_tick = HashMap::new();
 
 	for func in program.code.iter() {
-		let is_st = !contains_scheduled_jump(func) && !contains_back_edge(func);
+		let is_st = !contains_scheduled_jump(func) && !contains_back_edge(func) && !recursive.contains(&func.func_id());
 		is_single_tick.insert(func.func_id(), is_st);
 	}
 
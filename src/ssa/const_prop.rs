@@ -261,7 +261,7 @@ pub fn get_func_constants(func: &SsaFunction) -> LocalBlockMap<StaticState> {
 		//println!("\nNew iteration");
 
 		for node in reverse_postorder.iter() {
-			let mut entry_state = states.entry(*node).get_or_insert_with(StaticState::new).clone();
+			let mut entry_state = states.entry(*node).or_insert_with(StaticState::new).clone();
 
 			let preds = pred_info.get_predecessors(*node);
 			for pred in preds {
@@ -475,8 +475,8 @@ pub fn do_block_const_prop_from(_id: BlockId, block: &SsaBasicBlock, constants:
 					(_, StaticValue::Mask(_)) => {},
 					(StaticValue::Mask(msk), StaticValue::Constant(cst)) => {
 						let c = match cst {
-							TypedValue::I32(c) => c as u32 as u64,
-							TypedValue::I64(c) => c as u64,
+							TypedValue::I32(c) => (c as u32 as u64) % 32,
+							TypedValue::I64(c) => (c as u64) % 64,
 						};
 
 						let mut dst_mask = msk;
@@ -488,10 +488,10 @@ pub fn do_block_const_prop_from(_id: BlockId, block: &SsaBasicBlock, constants:
 					(StaticValue::Constant(cst1), StaticValue::Constant(cst2)) => {
 						match (cst1, cst2) {
 							(TypedValue::I32(c1), TypedValue::I32(c2)) => {
-								constants.insert(dst, (c1 | c2).into());
+								constants.insert(dst, c1.wrapping_shl(c2 as u32).into());
 							}
 							(TypedValue::I64(c1), TypedValue::I64(c2)) => {
-								constants.insert(dst, (c1 | c2).into());
+								constants.insert(dst, c1.wrapping_shl(c2 as u32).into());
 							}
 							_ => panic!(),
 						}
@@ -508,15 +508,19 @@ pub fn do_block_const_prop_from(_id: BlockId, block: &SsaBasicBlock, constants:
 						let is_ok_32 = lhs.ty() == ValType::I32 && msk.clr_bits & (1 << 31) != 0;
 						if is_ok_64 || is_ok_32 {
 							let c = match cst {
-								TypedValue::I32(c) => c as u32 as u64,
-								TypedValue::I64(c) => c as u64,
+								TypedValue::I32(c) => (c as u32 as u64) % 32,
+								TypedValue::I64(c) => (c as u64) % 64,
+							};
+							let b = match cst {
+								TypedValue::I32(_) => 31,
+								TypedValue::I64(_) => 63,
 							};
 
 							let mut dst_mask = msk;
 							dst_mask.set_bits >>= c;
 							dst_mask.clr_bits >>= c;
 							for i in 0..c {
-								dst_mask.clr_bits |= 1 << (31 - i)
+								dst_mask.clr_bits |= 1 << (b - i)
 							}
 							constants.insert(dst, dst_mask.into());
 						}
@@ -525,10 +529,10 @@ pub fn do_block_const_prop_from(_id: BlockId, block: &SsaBasicBlock, constants:
 					(StaticValue::Constant(cst1), StaticValue::Constant(cst2)) => {
 						match (cst1, cst2) {
 							(TypedValue::I32(c1), TypedValue::I32(c2)) => {
-								constants.insert(dst, (c1 >> (c2 % 32)).into());
+								constants.insert(dst, c1.wrapping_shr(c2 as u32).into());
 							}
 							(TypedValue::I64(c1), TypedValue::I64(c2)) => {
-								constants.insert(dst, (c1 >> (c2 % 64)).into());
+								constants.insert(dst, c1.wrapping_shr(c2 as u32).into());
 							}
 							_ => panic!(),
 						}
@@ -562,12 +566,10 @@ pub fn do_block_const_prop_from(_id: BlockId, block: &SsaBasicBlock, constants:
 					(StaticValue::Constant(cst1), StaticValue::Constant(cst2)) => {
 						match (cst1, cst2) {
 							(TypedValue::I32(lhs), TypedValue::I32(rhs)) => {
-								let rhs = rhs % 32;
-								constants.insert(dst, (((lhs as u32) >> rhs) as i32).into());
+								constants.insert(dst, ((lhs as u32).wrapping_shr(rhs as u32) as i32).into());
 							}
 							(TypedValue::I64(lhs), TypedValue::I64(rhs)) => {
-								let rhs = rhs % 64;
-								constants.insert(dst, (((lhs as u64) >> rhs) as i64).into());
+								constants.insert(dst, ((lhs as u64).wrapping_shr(rhs as u32) as i64).into());
 							}
 							_ => panic!(),
 						}
@@ -772,10 +774,10 @@ pub fn do_strength_reduction(block: &mut SsaBasicBlock, constants: &StaticState)
 					(StaticValue::Constant(cst1), StaticValue::Constant(cst2)) => {
 						match (cst1, cst2) {
 							(TypedValue::I32(c1), TypedValue::I32(c2)) => {
-								*instr = SsaInstr::I32Set(dst, (c1 as u32 >> c2) as i32);
+								*instr = SsaInstr::I32Set(dst, (c1 as u32).wrapping_shr(c2 as u32) as i32);
 							}
 							(TypedValue::I64(c1), TypedValue::I64(c2)) => {
-								*instr = SsaInstr::I64Set(dst, (c1 as u64 >> c2) as i64);
+								*instr = SsaInstr::I64Set(dst, (c1 as u64).wrapping_shr(c2 as u32) as i64);
 							}
 							_ => panic!(),
 						}
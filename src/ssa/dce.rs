@@ -1,9 +1,10 @@
 use std::collections::HashSet;
 
 use super::{SsaProgram, liveness::{LivenessInfo, FullLivenessInfo}, BlockId, SsaBasicBlock, SsaTerminator};
+use crate::remarks::Remarks;
 use rayon::prelude::*;
 
-pub fn do_dead_code_elim(program: &mut SsaProgram) {
+pub fn do_dead_code_elim(program: &mut SsaProgram, remarks: &Remarks) {
 	program.code.par_iter_mut().for_each(|func| {
 		let mut reachable_blocks = HashSet::new();
 
@@ -17,10 +18,14 @@ pub fn do_dead_code_elim(program: &mut SsaProgram) {
 			to_visit.extend(func.get(node).term.successors());
 		}
 
-		for idx in func.code.iter_all_keys() {
-			if !reachable_blocks.contains(&idx) {
-				func.code.remove(idx);
-			}
+		let unreachable = func.code.keys().filter(|idx| !reachable_blocks.contains(idx)).collect::<Vec<_>>();
+		let removed = unreachable.len();
+		for idx in unreachable {
+			func.code.remove(idx);
+		}
+
+		if removed != 0 {
+			remarks.emit("dce", func.func_id() as usize, None, || format!("removed {} unreachable blocks", removed));
 		}
 	});
 
@@ -29,6 +34,9 @@ pub fn do_dead_code_elim(program: &mut SsaProgram) {
 
 		for (block_id, block) in func.iter_mut() {
 			let changes = get_dce_changes(block_id, block, &live_info);
+			if !changes.is_empty() {
+				remarks.emit("dce", block_id.func, Some(block_id.block), || format!("removed {} dead instructions", changes.len()));
+			}
 
 			apply_changes(block, &changes);
 		}
@@ -54,6 +62,7 @@ pub fn do_dead_code_elim(program: &mut SsaProgram) {
 
 				if !indices_to_remove.is_empty() {
 					changed = true;
+					remarks.emit("dce", block_id.func, Some(block_id.block), || format!("removed {} unused params", indices_to_remove.len()));
 				}
 
 				remove_from_params(block, &indices_to_remove);
@@ -2,9 +2,9 @@ use std::collections::HashMap;
 
 use wasmparser::{ValType, MemoryImmediate};
 
-use crate::{ssa::{TypedSsaVar, const_prop::state_matches}, block_id_map::LocalBlockMap};
+use crate::{ssa::{TypedSsaVar, const_prop::state_matches}, block_id_map::LocalBlockMap, SnapshotRegion, DivByZero};
 
-use super::{BlockId, SsaBasicBlock, SsaVar, SsaProgram, Memory, Table, SsaVarOrConst, const_prop::StaticState};
+use super::{BlockId, SsaBasicBlock, SsaVar, SsaProgram, Memory, Table, SsaVarOrConst, TrapKind, const_prop::StaticState};
 
 #[derive(Debug)]
 pub struct Pc {
@@ -24,9 +24,9 @@ impl CallFrame {
 	pub fn new(block: BlockId, local_tys: &[ValType], return_vars: Option<Vec<TypedSsaVar>>) -> Self {
 		// TODO: Are locals zero-initialized???
 		let locals = local_tys.iter().map(|ty| match ty {
-			ValType::I32 => TypedValue::I32(0),
-			ValType::I64 => TypedValue::I64(0),
-			_ => panic!(),
+			ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => TypedValue::I32(0),
+			ValType::I64 | ValType::F64 => TypedValue::I64(0),
+			ty => panic!("locals of type {ty:?} aren't supported"),
 		}).collect();
 
 		Self {
@@ -164,10 +164,23 @@ pub struct SsaInterpreter {
 	globals: Vec<TypedValue>,
 	memory: Vec<Memory>,
 	tables: Vec<Table>,
+	elem_segments: Vec<Vec<Option<usize>>>,
+	/// Each snapshot region and the bytes last copied from it, which start out as zeros like in the datapack
+	snapshots: Vec<(SnapshotRegion, Vec<u8>)>,
 	program: HashMap<BlockId, SsaBasicBlock>,
 	constants: LocalBlockMap<StaticState>,
 	call_stack: CallStack,
 	steps: u64,
+	div_by_zero: DivByZero,
+}
+
+/// What an integer division or remainder by zero gives in the datapack:
+/// `fast` with `DivByZero::Fast`, or a trap with `DivByZero::Trap`.
+pub fn div_by_zero_result<T>(mode: DivByZero, fast: T) -> T {
+	match mode {
+		DivByZero::Fast => fast,
+		DivByZero::Trap => panic!("trap: {}", TrapKind::DivByZero),
+	}
 }
 
 impl SsaInterpreter {
@@ -180,10 +193,13 @@ impl SsaInterpreter {
 			globals: program.globals,
 			memory: program.memory,
 			tables: program.tables,
+			elem_segments: program.elem_segments,
+			snapshots: program.snapshot_regions.into_iter().map(|region| (region, vec![0; region.length as usize])).collect(),
 			program: code,
 			call_stack: CallStack(Vec::new()),
 			constants,
 			steps: 0,
+			div_by_zero: program.div_by_zero,
 		}
 	}
 
@@ -215,6 +231,8 @@ impl SsaInterpreter {
 	pub fn step(&mut self) -> Option<Vec<TypedValue>> {
 		//println!("{:?}", self.call_stack);
 
+		let div_by_zero = self.div_by_zero;
+
 		let frame = self.call_stack.last_mut().expect("stepped while halted");
 
 		let block = self.program.get(&frame.pc.block).unwrap();
@@ -229,7 +247,7 @@ impl SsaInterpreter {
 			//println!("{:?}", block.term);
 
 			match &block.term {
-				super::SsaTerminator::Unreachable => unreachable!(),
+				super::SsaTerminator::Unreachable => panic!("trap: {} in {:?}", TrapKind::Unreachable, frame.pc.block),
 				super::SsaTerminator::Jump(jump) |
 				super::SsaTerminator::ScheduleJump(jump, _) => {
 					let target_block = self.program.get(&jump.label).unwrap();
@@ -376,6 +394,120 @@ impl SsaInterpreter {
 				var_context.insert(dst.into_untyped(), result);
 			}
 
+			/// NaN results are canonicalized, like they are by the softfloat intrinsics.
+			fn do_float_binop(dst: TypedSsaVar, lhs: TypedSsaVar, rhs: TypedSsaVar, var_context: &mut VarContext, f: impl FnOnce(f32, f32) -> f32, g: impl FnOnce(f64, f64) -> f64) {
+				// Floats are stored as their bits
+				let l = var_context.get(lhs.into_untyped()).expect("lhs was uninit");
+				let r = var_context.get(rhs.into_untyped()).expect("rhs was uninit");
+
+				let result = match (dst.ty(), l, r) {
+					(ValType::F32, TypedValue::I32(l), TypedValue::I32(r)) => {
+						TypedValue::I32(f32_unaryop(l, |l| f(l, f32::from_bits(r as u32))))
+					}
+					(ValType::F64, TypedValue::I64(l), TypedValue::I64(r)) => {
+						TypedValue::I64(f64_unaryop(l, |l| g(l, f64::from_bits(r as u64))))
+					}
+					_ => panic!(),
+				};
+
+				var_context.insert(dst.into_untyped(), result);
+			}
+
+			fn do_float_unaryop(dst: TypedSsaVar, src: TypedSsaVar, var_context: &mut VarContext, f: impl FnOnce(i32) -> i32, g: impl FnOnce(i64) -> i64) {
+				// Floats are stored as their bits
+				let s = var_context.get(src.into_untyped()).expect("src was uninit");
+
+				let result = match (dst.ty(), s) {
+					(ValType::F32, TypedValue::I32(s)) => TypedValue::I32(f(s)),
+					(ValType::F64, TypedValue::I64(s)) => TypedValue::I64(g(s)),
+					_ => panic!(),
+				};
+
+				var_context.insert(dst.into_untyped(), result);
+			}
+
+			/// Unlike `f64::min`, NaN wins and -0 is less than +0
+			fn float_min(a: f64, b: f64) -> f64 {
+				if a.is_nan() || b.is_nan() {
+					f64::NAN
+				} else if a == b {
+					if a.is_sign_negative() { a } else { b }
+				} else {
+					a.min(b)
+				}
+			}
+
+			/// Unlike `f64::max`, NaN wins and +0 is greater than -0
+			fn float_max(a: f64, b: f64) -> f64 {
+				if a.is_nan() || b.is_nan() {
+					f64::NAN
+				} else if a == b {
+					if a.is_sign_positive() { a } else { b }
+				} else {
+					a.max(b)
+				}
+			}
+
+			/// Applies a float operation to the bits of a float.
+			/// NaN results are canonicalized, like they are by the softfloat intrinsics.
+			fn f32_unaryop(a: i32, f: impl FnOnce(f32) -> f32) -> i32 {
+				let result = f(f32::from_bits(a as u32));
+				if result.is_nan() {
+					0x7FC0_0000
+				} else {
+					result.to_bits() as i32
+				}
+			}
+
+			/// Applies a double operation to the bits of a double.
+			/// NaN results are canonicalized, like they are by the softfloat intrinsics.
+			fn f64_unaryop(a: i64, f: impl FnOnce(f64) -> f64) -> i64 {
+				let result = f(f64::from_bits(a as u64));
+				if result.is_nan() {
+					0x7FF8_0000_0000_0000
+				} else {
+					result.to_bits() as i64
+				}
+			}
+
+			fn do_trunc(dst: TypedSsaVar, src: TypedSsaVar, var_context: &mut VarContext, signed: bool) {
+				// Floats are stored as their bits, and every f32 is exactly representable as an f64
+				let s = var_context.get(src.into_untyped()).expect("src was uninit");
+				let s = match (src.ty(), s) {
+					(ValType::F32, TypedValue::I32(s)) => f32::from_bits(s as u32) as f64,
+					(ValType::F64, TypedValue::I64(s)) => f64::from_bits(s as u64),
+					_ => panic!(),
+				};
+
+				// `as` saturates and turns NaN into 0, which is exactly what the saturating variants need
+				let result = match (dst.ty(), signed) {
+					(ValType::I32, true) => TypedValue::I32(s as i32),
+					(ValType::I32, false) => TypedValue::I32(s as u32 as i32),
+					(ValType::I64, true) => TypedValue::I64(s as i64),
+					(ValType::I64, false) => TypedValue::I64(s as u64 as i64),
+					_ => panic!(),
+				};
+
+				var_context.insert(dst.into_untyped(), result);
+			}
+
+			fn do_convert(dst: TypedSsaVar, src: TypedSsaVar, var_context: &mut VarContext, signed: bool) {
+				let s = match (var_context.get_typed(src).expect("src was uninit"), signed) {
+					(TypedValue::I32(s), true) => s as i128,
+					(TypedValue::I32(s), false) => s as u32 as i128,
+					(TypedValue::I64(s), true) => s as i128,
+					(TypedValue::I64(s), false) => s as u64 as i128,
+				};
+
+				let result = match dst.ty() {
+					ValType::F32 => TypedValue::I32((s as f32).to_bits() as i32),
+					ValType::F64 => TypedValue::I64((s as f64).to_bits() as i64),
+					_ => panic!(),
+				};
+
+				var_context.insert(dst.into_untyped(), result);
+			}
+
 			fn do_unaryop(dst: TypedSsaVar, src: TypedSsaVar, var_context: &mut VarContext, f: impl FnOnce(i32) -> i32, g: impl FnOnce(i64) -> i64) {
 				let s = var_context.get_typed(src).expect("src was uninit");
 
@@ -453,6 +585,28 @@ impl SsaInterpreter {
 				var_context.insert(dst.into_untyped(), result);
 			}
 
+			fn buf_addr(ptr: TypedSsaVar, idx: TypedSsaVar, size: usize, var_context: &VarContext) -> usize {
+				let ptr = var_context.get_typed(ptr).unwrap().into_i32().unwrap();
+				let idx = var_context.get_typed(idx).unwrap().into_i32().unwrap();
+				ptr.wrapping_add(idx.wrapping_mul(size as i32)) as u32 as usize
+			}
+
+			fn do_buf_get(dst: TypedSsaVar, ptr: TypedSsaVar, idx: TypedSsaVar, size: usize, var_context: &mut VarContext, memory: &mut [Memory]) {
+				let addr = buf_addr(ptr, idx, size, var_context);
+
+				let mut buf = [0; 4];
+				buf[..size].copy_from_slice(memory[0].load(addr, size));
+
+				var_context.insert(dst.into_untyped(), TypedValue::I32(i32::from_le_bytes(buf)));
+			}
+
+			fn do_buf_set(ptr: TypedSsaVar, idx: TypedSsaVar, src: TypedSsaVar, size: usize, var_context: &mut VarContext, memory: &mut [Memory]) {
+				let addr = buf_addr(ptr, idx, size, var_context);
+
+				let src = var_context.get_typed(src).unwrap().into_i32().unwrap();
+				memory[0].store(addr, &src.to_le_bytes()[..size]);
+			}
+
 			match &block.body[frame.pc.instr] {
 				&super::SsaInstr::I32Set(dst, val) => {
 					assert_eq!(dst.ty(), ValType::I32);
@@ -472,10 +626,22 @@ impl SsaInterpreter {
 				&super::SsaInstr::Sub(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context, i32::wrapping_sub, i64::wrapping_sub),
 				&super::SsaInstr::Mul(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context, i32::wrapping_mul, i64::wrapping_mul),
 				// TODO: Test behavior with negative numbers
-				&super::SsaInstr::DivS(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| a / b, |a, b| a / b),
-				&super::SsaInstr::DivU(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| (a as u32 / b as u32) as i32, |a, b| (a as u64 / b as u64) as i64),
-				&super::SsaInstr::RemS(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context, i32::wrapping_rem, i64::wrapping_rem),
-				&super::SsaInstr::RemU(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| (a as u32 % b as u32) as i32, |a, b| (a as u64 % b as u64) as i64),
+				&super::SsaInstr::DivS(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context,
+					|a, b| if b == 0 { div_by_zero_result(div_by_zero, -1) } else { a / b },
+					|a, b| if b == 0 { div_by_zero_result(div_by_zero, -1) } else { a / b },
+				),
+				&super::SsaInstr::DivU(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context,
+					|a, b| if b == 0 { div_by_zero_result(div_by_zero, -1) } else { (a as u32 / b as u32) as i32 },
+					|a, b| if b == 0 { div_by_zero_result(div_by_zero, -1) } else { (a as u64 / b as u64) as i64 },
+				),
+				&super::SsaInstr::RemS(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context,
+					|a, b| if b == 0 { div_by_zero_result(div_by_zero, a) } else { a.wrapping_rem(b) },
+					|a, b| if b == 0 { div_by_zero_result(div_by_zero, a) } else { a.wrapping_rem(b) },
+				),
+				&super::SsaInstr::RemU(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context,
+					|a, b| if b == 0 { div_by_zero_result(div_by_zero, a) } else { (a as u32 % b as u32) as i32 },
+					|a, b| if b == 0 { div_by_zero_result(div_by_zero, a) } else { (a as u64 % b as u64) as i64 },
+				),
 				&super::SsaInstr::Shl(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| a << b.rem_euclid(32), |a, b| a << b.rem_euclid(64)),
 				&super::SsaInstr::ShrS(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| a >> b.rem_euclid(32), |a, b| a >> b.rem_euclid(64)),
 				&super::SsaInstr::ShrU(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| ((a as u32) >> (b as u32).rem_euclid(32)) as i32, |a, b| ((a as u64) >> (b as u64).rem_euclid(64)) as i64),
@@ -496,6 +662,22 @@ impl SsaInterpreter {
 				&super::SsaInstr::Eq(dst, lhs, rhs) => do_compare_op(dst, lhs, rhs, &mut frame.var_context, |a, b| a == b, |a, b| a == b),
 				&super::SsaInstr::Ne(dst, lhs, rhs) => do_compare_op(dst, lhs, rhs, &mut frame.var_context, |a, b| a != b, |a, b| a != b),
 
+				&super::SsaInstr::FAdd(dst, lhs, rhs) => do_float_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| a + b, |a, b| a + b),
+				&super::SsaInstr::FSub(dst, lhs, rhs) => do_float_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| a - b, |a, b| a - b),
+				&super::SsaInstr::FMul(dst, lhs, rhs) => do_float_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| a * b, |a, b| a * b),
+				&super::SsaInstr::FDiv(dst, lhs, rhs) => do_float_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| a / b, |a, b| a / b),
+				&super::SsaInstr::FMin(dst, lhs, rhs) => do_float_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| float_min(a as f64, b as f64) as f32, float_min),
+				&super::SsaInstr::FMax(dst, lhs, rhs) => do_float_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| float_max(a as f64, b as f64) as f32, float_max),
+				&super::SsaInstr::FCopysign(dst, lhs, rhs) => do_binop(dst, lhs, rhs, &mut frame.var_context, |a, b| (a & i32::MAX) | (b & i32::MIN), |a, b| (a & i64::MAX) | (b & i64::MIN)),
+
+				&super::SsaInstr::FNeg(dst, src) => do_float_unaryop(dst, src, &mut frame.var_context, |a| a ^ i32::MIN, |a| a ^ i64::MIN),
+				&super::SsaInstr::FAbs(dst, src) => do_float_unaryop(dst, src, &mut frame.var_context, |a| a & i32::MAX, |a| a & i64::MAX),
+				&super::SsaInstr::Sqrt(dst, src) => do_float_unaryop(dst, src, &mut frame.var_context, |a| f32_unaryop(a, f32::sqrt), |a| f64_unaryop(a, f64::sqrt)),
+				&super::SsaInstr::FFloor(dst, src) => do_float_unaryop(dst, src, &mut frame.var_context, |a| f32_unaryop(a, f32::floor), |a| f64_unaryop(a, f64::floor)),
+				&super::SsaInstr::FCeil(dst, src) => do_float_unaryop(dst, src, &mut frame.var_context, |a| f32_unaryop(a, f32::ceil), |a| f64_unaryop(a, f64::ceil)),
+				&super::SsaInstr::FTrunc(dst, src) => do_float_unaryop(dst, src, &mut frame.var_context, |a| f32_unaryop(a, f32::trunc), |a| f64_unaryop(a, f64::trunc)),
+				&super::SsaInstr::FNearest(dst, src) => do_float_unaryop(dst, src, &mut frame.var_context, |a| f32_unaryop(a, f32::round_ties_even), |a| f64_unaryop(a, f64::round_ties_even)),
+
 				&super::SsaInstr::Popcnt(dst, src) => do_unaryop(dst, src, &mut frame.var_context, |a| a.count_ones() as i32, |a| a.count_ones() as i64),
 				// TODO: Determine behavior when it is entirely zeros
 				&super::SsaInstr::Clz(dst, src) => do_unaryop(dst, src, &mut frame.var_context, |a| a.leading_zeros() as i32, |a| a.leading_zeros() as i64),
@@ -527,6 +709,11 @@ impl SsaInterpreter {
 				&super::SsaInstr::Store16(memarg, src, addr) => do_store_op(memarg, src, addr, 2, &mut frame.var_context, &mut self.memory),
 				&super::SsaInstr::Store8(memarg, src, addr) => do_store_op(memarg, src, addr, 1, &mut frame.var_context, &mut self.memory),
 
+				&super::SsaInstr::BufGetU8(dst, ptr, idx) => do_buf_get(dst, ptr, idx, 1, &mut frame.var_context, &mut self.memory),
+				&super::SsaInstr::BufGetU16(dst, ptr, idx) => do_buf_get(dst, ptr, idx, 2, &mut frame.var_context, &mut self.memory),
+				&super::SsaInstr::BufSetU8(ptr, idx, src) => do_buf_set(ptr, idx, src, 1, &mut frame.var_context, &mut self.memory),
+				&super::SsaInstr::BufSetU16(ptr, idx, src) => do_buf_set(ptr, idx, src, 2, &mut frame.var_context, &mut self.memory),
+
 				&super::SsaInstr::GlobalSet(dst, src) => {
 					let src = frame.var_context.get_typed(src).unwrap();
 					let dst = &mut self.globals[dst as usize];
@@ -566,6 +753,18 @@ impl SsaInterpreter {
 					};
 				}
 
+				&super::SsaInstr::TruncS(dst, src) => do_trunc(dst, src, &mut frame.var_context, true),
+				&super::SsaInstr::TruncU(dst, src) => do_trunc(dst, src, &mut frame.var_context, false),
+				&super::SsaInstr::TruncSatS(dst, src) => do_trunc(dst, src, &mut frame.var_context, true),
+				&super::SsaInstr::TruncSatU(dst, src) => do_trunc(dst, src, &mut frame.var_context, false),
+				&super::SsaInstr::ConvertS(dst, src) => do_convert(dst, src, &mut frame.var_context, true),
+				&super::SsaInstr::ConvertU(dst, src) => do_convert(dst, src, &mut frame.var_context, false),
+				&super::SsaInstr::Reinterpret(dst, src) => {
+					// Floats are stored as their bits, so the value doesn't change
+					let val = frame.var_context.get(src.into_untyped()).expect("src was uninit");
+					frame.var_context.insert(dst.into_untyped(), val);
+				}
+
 				&super::SsaInstr::Select { dst, true_var, false_var, cond } => {
 					let true_val = true_var.eval(&frame.var_context).unwrap();
 					let false_val = false_var.eval(&frame.var_context).unwrap();
@@ -612,9 +811,12 @@ impl SsaInterpreter {
 					incr_pc = false;
 
 					let table_entry = frame.var_context.get_typed(*table_entry).unwrap();
-					let table_entry = table_entry.into_i32().unwrap() as usize;
+					let table_entry = table_entry.into_i32().unwrap();
 
-					let function_index = self.tables[*table_index as usize].elements[table_entry].unwrap();
+					let function_index = usize::try_from(table_entry).ok()
+						.and_then(|idx| self.tables[*table_index as usize].elements.get(idx).copied())
+						.unwrap_or_else(|| panic!("trap: {} at index {table_entry}", TrapKind::TableOutOfBounds))
+						.unwrap_or_else(|| panic!("trap: {} at index {table_entry}", TrapKind::UninitializedElement));
 
 					let local_tys = self.local_types.get(&(function_index)).unwrap();
 
@@ -637,43 +839,208 @@ impl SsaInterpreter {
 
 				}
 
-				super::SsaInstr::Memset { dest, value, length, result } => {
+				super::SsaInstr::Memset { memory, dest, value, length, result } => {
 					let dest = dest.eval(&frame.var_context).unwrap().into_i32().unwrap();
 					let value = value.eval(&frame.var_context).unwrap().into_i32().unwrap();
 					let length = length.eval(&frame.var_context).unwrap().into_i32().unwrap();
 
-					if length < 0 {
-						panic!();
-					}
+					let memory = &mut self.memory[*memory as usize];
 
-					for i in 0..length {
-						self.memory[0].data[dest as usize + i as usize] = value as u8;
+					// Both are unsigned, and out-of-bounds fills trap even if the length is zero
+					let (dest, length) = (dest as u32 as usize, length as u32 as usize);
+					if dest + length > memory.data.len() {
+						panic!("out of bounds memory fill of {length} bytes at {dest}");
 					}
 
+					memory.data[dest..][..length].fill(value as u8);
+
 					frame.var_context.insert(result.into_untyped(), dest.into());
 				}
+				super::SsaInstr::Memcpy { memory, dest, src, length } => {
+					let dest = dest.eval(&frame.var_context).unwrap().into_i32().unwrap();
+					let src = src.eval(&frame.var_context).unwrap().into_i32().unwrap();
+					let length = length.eval(&frame.var_context).unwrap().into_i32().unwrap();
+
+					let memory = &mut self.memory[*memory as usize];
+
+					// All unsigned, and out-of-bounds copies trap even if the length is zero
+					let (dest, src, length) = (dest as u32 as usize, src as u32 as usize, length as u32 as usize);
+					if dest.max(src) + length > memory.data.len() {
+						panic!("out of bounds memory copy of {length} bytes from {src} to {dest}");
+					}
+
+					memory.data.copy_within(src..src + length, dest);
+				}
+
+				super::SsaInstr::MemoryGrow { memory, pages, result } => {
+					let pages = pages.eval(&frame.var_context).unwrap().into_i32().unwrap();
+
+					let old_pages = self.memory[*memory as usize].grow(pages as u32 as usize).map_or(-1, |p| p as i32);
+
+					frame.var_context.insert(result.into_untyped(), old_pages.into());
+				}
+				super::SsaInstr::MemorySize(memory, dst) => {
+					let pages = self.memory[*memory as usize].num_pages() as i32;
+					frame.var_context.insert(dst.into_untyped(), pages.into());
+				}
+
+				super::SsaInstr::RefNull(dst) => {
+					frame.var_context.insert(dst.into_untyped(), 0.into());
+				}
+				&super::SsaInstr::RefFunc(dst, func_idx) => {
+					frame.var_context.insert(dst.into_untyped(), (func_idx as i32 + 1).into());
+				}
+				super::SsaInstr::TableGet { table_index, dst, index } => {
+					let index = index.eval(&frame.var_context).unwrap().into_i32().unwrap() as u32 as usize;
+
+					let elements = &self.tables[*table_index as usize].elements;
+					let elem = *elements.get(index).unwrap_or_else(|| panic!("out of bounds table access at {index}"));
+
+					frame.var_context.insert(dst.into_untyped(), elem.map_or(0, |f| f as i32 + 1).into());
+				}
+				super::SsaInstr::TableSet { table_index, index, value } => {
+					let index = index.eval(&frame.var_context).unwrap().into_i32().unwrap() as u32 as usize;
+					let value = frame.var_context.get(value.into_untyped()).unwrap().into_i32().unwrap();
+
+					let elements = &mut self.tables[*table_index as usize].elements;
+					let elem = elements.get_mut(index).unwrap_or_else(|| panic!("out of bounds table access at {index}"));
+
+					*elem = if value == 0 { None } else { Some(value as usize - 1) };
+				}
+				super::SsaInstr::TableGrow { table_index, init, count, result } => {
+					let init = frame.var_context.get(init.into_untyped()).unwrap().into_i32().unwrap();
+					let count = count.eval(&frame.var_context).unwrap().into_i32().unwrap() as u32 as usize;
+
+					let init = if init == 0 { None } else { Some(init as usize - 1) };
+					let old_size = self.tables[*table_index as usize].grow(count, init).map_or(-1, |s| s as i32);
+
+					frame.var_context.insert(result.into_untyped(), old_size.into());
+				}
+				super::SsaInstr::TableSize { table_index, dst } => {
+					let size = self.tables[*table_index as usize].elements.len() as i32;
+					frame.var_context.insert(dst.into_untyped(), size.into());
+				}
+				super::SsaInstr::TableInit { table_index, segment, dst, src, length } => {
+					let dst = dst.eval(&frame.var_context).unwrap().into_i32().unwrap() as u32 as usize;
+					let src = src.eval(&frame.var_context).unwrap().into_i32().unwrap() as u32 as usize;
+					let length = length.eval(&frame.var_context).unwrap().into_i32().unwrap() as u32 as usize;
+
+					let segment = &self.elem_segments[*segment as usize];
+					let elements = &mut self.tables[*table_index as usize].elements;
+					if src + length > segment.len() || dst + length > elements.len() {
+						panic!("out of bounds table access initializing {length} entries from {src} to {dst}");
+					}
+
+					elements[dst..][..length].copy_from_slice(&segment[src..][..length]);
+				}
+				&super::SsaInstr::ElemDrop(segment) => {
+					self.elem_segments[segment as usize].clear();
+				}
 
 				super::SsaInstr::TurtleSetX(_) |
 				super::SsaInstr::TurtleSetY(_) |
 				super::SsaInstr::TurtleSetZ(_) |
 				super::SsaInstr::TurtleSetBlock(_) |
+				super::SsaInstr::TurtleSetBlockNbt { .. } |
 				super::SsaInstr::TurtleFillBlock { .. } |
 				super::SsaInstr::TurtleCopyRegion { .. } |
 				super::SsaInstr::TurtlePasteRegionMasked { .. } |
 				super::SsaInstr::TurtleCopy |
-				super::SsaInstr::TurtlePaste => {}
+				super::SsaInstr::TurtlePaste |
+				super::SsaInstr::TurtleFace { .. } |
+				super::SsaInstr::TurtleForward(_) |
+				super::SsaInstr::TurtleTurn { .. } |
+				super::SsaInstr::PlaySound { .. } |
+				super::SsaInstr::Particle { .. } |
+				super::SsaInstr::BossbarCreate(_) |
+				super::SsaInstr::BossbarSetValue(_, _) |
+				super::SsaInstr::BossbarSetMax(_, _) |
+				super::SsaInstr::BossbarSetName(_, _, _) |
+				super::SsaInstr::RawCommand(_) => {}
 
 				super::SsaInstr::PrintInt(v) => {
 					let value = v.eval(&frame.var_context).unwrap().into_i32().unwrap();
 					println!("{:?}", value);
 				}
+				super::SsaInstr::PrintLong(v) => {
+					let value = v.eval(&frame.var_context).unwrap().into_i64().unwrap();
+					println!("{:?}", value);
+				}
+				super::SsaInstr::PrintFloat(v) => {
+					// Floats are stored as their bits
+					let value = match (v.ty(), frame.var_context.get(v.into_untyped()).unwrap()) {
+						(ValType::F32, TypedValue::I32(v)) => f32::from_bits(v as u32) as f64,
+						(ValType::F64, TypedValue::I64(v)) => f64::from_bits(v as u64),
+						_ => panic!(),
+					};
+					println!("{}", super::format_float(value));
+				}
+				super::SsaInstr::WasiFdWrite { fd, iovs, iovs_len, nwritten, result } => {
+					let fd = fd.eval(&frame.var_context).unwrap().into_i32().unwrap();
+					let iovs = iovs.eval(&frame.var_context).unwrap().into_i32().unwrap();
+					let iovs_len = iovs_len.eval(&frame.var_context).unwrap().into_i32().unwrap();
+					let nwritten = nwritten.eval(&frame.var_context).unwrap().into_i32().unwrap();
+
+					let (output, errno) = self.memory[0].wasi_fd_write(fd, iovs, iovs_len, nwritten);
+					print!("{}", String::from_utf8_lossy(&output));
+
+					frame.var_context.insert(result.into_untyped(), errno.into());
+				}
+				super::SsaInstr::PrintStr(ptr, len) |
+				super::SsaInstr::Title { ptr, len, .. } => {
+					let ptr = ptr.eval(&frame.var_context).unwrap().into_i32().unwrap();
+					let len = len.eval(&frame.var_context).unwrap().into_i32().unwrap();
+
+					let bytes = self.memory[0].load(ptr as u32 as usize, len as u32 as usize);
+					println!("{}", String::from_utf8_lossy(bytes).trim_end_matches('\n'));
+				}
+				super::SsaInstr::PrintFmt(fmt, args) => {
+					let fmt = fmt.eval(&frame.var_context).unwrap().into_i32().unwrap();
+					let args = args.eval(&frame.var_context).unwrap().into_i32().unwrap();
+
+					let output = self.memory[0].print_fmt(fmt, args);
+					println!("{}", String::from_utf8_lossy(&output).trim_end_matches('\n'));
+				}
+				super::SsaInstr::MemorySnapshot(id) => {
+					let id = id.eval(&frame.var_context).unwrap().into_i32().unwrap();
+					// Like in the datapack, IDs without a region do nothing
+					if let Some((region, saved)) = self.snapshots.get_mut(id as u32 as usize) {
+						saved.copy_from_slice(self.memory[0].load(region.start as usize, region.length as usize));
+					}
+				}
+				super::SsaInstr::MemoryRestore(id) => {
+					let id = id.eval(&frame.var_context).unwrap().into_i32().unwrap();
+					if let Some((region, saved)) = self.snapshots.get(id as u32 as usize) {
+						self.memory[0].store(region.start as usize, saved);
+					}
+				}
 				super::SsaInstr::PutChar(_) |
 				super::SsaInstr::WasiProcExit(_) => todo!(),
-				super::SsaInstr::Todo(msg) => todo!("{}", msg),
-
-				super::SsaInstr::TurtleGetBlock(dst) => {
+				super::SsaInstr::Todo(msg) => panic!("trap: {}", TrapKind::Todo(msg)),
+
+				super::SsaInstr::TurtleGetBlock(dst) |
+				super::SsaInstr::TurtleRaycast { dst, .. } |
+				super::SsaInstr::TurtleLightLevel(dst) |
+				super::SsaInstr::TurtleCheckBlock { dst, .. } |
+				super::SsaInstr::NearestPlayerPos { dst, .. } |
+				super::SsaInstr::EntityCount { dst, .. } |
+				super::SsaInstr::Random { dst, .. } |
+				super::SsaInstr::RawCommandStore { dst, .. } => {
 					frame.var_context.insert(dst.into_untyped(), 0.into());
 				}
+				super::SsaInstr::PackCall { returns, .. } => {
+					// Other datapacks aren't simulated, so their exports always return zero
+					for ret in returns.iter() {
+						let zero = match ret.ty() {
+							ValType::I64 | ValType::F64 => TypedValue::I64(0),
+							_ => TypedValue::I32(0),
+						};
+						frame.var_context.insert(ret.into_untyped(), zero);
+					}
+				}
+				super::SsaInstr::GameTime(dst) => {
+					frame.var_context.insert(dst.into_untyped(), TypedValue::I64(0));
+				}
 
 
 				/*super::SsaInstr::TurtleSetX(_) |
@@ -1,23 +1,31 @@
-use std::{collections::{HashSet, HashMap}, fmt};
+use std::{collections::{BTreeSet, HashSet, HashMap}, fmt};
 
 use wasmparser::{MemoryImmediate, ValType};
 
-use crate::{lir::{Register, LirInstr, DoubleRegister, LirBasicBlock, LirProgram, LirFunction, LirTerminator, Condition, Half, LirJumpTarget}, ssa::{TypedSsaVar, SsaVarOrConst, liveness::{FullLivenessInfo, DomTree}, const_prop::{StaticState, self}}, jump_mode, JumpMode, CompileContext, block_id_map::LocalBlockMap};
+use crate::{lir::{Register, LirInstr, DoubleRegister, LirBasicBlock, LirProgram, LirFunction, LirTerminator, Condition, Half, LirJumpTarget}, ssa::{TypedSsaVar, SsaVarOrConst, liveness::{FullLivenessInfo, DomTree}, const_prop::{StaticState, self}}, CompileContext, block_id_map::LocalBlockMap, remarks::Remarks};
 
-use super::{SsaProgram, SsaFunction, SsaBasicBlock, BlockId, reg_alloc::*, liveness::{LivenessInfo}, call_graph::CallGraph, Table, const_prop::StaticValue, interp::TypedValue};
+use super::{SsaProgram, SsaFunction, SsaBasicBlock, BlockId, reg_alloc::*, liveness::{LivenessInfo}, call_graph::CallGraph, Table, const_prop::StaticValue, interp::TypedValue, TrapKind};
 
 
 struct LirFuncBuilder {
-	used_ids: HashSet<BlockId>,
+	func: usize,
+	used_ids: LocalBlockMap<()>,
+	/// All block indices below this one are known to be used
+	next_free: usize,
 	body: Vec<(BlockId, LirBasicBlock)>
 }
 
 impl LirFuncBuilder {
 	pub fn new(ssa_func: &SsaFunction) -> Self {
-		let used_ids = ssa_func.iter().map(|(id, _)| id).collect();
+		let mut used_ids = LocalBlockMap::new(ssa_func.func_id() as usize);
+		for (id, _) in ssa_func.iter() {
+			used_ids.insert(id, ());
+		}
 
 		LirFuncBuilder {
+			func: ssa_func.func_id() as usize,
 			used_ids,
+			next_free: 0,
 			body: Vec::new(),
 		}
 	}
@@ -31,30 +39,20 @@ impl LirFuncBuilder {
 	}
 
 	pub fn func(&self) -> usize {
-		let func = self.used_ids.iter().next().unwrap().func;
-
-		assert!(self.used_ids.iter().all(|id| id.func == func));
-
-		func
+		self.func
 	}
 
 	pub fn alloc_block_id(&mut self) -> BlockId {
-		let mut i = 0;
-		loop {
-			if self.used_ids.iter().all(|id| id.block != i) {
-				let block_id = BlockId { func: self.func(), block: i };
-				self.used_ids.insert(block_id);
-				return block_id;
-			}
-
-			i += 1;
-		}
+		let block_id = self.used_ids.first_vacant_from(self.next_free);
+		self.used_ids.insert(block_id, ());
+		self.next_free = block_id.block + 1;
+		block_id
 	}
 }
 
 /// Filters in only the functions from the table that have types compatible with the given parameters and returns.
 pub fn get_compatible_functions<'a>(parent: &'a SsaProgram, table: &'a Table, params: &'a [TypedSsaVar], returns: &'a [TypedSsaVar]) -> impl Iterator<Item=Option<usize>> + Clone + 'a {
-	table.elements.iter().map(|func_idx| func_idx.filter(|func_idx| {
+	table.call_targets().into_iter().map(move |func_idx| func_idx.filter(|func_idx| {
 		let mut is_compat = true;
 		let func = parent.get_func(*func_idx as u32);
 
@@ -114,6 +112,9 @@ fn lower_block<L>(
 	builder: &mut LirFuncBuilder,
 	static_values: &HashMap<TypedSsaVar, StaticValue>,
 	dom_tree: &DomTree,
+	yield_in_loops: bool,
+	call_indirect_checks: bool,
+	remarks: &Remarks,
 )
 	where L: LivenessInfo
 {
@@ -139,13 +140,21 @@ fn lower_block<L>(
 		}
 	}
 
-	fn do_shiftop<F, G, R>(dst: TypedSsaVar, lhs: TypedSsaVar, rhs: R, block: &mut Vec<LirInstr>, ra: &mut dyn RegAlloc, f: F, g: G)
+	#[allow(clippy::too_many_arguments)]
+	fn do_shiftop<F, G, R>(dst: TypedSsaVar, lhs: TypedSsaVar, rhs: R, block: &mut Vec<LirInstr>, ra: &mut dyn RegAlloc, static_values: &StaticState, f: F, g: G)
 		where
 			F: FnOnce(Register, Register, Register) -> LirInstr,
 			G: FnOnce(DoubleRegister, DoubleRegister, DoubleRegister) -> LirInstr,
 			R: Into<SsaVarOrConst>,
 	{
-		let rhs = rhs.into();
+		// Rotate counts are always variables, but a known count still gets the cheaper constant shift
+		let rhs = match rhs.into() {
+			SsaVarOrConst::Var(v) => match static_values.get(&v) {
+				Some(&StaticValue::Constant(c)) => SsaVarOrConst::Const(c),
+				_ => SsaVarOrConst::Var(v),
+			},
+			rhs => rhs,
+		};
 
 		assert_eq!(dst.ty(), lhs.ty());
 		assert_eq!(lhs.ty(), rhs.ty());
@@ -188,6 +197,158 @@ fn lower_block<L>(
 		}
 	}
 
+	fn do_floatop<F, G>(dst: TypedSsaVar, lhs: TypedSsaVar, rhs: TypedSsaVar, block: &mut Vec<LirInstr>, ra: &mut dyn RegAlloc, f: F, g: G)
+		where
+			F: FnOnce(Register, Register, Register) -> LirInstr,
+			G: FnOnce(DoubleRegister, DoubleRegister, DoubleRegister) -> LirInstr,
+	{
+		assert_eq!(dst.ty(), lhs.ty());
+		assert_eq!(lhs.ty(), rhs.ty());
+
+		match dst.ty() {
+			ValType::F32 => block.push(f(ra.get(dst.into_untyped()), ra.get(lhs.into_untyped()), ra.get(rhs.into_untyped()))),
+			ValType::F64 => block.push(g(ra.get_double(dst.into_untyped()), ra.get_double(lhs.into_untyped()), ra.get_double(rhs.into_untyped()))),
+			t => panic!("float operations can't take {t:?} values"),
+		}
+	}
+
+	fn do_float_unaryop<F, G>(dst: TypedSsaVar, src: TypedSsaVar, block: &mut Vec<LirInstr>, ra: &mut dyn RegAlloc, f: F, g: G)
+		where
+			F: FnOnce(Register, Register) -> LirInstr,
+			G: FnOnce(DoubleRegister, DoubleRegister) -> LirInstr,
+	{
+		assert_eq!(dst.ty(), src.ty());
+
+		match dst.ty() {
+			ValType::F32 => block.push(f(ra.get(dst.into_untyped()), ra.get(src.into_untyped()))),
+			ValType::F64 => block.push(g(ra.get_double(dst.into_untyped()), ra.get_double(src.into_untyped()))),
+			t => panic!("float operations can't take {t:?} values"),
+		}
+	}
+
+	/// Returns the registers holding the sign bits of a float operation's dst and src,
+	/// copying over the low word first if they are doubles.
+	fn sign_words(dst: TypedSsaVar, src: TypedSsaVar, block: &mut Vec<LirInstr>, ra: &mut dyn RegAlloc) -> (Register, Register) {
+		assert_eq!(dst.ty(), src.ty());
+
+		match dst.ty() {
+			ValType::F32 => (ra.get(dst.into_untyped()), ra.get(src.into_untyped())),
+			ValType::F64 => {
+				let dst = ra.get_double(dst.into_untyped());
+				let src = ra.get_double(src.into_untyped());
+				block.push(LirInstr::Assign(dst.lo(), src.lo()));
+				(dst.hi(), src.hi())
+			}
+			t => panic!("only floats have a sign bit to change, not {t:?} values"),
+		}
+	}
+
+	fn do_convert(dst: TypedSsaVar, src: TypedSsaVar, signed: bool, block: &mut Vec<LirInstr>, ra: &mut dyn RegAlloc) {
+		// The intrinsics always take 64 bits, so 32-bit integers are extended first
+		let src = match src.ty() {
+			ValType::I32 => {
+				let temp = ra.get_temp_double();
+				block.push(LirInstr::Assign(temp.lo(), ra.get(src.into_untyped())));
+				if signed {
+					block.push(LirInstr::SignExtend32(temp));
+				} else {
+					block.push(LirInstr::Set(temp.hi(), 0));
+				}
+				temp
+			}
+			ValType::I64 => ra.get_double(src.into_untyped()),
+			t => panic!("only integers can be converted to floats, not {t:?} values"),
+		};
+
+		let instr = match (dst.ty(), signed) {
+			(ValType::F32, true) => LirInstr::F32FromS64(ra.get(dst.into_untyped()), src),
+			(ValType::F32, false) => LirInstr::F32FromU64(ra.get(dst.into_untyped()), src),
+			(ValType::F64, true) => LirInstr::F64FromS64(ra.get_double(dst.into_untyped()), src),
+			(ValType::F64, false) => LirInstr::F64FromU64(ra.get_double(dst.into_untyped()), src),
+			(t, _) => panic!("integers can only be converted to floats, not to {t:?}"),
+		};
+		block.push(instr);
+	}
+
+	fn do_trunc_sat(dst: TypedSsaVar, src: TypedSsaVar, signed: bool, block: &mut Vec<LirInstr>, ra: &mut dyn RegAlloc) {
+		// The wrapping truncation already gives 0 for NaN and infinity and is exact for values in range,
+		// so only the out-of-range values need to be clamped afterwards.
+		// The result goes into a temporary so the source is still intact while clamping.
+		let result = ra.get_temp_double();
+
+		// The bits of a float order the same way as its magnitude,
+		// so the word holding the sign and exponent is enough to tell if it's out of range.
+		let (word, lo, inf_word, to_word): (_, _, _, fn(f64) -> i32) = match src.ty() {
+			ValType::F32 => {
+				let src = ra.get(src.into_untyped());
+				block.push(LirInstr::F32TruncTo64(result, src));
+				(src, None, 0x7F80_0000, |v| (v as f32).to_bits() as i32)
+			}
+			ValType::F64 => {
+				let src = ra.get_double(src.into_untyped());
+				block.push(LirInstr::F64TruncTo64(result, src));
+				(src.hi(), Some(src.lo()), 0x7FF0_0000, |v| (v.to_bits() >> 32) as i32)
+			}
+			t => panic!("only floats can be truncated, not {t:?} values"),
+		};
+
+		let bits = match dst.ty() {
+			ValType::I32 => 32,
+			ValType::I64 => 64,
+			t => panic!("floats can only be truncated to integers, not to {t:?}"),
+		};
+
+		// The smallest magnitude that doesn't fit, and the values to clamp to
+		let (limit, max, min) = if signed {
+			(2.0f64.powi(bits - 1), i64::MAX >> (64 - bits), i64::MIN >> (64 - bits))
+		} else {
+			(2.0f64.powi(bits), -1, 0)
+		};
+
+		let set_result = |val: i64, cond: Condition, block: &mut Vec<LirInstr>| {
+			block.push(LirInstr::Set(result.lo(), val as i32).if_cond(cond.clone()));
+			block.push(LirInstr::Set(result.hi(), (val >> 32) as i32).if_cond(cond));
+		};
+
+		// Anything from the limit up to positive infinity is too large
+		set_result(max, Condition::Matches(word, to_word(limit)..=inf_word), block);
+
+		if signed {
+			// Anything from the negative limit down to negative infinity is too small.
+			// The negative limit itself truncates to the minimum, so including it is harmless.
+			set_result(min, Condition::Matches(word, to_word(-limit)..=(inf_word | i32::MIN)), block);
+		} else {
+			// Every negative number either truncates to 0 or is too small
+			set_result(min, Condition::Matches(word, i32::MIN..=-1), block);
+		}
+
+		// A double NaN can have the same high word as infinity, so those have to be set back to 0
+		if let Some(lo) = lo {
+			for nan_word in [inf_word, inf_word | i32::MIN] {
+				for reg in [result.lo(), result.hi()] {
+					block.push(LirInstr::Set(reg, 0)
+						.if_cond(Condition::neq_zero(lo))
+						.if_cond(Condition::eq_const(word, nan_word)));
+				}
+			}
+		}
+
+		match dst.ty() {
+			ValType::I32 => block.push(LirInstr::Assign(ra.get(dst.into_untyped()), result.lo())),
+			_ => {
+				let dst = ra.get_double(dst.into_untyped());
+				block.push(LirInstr::Assign(dst.lo(), result.lo()));
+				block.push(LirInstr::Assign(dst.hi(), result.hi()));
+			}
+		}
+	}
+
+	/// The buffer intrinsics skip finding the position in the word at runtime when the low bits of their operands are known
+	fn buf_operand(var: TypedSsaVar, ra: &mut dyn RegAlloc, static_values: &StaticState) -> RegisterWithInfo {
+		let info = static_values.get(&var).copied().unwrap_or(StaticValue::unknown());
+		RegisterWithInfo(ra.get(var.unwrap_i32()), info)
+	}
+
 	fn map_ra_i32(r: SsaVarOrConst, ra: &mut dyn RegAlloc) -> Register {
 		match r {
 			SsaVarOrConst::Var(v) => ra.get(v.unwrap_i32()),
@@ -247,10 +408,8 @@ fn lower_block<L>(
 
 	fn do_store<F>(mem: &MemoryImmediate, src: TypedSsaVar, addr2: SsaVarOrConst, block: &mut Vec<LirInstr>, ra: &mut dyn RegAlloc, static_values: &StaticState, f: F)
 		where
-			F: FnOnce(Register, RegisterWithInfo) -> LirInstr
+			F: FnOnce(u32, Register, RegisterWithInfo) -> LirInstr
 	{
-		assert_eq!(mem.memory, 0);
-
 		//assert_eq!(src.ty(), ValType::I32);
 		let src = ra.get(src.into_untyped());
 
@@ -259,7 +418,7 @@ fn lower_block<L>(
 		if let Some(c) = addr.get_const() {
 			let addr = c + mem.offset as i32;
 			let reg = RegisterWithInfo(ra.get_const(addr), addr.into());
-			block.push(f(src, reg));
+			block.push(f(mem.memory, src, reg));
 		} else {
 			// TODO: Coalescing?
 			let temp = Register::temp_lo(0);
@@ -272,14 +431,12 @@ fn lower_block<L>(
 
 			block.push(LirInstr::Assign(temp, addr));
 			block.push(LirInstr::Add(temp, ra.get_const(mem.offset as i32)));
-			block.push(f(src, RegisterWithInfo(temp, info)));
+			block.push(f(mem.memory, src, RegisterWithInfo(temp, info)));
 		}
 	}
 
 	fn do_load_trunc(mem: &MemoryImmediate, dst: TypedSsaVar, addr: SsaVarOrConst, bits: u32, signed: bool, block: &mut Vec<LirInstr>, ra: &mut dyn RegAlloc, static_values: &StaticState)
 	{
-		assert_eq!(mem.memory, 0);
-
 		let dst_lo = match dst.ty() {
 			ValType::I32 | ValType::F32 => ra.get(dst.into_untyped()),
 			ValType::I64 | ValType::F64 => ra.get_double(dst.into_untyped()).lo(),
@@ -314,16 +471,16 @@ fn lower_block<L>(
 
 		match bits {
 			32 => {
-				block.push(LirInstr::Load32(dst_lo, addr_reg));
+				block.push(LirInstr::Load32(mem.memory, dst_lo, addr_reg));
 			}
 			16 => {
-				block.push(LirInstr::Load16(dst_lo, addr_reg));
+				block.push(LirInstr::Load16(mem.memory, dst_lo, addr_reg));
 				if signed {
 					block.push(LirInstr::SignExtend16(dst_lo));
 				}
 			}
 			8 => {
-				block.push(LirInstr::Load8(dst_lo, addr_reg));
+				block.push(LirInstr::Load8(mem.memory, dst_lo, addr_reg));
 				if signed {
 					block.push(LirInstr::SignExtend8(dst_lo));
 				}
@@ -389,7 +546,7 @@ fn lower_block<L>(
 			&super::SsaInstr::Assign(lhs, rhs) => {
 				assert_eq!(lhs.ty(), rhs.ty());
 				match lhs.ty() {
-					ValType::I32 | ValType::F32 => {
+					ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 						let lhs = ra.get(lhs.into_untyped());
 						let rhs = map_ra_i32(rhs, ra);
 						block.push(LirInstr::Assign(lhs, rhs));
@@ -426,15 +583,14 @@ fn lower_block<L>(
 				let temp = ra.get_temp();
 
 				let i32_sub = |dst, lhs, rhs, block: &mut Vec<LirInstr>| {
-					if dst == lhs {
-						if dst == rhs {
-							println!("missed optimization opportunity or bug (subtracting a number from itself)");
-						}
-
+					if lhs == rhs {
+						// Subtracting a number from itself
+						block.push(LirInstr::Set(dst, 0));
+					} else if dst == lhs {
 						block.push(LirInstr::Sub(dst, rhs));
 					} else if dst == rhs {
-						// FIXME: Write a test for this and related functions.
 						// Previously, if (dst == rhs), rhs would be prematurely overwritten by the `dst := lhs` assignment.
+						// See `test::binop_aliasing`.
 
 						block.push(LirInstr::Assign(temp, lhs));
 						block.push(LirInstr::Sub(temp, rhs));
@@ -541,11 +697,11 @@ fn lower_block<L>(
 				do_binop(dst, lhs, rhs, &mut block, ra, i32_remu, i64_remu);
 			}
 
-			super::SsaInstr::Shl(dst, lhs, rhs) => do_shiftop(*dst, *lhs, *rhs, &mut block, ra, LirInstr::Shl, LirInstr::Shl64),
-			super::SsaInstr::ShrS(dst, lhs, rhs) => do_shiftop(*dst, *lhs, *rhs, &mut block, ra, LirInstr::ShrS, LirInstr::ShrS64),
-			super::SsaInstr::ShrU(dst, lhs, rhs) => do_shiftop(*dst, *lhs, *rhs, &mut block, ra, LirInstr::ShrU, LirInstr::ShrU64),
-			super::SsaInstr::Rotl(dst, lhs, rhs) => do_shiftop(*dst, *lhs, *rhs, &mut block, ra, LirInstr::Rotl, LirInstr::Rotl64),
-			super::SsaInstr::Rotr(dst, lhs, rhs) => do_shiftop(*dst, *lhs, *rhs, &mut block, ra, LirInstr::Rotr, LirInstr::Rotr64),
+			super::SsaInstr::Shl(dst, lhs, rhs) => do_shiftop(*dst, *lhs, *rhs, &mut block, ra, static_values, LirInstr::Shl, LirInstr::Shl64),
+			super::SsaInstr::ShrS(dst, lhs, rhs) => do_shiftop(*dst, *lhs, *rhs, &mut block, ra, static_values, LirInstr::ShrS, LirInstr::ShrS64),
+			super::SsaInstr::ShrU(dst, lhs, rhs) => do_shiftop(*dst, *lhs, *rhs, &mut block, ra, static_values, LirInstr::ShrU, LirInstr::ShrU64),
+			super::SsaInstr::Rotl(dst, lhs, rhs) => do_shiftop(*dst, *lhs, *rhs, &mut block, ra, static_values, LirInstr::Rotl, LirInstr::Rotl64),
+			super::SsaInstr::Rotr(dst, lhs, rhs) => do_shiftop(*dst, *lhs, *rhs, &mut block, ra, static_values, LirInstr::Rotr, LirInstr::Rotr64),
 
 			super::SsaInstr::Xor(dst, lhs, rhs) => do_bitwiseop(*dst, *lhs, *rhs, &mut block, ra, static_values, LirInstr::Xor),
 			super::SsaInstr::And(dst, lhs, rhs) => do_bitwiseop(*dst, *lhs, *rhs, &mut block, ra, static_values, LirInstr::And),
@@ -562,6 +718,45 @@ fn lower_block<L>(
 			super::SsaInstr::Eq(dst, lhs, rhs) => do_compareop(*dst, *lhs, *rhs, &mut block, ra, LirInstr::Eq, LirInstr::Eq64),
 			super::SsaInstr::Ne(dst, lhs, rhs) => do_compareop(*dst, *lhs, *rhs, &mut block, ra, LirInstr::Ne, LirInstr::Ne64),
 
+			&super::SsaInstr::FAdd(dst, lhs, rhs) => do_floatop(dst, lhs, rhs, &mut block, ra, LirInstr::F32Add, LirInstr::F64Add),
+			&super::SsaInstr::FSub(dst, lhs, rhs) => do_floatop(dst, lhs, rhs, &mut block, ra, LirInstr::F32Sub, LirInstr::F64Sub),
+			&super::SsaInstr::FMul(dst, lhs, rhs) => do_floatop(dst, lhs, rhs, &mut block, ra, LirInstr::F32Mul, LirInstr::F64Mul),
+			&super::SsaInstr::FDiv(dst, lhs, rhs) => do_floatop(dst, lhs, rhs, &mut block, ra, LirInstr::F32Div, LirInstr::F64Div),
+			&super::SsaInstr::FMin(dst, lhs, rhs) => do_floatop(dst, lhs, rhs, &mut block, ra, LirInstr::F32Min, LirInstr::F64Min),
+			&super::SsaInstr::FMax(dst, lhs, rhs) => do_floatop(dst, lhs, rhs, &mut block, ra, LirInstr::F32Max, LirInstr::F64Max),
+			&super::SsaInstr::FCopysign(dst, lhs, rhs) => {
+				// The sign of rhs is saved first, in case it shares a register with dst
+				let rhs_sign = match rhs.ty() {
+					ValType::F32 => ra.get(rhs.into_untyped()),
+					ValType::F64 => ra.get_double(rhs.into_untyped()).hi(),
+					t => panic!("only floats have a sign bit to copy, not {t:?} values"),
+				};
+				let sign = ra.get_temp();
+				block.push(LirInstr::Assign(sign, rhs_sign));
+
+				// Adding the sign bit flips it, so clear it and then set it again if rhs is negative
+				let (dst, lhs) = sign_words(dst, lhs, &mut block, ra);
+				block.push(LirInstr::Assign(dst, lhs));
+				block.push(LirInstr::Add(dst, ra.get_const(i32::MIN)).if_cond(Condition::Matches(dst, i32::MIN..=-1)));
+				block.push(LirInstr::Add(dst, ra.get_const(i32::MIN)).if_cond(Condition::Matches(sign, i32::MIN..=-1)));
+			}
+
+			&super::SsaInstr::FNeg(dst, src) => {
+				// Adding the sign bit flips it
+				let (dst, src) = sign_words(dst, src, &mut block, ra);
+				block.push(LirInstr::Assign(dst, src));
+				block.push(LirInstr::Add(dst, ra.get_const(i32::MIN)));
+			}
+			&super::SsaInstr::FAbs(dst, src) => {
+				let (dst, src) = sign_words(dst, src, &mut block, ra);
+				block.push(LirInstr::And(dst, src.into(), ra.get_const(i32::MAX).into()));
+			}
+			&super::SsaInstr::Sqrt(dst, src) => do_float_unaryop(dst, src, &mut block, ra, LirInstr::F32Sqrt, LirInstr::F64Sqrt),
+			&super::SsaInstr::FFloor(dst, src) => do_float_unaryop(dst, src, &mut block, ra, LirInstr::F32Floor, LirInstr::F64Floor),
+			&super::SsaInstr::FCeil(dst, src) => do_float_unaryop(dst, src, &mut block, ra, LirInstr::F32Ceil, LirInstr::F64Ceil),
+			&super::SsaInstr::FTrunc(dst, src) => do_float_unaryop(dst, src, &mut block, ra, LirInstr::F32Trunc, LirInstr::F64Trunc),
+			&super::SsaInstr::FNearest(dst, src) => do_float_unaryop(dst, src, &mut block, ra, LirInstr::F32Nearest, LirInstr::F64Nearest),
+
 			super::SsaInstr::Popcnt(dst, src) => {
 				assert_eq!(dst.ty(), src.ty());
 				match dst.ty() {
@@ -590,7 +785,8 @@ fn lower_block<L>(
 				let dst = ra.get(dst.into_untyped());
 
 				match src.ty() {
-					ValType::I32 | ValType::F32 => {
+					// Also used for `ref.is_null`, since null references are 0
+					ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 						let src = ra.get(src.into_untyped());
 						block.push(LirInstr::Eqz(dst, src));
 					}
@@ -603,8 +799,6 @@ fn lower_block<L>(
 			}
 
 			super::SsaInstr::Load64(mem, dst, addr) => {
-				assert_eq!(mem.memory, 0);
-
 				assert!(dst.ty() == ValType::I64 || dst.ty() == ValType::F64);
 				let dst = ra.get_double(dst.into_untyped());
 
@@ -616,15 +810,15 @@ fn lower_block<L>(
 
 					// TODO: Making this a Load64 could get better performance for unaligned loads.
 					// TODO: Analysis info
-					block.push(LirInstr::Load32(dst.lo(), addr_lo.into()));
-					block.push(LirInstr::Load32(dst.hi(), addr_hi.into()));
+					block.push(LirInstr::Load32(mem.memory, dst.lo(), addr_lo.into()));
+					block.push(LirInstr::Load32(mem.memory, dst.hi(), addr_hi.into()));
 				} else {
 					// TODO: Coalescing?
 					let temp = Register::temp_lo(0);
 
 					block.push(LirInstr::Assign(temp, addr));
 					block.push(LirInstr::Add(temp, ra.get_const(mem.offset as i32)));
-					block.push(LirInstr::Load64(dst, temp.into())); // TODO: Analysis info
+					block.push(LirInstr::Load64(mem.memory, dst, temp.into())); // TODO: Analysis info
 				}
 			}
 			super::SsaInstr::Load32S(mem, dst, addr) => do_load_trunc(mem, *dst, *addr, 32, true, &mut block, ra, static_values),
@@ -635,8 +829,6 @@ fn lower_block<L>(
 			super::SsaInstr::Load8U(mem, dst, addr) => do_load_trunc(mem, *dst, *addr, 8, false, &mut block, ra, static_values),
 
 			super::SsaInstr::Store64(mem, src, addr) => {
-				assert_eq!(mem.memory, 0);
-
 				assert!(src.ty() == ValType::I64 || src.ty() == ValType::F64);
 				let src = ra.get_double(src.into_untyped());
 
@@ -647,8 +839,8 @@ fn lower_block<L>(
 					let addr_lo = c + mem.offset as i32;
 					let addr_hi = c + mem.offset as i32 + 4;
 
-					block.push(LirInstr::Store32(src.lo(), ra.get_const(addr_lo).into()));
-					block.push(LirInstr::Store32(src.hi(), ra.get_const(addr_hi).into()));
+					block.push(LirInstr::Store32(mem.memory, src.lo(), ra.get_const(addr_lo).into()));
+					block.push(LirInstr::Store32(mem.memory, src.hi(), ra.get_const(addr_hi).into()));
 				} else {
 					// TODO: Coalescing?
 					let temp = Register::temp_lo(0);
@@ -656,12 +848,12 @@ fn lower_block<L>(
 					block.push(LirInstr::Assign(temp, addr));
 					block.push(LirInstr::Add(temp, ra.get_const(mem.offset as i32)));
 					// TODO: Info
-					block.push(LirInstr::Store32(src.lo(), temp.into()));
+					block.push(LirInstr::Store32(mem.memory, src.lo(), temp.into()));
 
 					block.push(LirInstr::Assign(temp, addr));
 					block.push(LirInstr::Add(temp, ra.get_const(mem.offset as i32 + 4)));
 					// TODO: Info
-					block.push(LirInstr::Store32(src.hi(), temp.into()));
+					block.push(LirInstr::Store32(mem.memory, src.hi(), temp.into()));
 				}
 			}
 			super::SsaInstr::Store32(mem, src, addr) => do_store(mem, *src, *addr, &mut block, ra, static_values, LirInstr::Store32),
@@ -670,26 +862,36 @@ fn lower_block<L>(
 
 			super::SsaInstr::GlobalSet(dst, src) => {
 				match src.ty() {
-					ValType::I32 => {
+					ValType::I32 | ValType::FuncRef | ValType::ExternRef => {
 						let reg = ra.get(src.into_untyped());
 						block.push(LirInstr::GlobalSet(*dst, Half::Lo, reg));
 					}
+					ValType::I64 => {
+						let reg = ra.get_double(src.into_untyped());
+						block.push(LirInstr::GlobalSet(*dst, Half::Lo, reg.lo()));
+						block.push(LirInstr::GlobalSet(*dst, Half::Hi, reg.hi()));
+					}
 					_ => todo!()
 				}
 			}
 			super::SsaInstr::GlobalGet(dst, src) => {
 				match dst.ty() {
-					ValType::I32 => {
+					ValType::I32 | ValType::FuncRef | ValType::ExternRef => {
 						let reg = ra.get(dst.into_untyped());
 						block.push(LirInstr::GlobalGet(reg, *src, Half::Lo));
 					}
+					ValType::I64 => {
+						let reg = ra.get_double(dst.into_untyped());
+						block.push(LirInstr::GlobalGet(reg.lo(), *src, Half::Lo));
+						block.push(LirInstr::GlobalGet(reg.hi(), *src, Half::Hi));
+					}
 					_ => todo!(),
 				}
 			}
 
 			super::SsaInstr::LocalSet(dst, src) => {
 				match src.ty() {
-					ValType::I32 | ValType::F32 => {
+					ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 						let reg = ra.get(src.into_untyped());
 						block.push(LirInstr::LocalSet(*dst, Half::Lo, reg));
 					}
@@ -703,7 +905,7 @@ fn lower_block<L>(
 			}
 			super::SsaInstr::LocalGet(dst, src) => {
 				match dst.ty() {
-					ValType::I32 | ValType::F32 => {
+					ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 						let reg = ra.get(dst.into_untyped());
 						block.push(LirInstr::LocalGet(reg, *src, Half::Lo));
 					}
@@ -717,7 +919,7 @@ fn lower_block<L>(
 			}
 			super::SsaInstr::ParamGet(dst, src) => {
 				match dst.ty() {
-					ValType::I32 | ValType::F32 => {
+					ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 						let dst = ra.get(dst.into_untyped());
 						let src = Register::param_lo(*src);
 						block.push(LirInstr::Assign(dst, src));
@@ -778,6 +980,46 @@ fn lower_block<L>(
 				let src = ra.get_double(src.into_untyped());
 				block.push(LirInstr::Assign(dst, src.lo()));
 			}
+
+			&super::SsaInstr::TruncS(dst, src) |
+			&super::SsaInstr::TruncU(dst, src) => {
+				// The intrinsics always produce 64 bits,
+				// and results that are in range have the same bits whether they are signed or not.
+				let result = match dst.ty() {
+					ValType::I32 => ra.get_temp_double(),
+					ValType::I64 => ra.get_double(dst.into_untyped()),
+					t => panic!("floats can only be truncated to integers, not to {t:?}"),
+				};
+
+				match src.ty() {
+					ValType::F32 => block.push(LirInstr::F32TruncTo64(result, ra.get(src.into_untyped()))),
+					ValType::F64 => block.push(LirInstr::F64TruncTo64(result, ra.get_double(src.into_untyped()))),
+					t => panic!("only floats can be truncated, not {t:?} values"),
+				}
+
+				if dst.ty() == ValType::I32 {
+					block.push(LirInstr::Assign(ra.get(dst.into_untyped()), result.lo()));
+				}
+			}
+			&super::SsaInstr::TruncSatS(dst, src) => do_trunc_sat(dst, src, true, &mut block, ra),
+			&super::SsaInstr::TruncSatU(dst, src) => do_trunc_sat(dst, src, false, &mut block, ra),
+			&super::SsaInstr::ConvertS(dst, src) => do_convert(dst, src, true, &mut block, ra),
+			&super::SsaInstr::ConvertU(dst, src) => do_convert(dst, src, false, &mut block, ra),
+			&super::SsaInstr::Reinterpret(dst, src) => {
+				// Floats are already stored as their bits, so this is just a move
+				match dst.ty() {
+					ValType::I32 | ValType::F32 => {
+						block.push(LirInstr::Assign(ra.get(dst.into_untyped()), ra.get(src.into_untyped())));
+					}
+					ValType::I64 | ValType::F64 => {
+						let dst = ra.get_double(dst.into_untyped());
+						let src = ra.get_double(src.into_untyped());
+						block.push(LirInstr::Assign(dst.lo(), src.lo()));
+						block.push(LirInstr::Assign(dst.hi(), src.hi()));
+					}
+					t => panic!("{t:?} values can't be reinterpreted"),
+				}
+			}
 			super::SsaInstr::Select { dst, true_var, false_var, cond } => {
 				assert_eq!(cond.ty(), ValType::I32);
 				let cond = ra.get(cond.into_untyped());
@@ -794,7 +1036,7 @@ fn lower_block<L>(
 					dst.ty() == ValType::F64 && false_var.ty() == ValType::I64);
 
 				match dst.ty() {
-					ValType::I32 | ValType::F32 => {
+					ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 						let dst = ra.get(dst.into_untyped());
 						let true_reg = map_ra_i32(*true_var, ra);
 						let false_reg = map_ra_i32(*false_var, ra);
@@ -820,7 +1062,8 @@ fn lower_block<L>(
 				for return_var in returns.iter() {
 					to_save.remove(return_var);
 				}
-				let to_save = to_save.into_iter().collect::<Vec<_>>();
+				let mut to_save = to_save.into_iter().collect::<Vec<_>>();
+				to_save.sort();
 
 				let needs_save = call_graph.may_call(*function_index, block_id.func as u32);
 
@@ -828,22 +1071,24 @@ fn lower_block<L>(
 					emit_save(&mut block, &to_save, ra);
 				}
 
-				match jump_mode() {
-					JumpMode::Direct => {
-						if call_graph.is_single_tick(*function_index) {
-							block.push(LirInstr::Call { func: *function_index });
-						} else {
-							let next_block_id = builder.alloc_block_id();
+				if call_graph.is_single_tick(*function_index) {
+					block.push(LirInstr::Call { func: *function_index });
+				} else {
+					let next_block_id = builder.alloc_block_id();
 
-							let entry_point = BlockId { func: *function_index as usize, block: 0 };
+					let entry_point = BlockId { func: *function_index as usize, block: 0 };
 
-							block.push(LirInstr::PushReturnAddr(next_block_id));
-							builder.push(block_id, block, LirTerminator::Jump(LirJumpTarget{ label: entry_point, cmd_check: true }));
+					remarks.emit("lir", block_id.func, Some(ssa_block_id.block), || format!("cmd_check kept: call to func {} may take more than one tick", function_index));
 
-							block_id = next_block_id;
-							block = Vec::new();
-						}
+					if let Some(depth) = call_graph.recursion_yield_depth(*function_index) {
+						block.push(LirInstr::RecursionYieldCheck(depth));
 					}
+
+					block.push(LirInstr::PushReturnAddr(next_block_id));
+					builder.push(block_id, block, LirTerminator::Jump(LirJumpTarget{ label: entry_point, cmd_check: true }));
+
+					block_id = next_block_id;
+					block = Vec::new();
 				}
 
 				if needs_save {
@@ -859,7 +1104,8 @@ fn lower_block<L>(
 				for return_var in returns.iter() {
 					to_save.remove(return_var);
 				}
-				let to_save = to_save.into_iter().collect::<Vec<_>>();
+				let mut to_save = to_save.into_iter().collect::<Vec<_>>();
+				to_save.sort();
 
 				let table = &parent.tables[*table_index as usize];
 				let compat_funcs = get_compatible_functions(parent, table, params, returns);
@@ -879,59 +1125,80 @@ fn lower_block<L>(
 				}
 
 				assert_eq!(table_entry.ty(), ValType::I32);
-				let table_entry = ra.get(table_entry.into_untyped());
+				let index = ra.get(table_entry.into_untyped());
+				let mut table_entry = index;
+
+				// Calls through tables that are written at runtime dispatch on the stored function reference instead
+				if table.runtime_funcs.is_some() {
+					let func_ref = ra.get_temp();
+					block.push(LirInstr::TableGet { table: *table_index, dst: func_ref, index: table_entry });
+					table_entry = func_ref;
+				}
+
+				if call_indirect_checks {
+					let targets = table.call_targets();
+					let mut null = Vec::new();
+					let mut mismatched = Vec::new();
+					for (value, (target, compat)) in targets.iter().zip(compat_funcs.clone()).enumerate() {
+						match (target, compat) {
+							(None, _) => null.push(value),
+							(Some(_), None) => mismatched.push(value),
+							(Some(_), Some(_)) => {}
+						}
+					}
+
+					// Function references can't be out of bounds, since `table.get` checked the index already
+					let len = table.runtime_funcs.is_none().then(|| targets.len());
+					block.push(LirInstr::CallIndirectCheck { table_entry, index, null, mismatched, len });
+				}
 
 				let is_only_single_tick = compat_funcs.clone().flatten().all(|func_idx| call_graph.is_single_tick(func_idx as u32));
 				let is_only_multi_tick = compat_funcs.clone().flatten().all(|func_idx| !call_graph.is_single_tick(func_idx as u32));
 
-				match jump_mode() {
-					JumpMode::Direct => {
-						if is_only_single_tick {
-							block.push(LirInstr::CallIndirect { table: compat_funcs.collect(), table_entry })
-						} else if is_only_multi_tick {
-							let next_block_id = builder.alloc_block_id();
-
-							block.push(LirInstr::PushReturnAddr(next_block_id));
-
-							let arms = compat_funcs.map(|elem| {
-								elem.map(|func_idx| {
-									BlockId { func: func_idx, block: 0 }
-								})
-							}).collect();
-
-							builder.push(block_id, block, LirTerminator::JumpTable { arms, default: None, cond: table_entry });
-
-							block_id = next_block_id;
-							block = Vec::new();
-						} else {
-							let continued_block_idx = builder.alloc_block_id();
-
-							block.push(LirInstr::PushReturnAddr(continued_block_idx));
-
-							let arms = compat_funcs.map(|elem| {
-								elem.map(|func_idx| {
-									if call_graph.is_single_tick(func_idx as u32) {
-										let trampoline_id = builder.alloc_block_id();
-										let trampoline = vec![
-											LirInstr::Call { func: func_idx as u32 },
-											LirInstr::PopReturnAddr, // we can pop it because it's always guaranteed to be continued_block_idx
-										];
-										// Don't do the cmd_check here because it's done by the JumpTable instead
-										builder.push(trampoline_id, trampoline, LirTerminator::Jump(LirJumpTarget { label: continued_block_idx, cmd_check: false }));
-
-										trampoline_id
-									} else {
-										BlockId { func: func_idx, block: 0 }
-									}
-								})
-							}).collect();
-
-							builder.push(block_id, block, LirTerminator::JumpTable { arms, default: None, cond: table_entry });
-
-							block_id = continued_block_idx;
-							block = Vec::new();
-						}
-					}
+				if is_only_single_tick {
+					block.push(LirInstr::CallIndirect { table: compat_funcs.collect(), table_entry })
+				} else if is_only_multi_tick {
+					let next_block_id = builder.alloc_block_id();
+
+					block.push(LirInstr::PushReturnAddr(next_block_id));
+
+					let arms = compat_funcs.map(|elem| {
+						elem.map(|func_idx| {
+							BlockId { func: func_idx, block: 0 }
+						})
+					}).collect();
+
+					builder.push(block_id, block, LirTerminator::JumpTable { arms, default: None, cond: table_entry });
+
+					block_id = next_block_id;
+					block = Vec::new();
+				} else {
+					let continued_block_idx = builder.alloc_block_id();
+
+					block.push(LirInstr::PushReturnAddr(continued_block_idx));
+
+					let arms = compat_funcs.map(|elem| {
+						elem.map(|func_idx| {
+							if call_graph.is_single_tick(func_idx as u32) {
+								let trampoline_id = builder.alloc_block_id();
+								let trampoline = vec![
+									LirInstr::Call { func: func_idx as u32 },
+									LirInstr::PopReturnAddr, // we can pop it because it's always guaranteed to be continued_block_idx
+								];
+								// Don't do the cmd_check here because it's done by the JumpTable instead
+								builder.push(trampoline_id, trampoline, LirTerminator::Jump(LirJumpTarget { label: continued_block_idx, cmd_check: false }));
+
+								trampoline_id
+							} else {
+								BlockId { func: func_idx, block: 0 }
+							}
+						})
+					}).collect();
+
+					builder.push(block_id, block, LirTerminator::JumpTable { arms, default: None, cond: table_entry });
+
+					block_id = continued_block_idx;
+					block = Vec::new();
 				}
 
 				if needs_save {
@@ -941,7 +1208,7 @@ fn lower_block<L>(
 				emit_copy_from_returns(&mut block, returns, ra);
 			}
 
-			&super::SsaInstr::Memset { dest, value, length, result } => {
+			&super::SsaInstr::Memset { memory, dest, value, length, result } => {
 				assert_eq!(dest.ty(), ValType::I32);
 				assert_eq!(value.ty(), ValType::I32);
 				assert_eq!(length.ty(), ValType::I32);
@@ -952,7 +1219,107 @@ fn lower_block<L>(
 				let length = ra.get(length.into_untyped());
 				let result = ra.get(result.into_untyped());
 
-				block.push(LirInstr::Memset { dest, value, length, result });
+				block.push(LirInstr::Memset { memory, dest, value, length, result });
+			}
+			&super::SsaInstr::Memcpy { memory, dest, src, length } => {
+				let dest = ra.get(dest.unwrap_i32());
+				let src = ra.get(src.unwrap_i32());
+				let length = ra.get(length.unwrap_i32());
+
+				block.push(LirInstr::Memcpy { memory, dest, src, length });
+			}
+
+			&super::SsaInstr::MemoryGrow { memory, pages, result } => {
+				let pages = ra.get(pages.unwrap_i32());
+				let result = ra.get(result.unwrap_i32());
+
+				block.push(LirInstr::MemoryGrow { memory, pages, result });
+			}
+			&super::SsaInstr::MemorySize(memory, dst) => {
+				let dst = ra.get(dst.unwrap_i32());
+				block.push(LirInstr::MemorySize(memory, dst));
+			}
+
+			&super::SsaInstr::RefNull(dst) => {
+				let dst = ra.get(dst.unwrap_i32());
+				block.push(LirInstr::Set(dst, 0));
+			}
+			&super::SsaInstr::RefFunc(dst, func_idx) => {
+				let dst = ra.get(dst.unwrap_i32());
+				block.push(LirInstr::Set(dst, func_idx as i32 + 1));
+			}
+			&super::SsaInstr::TableGet { table_index, dst, index } => {
+				let dst = ra.get(dst.unwrap_i32());
+				let index = ra.get(index.unwrap_i32());
+
+				if parent.tables[table_index as usize].runtime_funcs.is_some() {
+					block.push(LirInstr::TableGet { table: table_index, dst, index });
+				} else {
+					// The entries never change, so they can be looked up the same way `call_indirect` does
+					let elements = &parent.tables[table_index as usize].elements;
+					block.push(LirInstr::Set(dst, 0));
+					for (idx, elem) in elements.iter().enumerate() {
+						if let Some(func_idx) = elem {
+							let instr = LirInstr::Set(dst, *func_idx as i32 + 1);
+							block.push(instr.if_cond(Condition::eq_const(index, idx as i32)));
+						}
+					}
+				}
+			}
+			&super::SsaInstr::TableSet { table_index, index, value } => {
+				let index = ra.get(index.unwrap_i32());
+				let src = ra.get(value.unwrap_i32());
+				block.push(LirInstr::TableSet { table: table_index, index, src });
+			}
+			&super::SsaInstr::TableGrow { table_index, init, count, result } => {
+				let init = ra.get(init.unwrap_i32());
+				let count = ra.get(count.unwrap_i32());
+				let result = ra.get(result.unwrap_i32());
+				block.push(LirInstr::TableGrow { table: table_index, init, count, result });
+			}
+			&super::SsaInstr::TableSize { table_index, dst } => {
+				let dst = ra.get(dst.unwrap_i32());
+
+				let table = &parent.tables[table_index as usize];
+				if table.capacity() == table.elements.len() {
+					block.push(LirInstr::Set(dst, table.elements.len() as i32));
+				} else {
+					block.push(LirInstr::TableSize { table: table_index, dst });
+				}
+			}
+			&super::SsaInstr::TableInit { table_index, segment, dst, src, length } => {
+				let dst = ra.get(dst.unwrap_i32());
+				let src = ra.get(src.unwrap_i32());
+				let length = ra.get(length.unwrap_i32());
+				block.push(LirInstr::TableInit { table: table_index, segment, dst, src, length });
+			}
+			&super::SsaInstr::ElemDrop(segment) => {
+				block.push(LirInstr::ElemDrop(segment));
+			}
+
+			&super::SsaInstr::BufGetU8(dst, ptr, idx) |
+			&super::SsaInstr::BufGetU16(dst, ptr, idx) => {
+				let dst = ra.get(dst.unwrap_i32());
+				let ptr = buf_operand(ptr, ra, static_values);
+				let idx = buf_operand(idx, ra, static_values);
+
+				if matches!(instr, super::SsaInstr::BufGetU8(..)) {
+					block.push(LirInstr::BufLoad8(dst, ptr, idx));
+				} else {
+					block.push(LirInstr::BufLoad16(dst, ptr, idx));
+				}
+			}
+			&super::SsaInstr::BufSetU8(ptr, idx, src) |
+			&super::SsaInstr::BufSetU16(ptr, idx, src) => {
+				let ptr = buf_operand(ptr, ra, static_values);
+				let idx = buf_operand(idx, ra, static_values);
+				let src = ra.get(src.unwrap_i32());
+
+				if matches!(instr, super::SsaInstr::BufSetU8(..)) {
+					block.push(LirInstr::BufStore8(src, ptr, idx));
+				} else {
+					block.push(LirInstr::BufStore16(src, ptr, idx));
+				}
 			}
 
 			&super::SsaInstr::TurtleSetX(v) => {
@@ -971,6 +1338,10 @@ fn lower_block<L>(
 				let reg = ra.get(v.unwrap_i32());
 				block.push(LirInstr::TurtleSetBlock(reg));
 			}
+			super::SsaInstr::TurtleSetBlockNbt { block: block_var, nbt } => {
+				let reg = ra.get(block_var.unwrap_i32());
+				block.push(LirInstr::TurtleSetBlockNbt { block: reg, nbt: nbt.clone() });
+			}
 			&super::SsaInstr::TurtleFillBlock { block: block_var, x_span, y_span, z_span } => {
 				let block_reg = map_ra_i32(block_var, ra);
 				let x_span = map_ra_i32(x_span, ra);
@@ -1000,18 +1371,150 @@ fn lower_block<L>(
 			&super::SsaInstr::TurtlePaste => {
 				block.push(LirInstr::TurtlePaste);
 			}
+			&super::SsaInstr::TurtleFace { yaw, pitch } => {
+				let yaw = ra.get(yaw.unwrap_i32());
+				let pitch = ra.get(pitch.unwrap_i32());
+				block.push(LirInstr::TurtleFace { yaw, pitch });
+			}
+			&super::SsaInstr::TurtleForward(blocks) => {
+				let blocks = ra.get(blocks.unwrap_i32());
+				block.push(LirInstr::TurtleForward(blocks));
+			}
+			&super::SsaInstr::TurtleTurn { degrees } => {
+				block.push(LirInstr::TurtleTurn { degrees });
+			}
+			&super::SsaInstr::TurtleRaycast { dst, max_dist } => {
+				let dst = ra.get(dst.unwrap_i32());
+				let max_dist = ra.get(max_dist.unwrap_i32());
+				block.push(LirInstr::TurtleRaycast { dst, max_dist });
+			}
+			&super::SsaInstr::TurtleLightLevel(dst) => {
+				let dst = ra.get(dst.unwrap_i32());
+				block.push(LirInstr::TurtleLightLevel(dst));
+			}
+			&super::SsaInstr::TurtleCheckBlock { dst, solid } => {
+				let dst = ra.get(dst.unwrap_i32());
+				block.push(LirInstr::TurtleCheckBlock { dst, solid });
+			}
+			&super::SsaInstr::PlaySound { sound, volume, pitch } => {
+				let sound = ra.get(sound.unwrap_i32());
+				block.push(LirInstr::PlaySound { sound, volume, pitch });
+			}
+			&super::SsaInstr::Particle { particle, count } => {
+				let particle = ra.get(particle.unwrap_i32());
+				block.push(LirInstr::Particle { particle, count });
+			}
+			&super::SsaInstr::BossbarCreate(bar) => {
+				let bar = ra.get(bar.unwrap_i32());
+				block.push(LirInstr::BossbarCreate(bar));
+			}
+			&super::SsaInstr::BossbarSetValue(bar, value) => {
+				let bar = ra.get(bar.unwrap_i32());
+				let value = ra.get(value.unwrap_i32());
+				block.push(LirInstr::BossbarSetValue(bar, value));
+			}
+			&super::SsaInstr::BossbarSetMax(bar, max) => {
+				let bar = ra.get(bar.unwrap_i32());
+				let max = ra.get(max.unwrap_i32());
+				block.push(LirInstr::BossbarSetMax(bar, max));
+			}
+			&super::SsaInstr::BossbarSetName(bar, ptr, len) => {
+				let bar = ra.get(bar.unwrap_i32());
+				let ptr = ra.get(ptr.unwrap_i32());
+				let len = ra.get(len.unwrap_i32());
+				block.push(LirInstr::BossbarSetName(bar, ptr, len));
+			}
+			&super::SsaInstr::NearestPlayerPos { dst, axis } => {
+				let dst = ra.get(dst.unwrap_i32());
+				block.push(LirInstr::NearestPlayerPos { dst, axis });
+			}
+			&super::SsaInstr::EntityCount { dst, selector } => {
+				let dst = ra.get(dst.unwrap_i32());
+				let selector = ra.get(selector.unwrap_i32());
+				block.push(LirInstr::EntityCount { dst, selector });
+			}
+			&super::SsaInstr::GameTime(dst) => {
+				let dst = ra.get_double(dst.unwrap_i64());
+				block.push(LirInstr::GameTime(dst));
+			}
+			&super::SsaInstr::Random { dst, bound } => {
+				let dst = ra.get(dst.unwrap_i32());
+				let bound = ra.get(bound.unwrap_i32());
+				block.push(LirInstr::Random { dst, bound });
+			}
 			&super::SsaInstr::PrintInt(v) => {
 				let reg = ra.get(v.unwrap_i32());
 				block.push(LirInstr::PrintInt(reg));
 			}
+			&super::SsaInstr::PrintLong(v) => {
+				let reg = ra.get_double(v.unwrap_i64());
+				block.push(LirInstr::PrintLong(reg));
+			}
+			&super::SsaInstr::PrintFloat(v) => {
+				match v.ty() {
+					ValType::F32 => block.push(LirInstr::PrintF32(ra.get(v.into_untyped()))),
+					ValType::F64 => block.push(LirInstr::PrintF64(ra.get_double(v.into_untyped()))),
+					t => panic!("only floats can be printed as floats, not {t:?} values"),
+				}
+			}
 			&super::SsaInstr::PutChar(v) => {
 				let reg = ra.get(v.unwrap_i32());
 				block.push(LirInstr::PutChar(reg));
 			}
+			&super::SsaInstr::PrintStr(ptr, len) => {
+				let ptr = ra.get(ptr.unwrap_i32());
+				let len = ra.get(len.unwrap_i32());
+				block.push(LirInstr::PrintStr(ptr, len));
+			}
+			&super::SsaInstr::Title { ptr, len, actionbar } => {
+				let ptr = ra.get(ptr.unwrap_i32());
+				let len = ra.get(len.unwrap_i32());
+				block.push(LirInstr::Title { ptr, len, actionbar });
+			}
+			&super::SsaInstr::PrintFmt(fmt, args) => {
+				let fmt = ra.get(fmt.unwrap_i32());
+				let args = ra.get(args.unwrap_i32());
+				block.push(LirInstr::PrintFmt(fmt, args));
+			}
 			&super::SsaInstr::WasiProcExit(v) => {
 				let reg = ra.get(v.unwrap_i32());
 				block.push(LirInstr::WasiProcExit(reg));
 			}
+			&super::SsaInstr::WasiFdWrite { fd, iovs, iovs_len, nwritten, result } => {
+				let fd = ra.get(fd.unwrap_i32());
+				let iovs = ra.get(iovs.unwrap_i32());
+				let iovs_len = ra.get(iovs_len.unwrap_i32());
+				let nwritten = ra.get(nwritten.unwrap_i32());
+				let result = ra.get(result.unwrap_i32());
+
+				block.push(LirInstr::WasiFdWrite { fd, iovs, iovs_len, nwritten, result });
+			}
+			super::SsaInstr::RawCommand(cmd) => {
+				block.push(LirInstr::RawCommand(cmd.clone()));
+			}
+			super::SsaInstr::RawCommandStore { dst, cmd, success } => {
+				let dst = ra.get(dst.unwrap_i32());
+				block.push(LirInstr::RawCommandStore { dst, cmd: cmd.clone(), success: *success });
+			}
+			super::SsaInstr::PackCall { namespace, export, params, returns } => {
+				// The other datapack has its own registers, so nothing needs to be saved around the call
+				emit_copy_to_params(&mut block, params, ra);
+				block.push(LirInstr::PackCall {
+					namespace: namespace.clone(),
+					export: export.clone(),
+					params: params.iter().map(|p| p.ty()).collect(),
+					returns: returns.iter().map(|r| r.ty()).collect(),
+				});
+				emit_copy_from_returns(&mut block, returns, ra);
+			}
+			&super::SsaInstr::MemorySnapshot(v) => {
+				let reg = ra.get(v.unwrap_i32());
+				block.push(LirInstr::MemorySnapshot(reg));
+			}
+			&super::SsaInstr::MemoryRestore(v) => {
+				let reg = ra.get(v.unwrap_i32());
+				block.push(LirInstr::MemoryRestore(reg));
+			}
 			&super::SsaInstr::Todo(msg) => {
 				block.push(LirInstr::Todo(msg));
 			}
@@ -1020,9 +1523,7 @@ fn lower_block<L>(
 
 	match &ssa_block.term {
 		crate::ssa::SsaTerminator::Unreachable => {
-			// TODO: PRINT A WARNING
-
-			builder.push(block_id, block, LirTerminator::Return);
+			builder.push(block_id, block, LirTerminator::Trap(TrapKind::Unreachable));
 		},
 		crate::ssa::SsaTerminator::ScheduleJump(target, delay) => {
 			assert!(target.params.is_empty());
@@ -1035,47 +1536,52 @@ fn lower_block<L>(
 			emit_copy(&mut block, &target.params, out_params, ra, &[]);
 
 			let is_back_edge = dom_tree.dominates(target.label, ssa_block_id);
+			let cmd_check = is_back_edge && loop_cmd_check(ssa_block_id, target.label, yield_in_loops, remarks);
 
-			builder.push(block_id, block, LirTerminator::Jump(LirJumpTarget { label: target.label, cmd_check: is_back_edge }));
+			builder.push(block_id, block, LirTerminator::Jump(LirJumpTarget { label: target.label, cmd_check }));
 		}
 		crate::ssa::SsaTerminator::BranchIf { cond, true_target, false_target } => {
-			if jump_mode() != JumpMode::Direct {
-				todo!()
-			}
-
 			assert_eq!(cond.ty(), ValType::I32);
-			// TODO: ?????
-			let cond2 = ra.get(cond.into_untyped());
-			let cond = ra.get_temp();
-			block.push(LirInstr::Assign(cond, cond2));
 
 			let true_out_params = &parent_func.get(true_target.label).params;
 			let false_out_params = &parent_func.get(false_target.label).params;
 
-			let true_conds = &[Condition::eq_zero(Register::cond_taken()), Condition::neq_zero(cond)];
-			let false_conds = &[Condition::eq_zero(Register::cond_taken()), Condition::eq_zero(cond)];
-
-			for param in true_out_params.iter().chain(false_out_params.iter()) {
-				assert_ne!(cond, ra.get(param.into_untyped()));
-			}
+			let cond = if true_out_params.is_empty() && false_out_params.is_empty() {
+				// Nothing is copied before the jump, so the condition can't be overwritten
+				// and there's no need to track which branch was taken.
+				ra.get(cond.into_untyped())
+			} else {
+				// The copies into the targets' parameters can overwrite the condition's register,
+				// so the condition is kept in a temporary until the jump.
+				let cond2 = ra.get(cond.into_untyped());
+				let cond = ra.get_temp();
+				block.push(LirInstr::Assign(cond, cond2));
+
+				let true_conds = &[Condition::eq_zero(Register::cond_taken()), Condition::neq_zero(cond)];
+				let false_conds = &[Condition::eq_zero(Register::cond_taken()), Condition::eq_zero(cond)];
+
+				// `emit_copy` swaps through the temporaries below the ones the allocator hands out,
+				// so only the parameters themselves could overwrite it
+				for param in true_out_params.iter().chain(false_out_params.iter()) {
+					assert_ne!(cond, ra.get(param.into_untyped()));
+				}
 
-			// FIXME: Make sure cond is not overwritten!
+				block.push(LirInstr::Set(Register::cond_taken(), 0));
+				emit_copy(&mut block, &true_target.params, true_out_params, ra, true_conds);
+				emit_copy(&mut block, &false_target.params, false_out_params, ra, false_conds);
 
-			block.push(LirInstr::Set(Register::cond_taken(), 0));
-			emit_copy(&mut block, &true_target.params, true_out_params, ra, true_conds);
-			emit_copy(&mut block, &false_target.params, false_out_params, ra, false_conds);
+				cond
+			};
 
 			let true_is_back_edge = dom_tree.dominates(true_target.label, ssa_block_id);
-			let true_label = LirJumpTarget { label: true_target.label, cmd_check: true_is_back_edge };
+			let true_check = true_is_back_edge && loop_cmd_check(ssa_block_id, true_target.label, yield_in_loops, remarks);
+			let true_label = LirJumpTarget { label: true_target.label, cmd_check: true_check };
 			let false_is_back_edge = dom_tree.dominates(false_target.label, ssa_block_id);
-			let false_label = LirJumpTarget { label: false_target.label, cmd_check: false_is_back_edge };
+			let false_check = false_is_back_edge && loop_cmd_check(ssa_block_id, false_target.label, yield_in_loops, remarks);
+			let false_label = LirJumpTarget { label: false_target.label, cmd_check: false_check };
 			builder.push(block_id, block, LirTerminator::JumpIf { true_label, false_label, cond });
 		}
 		crate::ssa::SsaTerminator::BranchTable { cond, default, arms } => {
-			if jump_mode() != JumpMode::Direct {
-				todo!()
-			}
-
 			if arms.is_empty() {
 				let out_params = &parent_func.get(default.label).params;
 				emit_copy(&mut block, &default.params, out_params, ra, &[]);
@@ -1088,6 +1594,8 @@ fn lower_block<L>(
 				let default_out_params = &parent_func.get(default.label).params;
 				let other_out_params = arms.iter().flat_map(|arm| &parent_func.get(arm.label).params);
 
+				let has_params = !default_out_params.is_empty() || arms.iter().any(|arm| !parent_func.get(arm.label).params.is_empty());
+
 				let mut needs_new_cond = false;
 				for out_param in other_out_params.chain(default_out_params.iter()) {
 					if cond == ra.get(out_param.into_untyped()) {
@@ -1102,18 +1610,22 @@ fn lower_block<L>(
 					cond = new_cond;
 				}
 
-				block.push(LirInstr::Set(Register::cond_taken(), 0));
-
 				assert!(!arms.is_empty());
-				let range = 0..=(arms.len() as i32 - 1);
 
-				let default_conds = &[Condition::eq_zero(Register::cond_taken()), Condition::NotMatches(cond, range)];
-				emit_copy(&mut block, &default.params, default_out_params, ra, default_conds);
+				// Without any parameters to copy, there's no need to track which arm was taken
+				if has_params {
+					block.push(LirInstr::Set(Register::cond_taken(), 0));
 
-				for (i, arm) in arms.iter().enumerate() {
-					let out_params = &parent_func.get(arm.label).params;
-					let conds = &[Condition::eq_zero(Register::cond_taken()), Condition::eq_const(cond, i as i32)];
-					emit_copy(&mut block, &arm.params, out_params, ra, conds);
+					let range = 0..=(arms.len() as i32 - 1);
+
+					let default_conds = &[Condition::eq_zero(Register::cond_taken()), Condition::NotMatches(cond, range)];
+					emit_copy(&mut block, &default.params, default_out_params, ra, default_conds);
+
+					for (i, arm) in arms.iter().enumerate() {
+						let out_params = &parent_func.get(arm.label).params;
+						let conds = &[Condition::eq_zero(Register::cond_taken()), Condition::eq_const(cond, i as i32)];
+						emit_copy(&mut block, &arm.params, out_params, ra, conds);
+					}
 				}
 
 				let arm_labels = arms.iter().map(|arm| Some(arm.label)).collect();
@@ -1124,7 +1636,7 @@ fn lower_block<L>(
 		crate::ssa::SsaTerminator::Return(return_vars) => {
 			for (idx, var) in return_vars.iter().enumerate() {
 				match var.ty() {
-					ValType::I32 | ValType::F32 => {
+					ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 						let src = ra.get(var.into_untyped());
 						let dst = Register::return_lo(idx as u32);
 						block.push(LirInstr::Assign(dst, src));
@@ -1148,10 +1660,20 @@ fn lower_block<L>(
 	}
 }
 
+/// Decides whether a loop back edge checks the command count, which is only skipped when loops never yield.
+fn loop_cmd_check(from: BlockId, to: BlockId, yield_in_loops: bool, remarks: &Remarks) -> bool {
+	if yield_in_loops {
+		remarks.emit("lir", from.func, Some(from.block), || format!("cmd_check kept: loop back edge to block {}", to.block));
+	} else {
+		remarks.emit("lir", from.func, Some(from.block), || format!("cmd_check removed: loop back edge to block {}, no tick budget", to.block));
+	}
+	yield_in_loops
+}
+
 fn emit_copy_to_params(block: &mut Vec<LirInstr>, vars: &[TypedSsaVar], ra: &mut dyn RegAlloc) {
 	for (id, var) in vars.iter().enumerate() {
 		match var.ty() {
-			ValType::I32 | ValType::F32 => {
+			ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 				let dst = Register::param_lo(id as u32);
 				let src = ra.get(var.into_untyped());
 				block.push(LirInstr::Assign(dst, src));
@@ -1170,7 +1692,7 @@ fn emit_copy_to_params(block: &mut Vec<LirInstr>, vars: &[TypedSsaVar], ra: &mut
 fn emit_copy_from_returns(block: &mut Vec<LirInstr>, vars: &[TypedSsaVar], ra: &mut dyn RegAlloc) {
 	for (id, var) in vars.iter().enumerate() {
 		match var.ty() {
-			ValType::I32 | ValType::F32 => {
+			ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 				let dst = ra.get(var.into_untyped());
 				let src = Register::return_lo(id as u32);
 				block.push(LirInstr::Assign(dst, src));
@@ -1189,7 +1711,7 @@ fn emit_copy_from_returns(block: &mut Vec<LirInstr>, vars: &[TypedSsaVar], ra: &
 fn get_save_reg_list(to_save: &[TypedSsaVar], ra: &mut dyn RegAlloc) -> Vec<Register> {
 	to_save.iter().flat_map(|var| {
 		match var.ty() {
-			ValType::I32 | ValType::F32 => {
+			ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 				let reg = ra.get(var.into_untyped());
 				[reg, reg].into_iter().take(1)
 
@@ -1226,7 +1748,7 @@ fn emit_copy(block: &mut Vec<LirInstr>, in_params: &[TypedSsaVar], out_params: &
 
 	let param_pairs = in_params.iter().zip(out_params.iter()).filter(|(i, o)| {
 		match i.ty() {
-			ValType::I32 | ValType::F32 => ra.get(i.into_untyped()) != ra.get(o.into_untyped()),
+			ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => ra.get(i.into_untyped()) != ra.get(o.into_untyped()),
 			ValType::I64 | ValType::F64 => ra.get_double(i.into_untyped()) != ra.get_double(o.into_untyped()),
 			_ => todo!(),
 		}
@@ -1234,7 +1756,7 @@ fn emit_copy(block: &mut Vec<LirInstr>, in_params: &[TypedSsaVar], out_params: &
 
 	let reg_pairs = param_pairs.flat_map(|(i, o)| {
 		match i.ty() {
-			ValType::I32 | ValType::F32 => vec![(ra.get(i.into_untyped()), ra.get(o.into_untyped()))],
+			ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => vec![(ra.get(i.into_untyped()), ra.get(o.into_untyped()))],
 			ValType::I64 | ValType::F64 => vec![
 				(ra.get_double(i.into_untyped()).lo(), ra.get_double(o.into_untyped()).lo()),
 				(ra.get_double(i.into_untyped()).hi(), ra.get_double(o.into_untyped()).hi()),
@@ -1280,7 +1802,7 @@ fn gen_prologue(ssa_func: &SsaFunction, ssa_program: &SsaProgram) -> Vec<LirInst
 	for (idx, (local, param)) in locals.iter().zip(ssa_func.params.iter()).enumerate() {
 		assert_eq!(*local, *param);
 		match param {
-			ValType::I32 | ValType::F32 => {
+			ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef => {
 				result.push(LirInstr::LocalSet(idx as u32, Half::Lo, Register::param_lo(idx as u32)));
 			}
 			ValType::I64 | ValType::F64 => {
@@ -1294,7 +1816,7 @@ fn gen_prologue(ssa_func: &SsaFunction, ssa_program: &SsaProgram) -> Vec<LirInst
 	result
 }
 
-fn lower(ctx: &CompileContext, ssa_func: &SsaFunction, ssa_program: &SsaProgram, call_graph: &CallGraph, constant_pool: &mut HashSet<i32>) -> LirFunction {
+fn lower(ctx: &CompileContext, ssa_func: &SsaFunction, ssa_program: &SsaProgram, call_graph: &CallGraph, constant_pool: &mut BTreeSet<i32>) -> LirFunction {
 	let mut reg_alloc: Box<dyn RegAlloc> = match ctx.regalloc {
 		crate::RegAllocMode::Noop => Box::new(NoopRegAlloc::analyze(ssa_func)),
 		crate::RegAllocMode::Auto => {
@@ -1302,29 +1824,49 @@ fn lower(ctx: &CompileContext, ssa_func: &SsaFunction, ssa_program: &SsaProgram,
 			let too_much = instr_count > 1300;
 			if too_much {
 				println!("Using no-op regalloc on func {}, has too many ({}) instructions, will take too long", ssa_func.func_id(), instr_count);
+				ctx.remarks().emit("regalloc", ssa_func.func_id() as usize, None, || format!("full regalloc skipped: size ({} instructions)", instr_count));
 			}
-			if too_much { Box::new(NoopRegAlloc::analyze(ssa_func)) } else { Box::new(FullRegAlloc::analyze(ssa_func)) }
+			if too_much { Box::new(NoopRegAlloc::analyze(ssa_func)) } else { Box::new(FullRegAlloc::analyze(ssa_func, ctx.remarks())) }
 		},
-		crate::RegAllocMode::Full => Box::new(FullRegAlloc::analyze(ssa_func)),
+		crate::RegAllocMode::Full => Box::new(FullRegAlloc::analyze(ssa_func, ctx.remarks())),
 	};
 
-	let mut builder = LirFuncBuilder::new(ssa_func);
-
-	let liveness_info = FullLivenessInfo::analyze(ssa_func);
-
 	let func_static_values = if ctx.do_const_prop {
 		const_prop::get_func_constants(ssa_func)
 	} else {
 		LocalBlockMap::new(ssa_func.func_id() as usize)
 	};
 
+	let result = lower_with_reg_alloc(ssa_func, ssa_program, call_graph, &mut *reg_alloc, &func_static_values, ctx.tick_budget.is_some(), ctx.call_indirect_checks, ctx.remarks());
+
+	constant_pool.extend(reg_alloc.const_pool().clone());
+
+	println!("Lowered func {} to LIR", ssa_func.func_id());
+
+	result
+}
+
+fn lower_with_reg_alloc(ssa_func: &SsaFunction, ssa_program: &SsaProgram, call_graph: &CallGraph, reg_alloc: &mut dyn RegAlloc, func_static_values: &LocalBlockMap<StaticState>, yield_in_loops: bool, call_indirect_checks: bool, remarks: &Remarks) -> LirFunction {
+	let mut builder = LirFuncBuilder::new(ssa_func);
+
+	let liveness_info = FullLivenessInfo::analyze(ssa_func);
+
 	let empty_static_values = HashMap::new();
 
 	let dom_tree = DomTree::analyze(ssa_func);
 
+	let mut code_ranges = HashMap::new();
+
 	for (block_id, block) in ssa_func.iter() {
 		let static_values = func_static_values.get(block_id).unwrap_or(&empty_static_values);
-		lower_block(ssa_program, ssa_func, block_id, block, &mut *reg_alloc, &liveness_info, call_graph, &mut builder, static_values, &dom_tree);
+		let first_lowered = builder.body.len();
+		lower_block(ssa_program, ssa_func, block_id, block, reg_alloc, &liveness_info, call_graph, &mut builder, static_values, &dom_tree, yield_in_loops, call_indirect_checks, remarks);
+
+		if let Some(ranges) = ssa_func.code_ranges.get(&block_id) {
+			for (lir_id, _) in builder.body[first_lowered..].iter() {
+				code_ranges.insert(*lir_id, ranges.clone());
+			}
+		}
 	}
 
 	let locals = ssa_program.local_types.get(&(ssa_func.func_id() as usize)).unwrap();
@@ -1339,22 +1881,18 @@ fn lower(ctx: &CompileContext, ssa_func: &SsaFunction, ssa_program: &SsaProgram,
 
 	let blocks = builder.body;
 
-	constant_pool.extend(reg_alloc.const_pool().clone());
-
-	println!("Lowered func {} to LIR", ssa_func.func_id());
-
-	LirFunction { code: blocks, returns: ssa_func.returns.clone() }
+	LirFunction { code: blocks, params: ssa_func.params.clone(), returns: ssa_func.returns.clone(), code_ranges }
 }
 
 pub fn convert(ctx: &CompileContext, ssa_program: SsaProgram) -> LirProgram {
 	use rayon::prelude::*;
 
-	let call_graph = CallGraph::new(&ssa_program);
+	let call_graph = CallGraph::new(&ssa_program, ctx.recursion_yield_depth);
 
-	let mut constants = HashSet::new();
+	let mut constants = BTreeSet::new();
 
 	let code = ssa_program.code.par_iter().map(|block| {
-		let mut consts = HashSet::new();
+		let mut consts = BTreeSet::new();
 		let res = lower(ctx, block, &ssa_program, &call_graph, &mut consts);
 		(consts, res)
 	}).collect::<Vec<_>>().into_iter().map(|john| {
@@ -1363,8 +1901,12 @@ pub fn convert(ctx: &CompileContext, ssa_program: SsaProgram) -> LirProgram {
 	}).collect::<Vec<_>>();
 
 	if ctx.dump_lir {
-		for (func_id, func) in code.iter().enumerate() {
-			println!("==== func {:?} ==== ", func_id);
+		for func in code.iter() {
+			let func_id = func.func_id();
+			match ssa_program.func_names.get(&func_id) {
+				Some(name) => println!("==== func {:?} ({}) ==== ", func_id, name),
+				None => println!("==== func {:?} ==== ", func_id),
+			}
 			for (block_id, block) in func.code.iter() {
 				println!("-- block {:?} --", block_id);
 				for instr in block.body.iter() {
@@ -1375,5 +1917,249 @@ pub fn convert(ctx: &CompileContext, ssa_program: SsaProgram) -> LirProgram {
 		}
 	}
 
-	LirProgram { code, memory: ssa_program.memory, tables: ssa_program.tables, globals: ssa_program.globals, constants, exports: ssa_program.exports }
+	LirProgram { code, memory: ssa_program.memory, tables: ssa_program.tables, elem_segments: ssa_program.elem_segments, globals: ssa_program.globals, constants, exports: ssa_program.exports, start: ssa_program.start, func_names: ssa_program.func_names, snapshot_regions: ssa_program.snapshot_regions, block_palette: ctx.block_palette.clone(), layout: ctx.layout, jump_mode: ctx.jump_mode, div_by_zero: ssa_program.div_by_zero }
+}
+
+#[cfg(test)]
+mod test {
+	use std::collections::{HashMap, BTreeSet};
+
+	use wasmparser::ValType;
+
+	use crate::{lir::{Register, DoubleRegister, LirProgram, interp::LirInterpreter}, block_id_map::LocalBlockMap, remarks::Remarks};
+	use crate::ssa::{SsaProgram, SsaFunction, SsaBasicBlock, SsaInstr, SsaTerminator, SsaVar, TypedSsaVar, JumpTarget, BlockId, call_graph::CallGraph, interp::{SsaInterpreter, TypedValue}, reg_alloc::RegAlloc};
+
+	/// Places SSA variables in specific registers, so that aliasing between operands can be forced.
+	struct AliasRegAlloc {
+		map: HashMap<SsaVar, u32>,
+		const_pool: BTreeSet<i32>,
+		temp: u32,
+	}
+
+	impl RegAlloc for AliasRegAlloc {
+		fn get(&self, val: SsaVar) -> Register {
+			Register::work_lo(0, self.map[&val])
+		}
+
+		fn get_double(&self, val: SsaVar) -> DoubleRegister {
+			DoubleRegister::Work(0, self.map[&val])
+		}
+
+		fn get_const(&mut self, val: i32) -> Register {
+			self.const_pool.insert(val);
+			Register::const_val(val)
+		}
+
+		fn get_double_const(&mut self, val: i64) -> DoubleRegister {
+			self.const_pool.insert(val as i32);
+			self.const_pool.insert((val >> 32) as i32);
+			DoubleRegister::const_val(val)
+		}
+
+		fn get_temp(&mut self) -> Register {
+			let reg = Register::temp_lo(self.temp);
+			self.temp += 1;
+			reg
+		}
+
+		fn get_temp_double(&mut self) -> DoubleRegister {
+			let reg = DoubleRegister::temp(self.temp);
+			self.temp += 1;
+			reg
+		}
+
+		fn const_pool(&self) -> &BTreeSet<i32> {
+			&self.const_pool
+		}
+	}
+
+	#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+	enum Aliasing {
+		None,
+		DstLhs,
+		DstRhs,
+		LhsRhs,
+		All,
+	}
+
+	const ALIASINGS: [Aliasing; 5] = [Aliasing::None, Aliasing::DstLhs, Aliasing::DstRhs, Aliasing::LhsRhs, Aliasing::All];
+
+	type MakeBinop = fn(TypedSsaVar, TypedSsaVar, TypedSsaVar) -> SsaInstr;
+
+	fn binops() -> Vec<(&'static str, bool, MakeBinop)> {
+		// name, whether the result is always an i32, constructor
+		let ops: [(&'static str, bool, MakeBinop); 25] = [
+			("add", false, |d, l, r| SsaInstr::Add(d, l.into(), r.into())),
+			("sub", false, |d, l, r| SsaInstr::Sub(d, l.into(), r.into())),
+			("mul", false, |d, l, r| SsaInstr::Mul(d, l, r.into())),
+			("div_s", false, |d, l, r| SsaInstr::DivS(d, l, r.into())),
+			("div_u", false, |d, l, r| SsaInstr::DivU(d, l, r.into())),
+			("rem_s", false, |d, l, r| SsaInstr::RemS(d, l, r.into())),
+			("rem_u", false, |d, l, r| SsaInstr::RemU(d, l, r.into())),
+			("shl", false, |d, l, r| SsaInstr::Shl(d, l, r.into())),
+			("shr_s", false, |d, l, r| SsaInstr::ShrS(d, l, r.into())),
+			("shr_u", false, |d, l, r| SsaInstr::ShrU(d, l, r.into())),
+			("rotl", false, |d, l, r| SsaInstr::Rotl(d, l, r)),
+			("rotr", false, |d, l, r| SsaInstr::Rotr(d, l, r)),
+			("xor", false, |d, l, r| SsaInstr::Xor(d, l, r.into())),
+			("and", false, |d, l, r| SsaInstr::And(d, l, r.into())),
+			("or", false, |d, l, r| SsaInstr::Or(d, l, r.into())),
+			("gt_s", true, |d, l, r| SsaInstr::GtS(d, l.into(), r.into())),
+			("gt_u", true, |d, l, r| SsaInstr::GtU(d, l.into(), r.into())),
+			("ge_s", true, |d, l, r| SsaInstr::GeS(d, l.into(), r.into())),
+			("ge_u", true, |d, l, r| SsaInstr::GeU(d, l.into(), r.into())),
+			("lt_s", true, |d, l, r| SsaInstr::LtS(d, l.into(), r.into())),
+			("lt_u", true, |d, l, r| SsaInstr::LtU(d, l.into(), r.into())),
+			("le_s", true, |d, l, r| SsaInstr::LeS(d, l.into(), r.into())),
+			("le_u", true, |d, l, r| SsaInstr::LeU(d, l.into(), r.into())),
+			("eq", true, |d, l, r| SsaInstr::Eq(d, l.into(), r.into())),
+			("ne", true, |d, l, r| SsaInstr::Ne(d, l.into(), r.into())),
+		];
+
+		ops.to_vec()
+	}
+
+	/// Builds a function that loads its two parameters, applies the binop, and returns the result.
+	/// Returns the program and the register each variable should be placed in.
+	fn make_program(make_binop: MakeBinop, ty: ValType, dst_ty: ValType, aliasing: Aliasing) -> (SsaProgram, HashMap<SsaVar, u32>) {
+		let lhs = TypedSsaVar(0, ty);
+		let rhs = TypedSsaVar(1, ty);
+		let dst = TypedSsaVar(2, dst_ty);
+		let ret = TypedSsaVar(3, dst_ty);
+
+		let (rhs_operand, dst_reg) = match aliasing {
+			Aliasing::None => (rhs, 2),
+			Aliasing::DstLhs => (rhs, 0),
+			Aliasing::DstRhs => (rhs, 1),
+			Aliasing::LhsRhs => (lhs, 2),
+			Aliasing::All => (lhs, 0),
+		};
+
+		let map = [(lhs, 0), (rhs, 1), (dst, dst_reg), (ret, 3)].into_iter().map(|(v, r)| (v.into_untyped(), r)).collect();
+
+		let entry = SsaBasicBlock {
+			params: Vec::new(),
+			body: vec![
+				SsaInstr::LocalGet(lhs, 0),
+				SsaInstr::LocalGet(rhs, 1),
+				make_binop(dst, lhs, rhs_operand),
+			],
+			term: SsaTerminator::Jump(JumpTarget { label: BlockId { func: 0, block: 1 }, params: vec![dst] }),
+		};
+
+		let exit = SsaBasicBlock {
+			params: vec![ret],
+			body: Vec::new(),
+			term: SsaTerminator::Return(vec![ret]),
+		};
+
+		let blocks = [(BlockId { func: 0, block: 0 }, entry), (BlockId { func: 0, block: 1 }, exit)];
+		let func = SsaFunction::new(blocks, Box::new([ty, ty]), Box::new([dst_ty]));
+
+		let program = SsaProgram {
+			local_types: [(0, vec![ty, ty])].into_iter().collect(),
+			globals: Vec::new(),
+			memory: Vec::new(),
+			tables: Vec::new(),
+			elem_segments: Vec::new(),
+			code: vec![func],
+			exports: HashMap::new(),
+			start: None,
+			func_names: HashMap::new(),
+			snapshot_regions: Vec::new(),
+			div_by_zero: crate::DivByZero::Fast,
+		};
+
+		(program, map)
+	}
+
+	fn run_lir(program: SsaProgram, map: HashMap<SsaVar, u32>, params: &[TypedValue]) -> Vec<TypedValue> {
+		let call_graph = CallGraph::new(&program, None);
+		let mut ra = AliasRegAlloc { map, const_pool: BTreeSet::new(), temp: 1000 };
+		let func = super::lower_with_reg_alloc(&program.code[0], &program, &call_graph, &mut ra, &LocalBlockMap::new(0), true, true, &Remarks::default());
+
+		let lir_program = LirProgram {
+			code: vec![func],
+			memory: Vec::new(),
+			tables: Vec::new(),
+			elem_segments: Vec::new(),
+			globals: Vec::new(),
+			constants: ra.const_pool,
+			exports: HashMap::new(),
+			start: None,
+			func_names: HashMap::new(),
+			snapshot_regions: Vec::new(),
+			block_palette: crate::pack_emitter::default_block_palette(),
+			layout: Default::default(),
+			jump_mode: crate::JumpMode::Direct,
+			div_by_zero: crate::DivByZero::Fast,
+		};
+
+		let mut interp = LirInterpreter::new(lir_program);
+		interp.call(0, params);
+		interp.run_until_halted()
+	}
+
+	#[test]
+	fn binop_aliasing() {
+		let i32_inputs = [(1234567, 89), (-98765, 4321), (-7, -3)];
+		let i64_inputs = [(0x1234_5678_9ABC, 0x3_0000_0007), (-0x7_0000_0001, 12345), (-7, -3)];
+
+		let inputs = i32_inputs.iter().map(|&(l, r)| (ValType::I32, TypedValue::I32(l), TypedValue::I32(r)))
+			.chain(i64_inputs.iter().map(|&(l, r)| (ValType::I64, TypedValue::I64(l), TypedValue::I64(r))));
+
+		for (ty, lhs, rhs) in inputs {
+			for (name, is_compare, make_binop) in binops() {
+				let dst_ty = if is_compare { ValType::I32 } else { ty };
+
+				for aliasing in ALIASINGS {
+					let params = [lhs, rhs];
+
+					let (program, _) = make_program(make_binop, ty, dst_ty, aliasing);
+					let mut interp = SsaInterpreter::new(program);
+					interp.call(0, params.to_vec());
+					let expected = interp.run_until_halted();
+
+					let (program, map) = make_program(make_binop, ty, dst_ty, aliasing);
+					let actual = run_lir(program, map, &params);
+
+					assert_eq!(actual, expected, "{ty:?} {name} with {aliasing:?} aliasing, params {params:?}");
+				}
+			}
+		}
+	}
+
+	fn make_unreachable_program() -> SsaProgram {
+		let entry = SsaBasicBlock { params: Vec::new(), body: Vec::new(), term: SsaTerminator::Unreachable };
+		let func = SsaFunction::new([(BlockId { func: 0, block: 0 }, entry)], Box::new([]), Box::new([]));
+
+		SsaProgram {
+			local_types: [(0, Vec::new())].into_iter().collect(),
+			globals: Vec::new(),
+			memory: Vec::new(),
+			tables: Vec::new(),
+			elem_segments: Vec::new(),
+			code: vec![func],
+			exports: HashMap::new(),
+			start: None,
+			func_names: HashMap::new(),
+			snapshot_regions: Vec::new(),
+			div_by_zero: crate::DivByZero::Fast,
+		}
+	}
+
+	#[test]
+	fn unreachable_traps_in_both_interpreters() {
+		let panic_message = |result: std::thread::Result<Vec<TypedValue>>| *result.unwrap_err().downcast::<String>().unwrap();
+
+		let expected = panic_message(std::panic::catch_unwind(|| {
+			let mut interp = SsaInterpreter::new(make_unreachable_program());
+			interp.call(0, Vec::new());
+			interp.run_until_halted()
+		}));
+		let actual = panic_message(std::panic::catch_unwind(|| run_lir(make_unreachable_program(), HashMap::new(), &[])));
+
+		assert!(expected.starts_with("trap: unreachable"), "{expected}");
+		assert_eq!(actual, expected);
+	}
 }
@@ -563,7 +563,7 @@ impl PredInfo {
 
 		for (pred_id, block) in func.iter() {
 			for succ_id in block.term.successors() {
-				let v = result.entry(succ_id).get_or_insert_with(Vec::new);
+				let v = result.entry(succ_id).or_insert_with(Vec::new);
 				if !v.contains(&pred_id) {
 					v.push(pred_id);
 				}
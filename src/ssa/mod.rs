@@ -6,7 +6,7 @@ pub mod const_prop;
 pub mod dce;
 pub mod reg_alloc;
 
-use std::collections::{HashMap, HashSet};
+use std::{collections::{BTreeMap, HashMap, HashSet}, fmt, ops::Range};
 
 use wasmparser::{MemoryImmediate, ValType};
 
@@ -135,7 +135,7 @@ impl TypedSsaVar {
 	}
 
 	pub fn unwrap_i32(self) -> SsaVar {
-		assert!(self.1 == ValType::I32 || self.1 == ValType::F32);
+		assert!(matches!(self.1, ValType::I32 | ValType::F32 | ValType::FuncRef | ValType::ExternRef));
 		SsaVar(self.0)
 	}
 
@@ -207,6 +207,26 @@ pub enum SsaInstr {
 	Eq(TypedSsaVar, SsaVarOrConst, SsaVarOrConst),
 	Ne(TypedSsaVar, SsaVarOrConst, SsaVarOrConst),
 
+	// float instructions: dst, lhs, rhs
+
+	FAdd(TypedSsaVar, TypedSsaVar, TypedSsaVar),
+	FSub(TypedSsaVar, TypedSsaVar, TypedSsaVar),
+	FMul(TypedSsaVar, TypedSsaVar, TypedSsaVar),
+	FDiv(TypedSsaVar, TypedSsaVar, TypedSsaVar),
+	FMin(TypedSsaVar, TypedSsaVar, TypedSsaVar),
+	FMax(TypedSsaVar, TypedSsaVar, TypedSsaVar),
+	FCopysign(TypedSsaVar, TypedSsaVar, TypedSsaVar),
+
+	// float unary instructions: dst, src
+
+	FNeg(TypedSsaVar, TypedSsaVar),
+	FAbs(TypedSsaVar, TypedSsaVar),
+	Sqrt(TypedSsaVar, TypedSsaVar),
+	FFloor(TypedSsaVar, TypedSsaVar),
+	FCeil(TypedSsaVar, TypedSsaVar),
+	FTrunc(TypedSsaVar, TypedSsaVar),
+	FNearest(TypedSsaVar, TypedSsaVar),
+
 	// unary instructions: dst, src
 
 	Popcnt(TypedSsaVar, TypedSsaVar),
@@ -255,6 +275,16 @@ pub enum SsaInstr {
 
 	Wrap(TypedSsaVar, TypedSsaVar),
 
+	// conversion instructions between integers and floats: dst, src
+
+	TruncS(TypedSsaVar, TypedSsaVar),
+	TruncU(TypedSsaVar, TypedSsaVar),
+	TruncSatS(TypedSsaVar, TypedSsaVar),
+	TruncSatU(TypedSsaVar, TypedSsaVar),
+	ConvertS(TypedSsaVar, TypedSsaVar),
+	ConvertU(TypedSsaVar, TypedSsaVar),
+	Reinterpret(TypedSsaVar, TypedSsaVar),
+
 	// misc instructions
 
 	Select {
@@ -278,7 +308,38 @@ pub enum SsaInstr {
 
 	// Optimized C stdlib calls
 
-	Memset { dest: TypedSsaVar, value: TypedSsaVar, length: TypedSsaVar, result: TypedSsaVar },
+	Memset { memory: u32, dest: TypedSsaVar, value: TypedSsaVar, length: TypedSsaVar, result: TypedSsaVar },
+	/// Like `memmove`, the regions may overlap
+	Memcpy { memory: u32, dest: TypedSsaVar, src: TypedSsaVar, length: TypedSsaVar },
+
+	// Linear memory instructions
+
+	/// Returns the old size in pages, or -1 if the memory can't grow that much
+	MemoryGrow { memory: u32, pages: TypedSsaVar, result: TypedSsaVar },
+	/// Returns the current size in pages
+	MemorySize(u32, TypedSsaVar),
+
+	// Reference and table instructions
+	// Function references hold the function index plus one, so null is zero
+
+	RefNull(TypedSsaVar),
+	RefFunc(TypedSsaVar, u32),
+	TableGet { table_index: u32, dst: TypedSsaVar, index: TypedSsaVar },
+	TableSet { table_index: u32, index: TypedSsaVar, value: TypedSsaVar },
+	/// Returns the old size, or -1 if the table can't grow that much
+	TableGrow { table_index: u32, init: TypedSsaVar, count: TypedSsaVar, result: TypedSsaVar },
+	TableSize { table_index: u32, dst: TypedSsaVar },
+	TableInit { table_index: u32, segment: u32, dst: TypedSsaVar, src: TypedSsaVar, length: TypedSsaVar },
+	ElemDrop(u32),
+
+	// Typed array instructions
+	// gets: dst, ptr, idx
+	// sets: ptr, idx, src
+
+	BufGetU8(TypedSsaVar, TypedSsaVar, TypedSsaVar),
+	BufGetU16(TypedSsaVar, TypedSsaVar, TypedSsaVar),
+	BufSetU8(TypedSsaVar, TypedSsaVar, TypedSsaVar),
+	BufSetU16(TypedSsaVar, TypedSsaVar, TypedSsaVar),
 
 	// Minecraft IO instructions
 
@@ -286,16 +347,75 @@ pub enum SsaInstr {
 	TurtleSetY(SsaVarOrConst),
 	TurtleSetZ(SsaVarOrConst),
 	TurtleSetBlock(TypedSsaVar),
+	/// Sets the block at the turtle's position along with its block entity data, given as an SNBT compound
+	TurtleSetBlockNbt { block: TypedSsaVar, nbt: String },
 	TurtleFillBlock { block: SsaVarOrConst, x_span: SsaVarOrConst, y_span: SsaVarOrConst, z_span: SsaVarOrConst },
 	TurtleCopyRegion { x_span: SsaVarOrConst, y_span: SsaVarOrConst, z_span: SsaVarOrConst },
 	TurtlePasteRegionMasked { x_span: SsaVarOrConst, y_span: SsaVarOrConst, z_span: SsaVarOrConst },
 	TurtleGetBlock(TypedSsaVar),
 	TurtleCopy,
 	TurtlePaste,
+	/// Sets the direction the turtle faces, in degrees
+	TurtleFace { yaw: TypedSsaVar, pitch: TypedSsaVar },
+	/// Moves the turtle a number of blocks in the direction it faces, or backwards if negative
+	TurtleForward(TypedSsaVar),
+	/// Turns the turtle around the vertical axis, clockwise when seen from above for positive degrees
+	TurtleTurn { degrees: i32 },
+	/// Moves the turtle forward one block at a time until it's in a block that isn't air or it has moved `max_dist` blocks,
+	/// then gets the block there
+	TurtleRaycast { dst: TypedSsaVar, max_dist: TypedSsaVar },
+	/// The light level from 0 to 15 at the turtle's position
+	TurtleLightLevel(TypedSsaVar),
+	/// 1 if the block at the turtle's position is air, or if `solid` is set, 1 if it's neither air nor a fluid
+	TurtleCheckBlock { dst: TypedSsaVar, solid: bool },
+	/// Plays one of the sounds in `mcinterface.h` at the turtle, with the volume and pitch in hundredths
+	PlaySound { sound: TypedSsaVar, volume: i32, pitch: i32 },
+	/// Shows one of the particles in `mcinterface.h` at the turtle
+	Particle { particle: TypedSsaVar, count: i32 },
+	/// Creates a bossbar, or resets it if it already exists, and shows it to all players
+	BossbarCreate(TypedSsaVar),
+	/// Sets the value of a bossbar: bossbar, value
+	BossbarSetValue(TypedSsaVar, TypedSsaVar),
+	/// Sets the maximum value of a bossbar: bossbar, max
+	BossbarSetMax(TypedSsaVar, TypedSsaVar),
+	/// Sets the name of a bossbar to a string from memory: bossbar, pointer, length
+	BossbarSetName(TypedSsaVar, TypedSsaVar, TypedSsaVar),
+	/// One coordinate of the player nearest to the turtle, with 0, 1, and 2 for x, y, and z
+	NearestPlayerPos { dst: TypedSsaVar, axis: u8 },
+	/// The number of entities matching one of the selectors in `mcinterface.h`
+	EntityCount { dst: TypedSsaVar, selector: TypedSsaVar },
+	/// The number of ticks the world has existed for, as an i64
+	GameTime(TypedSsaVar),
+	/// A random number from 0 up to but not including a positive bound
+	Random { dst: TypedSsaVar, bound: TypedSsaVar },
 	PrintInt(TypedSsaVar),
+	/// Prints an i64 as a chat message
+	PrintLong(TypedSsaVar),
+	/// Prints an f32 or f64 as a chat message
+	PrintFloat(TypedSsaVar),
 	PutChar(TypedSsaVar),
+	/// Prints a string from memory as a chat message: pointer, length
+	PrintStr(TypedSsaVar, TypedSsaVar),
+	/// Prints a printf-style format string from memory as a chat message: format pointer, argument pointer
+	PrintFmt(TypedSsaVar, TypedSsaVar),
+	/// Shows a string from memory as a title, or above the hotbar if `actionbar` is set
+	Title { ptr: TypedSsaVar, len: TypedSsaVar, actionbar: bool },
 	WasiProcExit(TypedSsaVar),
+	/// Writes the iovecs to stdout if the file descriptor is 1 or 2, storing the number of bytes written
+	WasiFdWrite { fd: TypedSsaVar, iovs: TypedSsaVar, iovs_len: TypedSsaVar, nwritten: TypedSsaVar, result: TypedSsaVar },
+	/// A Minecraft command to run as-is
+	RawCommand(String),
+	/// Runs a Minecraft command as-is, storing its result, or 1 if it succeeded and 0 otherwise
+	RawCommandStore { dst: TypedSsaVar, cmd: String, success: bool },
+	/// Calls an export of another datapack compiled by wasmcraft through its `call` function
+	PackCall { namespace: String, export: String, params: Vec<TypedSsaVar>, returns: Vec<TypedSsaVar> },
+
+	// Memory snapshot instructions: region id
+
+	MemorySnapshot(TypedSsaVar),
+	MemoryRestore(TypedSsaVar),
 
+	/// An instruction that isn't supported yet, which traps if it's run
 	Todo(&'static str),
 }
 
@@ -336,6 +456,22 @@ impl SsaInstr {
 			SsaInstr::Eq(_, lhs, rhs) |
 			SsaInstr::Ne(_, lhs, rhs) => lhs.get_var().into_iter().chain(rhs.get_var()).collect(),
 
+			SsaInstr::FAdd(_, lhs, rhs) |
+			SsaInstr::FSub(_, lhs, rhs) |
+			SsaInstr::FMul(_, lhs, rhs) |
+			SsaInstr::FDiv(_, lhs, rhs) |
+			SsaInstr::FMin(_, lhs, rhs) |
+			SsaInstr::FMax(_, lhs, rhs) |
+			SsaInstr::FCopysign(_, lhs, rhs) => vec![*lhs, *rhs],
+
+			SsaInstr::FNeg(_, src) |
+			SsaInstr::FAbs(_, src) |
+			SsaInstr::Sqrt(_, src) |
+			SsaInstr::FFloor(_, src) |
+			SsaInstr::FCeil(_, src) |
+			SsaInstr::FTrunc(_, src) |
+			SsaInstr::FNearest(_, src) => vec![*src],
+
 			SsaInstr::Mul(_, lhs, SsaVarOrConst::Const(_)) |
 			SsaInstr::And(_, lhs, SsaVarOrConst::Const(_)) |
 			SsaInstr::Xor(_, lhs, SsaVarOrConst::Const(_)) |
@@ -380,7 +516,14 @@ impl SsaInstr {
 			SsaInstr::Extend16S(_, src) |
 			SsaInstr::Extend32S(_, src) |
 			SsaInstr::Extend32U(_, src) |
-			SsaInstr::Wrap(_, src) => vec![*src],
+			SsaInstr::Wrap(_, src) |
+			SsaInstr::TruncS(_, src) |
+			SsaInstr::TruncU(_, src) |
+			SsaInstr::TruncSatS(_, src) |
+			SsaInstr::TruncSatU(_, src) |
+			SsaInstr::ConvertS(_, src) |
+			SsaInstr::ConvertU(_, src) |
+			SsaInstr::Reinterpret(_, src) => vec![*src],
 
 			SsaInstr::Select { dst: _, true_var, false_var, cond } => {
 				true_var.get_var().into_iter().chain(false_var.get_var()).chain(vec![*cond]).collect()
@@ -390,9 +533,27 @@ impl SsaInstr {
 				params.iter().copied().chain(Some(*table_entry)).collect()
 			},
 
-			SsaInstr::Memset { dest, value, length, result: _, } => {
+			SsaInstr::Memset { memory: _, dest, value, length, result: _, } => {
 				vec![*dest, *value, *length]
 			}
+			SsaInstr::Memcpy { memory: _, dest, src, length } => vec![*dest, *src, *length],
+
+			SsaInstr::MemoryGrow { memory: _, pages, result: _ } => vec![*pages],
+			SsaInstr::MemorySize(_, _) => Vec::new(),
+
+			SsaInstr::RefNull(_) => Vec::new(),
+			SsaInstr::RefFunc(_, _) => Vec::new(),
+			SsaInstr::TableGet { table_index: _, dst: _, index } => vec![*index],
+			SsaInstr::TableSet { table_index: _, index, value } => vec![*index, *value],
+			SsaInstr::TableGrow { table_index: _, init, count, result: _ } => vec![*init, *count],
+			SsaInstr::TableSize { .. } => Vec::new(),
+			SsaInstr::TableInit { table_index: _, segment: _, dst, src, length } => vec![*dst, *src, *length],
+			SsaInstr::ElemDrop(_) => Vec::new(),
+
+			SsaInstr::BufGetU8(_, ptr, idx) |
+			SsaInstr::BufGetU16(_, ptr, idx) => vec![*ptr, *idx],
+			SsaInstr::BufSetU8(ptr, idx, src) |
+			SsaInstr::BufSetU16(ptr, idx, src) => vec![*ptr, *idx, *src],
 
 			SsaInstr::TurtleSetX(SsaVarOrConst::Var(v)) |
 			SsaInstr::TurtleSetY(SsaVarOrConst::Var(v)) |
@@ -403,6 +564,7 @@ impl SsaInstr {
 			SsaInstr::TurtleSetZ(SsaVarOrConst::Const(_)) => Vec::new(),
 
 			SsaInstr::TurtleSetBlock(b) => vec![*b],
+			SsaInstr::TurtleSetBlockNbt { block, .. } => vec![*block],
 			SsaInstr::TurtleFillBlock { block, x_span, y_span, z_span } => {
 				let mut result = Vec::new();
 				result.extend(block.get_var());
@@ -428,9 +590,36 @@ impl SsaInstr {
 			SsaInstr::TurtleGetBlock(_) => Vec::new(),
 			SsaInstr::TurtleCopy => Vec::new(),
 			SsaInstr::TurtlePaste => Vec::new(),
+			SsaInstr::TurtleFace { yaw, pitch } => vec![*yaw, *pitch],
+			SsaInstr::TurtleForward(blocks) => vec![*blocks],
+			SsaInstr::TurtleTurn { .. } => Vec::new(),
+			SsaInstr::TurtleRaycast { dst: _, max_dist } => vec![*max_dist],
+			SsaInstr::TurtleLightLevel(_) => Vec::new(),
+			SsaInstr::TurtleCheckBlock { .. } => Vec::new(),
+			SsaInstr::PlaySound { sound, .. } => vec![*sound],
+			SsaInstr::Particle { particle, .. } => vec![*particle],
+			SsaInstr::BossbarCreate(bar) => vec![*bar],
+			SsaInstr::BossbarSetValue(bar, value) => vec![*bar, *value],
+			SsaInstr::BossbarSetMax(bar, max) => vec![*bar, *max],
+			SsaInstr::BossbarSetName(bar, ptr, len) => vec![*bar, *ptr, *len],
+			SsaInstr::NearestPlayerPos { .. } => Vec::new(),
+			SsaInstr::EntityCount { dst: _, selector } => vec![*selector],
+			SsaInstr::GameTime(_) => Vec::new(),
+			SsaInstr::Random { dst: _, bound } => vec![*bound],
 			SsaInstr::PrintInt(i) => vec![*i],
+			SsaInstr::PrintLong(i) => vec![*i],
+			SsaInstr::PrintFloat(f) => vec![*f],
 			SsaInstr::PutChar(i) => vec![*i],
+			SsaInstr::PrintStr(ptr, len) => vec![*ptr, *len],
+			SsaInstr::PrintFmt(fmt, args) => vec![*fmt, *args],
+			SsaInstr::Title { ptr, len, .. } => vec![*ptr, *len],
 			SsaInstr::WasiProcExit(i) => vec![*i],
+			SsaInstr::WasiFdWrite { fd, iovs, iovs_len, nwritten, result: _ } => vec![*fd, *iovs, *iovs_len, *nwritten],
+			SsaInstr::RawCommand(_) => Vec::new(),
+			SsaInstr::RawCommandStore { .. } => Vec::new(),
+			SsaInstr::PackCall { params, .. } => params.clone(),
+			SsaInstr::MemorySnapshot(r) => vec![*r],
+			SsaInstr::MemoryRestore(r) => vec![*r],
 			SsaInstr::Todo(_) => Vec::new(),
 		}
 	}
@@ -468,6 +657,22 @@ impl SsaInstr {
 			SsaInstr::Eq(dst, _, _) |
 			SsaInstr::Ne(dst, _, _) => vec![*dst],
 
+			SsaInstr::FAdd(dst, _, _) |
+			SsaInstr::FSub(dst, _, _) |
+			SsaInstr::FMul(dst, _, _) |
+			SsaInstr::FDiv(dst, _, _) |
+			SsaInstr::FMin(dst, _, _) |
+			SsaInstr::FMax(dst, _, _) |
+			SsaInstr::FCopysign(dst, _, _) => vec![*dst],
+
+			SsaInstr::FNeg(dst, _) |
+			SsaInstr::FAbs(dst, _) |
+			SsaInstr::Sqrt(dst, _) |
+			SsaInstr::FFloor(dst, _) |
+			SsaInstr::FCeil(dst, _) |
+			SsaInstr::FTrunc(dst, _) |
+			SsaInstr::FNearest(dst, _) => vec![*dst],
+
 			SsaInstr::Popcnt(dst, _) |
 			SsaInstr::Clz(dst, _) |
 			SsaInstr::Ctz(dst, _) => vec![*dst],
@@ -499,29 +704,82 @@ impl SsaInstr {
 			SsaInstr::Extend16S(dst, _) |
 			SsaInstr::Extend32S(dst, _) |
 			SsaInstr::Extend32U(dst, _) |
-			SsaInstr::Wrap(dst, _) => vec![*dst],
+			SsaInstr::Wrap(dst, _) |
+			SsaInstr::TruncS(dst, _) |
+			SsaInstr::TruncU(dst, _) |
+			SsaInstr::TruncSatS(dst, _) |
+			SsaInstr::TruncSatU(dst, _) |
+			SsaInstr::ConvertS(dst, _) |
+			SsaInstr::ConvertU(dst, _) |
+			SsaInstr::Reinterpret(dst, _) => vec![*dst],
 
 			SsaInstr::Select { dst, true_var: _, false_var: _, cond: _ } => vec![*dst],
 			SsaInstr::Call { function_index: _, params: _, returns } => returns.clone(),
 			SsaInstr::CallIndirect { returns, .. } => returns.clone(),
 
-			SsaInstr::Memset { dest: _, value: _, length: _, result, } => {
+			SsaInstr::Memset { memory: _, dest: _, value: _, length: _, result, } => {
 				vec![*result]
 			}
+			SsaInstr::Memcpy { .. } => Vec::new(),
+
+			SsaInstr::MemoryGrow { memory: _, pages: _, result } => vec![*result],
+			SsaInstr::MemorySize(_, dst) => vec![*dst],
+
+			SsaInstr::RefNull(dst) => vec![*dst],
+			SsaInstr::RefFunc(dst, _) => vec![*dst],
+			SsaInstr::TableGet { table_index: _, dst, index: _ } => vec![*dst],
+			SsaInstr::TableSet { .. } => Vec::new(),
+			SsaInstr::TableGrow { table_index: _, init: _, count: _, result } => vec![*result],
+			SsaInstr::TableSize { table_index: _, dst } => vec![*dst],
+			SsaInstr::TableInit { .. } => Vec::new(),
+			SsaInstr::ElemDrop(_) => Vec::new(),
+
+			SsaInstr::BufGetU8(dst, _, _) |
+			SsaInstr::BufGetU16(dst, _, _) => vec![*dst],
+			SsaInstr::BufSetU8(_, _, _) |
+			SsaInstr::BufSetU16(_, _, _) => vec![],
 
 			SsaInstr::TurtleSetX(_) => Vec::new(),
 			SsaInstr::TurtleSetY(_) => Vec::new(),
 			SsaInstr::TurtleSetZ(_) => Vec::new(),
 			SsaInstr::TurtleSetBlock(_) => Vec::new(),
+			SsaInstr::TurtleSetBlockNbt { .. } => Vec::new(),
 			SsaInstr::TurtleFillBlock { .. } => Vec::new(),
 			SsaInstr::TurtleCopyRegion { .. } => Vec::new(),
 			SsaInstr::TurtlePasteRegionMasked { .. } => Vec::new(),
 			SsaInstr::TurtleGetBlock(b) => vec![*b],
 			SsaInstr::TurtleCopy => Vec::new(),
 			SsaInstr::TurtlePaste => Vec::new(),
+			SsaInstr::TurtleFace { .. } => Vec::new(),
+			SsaInstr::TurtleForward(_) => Vec::new(),
+			SsaInstr::TurtleTurn { .. } => Vec::new(),
+			SsaInstr::TurtleRaycast { dst, .. } => vec![*dst],
+			SsaInstr::TurtleLightLevel(dst) => vec![*dst],
+			SsaInstr::TurtleCheckBlock { dst, .. } => vec![*dst],
+			SsaInstr::PlaySound { .. } => Vec::new(),
+			SsaInstr::Particle { .. } => Vec::new(),
+			SsaInstr::BossbarCreate(_) => Vec::new(),
+			SsaInstr::BossbarSetValue(_, _) => Vec::new(),
+			SsaInstr::BossbarSetMax(_, _) => Vec::new(),
+			SsaInstr::BossbarSetName(_, _, _) => Vec::new(),
+			SsaInstr::NearestPlayerPos { dst, .. } => vec![*dst],
+			SsaInstr::EntityCount { dst, .. } => vec![*dst],
+			SsaInstr::GameTime(dst) => vec![*dst],
+			SsaInstr::Random { dst, .. } => vec![*dst],
 			SsaInstr::PrintInt(_) => Vec::new(),
+			SsaInstr::PrintLong(_) => Vec::new(),
+			SsaInstr::PrintFloat(_) => Vec::new(),
 			SsaInstr::PutChar(_) => Vec::new(),
+			SsaInstr::PrintStr(_, _) => Vec::new(),
+			SsaInstr::PrintFmt(_, _) => Vec::new(),
+			SsaInstr::Title { .. } => Vec::new(),
 			SsaInstr::WasiProcExit(_) => Vec::new(),
+			SsaInstr::WasiFdWrite { result, .. } => vec![*result],
+			SsaInstr::RawCommand(_) => Vec::new(),
+			SsaInstr::RawCommandStore { dst, .. } => vec![*dst],
+			SsaInstr::PackCall { returns, .. } => returns.clone(),
+			SsaInstr::MemorySnapshot(_) => Vec::new(),
+			SsaInstr::MemoryRestore(_) => Vec::new(),
 			SsaInstr::Todo(_) => Vec::new(),
 		}
 	}
@@ -556,6 +814,20 @@ impl SsaInstr {
 			SsaInstr::LeU(_, _, _) |
 			SsaInstr::Eq(_, _, _) |
 			SsaInstr::Ne(_, _, _) |
+			SsaInstr::FAdd(_, _, _) |
+			SsaInstr::FSub(_, _, _) |
+			SsaInstr::FMul(_, _, _) |
+			SsaInstr::FDiv(_, _, _) |
+			SsaInstr::FMin(_, _, _) |
+			SsaInstr::FMax(_, _, _) |
+			SsaInstr::FCopysign(_, _, _) |
+			SsaInstr::FNeg(_, _) |
+			SsaInstr::FAbs(_, _) |
+			SsaInstr::Sqrt(_, _) |
+			SsaInstr::FFloor(_, _) |
+			SsaInstr::FCeil(_, _) |
+			SsaInstr::FTrunc(_, _) |
+			SsaInstr::FNearest(_, _) |
 			SsaInstr::Popcnt(_, _) |
 			SsaInstr::Clz(_, _) |
 			SsaInstr::Ctz(_, _) |
@@ -572,6 +844,10 @@ impl SsaInstr {
 			SsaInstr::Store32(_, _, _) |
 			SsaInstr::Store16(_, _, _) |
 			SsaInstr::Store8(_, _, _) |
+			SsaInstr::BufGetU8(_, _, _) |
+			SsaInstr::BufGetU16(_, _, _) |
+			SsaInstr::BufSetU8(_, _, _) |
+			SsaInstr::BufSetU16(_, _, _) |
 			SsaInstr::GlobalSet(_, _) |
 			SsaInstr::GlobalGet(_, _) |
 			SsaInstr::LocalSet(_, _) |
@@ -583,24 +859,70 @@ impl SsaInstr {
 			SsaInstr::Extend32S(_, _) |
 			SsaInstr::Extend32U(_, _) |
 			SsaInstr::Wrap(_, _) |
-			SsaInstr::Select { .. } => false,
+			SsaInstr::TruncS(_, _) |
+			SsaInstr::TruncU(_, _) |
+			SsaInstr::TruncSatS(_, _) |
+			SsaInstr::TruncSatU(_, _) |
+			SsaInstr::ConvertS(_, _) |
+			SsaInstr::ConvertU(_, _) |
+			SsaInstr::Reinterpret(_, _) |
+			SsaInstr::Select { .. } |
+			SsaInstr::MemorySize(_, _) |
+			SsaInstr::RefNull(_) |
+			SsaInstr::RefFunc(_, _) |
+			SsaInstr::TableGet { .. } |
+			SsaInstr::TableSize { .. } => false,
 
 			SsaInstr::Call { .. } |
 			SsaInstr::CallIndirect { .. } |
 			SsaInstr::Memset { .. } |
+			SsaInstr::Memcpy { .. } |
+			SsaInstr::MemoryGrow { .. } |
+			SsaInstr::TableSet { .. } |
+			SsaInstr::TableGrow { .. } |
+			SsaInstr::TableInit { .. } |
+			SsaInstr::ElemDrop(_) |
 			SsaInstr::TurtleSetX(_) |
 			SsaInstr::TurtleSetY(_) |
 			SsaInstr::TurtleSetZ(_) |
 			SsaInstr::TurtleSetBlock(_) |
+			SsaInstr::TurtleSetBlockNbt { .. } |
 			SsaInstr::TurtleFillBlock { .. } |
 			SsaInstr::TurtleCopyRegion { .. } |
 			SsaInstr::TurtlePasteRegionMasked { .. } |
 			SsaInstr::TurtleGetBlock(_) |
 			SsaInstr::TurtleCopy |
 			SsaInstr::TurtlePaste |
+			SsaInstr::TurtleFace { .. } |
+			SsaInstr::TurtleForward(_) |
+			SsaInstr::TurtleTurn { .. } |
+			SsaInstr::TurtleRaycast { .. } |
+			SsaInstr::TurtleLightLevel(_) |
+			SsaInstr::TurtleCheckBlock { .. } |
+			SsaInstr::PlaySound { .. } |
+			SsaInstr::Particle { .. } |
+			SsaInstr::BossbarCreate(_) |
+			SsaInstr::BossbarSetValue(_, _) |
+			SsaInstr::BossbarSetMax(_, _) |
+			SsaInstr::BossbarSetName(_, _, _) |
+			SsaInstr::NearestPlayerPos { .. } |
+			SsaInstr::EntityCount { .. } |
+			SsaInstr::GameTime(_) |
+			SsaInstr::Random { .. } |
 			SsaInstr::PrintInt(_) |
+			SsaInstr::PrintLong(_) |
+			SsaInstr::PrintFloat(_) |
 			SsaInstr::PutChar(_) |
+			SsaInstr::PrintStr(_, _) |
+			SsaInstr::PrintFmt(_, _) |
+			SsaInstr::Title { .. } |
 			SsaInstr::WasiProcExit(_) |
+			SsaInstr::WasiFdWrite { .. } |
+			SsaInstr::RawCommand(_) |
+			SsaInstr::RawCommandStore { .. } |
+			SsaInstr::PackCall { .. } |
+			SsaInstr::MemorySnapshot(_) |
+			SsaInstr::MemoryRestore(_) |
 			SsaInstr::Todo(_) => true,
 		}
 
@@ -671,6 +993,7 @@ impl SsaInstr {
 			SsaInstr::Add(dst, _, SsaVarOrConst::Var(rhs)) if dst.ty() == ValType::I32 => vec![(dst, rhs)],
 
 			SsaInstr::Assign(dst, SsaVarOrConst::Var(src)) => vec![(dst, src)],
+			SsaInstr::Reinterpret(dst, src) => vec![(dst, src)],
 
 			SsaInstr::Mul(dst, lhs, _rhs) if dst.ty() == ValType::I32 => vec![(dst, lhs), /*(dst, rhs)*/],
 			SsaInstr::ShrS(dst, lhs, _) if dst.ty() == ValType::I32 => vec![(dst, lhs)],
@@ -696,8 +1019,61 @@ pub struct JumpTarget {
 	pub params: Vec<TypedSsaVar>,
 }
 
+/// Why a program stopped running because of a trap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrapKind {
+	/// An `unreachable` instruction was run
+	Unreachable,
+	/// A `call_indirect` found no function at its table index
+	UninitializedElement,
+	/// A `call_indirect` found a function of another type at its table index
+	IndirectCallTypeMismatch,
+	/// A table was accessed out of its bounds
+	TableOutOfBounds,
+	/// An integer division or remainder had a divisor of zero
+	DivByZero,
+	/// A call went deeper than the call stack allows
+	CallStackExhausted,
+	/// An instruction checked by `--overflow-checks` overflowed
+	IntegerOverflow,
+	/// An instruction that isn't supported yet was run
+	Todo(&'static str),
+}
+
+impl TrapKind {
+	/// The value the generated datapack stores in the trap holder once the program traps this way, which is never 0.
+	pub fn code(self) -> i32 {
+		match self {
+			TrapKind::Unreachable => 1,
+			TrapKind::UninitializedElement => 2,
+			TrapKind::TableOutOfBounds => 3,
+			TrapKind::Todo(_) => 4,
+			TrapKind::DivByZero => 5,
+			TrapKind::IndirectCallTypeMismatch => 6,
+			TrapKind::CallStackExhausted => 7,
+			TrapKind::IntegerOverflow => 8,
+		}
+	}
+}
+
+impl fmt::Display for TrapKind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			TrapKind::Unreachable => write!(f, "unreachable"),
+			TrapKind::UninitializedElement => write!(f, "uninitialized element"),
+			TrapKind::IndirectCallTypeMismatch => write!(f, "indirect call type mismatch"),
+			TrapKind::TableOutOfBounds => write!(f, "out of bounds table access"),
+			TrapKind::Todo(msg) => write!(f, "unsupported instruction ({msg})"),
+			TrapKind::DivByZero => write!(f, "integer divide by zero"),
+			TrapKind::CallStackExhausted => write!(f, "call stack exhausted"),
+			TrapKind::IntegerOverflow => write!(f, "integer overflow"),
+		}
+	}
+}
+
 #[derive(Debug, Clone)]
 pub enum SsaTerminator {
+	/// Traps if it's run, which also ends blocks that are never reached
 	Unreachable,
 	ScheduleJump(JumpTarget, u32),
 	Jump(JumpTarget),
@@ -788,6 +1164,20 @@ impl SsaTerminator {
 
 	}
 
+	/// Returns mutable references to every jump target of this terminator.
+	pub fn targets_mut(&mut self) -> Vec<&mut JumpTarget> {
+		match self {
+			SsaTerminator::Unreachable | SsaTerminator::Return(_) => Vec::new(),
+			SsaTerminator::Jump(t) | SsaTerminator::ScheduleJump(t, _) => vec![t],
+			SsaTerminator::BranchIf { cond: _, true_target, false_target } => vec![true_target, false_target],
+			SsaTerminator::BranchTable { cond: _, default, arms } => {
+				let mut result = vec![default];
+				result.extend(arms.iter_mut());
+				result
+			}
+		}
+	}
+
 	pub fn successors(&self) -> Vec<BlockId> {
 		match self {
 			SsaTerminator::Unreachable => Vec::new(),
@@ -810,6 +1200,9 @@ pub struct SsaFunction {
 	pub code: LocalBlockMap<SsaBasicBlock>,
 	pub params: Box<[ValType]>,
 	pub returns: Box<[ValType]>,
+	/// The wasm code each block was converted from, as offsets into the code section.
+	/// Blocks that didn't come straight from the module, like ones the linker adds, have none.
+	pub code_ranges: HashMap<BlockId, Vec<Range<usize>>>,
 }
 
 impl SsaFunction {
@@ -818,7 +1211,7 @@ impl SsaFunction {
 	{
 		let code = blocks.into_iter().collect();
 
-		SsaFunction { code, params, returns }
+		SsaFunction { code, params, returns, code_ranges: HashMap::new() }
 	}
 
 	pub fn iter<'a>(&'a self) -> impl Iterator<Item=(BlockId, &'a SsaBasicBlock)> + 'a {
@@ -846,6 +1239,23 @@ impl SsaFunction {
 		BlockId { func, block: 0 }
 	}
 
+	/// Renumbers the blocks of this function so their IDs are densely packed,
+	/// updating every jump to match. The entry point keeps its ID.
+	pub fn renumber_blocks(&mut self) {
+		let mapping = self.code.renumber();
+		assert_eq!(mapping.get(&self.entry_point_id()), Some(&self.entry_point_id()));
+
+		for (_, block) in self.code.iter_mut() {
+			for target in block.term.targets_mut() {
+				target.label = mapping[&target.label];
+			}
+		}
+
+		self.code_ranges = std::mem::take(&mut self.code_ranges).into_iter()
+			.filter_map(|(block_id, ranges)| Some((*mapping.get(&block_id)?, ranges)))
+			.collect();
+	}
+
 	pub fn coalescable_term_vars(&self, source_id: BlockId) -> Vec<(TypedSsaVar, TypedSsaVar)> {
 		let mut result = Vec::new();
 		let source = self.get(source_id);
@@ -882,8 +1292,19 @@ pub struct SsaProgram {
 	pub globals: Vec<TypedValue>,
 	pub memory: Vec<Memory>,
 	pub tables: Vec<Table>,
+	/// The functions in each element segment, for `table.init`.
+	/// Only passive segments have any, since the others are dropped once the module is instantiated.
+	pub elem_segments: Vec<Vec<Option<usize>>>,
 	pub code: Vec<SsaFunction>,
 	pub exports: HashMap<String, BlockId>,
+	/// The function from the start section, which is run once the program is initialized.
+	pub start: Option<BlockId>,
+	/// Names of functions from the wasm `name` section, used to make the output easier to debug.
+	pub func_names: HashMap<usize, String>,
+	/// The regions of the first memory that `memory_snapshot` and `memory_restore` copy, indexed by their ID.
+	pub snapshot_regions: Vec<crate::SnapshotRegion>,
+	/// What integer division and remainder by zero do. The interpreters follow it so they agree with the datapack.
+	pub div_by_zero: crate::DivByZero,
 }
 
 impl SsaProgram {
@@ -895,6 +1316,9 @@ impl SsaProgram {
 pub struct Memory {
 	pub data: Vec<u8>,
 	pub maximum: Option<usize>,
+	/// If this memory is imported from another program, the byte ranges this program initializes.
+	/// Everything else is initialized by the program that exports the memory.
+	pub imported: Option<Vec<std::ops::Range<usize>>>,
 }
 
 impl Memory {
@@ -902,9 +1326,34 @@ impl Memory {
 		Memory {
 			data: vec![0; 65536 * initial],
 			maximum,
+			imported: None,
 		}
 	}
 
+	pub fn new_imported(initial: usize, maximum: Option<usize>) -> Memory {
+		Memory {
+			imported: Some(Vec::new()),
+			..Memory::new(initial, maximum)
+		}
+	}
+
+	/// Records that this program initializes the given bytes.
+	pub fn mark_initialized(&mut self, range: std::ops::Range<usize>) {
+		if let Some(ranges) = &mut self.imported {
+			ranges.push(range);
+		}
+	}
+
+	/// The words of imported memory this program initializes, as their address and a mask of the bytes in them it initializes,
+	/// with bit 0 for the lowest byte. Words it initializes only some bytes of keep the rest of what the exporting program put there.
+	pub fn initialized_words(&self) -> BTreeMap<usize, u8> {
+		let mut words = BTreeMap::new();
+		for addr in self.imported.iter().flatten().flat_map(|range| range.clone()) {
+			*words.entry(addr & !3).or_insert(0) |= 1 << (addr % 4);
+		}
+		words
+	}
+
 	pub fn store(&mut self, addr: usize, bytes: &[u8]) {
 		let dest = &mut self.data[addr..][..bytes.len()];
 		dest.copy_from_slice(bytes);
@@ -913,10 +1362,172 @@ impl Memory {
 	pub fn load(&self, addr: usize, len: usize) -> &[u8] {
 		&self.data[addr..][..len]
 	}
+
+	/// The current size of this memory in pages.
+	pub fn num_pages(&self) -> usize {
+		self.data.len() / 65536
+	}
+
+	/// The size in pages this memory can't grow past.
+	pub fn max_pages(&self) -> usize {
+		self.maximum.unwrap_or(65536)
+	}
+
+	/// Grows this memory by the given number of zeroed pages, returning the old size in pages,
+	/// or `None` if the new size would be larger than the maximum.
+	pub fn grow(&mut self, pages: usize) -> Option<usize> {
+		let old_pages = self.num_pages();
+		let new_pages = old_pages.checked_add(pages)?;
+		if new_pages > self.max_pages() {
+			return None;
+		}
+
+		self.data.resize(new_pages * 65536, 0);
+		Some(old_pages)
+	}
+
+	/// Formats a null-terminated printf-style string, supporting `%d`, `%u`, `%x`, `%c`, `%s`, and `%%`.
+	/// Each argument takes up one word in the argument array, like a `va_list` in wasm32.
+	/// Any other conversion is printed as-is.
+	pub fn print_fmt(&self, fmt: i32, args: i32) -> Vec<u8> {
+		let load_u32 = |addr: usize| u32::from_le_bytes(self.load(addr, 4).try_into().unwrap());
+		let load_c_str = |addr: usize| self.data[addr..].iter().copied().take_while(|&b| b != 0);
+
+		let mut output = Vec::new();
+		let mut chars = load_c_str(fmt as u32 as usize);
+		let mut args = args as u32 as usize;
+		while let Some(c) = chars.next() {
+			if c != b'%' {
+				output.push(c);
+				continue;
+			}
+
+			let conv = match chars.next() {
+				Some(conv) => conv,
+				None => break,
+			};
+			if matches!(conv, b'd' | b'u' | b'x' | b'c' | b's') {
+				let arg = load_u32(args);
+				args += 4;
+
+				match conv {
+					b'd' => output.extend((arg as i32).to_string().bytes()),
+					b'u' => output.extend(arg.to_string().bytes()),
+					b'x' => output.extend(format!("{arg:x}").bytes()),
+					b'c' => output.push(arg as u8),
+					b's' => output.extend(load_c_str(arg as usize)),
+					_ => unreachable!(),
+				}
+			} else if conv == b'%' {
+				output.push(b'%');
+			} else {
+				output.extend([b'%', conv]);
+			}
+		}
+
+		output
+	}
+
+	/// Implements WASI's `fd_write` for stdout and stderr, returning the bytes that were written and the errno.
+	/// Any other file descriptor gives `EBADF`.
+	pub fn wasi_fd_write(&mut self, fd: i32, iovs: i32, iovs_len: i32, nwritten: i32) -> (Vec<u8>, i32) {
+		const ERRNO_BADF: i32 = 8;
+
+		if !matches!(fd, 1 | 2) {
+			return (Vec::new(), ERRNO_BADF);
+		}
+
+		let load_u32 = |mem: &Memory, addr: usize| u32::from_le_bytes(mem.load(addr, 4).try_into().unwrap()) as usize;
+
+		let mut output = Vec::new();
+		for iov in 0..iovs_len as u32 as usize {
+			let iov = iovs as u32 as usize + iov * 8;
+			let (buf, len) = (load_u32(self, iov), load_u32(self, iov + 4));
+			output.extend_from_slice(self.load(buf, len));
+		}
+
+		self.store(nwritten as u32 as usize, &(output.len() as u32).to_le_bytes());
+		(output, 0)
+	}
+}
+
+/// Formats a float the same way the `intrinsic:print_f64` routine does,
+/// with six digits after the decimal point, rounded towards zero.
+/// Numbers of 2^63 or more and nonzero numbers below 2^-14 are scaled to between 1 and 10 first,
+/// and the power of ten they were scaled by is added after an `e`.
+pub fn format_float(value: f64) -> String {
+	let sign = if value.is_sign_negative() { "-" } else { "" };
+	let mut value = value.abs();
+	if value.is_nan() {
+		return format!("{sign}nan");
+	} else if value.is_infinite() {
+		return format!("{sign}inf");
+	}
+
+	// These use the same operations as the datapack, so rounding errors from scaling are the same too
+	let mut exp = 0;
+	if value >= 2.0f64.powi(63) {
+		while value >= 10.0 {
+			value /= 10.0;
+			exp += 1;
+		}
+	} else if value != 0.0 && value < 2.0f64.powi(-14) {
+		while value < 1.0 {
+			value *= 10.0;
+			exp -= 1;
+		}
+	}
+
+	let int = value.trunc() as u64;
+	let frac = ((value - int as f64) * 1e6).trunc() as u32;
+	if exp == 0 {
+		format!("{sign}{int}.{frac:06}")
+	} else {
+		format!("{sign}{int}.{frac:06}e{exp}")
+	}
 }
 
 #[derive(Debug)]
 pub struct Table {
 	pub max: Option<usize>,
 	pub elements: Vec<Option<usize>>,
+	/// If the table is written at runtime, every function that could be stored in it.
+	/// Its entries then live in the datapack instead of being fixed at compile time.
+	pub runtime_funcs: Option<Vec<usize>>,
+}
+
+impl Table {
+	/// The function a `call_indirect` through this table calls for each possible operand.
+	/// For tables written at runtime, the operand is the function reference stored in the entry
+	/// instead of the element index.
+	pub fn call_targets(&self) -> Vec<Option<usize>> {
+		match &self.runtime_funcs {
+			Some(funcs) => {
+				let mut targets = vec![None; funcs.iter().max().map_or(0, |f| f + 2)];
+				for &func in funcs.iter() {
+					targets[func + 1] = Some(func);
+				}
+				targets
+			}
+			None => self.elements.clone(),
+		}
+	}
+
+	/// The size this table can't grow past.
+	pub fn capacity(&self) -> usize {
+		self.max.unwrap_or(self.elements.len())
+	}
+
+	/// Grows this table by the given number of entries holding `init`, returning the old size,
+	/// or `None` if the new size would be larger than the capacity.
+	pub fn grow(&mut self, count: usize, init: Option<usize>) -> Option<usize> {
+		let old_size = self.elements.len();
+		let new_size = old_size.checked_add(count)?;
+		if new_size > self.capacity() {
+			return None;
+		}
+
+		self.elements.resize(new_size, init);
+		Some(old_size)
+	}
 }
@@ -1,6 +1,6 @@
-use std::collections::{HashSet, HashMap};
+use std::collections::{BTreeSet, HashMap};
 
-use crate::{lir::{Register, DoubleRegister}};
+use crate::{lir::{Register, DoubleRegister}, remarks::Remarks};
 
 use super::{SsaFunction, SsaVar, liveness::{NoopLivenessInfo, LivenessInfo, FullLivenessInfo}, TypedSsaVar, BlockId};
 
@@ -19,18 +19,18 @@ pub trait RegAlloc {
 
 	fn get_temp_double(&mut self) -> DoubleRegister;
 
-	fn const_pool(&self) -> &HashSet<i32>;
+	fn const_pool(&self) -> &BTreeSet<i32>;
 }
 
 pub struct NoopRegAlloc {
-	pub const_pool: HashSet<i32>,
+	pub const_pool: BTreeSet<i32>,
 	func: u32,
 	temp: u32,
 }
 
 impl NoopRegAlloc {
 	pub fn analyze(func: &SsaFunction) -> Self {
-		NoopRegAlloc { const_pool: HashSet::new(), func: func.iter().next().unwrap().0.func as u32, temp: 1000 }
+		NoopRegAlloc { const_pool: BTreeSet::new(), func: func.iter().next().unwrap().0.func as u32, temp: 1000 }
 	}
 }
 
@@ -66,13 +66,13 @@ impl RegAlloc for NoopRegAlloc {
 		reg
 	}
 
-	fn const_pool(&self) -> &HashSet<i32> {
+	fn const_pool(&self) -> &BTreeSet<i32> {
 		&self.const_pool
 	}
 }
 
 pub struct FullRegAlloc {
-	pub const_pool: HashSet<i32>,
+	pub const_pool: BTreeSet<i32>,
 	pub map: HashMap<SsaVar, u32>,
 	func: u32,
 	temp: u32,
@@ -251,7 +251,7 @@ mod register_set {
 
 use register_set::*;
 
-fn try_merge_term(sets: &mut RegisterSet, block_id: BlockId, dst: &TypedSsaVar, src: &TypedSsaVar, func: &SsaFunction, interf_graph: &InterfGraph) {
+fn try_merge_term(sets: &mut RegisterSet, block_id: BlockId, dst: &TypedSsaVar, src: &TypedSsaVar, func: &SsaFunction, interf_graph: &InterfGraph, remarks: &Remarks) {
 	if sets.get_idx(*dst) == sets.get_idx(*src) {
 		return;
 	}
@@ -268,6 +268,7 @@ fn try_merge_term(sets: &mut RegisterSet, block_id: BlockId, dst: &TypedSsaVar,
 	}
 
 	if interf_graph.interferes(dst_set, src_set) {
+		remarks.emit("regalloc", block_id.func, Some(block_id.block), || format!("could not coalesce {:?}/{:?}: interference", dst, src));
 		return;
 	}
 
@@ -276,6 +277,7 @@ fn try_merge_term(sets: &mut RegisterSet, block_id: BlockId, dst: &TypedSsaVar,
 	for succ_block in block.term.successors() {
 		if dst_set.live_range.0.get(succ_block).unwrap().live_in {
 			// This variable isn't actually defined by the terminator, so don't try to coalesce it.
+			remarks.emit("regalloc", block_id.func, Some(block_id.block), || format!("could not coalesce {:?}/{:?}: live into successor", dst, src));
 			return
 		}
 
@@ -300,10 +302,12 @@ fn try_merge_term(sets: &mut RegisterSet, block_id: BlockId, dst: &TypedSsaVar,
 		}*/
 
 		sets.merge(*dst, *src);
+	} else {
+		remarks.emit("regalloc", block_id.func, Some(block_id.block), || format!("could not coalesce {:?}/{:?}: live ranges overlap", dst, src));
 	}
 }
 
-fn try_merge(sets: &mut RegisterSet, block_id: BlockId, instr_idx: usize, dst: &TypedSsaVar, src: &TypedSsaVar, interf_graph: &InterfGraph) {
+fn try_merge(sets: &mut RegisterSet, block_id: BlockId, instr_idx: usize, dst: &TypedSsaVar, src: &TypedSsaVar, interf_graph: &InterfGraph, remarks: &Remarks) {
 	if sets.get_idx(*dst) == sets.get_idx(*src) {
 		return;
 	}
@@ -312,6 +316,7 @@ fn try_merge(sets: &mut RegisterSet, block_id: BlockId, instr_idx: usize, dst: &
 	let src_set = sets.get(*src);
 
 	if interf_graph.interferes(dst_set, src_set) {
+		remarks.emit("regalloc", block_id.func, Some(block_id.block), || format!("could not coalesce {:?}/{:?} at instruction {}: interference", dst, src, instr_idx));
 		return;
 	}
 
@@ -336,11 +341,13 @@ fn try_merge(sets: &mut RegisterSet, block_id: BlockId, instr_idx: usize, dst: &
 		}
 
 		sets.merge(*dst, *src);
+	} else {
+		remarks.emit("regalloc", block_id.func, Some(block_id.block), || format!("could not coalesce {:?}/{:?} at instruction {}: live ranges overlap", dst, src, instr_idx));
 	}
 }
 
 impl FullRegAlloc {
-	pub fn analyze(func: &SsaFunction) -> Self {
+	pub fn analyze(func: &SsaFunction, remarks: &Remarks) -> Self {
 		//println!("Starting regalloc for {}", func.func_id());
 
 		let interf_graph = InterfGraph::new(func);
@@ -357,18 +364,19 @@ impl FullRegAlloc {
 					assert!(uses.contains(src));
 					assert!(defs.contains(dst));
 
-					try_merge(&mut sets, block_id, instr_idx, dst, src, &interf_graph);
+					try_merge(&mut sets, block_id, instr_idx, dst, src, &interf_graph, remarks);
 				}
 			}
 
 			for (dst, src) in func.coalescable_term_vars(block_id) {
-				try_merge_term(&mut sets, block_id, &dst, &src, func, &interf_graph);
+				try_merge_term(&mut sets, block_id, &dst, &src, func, &interf_graph, remarks);
 			}
 		}
 
 		println!("Coalesced func {} into {} registers", func.func_id(), sets.len());
+		remarks.emit("regalloc", func.func_id() as usize, None, || format!("coalesced into {} registers", sets.len()));
 
-		FullRegAlloc { const_pool: HashSet::new(), map: sets.to_map(), func: func.func_id(), temp: 1000 }
+		FullRegAlloc { const_pool: BTreeSet::new(), map: sets.to_map(), func: func.func_id(), temp: 1000 }
 	}
 }
 
@@ -406,7 +414,7 @@ impl RegAlloc for FullRegAlloc {
 		reg
 	}
 
-	fn const_pool(&self) -> &HashSet<i32> {
+	fn const_pool(&self) -> &BTreeSet<i32> {
 		&self.const_pool
 	}
 }
@@ -415,7 +423,7 @@ impl RegAlloc for FullRegAlloc {
 mod test {
 	use wasmparser::ValType;
 
-	use crate::ssa::{SsaBasicBlock, TypedSsaVar, SsaInstr, SsaTerminator, JumpTarget, BlockId, SsaFunction};
+	use crate::{remarks::Remarks, ssa::{SsaBasicBlock, TypedSsaVar, SsaInstr, SsaTerminator, JumpTarget, BlockId, SsaFunction}};
 
 	use super::{FullRegAlloc, RegAlloc};
 
@@ -481,7 +489,7 @@ mod test {
 			Box::new([ValType::I32]),
 		);
 
-		let reg_alloc = FullRegAlloc::analyze(&func);
+		let reg_alloc = FullRegAlloc::analyze(&func, &Remarks::default());
 
 		let reg0 = reg_alloc.get(r0.into_untyped());
 		let reg1 = reg_alloc.get(r1.into_untyped());
@@ -527,7 +535,7 @@ mod test {
 			Box::new([ValType::I32]),
 		);
 
-		let reg_alloc = FullRegAlloc::analyze(&func);
+		let reg_alloc = FullRegAlloc::analyze(&func, &Remarks::default());
 
 		println!("{:?}", reg_alloc.map);
 		panic!();
@@ -2,11 +2,14 @@
 //!
 //! WebAssembly local variables are entirely removed in this stage.
 
-use std::{ops::{Index, IndexMut}, collections::HashMap};
+use std::{ops::{Index, IndexMut, Range}, collections::{HashMap, HashSet, BTreeSet}};
 
 use wasmparser::{Operator, MemoryImmediate, DataKind, ElementKind, ElementItem, ExternalKind, ValType, FuncType};
 
-use crate::{wasm_file::{WasmFile, eval_const_expr_single}, ssa::{SsaBasicBlock, BlockId, SsaTerminator, TypedSsaVar, SsaInstr, SsaVarAlloc, JumpTarget, SsaProgram, SsaFunction, Memory, Table, SsaVarOrConst}, CompileContext};
+use crate::{wasm_file::{WasmFile, eval_const_expr_single}, ssa::{SsaBasicBlock, BlockId, SsaTerminator, TypedSsaVar, SsaInstr, SsaVarAlloc, JumpTarget, SsaProgram, SsaFunction, Memory, Table, SsaVarOrConst, interp::TypedValue}, CompileContext};
+
+/// Creates a load or store from its memory immediate, value, and address.
+type MemoryInstrFn = fn(MemoryImmediate, TypedSsaVar, SsaVarOrConst) -> SsaInstr;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum UncertainVar {
@@ -423,7 +426,10 @@ macro_rules! zip_vars {
 }
 
 struct ValidationState<'a> {
+	ctx: &'a CompileContext,
 	wasm_file: &'a WasmFile<'a>,
+	/// Imported functions that another module linked into the program provides
+	linked_funcs: &'a HashSet<usize>,
 	func: usize,
 	builder: SsaFuncBuilder,
 	alloc: SsaVarAlloc,
@@ -434,11 +440,41 @@ impl ValidationState<'_> {
 	pub fn visit_operator(&mut self, op: &Operator) {
 		let locals = self.wasm_file.func_locals(self.func);
 
+		let ctx = self.ctx;
 		let wasm_file = &self.wasm_file;
+		let linked_funcs = self.linked_funcs;
 		let builder = &mut self.builder;
 		let alloc = &mut self.alloc;
 		let validator = &mut self.validator;
 
+		fn make_float_binop<F>(f: F, ty: ValType, builder: &mut SsaFuncBuilder, validator: &mut Validator, alloc: &mut SsaVarAlloc)
+			where
+				F: FnOnce(TypedSsaVar, TypedSsaVar, TypedSsaVar) -> SsaInstr,
+		{
+			let rhs = validator.pop_value_ty(ty.into());
+			let lhs = validator.pop_value_ty(ty.into());
+
+			let dst = alloc.new_typed(ty);
+			validator.push_value(dst);
+
+			if let Some((lhs, rhs)) = zip_vars!(lhs, rhs) {
+				builder.current_block_mut().body.push(f(dst, lhs, rhs));
+			}
+		}
+
+		fn make_float_unaryop<F>(f: F, ty: ValType, builder: &mut SsaFuncBuilder, validator: &mut Validator, alloc: &mut SsaVarAlloc)
+			where
+				F: FnOnce(TypedSsaVar, TypedSsaVar) -> SsaInstr,
+		{
+			let src = validator.pop_value_ty(ty.into());
+			let dst = alloc.new_typed(ty);
+			validator.push_value(dst);
+
+			if let Some(src) = src.into() {
+				builder.current_block_mut().body.push(f(dst, src));
+			}
+		}
+
 		fn make_i32_binop<F, L, R>(f: F, builder: &mut SsaFuncBuilder, validator: &mut Validator, alloc: &mut SsaVarAlloc)
 			where
 				F: FnOnce(TypedSsaVar, L, R) -> SsaInstr,
@@ -614,6 +650,88 @@ impl ValidationState<'_> {
 			}
 		}
 
+		// Execution is single-threaded, so atomic read-modify-write instructions are just a load and a store.
+		// Narrow accesses zero-extend the old value and truncate the new one.
+		#[allow(clippy::too_many_arguments)]
+		fn make_atomic_rmw<F, L, R>(f: F, ty: ValType, load: MemoryInstrFn, store: MemoryInstrFn, memarg: MemoryImmediate, builder: &mut SsaFuncBuilder, validator: &mut Validator, alloc: &mut SsaVarAlloc)
+			where
+				F: FnOnce(TypedSsaVar, L, R) -> SsaInstr,
+				L: From<TypedSsaVar>,
+				R: From<TypedSsaVar>,
+		{
+			let value = validator.pop_value_ty(ty.into());
+			let addr = validator.pop_value_ty(ValType::I32.into());
+
+			let old = alloc.new_typed(ty);
+			validator.push_value(old);
+
+			if let Some((value, addr)) = zip_vars!(value, addr) {
+				let new = alloc.new_typed(ty);
+
+				let body = &mut builder.current_block_mut().body;
+				body.push(load(memarg, old, addr.into()));
+				body.push(f(new, old.into(), value.into()));
+				body.push(store(memarg, new, addr.into()));
+			}
+		}
+
+		fn atomic_xchg(dst: TypedSsaVar, _old: TypedSsaVar, value: TypedSsaVar) -> SsaInstr {
+			SsaInstr::Assign(dst, value.into())
+		}
+
+		// The expected value is wrapped to the width of the access before it is compared
+		#[allow(clippy::too_many_arguments)]
+		fn make_atomic_cmpxchg(ty: ValType, mask: Option<i64>, load: MemoryInstrFn, store: MemoryInstrFn, memarg: MemoryImmediate, builder: &mut SsaFuncBuilder, validator: &mut Validator, alloc: &mut SsaVarAlloc) {
+			let replacement = validator.pop_value_ty(ty.into());
+			let expected = validator.pop_value_ty(ty.into());
+			let addr = validator.pop_value_ty(ValType::I32.into());
+
+			let old = alloc.new_typed(ty);
+			validator.push_value(old);
+
+			if let Some((replacement, expected, addr)) = zip_vars!(replacement, expected, addr) {
+				let body = &mut builder.current_block_mut().body;
+				body.push(load(memarg, old, addr.into()));
+
+				let expected = if let Some(mask) = mask {
+					let mask = if ty == ValType::I32 { TypedValue::I32(mask as i32) } else { TypedValue::I64(mask) };
+					let wrapped = alloc.new_typed(ty);
+					body.push(SsaInstr::And(wrapped, expected, mask.into()));
+					wrapped
+				} else {
+					expected
+				};
+
+				let is_equal = alloc.new_i32();
+				body.push(SsaInstr::Eq(is_equal, old.into(), expected.into()));
+
+				let new = alloc.new_typed(ty);
+				body.push(SsaInstr::Select { dst: new, true_var: replacement.into(), false_var: old.into(), cond: is_equal });
+				body.push(store(memarg, new, addr.into()));
+			}
+		}
+
+		// Nothing else can ever change the value, so instead of blocking forever this times out right away.
+		// Returns 1 ("not-equal") if the value differs, or 2 ("timed-out") if it matches.
+		fn make_atomic_wait(ty: ValType, load: MemoryInstrFn, memarg: MemoryImmediate, builder: &mut SsaFuncBuilder, validator: &mut Validator, alloc: &mut SsaVarAlloc) {
+			let _timeout = validator.pop_value_ty(ValType::I64.into());
+			let expected = validator.pop_value_ty(ty.into());
+			let addr = validator.pop_value_ty(ValType::I32.into());
+
+			let dst = alloc.new_i32();
+			validator.push_value(dst);
+
+			if let Some((expected, addr)) = zip_vars!(expected, addr) {
+				let old = alloc.new_typed(ty);
+				let is_equal = alloc.new_i32();
+
+				let body = &mut builder.current_block_mut().body;
+				body.push(load(memarg, old, addr.into()));
+				body.push(SsaInstr::Eq(is_equal, old.into(), expected.into()));
+				body.push(SsaInstr::Select { dst, true_var: TypedValue::I32(2).into(), false_var: TypedValue::I32(1).into(), cond: is_equal });
+			}
+		}
+
 		fn make_i32_extend<F>(f: F, builder: &mut SsaFuncBuilder, validator: &mut Validator, alloc: &mut SsaVarAlloc)
 			where
 				F: FnOnce(TypedSsaVar, TypedSsaVar) -> SsaInstr,
@@ -640,6 +758,19 @@ impl ValidationState<'_> {
 			}
 		}
 
+		fn make_conversion<F>(f: F, src_ty: ValType, dst_ty: ValType, builder: &mut SsaFuncBuilder, validator: &mut Validator, alloc: &mut SsaVarAlloc)
+			where
+				F: FnOnce(TypedSsaVar, TypedSsaVar) -> SsaInstr,
+		{
+			let src = validator.pop_value_ty(src_ty.into());
+			let dst = alloc.new_typed(dst_ty);
+			validator.push_value(dst);
+
+			if validator.reachable() {
+				builder.current_block_mut().body.push(f(dst, src.unwrap()));
+			}
+		}
+
 		match op {
 			&Operator::I32Const { value } => {
 				let var = alloc.new_i32();
@@ -711,6 +842,36 @@ impl ValidationState<'_> {
 			Operator::I64And => make_i64_binop(SsaInstr::And, builder, validator, alloc),
 			Operator::I64Or => make_i64_binop(SsaInstr::Or, builder, validator, alloc),
 
+			Operator::F32Add => make_float_binop(SsaInstr::FAdd, ValType::F32, builder, validator, alloc),
+			Operator::F32Sub => make_float_binop(SsaInstr::FSub, ValType::F32, builder, validator, alloc),
+			Operator::F32Mul => make_float_binop(SsaInstr::FMul, ValType::F32, builder, validator, alloc),
+			Operator::F32Div => make_float_binop(SsaInstr::FDiv, ValType::F32, builder, validator, alloc),
+			Operator::F32Min => make_float_binop(SsaInstr::FMin, ValType::F32, builder, validator, alloc),
+			Operator::F32Max => make_float_binop(SsaInstr::FMax, ValType::F32, builder, validator, alloc),
+			Operator::F32Copysign => make_float_binop(SsaInstr::FCopysign, ValType::F32, builder, validator, alloc),
+			Operator::F32Neg => make_float_unaryop(SsaInstr::FNeg, ValType::F32, builder, validator, alloc),
+			Operator::F32Abs => make_float_unaryop(SsaInstr::FAbs, ValType::F32, builder, validator, alloc),
+			Operator::F32Sqrt => make_float_unaryop(SsaInstr::Sqrt, ValType::F32, builder, validator, alloc),
+			Operator::F32Floor => make_float_unaryop(SsaInstr::FFloor, ValType::F32, builder, validator, alloc),
+			Operator::F32Ceil => make_float_unaryop(SsaInstr::FCeil, ValType::F32, builder, validator, alloc),
+			Operator::F32Trunc => make_float_unaryop(SsaInstr::FTrunc, ValType::F32, builder, validator, alloc),
+			Operator::F32Nearest => make_float_unaryop(SsaInstr::FNearest, ValType::F32, builder, validator, alloc),
+
+			Operator::F64Add => make_float_binop(SsaInstr::FAdd, ValType::F64, builder, validator, alloc),
+			Operator::F64Sub => make_float_binop(SsaInstr::FSub, ValType::F64, builder, validator, alloc),
+			Operator::F64Mul => make_float_binop(SsaInstr::FMul, ValType::F64, builder, validator, alloc),
+			Operator::F64Div => make_float_binop(SsaInstr::FDiv, ValType::F64, builder, validator, alloc),
+			Operator::F64Min => make_float_binop(SsaInstr::FMin, ValType::F64, builder, validator, alloc),
+			Operator::F64Max => make_float_binop(SsaInstr::FMax, ValType::F64, builder, validator, alloc),
+			Operator::F64Copysign => make_float_binop(SsaInstr::FCopysign, ValType::F64, builder, validator, alloc),
+			Operator::F64Neg => make_float_unaryop(SsaInstr::FNeg, ValType::F64, builder, validator, alloc),
+			Operator::F64Abs => make_float_unaryop(SsaInstr::FAbs, ValType::F64, builder, validator, alloc),
+			Operator::F64Sqrt => make_float_unaryop(SsaInstr::Sqrt, ValType::F64, builder, validator, alloc),
+			Operator::F64Floor => make_float_unaryop(SsaInstr::FFloor, ValType::F64, builder, validator, alloc),
+			Operator::F64Ceil => make_float_unaryop(SsaInstr::FCeil, ValType::F64, builder, validator, alloc),
+			Operator::F64Trunc => make_float_unaryop(SsaInstr::FTrunc, ValType::F64, builder, validator, alloc),
+			Operator::F64Nearest => make_float_unaryop(SsaInstr::FNearest, ValType::F64, builder, validator, alloc),
+
 			Operator::I32Popcnt => make_i32_unaryop(SsaInstr::Popcnt, builder, validator, alloc),
 			Operator::I32Clz => make_i32_unaryop(SsaInstr::Clz, builder, validator, alloc),
 			Operator::I32Ctz => make_i32_unaryop(SsaInstr::Ctz, builder, validator, alloc),
@@ -761,6 +922,38 @@ impl ValidationState<'_> {
 				validator.push_value(dst);
 			},
 
+			Operator::I32TruncF32S => make_conversion(SsaInstr::TruncS, ValType::F32, ValType::I32, builder, validator, alloc),
+			Operator::I32TruncF32U => make_conversion(SsaInstr::TruncU, ValType::F32, ValType::I32, builder, validator, alloc),
+			Operator::I32TruncF64S => make_conversion(SsaInstr::TruncS, ValType::F64, ValType::I32, builder, validator, alloc),
+			Operator::I32TruncF64U => make_conversion(SsaInstr::TruncU, ValType::F64, ValType::I32, builder, validator, alloc),
+			Operator::I64TruncF32S => make_conversion(SsaInstr::TruncS, ValType::F32, ValType::I64, builder, validator, alloc),
+			Operator::I64TruncF32U => make_conversion(SsaInstr::TruncU, ValType::F32, ValType::I64, builder, validator, alloc),
+			Operator::I64TruncF64S => make_conversion(SsaInstr::TruncS, ValType::F64, ValType::I64, builder, validator, alloc),
+			Operator::I64TruncF64U => make_conversion(SsaInstr::TruncU, ValType::F64, ValType::I64, builder, validator, alloc),
+
+			Operator::I32TruncSatF32S => make_conversion(SsaInstr::TruncSatS, ValType::F32, ValType::I32, builder, validator, alloc),
+			Operator::I32TruncSatF32U => make_conversion(SsaInstr::TruncSatU, ValType::F32, ValType::I32, builder, validator, alloc),
+			Operator::I32TruncSatF64S => make_conversion(SsaInstr::TruncSatS, ValType::F64, ValType::I32, builder, validator, alloc),
+			Operator::I32TruncSatF64U => make_conversion(SsaInstr::TruncSatU, ValType::F64, ValType::I32, builder, validator, alloc),
+			Operator::I64TruncSatF32S => make_conversion(SsaInstr::TruncSatS, ValType::F32, ValType::I64, builder, validator, alloc),
+			Operator::I64TruncSatF32U => make_conversion(SsaInstr::TruncSatU, ValType::F32, ValType::I64, builder, validator, alloc),
+			Operator::I64TruncSatF64S => make_conversion(SsaInstr::TruncSatS, ValType::F64, ValType::I64, builder, validator, alloc),
+			Operator::I64TruncSatF64U => make_conversion(SsaInstr::TruncSatU, ValType::F64, ValType::I64, builder, validator, alloc),
+
+			Operator::F32ConvertI32S => make_conversion(SsaInstr::ConvertS, ValType::I32, ValType::F32, builder, validator, alloc),
+			Operator::F32ConvertI32U => make_conversion(SsaInstr::ConvertU, ValType::I32, ValType::F32, builder, validator, alloc),
+			Operator::F32ConvertI64S => make_conversion(SsaInstr::ConvertS, ValType::I64, ValType::F32, builder, validator, alloc),
+			Operator::F32ConvertI64U => make_conversion(SsaInstr::ConvertU, ValType::I64, ValType::F32, builder, validator, alloc),
+			Operator::F64ConvertI32S => make_conversion(SsaInstr::ConvertS, ValType::I32, ValType::F64, builder, validator, alloc),
+			Operator::F64ConvertI32U => make_conversion(SsaInstr::ConvertU, ValType::I32, ValType::F64, builder, validator, alloc),
+			Operator::F64ConvertI64S => make_conversion(SsaInstr::ConvertS, ValType::I64, ValType::F64, builder, validator, alloc),
+			Operator::F64ConvertI64U => make_conversion(SsaInstr::ConvertU, ValType::I64, ValType::F64, builder, validator, alloc),
+
+			Operator::I32ReinterpretF32 => make_conversion(SsaInstr::Reinterpret, ValType::F32, ValType::I32, builder, validator, alloc),
+			Operator::I64ReinterpretF64 => make_conversion(SsaInstr::Reinterpret, ValType::F64, ValType::I64, builder, validator, alloc),
+			Operator::F32ReinterpretI32 => make_conversion(SsaInstr::Reinterpret, ValType::I32, ValType::F32, builder, validator, alloc),
+			Operator::F64ReinterpretI64 => make_conversion(SsaInstr::Reinterpret, ValType::I64, ValType::F64, builder, validator, alloc),
+
 			&Operator::I32Load { memarg } => make_i32_load(SsaInstr::Load32S, memarg, builder, validator, alloc),
 			&Operator::I32Load16S { memarg } => make_i32_load(SsaInstr::Load16S, memarg, builder, validator, alloc),
 			&Operator::I32Load16U { memarg } => make_i32_load(SsaInstr::Load16U, memarg, builder, validator, alloc),
@@ -792,8 +985,96 @@ impl ValidationState<'_> {
 
 			&Operator::F64Store { memarg } => make_f64_store(SsaInstr::Store64, memarg, builder, validator, alloc),
 
+			&Operator::I32AtomicLoad { memarg } => make_i32_load(SsaInstr::Load32S, memarg, builder, validator, alloc),
+			&Operator::I32AtomicLoad16U { memarg } => make_i32_load(SsaInstr::Load16U, memarg, builder, validator, alloc),
+			&Operator::I32AtomicLoad8U { memarg } => make_i32_load(SsaInstr::Load8U, memarg, builder, validator, alloc),
+
+			&Operator::I64AtomicLoad { memarg } => make_i64_load(SsaInstr::Load64, memarg, builder, validator, alloc),
+			&Operator::I64AtomicLoad32U { memarg } => make_i64_load(SsaInstr::Load32U, memarg, builder, validator, alloc),
+			&Operator::I64AtomicLoad16U { memarg } => make_i64_load(SsaInstr::Load16U, memarg, builder, validator, alloc),
+			&Operator::I64AtomicLoad8U { memarg } => make_i64_load(SsaInstr::Load8U, memarg, builder, validator, alloc),
+
+			&Operator::I32AtomicStore { memarg } => make_i32_store(SsaInstr::Store32, memarg, builder, validator, alloc),
+			&Operator::I32AtomicStore16 { memarg } => make_i32_store(SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I32AtomicStore8 { memarg } => make_i32_store(SsaInstr::Store8, memarg, builder, validator, alloc),
+
+			&Operator::I64AtomicStore { memarg } => make_i64_store(SsaInstr::Store64, memarg, builder, validator, alloc),
+			&Operator::I64AtomicStore32 { memarg } => make_i64_store(SsaInstr::Store32, memarg, builder, validator, alloc),
+			&Operator::I64AtomicStore16 { memarg } => make_i64_store(SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicStore8 { memarg } => make_i64_store(SsaInstr::Store8, memarg, builder, validator, alloc),
+
+			&Operator::I32AtomicRmwAdd { memarg } => make_atomic_rmw(SsaInstr::Add, ValType::I32, SsaInstr::Load32S, SsaInstr::Store32, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw8AddU { memarg } => make_atomic_rmw(SsaInstr::Add, ValType::I32, SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw16AddU { memarg } => make_atomic_rmw(SsaInstr::Add, ValType::I32, SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmwAdd { memarg } => make_atomic_rmw(SsaInstr::Add, ValType::I64, SsaInstr::Load64, SsaInstr::Store64, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw8AddU { memarg } => make_atomic_rmw(SsaInstr::Add, ValType::I64, SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw16AddU { memarg } => make_atomic_rmw(SsaInstr::Add, ValType::I64, SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw32AddU { memarg } => make_atomic_rmw(SsaInstr::Add, ValType::I64, SsaInstr::Load32U, SsaInstr::Store32, memarg, builder, validator, alloc),
+
+			&Operator::I32AtomicRmwSub { memarg } => make_atomic_rmw(SsaInstr::Sub, ValType::I32, SsaInstr::Load32S, SsaInstr::Store32, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw8SubU { memarg } => make_atomic_rmw(SsaInstr::Sub, ValType::I32, SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw16SubU { memarg } => make_atomic_rmw(SsaInstr::Sub, ValType::I32, SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmwSub { memarg } => make_atomic_rmw(SsaInstr::Sub, ValType::I64, SsaInstr::Load64, SsaInstr::Store64, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw8SubU { memarg } => make_atomic_rmw(SsaInstr::Sub, ValType::I64, SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw16SubU { memarg } => make_atomic_rmw(SsaInstr::Sub, ValType::I64, SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw32SubU { memarg } => make_atomic_rmw(SsaInstr::Sub, ValType::I64, SsaInstr::Load32U, SsaInstr::Store32, memarg, builder, validator, alloc),
+
+			&Operator::I32AtomicRmwAnd { memarg } => make_atomic_rmw(SsaInstr::And, ValType::I32, SsaInstr::Load32S, SsaInstr::Store32, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw8AndU { memarg } => make_atomic_rmw(SsaInstr::And, ValType::I32, SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw16AndU { memarg } => make_atomic_rmw(SsaInstr::And, ValType::I32, SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmwAnd { memarg } => make_atomic_rmw(SsaInstr::And, ValType::I64, SsaInstr::Load64, SsaInstr::Store64, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw8AndU { memarg } => make_atomic_rmw(SsaInstr::And, ValType::I64, SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw16AndU { memarg } => make_atomic_rmw(SsaInstr::And, ValType::I64, SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw32AndU { memarg } => make_atomic_rmw(SsaInstr::And, ValType::I64, SsaInstr::Load32U, SsaInstr::Store32, memarg, builder, validator, alloc),
+
+			&Operator::I32AtomicRmwOr { memarg } => make_atomic_rmw(SsaInstr::Or, ValType::I32, SsaInstr::Load32S, SsaInstr::Store32, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw8OrU { memarg } => make_atomic_rmw(SsaInstr::Or, ValType::I32, SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw16OrU { memarg } => make_atomic_rmw(SsaInstr::Or, ValType::I32, SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmwOr { memarg } => make_atomic_rmw(SsaInstr::Or, ValType::I64, SsaInstr::Load64, SsaInstr::Store64, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw8OrU { memarg } => make_atomic_rmw(SsaInstr::Or, ValType::I64, SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw16OrU { memarg } => make_atomic_rmw(SsaInstr::Or, ValType::I64, SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw32OrU { memarg } => make_atomic_rmw(SsaInstr::Or, ValType::I64, SsaInstr::Load32U, SsaInstr::Store32, memarg, builder, validator, alloc),
+
+			&Operator::I32AtomicRmwXor { memarg } => make_atomic_rmw(SsaInstr::Xor, ValType::I32, SsaInstr::Load32S, SsaInstr::Store32, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw8XorU { memarg } => make_atomic_rmw(SsaInstr::Xor, ValType::I32, SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw16XorU { memarg } => make_atomic_rmw(SsaInstr::Xor, ValType::I32, SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmwXor { memarg } => make_atomic_rmw(SsaInstr::Xor, ValType::I64, SsaInstr::Load64, SsaInstr::Store64, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw8XorU { memarg } => make_atomic_rmw(SsaInstr::Xor, ValType::I64, SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw16XorU { memarg } => make_atomic_rmw(SsaInstr::Xor, ValType::I64, SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw32XorU { memarg } => make_atomic_rmw(SsaInstr::Xor, ValType::I64, SsaInstr::Load32U, SsaInstr::Store32, memarg, builder, validator, alloc),
+
+			&Operator::I32AtomicRmwXchg { memarg } => make_atomic_rmw(atomic_xchg, ValType::I32, SsaInstr::Load32S, SsaInstr::Store32, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw8XchgU { memarg } => make_atomic_rmw(atomic_xchg, ValType::I32, SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw16XchgU { memarg } => make_atomic_rmw(atomic_xchg, ValType::I32, SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmwXchg { memarg } => make_atomic_rmw(atomic_xchg, ValType::I64, SsaInstr::Load64, SsaInstr::Store64, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw8XchgU { memarg } => make_atomic_rmw(atomic_xchg, ValType::I64, SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw16XchgU { memarg } => make_atomic_rmw(atomic_xchg, ValType::I64, SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw32XchgU { memarg } => make_atomic_rmw(atomic_xchg, ValType::I64, SsaInstr::Load32U, SsaInstr::Store32, memarg, builder, validator, alloc),
+
+			&Operator::I32AtomicRmwCmpxchg { memarg } => make_atomic_cmpxchg(ValType::I32, None, SsaInstr::Load32S, SsaInstr::Store32, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw8CmpxchgU { memarg } => make_atomic_cmpxchg(ValType::I32, Some(0xFF), SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I32AtomicRmw16CmpxchgU { memarg } => make_atomic_cmpxchg(ValType::I32, Some(0xFFFF), SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmwCmpxchg { memarg } => make_atomic_cmpxchg(ValType::I64, None, SsaInstr::Load64, SsaInstr::Store64, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw8CmpxchgU { memarg } => make_atomic_cmpxchg(ValType::I64, Some(0xFF), SsaInstr::Load8U, SsaInstr::Store8, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw16CmpxchgU { memarg } => make_atomic_cmpxchg(ValType::I64, Some(0xFFFF), SsaInstr::Load16U, SsaInstr::Store16, memarg, builder, validator, alloc),
+			&Operator::I64AtomicRmw32CmpxchgU { memarg } => make_atomic_cmpxchg(ValType::I64, Some(0xFFFF_FFFF), SsaInstr::Load32U, SsaInstr::Store32, memarg, builder, validator, alloc),
+
+			&Operator::MemoryAtomicWait32 { memarg } => make_atomic_wait(ValType::I32, SsaInstr::Load32S, memarg, builder, validator, alloc),
+			&Operator::MemoryAtomicWait64 { memarg } => make_atomic_wait(ValType::I64, SsaInstr::Load64, memarg, builder, validator, alloc),
+			Operator::MemoryAtomicNotify { .. } => {
+				// There are never any waiting threads to wake up
+				let _count = validator.pop_value_ty(ValType::I32.into());
+				let _addr = validator.pop_value_ty(ValType::I32.into());
+
+				let dst = alloc.new_i32();
+				validator.push_value(dst);
+
+				builder.current_block_mut().body.push(SsaInstr::I32Set(dst, 0));
+			}
+			Operator::AtomicFence { .. } => {}
+
 			&Operator::GlobalSet { global_index } => {
-				let ty = wasm_file.global(global_index).ty;
+				let ty = wasm_file.global_type(global_index);
 				assert!(ty.mutable);
 				let ty = ty.content_type;
 				let src = validator.pop_value_ty(ty.into());
@@ -802,7 +1083,7 @@ impl ValidationState<'_> {
 				}
 			}
 			&Operator::GlobalGet { global_index } => {
-				let ty = wasm_file.global(global_index).ty.content_type;
+				let ty = wasm_file.global_type(global_index).content_type;
 				let dst = alloc.new_typed(ty);
 				builder.current_block_mut().body.push(SsaInstr::GlobalGet(dst, global_index));
 				validator.push_value(dst);
@@ -876,9 +1157,26 @@ impl ValidationState<'_> {
 				validator.push_values(&returns);
 
 				if let Some(params) = params {
-					if !wasm_file.func_is_defined(function_index as usize) {
+					if !wasm_file.func_is_defined(function_index as usize) && !linked_funcs.contains(&(function_index as usize)) {
 						let import = wasm_file.func_import(function_index as usize);
-						match (import.module, import.field) {
+						match ctx.map_import(import.module, import.field) {
+							(module, field) if ctx.imports.contains(module, field) => {
+								let lowering = ctx.imports.get(module, field).unwrap();
+								lowering(ImportCall {
+									params: &params,
+									returns: &returns,
+									alloc,
+									body: &mut builder.current_block_mut().body,
+								});
+							}
+							(module, field) if ctx.pack_imports.contains_key(module) => {
+								builder.current_block_mut().body.push(SsaInstr::PackCall {
+									namespace: ctx.pack_imports[module].clone(),
+									export: field.to_string(),
+									params: params.clone(),
+									returns: returns.clone(),
+								});
+							}
 							("env", "turtle_x") => {
 								assert_eq!(params.len(), 1);
 								assert_eq!(returns.len(), 0);
@@ -899,6 +1197,13 @@ impl ValidationState<'_> {
 								assert_eq!(returns.len(), 0);
 								builder.current_block_mut().body.push(SsaInstr::TurtleSetBlock(params[0]));
 							}
+							("env", "turtle_set_block_nbt") => {
+								assert_eq!(params.len(), 3);
+								assert_eq!(returns.len(), 0);
+
+								let nbt = get_const_nbt("turtle_set_block_nbt", wasm_file, &builder.current_block_mut().body, params[1], params[2]);
+								builder.current_block_mut().body.push(SsaInstr::TurtleSetBlockNbt { block: params[0], nbt });
+							}
 							("env", "turtle_fill") => {
 								assert_eq!(params.len(), 4);
 								assert_eq!(returns.len(), 0);
@@ -929,6 +1234,97 @@ impl ValidationState<'_> {
 								assert_eq!(returns.len(), 1);
 								builder.current_block_mut().body.push(SsaInstr::TurtleGetBlock(returns[0]));
 							}
+							("env", "mc_playsound") => {
+								assert_eq!(params.len(), 3);
+								assert_eq!(returns.len(), 0);
+								let body = &builder.current_block_mut().body;
+								let volume = get_const_param("mc_playsound", "volume", body, params[1]);
+								let pitch = get_const_param("mc_playsound", "pitch", body, params[2]);
+								builder.current_block_mut().body.push(SsaInstr::PlaySound { sound: params[0], volume, pitch });
+							}
+							("env", "mc_particle") => {
+								assert_eq!(params.len(), 2);
+								assert_eq!(returns.len(), 0);
+								let count = get_const_param("mc_particle", "count", &builder.current_block_mut().body, params[1]);
+								builder.current_block_mut().body.push(SsaInstr::Particle { particle: params[0], count });
+							}
+							("env", "mc_bossbar_create") => {
+								assert_eq!(params.len(), 1);
+								assert_eq!(returns.len(), 0);
+								builder.current_block_mut().body.push(SsaInstr::BossbarCreate(params[0]));
+							}
+							("env", "mc_bossbar_value") => {
+								assert_eq!(params.len(), 2);
+								assert_eq!(returns.len(), 0);
+								builder.current_block_mut().body.push(SsaInstr::BossbarSetValue(params[0], params[1]));
+							}
+							("env", "mc_bossbar_max") => {
+								assert_eq!(params.len(), 2);
+								assert_eq!(returns.len(), 0);
+								builder.current_block_mut().body.push(SsaInstr::BossbarSetMax(params[0], params[1]));
+							}
+							("env", "mc_bossbar_name") => {
+								assert_eq!(params.len(), 3);
+								assert_eq!(returns.len(), 0);
+								builder.current_block_mut().body.push(SsaInstr::BossbarSetName(params[0], params[1], params[2]));
+							}
+							("env", "turtle_face") => {
+								assert_eq!(params.len(), 2);
+								assert_eq!(returns.len(), 0);
+								builder.current_block_mut().body.push(SsaInstr::TurtleFace { yaw: params[0], pitch: params[1] });
+							}
+							("env", "turtle_forward") => {
+								assert_eq!(params.len(), 1);
+								assert_eq!(returns.len(), 0);
+								builder.current_block_mut().body.push(SsaInstr::TurtleForward(params[0]));
+							}
+							("env", field @ ("turtle_turn_left" | "turtle_turn_right")) => {
+								assert_eq!(params.len(), 0);
+								assert_eq!(returns.len(), 0);
+								let degrees = if field == "turtle_turn_right" { 90 } else { -90 };
+								builder.current_block_mut().body.push(SsaInstr::TurtleTurn { degrees });
+							}
+							("env", "turtle_raycast") => {
+								assert_eq!(params.len(), 1);
+								assert_eq!(returns.len(), 1);
+								builder.current_block_mut().body.push(SsaInstr::TurtleRaycast { dst: returns[0], max_dist: params[0] });
+							}
+							("env", "turtle_light_level") => {
+								assert_eq!(params.len(), 0);
+								assert_eq!(returns.len(), 1);
+								builder.current_block_mut().body.push(SsaInstr::TurtleLightLevel(returns[0]));
+							}
+							("env", field @ ("turtle_is_air" | "turtle_is_solid")) => {
+								assert_eq!(params.len(), 0);
+								assert_eq!(returns.len(), 1);
+								let solid = field == "turtle_is_solid";
+								builder.current_block_mut().body.push(SsaInstr::TurtleCheckBlock { dst: returns[0], solid });
+							}
+							("env", field @ ("nearest_player_x" | "nearest_player_y" | "nearest_player_z")) => {
+								assert_eq!(params.len(), 0);
+								assert_eq!(returns.len(), 1);
+								let axis = match field {
+									"nearest_player_x" => 0,
+									"nearest_player_y" => 1,
+									_ => 2,
+								};
+								builder.current_block_mut().body.push(SsaInstr::NearestPlayerPos { dst: returns[0], axis });
+							}
+							("env", "entity_count") => {
+								assert_eq!(params.len(), 1);
+								assert_eq!(returns.len(), 1);
+								builder.current_block_mut().body.push(SsaInstr::EntityCount { dst: returns[0], selector: params[0] });
+							}
+							("env", "mc_gametime") => {
+								assert_eq!(params.len(), 0);
+								assert_eq!(returns.len(), 1);
+								builder.current_block_mut().body.push(SsaInstr::GameTime(returns[0]));
+							}
+							("env", "mc_random") => {
+								assert_eq!(params.len(), 1);
+								assert_eq!(returns.len(), 1);
+								builder.current_block_mut().body.push(SsaInstr::Random { dst: returns[0], bound: params[0] });
+							}
 							("env", "turtle_copy") => {
 								assert_eq!(params.len(), 0);
 								assert_eq!(returns.len(), 0);
@@ -942,17 +1338,25 @@ impl ValidationState<'_> {
 							("env", "memset") => {
 								assert_eq!(params.len(), 3);
 								assert_eq!(returns.len(), 1);
-								let instr = SsaInstr::Memset { dest: params[0], value: params[1], length: params[2], result: returns[0] };
+								let instr = SsaInstr::Memset { memory: 0, dest: params[0], value: params[1], length: params[2], result: returns[0] };
 								builder.current_block_mut().body.push(instr);
 							}
-							("env", "sleep" | "mc_sleep") => {
-								assert_eq!(params.len(), 0);
+							("env", field @ ("sleep" | "mc_sleep" | "mc_sleep_ticks")) => {
+								assert_eq!(params.len(), (field == "mc_sleep_ticks") as usize);
 								assert_eq!(returns.len(), 0);
 
+								let ticks = match params.first() {
+									Some(&ticks) => get_const_param(field, "number of ticks", &builder.current_block_mut().body, ticks),
+									None => 1,
+								};
+								if ticks == 0 {
+									panic!("`{field}` has to sleep for at least one tick");
+								}
+
 								let next_block = builder.alloc_block();
 
 								let target = JumpTarget { label: next_block, params: Vec::new() };
-								builder.finish_block(SsaTerminator::ScheduleJump(target, 1));
+								builder.finish_block(SsaTerminator::ScheduleJump(target, ticks as u32));
 
 								builder.set_block(next_block);
 							}
@@ -962,20 +1366,120 @@ impl ValidationState<'_> {
 
 								builder.current_block_mut().body.push(SsaInstr::PrintInt(params[0]));
 							}
+							("env", "print_long") => {
+								assert_eq!(params.len(), 1);
+								assert_eq!(returns.len(), 0);
+								assert_eq!(params[0].ty(), ValType::I64);
+
+								builder.current_block_mut().body.push(SsaInstr::PrintLong(params[0]));
+							}
+							("env", "print_float" | "print_double") => {
+								assert_eq!(params.len(), 1);
+								assert_eq!(returns.len(), 0);
+								assert!(matches!(params[0].ty(), ValType::F32 | ValType::F64));
+
+								builder.current_block_mut().body.push(SsaInstr::PrintFloat(params[0]));
+							}
 							("env", "mc_putc") => {
 								assert_eq!(params.len(), 1);
 								assert_eq!(returns.len(), 0);
 
 								builder.current_block_mut().body.push(SsaInstr::PutChar(params[0]));
 							}
+							("env", "print_str") => {
+								assert_eq!(params.len(), 2);
+								assert_eq!(returns.len(), 0);
+
+								builder.current_block_mut().body.push(SsaInstr::PrintStr(params[0], params[1]));
+							}
+							("env", field @ ("mc_title" | "mc_actionbar")) => {
+								assert_eq!(params.len(), 2);
+								assert_eq!(returns.len(), 0);
+
+								let actionbar = field == "mc_actionbar";
+								builder.current_block_mut().body.push(SsaInstr::Title { ptr: params[0], len: params[1], actionbar });
+							}
+							("env", "print_fmt") => {
+								assert_eq!(params.len(), 2);
+								assert_eq!(returns.len(), 0);
+
+								builder.current_block_mut().body.push(SsaInstr::PrintFmt(params[0], params[1]));
+							}
+							("env", field @ ("buf_get_u8" | "buf_get_u16")) => {
+								assert_eq!(params.len(), 2);
+								assert_eq!(returns.len(), 1);
+
+								let instr = if field == "buf_get_u8" { SsaInstr::BufGetU8 } else { SsaInstr::BufGetU16 };
+								builder.current_block_mut().body.push(instr(returns[0], params[0], params[1]));
+							}
+							("env", field @ ("buf_set_u8" | "buf_set_u16")) => {
+								assert_eq!(params.len(), 3);
+								assert_eq!(returns.len(), 0);
+
+								let instr = if field == "buf_set_u8" { SsaInstr::BufSetU8 } else { SsaInstr::BufSetU16 };
+								builder.current_block_mut().body.push(instr(params[0], params[1], params[2]));
+							}
+							("env", "mc_command") => {
+								assert_eq!(params.len(), 2);
+								assert_eq!(returns.len(), 0);
+
+								let cmd = get_const_command("mc_command", wasm_file, &builder.current_block_mut().body, params[0], params[1]);
+								builder.current_block_mut().body.push(SsaInstr::RawCommand(cmd));
+							}
+							("env", field @ ("mc_command_result" | "mc_command_success")) => {
+								assert_eq!(params.len(), 2);
+								assert_eq!(returns.len(), 1);
+
+								let cmd = get_const_command(field, wasm_file, &builder.current_block_mut().body, params[0], params[1]);
+								let success = field == "mc_command_success";
+								builder.current_block_mut().body.push(SsaInstr::RawCommandStore { dst: returns[0], cmd, success });
+							}
+							("env", "memory_snapshot") => {
+								assert_eq!(params.len(), 1);
+								assert_eq!(returns.len(), 0);
+
+								builder.current_block_mut().body.push(SsaInstr::MemorySnapshot(params[0]));
+							}
+							("env", "memory_restore") => {
+								assert_eq!(params.len(), 1);
+								assert_eq!(returns.len(), 0);
+
+								builder.current_block_mut().body.push(SsaInstr::MemoryRestore(params[0]));
+							}
 							("wasi_snapshot_preview1", "proc_exit") => {
 								assert_eq!(params.len(), 1);
 								assert_eq!(returns.len(), 0);
 
 								builder.current_block_mut().body.push(SsaInstr::WasiProcExit(params[0]));
 							}
-							("wasi_snapshot_preview1", _) => {
-								let idx = wasm_file.find_func(format!("__wasi_impl_{}", import.field).as_str());
+							("wasi_snapshot_preview1", "fd_write") if wasm_file.find_func("__wasi_impl_fd_write").is_none() => {
+								assert_eq!(params.len(), 4);
+								assert_eq!(returns.len(), 1);
+
+								builder.current_block_mut().body.push(SsaInstr::WasiFdWrite {
+									fd: params[0],
+									iovs: params[1],
+									iovs_len: params[2],
+									nwritten: params[3],
+									result: returns[0],
+								});
+							}
+							("wasi_snapshot_preview1", field @ ("args_sizes_get" | "environ_sizes_get")) if wasm_file.find_func(&format!("__wasi_impl_{field}")).is_none() => {
+								assert_eq!(params.len(), 2);
+								assert_eq!(returns.len(), 1);
+
+								let strings = if field == "args_sizes_get" { &ctx.wasi_args } else { &ctx.wasi_env };
+								emit_wasi_sizes_get(strings, params[0], params[1], returns[0], builder, alloc);
+							}
+							("wasi_snapshot_preview1", field @ ("args_get" | "environ_get")) if wasm_file.find_func(&format!("__wasi_impl_{field}")).is_none() => {
+								assert_eq!(params.len(), 2);
+								assert_eq!(returns.len(), 1);
+
+								let strings = if field == "args_get" { &ctx.wasi_args } else { &ctx.wasi_env };
+								emit_wasi_strings_get(strings, params[0], params[1], returns[0], builder, alloc);
+							}
+							("wasi_snapshot_preview1", field) => {
+								let idx = wasm_file.find_func(format!("__wasi_impl_{}", field).as_str());
 								if idx.is_none() {
 									todo!("{:?}", import);
 								}
@@ -996,11 +1500,7 @@ impl ValidationState<'_> {
 					}
 				}
 			}
-			&Operator::CallIndirect { index, table_index, table_byte } => {
-				if table_byte as u32 != table_index {
-					todo!()
-				}
-
+			&Operator::CallIndirect { index, table_index, table_byte: _ } => {
 				let called_ty = wasm_file.types.func_type(index);
 
 				let table_entry: Option<_> = validator.pop_value_ty(ValType::I32.into()).into();
@@ -1293,67 +1793,115 @@ impl ValidationState<'_> {
 				make_params(builder, validator, &label_types, alloc);
 			}
 
-			&Operator::MemoryGrow { mem, mem_byte } => {
-				builder.current_block_mut().body.push(SsaInstr::Todo("memory.grow"));
-				if mem != 0 || mem_byte != 0 {
-					todo!()
-				}
+			&Operator::MemoryGrow { mem, mem_byte: _ } => {
+				let pages = validator.pop_value_ty(ValType::I32.into());
+
+				let result = alloc.new_i32();
+				validator.push_value(result);
 
-				// TODO:
-				let _n = validator.pop_value_ty(ValType::I32.into());
-				self.visit_operator(&Operator::I32Const { value: -1 });
+				if let Some(pages) = pages.into() {
+					builder.current_block_mut().body.push(SsaInstr::MemoryGrow { memory: mem, pages, result });
+				}
 			}
 			&Operator::MemoryCopy { src, dst } => {
-				//builder.current_block_mut().body.push(SsaInstr::Todo("memory.copy"));
-				if src != 0 || dst != 0 {
-					todo!()
+				if src != dst {
+					panic!("memory.copy from memory {src} to memory {dst} is not supported, only copies within a single memory are");
 				}
+				let memory = dst;
 
-				let func_name = "__op_impl_MemoryCopy";
+				let length = validator.pop_value_ty(ValType::I32.into());
+				let src = validator.pop_value_ty(ValType::I32.into());
+				let dest = validator.pop_value_ty(ValType::I32.into());
 
-				let idx = wasm_file.find_func(func_name);
-				if idx.is_none() {
-					todo!("{:?}, you can implement it by exporting {}", op, func_name);
+				if let Some((dest, src, length)) = zip_vars!(dest, src, length) {
+					builder.current_block_mut().body.push(SsaInstr::Memcpy { memory, dest, src, length });
 				}
-				let idx = idx.unwrap();
-				let func_ty = wasm_file.func_type(idx);
+			}
+			&Operator::MemorySize { mem, mem_byte: _ } => {
+				let dst = alloc.new_i32();
+				validator.push_value(dst);
 
-				let params = validator.pop_values(&func_ty.params);
-				let params: Option<Vec<TypedSsaVar>> = params.into_iter().map(Option::from).collect::<Option<Vec<_>>>();
-				let returns = func_ty.returns.iter().map(|ty| alloc.new_typed(*ty)).collect::<Vec<_>>();
+				builder.current_block_mut().body.push(SsaInstr::MemorySize(mem, dst));
+			}
+			&Operator::MemoryFill { mem } => {
+				let length = validator.pop_value_ty(ValType::I32.into());
+				let value = validator.pop_value_ty(ValType::I32.into());
+				let dest = validator.pop_value_ty(ValType::I32.into());
 
-				validator.push_values(&returns);
+				if let Some((dest, value, length)) = zip_vars!(dest, value, length) {
+					// memory.fill doesn't return anything, unlike the memset intrinsic
+					let result = alloc.new_i32();
 
-				builder.current_block_mut().body.push(SsaInstr::Call {
-					function_index: idx as u32,
-					params: params.unwrap(),
-					returns,
-				});
+					builder.current_block_mut().body.push(SsaInstr::Memset { memory: mem, dest, value, length, result });
+				}
+			}
 
-				// TODO:
-				//let src = validator.pop_value_ty(ValType::I32.into()).unwrap();
-				//let dst = validator.pop_value_ty(ValType::I32.into()).unwrap();
-				//let size = validator.pop_value_ty(ValType::I32.into()).unwrap();
+			&Operator::RefNull { ty } => {
+				let dst = alloc.new_typed(ty);
+				builder.current_block_mut().body.push(SsaInstr::RefNull(dst));
+				validator.push_value(dst);
 			}
-			&Operator::MemorySize { mem, mem_byte } => {
-				builder.current_block_mut().body.push(SsaInstr::Todo("memory.size"));
-				if mem != 0 || mem_byte != 0 {
-					todo!()
+			Operator::RefIsNull => {
+				let src = validator.pop_value();
+
+				let dst = alloc.new_i32();
+				validator.push_value(dst);
+
+				// References are represented as handles where null is 0
+				if let Some(src) = src.into() {
+					builder.current_block_mut().body.push(SsaInstr::Eqz(dst, src));
 				}
+			}
+			&Operator::RefFunc { function_index } => {
+				let dst = alloc.new_typed(ValType::FuncRef);
+				builder.current_block_mut().body.push(SsaInstr::RefFunc(dst, function_index));
+				validator.push_value(dst);
+			}
+			&Operator::TableGet { table } => {
+				let index = validator.pop_value_ty(ValType::I32.into());
 
-				// TODO:
-				self.visit_operator(&Operator::I32Const { value: -1 });
+				let dst = alloc.new_typed(wasm_file.tables.tables[table as usize].element_type);
+				validator.push_value(dst);
+
+				if let Some(index) = index.into() {
+					builder.current_block_mut().body.push(SsaInstr::TableGet { table_index: table, dst, index });
+				}
 			}
-			&Operator::MemoryFill { mem } => {
-				builder.current_block_mut().body.push(SsaInstr::Todo("memory.fill"));
-				if mem != 0 {
-					todo!()
+			&Operator::TableSet { table } => {
+				let value = validator.pop_value_ty(wasm_file.tables.tables[table as usize].element_type.into());
+				let index = validator.pop_value_ty(ValType::I32.into());
+
+				if let Some((index, value)) = zip_vars!(index, value) {
+					builder.current_block_mut().body.push(SsaInstr::TableSet { table_index: table, index, value });
 				}
+			}
+			&Operator::TableGrow { table } => {
+				let count = validator.pop_value_ty(ValType::I32.into());
+				let init = validator.pop_value_ty(wasm_file.tables.tables[table as usize].element_type.into());
+
+				let result = alloc.new_i32();
+				validator.push_value(result);
 
-				// TODO:
-				let _n = validator.pop_value_ty(ValType::I32.into());
-				let _n = validator.pop_value_ty(ValType::I32.into());
-				self.visit_operator(&Operator::I32Const { value: -1 });
+				if let Some((init, count)) = zip_vars!(init, count) {
+					builder.current_block_mut().body.push(SsaInstr::TableGrow { table_index: table, init, count, result });
+				}
+			}
+			&Operator::TableInit { segment, table } => {
+				let length = validator.pop_value_ty(ValType::I32.into());
+				let src = validator.pop_value_ty(ValType::I32.into());
+				let dst = validator.pop_value_ty(ValType::I32.into());
+
+				if let Some((dst, src, length)) = zip_vars!(dst, src, length) {
+					builder.current_block_mut().body.push(SsaInstr::TableInit { table_index: table, segment, dst, src, length });
+				}
+			}
+			&Operator::ElemDrop { segment } => {
+				builder.current_block_mut().body.push(SsaInstr::ElemDrop(segment));
+			}
+			&Operator::TableSize { table } => {
+				let dst = alloc.new_i32();
+				builder.current_block_mut().body.push(SsaInstr::TableSize { table_index: table, dst });
+				validator.push_value(dst);
 			}
 
 			Operator::Nop => {},
@@ -1390,6 +1938,58 @@ impl ValidationState<'_> {
 	}
 }
 
+/// Stores a constant word to memory at the given address plus an offset.
+fn emit_store_const(value: i32, addr: TypedSsaVar, offset: u32, builder: &mut SsaFuncBuilder, alloc: &mut SsaVarAlloc) {
+	let memarg = MemoryImmediate { align: 0, offset: offset as u64, memory: 0 };
+
+	let var = alloc.new_i32();
+	builder.current_block_mut().body.push(SsaInstr::I32Set(var, value));
+	builder.current_block_mut().body.push(SsaInstr::Store32(memarg, var, addr.into()));
+}
+
+/// Lowers WASI's `args_sizes_get` or `environ_sizes_get`, which give the number of strings
+/// and the total size of the strings including their null terminators.
+fn emit_wasi_sizes_get(strings: &[String], count_ptr: TypedSsaVar, buf_size_ptr: TypedSsaVar, result: TypedSsaVar, builder: &mut SsaFuncBuilder, alloc: &mut SsaVarAlloc) {
+	let buf_size = strings.iter().map(|s| s.len() + 1).sum::<usize>();
+
+	emit_store_const(strings.len() as i32, count_ptr, 0, builder, alloc);
+	emit_store_const(buf_size as i32, buf_size_ptr, 0, builder, alloc);
+	builder.current_block_mut().body.push(SsaInstr::I32Set(result, 0));
+}
+
+/// Lowers WASI's `args_get` or `environ_get`, which copy the null-terminated strings one after another into `buf`
+/// and store a pointer to each one in the array at `ptrs`.
+fn emit_wasi_strings_get(strings: &[String], ptrs: TypedSsaVar, buf: TypedSsaVar, result: TypedSsaVar, builder: &mut SsaFuncBuilder, alloc: &mut SsaVarAlloc) {
+	let mut bytes = Vec::new();
+	for (idx, s) in strings.iter().enumerate() {
+		let ptr = alloc.new_i32();
+		let offset = SsaVarOrConst::Const(TypedValue::I32(bytes.len() as i32));
+		builder.current_block_mut().body.push(SsaInstr::Add(ptr, buf.into(), offset));
+
+		let memarg = MemoryImmediate { align: 0, offset: 4 * idx as u64, memory: 0 };
+		builder.current_block_mut().body.push(SsaInstr::Store32(memarg, ptr, ptrs.into()));
+
+		bytes.extend_from_slice(s.as_bytes());
+		bytes.push(0);
+	}
+
+	// The strings are stored a word at a time, with any leftover bytes stored individually
+	let words = bytes.chunks_exact(4);
+	let rest_start = bytes.len() - words.remainder().len();
+	for (idx, word) in words.enumerate() {
+		emit_store_const(i32::from_le_bytes(word.try_into().unwrap()), buf, 4 * idx as u32, builder, alloc);
+	}
+	for (idx, &byte) in bytes.iter().enumerate().skip(rest_start) {
+		let memarg = MemoryImmediate { align: 0, offset: idx as u64, memory: 0 };
+
+		let var = alloc.new_i32();
+		builder.current_block_mut().body.push(SsaInstr::I32Set(var, byte as i32));
+		builder.current_block_mut().body.push(SsaInstr::Store8(memarg, var, buf.into()));
+	}
+
+	builder.current_block_mut().body.push(SsaInstr::I32Set(result, 0));
+}
+
 /// Adds the prologue to the given SSA function. See [validate] for usage.
 ///
 /// The prologue fetches the function parameters and initializes the other locals to zero.
@@ -1423,7 +2023,8 @@ fn add_prologue(builder: &mut SsaFuncBuilder, func_ty: &FuncType) {
 }
 
 /// Converts a single function (the one with ID `func`) from WebAssembly into an SSA function.
-pub fn validate(wasm_file: &WasmFile, func: usize) -> SsaFunction {
+/// Calls to the imports in `linked_funcs` are left as calls to the import's index, for the linker to redirect.
+pub fn validate(ctx: &CompileContext, wasm_file: &WasmFile, func: usize, linked_funcs: &HashSet<usize>) -> SsaFunction {
 	let func_ty = wasm_file.func_type(func);
 	let func_body = wasm_file.func_body(func);
 
@@ -1447,15 +2048,28 @@ pub fn validate(wasm_file: &WasmFile, func: usize) -> SsaFunction {
 	validator.push_ctrl(ControlOp::Block(end_block), &[], Box::new([]), func_ty.returns.clone());
 
 	let mut state = ValidationState {
+		ctx,
 		wasm_file,
+		linked_funcs,
 		func,
 		builder,
 		alloc,
 		validator,
 	};
 
-	for op in func_body.operators.iter() {
+	// Each operator belongs to the block it's visited in, even the ones that end it
+	let mut code_ranges = HashMap::<BlockId, Vec<Range<usize>>>::new();
+	for (idx, op) in func_body.operators.iter().enumerate() {
+		let block_id = BlockId { func, block: state.builder.current_block };
 		state.visit_operator(op);
+
+		let start = func_body.offsets[idx];
+		let end = func_body.offsets.get(idx + 1).copied().unwrap_or(func_body.end_offset);
+		let ranges = code_ranges.entry(block_id).or_default();
+		match ranges.last_mut() {
+			Some(last) if last.end == start => last.end = end,
+			_ => ranges.push(start..end),
+		}
 	}
 
 	assert_eq!(state.builder.current_block, 1);
@@ -1507,19 +2121,253 @@ pub fn validate(wasm_file: &WasmFile, func: usize) -> SsaFunction {
 		}
 	}*/
 
-	SsaFunction::new(blocks, func_ty.params.clone(), func_ty.returns.clone())
+	let mut ssa_func = SsaFunction::new(blocks, func_ty.params.clone(), func_ty.returns.clone());
+	ssa_func.code_ranges = code_ranges;
+	ssa_func
 }
 
 /// Converts an entire WebAssembly file into an SSA-form program.
+/// Functions imported from the `env` module that are implemented as intrinsics.
+pub const ENV_INTRINSICS: &[&str] = &[
+	"turtle_x", "turtle_y", "turtle_z", "turtle_set", "turtle_set_block_nbt", "turtle_fill", "turtle_copy_region",
+	"turtle_paste_region_masked", "turtle_get", "turtle_copy", "turtle_paste",
+	"turtle_face", "turtle_forward", "turtle_turn_left", "turtle_turn_right", "turtle_raycast", "turtle_light_level", "turtle_is_air", "turtle_is_solid", "nearest_player_x", "nearest_player_y", "nearest_player_z", "entity_count", "mc_gametime", "mc_random",
+	"mc_playsound", "mc_particle", "mc_bossbar_create", "mc_bossbar_value", "mc_bossbar_max", "mc_bossbar_name",
+	"memset", "sleep", "mc_sleep", "mc_sleep_ticks", "print", "print_long", "print_float", "print_double",
+	"mc_putc", "print_str", "print_fmt", "mc_title", "mc_actionbar",
+	"buf_get_u8", "buf_get_u16", "buf_set_u8", "buf_set_u16",
+	"memory_snapshot", "memory_restore", "mc_command", "mc_command_result", "mc_command_success",
+];
+
+/// Returns the value of a variable if it was set to a constant earlier in the block.
+fn find_i32_const(body: &[SsaInstr], var: TypedSsaVar) -> Option<i32> {
+	body.iter().rev().find_map(|instr| match instr {
+		&SsaInstr::I32Set(dst, value) if dst == var => Some(value),
+		_ => None,
+	})
+}
+
+/// Reads a parameter of an intrinsic that has to be a non-negative constant, since it ends up in the text of a command.
+fn get_const_param(intrinsic: &str, param: &str, body: &[SsaInstr], var: TypedSsaVar) -> i32 {
+	match find_i32_const(body, var) {
+		Some(value) if value >= 0 => value,
+		Some(value) => panic!("the {param} passed to `{intrinsic}` can't be negative, but it was {value}"),
+		None => panic!("the {param} passed to `{intrinsic}` must be a constant"),
+	}
+}
+
+/// Reads a string parameter of an intrinsic that has to be a constant in the module's data,
+/// since the datapack format doesn't support building commands at runtime.
+fn get_const_string<'a>(intrinsic: &str, param: &str, wasm_file: &'a WasmFile, body: &[SsaInstr], ptr: TypedSsaVar, len: TypedSsaVar) -> &'a str {
+	let (ptr, len) = match (find_i32_const(body, ptr), find_i32_const(body, len)) {
+		(Some(ptr), Some(len)) => (ptr as u32 as usize, len as u32 as usize),
+		_ => panic!("the {param} passed to `{intrinsic}` must be a constant string"),
+	};
+
+	let bytes = wasm_file.static_data(ptr, len)
+		.unwrap_or_else(|| panic!("the {param} passed to `{intrinsic}` at {ptr:#X} ({len} bytes) isn't in the module's data"));
+
+	let s = std::str::from_utf8(bytes)
+		.unwrap_or_else(|err| panic!("the {param} passed to `{intrinsic}` at {ptr:#X} isn't valid UTF-8: {err}"));
+
+	// C string literals often include the null terminator
+	s.trim_end_matches('\0').trim()
+}
+
+/// Reads the command passed to `mc_command` or a similar intrinsic.
+fn get_const_command(intrinsic: &str, wasm_file: &WasmFile, body: &[SsaInstr], ptr: TypedSsaVar, len: TypedSsaVar) -> String {
+	let cmd = get_const_string(intrinsic, "command", wasm_file, body, ptr, len);
+
+	// Commands typed in chat start with a slash
+	let cmd = cmd.strip_prefix('/').unwrap_or(cmd);
+
+	if cmd.is_empty() || cmd.contains(['\n', '\r']) {
+		panic!("the command passed to `{intrinsic}` must be a single command, got {cmd:?}");
+	}
+
+	cmd.to_string()
+}
+
+/// Reads the block entity data passed to `turtle_set_block_nbt`, which has to be a single SNBT compound.
+fn get_const_nbt(intrinsic: &str, wasm_file: &WasmFile, body: &[SsaInstr], ptr: TypedSsaVar, len: TypedSsaVar) -> String {
+	let nbt = get_const_string(intrinsic, "NBT", wasm_file, body, ptr, len);
+
+	if !nbt.starts_with('{') || !nbt.ends_with('}') || nbt.contains(['\n', '\r']) {
+		panic!("the NBT passed to `{intrinsic}` must be a compound on a single line, like `{{Items:[]}}`, got {nbt:?}");
+	}
+
+	nbt.to_string()
+}
+
+/// A call to a registered import, which the import's lowering turns into SSA instructions.
+pub struct ImportCall<'a> {
+	/// The arguments to the call
+	pub params: &'a [TypedSsaVar],
+	/// The variables the lowering must define with the call's results
+	pub returns: &'a [TypedSsaVar],
+	/// Allocates any temporary variables the lowering needs
+	pub alloc: &'a mut SsaVarAlloc,
+	/// The instructions of the block the call is in, which the lowering appends to
+	pub body: &'a mut Vec<SsaInstr>,
+}
+
+/// Lowers a call to a registered import into SSA instructions.
+pub type ImportLowering = dyn Fn(ImportCall) + Send + Sync;
+
+/// Imports declared by users of the crate in addition to the built-in intrinsics,
+/// so downstream projects can add their own without changing the validator.
+/// Registered imports take priority over built-in ones with the same name.
+#[derive(Default)]
+pub struct ImportRegistry {
+	lowerings: HashMap<String, Box<ImportLowering>>,
+}
+
+impl ImportRegistry {
+	/// Registers an import, replacing any previous registration with the same module and field.
+	pub fn register(&mut self, module: &str, field: &str, lowering: impl Fn(ImportCall) + Send + Sync + 'static) {
+		self.lowerings.insert(format!("{module}.{field}"), Box::new(lowering));
+	}
+
+	pub fn get(&self, module: &str, field: &str) -> Option<&ImportLowering> {
+		self.lowerings.get(&format!("{module}.{field}")).map(|l| &**l)
+	}
+
+	pub fn contains(&self, module: &str, field: &str) -> bool {
+		self.get(module, field).is_some()
+	}
+
+	/// The `module.field` names of all registered imports.
+	pub fn names(&self) -> impl Iterator<Item = &str> {
+		self.lowerings.keys().map(String::as_str)
+	}
+}
+
+fn is_supported_import(ctx: &CompileContext, wasm_file: &WasmFile, module: &str, field: &str) -> bool {
+	match (module, field) {
+		(module, field) if ctx.imports.contains(module, field) => true,
+		(module, _) if ctx.pack_imports.contains_key(module) => true,
+		("env", field) => ENV_INTRINSICS.contains(&field),
+		("wasi_snapshot_preview1", "proc_exit" | "fd_write") => true,
+		("wasi_snapshot_preview1", "args_get" | "args_sizes_get" | "environ_get" | "environ_sizes_get") => true,
+		("wasi_snapshot_preview1", field) => wasm_file.find_func(&format!("__wasi_impl_{field}")).is_some(),
+		_ => false,
+	}
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+	let b = b.chars().collect::<Vec<_>>();
+	let mut prev = (0..=b.len()).collect::<Vec<_>>();
+	for (i, ca) in a.chars().enumerate() {
+		let mut curr = vec![i + 1];
+		for (j, cb) in b.iter().enumerate() {
+			let cost = if ca == *cb { 0 } else { 1 };
+			curr.push((prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1));
+		}
+		prev = curr;
+	}
+	prev[b.len()]
+}
+
+/// Returns the intrinsic with the most similar name, if any are close enough to be a likely typo.
+fn nearest_intrinsic(field: &str) -> Option<&'static str> {
+	let (name, dist) = ENV_INTRINSICS.iter()
+		.map(|name| (*name, edit_distance(field, name)))
+		.min_by_key(|(_, dist)| *dist)?;
+
+	if dist <= (field.len() / 3).max(2) {
+		Some(name)
+	} else {
+		None
+	}
+}
+
+/// Makes sure every import that is actually called is supported,
+/// explaining what can be done about the ones that aren't.
+fn check_imports(ctx: &CompileContext, wasm_file: &WasmFile, linked_funcs: &HashSet<usize>) {
+	let mut errors = Vec::new();
+
+	for (idx, import) in wasm_file.imports.func_imports().iter().enumerate() {
+		let (module, field) = ctx.map_import(import.module, import.field);
+		if linked_funcs.contains(&idx) || is_supported_import(ctx, wasm_file, module, field) {
+			continue;
+		}
+
+		let callers = wasm_file.defined_funcs().filter(|&func| {
+			wasm_file.func_body(func).operators.iter().any(|op| {
+				matches!(op, &Operator::Call { function_index } if function_index as usize == idx)
+			})
+		}).collect::<Vec<_>>();
+
+		if callers.is_empty() {
+			continue;
+		}
+
+		let ty = wasm_file.func_type(idx);
+		let mut msg = format!("unsupported import `{}.{}` {:?} -> {:?}", import.module, import.field, ty.params, ty.returns);
+		if (module, field) != (import.module, import.field) {
+			msg.push_str(&format!(" (mapped to `{module}.{field}`)"));
+		}
+
+		let callers = callers.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+		msg.push_str(&format!("\n  called by function(s): {}", callers.join(", ")));
+
+		if module == "wasi_snapshot_preview1" {
+			msg.push_str(&format!("\n  help: implement it in the program as an exported function named `__wasi_impl_{field}`"));
+		} else if let Some(name) = nearest_intrinsic(field) {
+			msg.push_str(&format!("\n  help: did you mean the intrinsic `env.{name}`? Rename the import or pass `--import-map {}.{}=env.{name}`", import.module, import.field));
+		} else {
+			msg.push_str("\n  help: use --import-map or --import-map-file to redirect it to a supported import");
+		}
+
+		errors.push(msg);
+	}
+
+	if !errors.is_empty() {
+		let mut supported = ENV_INTRINSICS.to_vec();
+		supported.sort_unstable();
+
+		let mut msg = format!("{}\nsupported `env` intrinsics: {}", errors.join("\n"), supported.join(", "));
+
+		let mut registered = ctx.imports.names().collect::<Vec<_>>();
+		if !registered.is_empty() {
+			registered.sort_unstable();
+			msg.push_str(&format!("\nregistered imports: {}", registered.join(", ")));
+		}
+
+		panic!("{}", msg);
+	}
+}
+
+/// Returns the function an element segment item refers to, or `None` for a null reference.
+fn eval_elem_item(item: ElementItem, globals: &[Option<TypedValue>]) -> Option<usize> {
+	match item {
+		ElementItem::Func(func_idx) => Some(func_idx as usize),
+		// Function references are stored as one more than the function's index
+		ElementItem::Expr(expr) => match eval_const_expr_single(&expr, globals) {
+			TypedValue::I32(0) => None,
+			TypedValue::I32(handle) => Some(handle as usize - 1),
+			value => panic!("element segment item evaluated to {value:?}, which isn't a function reference"),
+		}
+	}
+}
+
 pub fn wasm_to_ssa(ctx: &CompileContext, wasm_file: &WasmFile) -> SsaProgram {
+	finish_ssa(ctx, module_to_ssa(ctx, wasm_file, &HashSet::new()))
+}
+
+/// Converts a module into an SSA program without optimizing it, leaving the imports in `linked_funcs`
+/// as calls to the import's index.
+pub fn module_to_ssa(ctx: &CompileContext, wasm_file: &WasmFile, linked_funcs: &HashSet<usize>) -> SsaProgram {
 	use rayon::prelude::*;
 
+	check_imports(ctx, wasm_file, linked_funcs);
+
 	let mut code = Vec::new();
 	let mut local_types = HashMap::new();
 
 	for (func, ssa_func, func_locals) in (0..wasm_file.functions.functions.len()).into_par_iter()
 		.filter(|func| wasm_file.func_is_defined(*func)).map(|func| {
-		let ssa_func = validate(wasm_file, func);
+		let ssa_func = validate(ctx, wasm_file, func, linked_funcs);
 		let func_locals = wasm_file.func_locals(func);
 		(func, ssa_func, func_locals)
 	}).collect::<Vec<_>>() {
@@ -1527,69 +2375,141 @@ pub fn wasm_to_ssa(ctx: &CompileContext, wasm_file: &WasmFile) -> SsaProgram {
 		local_types.insert(func, func_locals);
 	}
 
-	let globals = wasm_file.globals().iter().map(|global| {
-		let val = eval_const_expr_single(&global.init_expr);
-		assert_eq!(val.ty(), global.ty.content_type);
+	// Imported globals are materialized as ordinary ones that start out as zero,
+	// whoever provides them has to set them after the program is initialized.
+	// References start out null, which is stored as 0.
+	let imported_globals = wasm_file.imports.global_imports().iter().map(|import| {
+		match import.content_type {
+			ValType::I32 | ValType::FuncRef | ValType::ExternRef => TypedValue::I32(0),
+			ValType::I64 => TypedValue::I64(0),
+			ty => panic!("imported global `{}.{}` has type {ty:?}, but only integer and reference globals are supported", import.module, import.field),
+		}
+	});
+
+	let const_globals = wasm_file.const_globals();
+	let import_count = wasm_file.imports.global_imports().len();
+	let defined_globals = wasm_file.globals.globals.iter().enumerate().map(|(idx, global)| {
+		let val = eval_const_expr_single(&global.init_expr, &const_globals[..import_count + idx]);
+		// References are stored as i32 handles
+		let ty = match global.ty.content_type {
+			ValType::FuncRef | ValType::ExternRef => ValType::I32,
+			ty => ty,
+		};
+		assert_eq!(val.ty(), ty);
 		val
-	}).collect();
+	});
 
-	let mut tables = wasm_file.tables.tables.iter().map(|table_ty| {
-		if table_ty.element_type != ValType::FuncRef {
-			todo!()
-		}
+	let globals = imported_globals.chain(defined_globals).collect();
 
+	let mut tables = wasm_file.tables.tables.iter().map(|table_ty| {
 		Table {
 			max: table_ty.maximum.map(|m| m as usize),
 			elements: vec![None; table_ty.initial as usize],
+			runtime_funcs: None,
 		}
 	}).collect::<Vec<_>>();
 
+	let mut elem_segments = Vec::new();
 	for elem in wasm_file.elements.elements.iter() {
-		if elem.ty != ValType::FuncRef {
-			todo!()
-		}
+		let mut segment = Vec::new();
 
 		match elem.kind {
 			ElementKind::Active { table_index, offset_expr } => {
 				let table = &mut tables[table_index as usize];
 
-				let offset = eval_const_expr_single(&offset_expr);
+				let offset = eval_const_expr_single(&offset_expr, &const_globals);
 				let offset = offset.into_i32().unwrap();
 
 				for (idx, item) in elem.items.get_items_reader().unwrap().into_iter().enumerate() {
-					let item = item.unwrap();
+					let index = idx + offset as usize;
+					assert!(table.elements[index].is_none());
+					table.elements[index] = eval_elem_item(item.unwrap(), &const_globals);
+				}
+			}
+			ElementKind::Passive => {
+				for item in elem.items.get_items_reader().unwrap().into_iter() {
+					segment.push(eval_elem_item(item.unwrap(), &const_globals));
+				}
+			}
+			// Only lists the functions that `ref.func` is used on
+			ElementKind::Declared => {}
+		}
 
-					if let ElementItem::Func(item) = item {
-						let index = idx + offset as usize;
-						assert!(table.elements[index].is_none());
-						table.elements[index] = Some(item as usize);
-					} else {
-						todo!()
+		elem_segments.push(segment);
+	}
+
+	// A table that is written to at runtime can end up holding any function a reference is taken to
+	let mut referenced_funcs = tables.iter().map(|table| &table.elements).chain(elem_segments.iter())
+		.flat_map(|elements| elements.iter().copied().flatten())
+		.collect::<BTreeSet<_>>();
+	let mut written_tables = BTreeSet::new();
+	let mut grown_tables = BTreeSet::new();
+	for func in code.iter() {
+		for (_, block) in func.iter() {
+			for instr in block.body.iter() {
+				match instr {
+					SsaInstr::RefFunc(_, func_idx) => { referenced_funcs.insert(*func_idx as usize); }
+					SsaInstr::TableSet { table_index, .. } |
+					SsaInstr::TableInit { table_index, .. } => { written_tables.insert(*table_index); }
+					SsaInstr::TableGrow { table_index, .. } => {
+						written_tables.insert(*table_index);
+						grown_tables.insert(*table_index);
 					}
+					_ => {}
 				}
 			}
-			ElementKind::Passive => todo!(),
-			ElementKind::Declared => todo!(),
 		}
 	}
+	for table_index in written_tables {
+		tables[table_index as usize].runtime_funcs = Some(referenced_funcs.iter().copied().collect());
+	}
+
+	// Tables that are never grown keep their initial size, and the rest are capped so every entry can have a holder
+	for (table_index, table) in tables.iter_mut().enumerate() {
+		let initial = table.elements.len();
+		if grown_tables.contains(&(table_index as u32)) {
+			let max_size = ctx.max_table_size as usize;
+			table.max = Some(table.max.map_or(max_size, |m| m.min(max_size)).max(initial));
+		} else {
+			table.max = Some(initial);
+		}
+	}
+
+	// Imported memories come first in the index space.
+	// They are shared with the program that exports them, which is responsible for initializing them.
+	let imported_memory = wasm_file.imports.memory_imports().iter().map(|import| {
+		assert!(!import.ty.memory64);
+
+		Memory::new_imported(import.ty.initial as usize, import.ty.maximum.map(|m| m as usize))
+	});
 
-	let mut memory = wasm_file.memory.memory.iter().map(|mem_ty| {
+	// Shared memories are treated like any other, since there is only ever one thread
+	let defined_memory = wasm_file.memory.memory.iter().map(|mem_ty| {
 		assert!(!mem_ty.memory64);
-		assert!(!mem_ty.shared);
 
 		Memory::new(mem_ty.initial as usize, mem_ty.maximum.map(|m| m as usize))
-	}).collect::<Vec<_>>();
+	});
+
+	let mut memory = imported_memory.chain(defined_memory).collect::<Vec<_>>();
+
+	if let Some(max_pages) = ctx.max_memory_pages {
+		for memory in memory.iter_mut() {
+			let max_pages = max_pages as usize;
+			memory.maximum = Some(memory.maximum.map_or(max_pages, |m| m.min(max_pages)));
+		}
+	}
 
 	for data in wasm_file.data.data.iter() {
 		match data.kind {
 			DataKind::Active { memory_index, offset_expr } => {
-				let offset = eval_const_expr_single(&offset_expr);
+				let offset = eval_const_expr_single(&offset_expr, &const_globals);
 				let offset = offset.into_i32().unwrap();
 
 				let memory = &mut memory[memory_index as usize];
 
 				let slice = &mut memory.data[offset as usize..][..data.data.len()];
 				slice.copy_from_slice(data.data);
+				memory.mark_initialized(offset as usize..offset as usize + data.data.len());
 			}
 			DataKind::Passive => todo!(),
 		}
@@ -1607,18 +2527,67 @@ pub fn wasm_to_ssa(ctx: &CompileContext, wasm_file: &WasmFile) -> SsaProgram {
 		}
 	}).collect();
 
+	let start = wasm_file.start_func.map(|func| {
+		// A linked import is replaced by the function it's linked to, but other imports can't be called at startup
+		if !wasm_file.func_is_defined(func) && !linked_funcs.contains(&func) {
+			let import = wasm_file.func_import(func);
+			panic!("start function {func} is the import `{}.{}`, but only functions defined by the module or linked from another one can be start functions", import.module, import.field);
+		}
+
+		let ty = wasm_file.func_type(func);
+		assert!(ty.params.is_empty() && ty.returns.is_empty(), "start function must have type [] -> []");
+
+		BlockId { func, block: 0 }
+	});
+
 	for func in code.iter() {
 		validate_ssa_jump_params(func);
 	}
 
-	let mut program = SsaProgram {
+	SsaProgram {
 		local_types,
 		globals,
 		memory,
 		tables,
+		elem_segments,
 		code,
 		exports,
-	};
+		start,
+		func_names: wasm_file.func_names.clone(),
+		snapshot_regions: Vec::new(),
+		div_by_zero: ctx.div_by_zero,
+	}
+}
+
+/// Places the `--data` files in the program's first memory and optimizes it.
+pub fn finish_ssa(ctx: &CompileContext, mut program: SsaProgram) -> SsaProgram {
+	use rayon::prelude::*;
+
+	for data_file in ctx.data_files.iter() {
+		let data = std::fs::read(&data_file.path).unwrap_or_else(|err| panic!("failed to read data file {:?}: {}", data_file.path, err));
+
+		let memory = program.memory.first_mut().unwrap_or_else(|| panic!("data file {:?} given, but the module has no memory", data_file.path));
+
+		let start = data_file.address as usize;
+		if start + data.len() > memory.data.len() {
+			panic!("data file {:?} does not fit in memory ({} bytes at {:#X}, memory is {} bytes)", data_file.path, data.len(), start, memory.data.len());
+		}
+
+		memory.store(start, &data);
+		memory.mark_initialized(start..start + data.len());
+	}
+
+	if !ctx.snapshot_regions.is_empty() {
+		let memory = program.memory.first().unwrap_or_else(|| panic!("snapshot regions given, but the module has no memory"));
+
+		let max_len = memory.max_pages() * 65536;
+		for region in ctx.snapshot_regions.iter() {
+			if region.start as usize + region.length as usize > max_len {
+				panic!("snapshot region {}:{} does not fit in memory, which can be at most {} bytes", region.start, region.length, max_len);
+			}
+		}
+		program.snapshot_regions = ctx.snapshot_regions.clone();
+	}
 
 	if ctx.do_const_prop {
 		program.code.par_iter_mut().for_each(|func| {
@@ -1627,7 +2596,10 @@ pub fn wasm_to_ssa(ctx: &CompileContext, wasm_file: &WasmFile) -> SsaProgram {
 	}
 
 	if ctx.do_dead_code_elim {
-		crate::ssa::dce::do_dead_code_elim(&mut program);
+		crate::ssa::dce::do_dead_code_elim(&mut program, ctx.remarks());
+
+		// Removing unreachable blocks can leave holes in the block IDs
+		program.code.par_iter_mut().for_each(|func| func.renumber_blocks());
 	}
 
 	program.code.par_iter().for_each(|func| {
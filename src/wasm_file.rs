@@ -1,6 +1,8 @@
-use wasmparser::{Data, Element, Export, FuncType, Global, Import, MemoryType, Operator, Parser, Payload, TableType, BlockType, ExternalKind, GlobalType, ConstExpr, ValType, Type, TypeRef};
+use wasmparser::{Data, DataKind, Element, Export, FuncType, Global, Import, MemoryType, Operator, Parser, Payload, TableType, BlockType, ExternalKind, GlobalType, ConstExpr, ValType, Type, TypeRef, NameSectionReader, Name};
 
-use crate::ssa::interp::TypedValue;
+use std::collections::HashMap;
+
+use crate::{ssa::interp::TypedValue, demangle::demangle};
 
 #[derive(Debug, Default)]
 pub struct DataList<'a> {
@@ -70,17 +72,25 @@ pub struct FuncImport<'a> {
 }
 
 #[derive(Debug, Clone, Copy)]
-struct GlobalImport<'a> {
+pub struct GlobalImport<'a> {
     pub module: &'a str,
     pub field: &'a str,
     pub content_type: ValType,
     pub mutable: bool,
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct MemoryImport<'a> {
+    pub module: &'a str,
+    pub field: &'a str,
+    pub ty: MemoryType,
+}
+
 #[derive(Debug, Default)]
 pub struct ImportList<'a> {
     func_imports: Vec<FuncImport<'a>>,
     global_imports: Vec<GlobalImport<'a>>,
+    memory_imports: Vec<MemoryImport<'a>>,
 }
 
 impl<'a> ImportList<'a> {
@@ -88,6 +98,18 @@ impl<'a> ImportList<'a> {
         ImportList::default()
     }
 
+    pub fn func_imports(&self) -> &[FuncImport<'a>] {
+        &self.func_imports
+    }
+
+    pub fn global_imports(&self) -> &[GlobalImport<'a>] {
+        &self.global_imports
+    }
+
+    pub fn memory_imports(&self) -> &[MemoryImport<'a>] {
+        &self.memory_imports
+    }
+
     pub fn add_import(&mut self, i: Import<'a>) {
         match i.ty {
             TypeRef::Func(ty) => {
@@ -96,6 +118,9 @@ impl<'a> ImportList<'a> {
             TypeRef::Global(GlobalType { content_type, mutable }) => {
                 self.global_imports.push(GlobalImport { module: i.module, field: i.name, content_type, mutable })
             }
+            TypeRef::Memory(ty) => {
+                self.memory_imports.push(MemoryImport { module: i.module, field: i.name, ty })
+            }
             _ => todo!("{:?}", i),
         }
     }
@@ -145,6 +170,10 @@ impl MemoryList {
 #[derive(Debug)]
 pub struct FunctionBody<'a> {
     pub operators: Vec<Operator<'a>>,
+    /// Where each operator starts, relative to the start of the code section's contents like DWARF addresses are
+    pub offsets: Vec<usize>,
+    /// Where the body ends, relative to the same place as `offsets`
+    pub end_offset: usize,
     pub locals: Vec<(u32, ValType)>,
 }
 
@@ -220,9 +249,31 @@ pub struct WasmFile<'a> {
     /// Includes imported functions
     pub functions: FunctionList,
     pub bodies: Vec<FunctionBody<'a>>,
+    /// The function given by the start section
+    pub start_func: Option<usize>,
+    /// Demangled names of functions from the `name` custom section, if the module has one
+    pub func_names: HashMap<usize, String>,
+    /// Contents of the `.debug_*` custom sections holding DWARF debug info, by section name
+    pub debug_sections: HashMap<&'a str, &'a [u8]>,
 }
 
 impl<'a> WasmFile<'a> {
+    /// Returns the bytes the data segments place at the given range of the first memory,
+    /// if a single active segment covers the whole range.
+    pub fn static_data(&self, addr: usize, len: usize) -> Option<&'a [u8]> {
+        let globals = self.const_globals();
+        self.data.data.iter().find_map(|data| {
+            match data.kind {
+                DataKind::Active { memory_index: 0, offset_expr } => {
+                    let offset = eval_const_expr_single(&offset_expr, &globals).into_i32()? as u32 as usize;
+                    let start = addr.checked_sub(offset)?;
+                    data.data.get(start..start.checked_add(len)?)
+                }
+                _ => None,
+            }
+        })
+    }
+
     pub fn func_body(&self, func_idx: usize) -> &FunctionBody<'a> {
         let import_count = self.imports.func_imports.len();
         if !self.func_is_defined(func_idx) {
@@ -276,20 +327,26 @@ impl<'a> WasmFile<'a> {
         self.exports.find_func(name)
     }
 
-    pub fn global(&self, index: u32) -> Global {
-        if !self.imports.global_imports.is_empty() {
-            todo!()
+    /// Includes imported globals
+    pub fn global_type(&self, index: u32) -> GlobalType {
+        let import_count = self.imports.global_imports.len();
+        if let Some(import) = self.imports.global_imports.get(index as usize) {
+            GlobalType { content_type: import.content_type, mutable: import.mutable }
+        } else {
+            self.globals.globals[index as usize - import_count].ty
         }
-
-        self.globals.globals[index as usize]
     }
 
-    pub fn globals(&self) -> &[Global] {
-        if !self.imports.global_imports.is_empty() {
-            todo!()
+    /// The values of the globals that constant expressions can read, including imported globals.
+    /// Imported and mutable globals are `None`, since their values aren't known when compiling.
+    pub fn const_globals(&self) -> Vec<Option<TypedValue>> {
+        let mut globals = vec![None; self.imports.global_imports.len()];
+        for global in self.globals.globals.iter() {
+            // Each global can only read the ones before it
+            let value = eval_const_expr_single(&global.init_expr, &globals);
+            globals.push(Some(value).filter(|_| !global.ty.mutable));
         }
-
-        &self.globals.globals
+        globals
     }
 }
 
@@ -305,6 +362,12 @@ impl<'a> From<&'a [u8]> for WasmFile<'a> {
         let mut elements = ElementList::new();
 
         let mut func_reader = None;
+        let mut code_start = 0;
+
+        let mut start_func = None;
+
+        let mut func_names = HashMap::new();
+        let mut debug_sections = HashMap::new();
 
         let mut codes = Vec::new();
 
@@ -355,18 +418,24 @@ impl<'a> From<&'a [u8]> for WasmFile<'a> {
                     assert!(func_reader.is_none());
                     func_reader = Some(f);
                 }
-                Payload::CodeSectionStart { .. } => {}
+                Payload::CodeSectionStart { range, .. } => {
+                    code_start = range.start;
+                }
                 Payload::CodeSectionEntry(e) => {
-                    let operators = e.get_operators_reader().unwrap()
-                        .into_iter()
-                        .map(|o| o.unwrap())
-                        .collect::<Vec<_>>();
+                    let (operators, offsets) = e.get_operators_reader().unwrap()
+                        .into_iter_with_offsets()
+                        .map(|o| {
+                            let (op, offset) = o.unwrap();
+                            (op, offset - code_start)
+                        })
+                        .unzip();
+                    let end_offset = e.range().end - code_start;
                     let locals = e.get_locals_reader().unwrap()
                         .into_iter()
                         .map(|l| l.unwrap())
                         .collect::<Vec<_>>();
 
-                    codes.push(FunctionBody { operators, locals });
+                    codes.push(FunctionBody { operators, offsets, end_offset, locals });
                 }
                 Payload::TableSection(t) => {
                     for table in t {
@@ -380,6 +449,24 @@ impl<'a> From<&'a [u8]> for WasmFile<'a> {
                         elements.add_element(elem);
                     }
                 }
+                Payload::StartSection { func, .. } => {
+                    start_func = Some(func as usize);
+                }
+                Payload::CustomSection(c) if c.name() == "name" => {
+                    let reader = NameSectionReader::new(c.data(), c.data_offset()).unwrap();
+                    for name in reader {
+                        if let Name::Function(map) = name.unwrap() {
+                            let mut map = map.get_map().unwrap();
+                            for _ in 0..map.get_count() {
+                                let naming = map.read().unwrap();
+                                func_names.insert(naming.index as usize, demangle(naming.name));
+                            }
+                        }
+                    }
+                }
+                Payload::CustomSection(c) if c.name().starts_with(".debug_") => {
+                    debug_sections.insert(c.name(), c.data());
+                }
                 Payload::End(_) => {}
                 _other => {
                     println!("TODO: Unknown section {:?}", _other);
@@ -403,23 +490,66 @@ impl<'a> From<&'a [u8]> for WasmFile<'a> {
 
         println!("{:?}", exports);
 
-        WasmFile { functions, memory, globals, exports, imports, types, tables, data, elements, bodies: codes }
+        WasmFile { functions, memory, globals, exports, imports, types, tables, data, elements, bodies: codes, start_func, func_names, debug_sections }
     }
 }
 
-pub fn eval_const_expr(init_expr: &ConstExpr) -> Vec<TypedValue> {
-    let ops = init_expr.get_operators_reader().into_iter().map(|o| o.unwrap()).collect::<Vec<_>>();
+/// Evaluates a constant expression, including the arithmetic allowed by the extended-const proposal.
+/// `globals` holds the values it can read, as returned by `WasmFile::const_globals`.
+pub fn eval_const_expr(init_expr: &ConstExpr, globals: &[Option<TypedValue>]) -> Vec<TypedValue> {
+    fn pop_i32(stack: &mut Vec<TypedValue>) -> i32 {
+        stack.pop().and_then(TypedValue::into_i32).expect("expected an i32 operand in constant expression")
+    }
+
+    fn pop_i64(stack: &mut Vec<TypedValue>) -> i64 {
+        stack.pop().and_then(TypedValue::into_i64).expect("expected an i64 operand in constant expression")
+    }
+
+    let mut stack = Vec::new();
 
-    match &ops[..] {
-        &[Operator::I32Const { value }, Operator::End] => {
-            vec![TypedValue::I32(value)]
+    for op in init_expr.get_operators_reader().into_iter() {
+        match op.unwrap() {
+            Operator::I32Const { value } => stack.push(TypedValue::I32(value)),
+            Operator::I64Const { value } => stack.push(TypedValue::I64(value)),
+            // References are stored as handles, the same way `ref.null` and `ref.func` are lowered
+            Operator::RefNull { .. } => stack.push(TypedValue::I32(0)),
+            Operator::RefFunc { function_index } => stack.push(TypedValue::I32(function_index as i32 + 1)),
+            Operator::GlobalGet { global_index } => {
+                let value = globals.get(global_index as usize).copied().flatten().unwrap_or_else(|| {
+                    panic!("constant expression reads global {global_index}, whose value isn't known when compiling, only immutable globals defined before it can be read")
+                });
+                stack.push(value);
+            }
+            op @ (Operator::I32Add | Operator::I32Sub | Operator::I32Mul) => {
+                let rhs = pop_i32(&mut stack);
+                let lhs = pop_i32(&mut stack);
+                let result = match op {
+                    Operator::I32Add => lhs.wrapping_add(rhs),
+                    Operator::I32Sub => lhs.wrapping_sub(rhs),
+                    _ => lhs.wrapping_mul(rhs),
+                };
+                stack.push(TypedValue::I32(result));
+            }
+            op @ (Operator::I64Add | Operator::I64Sub | Operator::I64Mul) => {
+                let rhs = pop_i64(&mut stack);
+                let lhs = pop_i64(&mut stack);
+                let result = match op {
+                    Operator::I64Add => lhs.wrapping_add(rhs),
+                    Operator::I64Sub => lhs.wrapping_sub(rhs),
+                    _ => lhs.wrapping_mul(rhs),
+                };
+                stack.push(TypedValue::I64(result));
+            }
+            Operator::End => break,
+            op => panic!("{op:?} is not supported in constant expressions"),
         }
-        ops => todo!("{:?}", ops)
     }
+
+    stack
 }
 
-pub fn eval_const_expr_single(init_expr: &ConstExpr) -> TypedValue {
-    let result = eval_const_expr(init_expr);
+pub fn eval_const_expr_single(init_expr: &ConstExpr, globals: &[Option<TypedValue>]) -> TypedValue {
+    let result = eval_const_expr(init_expr, globals);
     assert_eq!(result.len(), 1);
     result.into_iter().next().unwrap()
 }
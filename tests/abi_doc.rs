@@ -0,0 +1,97 @@
+use datapack_common::functions::command_components::{Objective, ScoreHolder};
+use datapack_vm::Interpreter;
+use serde_json::Value;
+use wasmcraft::{wasm_file::WasmFile, abi_doc::{self, AbiDocFormat}};
+
+mod test_common;
+use test_common::{compile_with, context, get_var, run_function};
+
+/// A module exporting:
+///
+/// (global $count (mut i32) (i32.const 7))
+///
+/// (func $scale (param i64 i32) (result i64)
+///   (i64.mul (local.get 0) (i64.extend_i32_s (local.get 1))))
+const ABI_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x07, 0x01, 0x60, 0x02, 0x7E, 0x7F, 0x01, 0x7E,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Global section
+	0x06, 0x06, 0x01, 0x7F, 0x01, 0x41, 0x07, 0x0B,
+	// Export section
+	0x07, 0x09, 0x01, 0x05, b's', b'c', b'a', b'l', b'e', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0A, 0x01,
+	0x08, 0x00, 0x20, 0x00, 0x20, 0x01, 0xAC, 0x7E, 0x0B,
+];
+
+/// Compiles the module and loads it into datapack_vm, returning it with its JSON ABI document
+fn start_with_doc() -> (Interpreter, Value) {
+	let ctx = context(&["-O1"]);
+	let file = WasmFile::from(ABI_MODULE);
+	let doc = abi_doc::generate(&ctx, &file, &ctx.compute_ssa(&file), AbiDocFormat::Json);
+	let doc = serde_json::from_str(&doc).unwrap_or_else(|err| panic!("invalid JSON: {err}\n{doc}"));
+
+	let mut interp = Interpreter::new(compile_with(&ctx, ABI_MODULE), 0);
+	run_function(&mut interp, "wasmrunner:init");
+	(interp, doc)
+}
+
+fn holders(values: &Value) -> Vec<Vec<String>> {
+	values.as_array().unwrap().iter().map(|value| {
+		value["holders"].as_array().unwrap().iter().map(|holder| holder.as_str().unwrap().to_string()).collect()
+	}).collect()
+}
+
+fn set_score(interp: &mut Interpreter, holder: &str, objective: &str, value: i32) {
+	interp.set_named_score(&ScoreHolder::new(holder.to_string()).unwrap(), &Objective::new(objective.to_string()).unwrap(), value);
+}
+
+/// Calls `scale` through the given function and holders, like another datapack following the document would
+fn call_scale(interp: &mut Interpreter, function: &Value, params: &Value, returns: &Value, objective: &str) -> i64 {
+	let params = holders(params);
+	let lhs = 0x1_0000_0003_i64;
+	set_score(interp, &params[0][0], objective, lhs as i32);
+	set_score(interp, &params[0][1], objective, (lhs >> 32) as i32);
+	set_score(interp, &params[1][0], objective, -2);
+
+	run_function(interp, function.as_str().unwrap());
+
+	let returns = holders(returns);
+	let lo = get_var(interp, &format!("{} {objective}", returns[0][0])).unwrap();
+	let hi = get_var(interp, &format!("{} {objective}", returns[0][1])).unwrap();
+	(lo as u32 as i64) | ((hi as i64) << 32)
+}
+
+#[test]
+fn documented_holders_call_the_export() {
+	let (mut interp, doc) = start_with_doc();
+	let objective = doc["objective"].as_str().unwrap();
+	let export = doc["exports"].as_array().unwrap().iter().find(|export| export["name"] == "scale").unwrap();
+
+	let result = call_scale(&mut interp, &export["function"], &export["params"], &export["returns"], objective);
+	assert_eq!(result, 0x1_0000_0003 * -2);
+}
+
+#[test]
+fn documented_call_function_calls_the_export() {
+	let (mut interp, doc) = start_with_doc();
+	let objective = doc["call_objective"].as_str().unwrap();
+	let call = &doc["exports"][0]["call"];
+
+	let result = call_scale(&mut interp, &call["function"], &call["params"], &call["returns"], objective);
+	assert_eq!(result, 0x1_0000_0003 * -2);
+}
+
+#[test]
+fn documented_global_holders_hold_the_globals() {
+	let (interp, doc) = start_with_doc();
+	let objective = doc["objective"].as_str().unwrap();
+	let global = &doc["globals"][0];
+	assert_eq!(global["type"], "i32");
+
+	let holder = global["holders"][0].as_str().unwrap();
+	assert_eq!(get_var(&interp, &format!("{holder} {objective}")), Some(7));
+}
@@ -0,0 +1,89 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{all_cmds, compile, i32_params, run_lir, run_ssa};
+
+/// A module with one page of shared memory starting with the words 0xFF and 7, exporting:
+///
+/// (func $add (param $v i32) (result i32)
+///   (i32.add
+///     (i32.mul (i32.atomic.rmw8.add_u (i32.const 0) (local.get $v)) (i32.const 1000))
+///     (i32.atomic.load (i32.const 0))))
+///
+/// (func $cas (param $expected i32) (param $new i32) (result i32)
+///   (i32.add
+///     (i32.mul (i32.atomic.rmw.cmpxchg (i32.const 4) (local.get $expected) (local.get $new)) (i32.const 1000))
+///     (i32.atomic.load (i32.const 4))))
+///
+/// (func $wait (param $expected i32) (result i32)
+///   (atomic.fence)
+///   (i32.add
+///     (memory.atomic.wait32 (i32.const 4) (local.get $expected) (i64.const -1))
+///     (i32.mul (memory.atomic.notify (i32.const 4) (i32.const 1)) (i32.const 10))))
+const ATOMICS_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0C, 0x02,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x04, 0x03, 0x00, 0x01, 0x00,
+	// Memory section
+	0x05, 0x04, 0x01, 0x03, 0x01, 0x01,
+	// Export section
+	0x07, 0x14, 0x03,
+	0x03, b'a', b'd', b'd', 0x00, 0x00,
+	0x03, b'c', b'a', b's', 0x00, 0x01,
+	0x04, b'w', b'a', b'i', b't', 0x00, 0x02,
+	// Code section
+	0x0A, 0x4B, 0x03,
+	0x15, 0x00,
+	0x41, 0x00, 0x20, 0x00, 0xFE, 0x20, 0x00, 0x00, 0x41, 0xE8, 0x07, 0x6C,
+	0x41, 0x00, 0xFE, 0x10, 0x02, 0x00,
+	0x6A,
+	0x0B,
+	0x17, 0x00,
+	0x41, 0x04, 0x20, 0x00, 0x20, 0x01, 0xFE, 0x48, 0x02, 0x00, 0x41, 0xE8, 0x07, 0x6C,
+	0x41, 0x04, 0xFE, 0x10, 0x02, 0x00,
+	0x6A,
+	0x0B,
+	0x1B, 0x00,
+	0xFE, 0x03, 0x00,
+	0x41, 0x04, 0x20, 0x00, 0x42, 0x7F, 0xFE, 0x01, 0x02, 0x00,
+	0x41, 0x04, 0x41, 0x01, 0xFE, 0x00, 0x02, 0x00, 0x41, 0x0A, 0x6C,
+	0x6A,
+	0x0B,
+	// Data section
+	0x0B, 0x0E, 0x01, 0x00, 0x41, 0x00, 0x0B, 0x08,
+	0xFF, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn atomics_match() {
+	let ctx = CompileContext::new_from_opt(1);
+	let cases: [(&str, &[i32], i32); 6] = [
+		// name, args, expected
+		// Narrow read-modify-write instructions wrap around
+		("add", &[1], 255_000),
+		("add", &[2], 255_001),
+		("cas", &[7, 9], 7_009),
+		("cas", &[8, 9], 7_007),
+		// Waiting on a value that can never change times out right away
+		("wait", &[7], 2),
+		("wait", &[8], 1),
+	];
+
+	for (name, args, expected) in cases {
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, ATOMICS_MODULE, name, &i32_params(args)), expected, "SSA, {name}{args:?}");
+		assert_eq!(run_lir(&ctx, ATOMICS_MODULE, name, &i32_params(args)), expected, "LIR, {name}{args:?}");
+	}
+}
+
+#[test]
+fn narrow_rmw_stores_one_byte() {
+	let cmds = all_cmds(&compile(ATOMICS_MODULE, &["-O1"]));
+	// Only the low byte of the sum is written back, the same way `i32.store8` does it
+	let byte_store = |cmd: &String| cmd == "scoreboard players operation %param2%0 reg %= %%256 reg" || cmd == "function intrinsic:store_byte";
+	assert!(cmds.iter().any(byte_store), "{cmds:?}");
+}
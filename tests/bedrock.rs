@@ -0,0 +1,109 @@
+use clap::Parser;
+use wasmcraft::{Args, CompileContext, pack_emitter::{PackBackend, bedrock::{BehaviorPack, UNSUPPORTED_LINE_PREFIX}}};
+
+mod test_common;
+use test_common::compile_with;
+
+/// A module exporting:
+///
+/// (func $calc (param i32) (result i32)
+///   (i32.mul
+///     (i32.add (local.get 0) (i32.const 12345))
+///     (i32.const 7)))
+const CALC_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'c', b'a', b'l', b'c', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0E, 0x01,
+	0x0C, 0x00,
+	0x20, 0x00, 0x41, 0xB9, 0xE0, 0x00, 0x6A,
+	0x41, 0x07, 0x6C,
+	0x0B,
+];
+
+fn compile(extra_args: &[&str]) -> (BehaviorPack, Vec<(String, String)>) {
+	let args = ["wasmcraft", "calc.wasm", "-o", "out/calc", "-O1", "--edition", "bedrock"].iter().chain(extra_args.iter()).copied();
+	let ctx = CompileContext::new(Args::parse_from(args));
+	let datapack = compile_with(&ctx, CALC_MODULE);
+
+	let pack = ctx.behavior_pack();
+	let (files, _) = pack.files(&datapack);
+	(pack, files)
+}
+
+/// The lines of every function that were translated, leaving out comments.
+fn commands(files: &[(String, String)]) -> Vec<&str> {
+	files.iter()
+		.filter(|(path, _)| path.ends_with(".mcfunction"))
+		.flat_map(|(_, contents)| contents.lines())
+		.filter(|line| !line.is_empty() && !line.starts_with('#'))
+		.collect()
+}
+
+#[test]
+fn pack_has_manifest_and_functions_by_path() {
+	let (_, files) = compile(&[]);
+
+	let manifest: serde_json::Value = serde_json::from_str(&files[0].1).unwrap();
+	assert_eq!(files[0].0, "manifest.json");
+	assert_eq!(manifest["header"]["name"], "calc");
+	assert_eq!(manifest["header"]["min_engine_version"], serde_json::json!([1, 19, 50]));
+	assert_eq!(manifest["modules"][0]["type"], "data");
+	assert_ne!(manifest["header"]["uuid"], manifest["modules"][0]["uuid"]);
+
+	// The UUIDs stay the same, so a recompiled pack replaces the old one
+	let (_, again) = compile(&[]);
+	assert_eq!(files[0], again[0]);
+
+	assert!(files.iter().any(|(path, _)| path == "functions/wasmrunner/init.mcfunction"));
+	assert!(files[1..].iter().all(|(path, _)| path.starts_with("functions/") && path.ends_with(".mcfunction")), "{files:?}");
+
+	let cmds = commands(&files);
+	assert!(cmds.iter().any(|cmd| cmd.contains("function wasmrunner/")), "{cmds:?}");
+	assert!(cmds.iter().all(|cmd| !cmd.contains("function wasmrunner:")), "{cmds:?}");
+}
+
+#[test]
+fn commands_are_translated_to_bedrock() {
+	let (pack, _) = compile(&[]);
+
+	assert_eq!(
+		pack.translate("execute if score %a reg matches 1 run function wasmrunner:foo").as_deref(),
+		Some("execute if score %a reg matches 1 run function wasmrunner/foo"),
+	);
+	assert_eq!(
+		pack.translate(r#"tellraw @a [{"text":"x: ","bold":true},{"score":{"name":"%x","objective":"reg"}}]"#).as_deref(),
+		Some(r#"tellraw @a {"rawtext":[{"text":"x: "},{"score":{"name":"%x","objective":"reg"}}]}"#),
+	);
+	assert_eq!(pack.translate("kill @e[tag=foo,limit=1]").as_deref(), Some("kill @e[tag=foo,c=1]"));
+
+	assert_eq!(pack.translate("execute store result score %a reg run data get storage wasm:x y"), None);
+	assert_eq!(pack.translate("execute if data storage wasm:x y run say hi"), None);
+	assert_eq!(pack.translate("summon minecraft:marker 0 0 0"), None);
+	assert_eq!(pack.translate("function wasmrunner:foo with storage wasm:x y"), None);
+}
+
+#[test]
+fn untranslated_commands_are_left_as_comments() {
+	let (_, files) = compile(&["--bedrock-no-score-operations"]);
+	let all_lines = files[1..].iter().flat_map(|(_, contents)| contents.lines()).collect::<Vec<_>>();
+
+	assert!(all_lines.iter().any(|line| line.starts_with(&format!("{UNSUPPORTED_LINE_PREFIX}scoreboard players operation"))));
+	assert!(commands(&files).iter().all(|cmd| !cmd.contains("scoreboard players operation")));
+
+	let (_, files) = compile(&["--bedrock-engine-version", "1.19.0"]);
+	assert!(commands(&files).iter().all(|cmd| !cmd.starts_with("execute ")));
+}
+
+#[test]
+#[should_panic(expected = "--edition bedrock needs --mc-version 1.20.1 or older")]
+fn bedrock_needs_version_without_macros() {
+	CompileContext::new(Args::parse_from(["wasmcraft", "calc.wasm", "-o", "out", "--edition", "bedrock", "--mc-version", "1.20.2"]));
+}
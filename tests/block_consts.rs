@@ -0,0 +1,75 @@
+use clap::Parser;
+use wasmcraft::{Args, CompileContext, block_consts::BlockConstsFormat};
+
+const REGISTRY: &str = r#"{
+	"minecraft:air": { "states": [{ "id": 0, "default": true }] },
+	"minecraft:glass": { "states": [{ "id": 1, "default": true }] },
+	"minecraft:oak_stairs": {
+		"properties": { "facing": ["north", "south", "west", "east"] },
+		"states": [{ "id": 2, "properties": { "facing": "north" } }]
+	}
+}"#;
+
+fn context(name: &str, palette: &str, registry: Option<&str>) -> CompileContext {
+	let palette_path = std::env::temp_dir().join(format!("wasmcraft_{name}_consts_palette.json"));
+	std::fs::write(&palette_path, palette).unwrap();
+
+	let mut args = vec![
+		"wasmcraft".to_string(), "consts.wasm".to_string(), "-o".to_string(), "out".to_string(),
+		"--block-palette".to_string(), palette_path.to_str().unwrap().to_string(),
+	];
+
+	if let Some(registry) = registry {
+		let registry_path = std::env::temp_dir().join(format!("wasmcraft_{name}_consts_registry.json"));
+		std::fs::write(&registry_path, registry).unwrap();
+		args.extend(["--block-registry".to_string(), registry_path.to_str().unwrap().to_string()]);
+	}
+
+	CompileContext::new(Args::parse_from(args))
+}
+
+#[test]
+fn consts_match_palette() {
+	let ctx = context("match", r#"["minecraft:air", "glass", "minecraft:oak_stairs[facing=east]", "mymod:custom_block"]"#, None);
+
+	let header = ctx.compute_block_consts(BlockConstsFormat::C);
+	assert!(header.contains("AIR = 0,"), "{header}");
+	assert!(header.contains("GLASS = 1,"), "{header}");
+	assert!(header.contains("OAK_STAIRS_FACING_EAST = 2,"), "{header}");
+	assert!(header.contains("MYMOD_CUSTOM_BLOCK = 3,"), "{header}");
+
+	let module = ctx.compute_block_consts(BlockConstsFormat::Rust);
+	assert!(module.contains("pub const OAK_STAIRS_FACING_EAST: i32 = 2;"), "{module}");
+}
+
+#[test]
+fn duplicate_names_get_their_id() {
+	let ctx = context("duplicate", r#"["minecraft:air", "mymod:air"]"#, None);
+
+	let header = ctx.compute_block_consts(BlockConstsFormat::C);
+	assert!(header.contains("AIR = 0,"), "{header}");
+	assert!(header.contains("MYMOD_AIR = 1,"), "{header}");
+
+	let ctx = context("duplicate_props", r#"["minecraft:oak_stairs[facing=east]", "minecraft:oak_stairs_facing_east"]"#, None);
+
+	let header = ctx.compute_block_consts(BlockConstsFormat::C);
+	assert!(header.contains("OAK_STAIRS_FACING_EAST_0 = 0,"), "{header}");
+	assert!(header.contains("OAK_STAIRS_FACING_EAST_1 = 1,"), "{header}");
+}
+
+#[test]
+fn registry_accepts_known_blocks() {
+	context("known", r#"["minecraft:air", "glass", "minecraft:oak_stairs[facing=west]"]"#, Some(REGISTRY));
+}
+
+#[test]
+#[should_panic(expected = "unknown block")]
+fn registry_rejects_unknown_blocks() {
+	context("unknown", r#"["minecraft:air", "minecraft:cobblestone"]"#, Some(REGISTRY));
+}
+
+#[test]
+#[should_panic(expected = "is not a value of")]
+fn registry_rejects_unknown_property_values() {
+	context("property", r#"["minecraft:air", "minecraft:oak_stairs[facing=up]"]"#, Some(REGISTRY));
+}
@@ -0,0 +1,49 @@
+mod test_common;
+use test_common::{all_cmds, compile};
+
+/// A module exporting:
+///
+/// (import "env" "turtle_set" (func $turtle_set (param i32)))
+///
+/// (func $place
+///   (call $turtle_set (i32.const 1)))
+const BLOCK_PALETTE_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x08, 0x02,
+	0x60, 0x01, 0x7F, 0x00,
+	0x60, 0x00, 0x00,
+	// Import section
+	0x02, 0x12, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x0A, b't', b'u', b'r', b't', b'l', b'e', b'_', b's', b'e', b't', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Export section
+	0x07, 0x09, 0x01,
+	0x05, b'p', b'l', b'a', b'c', b'e', 0x00, 0x01,
+	// Code section
+	0x0A, 0x08, 0x01,
+	0x06, 0x00, 0x41, 0x01, 0x10, 0x00, 0x0B,
+];
+
+fn compile_with_palette(name: &str, palette: &str) -> Vec<String> {
+	let path = std::env::temp_dir().join(format!("wasmcraft_{name}_palette.json"));
+	std::fs::write(&path, palette).unwrap();
+
+	all_cmds(&compile(BLOCK_PALETTE_MODULE, &["-O1", "--block-palette", path.to_str().unwrap()]))
+}
+
+#[test]
+fn palette_replaces_default_blocks() {
+	let cmds = compile_with_palette("custom", r#"["minecraft:air", "minecraft:glass"]"#);
+
+	assert!(cmds.iter().any(|cmd| cmd.contains("setblock ~ ~ ~ minecraft:glass")), "{cmds:?}");
+	assert!(!cmds.iter().any(|cmd| cmd.contains("minecraft:cobblestone")), "{cmds:?}");
+}
+
+#[test]
+#[should_panic(expected = "at least one block")]
+fn palette_must_not_be_empty() {
+	compile_with_palette("empty", "[]");
+}
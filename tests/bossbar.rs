@@ -0,0 +1,44 @@
+mod test_common;
+use test_common::{context, run_ssa, ssa_instrs};
+use wasmcraft::ssa::{SsaInstr, interp::TypedValue};
+
+/// A module exporting:
+///
+/// (import "env" "mc_bossbar_create" (func $mc_bossbar_create (param i32)))
+/// (import "env" "mc_bossbar_value" (func $mc_bossbar_value (param i32 i32)))
+///
+/// (func $show (param $value i32)
+///   (call $mc_bossbar_create (i32.const 1))
+///   (call $mc_bossbar_value (i32.const 1) (local.get $value)))
+const BOSSBAR_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0A, 0x02,
+	0x60, 0x01, 0x7F, 0x00,
+	0x60, 0x02, 0x7F, 0x7F, 0x00,
+	// Import section
+	0x02, 0x30, 0x02,
+	0x03, b'e', b'n', b'v',
+	0x11, b'm', b'c', b'_', b'b', b'o', b's', b's', b'b', b'a', b'r', b'_', b'c', b'r', b'e', b'a', b't', b'e', 0x00, 0x00,
+	0x03, b'e', b'n', b'v',
+	0x10, b'm', b'c', b'_', b'b', b'o', b's', b's', b'b', b'a', b'r', b'_', b'v', b'a', b'l', b'u', b'e', 0x00, 0x01,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b's', b'h', b'o', b'w', 0x00, 0x02,
+	// Code section
+	0x0A, 0x0E, 0x01,
+	0x0C, 0x00, 0x41, 0x01, 0x10, 0x00, 0x41, 0x01, 0x20, 0x00, 0x10, 0x01, 0x0B,
+];
+
+#[test]
+fn bossbar_intrinsics_are_lowered() {
+	let ctx = context(&["-O1"]);
+	let instrs = ssa_instrs(&ctx, BOSSBAR_MODULE);
+	assert_eq!(instrs.iter().filter(|instr| matches!(instr, SsaInstr::BossbarCreate(_))).count(), 1);
+	assert_eq!(instrs.iter().filter(|instr| matches!(instr, SsaInstr::BossbarSetValue(_, _))).count(), 1);
+
+	// Bossbars only exist in the game, so the interpreter skips over them
+	assert_eq!(run_ssa(&ctx, BOSSBAR_MODULE, "show", &[TypedValue::I32(5)]), Vec::new());
+}
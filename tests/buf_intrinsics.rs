@@ -0,0 +1,135 @@
+mod test_common;
+use test_common::{call_datapack, compile, func_cmds, i32_params, start_datapack};
+use wasmcraft::ssa::interp::TypedValue;
+
+/// A module with one page of memory holding the bytes 0x11 to 0x88 at address 16, exporting:
+///
+/// (import "env" "buf_get_u8" (func $buf_get_u8 (param i32 i32) (result i32)))
+/// (import "env" "buf_get_u16" (func $buf_get_u16 (param i32 i32) (result i32)))
+/// (import "env" "buf_set_u8" (func $buf_set_u8 (param i32 i32 i32)))
+/// (import "env" "buf_set_u16" (func $buf_set_u16 (param i32 i32 i32)))
+///
+/// (func $get8 (param i32 i32) (result i32)
+///   (call $buf_get_u8 (local.get 0) (local.get 1)))
+/// (func $get8_fixed (result i32)
+///   (call $buf_get_u8 (i32.const 16) (i32.const 3)))
+/// (func $get16_crossing (result i32)
+///   (call $buf_get_u16 (i32.const 17) (i32.const 1)))
+/// (func $set8_fixed (param i32)
+///   (call $buf_set_u8 (i32.const 16) (i32.const 1) (local.get 0)))
+/// (func $set16_fixed (param i32)
+///   (call $buf_set_u16 (i32.const 16) (i32.const 1) (local.get 0)))
+const BUF_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x15, 0x04,
+	0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	0x60, 0x03, 0x7F, 0x7F, 0x7F, 0x00,
+	0x60, 0x00, 0x01, 0x7F,
+	0x60, 0x01, 0x7F, 0x00,
+	// Import section
+	0x02, 0x47, 0x04,
+	0x03, b'e', b'n', b'v', 0x0A, b'b', b'u', b'f', b'_', b'g', b'e', b't', b'_', b'u', b'8', 0x00, 0x00,
+	0x03, b'e', b'n', b'v', 0x0B, b'b', b'u', b'f', b'_', b'g', b'e', b't', b'_', b'u', b'1', b'6', 0x00, 0x00,
+	0x03, b'e', b'n', b'v', 0x0A, b'b', b'u', b'f', b'_', b's', b'e', b't', b'_', b'u', b'8', 0x00, 0x01,
+	0x03, b'e', b'n', b'v', 0x0B, b'b', b'u', b'f', b'_', b's', b'e', b't', b'_', b'u', b'1', b'6', 0x00, 0x01,
+	// Function section
+	0x03, 0x06, 0x05, 0x00, 0x02, 0x02, 0x03, 0x03,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x41, 0x05,
+	0x04, b'g', b'e', b't', b'8', 0x00, 0x04,
+	0x0A, b'g', b'e', b't', b'8', b'_', b'f', b'i', b'x', b'e', b'd', 0x00, 0x05,
+	0x0E, b'g', b'e', b't', b'1', b'6', b'_', b'c', b'r', b'o', b's', b's', b'i', b'n', b'g', 0x00, 0x06,
+	0x0A, b's', b'e', b't', b'8', b'_', b'f', b'i', b'x', b'e', b'd', 0x00, 0x07,
+	0x0B, b's', b'e', b't', b'1', b'6', b'_', b'f', b'i', b'x', b'e', b'd', 0x00, 0x08,
+	// Code section
+	0x0A, 0x32, 0x05,
+	0x08, 0x00, 0x20, 0x00, 0x20, 0x01, 0x10, 0x00, 0x0B,
+	0x08, 0x00, 0x41, 0x10, 0x41, 0x03, 0x10, 0x00, 0x0B,
+	0x08, 0x00, 0x41, 0x11, 0x41, 0x01, 0x10, 0x01, 0x0B,
+	0x0A, 0x00, 0x41, 0x10, 0x41, 0x01, 0x20, 0x00, 0x10, 0x02, 0x0B,
+	0x0A, 0x00, 0x41, 0x10, 0x41, 0x01, 0x20, 0x00, 0x10, 0x03, 0x0B,
+	// Data section
+	0x0B, 0x0E, 0x01,
+	0x00, 0x41, 0x10, 0x0B, 0x08, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88,
+];
+
+/// How many commands running a function can take, counting the commands of the functions it calls
+fn expanded_len(funcs: &[(String, Vec<String>)], id: &str, calls: &mut Vec<String>) -> usize {
+	let Some((_, cmds)) = funcs.iter().find(|(name, _)| name == id) else { return 0 };
+	if calls.iter().any(|call| call == id) {
+		return 0;
+	}
+
+	calls.push(id.to_string());
+	let len = cmds.iter().map(|cmd| {
+		match cmd.split_once("function ") {
+			Some((_, callee)) if !callee.contains(' ') => 1 + expanded_len(funcs, callee, calls),
+			_ => 1,
+		}
+	}).sum();
+	calls.pop();
+	len
+}
+
+fn reaches(funcs: &[(String, Vec<String>)], id: &str, callee: &str) -> bool {
+	let mut visited = vec![id.to_string()];
+	let mut idx = 0;
+	while let Some(id) = visited.get(idx).cloned() {
+		idx += 1;
+		let Some((_, cmds)) = funcs.iter().find(|(name, _)| *name == id) else { continue };
+		for cmd in cmds {
+			if let Some((_, called)) = cmd.split_once("function ").filter(|(_, called)| !called.contains(' ')) {
+				if called == callee {
+					return true;
+				}
+				if !visited.iter().any(|seen| seen == called) {
+					visited.push(called.to_string());
+				}
+			}
+		}
+	}
+	false
+}
+
+#[test]
+fn known_positions_skip_the_intrinsics() {
+	let funcs = func_cmds(&compile(BUF_MODULE, &["-O1"]));
+
+	assert!(reaches(&funcs, "wasmrunner:get8", "intrinsic:buf_get_u8"));
+	assert!(!reaches(&funcs, "wasmrunner:get8_fixed", "intrinsic:buf_get_u8"));
+	assert!(!reaches(&funcs, "wasmrunner:set8_fixed", "intrinsic:buf_set_u8"));
+	assert!(!reaches(&funcs, "wasmrunner:set16_fixed", "intrinsic:buf_set_u16"));
+	assert!(!reaches(&funcs, "wasmrunner:get16_crossing", "intrinsic:buf_get_u16"));
+
+	// Halfwords crossing a word still share the intrinsic's second half
+	assert!(reaches(&funcs, "wasmrunner:get16_crossing", "intrinsic:buf_get_u16/crossing"));
+
+	let generic = expanded_len(&funcs, "wasmrunner:get8", &mut Vec::new());
+	let fixed = expanded_len(&funcs, "wasmrunner:get8_fixed", &mut Vec::new());
+	assert!(fixed < generic, "{fixed} >= {generic}");
+}
+
+#[test]
+fn known_positions_access_the_right_bytes() {
+	let mut interp = start_datapack(BUF_MODULE, &["-O1"]);
+	let mut call = |name: &str, params: &[i32]| call_datapack(&mut interp, name, &i32_params(params), false);
+
+	assert_eq!(call("get8_fixed", &[]), TypedValue::I32(0x44));
+	assert_eq!(call("get16_crossing", &[]), TypedValue::I32(0x5544));
+
+	// Only the low byte is written
+	call("set8_fixed", &[0x1AB]);
+	assert_eq!(call("get8", &[16, 0]), TypedValue::I32(0x11));
+	assert_eq!(call("get8", &[16, 1]), TypedValue::I32(0xAB));
+	assert_eq!(call("get8", &[16, 2]), TypedValue::I32(0x33));
+
+	// Leaves the word negative, which the top byte has to be read out of correctly
+	call("set16_fixed", &[0xBEEF]);
+	assert_eq!(call("get8", &[16, 1]), TypedValue::I32(0xAB));
+	assert_eq!(call("get8", &[16, 2]), TypedValue::I32(0xEF));
+	assert_eq!(call("get8_fixed", &[]), TypedValue::I32(0xBE));
+	assert_eq!(call("get16_crossing", &[]), TypedValue::I32(0x55BE));
+}
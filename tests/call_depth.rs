@@ -0,0 +1,58 @@
+use datapack_vm::Interpreter;
+use wasmcraft::lir::Register;
+
+mod test_common;
+use test_common::{all_cmds, compile, get_trap, run_function};
+
+/// (func (export "down") (param i32) (result i32)
+///   (if (result i32) (local.get 0)
+///     (then (i32.add (call 0 (i32.sub (local.get 0) (i32.const 1))) (i32.const 1)))
+///     (else (i32.const 0))))
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'd', b'o', b'w', b'n', 0x00, 0x00,
+	// Code section
+	0x0A, 0x16, 0x01,
+	0x14, 0x00, 0x20, 0x00, 0x04, 0x7F, 0x20, 0x00, 0x41, 0x01, 0x6B, 0x10, 0x00, 0x41, 0x01, 0x6A, 0x05, 0x41, 0x00, 0x0B, 0x0B,
+];
+
+fn down(interp: &mut Interpreter, n: i32) -> Option<i32> {
+	let (holder, objective) = Register::param_lo(0).scoreboard_pair();
+	interp.set_named_score(&holder, &objective, n);
+	run_function(interp, "wasmrunner:down");
+	let (holder, objective) = Register::return_lo(0).scoreboard_pair();
+	interp.get_named_score(&holder, &objective)
+}
+
+#[test]
+fn deep_calls_trap() {
+	let datapack = compile(MODULE, &["--max-call-depth", "10"]);
+
+	let cmds = all_cmds(&datapack);
+	assert!(cmds.iter().any(|cmd| cmd.contains(r#"{"text":"Trap: call stack exhausted after 10 nested calls (wasm function 0, block "#)), "{cmds:?}");
+
+	let mut interp = Interpreter::new(datapack, 0);
+	run_function(&mut interp, "wasmrunner:init");
+
+	assert_eq!(down(&mut interp, 9), Some(9));
+	assert_eq!(get_trap(&mut interp), Some(0));
+
+	down(&mut interp, 10);
+	assert_eq!(get_trap(&mut interp), Some(7));
+
+	// Every frame was popped again, so the depth starts over
+	assert_eq!(down(&mut interp, 9), Some(9));
+	assert_eq!(get_trap(&mut interp), Some(0));
+}
+
+#[test]
+fn zero_turns_check_off() {
+	let cmds = all_cmds(&compile(MODULE, &["--max-call-depth", "0"]));
+	assert!(cmds.iter().all(|cmd| !cmd.contains("call stack exhausted")), "{cmds:?}");
+}
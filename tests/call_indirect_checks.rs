@@ -0,0 +1,77 @@
+use datapack_vm::Interpreter;
+use wasmcraft::{CompileContext, lir::Register};
+
+mod test_common;
+use test_common::{all_cmds, compile, get_trap, i32_params, run_function, run_ssa};
+
+/// (type $t0 (func (param i32) (result i32)))
+/// (type $t1 (func))
+///
+/// (table 3 funcref)
+/// (elem (i32.const 0) $id $nop)
+///
+/// (func $id (type $t0) (local.get 0))
+/// (func $nop (type $t1))
+///
+/// (func (export "run") (param i32) (result i32)
+///   (call_indirect (type $t0) (i32.const 7) (local.get 0)))
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x09, 0x02,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	0x60, 0x00, 0x00,
+	// Function section
+	0x03, 0x04, 0x03, 0x00, 0x01, 0x00,
+	// Table section
+	0x04, 0x04, 0x01, 0x70, 0x00, 0x03,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x02,
+	// Element section
+	0x09, 0x08, 0x01,
+	0x00, 0x41, 0x00, 0x0B, 0x02, 0x00, 0x01,
+	// Code section
+	0x0A, 0x13, 0x03,
+	0x04, 0x00, 0x20, 0x00, 0x0B,
+	0x02, 0x00, 0x0B,
+	0x09, 0x00, 0x41, 0x07, 0x20, 0x00, 0x11, 0x00, 0x00, 0x0B,
+];
+
+#[test]
+fn bad_entries_trap_with_index() {
+	let datapack = compile(MODULE, &[]);
+
+	let cmds = all_cmds(&datapack);
+	for kind in ["uninitialized element", "indirect call type mismatch", "out of bounds table access"] {
+		let message = format!(r#"tellraw @a [{{"text":"Trap: {kind} at index ","color":"red"}},{{"score":"#);
+		assert!(cmds.iter().any(|cmd| cmd.contains(&message)), "{kind}: {cmds:?}");
+	}
+
+	let mut interp = Interpreter::new(datapack, 0);
+	run_function(&mut interp, "wasmrunner:init");
+
+	let (holder, objective) = Register::param_lo(0).scoreboard_pair();
+	for (idx, trap) in [(0, 0), (1, 6), (2, 2), (3, 3), (-1, 3)] {
+		interp.set_named_score(&holder, &objective, idx);
+		run_function(&mut interp, "wasmrunner:run");
+		assert_eq!(get_trap(&mut interp), Some(trap), "index {idx}");
+	}
+
+	interp.set_named_score(&holder, &objective, 0);
+	run_function(&mut interp, "wasmrunner:run");
+	let (holder, objective) = Register::return_lo(0).scoreboard_pair();
+	assert_eq!(interp.get_named_score(&holder, &objective), Some(7));
+}
+
+#[test]
+fn checks_can_be_left_out() {
+	let cmds = all_cmds(&compile(MODULE, &["--no-call-indirect-checks"]));
+	assert!(cmds.iter().all(|cmd| !cmd.contains(" at index ")), "{cmds:?}");
+}
+
+#[test]
+#[should_panic(expected = "trap: uninitialized element at index 2")]
+fn interpreter_panics_on_null_entry() {
+	run_ssa(&CompileContext::new_from_opt(1), MODULE, "run", &i32_params(&[2]));
+}
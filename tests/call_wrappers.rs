@@ -0,0 +1,74 @@
+use wasmcraft::{CompileContext, wasm_file::WasmFile, abi_doc::{self, AbiDocFormat}};
+
+mod test_common;
+use test_common::{compile_with, find_func, func_cmds};
+
+/// A module exporting:
+///
+/// (func $add (param i32 i32) (result i32)
+///   (i32.add (local.get 0) (local.get 1)))
+///
+/// (func $wide (param i64) (result i64)
+///   (local.get 0))
+const CALL_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0C, 0x02,
+	0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	0x60, 0x01, 0x7E, 0x01, 0x7E,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x01,
+	// Export section
+	0x07, 0x0E, 0x02,
+	0x03, b'a', b'd', b'd', 0x00, 0x00,
+	0x04, b'w', b'i', b'd', b'e', 0x00, 0x01,
+	// Code section
+	0x0A, 0x0E, 0x02,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6A, 0x0B,
+	0x04, 0x00, 0x20, 0x00, 0x0B,
+];
+
+fn context() -> CompileContext {
+	test_common::context(&["-O1"])
+}
+
+fn compile() -> Vec<(String, Vec<String>)> {
+	func_cmds(&compile_with(&context(), CALL_MODULE))
+}
+
+#[test]
+fn call_function_uses_call_objective() {
+	let funcs = compile();
+
+	let init = find_func(&funcs, "wasmrunner:init");
+	assert!(init.iter().any(|cmd| cmd == "scoreboard objectives add wasm_call dummy"), "{init:?}");
+
+	let call = find_func(&funcs, "wasmrunner:call/add");
+	assert!(call.iter().any(|cmd| cmd.starts_with("scoreboard players operation %param%0%lo reg = arg0 wasm_call")), "{call:?}");
+	assert!(call.iter().any(|cmd| cmd.starts_with("scoreboard players operation %param%1%lo reg = arg1 wasm_call")), "{call:?}");
+	assert!(call.iter().any(|cmd| cmd == "function wasmrunner:add"), "{call:?}");
+	assert!(call.last().unwrap().starts_with("scoreboard players operation ret0 wasm_call = %return%0%lo reg"), "{call:?}");
+}
+
+#[test]
+fn call_function_splits_64_bit_values() {
+	let funcs = compile();
+
+	let call = find_func(&funcs, "wasmrunner:call/wide");
+	assert!(call.iter().any(|cmd| cmd.starts_with("scoreboard players operation %param%0%hi reg = arg0_hi wasm_call")), "{call:?}");
+	assert!(call.iter().any(|cmd| cmd.starts_with("scoreboard players operation ret0_hi wasm_call = %return%0%hi reg")), "{call:?}");
+}
+
+#[test]
+fn abi_doc_lists_call_function() {
+	let ctx = context();
+	let file = WasmFile::from(CALL_MODULE);
+	let program = ctx.compute_ssa(&file);
+
+	let doc = abi_doc::generate(&ctx, &file, &program, AbiDocFormat::Markdown);
+	assert!(doc.contains("Call function: `wasmrunner:call/add`"), "{doc}");
+	assert!(doc.contains("- Parameter 1 (i32): `arg1 wasm_call`"), "{doc}");
+
+	let doc = abi_doc::generate(&ctx, &file, &program, AbiDocFormat::Json);
+	assert!(doc.contains(r#""call":{"function":"wasmrunner:call/wide""#), "{doc}");
+}
@@ -0,0 +1,112 @@
+use wasmcraft::{wasm_file::WasmFile, pack_emitter::command_blocks::CommandBlockScript};
+
+mod test_common;
+use test_common::context;
+
+/// A module exporting:
+///
+/// (func $calc (param i32) (result i32)
+///   (i32.mul
+///     (i32.add (local.get 0) (i32.const 12345))
+///     (i32.const 7)))
+const CALC_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'c', b'a', b'l', b'c', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0E, 0x01,
+	0x0C, 0x00,
+	0x20, 0x00, 0x41, 0xB9, 0xE0, 0x00, 0x6A,
+	0x41, 0x07, 0x6C,
+	0x0B,
+];
+
+/// A module exporting:
+///
+/// (func $sum (param i32) (result i32) (local i32)
+///   (block
+///     (loop
+///       (br_if 1 (i32.eqz (local.get 0)))
+///       (local.set 1 (i32.add (local.get 1) (local.get 0)))
+///       (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+///       (br 0)))
+///   (local.get 1))
+const SUM_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b's', b'u', b'm', 0x00, 0x00,
+	// Code section
+	0x0A, 0x23, 0x01,
+	0x21, 0x01, 0x01, 0x7F,
+	0x02, 0x40,
+	0x03, 0x40,
+	0x20, 0x00, 0x45, 0x0D, 0x01,
+	0x20, 0x01, 0x20, 0x00, 0x6A, 0x21, 0x01,
+	0x20, 0x00, 0x41, 0x01, 0x6B, 0x21, 0x00,
+	0x0C, 0x00,
+	0x0B,
+	0x0B,
+	0x20, 0x01,
+	0x0B,
+];
+
+fn build(module: &[u8]) -> CommandBlockScript {
+	let ctx = context(&["-O1", "--command-blocks", "out.txt", "--command-blocks-origin", "10,64,20"]);
+	let file = WasmFile::from(module);
+	let lir_program = ctx.compute_lir(ctx.compute_ssa(&file));
+	let datapack = ctx.compute_datapack(&lir_program);
+	ctx.compute_command_blocks(&lir_program, &datapack)
+}
+
+#[test]
+fn straight_line_exports_become_chains() {
+	let script = build(CALC_MODULE);
+
+	let (_, len, pos) = script.chains.iter().find(|(entry, _, _)| entry == "wasmrunner:calc").unwrap_or_else(|| panic!("{:?}", script.skipped));
+	assert!(*len > 0);
+	let calc = script.commands.iter()
+		.filter(|cmd| cmd.contains(&format!(" {} {} ", pos.1, pos.2)))
+		.collect::<Vec<_>>();
+	assert_eq!(calc.len(), *len);
+
+	assert!(calc[0].starts_with(&format!("setblock {} {} {} command_block[facing=east]{{Command:\"", pos.0, pos.1, pos.2)), "{}", calc[0]);
+	for (idx, cmd) in calc.iter().enumerate().skip(1) {
+		assert!(cmd.starts_with(&format!("setblock {} {} {} chain_command_block[facing=east]{{auto:1b,", pos.0 + idx as i32, pos.1, pos.2)), "{cmd}");
+	}
+
+	// Every call was inlined
+	assert!(!calc.iter().any(|cmd| cmd.contains("function ")), "{calc:?}");
+}
+
+#[test]
+fn chains_are_placed_a_row_apart() {
+	let script = build(CALC_MODULE);
+
+	let positions = script.chains.iter().map(|(_, _, pos)| *pos).collect::<Vec<_>>();
+	assert_eq!(positions[0], (10, 64, 20));
+	for (idx, pos) in positions.iter().enumerate() {
+		assert_eq!(*pos, (10, 64, 20 + 2 * idx as i32));
+	}
+	assert_eq!(script.to_script().lines().count(), script.commands.len());
+}
+
+#[test]
+fn loops_are_skipped() {
+	let script = build(SUM_MODULE);
+
+	let (_, reason) = script.skipped.iter().find(|(entry, _)| entry == "wasmrunner:sum").unwrap();
+	assert!(reason.contains("loops") || reason.contains("later tick"), "{reason}");
+	assert!(!script.chains.iter().any(|(entry, _, _)| entry == "wasmrunner:sum"));
+}
@@ -0,0 +1,56 @@
+mod test_common;
+use test_common::{find_func, func_cmds};
+
+/// A module exporting:
+///
+/// (func $calc (param i32) (result i32)
+///   (i32.mul
+///     (i32.add (local.get 0) (i32.const 12345))
+///     (i32.const 7)))
+const CALC_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'c', b'a', b'l', b'c', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0E, 0x01,
+	0x0C, 0x00,
+	0x20, 0x00, 0x41, 0xB9, 0xE0, 0x00, 0x6A,
+	0x41, 0x07, 0x6C,
+	0x0B,
+];
+
+fn compile() -> Vec<(String, Vec<String>)> {
+	func_cmds(&test_common::compile(CALC_MODULE, &["-O1"]))
+}
+
+#[test]
+fn constants_are_set_up_once_by_their_own_function() {
+	let funcs = compile();
+
+	let init = find_func(&funcs, "wasmrunner:init");
+	assert!(init.iter().any(|cmd| cmd == "function wasmrunner:__init_constants"), "{init:?}");
+	assert!(!init.iter().any(|cmd| cmd.starts_with("scoreboard players set %const%")), "{init:?}");
+
+	let constants = find_func(&funcs, "wasmrunner:__init_constants");
+	assert!(constants.iter().any(|cmd| cmd == "scoreboard players set %const%7 reg 7"), "{constants:?}");
+
+	let mut sorted = constants.to_vec();
+	sorted.sort_by_key(|cmd| cmd.rsplit(' ').next().unwrap().parse::<i32>().unwrap());
+	sorted.dedup();
+	assert_eq!(constants, sorted);
+}
+
+#[test]
+fn constants_used_as_literals_have_no_holder() {
+	let funcs = compile();
+
+	let all = funcs.iter().flat_map(|(_, cmds)| cmds.iter()).collect::<Vec<_>>();
+	assert!(all.iter().any(|cmd| cmd.ends_with(" 12345") && cmd.starts_with("scoreboard players add ")), "{all:?}");
+	assert!(!all.iter().any(|cmd| cmd.contains("%const%12345")), "{all:?}");
+}
@@ -0,0 +1,81 @@
+mod test_common;
+use test_common::{find_func, func_cmds};
+
+/// A module exporting:
+///
+/// (func $_start)
+/// (func $test_one)
+const CONTROLS_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x04, 0x01,
+	0x60, 0x00, 0x00,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x00,
+	// Export section
+	0x07, 0x15, 0x02,
+	0x06, b'_', b's', b't', b'a', b'r', b't', 0x00, 0x00,
+	0x08, b't', b'e', b's', b't', b'_', b'o', b'n', b'e', 0x00, 0x01,
+	// Code section
+	0x0A, 0x07, 0x02,
+	0x02, 0x00, 0x0B,
+	0x02, 0x00, 0x0B,
+];
+
+fn compile(extra_args: &[&str]) -> Vec<(String, Vec<String>)> {
+	func_cmds(&test_common::compile(CONTROLS_MODULE, &[&["-O1"], extra_args].concat()))
+}
+
+fn button(text: &str, color: &str, command: &str) -> String {
+	format!(r#"{{"text":"[{text}]","color":"{color}","clickEvent":{{"action":"run_command","value":"/{command}"}}}}"#)
+}
+
+#[test]
+fn controls_show_a_button_for_each_action() {
+	let funcs = compile(&["--controls"]);
+
+	let controls = find_func(&funcs, "wasmrunner:controls");
+	let buttons = [
+		button("Reset", "yellow", "function wasmrunner:init"),
+		button("Stop", "red", "function wasmrunner:__stop"),
+		button("Run tests", "aqua", "function wasmrunner:__run_tests"),
+		button("Status", "gray", "function wasmrunner:__status"),
+	];
+	assert_eq!(controls, [
+		r#"tellraw @s [{"text":"Wasmcraft controls","bold":true}]"#.to_string(),
+		format!("tellraw @s [{}]", buttons.join(r#",{"text":" "},"#)),
+		format!("tellraw @s [{}]", button("Start", "green", "function wasmrunner:_start")),
+	]);
+}
+
+#[test]
+fn run_tests_calls_every_test_export() {
+	let funcs = compile(&["--controls"]);
+
+	let run_tests = find_func(&funcs, "wasmrunner:__run_tests");
+	assert_eq!(run_tests, [r#"tellraw @a [{"text":"Running test_one"}]"#, "function wasmrunner:test_one"]);
+}
+
+#[test]
+fn status_shows_the_command_counts() {
+	let funcs = compile(&["--controls"]);
+
+	let status = find_func(&funcs, "wasmrunner:__status");
+	assert_eq!(status[0], r#"tellraw @a [{"text":"Commands run this tick: "},{"score":{"name":"%%commands_run","objective":"reg"}}]"#);
+	assert_eq!(status[1], r#"tellraw @a [{"text":"Command limit per tick: "},{"score":{"name":"%%max_commands","objective":"reg"}}]"#);
+}
+
+#[test]
+fn stop_cancels_the_bounce_loop() {
+	let funcs = compile(&["--controls", "--jump-mode", "bounce"]);
+
+	let stop = find_func(&funcs, "wasmrunner:__stop");
+	assert_eq!(stop, ["schedule clear wasmrunner:__bounce_resume", r#"tellraw @a [{"text":"Program stopped"}]"#]);
+}
+
+#[test]
+fn controls_are_only_made_when_asked_for() {
+	let funcs = compile(&[]);
+
+	assert!(!funcs.iter().any(|(name, _)| name == "wasmrunner:controls" || name == "wasmrunner:__stop"));
+}
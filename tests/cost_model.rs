@@ -0,0 +1,70 @@
+use wasmcraft::ssa::interp::TypedValue;
+
+mod test_common;
+use test_common::{call_datapack, i32_params, start_datapack};
+
+/// A module exporting:
+///
+/// (func $sum (param i32) (result i32) (local i32)
+///   (block
+///     (loop
+///       (br_if 1 (i32.eqz (local.get 0)))
+///       (local.set 1 (i32.add (local.get 1) (local.get 0)))
+///       (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+///       (br 0)))
+///   (local.get 1))
+const SUM_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b's', b'u', b'm', 0x00, 0x00,
+	// Code section
+	0x0A, 0x23, 0x01,
+	0x21, 0x01, 0x01, 0x7F,
+	0x02, 0x40,
+	0x03, 0x40,
+	0x20, 0x00, 0x45, 0x0D, 0x01,
+	0x20, 0x01, 0x20, 0x00, 0x6A, 0x21, 0x01,
+	0x20, 0x00, 0x41, 0x01, 0x6B, 0x21, 0x00,
+	0x0C, 0x00,
+	0x0B,
+	0x0B,
+	0x20, 0x01,
+	0x0B,
+];
+
+fn write_cost_model(name: &str, contents: &str) -> String {
+	let path = std::env::temp_dir().join(format!("wasmcraft_{name}_cost_model.toml"));
+	std::fs::write(&path, contents).unwrap();
+	path.to_str().unwrap().to_string()
+}
+
+/// Sums 1 to 5000 in the datapack, returning how many ticks it took
+fn sum_ticks(args: &[&str]) -> u64 {
+	let mut interp = start_datapack(SUM_MODULE, &[&["-O1"], args].concat());
+	let start = interp.tick;
+	assert_eq!(call_datapack(&mut interp, "sum", &i32_params(&[5000]), false), TypedValue::I32(12502500));
+	(interp.tick - start) as u64
+}
+
+#[test]
+fn smaller_budgets_take_more_ticks() {
+	let vanilla = sum_ticks(&["--cost-model", "vanilla"]);
+	let server = sum_ticks(&["--cost-model", "server"]);
+	assert!(server > vanilla, "{server} <= {vanilla}");
+}
+
+#[test]
+fn cost_model_file_sets_the_budget() {
+	let path = write_cost_model("budget", "max_cmds_per_tick = 500\n");
+	let ticks = sum_ticks(&["--cost-model-file", &path]);
+
+	// 5000 iterations can't fit in 500 commands a tick
+	assert!(ticks >= 10, "{ticks}");
+	assert!(ticks > sum_ticks(&[]));
+}
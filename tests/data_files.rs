@@ -0,0 +1,64 @@
+use wasmcraft::ssa::interp::TypedValue;
+
+mod test_common;
+use test_common::{call_datapack, context, i32_params, run_lir, run_ssa, start_datapack};
+
+/// A module with one page of memory, which starts out holding the bytes 0x11 to 0x88, exporting:
+///
+/// (func $load (param i32) (result i32)
+///   (i32.load (local.get 0)))
+const DATA_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x08, 0x01, 0x04, b'l', b'o', b'a', b'd', 0x00, 0x00,
+	// Code section
+	0x0A, 0x09, 0x01,
+	0x07, 0x00, 0x20, 0x00, 0x28, 0x02, 0x00, 0x0B,
+	// Data section
+	0x0B, 0x0E, 0x01,
+	0x00, 0x41, 0x00, 0x0B, 0x08, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88,
+];
+
+fn write_data_file(name: &str, contents: &[u8]) -> String {
+	let path = std::env::temp_dir().join(format!("wasmcraft_{name}_data.bin"));
+	std::fs::write(&path, contents).unwrap();
+	path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn data_files_are_placed_over_the_segments() {
+	let patch = write_data_file("patch", &[0xAA, 0xBB]);
+	let far = write_data_file("far", &[0x01, 0x02, 0x03, 0x04]);
+	let patch = format!("{patch}@1");
+	let far = format!("{far}@0x100");
+	let args = ["-O1", "--data", &patch, "--data", &far];
+
+	let cases = [
+		// addr, expected
+		(0, 0x44BBAA11),
+		(4, 0x88776655_u32 as i32),
+		(0x100, 0x04030201),
+	];
+
+	let ctx = context(&args);
+	let mut interp = start_datapack(DATA_MODULE, &args);
+	for (addr, expected) in cases {
+		let expected = TypedValue::I32(expected);
+		assert_eq!(run_ssa(&ctx, DATA_MODULE, "load", &i32_params(&[addr])), vec![expected], "SSA, {addr}");
+		assert_eq!(run_lir(&ctx, DATA_MODULE, "load", &i32_params(&[addr])), vec![expected], "LIR, {addr}");
+		assert_eq!(call_datapack(&mut interp, "load", &i32_params(&[addr]), false), expected, "datapack, {addr}");
+	}
+}
+
+#[test]
+#[should_panic(expected = "does not fit in memory (2 bytes at 0xFFFF, memory is 65536 bytes)")]
+fn data_files_past_the_end_of_memory_are_rejected() {
+	let path = format!("{}@65535", write_data_file("past_end", &[0xAA, 0xBB]));
+	run_ssa(&context(&["-O1", "--data", &path]), DATA_MODULE, "load", &i32_params(&[0]));
+}
@@ -0,0 +1,94 @@
+use wasmcraft::{wasm_file::WasmFile, debug_info::{self, SourceLine}, mc_version::McVersion};
+
+mod test_common;
+use test_common::context;
+
+/// (func (export "abs") (param i32) (result i32)
+///   (if (result i32) (i32.lt_s (local.get 0) (i32.const 0))   ;; /src/abs.c:2
+///     (then (i32.sub (i32.const 0) (local.get 0)))            ;; /src/abs.c:3
+///     (else (local.get 0))))                                  ;; /src/abs.c:5, and the ends on line 6
+///
+/// With a DWARF 4 `.debug_line` section giving those lines.
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'a', b'b', b's', 0x00, 0x00,
+	// Code section
+	0x0A, 0x14, 0x01,
+	0x12, 0x00, 0x20, 0x00, 0x41, 0x00, 0x48, 0x04, 0x7F, 0x41, 0x00, 0x20, 0x00, 0x6B, 0x05, 0x20, 0x00, 0x0B, 0x0B,
+	// .debug_line custom section
+	0x00, 0x52, 0x0B, b'.', b'd', b'e', b'b', b'u', b'g', b'_', b'l', b'i', b'n', b'e',
+	// Header
+	0x42, 0x00, 0x00, 0x00, 0x04, 0x00, 0x22, 0x00, 0x00, 0x00, 0x01, 0x01, 0x01, 0xFB, 0x0E, 0x0D,
+	0x00, 0x01, 0x01, 0x01, 0x01, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x01,
+	b'/', b's', b'r', b'c', 0x00, 0x00,
+	b'a', b'b', b's', b'.', b'c', 0x00, 0x01, 0x00, 0x00, 0x00,
+	// Line number program
+	0x00, 0x05, 0x02, 0x03, 0x00, 0x00, 0x00, 0x03, 0x01, 0x01,
+	0x02, 0x07, 0x03, 0x01, 0x01,
+	0x68,
+	0x02, 0x02, 0x03, 0x01, 0x01,
+	0x02, 0x02, 0x00, 0x01, 0x01,
+];
+
+/// Where the `i32.sub` is, relative to the start of the code section's contents
+const SUB_OFFSET: usize = 14;
+
+fn line(line: u64) -> SourceLine {
+	SourceLine { file: "/src/abs.c".to_string(), line }
+}
+
+#[test]
+fn maps_blocks_to_offsets_and_lines() {
+	let ctx = context(&[]);
+	let file = WasmFile::from(MODULE);
+	let lir_program = ctx.compute_lir(ctx.compute_ssa(&file));
+	let datapack = ctx.compute_datapack(&lir_program);
+
+	let version = McVersion::new(19, 4);
+	let line_table = debug_info::read_line_table(&file).unwrap();
+	let info = debug_info::compute(&datapack, &lir_program, Some(&line_table), version);
+
+	let entry = info.functions.iter().find(|func| func.block == 0).unwrap();
+	assert_eq!(entry.id, "wasmrunner:wasm_0_0");
+	assert_eq!(entry.wasm_function, 0);
+	assert_eq!(entry.code_ranges, vec![3..10]);
+	assert_eq!(entry.source, vec![line(2)]);
+
+	let written = datapack.iter().find(|func| func.id.to_string() == entry.id).unwrap();
+	assert_eq!(entry.lines, wasmcraft::pack_emitter::written_lines(written, version).unwrap().len());
+
+	let then = info.functions.iter().find(|func| func.code_ranges.iter().any(|range| range.contains(&SUB_OFFSET))).unwrap();
+	assert_eq!(then.code_ranges, vec![10..16]);
+	assert_eq!(then.source, vec![line(3)]);
+
+	// Each operator is in exactly one block
+	let mut ranges = info.functions.iter().flat_map(|func| func.code_ranges.iter().cloned()).collect::<Vec<_>>();
+	ranges.sort_by_key(|range| range.start);
+	ranges.dedup();
+	assert!(ranges.windows(2).all(|pair| pair[0].end <= pair[1].start), "{ranges:?}");
+
+	let json: serde_json::Value = serde_json::from_str(&info.to_json()).unwrap();
+	assert_eq!(json["functions"][0]["function"], "wasmrunner:wasm_0_0");
+	assert_eq!(json["functions"][0]["source"][0]["file"], "/src/abs.c");
+}
+
+#[test]
+fn reads_offsets_without_line_table() {
+	let ctx = context(&[]);
+	let file = WasmFile::from(&MODULE[..MODULE.len() - 0x54]);
+	assert!(debug_info::read_line_table(&file).is_none());
+
+	let lir_program = ctx.compute_lir(ctx.compute_ssa(&file));
+	let datapack = ctx.compute_datapack(&lir_program);
+	let info = debug_info::compute(&datapack, &lir_program, None, McVersion::new(19, 4));
+
+	let entry = info.functions.iter().find(|func| func.block == 0).unwrap();
+	assert_eq!(entry.code_ranges, vec![3..10]);
+	assert!(entry.source.is_empty());
+}
@@ -0,0 +1,60 @@
+use wasmcraft::{mc_version::McVersion, pack_emitter};
+
+mod test_common;
+use test_common::compile;
+
+/// A module exporting one recursive function as `a`, `b`, `c` and `d`,
+/// so several registers have to be saved around the call:
+///
+/// (func $f (param i32 i32) (result i32)
+///   (if (result i32) (local.get 0)
+///     (then
+///       (i32.add
+///         (i32.mul
+///           (call $f (i32.sub (local.get 0) (i32.const 1)) (local.get 1))
+///           (local.get 0))
+///         (local.get 1)))
+///     (else (i32.const 0))))
+const RECURSIVE_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x07, 0x01,
+	0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x11, 0x04,
+	0x01, b'a', 0x00, 0x00,
+	0x01, b'b', 0x00, 0x00,
+	0x01, b'c', 0x00, 0x00,
+	0x01, b'd', 0x00, 0x00,
+	// Code section
+	0x0A, 0x1B, 0x01,
+	0x19, 0x00,
+	0x20, 0x00, 0x04, 0x7F,
+	0x20, 0x00, 0x41, 0x01, 0x6B, 0x20, 0x01, 0x10, 0x00,
+	0x20, 0x00, 0x6C, 0x20, 0x01, 0x6A,
+	0x05,
+	0x41, 0x00,
+	0x0B,
+	0x0B,
+];
+
+/// Compiles the module and writes it as a zip archive, returning the archive's bytes.
+fn build(opt_level: &str, idx: u32) -> Vec<u8> {
+	let datapack = compile(RECURSIVE_MODULE, &[opt_level]);
+
+	let path = std::env::temp_dir().join(format!("wasmcraft_deterministic{opt_level}_{idx}.zip"));
+	pack_emitter::persist_program_zip(&path, &datapack, McVersion::new(20, 2));
+	std::fs::read(path).unwrap()
+}
+
+#[test]
+fn builds_are_byte_identical() {
+	for opt_level in ["-O0", "-O1"] {
+		let first = build(opt_level, 0);
+		for idx in 1..4 {
+			assert!(build(opt_level, idx) == first, "build {idx} with {opt_level} differs from the first");
+		}
+	}
+}
@@ -0,0 +1,105 @@
+use datapack_vm::Interpreter;
+use wasmcraft::ssa::interp::TypedValue;
+
+mod test_common;
+use test_common::{call_datapack, context, get_trap, i32_params, run_lir, run_ssa, start_datapack};
+
+/// (func (export "div") (param i32 i32) (result i32)
+///   (i32.div_s (local.get 0) (local.get 1)))
+///
+/// (func (export "divu") (param i32 i32) (result i32)
+///   (i32.div_u (local.get 0) (local.get 1)))
+///
+/// (func (export "rem") (param i32 i32) (result i32)
+///   (i32.rem_s (local.get 0) (local.get 1)))
+///
+/// (func (export "div64") (param i64 i64) (result i64)
+///   (i64.div_s (local.get 0) (local.get 1)))
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0D, 0x02,
+	0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	0x60, 0x02, 0x7E, 0x7E, 0x01, 0x7E,
+	// Function section
+	0x03, 0x05, 0x04, 0x00, 0x00, 0x00, 0x01,
+	// Export section
+	0x07, 0x1C, 0x04,
+	0x03, b'd', b'i', b'v', 0x00, 0x00,
+	0x04, b'd', b'i', b'v', b'u', 0x00, 0x01,
+	0x03, b'r', b'e', b'm', 0x00, 0x02,
+	0x05, b'd', b'i', b'v', b'6', b'4', 0x00, 0x03,
+	// Code section
+	0x0A, 0x21, 0x04,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6D, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6E, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6F, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x7F, 0x0B,
+];
+
+fn call(interp: &mut Interpreter, name: &str, lhs: i32, rhs: i32) -> TypedValue {
+	call_datapack(interp, name, &i32_params(&[lhs, rhs]), false)
+}
+
+fn call_64(interp: &mut Interpreter, name: &str, lhs: i64, rhs: i64) -> TypedValue {
+	call_datapack(interp, name, &[TypedValue::I64(lhs), TypedValue::I64(rhs)], true)
+}
+
+#[test]
+fn fast_mode_gives_defined_results() {
+	let mut interp = start_datapack(MODULE, &[]);
+
+	assert_eq!(call(&mut interp, "div", -7, 2), TypedValue::I32(-3));
+	assert_eq!(call(&mut interp, "div", -7, 0), TypedValue::I32(-1));
+	assert_eq!(call(&mut interp, "div", 7, 0), TypedValue::I32(-1));
+	assert_eq!(call(&mut interp, "divu", 7, 0), TypedValue::I32(-1));
+	assert_eq!(call(&mut interp, "divu", -7, 0), TypedValue::I32(-1));
+	assert_eq!(call(&mut interp, "rem", -7, 0), TypedValue::I32(-7));
+	assert_eq!(call_64(&mut interp, "div64", -7, 0), TypedValue::I64(-1));
+	assert_eq!(call_64(&mut interp, "div64", 7, 0), TypedValue::I64(-1));
+	assert_eq!(get_trap(&mut interp), Some(0));
+}
+
+#[test]
+fn trap_mode_traps() {
+	let mut interp = start_datapack(MODULE, &["--div-by-zero", "trap"]);
+
+	assert_eq!(call(&mut interp, "div", -7, 2), TypedValue::I32(-3));
+	assert_eq!(get_trap(&mut interp), Some(0));
+
+	call(&mut interp, "rem", 7, 0);
+	assert_eq!(get_trap(&mut interp), Some(5));
+
+	call_64(&mut interp, "div64", 7, 1);
+	assert_eq!(get_trap(&mut interp), Some(0));
+	call_64(&mut interp, "div64", 7, 0);
+	assert_eq!(get_trap(&mut interp), Some(5));
+}
+
+#[test]
+fn interpreters_match_fast_mode() {
+	let ctx = context(&[]);
+	let cases = [
+		("div", i32_params(&[7, 0]), TypedValue::I32(-1)),
+		("divu", i32_params(&[-7, 0]), TypedValue::I32(-1)),
+		("rem", i32_params(&[-7, 0]), TypedValue::I32(-7)),
+		("div64", vec![TypedValue::I64(7), TypedValue::I64(0)], TypedValue::I64(-1)),
+	];
+
+	for (name, params, expected) in cases {
+		assert_eq!(run_ssa(&ctx, MODULE, name, &params), vec![expected], "SSA {name}");
+		assert_eq!(run_lir(&ctx, MODULE, name, &params), vec![expected], "LIR {name}");
+	}
+}
+
+#[test]
+#[should_panic(expected = "trap: integer divide by zero")]
+fn ssa_interpreter_traps_in_trap_mode() {
+	run_ssa(&context(&["--div-by-zero", "trap"]), MODULE, "rem", &i32_params(&[7, 0]));
+}
+
+#[test]
+#[should_panic(expected = "trap: integer divide by zero")]
+fn lir_interpreter_traps_in_trap_mode() {
+	run_lir(&context(&["--div-by-zero", "trap"]), MODULE, "div64", &[TypedValue::I64(7), TypedValue::I64(0)]);
+}
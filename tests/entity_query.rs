@@ -0,0 +1,50 @@
+mod test_common;
+use test_common::{context, run_ssa, ssa_instrs};
+use wasmcraft::ssa::{SsaInstr, interp::TypedValue};
+
+/// A module exporting:
+///
+/// (import "env" "nearest_player_y" (func $nearest_player_y (result i32)))
+/// (import "env" "entity_count" (func $entity_count (param i32) (result i32)))
+///
+/// (func $count (param $selector i32) (result i32)
+///   (i32.add (call $nearest_player_y) (call $entity_count (local.get $selector))))
+const ENTITY_QUERY_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0A, 0x02,
+	0x60, 0x00, 0x01, 0x7F,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Import section
+	0x02, 0x2B, 0x02,
+	0x03, b'e', b'n', b'v',
+	0x10, b'n', b'e', b'a', b'r', b'e', b's', b't', b'_', b'p', b'l', b'a', b'y', b'e', b'r', b'_', b'y', 0x00, 0x00,
+	0x03, b'e', b'n', b'v',
+	0x0C, b'e', b'n', b't', b'i', b't', b'y', b'_', b'c', b'o', b'u', b'n', b't', 0x00, 0x01,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Export section
+	0x07, 0x09, 0x01,
+	0x05, b'c', b'o', b'u', b'n', b't', 0x00, 0x02,
+	// Code section
+	0x0A, 0x0B, 0x01,
+	0x09, 0x00, 0x10, 0x00, 0x20, 0x00, 0x10, 0x01, 0x6A, 0x0B,
+];
+
+#[test]
+fn entity_queries_are_lowered() {
+	let ctx = context(&["-O1"]);
+	let instrs = ssa_instrs(&ctx, ENTITY_QUERY_MODULE);
+
+	let axes = instrs.iter()
+		.filter_map(|instr| match instr {
+			SsaInstr::NearestPlayerPos { axis, .. } => Some(*axis),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(axes, [1]);
+	assert_eq!(instrs.iter().filter(|instr| matches!(instr, SsaInstr::EntityCount { .. })).count(), 1);
+
+	// There is no world to query, so the interpreter finds nothing
+	assert_eq!(run_ssa(&ctx, ENTITY_QUERY_MODULE, "count", &[TypedValue::I32(0)]), vec![TypedValue::I32(0)]);
+}
@@ -0,0 +1,120 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue, wasm_file::WasmFile};
+
+mod test_common;
+use test_common::{all_cmds, compile, context, run_lir, run_ssa};
+
+/// A module using extended constant expressions for its globals and data segment, exporting:
+///
+/// (global $g i32 (i32.add (i32.const 1000) (i32.mul (i32.const 6) (i32.const 7))))
+/// (global $big i64 (i64.sub (i64.const 0) (i64.const 5)))
+///
+/// (data (i32.sub (i32.const 10) (i32.const 2)) "\2A")
+///
+/// (func $get (result i32)
+///   (i32.add (global.get $g) (i32.load8_u (i32.const 8))))
+///
+/// (func $big (result i64) (global.get $big))
+const EXTENDED_CONST_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x09, 0x02, 0x60, 0x00, 0x01, 0x7F, 0x60, 0x00, 0x01, 0x7E,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x01,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Global section
+	0x06, 0x15, 0x02,
+	0x7F, 0x00, 0x41, 0xE8, 0x07, 0x41, 0x06, 0x41, 0x07, 0x6C, 0x6A, 0x0B,
+	0x7E, 0x00, 0x42, 0x00, 0x42, 0x05, 0x7D, 0x0B,
+	// Export section
+	0x07, 0x0D, 0x02,
+	0x03, b'g', b'e', b't', 0x00, 0x00,
+	0x03, b'b', b'i', b'g', 0x00, 0x01,
+	// Code section
+	0x0A, 0x11, 0x02,
+	0x0A, 0x00, 0x23, 0x00, 0x41, 0x08, 0x2D, 0x00, 0x00, 0x6A, 0x0B,
+	0x04, 0x00, 0x23, 0x01, 0x0B,
+	// Data section
+	0x0B, 0x0A, 0x01, 0x00, 0x41, 0x0A, 0x41, 0x02, 0x6B, 0x0B, 0x01, 0x2A,
+];
+
+#[test]
+fn extended_const_initializers() {
+	let cases = [
+		// name, expected
+		("get", TypedValue::I32(1084)),
+		("big", TypedValue::I64(-5)),
+	];
+
+	let ctx = CompileContext::new_from_opt(1);
+	for (name, expected) in cases {
+		assert_eq!(run_ssa(&ctx, EXTENDED_CONST_MODULE, name, &[]), vec![expected], "SSA, {name}");
+		assert_eq!(run_lir(&ctx, EXTENDED_CONST_MODULE, name, &[]), vec![expected], "LIR, {name}");
+	}
+}
+
+#[test]
+fn globals_are_initialized_to_folded_values() {
+	let cmds = all_cmds(&compile(EXTENDED_CONST_MODULE, &["-O1"]));
+	for init in ["%global%0%lo reg 1042", "%global%1%lo reg -5", "%global%1%hi reg -1"] {
+		let init = format!("scoreboard players set {init}");
+		assert!(cmds.contains(&init), "{init}: {cmds:?}");
+	}
+}
+
+/// A module whose initializers read an earlier global, exporting:
+///
+/// (global $base i32 (i32.const 100))
+/// (global $derived i32 (i32.add (global.get $base) (i32.const 5)))
+///
+/// (data (i32.add (global.get $base) (i32.const 4)) "\2A")
+///
+/// (func $get (result i32)
+///   (i32.add (global.get $derived) (i32.load8_u (i32.const 104))))
+const GLOBAL_GET_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Global section
+	0x06, 0x0F, 0x02,
+	0x7F, 0x00, 0x41, 0xE4, 0x00, 0x0B,
+	0x7F, 0x00, 0x23, 0x00, 0x41, 0x05, 0x6A, 0x0B,
+	// Export section
+	0x07, 0x07, 0x01, 0x03, b'g', b'e', b't', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0D, 0x01,
+	0x0B, 0x00, 0x23, 0x01, 0x41, 0xE8, 0x00, 0x2D, 0x00, 0x00, 0x6A, 0x0B,
+	// Data section
+	0x0B, 0x0A, 0x01, 0x00, 0x23, 0x00, 0x41, 0x04, 0x6A, 0x0B, 0x01, 0x2A,
+];
+
+/// A module whose global is initialized from an imported one:
+///
+/// (import "env" "base" (global $base i32))
+/// (global $derived i32 (global.get $base))
+const IMPORTED_GLOBAL_GET_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Import section
+	0x02, 0x0D, 0x01,
+	0x03, b'e', b'n', b'v', 0x04, b'b', b'a', b's', b'e', 0x03, 0x7F, 0x00,
+	// Global section
+	0x06, 0x06, 0x01, 0x7F, 0x00, 0x23, 0x00, 0x0B,
+];
+
+#[test]
+fn initializers_read_earlier_globals() {
+	let ctx = CompileContext::new_from_opt(1);
+	let expected = vec![TypedValue::I32(147)];
+	assert_eq!(run_ssa(&ctx, GLOBAL_GET_MODULE, "get", &[]), expected, "SSA");
+	assert_eq!(run_lir(&ctx, GLOBAL_GET_MODULE, "get", &[]), expected, "LIR");
+}
+
+#[test]
+#[should_panic(expected = "constant expression reads global 0, whose value isn't known when compiling")]
+fn initializers_cannot_read_imported_globals() {
+	context(&["-O1"]).compute_ssa(&WasmFile::from(IMPORTED_GLOBAL_GET_MODULE));
+}
@@ -0,0 +1,72 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{all_cmds, compile, i32_params, run_lir, run_ssa};
+
+/// A module with one page of memory holding two iovecs at 0, for `"hi\n"` and `"hi"`, exporting:
+///
+/// (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+///
+/// (func $write (param $fd i32) (param $iovs_len i32) (result i32)
+///   (i32.add
+///     (i32.mul (call $fd_write (local.get $fd) (i32.const 0) (local.get $iovs_len) (i32.const 32)) (i32.const 100))
+///     (i32.load (i32.const 32))))
+const FD_WRITE_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0F, 0x02,
+	0x60, 0x04, 0x7F, 0x7F, 0x7F, 0x7F, 0x01, 0x7F,
+	0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	// Import section
+	0x02, 0x23, 0x01,
+	0x16, b'w', b'a', b's', b'i', b'_', b's', b'n', b'a', b'p', b's', b'h', b'o', b't', b'_',
+	b'p', b'r', b'e', b'v', b'i', b'e', b'w', b'1',
+	0x08, b'f', b'd', b'_', b'w', b'r', b'i', b't', b'e', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x09, 0x01,
+	0x05, b'w', b'r', b'i', b't', b'e', 0x00, 0x01,
+	// Code section
+	0x0A, 0x18, 0x01,
+	0x16, 0x00,
+	0x20, 0x00, 0x41, 0x00, 0x20, 0x01, 0x41, 0x20, 0x10, 0x00, 0x41, 0xE4, 0x00, 0x6C,
+	0x41, 0x20, 0x28, 0x02, 0x00,
+	0x6A,
+	0x0B,
+	// Data section
+	0x0B, 0x19, 0x01, 0x00, 0x41, 0x00, 0x0B, 0x13,
+	0x10, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00,
+	0x10, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00,
+	b'h', b'i', b'\n',
+];
+
+#[test]
+fn fd_write_counts_bytes() {
+	let cases = [
+		// fd, iovs_len, expected
+		(1, 2, 5),
+		(2, 1, 3),
+		(1, 0, 0),
+		// Other file descriptors give EBADF and don't write anything
+		(3, 2, 800),
+	];
+
+	let ctx = CompileContext::new_from_opt(1);
+	for (fd, iovs_len, expected) in cases {
+		let args = [fd, iovs_len];
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, FD_WRITE_MODULE, "write", &i32_params(&args)), expected, "SSA, write{args:?}");
+		assert_eq!(run_lir(&ctx, FD_WRITE_MODULE, "write", &i32_params(&args)), expected, "LIR, write{args:?}");
+	}
+}
+
+#[test]
+fn fd_write_calls_intrinsic() {
+	let cmds = all_cmds(&compile(FD_WRITE_MODULE, &["-O1"]));
+	let call = cmds.iter().position(|cmd| cmd == "function intrinsic:fd_write").unwrap_or_else(|| panic!("{cmds:?}"));
+	assert!(cmds[call - 1].starts_with("scoreboard players operation %param3%0 reg = "), "{cmds:?}");
+	assert!(cmds[call + 1].ends_with(" = %return%0 reg"), "{cmds:?}");
+}
@@ -0,0 +1,50 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{i32_params, run_lir, run_ssa};
+
+/// A module taking and returning the bits of floats, exporting:
+///
+/// (func $div (param i32) (param i32) (result i32)
+///   (i32.reinterpret_f32 (f32.div (f32.reinterpret_i32 (local.get 0)) (f32.reinterpret_i32 (local.get 1)))))
+///
+/// (func $copysign (param i32) (param i32) (result i32)
+///   (i32.reinterpret_f32 (f32.copysign (f32.reinterpret_i32 (local.get 0)) (f32.reinterpret_i32 (local.get 1)))))
+const NAN_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x07, 0x01, 0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x00,
+	// Export section
+	0x07, 0x12, 0x02,
+	0x03, b'd', b'i', b'v', 0x00, 0x00,
+	0x08, b'c', b'o', b'p', b'y', b's', b'i', b'g', b'n', 0x00, 0x01,
+	// Code section
+	0x0A, 0x17, 0x02,
+	0x0A, 0x00, 0x20, 0x00, 0xBE, 0x20, 0x01, 0xBE, 0x95, 0xBC, 0x0B,
+	0x0A, 0x00, 0x20, 0x00, 0xBE, 0x20, 0x01, 0xBE, 0x98, 0xBC, 0x0B,
+];
+
+#[test]
+fn interpreters_canonicalize_nan() {
+	let ctx = CompileContext::new_from_opt(1);
+	let cases: [(&str, u32, u32, u32); 6] = [
+		// name, lhs, rhs, expected
+		("div", 0x0000_0000, 0x0000_0000, 0x7FC0_0000),
+		// NaN payloads and signs are not propagated through arithmetic, just like in the datapack
+		("div", 0x7FC0_0001, 0x3F80_0000, 0x7FC0_0000),
+		("div", 0xFFC0_0000, 0x3F80_0000, 0x7FC0_0000),
+		("div", 0x3F80_0000, 0x4000_0000, 0x3F00_0000),
+		// But copysign only touches the sign bit
+		("copysign", 0x7FC0_0001, 0x8000_0000, 0xFFC0_0001),
+		("copysign", 0x3F80_0000, 0xFFC0_0000, 0xBF80_0000),
+	];
+
+	for (name, lhs, rhs, expected) in cases {
+		let args = [lhs as i32, rhs as i32];
+		let expected = vec![TypedValue::I32(expected as i32)];
+		assert_eq!(run_ssa(&ctx, NAN_MODULE, name, &i32_params(&args)), expected, "SSA, {name}({lhs:#010X}, {rhs:#010X})");
+		assert_eq!(run_lir(&ctx, NAN_MODULE, name, &i32_params(&args)), expected, "LIR, {name}({lhs:#010X}, {rhs:#010X})");
+	}
+}
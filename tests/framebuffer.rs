@@ -0,0 +1,55 @@
+mod test_common;
+use test_common::{find_func, func_cmds};
+
+/// A module with one page of memory starting with the bytes 1, 2, 3, and 0x1F, exporting:
+///
+/// (func $run (result i32)
+///   (i32.const 7))
+const FRAMEBUFFER_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x05, 0x01,
+	0x60, 0x00, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x00,
+	// Code section
+	0x0A, 0x06, 0x01,
+	0x04, 0x00, 0x41, 0x07, 0x0B,
+	// Data section
+	0x0B, 0x0A, 0x01, 0x00, 0x41, 0x00, 0x0B, 0x04, 0x01, 0x02, 0x03, 0x1F,
+];
+
+fn compile(extra_args: &[&str]) -> Vec<(String, Vec<String>)> {
+	func_cmds(&test_common::compile(FRAMEBUFFER_MODULE, &[&["-O1"], extra_args].concat()))
+}
+
+#[test]
+fn framebuffer_is_drawn_from_memory() {
+	let funcs = compile(&["--framebuffer", "0:4x2", "--framebuffer-origin", "10,70,-5", "--framebuffer-interval", "2"]);
+
+	let init = find_func(&funcs, "wasmrunner:init");
+	assert!(init.iter().any(|cmd| cmd.starts_with("fill 10 70 -5 13 70 -5 ")), "{init:?}");
+	assert!(init.iter().any(|cmd| cmd.starts_with("fill 10 69 -5 13 69 -5 ")), "{init:?}");
+	assert!(init.iter().any(|cmd| cmd == "schedule function wasmrunner:__framebuffer 2"), "{init:?}");
+
+	// Two words, each only redrawn when it changes
+	let update = find_func(&funcs, "wasmrunner:__framebuffer");
+	assert!(update.iter().any(|cmd| cmd == "execute unless score %fb_word reg = %%framebuffer_1 reg run function wasmrunner:__framebuffer_1"), "{update:?}");
+	assert_eq!(update.last().unwrap(), "schedule function wasmrunner:__framebuffer 2");
+
+	// The second row is drawn below the first
+	let word = find_func(&funcs, "wasmrunner:__framebuffer_1");
+	assert!(word.iter().any(|cmd| cmd.ends_with("setblock 13 69 -5 minecraft:black_concrete")), "{word:?}");
+	assert!(!funcs.iter().any(|(name, _)| name == "wasmrunner:__framebuffer_2"));
+}
+
+#[test]
+fn framebuffer_is_opt_in() {
+	let funcs = compile(&[]);
+	assert!(!funcs.iter().any(|(name, _)| name.starts_with("wasmrunner:__framebuffer")));
+}
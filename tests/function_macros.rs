@@ -0,0 +1,89 @@
+mod test_common;
+use test_common::compile;
+use wasmcraft::{mc_version::McVersion, pack_emitter};
+
+/// A module exporting:
+///
+/// (func $pick (param i32) (result i32)
+///   (block
+///     (block
+///       (block
+///         (br_table 0 1 2 (local.get 0)))
+///       (return (i32.const 10)))
+///     (return (i32.const 20)))
+///   (i32.const 30))
+const JUMP_TABLE_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'p', b'i', b'c', b'k', 0x00, 0x00,
+	// Code section
+	0x0A, 0x1C, 0x01,
+	0x1A, 0x00,
+	0x02, 0x40, 0x02, 0x40, 0x02, 0x40,
+	0x20, 0x00, 0x0E, 0x02, 0x00, 0x01, 0x02,
+	0x0B, 0x41, 0x0A, 0x0F,
+	0x0B, 0x41, 0x14, 0x0F,
+	0x0B, 0x41, 0x1E, 0x0B,
+];
+
+/// Compiles the module and writes it for the given version, returning the folder it was written to
+/// and the path of the jump table function within it.
+fn persist(version: &str) -> (std::path::PathBuf, String) {
+	let datapack = compile(JUMP_TABLE_MODULE, &["-O1", "--mc-version", version]);
+
+	let jump_table = datapack.iter()
+		.map(|func| func.id.to_string())
+		.find(|id| id.starts_with("wasmrunner:__jump_table_") && id != "wasmrunner:__jump_table_lookup")
+		.expect("no jump table was emitted");
+	let jump_table = format!("data/wasmrunner/functions/{}.mcfunction", jump_table.strip_prefix("wasmrunner:").unwrap());
+
+	let folder = std::env::temp_dir().join(format!("wasmcraft_macros_{version}"));
+	let _ = std::fs::remove_dir_all(&folder);
+	pack_emitter::persist_program(&folder, &datapack, McVersion::parse(version).unwrap());
+	(folder, jump_table)
+}
+
+fn read(folder: &std::path::Path, path: &str) -> String {
+	std::fs::read_to_string(folder.join(path)).unwrap_or_else(|err| panic!("failed to read {path}: {err}"))
+}
+
+#[test]
+fn jump_tables_use_macros_when_available() {
+	let (folder, jump_table) = persist("1.20.2");
+
+	let table = read(&folder, &jump_table);
+	assert!(table.contains("function wasmrunner:__jump_table_lookup with storage wasm:scratch jump"), "{table}");
+	assert!(table.contains("function wasmrunner:__call_target with storage wasm:scratch jump"), "{table}");
+	assert!(!table.contains("# !MACRO"), "{table}");
+	assert!(!table.contains("%condtaken"), "{table}");
+
+	let call_target = read(&folder, "data/wasmrunner/functions/__call_target.mcfunction");
+	assert_eq!(call_target.trim(), "$function $(target)");
+
+	let lookup = read(&folder, "data/wasmrunner/functions/__jump_table_lookup.mcfunction");
+	assert!(lookup.starts_with("$data modify storage wasm:scratch jump.target set from storage wasm:scratch jump.table[$(idx)]"), "{lookup}");
+
+	let ret = read(&folder, "data/wasmrunner/functions/__return_to_saved.mcfunction");
+	assert!(ret.contains("function wasmrunner:__call_target with storage wasm:scratch stack.data"), "{ret}");
+}
+
+#[test]
+fn jump_tables_check_each_arm_without_macros() {
+	let (folder, jump_table) = persist("1.20.1");
+
+	let table = read(&folder, &jump_table);
+	assert!(table.contains("matches 0 run function wasmrunner:"), "{table}");
+	assert!(!table.contains("with storage"), "{table}");
+	assert!(!table.contains("MACRO"), "{table}");
+
+	assert!(!folder.join("data/wasmrunner/functions/__call_target.mcfunction").exists());
+
+	let ret = read(&folder, "data/wasmrunner/functions/__return_to_saved.mcfunction");
+	assert!(!ret.contains("__call_target"), "{ret}");
+}
@@ -0,0 +1,38 @@
+use wasmcraft::wasm_file::WasmFile;
+
+mod test_common;
+use test_common::compile;
+
+/// A module with a `name` section, exporting:
+///
+/// (func $my_crate::render (result i32) (i32.const 7))
+const NAMED_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x0A, 0x01, 0x06, b'r', b'e', b'n', b'd', b'e', b'r', 0x00, 0x00,
+	// Code section
+	0x0A, 0x06, 0x01, 0x04, 0x00, 0x41, 0x07, 0x0B,
+	// Name section
+	0x00, 0x1A, 0x04, b'n', b'a', b'm', b'e',
+	0x01, 0x13, 0x01, 0x00,
+	0x10, b'm', b'y', b'_', b'c', b'r', b'a', b't', b'e', b':', b':', b'r', b'e', b'n', b'd', b'e', b'r',
+];
+
+#[test]
+fn name_section_is_parsed() {
+	let file = WasmFile::from(NAMED_MODULE);
+	assert_eq!(file.func_names.get(&0).map(String::as_str), Some("my_crate::render"));
+}
+
+#[test]
+fn datapack_functions_are_named() {
+	let datapack = compile(NAMED_MODULE, &["-O1"]);
+
+	let ids = datapack.iter().map(|func| func.id.to_string()).collect::<Vec<_>>();
+	assert!(ids.iter().any(|id| id == "wasmrunner:wasm_0_my_crate__render_0"), "{ids:?}");
+	assert!(!ids.iter().any(|id| id == "wasmrunner:wasm_0_0"), "{ids:?}");
+}
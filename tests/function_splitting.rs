@@ -0,0 +1,129 @@
+use datapack_vm::Interpreter;
+use wasmcraft::ssa::interp::TypedValue;
+
+mod test_common;
+use test_common::{call_datapack, func_cmds, run_function};
+
+/// A module exporting:
+///
+/// (func $calc (param i32) (result i32)
+///   (i32.mul
+///     (i32.add (local.get 0) (i32.const 12345))
+///     (i32.const 7)))
+const CALC_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'c', b'a', b'l', b'c', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0E, 0x01,
+	0x0C, 0x00,
+	0x20, 0x00, 0x41, 0xB9, 0xE0, 0x00, 0x6A,
+	0x41, 0x07, 0x6C,
+	0x0B,
+];
+
+fn compile(extra_args: &[&str]) -> Vec<(String, Vec<String>)> {
+	func_cmds(&test_common::compile(CALC_MODULE, &[&["-O1"], extra_args].concat()))
+}
+
+/// (memory 1)
+/// (func (export "load") (param i32) (result i32)
+///   (i32.load (local.get 0)))
+/// (data (i32.const 0) "...")
+///
+/// With `DATA_LEN` bytes of data given by `data_byte`, so that `wasmrunner:init` has a command for every word of it.
+fn data_module() -> Vec<u8> {
+	let mut module = vec![
+		0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+		// Type section
+		0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+		// Function section
+		0x03, 0x02, 0x01, 0x00,
+		// Memory section
+		0x05, 0x03, 0x01, 0x00, 0x01,
+		// Export section
+		0x07, 0x08, 0x01,
+		0x04, b'l', b'o', b'a', b'd', 0x00, 0x00,
+		// Code section
+		0x0A, 0x09, 0x01,
+		0x07, 0x00, 0x20, 0x00, 0x28, 0x02, 0x00, 0x0B,
+		// Data section, with a single active segment at 0
+		0x0B, 0x87, 0x08, 0x01,
+		0x00, 0x41, 0x00, 0x0B, 0x80, 0x08,
+	];
+	module.extend((0..DATA_LEN).map(data_byte));
+	module
+}
+
+const DATA_LEN: usize = 1024;
+
+fn data_byte(idx: usize) -> u8 {
+	(idx % 251 + 1) as u8
+}
+
+fn find_func<'a>(funcs: &'a [(String, Vec<String>)], id: &str) -> Option<&'a [String]> {
+	funcs.iter().find(|(name, _)| name == id).map(|(_, cmds)| cmds.as_slice())
+}
+
+#[test]
+fn long_functions_are_split_into_parts() {
+	let whole = compile(&[]);
+	let split = compile(&["--max-command-chain-length", "8"]);
+
+	// Functions with macro lines are left whole, since their lines depend on the version
+	let has_macro_lines = |cmds: &[String]| cmds.iter().any(|cmd| cmd.starts_with("# !MACRO: "));
+	assert!(split.iter().all(|(_, cmds)| cmds.len() <= 8 || has_macro_lines(cmds)), "{split:?}");
+
+	let init = find_func(&whole, "wasmrunner:init").unwrap();
+	assert!(init.len() > 8);
+	assert!(find_func(&whole, "wasmrunner:init__part1").is_none());
+
+	// Following the chain of parts gives back the whole function,
+	// and since nothing calls `wasmrunner:init`, each part runs on the tick after the one before it
+	let mut rejoined = Vec::new();
+	let mut id = "wasmrunner:init".to_string();
+	loop {
+		let part = find_func(&split, &id).unwrap_or_else(|| panic!("missing {id}"));
+		let next = format!("wasmrunner:init__part{}", rejoined.len() / 7 + 1);
+		match part.split_last() {
+			Some((last, rest)) if *last == format!("schedule function {next} 1") => {
+				rejoined.extend_from_slice(rest);
+				id = next;
+			}
+			_ => {
+				rejoined.extend_from_slice(part);
+				break;
+			}
+		}
+	}
+	assert_eq!(rejoined, init);
+}
+
+#[test]
+fn short_functions_are_left_whole() {
+	let whole = compile(&[]);
+	let split = compile(&["--max-command-chain-length", "100000"]);
+	assert_eq!(whole, split);
+}
+
+#[test]
+fn split_init_runs_within_command_limit() {
+	let datapack = test_common::compile(&data_module(), &["-O1", "--max-command-chain-length", "64"]);
+	let mut interp = Interpreter::new(datapack, 0);
+	// Far fewer than the whole of `wasmrunner:init`, which has a command for each of the 256 words
+	interp.max_tick_commands = 200;
+	run_function(&mut interp, "wasmrunner:init");
+	assert!(interp.tick > 1);
+
+	for addr in [0, 512, DATA_LEN - 4] {
+		let expected = i32::from_le_bytes([0, 1, 2, 3].map(|idx| data_byte(addr + idx)));
+		let actual = call_datapack(&mut interp, "load", &[TypedValue::I32(addr as i32)], false);
+		assert_eq!(actual, TypedValue::I32(expected), "load({addr})");
+	}
+}
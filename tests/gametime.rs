@@ -0,0 +1,39 @@
+mod test_common;
+use test_common::{context, count_ssa_instrs, run_ssa};
+use wasmcraft::ssa::{SsaInstr, interp::TypedValue};
+
+/// A module exporting:
+///
+/// (import "env" "mc_gametime" (func $mc_gametime (result i64)))
+///
+/// (func $elapsed (param $start i64) (result i64)
+///   (i64.sub (call $mc_gametime) (local.get $start)))
+const GAMETIME_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0A, 0x02,
+	0x60, 0x00, 0x01, 0x7E,
+	0x60, 0x01, 0x7E, 0x01, 0x7E,
+	// Import section
+	0x02, 0x13, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x0B, b'm', b'c', b'_', b'g', b'a', b'm', b'e', b't', b'i', b'm', b'e', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Export section
+	0x07, 0x0B, 0x01,
+	0x07, b'e', b'l', b'a', b'p', b's', b'e', b'd', 0x00, 0x01,
+	// Code section
+	0x0A, 0x09, 0x01,
+	0x07, 0x00, 0x10, 0x00, 0x20, 0x00, 0x7D, 0x0B,
+];
+
+#[test]
+fn gametime_is_lowered() {
+	let ctx = context(&["-O1"]);
+	let count = count_ssa_instrs(&ctx, GAMETIME_MODULE, |instr| matches!(instr, SsaInstr::GameTime(_)));
+	assert_eq!(count, 1);
+
+	// The interpreter has no world, so time never starts
+	assert_eq!(run_ssa(&ctx, GAMETIME_MODULE, "elapsed", &[TypedValue::I64(5)]), vec![TypedValue::I64(-5)]);
+}
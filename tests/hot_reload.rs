@@ -0,0 +1,115 @@
+use clap::Parser;
+use wasmcraft::{Args, CompileContext, pack_emitter::{self, PackBackend}};
+
+mod test_common;
+use test_common::compile_with;
+
+/// A module exporting:
+///
+/// (func $calc (param i32) (result i32)
+///   (i32.mul
+///     (i32.add (local.get 0) (i32.const 12345))
+///     (i32.const 7)))
+const CALC_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'c', b'a', b'l', b'c', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0E, 0x01,
+	0x0C, 0x00,
+	0x20, 0x00, 0x41, 0xB9, 0xE0, 0x00, 0x6A,
+	0x41, 0x07, 0x6C,
+	0x0B,
+];
+
+/// The same module, multiplying by 9 instead of 7.
+const CHANGED_CALC_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'c', b'a', b'l', b'c', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0E, 0x01,
+	0x0C, 0x00,
+	0x20, 0x00, 0x41, 0xB9, 0xE0, 0x00, 0x6A,
+	0x41, 0x09, 0x6C,
+	0x0B,
+];
+
+fn context() -> CompileContext {
+	test_common::context(&["-O1", "--hot-reload"])
+}
+
+/// Every file in a written datapack, relative to its root.
+fn pack_files(folder: &std::path::Path) -> Vec<std::path::PathBuf> {
+	let mut files = Vec::new();
+	let mut dirs = vec![folder.to_path_buf()];
+	while let Some(dir) = dirs.pop() {
+		for entry in std::fs::read_dir(dir).unwrap() {
+			let path = entry.unwrap().path();
+			if path.is_dir() {
+				dirs.push(path);
+			} else {
+				files.push(path.strip_prefix(folder).unwrap().to_path_buf());
+			}
+		}
+	}
+	files
+}
+
+#[test]
+fn only_changed_files_are_rewritten() {
+	let ctx = context();
+	let folder = std::env::temp_dir().join("wasmcraft_hot_reload");
+	let _ = std::fs::remove_dir_all(&folder);
+
+	pack_emitter::update_datapack(&ctx.datapack(), &folder, &compile_with(&ctx, CALC_MODULE));
+	for path in pack_files(&folder) {
+		std::fs::File::options().write(true).open(folder.join(path)).unwrap().set_modified(std::time::UNIX_EPOCH).unwrap();
+	}
+	std::fs::write(folder.join("data/wasmrunner/functions/stale.mcfunction"), "say removed").unwrap();
+
+	pack_emitter::update_datapack(&ctx.datapack(), &folder, &compile_with(&ctx, CHANGED_CALC_MODULE));
+	let files = pack_files(&folder);
+	assert!(!files.iter().any(|path| path.ends_with("stale.mcfunction")));
+
+	let rewritten = files.iter()
+		.filter(|path| std::fs::metadata(folder.join(path)).unwrap().modified().unwrap() != std::time::UNIX_EPOCH)
+		.collect::<Vec<_>>();
+	assert!(!rewritten.is_empty());
+	assert!(rewritten.len() < files.len() / 2, "{rewritten:?}");
+	assert!(!rewritten.iter().any(|path| path.ends_with("init.mcfunction") || path.ends_with("pack.mcmeta")), "{rewritten:?}");
+}
+
+#[test]
+fn loading_sets_up_constants_of_new_version() {
+	let ctx = context();
+	let (files, _) = ctx.datapack().files(&compile_with(&ctx, CALC_MODULE));
+	let find_file = |path: &str| files.iter().find(|(name, _)| name == path).unwrap_or_else(|| panic!("missing {path}")).1.clone();
+
+	let load = find_file("data/minecraft/tags/functions/load.json");
+	assert_eq!(load, r#"{"values":["wasmrunner:__hot_reload"]}"#);
+
+	// Only constants are set, the program's state is left as it is
+	let reload = find_file("data/wasmrunner/functions/__hot_reload.mcfunction");
+	assert!(reload.lines().any(|cmd| cmd == "function wasmrunner:__init_constants"), "{reload}");
+	assert!(reload.lines().any(|cmd| cmd.starts_with("scoreboard players set %%returnaddr_wasm_0_0 reg ")), "{reload}");
+	assert!(reload.lines().all(|cmd| !cmd.contains("storage") && !cmd.contains("%global%")), "{reload}");
+}
+
+#[test]
+#[should_panic(expected = "can't be used with --zip")]
+fn hot_reload_needs_datapack_folder() {
+	CompileContext::new(Args::parse_from(["wasmcraft", "calc.wasm", "-o", "out", "--hot-reload", "--zip"]));
+}
@@ -0,0 +1,60 @@
+mod test_common;
+use test_common::{all_cmds, compile};
+
+/// A module exporting:
+///
+/// (import "lib" "log" (func $log (param i32)))
+///
+/// (func $run
+///   (call $log (i32.const 42)))
+const IMPORT_MAP_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x08, 0x02,
+	0x60, 0x01, 0x7F, 0x00,
+	0x60, 0x00, 0x00,
+	// Import section
+	0x02, 0x0B, 0x01,
+	0x03, b'l', b'i', b'b',
+	0x03, b'l', b'o', b'g', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x01,
+	// Code section
+	0x0A, 0x08, 0x01,
+	0x06, 0x00, 0x41, 0x2A, 0x10, 0x00, 0x0B,
+];
+
+fn prints(cmds: &[String]) -> bool {
+	cmds.iter().any(|cmd| cmd.starts_with(r#"tellraw @a [{"text":"Printed "}"#))
+}
+
+#[test]
+fn mapped_import_becomes_builtin() {
+	let cmds = all_cmds(&compile(IMPORT_MAP_MODULE, &["-O1", "--import-map", "lib.log=env.print"]));
+	assert!(prints(&cmds), "{cmds:?}");
+}
+
+fn write_import_map(name: &str, contents: &str) -> String {
+	let path = std::env::temp_dir().join(format!("wasmcraft_{name}_import_map.toml"));
+	std::fs::write(&path, contents).unwrap();
+	path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn mappings_are_read_from_file() {
+	let path = write_import_map("file", "\"lib.log\" = \"env.print\"\n");
+
+	let cmds = all_cmds(&compile(IMPORT_MAP_MODULE, &["-O1", "--import-map-file", &path]));
+	assert!(prints(&cmds), "{cmds:?}");
+}
+
+#[test]
+fn command_line_mappings_override_the_file() {
+	let path = write_import_map("override", "\"lib.log\" = \"env.print_missing\"\n");
+
+	let cmds = all_cmds(&compile(IMPORT_MAP_MODULE, &["-O1", "--import-map-file", &path, "--import-map", "lib.log=env.print"]));
+	assert!(prints(&cmds), "{cmds:?}");
+}
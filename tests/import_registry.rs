@@ -0,0 +1,64 @@
+use wasmcraft::{CompileContext, wasm_file::WasmFile, ssa::{SsaInstr, SsaVarOrConst, interp::TypedValue}};
+
+mod test_common;
+use test_common::{all_cmds, compile_with, i32_params, run_lir, run_ssa};
+
+/// A module importing a function that isn't a built-in intrinsic, exporting:
+///
+/// (import "env" "triple_plus_one" (func $triple_plus_one (param i32) (result i32)))
+///
+/// (func $run (param $x i32) (result i32)
+///   (call $triple_plus_one (local.get $x)))
+const REGISTRY_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Import section
+	0x02, 0x17, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x0F, b't', b'r', b'i', b'p', b'l', b'e', b'_', b'p', b'l', b'u', b's', b'_', b'o', b'n', b'e', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x01,
+	// Code section
+	0x0A, 0x08, 0x01,
+	0x06, 0x00, 0x20, 0x00, 0x10, 0x00, 0x0B,
+];
+
+fn make_ctx() -> CompileContext {
+	let mut ctx = CompileContext::new_from_opt(1);
+	ctx.register_import("env", "triple_plus_one", |call| {
+		let tripled = call.alloc.new_i32();
+		call.body.push(SsaInstr::Mul(tripled, call.params[0], SsaVarOrConst::Const(TypedValue::I32(3))));
+		call.body.push(SsaInstr::Add(call.returns[0], tripled.into(), SsaVarOrConst::Const(TypedValue::I32(1))));
+	});
+	ctx
+}
+
+#[test]
+fn registered_import_is_lowered() {
+	let ctx = make_ctx();
+	for (x, expected) in [(0, 1), (5, 16), (-2, -5)] {
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, REGISTRY_MODULE, "run", &i32_params(&[x])), expected, "SSA, run({x})");
+		assert_eq!(run_lir(&ctx, REGISTRY_MODULE, "run", &i32_params(&[x])), expected, "LIR, run({x})");
+	}
+}
+
+#[test]
+fn registered_import_is_inlined() {
+	let cmds = all_cmds(&compile_with(&make_ctx(), REGISTRY_MODULE));
+	assert!(cmds.iter().any(|cmd| cmd.contains(" *= ") && cmd.ends_with("3 reg")), "{cmds:?}");
+	assert!(!cmds.iter().any(|cmd| cmd.contains("triple_plus_one")), "{cmds:?}");
+}
+
+#[test]
+#[should_panic(expected = "unsupported import `env.triple_plus_one`")]
+fn unregistered_import_is_rejected() {
+	let ctx = CompileContext::new_from_opt(1);
+	let file = WasmFile::from(REGISTRY_MODULE);
+	ctx.compute_ssa(&file);
+}
@@ -0,0 +1,101 @@
+use wasmcraft::{CompileContext, wasm_file::WasmFile, ssa::interp::TypedValue, abi_doc::{self, AbiDocFormat}};
+
+mod test_common;
+use test_common::{all_cmds, compile, i32_params, run_lir, run_ssa};
+
+/// A module importing a mutable global and defining one of its own, exporting:
+///
+/// (global $sp (import "env" "__stack_pointer") (mut i32))
+/// (global $g i32 (i32.const 5))
+///
+/// (func $run (param $v i32) (result i32)
+///   (global.set $sp (local.get $v))
+///   (i32.add (i32.mul (global.get $sp) (i32.const 10)) (global.get $g)))
+const IMPORTED_GLOBAL_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Import section
+	0x02, 0x18, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x0F, b'_', b'_', b's', b't', b'a', b'c', b'k', b'_', b'p', b'o', b'i', b'n', b't', b'e', b'r',
+	0x03, 0x7F, 0x01,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Global section
+	0x06, 0x06, 0x01, 0x7F, 0x00, 0x41, 0x05, 0x0B,
+	// Export section
+	0x07, 0x07, 0x01, 0x03, b'r', b'u', b'n', 0x00, 0x00,
+	// Code section
+	0x0A, 0x10, 0x01,
+	0x0E, 0x00,
+	0x20, 0x00, 0x24, 0x00,
+	0x23, 0x00, 0x41, 0x0A, 0x6C,
+	0x23, 0x01,
+	0x6A,
+	0x0B,
+];
+
+#[test]
+fn imported_global_is_ordinary_global() {
+	let cases = [
+		// v, expected
+		(3, 35),
+		(-2, -15),
+	];
+
+	let ctx = CompileContext::new_from_opt(1);
+	for (arg, expected) in cases {
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, IMPORTED_GLOBAL_MODULE, "run", &i32_params(&[arg])), expected, "SSA, run({arg})");
+		assert_eq!(run_lir(&ctx, IMPORTED_GLOBAL_MODULE, "run", &i32_params(&[arg])), expected, "LIR, run({arg})");
+	}
+}
+
+#[test]
+fn imported_global_starts_at_zero() {
+	let cmds = all_cmds(&compile(IMPORTED_GLOBAL_MODULE, &["-O1"]));
+	assert!(cmds.iter().any(|cmd| cmd == "scoreboard players set %global%0%lo reg 0"), "{cmds:?}");
+	assert!(cmds.iter().any(|cmd| cmd == "scoreboard players set %global%1%lo reg 5"), "{cmds:?}");
+}
+
+#[test]
+fn abi_doc_names_imported_global() {
+	let ctx = CompileContext::new_from_opt(1);
+	let file = WasmFile::from(IMPORTED_GLOBAL_MODULE);
+	let program = ctx.compute_ssa(&file);
+
+	let doc = abi_doc::generate(&ctx, &file, &program, AbiDocFormat::Markdown);
+	assert!(doc.contains("- Global 0 (i32), imported as `env.__stack_pointer`:"), "{doc}");
+	assert!(doc.contains("- Global 1 (i32):"), "{doc}");
+
+	let doc = abi_doc::generate(&ctx, &file, &program, AbiDocFormat::Json);
+	assert!(doc.contains(r#""index":0,"type":"i32","import":"env.__stack_pointer""#), "{doc}");
+	assert!(doc.contains(r#""index":1,"type":"i32","import":null"#), "{doc}");
+}
+
+/// A module importing a float global:
+///
+/// (global $scale (import "env" "scale") f32)
+///
+/// (func $run)
+const IMPORTED_FLOAT_GLOBAL_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+	// Import section
+	0x02, 0x0E, 0x01,
+	0x03, b'e', b'n', b'v', 0x05, b's', b'c', b'a', b'l', b'e', 0x03, 0x7D, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01, 0x03, b'r', b'u', b'n', 0x00, 0x00,
+	// Code section
+	0x0A, 0x04, 0x01, 0x02, 0x00, 0x0B,
+];
+
+#[test]
+#[should_panic(expected = "imported global `env.scale` has type F32, but only integer and reference globals are supported")]
+fn imported_float_global_is_rejected() {
+	compile(IMPORTED_FLOAT_GLOBAL_MODULE, &["-O1"]);
+}
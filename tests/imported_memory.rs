@@ -0,0 +1,98 @@
+use command_parser::CommandParse;
+use datapack_common::functions::{Function, command_components::FunctionIdent};
+use datapack_vm::Interpreter;
+use wasmcraft::ssa::{Memory, interp::TypedValue};
+
+mod test_common;
+use test_common::{call_datapack, compile, find_func, func_cmds, i32_params, run_function};
+
+/// A module exporting its memory, which starts out holding the bytes 0x11 to 0x88, and:
+///
+/// (func $load (param i32) (result i32)
+///   (i32.load (local.get 0)))
+const EXPORTER_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x11, 0x02,
+	0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00,
+	0x04, b'l', b'o', b'a', b'd', 0x00, 0x00,
+	// Code section
+	0x0A, 0x09, 0x01,
+	0x07, 0x00, 0x20, 0x00, 0x28, 0x02, 0x00, 0x0B,
+	// Data section
+	0x0B, 0x0E, 0x01,
+	0x00, 0x41, 0x00, 0x0B, 0x08, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88,
+];
+
+/// A module importing `env.memory`, with data segments that only cover parts of the first two words:
+///
+/// (import "env" "memory" (memory 1))
+/// (data (i32.const 1) "\aa\bb")
+/// (data (i32.const 7) "\cc")
+/// (data (i32.const 8) "\01\02\03\04")
+/// (func $nop)
+const IMPORTER_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+	// Import section
+	0x02, 0x0F, 0x01,
+	0x03, b'e', b'n', b'v', 0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00, 0x01,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01, 0x03, b'n', b'o', b'p', 0x00, 0x00,
+	// Code section
+	0x0A, 0x04, 0x01, 0x02, 0x00, 0x0B,
+	// Data section
+	0x0B, 0x17, 0x03,
+	0x00, 0x41, 0x01, 0x0B, 0x02, 0xAA, 0xBB,
+	0x00, 0x41, 0x07, 0x0B, 0x01, 0xCC,
+	0x00, 0x41, 0x08, 0x0B, 0x04, 0x01, 0x02, 0x03, 0x04,
+];
+
+#[test]
+fn unaligned_ranges_mark_their_bytes() {
+	let mut memory = Memory::new_imported(1, None);
+	memory.mark_initialized(1..3);
+	memory.mark_initialized(7..12);
+	memory.mark_initialized(2..4);
+
+	let words = memory.initialized_words().into_iter().collect::<Vec<_>>();
+	assert_eq!(words, [(0, 0b1110), (4, 0b1000), (8, 0b1111)]);
+}
+
+#[test]
+fn partly_initialized_words_are_read_first() {
+	let funcs = func_cmds(&compile(IMPORTER_MODULE, &[]));
+	let init = find_func(&funcs, "wasmrunner:init");
+
+	let reads = init.iter().filter(|cmd| cmd.starts_with("execute store result score %%memory_init_word reg run data get block ")).count();
+	assert_eq!(reads, 2, "{init:?}");
+	assert!(init.iter().any(|cmd| cmd.ends_with(" RecordItem.tag.Memory set value 67305985")), "{init:?}");
+}
+
+/// Runs the importing program's `init` after the exporting program's, like they would be in a world they're both installed in.
+#[test]
+fn imported_data_keeps_the_other_bytes() {
+	let mut datapack = compile(EXPORTER_MODULE, &[]);
+	let importer = compile(IMPORTER_MODULE, &[]);
+	let importer_init = importer.into_iter().find(|func| func.id.to_string() == "wasmrunner:init").unwrap();
+	let (_, id) = FunctionIdent::parse_from_command("test:importer_init").unwrap();
+	datapack.push(Function { id, cmds: importer_init.cmds });
+
+	let mut interp = Interpreter::new(datapack, 0);
+	run_function(&mut interp, "wasmrunner:init");
+	run_function(&mut interp, "test:importer_init");
+
+	let expected = [(0, 0x44BBAA11), (4, 0xCC776655_u32 as i32), (8, 0x04030201)];
+	for (addr, word) in expected {
+		assert_eq!(call_datapack(&mut interp, "load", &i32_params(&[addr]), false), TypedValue::I32(word), "{addr}");
+	}
+}
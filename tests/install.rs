@@ -0,0 +1,71 @@
+use clap::Parser;
+use wasmcraft::{Args, CompileContext, mc_version::McVersion, pack_emitter};
+
+mod test_common;
+use test_common::compile_with;
+
+/// A module exporting:
+///
+/// (func $nop)
+const NOP_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x04, 0x01,
+	0x60, 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'n', b'o', b'p', 0x00, 0x00,
+	// Code section
+	0x0A, 0x04, 0x01,
+	0x02, 0x00, 0x0B,
+];
+
+/// Creates an empty world save with only a `level.dat`.
+fn make_world(name: &str) -> std::path::PathBuf {
+	let world = std::env::temp_dir().join(name);
+	let _ = std::fs::remove_dir_all(&world);
+	std::fs::create_dir_all(&world).unwrap();
+	std::fs::write(world.join("level.dat"), b"").unwrap();
+	world
+}
+
+#[test]
+fn install_writes_into_world_datapacks() {
+	let world = make_world("wasmcraft_install_world");
+	let ctx = CompileContext::new(Args::parse_from(["wasmcraft", "nop.wasm", "-o", "out/mypack", "--install", world.to_str().unwrap()]));
+	assert_eq!(ctx.output(), world.join("datapacks").join("mypack"));
+
+	let datapack = compile_with(&ctx, NOP_MODULE);
+	pack_emitter::persist_program(ctx.output(), &datapack, McVersion::default());
+	assert!(world.join("datapacks/mypack/pack.mcmeta").exists());
+	assert!(world.join("datapacks/mypack/data/wasmrunner/functions/nop.mcfunction").exists());
+
+	// No load tag is written unless markers are refreshed
+	assert!(!world.join("datapacks/mypack/data/minecraft").exists());
+}
+
+#[test]
+#[should_panic(expected = "is not a world save")]
+fn install_needs_world() {
+	let folder = std::env::temp_dir().join("wasmcraft_install_not_world");
+	let _ = std::fs::remove_dir_all(&folder);
+	std::fs::create_dir_all(&folder).unwrap();
+	CompileContext::new(Args::parse_from(["wasmcraft", "nop.wasm", "-o", "mypack", "--install", folder.to_str().unwrap()]));
+}
+
+#[test]
+fn refresh_markers_runs_on_load() {
+	let world = make_world("wasmcraft_install_refresh_world");
+	let ctx = CompileContext::new(Args::parse_from(["wasmcraft", "nop.wasm", "-o", "mypack", "--install", world.to_str().unwrap(), "--refresh-markers"]));
+
+	let datapack = compile_with(&ctx, NOP_MODULE);
+	pack_emitter::persist_program(ctx.output(), &datapack, McVersion::default());
+
+	let load = std::fs::read_to_string(world.join("datapacks/mypack/data/minecraft/tags/functions/load.json")).unwrap();
+	assert_eq!(load, r#"{"values":["wasmrunner:__refresh_markers"]}"#);
+
+	let refresh = std::fs::read_to_string(world.join("datapacks/mypack/data/wasmrunner/functions/__refresh_markers.mcfunction")).unwrap();
+	assert!(refresh.contains("summon minecraft:marker"), "{refresh}");
+}
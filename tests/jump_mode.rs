@@ -0,0 +1,101 @@
+use wasmcraft::{mc_version::McVersion, pack_emitter};
+use datapack_common::functions::Function;
+
+mod test_common;
+
+/// A module exporting:
+///
+/// (func $sum (param i32) (result i32) (local i32)
+///   (block
+///     (loop
+///       (br_if 1 (i32.eqz (local.get 0)))
+///       (local.set 1 (i32.add (local.get 1) (local.get 0)))
+///       (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+///       (br 0)))
+///   (local.get 1))
+const SUM_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b's', b'u', b'm', 0x00, 0x00,
+	// Code section
+	0x0A, 0x23, 0x01,
+	0x21, 0x01, 0x01, 0x7F,
+	0x02, 0x40,
+	0x03, 0x40,
+	0x20, 0x00, 0x45, 0x0D, 0x01,
+	0x20, 0x01, 0x20, 0x00, 0x6A, 0x21, 0x01,
+	0x20, 0x00, 0x41, 0x01, 0x6B, 0x21, 0x00,
+	0x0C, 0x00,
+	0x0B,
+	0x0B,
+	0x20, 0x01,
+	0x0B,
+];
+
+fn compile(jump_mode: &str) -> Vec<Function> {
+	test_common::compile(SUM_MODULE, &["-O1", "--jump-mode", jump_mode])
+}
+
+fn lines(funcs: &[Function], id: &str, version: McVersion) -> Vec<String> {
+	let func = funcs.iter().find(|func| func.id.to_string() == id).unwrap_or_else(|| panic!("missing {id}"));
+	pack_emitter::written_lines(func, version).unwrap()
+}
+
+#[test]
+fn loops_bounce_instead_of_scheduling_blocks() {
+	let funcs = compile("bounce");
+
+	let block_lines = funcs.iter()
+		.filter(|func| func.id.to_string().starts_with("wasmrunner:wasm_0"))
+		.flat_map(|func| func.cmds.iter().map(|cmd| cmd.to_string()))
+		.collect::<Vec<_>>();
+	assert!(block_lines.iter().any(|cmd| cmd.contains("scoreboard players operation %%bounce_next reg = ")), "{block_lines:?}");
+	assert!(!block_lines.iter().any(|cmd| cmd.contains("schedule function")), "{block_lines:?}");
+	assert!(!block_lines.iter().any(|cmd| cmd.contains("%%max_commands")), "{block_lines:?}");
+
+	let init = lines(&funcs, "wasmrunner:init", McVersion::new(19, 4));
+	assert!(init.contains(&"scoreboard players set %%bounce_next reg -1".to_string()));
+}
+
+#[test]
+fn exports_run_the_dispatcher() {
+	let funcs = compile("bounce");
+
+	let export = lines(&funcs, "wasmrunner:sum", McVersion::new(19, 4));
+	assert_eq!(export.last().unwrap(), "execute unless score %%bounce_next reg matches -1 run function wasmrunner:__bounce");
+
+	let dispatcher = lines(&funcs, "wasmrunner:__bounce", McVersion::new(19, 4));
+	assert!(dispatcher.iter().any(|cmd| cmd.ends_with("run schedule function wasmrunner:__bounce_resume 1")), "{dispatcher:?}");
+	assert!(funcs.iter().any(|func| func.id.to_string() == "wasmrunner:__bounce_resume"));
+}
+
+#[test]
+fn dispatcher_uses_macros_when_available() {
+	let funcs = compile("bounce");
+
+	let old = lines(&funcs, "wasmrunner:__bounce_step", McVersion::new(19, 4));
+	assert!(old.iter().any(|cmd| cmd.starts_with("function wasmrunner:__bounce_to_")), "{old:?}");
+	assert!(!old.iter().any(|cmd| cmd.contains("return run")), "{old:?}");
+
+	let new = lines(&funcs, "wasmrunner:__bounce_step", McVersion::new(20, 2));
+	assert!(new.iter().any(|cmd| cmd.ends_with("run function wasmrunner:__call_target with storage wasm:bounce next")), "{new:?}");
+	assert!(new.iter().any(|cmd| cmd.ends_with("run return run function wasmrunner:__bounce")), "{new:?}");
+}
+
+#[test]
+fn modes_can_be_mixed_in_one_process() {
+	let direct = compile("direct");
+	let bounce = compile("bounce");
+	let direct_again = compile("direct");
+
+	let ids = |funcs: &[Function]| funcs.iter().map(|func| func.id.to_string()).collect::<Vec<_>>();
+	assert!(!ids(&direct).contains(&"wasmrunner:__bounce".to_string()));
+	assert!(ids(&bounce).contains(&"wasmrunner:__bounce".to_string()));
+	assert_eq!(ids(&direct), ids(&direct_again));
+}
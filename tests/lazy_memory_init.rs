@@ -0,0 +1,66 @@
+use wasmcraft::ssa::interp::TypedValue;
+
+mod test_common;
+use test_common::{call_datapack, find_func, func_cmds, start_datapack};
+
+/// A module whose start function calls an export with a different argument, exporting:
+///
+/// (memory 1)
+/// (data (i32.const 0) "\2a\00\00\00\07\00\00\00")
+///
+/// (global $g (mut i32) (i32.const 0))
+///
+/// (func $load (param i32) (result i32) (i32.load (local.get 0)))
+///
+/// (func $init (global.set $g (call $load (i32.const 4))))
+///
+/// (start $init)
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x09, 0x02, 0x60, 0x01, 0x7F, 0x01, 0x7F, 0x60, 0x00, 0x00,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x01,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Global section
+	0x06, 0x06, 0x01, 0x7F, 0x01, 0x41, 0x00, 0x0B,
+	// Export section
+	0x07, 0x08, 0x01, 0x04, b'l', b'o', b'a', b'd', 0x00, 0x00,
+	// Start section
+	0x08, 0x01, 0x01,
+	// Code section
+	0x0A, 0x12, 0x02,
+	0x07, 0x00, 0x20, 0x00, 0x28, 0x02, 0x00, 0x0B,
+	0x08, 0x00, 0x41, 0x04, 0x10, 0x00, 0x24, 0x00, 0x0B,
+	// Data section
+	0x0B, 0x0E, 0x01, 0x00, 0x41, 0x00, 0x0B, 0x08,
+	0x2A, 0x00, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn first_call_waits_for_memory() {
+	let funcs = func_cmds(&test_common::compile(MODULE, &["--lazy-memory-init"]));
+
+	let init = find_func(&funcs, "wasmrunner:init");
+	assert!(!init.iter().any(|cmd| cmd.ends_with("RecordItem.tag.Memory set value 42")), "{init:?}");
+
+	let export = find_func(&funcs, "wasmrunner:load");
+	assert!(export.iter().any(|cmd| cmd.ends_with("run schedule function wasmrunner:__lazy_retry_load 1")), "{export:?}");
+	assert!(export.iter().any(|cmd| cmd.ends_with("run scoreboard players operation %%lazy_load_arg0 reg = %param%0%lo reg")), "{export:?}");
+
+	let retry = find_func(&funcs, "wasmrunner:__lazy_retry_load");
+	assert_eq!(retry, ["scoreboard players operation %param%0%lo reg = %%lazy_load_arg0 reg", "function wasmrunner:load"]);
+}
+
+#[test]
+fn first_call_keeps_its_arguments() {
+	let mut interp = start_datapack(MODULE, &["--lazy-memory-init"]);
+
+	// The start function calls `load` with 4 right before this call is retried
+	let actual = call_datapack(&mut interp, "load", &[TypedValue::I32(0)], false);
+	assert_eq!(actual, TypedValue::I32(42));
+
+	let actual = call_datapack(&mut interp, "load", &[TypedValue::I32(4)], false);
+	assert_eq!(actual, TypedValue::I32(7));
+}
@@ -0,0 +1,176 @@
+use wasmcraft::{wasm_file::WasmFile, ssa::{BlockId, interp::{SsaInterpreter, TypedValue}}, lir::interp::LirInterpreter};
+
+mod test_common;
+use test_common::context;
+
+/// A library module, exporting:
+///
+/// (memory (export "memory") 1)
+/// (global (export "base") (mut i32) (i32.const 100))
+///
+/// (func (export "offset") (param i32) (result i32)
+///   (i32.add (local.get 0) (global.get 0)))
+const LIB_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Global section
+	0x06, 0x07, 0x01, 0x7F, 0x01, 0x41, 0xE4, 0x00, 0x0B,
+	// Export section
+	0x07, 0x1A, 0x03,
+	0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00,
+	0x04, b'b', b'a', b's', b'e', 0x03, 0x00,
+	0x06, b'o', b'f', b'f', b's', b'e', b't', 0x00, 0x00,
+	// Code section
+	0x0A, 0x09, 0x01,
+	0x07, 0x00, 0x20, 0x00, 0x23, 0x00, 0x6A, 0x0B,
+];
+
+/// A module using the library, exporting:
+///
+/// (import "mathlib" "offset" (func $offset (param i32) (result i32)))
+/// (import "mathlib" "memory" (memory 1))
+/// (import "mathlib" "base" (global $base (mut i32)))
+///
+/// (func (export "calc") (param i32) (result i32)
+///   (global.set $base (i32.add (global.get $base) (i32.const 1)))
+///   (i32.add (call $offset (local.get 0)) (i32.load (i32.const 16))))
+///
+/// (data (i32.const 16) "\07\00\00\00")
+const MAIN_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Import section
+	0x02, 0x34, 0x03,
+	0x07, b'm', b'a', b't', b'h', b'l', b'i', b'b', 0x06, b'o', b'f', b'f', b's', b'e', b't', 0x00, 0x00,
+	0x07, b'm', b'a', b't', b'h', b'l', b'i', b'b', 0x06, b'm', b'e', b'm', b'o', b'r', b'y', 0x02, 0x00, 0x01,
+	0x07, b'm', b'a', b't', b'h', b'l', b'i', b'b', 0x04, b'b', b'a', b's', b'e', 0x03, 0x7F, 0x01,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01, 0x04, b'c', b'a', b'l', b'c', 0x00, 0x01,
+	// Code section
+	0x0A, 0x15, 0x01,
+	0x13, 0x00,
+	0x23, 0x00, 0x41, 0x01, 0x6A, 0x24, 0x00,
+	0x20, 0x00, 0x10, 0x00,
+	0x41, 0x10, 0x28, 0x02, 0x00,
+	0x6A,
+	0x0B,
+	// Data section
+	0x0B, 0x0A, 0x01,
+	0x00, 0x41, 0x10, 0x0B, 0x04, 0x07, 0x00, 0x00, 0x00,
+];
+
+#[test]
+fn imports_are_resolved_to_other_modules() {
+	let ctx = context(&["-O1"]);
+	let main = WasmFile::from(MAIN_MODULE);
+	let lib = WasmFile::from(LIB_MODULE);
+	let program = ctx.compute_linked_ssa(&[("main", &main), ("mathlib", &lib)]);
+
+	// The first module keeps its function indices, and the library's follow them
+	assert_eq!(program.exports["calc"], BlockId { func: 1, block: 0 });
+	assert_eq!(program.exports["offset"], BlockId { func: 2, block: 0 });
+
+	// The memory and global are shared instead of being imported from outside the program
+	assert_eq!(program.globals, vec![TypedValue::I32(100)]);
+	assert_eq!(program.memory.len(), 1);
+	assert!(program.memory[0].imported.is_none());
+	assert_eq!(program.memory[0].load(16, 4), &[7, 0, 0, 0]);
+}
+
+#[test]
+fn linked_program_runs() {
+	let ctx = context(&["-O1"]);
+	let main = WasmFile::from(MAIN_MODULE);
+	let lib = WasmFile::from(LIB_MODULE);
+	let calc = main.find_func("calc").unwrap();
+
+	// Each call adds one to the shared global before the library reads it
+	let mut interp = SsaInterpreter::new(ctx.compute_linked_ssa(&[("main", &main), ("mathlib", &lib)]));
+	for expected in [113, 114] {
+		interp.call(calc, vec![TypedValue::I32(5)]);
+		assert_eq!(interp.run_until_halted(), vec![TypedValue::I32(expected)], "SSA");
+	}
+
+	let mut interp = LirInterpreter::new(ctx.compute_lir(ctx.compute_linked_ssa(&[("main", &main), ("mathlib", &lib)])));
+	for expected in [113, 114] {
+		interp.call(calc, &[TypedValue::I32(5)]);
+		assert_eq!(interp.run_until_halted(), vec![TypedValue::I32(expected)], "LIR");
+	}
+}
+
+#[test]
+#[should_panic(expected = "unsupported import `mathlib.offset`")]
+fn imports_are_only_resolved_by_module_name() {
+	let ctx = context(&["-O1"]);
+	let main = WasmFile::from(MAIN_MODULE);
+	let lib = WasmFile::from(LIB_MODULE);
+	ctx.compute_linked_ssa(&[("main", &main), ("otherlib", &lib)]);
+}
+
+/// A library module whose `setup` sets a global, exporting:
+///
+/// (global $value (mut i32) (i32.const 0))
+///
+/// (func (export "setup")
+///   (global.set $value (i32.const 42)))
+///
+/// (func (export "get") (result i32)
+///   (global.get $value))
+const SETUP_LIB_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x08, 0x02, 0x60, 0x00, 0x00, 0x60, 0x00, 0x01, 0x7F,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x01,
+	// Global section
+	0x06, 0x06, 0x01, 0x7F, 0x01, 0x41, 0x00, 0x0B,
+	// Export section
+	0x07, 0x0F, 0x02,
+	0x05, b's', b'e', b't', b'u', b'p', 0x00, 0x00,
+	0x03, b'g', b'e', b't', 0x00, 0x01,
+	// Code section
+	0x0A, 0x0D, 0x02,
+	0x06, 0x00, 0x41, 0x2A, 0x24, 0x00, 0x0B,
+	0x04, 0x00, 0x23, 0x00, 0x0B,
+];
+
+/// A module whose start function is imported from the library:
+///
+/// (import "setuplib" "setup" (func $setup))
+///
+/// (start $setup)
+const SETUP_MAIN_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+	// Import section
+	0x02, 0x12, 0x01,
+	0x08, b's', b'e', b't', b'u', b'p', b'l', b'i', b'b', 0x05, b's', b'e', b't', b'u', b'p', 0x00, 0x00,
+	// Start section
+	0x08, 0x01, 0x00,
+];
+
+#[test]
+fn imported_start_function_runs_the_linked_function() {
+	let ctx = context(&["-O1"]);
+	let main = WasmFile::from(SETUP_MAIN_MODULE);
+	let lib = WasmFile::from(SETUP_LIB_MODULE);
+	let program = ctx.compute_linked_ssa(&[("main", &main), ("setuplib", &lib)]);
+	assert_eq!(program.start, Some(program.exports["setup"]));
+
+	let start = program.start.unwrap();
+	let get = program.exports["get"];
+	let mut interp = SsaInterpreter::new(program);
+	interp.call(start.func, Vec::new());
+	interp.run_until_halted();
+	interp.call(get.func, Vec::new());
+	assert_eq!(interp.run_until_halted(), vec![TypedValue::I32(42)]);
+}
@@ -0,0 +1,137 @@
+mod test_common;
+use test_common::compile;
+use clap::Parser;
+use wasmcraft::{Args, mc_version::McVersion, pack_emitter};
+
+/// A module with one page of memory exporting:
+///
+/// (import "env" "mc_random" (func $mc_random (param i32) (result i32)))
+///
+/// (func $roll (result i32)
+///   (call $mc_random (i32.const 6)))
+const VERSION_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0A, 0x02,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	0x60, 0x00, 0x01, 0x7F,
+	// Import section
+	0x02, 0x11, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x09, b'm', b'c', b'_', b'r', b'a', b'n', b'd', b'o', b'm', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'r', b'o', b'l', b'l', 0x00, 0x01,
+	// Code section
+	0x0A, 0x08, 0x01,
+	0x06, 0x00, 0x41, 0x06, 0x10, 0x00, 0x0B,
+];
+
+/// A module exporting:
+///
+/// (import "env" "turtle_light_level" (func $turtle_light_level (result i32)))
+///
+/// (func $light (result i32)
+///   (call $turtle_light_level))
+const LIGHT_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7F,
+	// Import section
+	0x02, 0x1A, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x12, b't', b'u', b'r', b't', b'l', b'e', b'_', b'l', b'i', b'g', b'h', b't', b'_', b'l', b'e', b'v', b'e', b'l', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x09, 0x01,
+	0x05, b'l', b'i', b'g', b'h', b't', 0x00, 0x01,
+	// Code section
+	0x0A, 0x06, 0x01,
+	0x04, 0x00, 0x10, 0x00, 0x0B,
+];
+
+/// Compiles the module and writes it for the given version, returning the folder it was written to.
+fn persist(version: &str) -> std::path::PathBuf {
+	persist_module(VERSION_MODULE, "version", version)
+}
+
+fn persist_module(module: &[u8], name: &str, version: &str) -> std::path::PathBuf {
+	let datapack = compile(module, &["-O1", "--mc-version", version]);
+
+	let folder = std::env::temp_dir().join(format!("wasmcraft_mc_version_{name}_{version}"));
+	let _ = std::fs::remove_dir_all(&folder);
+	pack_emitter::persist_program(&folder, &datapack, McVersion::parse(version).unwrap());
+	folder
+}
+
+fn read(folder: &std::path::Path, path: &str) -> String {
+	std::fs::read_to_string(folder.join(path)).unwrap_or_else(|err| panic!("failed to read {path}: {err}"))
+}
+
+#[test]
+fn versions_are_parsed() {
+	assert_eq!(McVersion::parse("1.20.4"), Ok(McVersion::new(20, 4)));
+	assert_eq!(McVersion::parse("1.21"), Ok(McVersion::new(21, 0)));
+	assert_eq!(McVersion::new(21, 0).to_string(), "1.21");
+
+	assert!(McVersion::parse("1.16.5").is_err());
+	assert!(McVersion::parse("1.22").is_err());
+	assert!(McVersion::parse("2.0").is_err());
+	assert!(McVersion::parse("1.20.x").is_err());
+	assert!(Args::try_parse_from(["wasmcraft", "version.wasm", "-o", "out", "--mc-version", "1.12.2"]).is_err());
+}
+
+#[test]
+fn pack_format_follows_version() {
+	assert_eq!(McVersion::new(17, 1).pack_format(), 7);
+	assert_eq!(McVersion::new(19, 4).pack_format(), 12);
+	assert_eq!(McVersion::new(20, 4).pack_format(), 26);
+	assert_eq!(McVersion::new(21, 0).pack_format(), 48);
+}
+
+#[test]
+fn default_version_uses_plural_folders_and_item_tags() {
+	let folder = persist("1.19.4");
+
+	assert!(read(&folder, "pack.mcmeta").contains(r#""pack_format": 12"#));
+	assert!(read(&folder, "data/wasmrunner/functions/init.mcfunction").contains("tag:{Memory:0}"));
+	assert!(read(&folder, "data/intrinsic/functions/random.mcfunction").contains("%%rng_state"));
+	// Nothing reads the light level
+	assert!(!folder.join("data/wasmrunner/predicates").exists());
+}
+
+#[test]
+fn newer_versions_use_new_commands_and_folders() {
+	let folder = persist("1.21");
+
+	assert!(read(&folder, "pack.mcmeta").contains(r#""pack_format": 48"#));
+
+	let init = read(&folder, "data/wasmrunner/function/init.mcfunction");
+	assert!(init.contains(r#"RecordItem:{id:"minecraft:stone",count:1,components:{"minecraft:custom_data":{Memory:0}}}"#), "{init}");
+	assert!(!init.contains("tag:{Memory"), "{init}");
+
+	let random = read(&folder, "data/intrinsic/function/random.mcfunction");
+	assert!(random.contains("run random value"), "{random}");
+
+	let load = read(&folder, "data/intrinsic/function/load_word.mcfunction");
+	assert!(load.contains(r#"RecordItem.components."minecraft:custom_data".Memory"#), "{load}");
+
+	assert!(!folder.join("data/wasmrunner/predicate").exists());
+	assert!(!folder.join("data/wasmrunner/functions").exists());
+}
+
+#[test]
+fn light_predicates_are_written_when_the_light_level_is_read() {
+	let folder = persist_module(LIGHT_MODULE, "light", "1.19.4");
+	assert!(folder.join("data/wasmrunner/predicates/light/1.json").exists());
+	assert!(folder.join("data/wasmrunner/predicates/light/15.json").exists());
+
+	let folder = persist_module(LIGHT_MODULE, "light", "1.21");
+	assert!(folder.join("data/wasmrunner/predicate/light/15.json").exists());
+	assert!(!folder.join("data/wasmrunner/predicates").exists());
+}
@@ -0,0 +1,130 @@
+use clap::Parser;
+use wasmcraft::{Args, CompileContext, mc_version::McVersion, pack_emitter};
+
+mod test_common;
+
+/// A module with three pages of memory exporting:
+///
+/// (data (i32.const 16) "\2a")
+///
+/// (func $swap (param i32) (result i32)
+///   (i32.store (local.get 0) (i32.const 7))
+///   (i32.load (i32.const 16)))
+const MEMORY_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x03,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b's', b'w', b'a', b'p', 0x00, 0x00,
+	// Code section
+	0x0A, 0x10, 0x01,
+	0x0E, 0x00,
+	0x20, 0x00, 0x41, 0x07, 0x36, 0x02, 0x00,
+	0x41, 0x10, 0x28, 0x02, 0x00,
+	0x0B,
+	// Data section
+	0x0B, 0x07, 0x01,
+	0x00, 0x41, 0x10, 0x0B, 0x01, 0x2A,
+];
+
+fn compile(backend: &str, version: &str) -> Vec<datapack_common::functions::Function> {
+	test_common::compile(MEMORY_MODULE, &["-O1", "--memory-backend", backend, "--mc-version", version])
+}
+
+/// Reads every function in a written datapack into one string.
+fn read_functions(folder: &std::path::Path) -> String {
+	let mut contents = String::new();
+	let mut dirs = vec![folder.join("data")];
+	while let Some(dir) = dirs.pop() {
+		for entry in std::fs::read_dir(dir).unwrap() {
+			let path = entry.unwrap().path();
+			if path.is_dir() {
+				dirs.push(path);
+			} else if path.extension() == Some(std::ffi::OsStr::new("mcfunction")) {
+				contents.push_str(&std::fs::read_to_string(path).unwrap());
+			}
+		}
+	}
+	contents
+}
+
+/// Compiles the module with a backend and writes it for 1.20.2, returning the folder it was written to.
+fn persist(backend: &str) -> std::path::PathBuf {
+	let datapack = compile(backend, "1.20.2");
+
+	let folder = std::env::temp_dir().join(format!("wasmcraft_{backend}_memory"));
+	let _ = std::fs::remove_dir_all(&folder);
+	pack_emitter::persist_program(&folder, &datapack, McVersion::new(20, 2));
+	folder
+}
+
+#[test]
+fn jukebox_memory_is_filled_two_pages_at_a_time() {
+	let datapack = compile("jukebox", "1.20.2");
+	let init = datapack.iter().find(|func| func.id.to_string() == "wasmrunner:init").unwrap();
+	let fills = init.cmds.iter().map(|cmd| cmd.to_string()).filter(|cmd| cmd.starts_with("fill ")).collect::<Vec<_>>();
+
+	assert_eq!(fills.len(), 4, "{fills:?}");
+	assert!(fills[0].starts_with("fill 0 0 0 3 255 31 minecraft:air"), "{fills:?}");
+	assert!(fills[2].starts_with("fill 4 0 0 5 255 31 minecraft:air"), "{fills:?}");
+
+	// Only the data segment's word is written after that
+	let words = init.cmds.iter().map(|cmd| cmd.to_string()).filter(|cmd| cmd.contains("RecordItem.tag.Memory set value")).collect::<Vec<_>>();
+	assert_eq!(words, ["data modify block 0 0 4 RecordItem.tag.Memory set value 42"]);
+}
+
+#[test]
+fn memory_is_kept_in_storage() {
+	let folder = persist("storage");
+
+	let init = std::fs::read_to_string(folder.join("data/wasmrunner/functions/init.mcfunction")).unwrap();
+	assert!(init.contains("data modify storage wasm:memory m0 append from storage wasm:memory zero_page"), "{init}");
+	// The data segment's word is the fifth in the first page
+	assert!(init.contains("data modify storage wasm:memory m0[0][4] set value 42"), "{init}");
+
+	let funcs = read_functions(&folder);
+	assert!(!funcs.contains("jukebox"), "{funcs}");
+	assert!(!funcs.contains("RecordItem"), "{funcs}");
+	assert!(!funcs.contains("# !STORAGE"), "{funcs}");
+	assert!(funcs.contains("function intrinsic:storage/store_word with storage wasm:scratch mem"), "{funcs}");
+	assert!(funcs.contains("run data get storage wasm:memory m0[0][4]"), "{funcs}");
+
+	let load = std::fs::read_to_string(folder.join("data/intrinsic/functions/storage/load_word.mcfunction")).unwrap();
+	assert_eq!(load.trim(), "$return run data get storage wasm:memory m$(memory)[$(page)][$(word)] 1");
+}
+
+#[test]
+fn memory_is_kept_in_scores() {
+	let folder = persist("scoreboard");
+
+	let init = std::fs::read_to_string(folder.join("data/wasmrunner/functions/init.mcfunction")).unwrap();
+	assert!(init.contains("scoreboard objectives add wasm_memory0 dummy"), "{init}");
+	assert!(init.contains("scoreboard players set %p0w4 wasm_memory0 42"), "{init}");
+
+	let funcs = read_functions(&folder);
+	assert!(!funcs.contains("jukebox"), "{funcs}");
+	assert!(!funcs.contains("RecordItem"), "{funcs}");
+	assert!(funcs.contains("function intrinsic:scoreboard/store_word with storage wasm:scratch mem"), "{funcs}");
+	assert!(funcs.contains("run scoreboard players get %p0w4 wasm_memory0"), "{funcs}");
+
+	let store = std::fs::read_to_string(folder.join("data/intrinsic/functions/scoreboard/store_word.mcfunction")).unwrap();
+	assert_eq!(store.trim(), "$execute store result score %p$(page)w$(word) wasm_memory$(memory) run data get storage wasm:scratch mem.value 1");
+}
+
+#[test]
+#[should_panic(expected = "needs function macros")]
+fn macro_backends_need_macros() {
+	compile("storage", "1.20.1");
+}
+
+#[test]
+#[should_panic(expected = "can only run programs using the jukebox memory backend")]
+fn interpreter_needs_jukebox_memory() {
+	CompileContext::new(Args::parse_from(["wasmcraft", "memory.wasm", "-o", "out", "--memory-backend", "scoreboard", "--mc-version", "1.20.2", "--run-output"]));
+}
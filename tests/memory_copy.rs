@@ -0,0 +1,81 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{all_cmds, compile, i32_params, run_lir, run_ssa};
+
+/// A module with one page of memory starting with the bytes 1 through 8, exporting:
+///
+/// (func $copy (param $dest i32) (param $src i32) (param $len i32) (param $probe i32) (result i32)
+///   (memory.copy (local.get $dest) (local.get $src) (local.get $len))
+///   (i32.load8_u (local.get $probe)))
+const COPY_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x09, 0x01, 0x60, 0x04, 0x7F, 0x7F, 0x7F, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x08, 0x01, 0x04, b'c', b'o', b'p', b'y', 0x00, 0x00,
+	// Code section
+	0x0A, 0x13, 0x01, 0x11, 0x00,
+	0x20, 0x00, 0x20, 0x01, 0x20, 0x02,
+	0xFC, 0x0A, 0x00, 0x00,
+	0x20, 0x03, 0x2D, 0x00, 0x00,
+	0x0B,
+	// Data section
+	0x0B, 0x0E, 0x01, 0x00, 0x41, 0x00, 0x0B, 0x08,
+	0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+];
+
+#[test]
+fn memory_copy_matches() {
+	let ctx = CompileContext::new_from_opt(1);
+	let cases = [
+		// dest, src, len, probe, expected
+		(16, 0, 4, 16, 1),
+		(16, 0, 4, 19, 4),
+		(16, 0, 4, 20, 0),
+		(17, 3, 2, 18, 5),
+		// Overlapping copies behave like memmove in both directions
+		(2, 0, 4, 2, 1),
+		(2, 0, 4, 5, 4),
+		(0, 2, 4, 0, 3),
+		(0, 2, 4, 3, 6),
+		(0, 2, 4, 4, 5),
+		// Zero-length copies do nothing
+		(0, 4, 0, 0, 1),
+		// Copies at the very end of memory are allowed
+		(65532, 0, 4, 65535, 4),
+		(65536, 65536, 0, 0, 1),
+	];
+
+	for (dest, src, len, probe, expected) in cases {
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, COPY_MODULE, "copy", &i32_params(&[dest, src, len, probe])), expected, "SSA, copy({dest}, {src}, {len}), probe {probe}");
+		assert_eq!(run_lir(&ctx, COPY_MODULE, "copy", &i32_params(&[dest, src, len, probe])), expected, "LIR, copy({dest}, {src}, {len}), probe {probe}");
+	}
+}
+
+#[test]
+fn memory_copy_calls_memcpy() {
+	let cmds = all_cmds(&compile(COPY_MODULE, &["-O1"]));
+
+	let call = cmds.iter().position(|cmd| cmd == "function intrinsic:memcpy").unwrap();
+	assert!(cmds[call - 3].starts_with("scoreboard players operation %param0%0 reg = "), "{}", cmds[call - 3]);
+	assert!(cmds[call - 2].starts_with("scoreboard players operation %param1%0 reg = "), "{}", cmds[call - 2]);
+	assert!(cmds[call - 1].starts_with("scoreboard players operation %param2%0 reg = "), "{}", cmds[call - 1]);
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn memory_copy_out_of_bounds_ssa() {
+	run_ssa(&CompileContext::new_from_opt(1), COPY_MODULE, "copy", &i32_params(&[0, 65534, 4, 0]));
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn memory_copy_out_of_bounds_lir() {
+	run_lir(&CompileContext::new_from_opt(1), COPY_MODULE, "copy", &i32_params(&[65534, 0, 4, 0]));
+}
@@ -0,0 +1,84 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{all_cmds, compile, i32_params, run_lir, run_ssa};
+
+/// A module with one page of memory, exporting:
+///
+/// (func $fill (param $dest i32) (param $value i32) (param $len i32) (param $probe i32) (result i32)
+///   (memory.fill (local.get $dest) (local.get $value) (local.get $len))
+///   (i32.load8_u (local.get $probe)))
+const FILL_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x09, 0x01, 0x60, 0x04, 0x7F, 0x7F, 0x7F, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x08, 0x01, 0x04, b'f', b'i', b'l', b'l', 0x00, 0x00,
+	// Code section
+	0x0A, 0x12, 0x01, 0x10, 0x00,
+	0x20, 0x00, 0x20, 0x01, 0x20, 0x02,
+	0xFC, 0x0B, 0x00,
+	0x20, 0x03, 0x2D, 0x00, 0x00,
+	0x0B,
+];
+
+#[test]
+fn memory_fill_matches() {
+	let cases = [
+		// dest, value, len, probe, expected
+		(0, 0x12, 4, 0, 0x12),
+		(0, 0x12, 4, 3, 0x12),
+		(0, 0x12, 4, 4, 0),
+		(5, 0x34, 3, 4, 0),
+		(5, 0x34, 3, 5, 0x34),
+		(5, 0x34, 3, 7, 0x34),
+		(5, 0x34, 3, 8, 0),
+		// Only the low byte of the value is used
+		(8, 0x1FF, 1, 8, 0xFF),
+		(8, -1, 1, 8, 0xFF),
+		// Zero-length fills do nothing
+		(8, 0x56, 0, 8, 0),
+		// Zero-length fills at the very end of memory are allowed
+		(65536, 0x56, 0, 0, 0),
+		(65532, 0x78, 4, 65535, 0x78),
+	];
+
+	let ctx = CompileContext::new_from_opt(1);
+	for (dest, value, len, probe, expected) in cases {
+		let params = i32_params(&[dest, value, len, probe]);
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, FILL_MODULE, "fill", &params), expected, "SSA, fill({dest}, {value}, {len}), probe {probe}");
+		assert_eq!(run_lir(&ctx, FILL_MODULE, "fill", &params), expected, "LIR, fill({dest}, {value}, {len}), probe {probe}");
+	}
+}
+
+#[test]
+fn memory_fill_calls_memset() {
+	let cmds = all_cmds(&compile(FILL_MODULE, &["-O1"]));
+
+	let call = cmds.iter().position(|cmd| cmd == "function intrinsic:memset").unwrap();
+	assert_eq!(cmds[call - 2], "scoreboard players operation %param1%0 reg %= %%256 reg");
+	assert!(cmds[call - 1].starts_with("scoreboard players operation %param2%0 reg = "), "{}", cmds[call - 1]);
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn memory_fill_out_of_bounds_ssa() {
+	run_ssa(&CompileContext::new_from_opt(1), FILL_MODULE, "fill", &i32_params(&[65534, 0, 4, 0]));
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn memory_fill_out_of_bounds_zero_length_ssa() {
+	run_ssa(&CompileContext::new_from_opt(1), FILL_MODULE, "fill", &i32_params(&[65537, 0, 0, 0]));
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn memory_fill_out_of_bounds_lir() {
+	run_lir(&CompileContext::new_from_opt(1), FILL_MODULE, "fill", &i32_params(&[65534, 0, 4, 0]));
+}
@@ -0,0 +1,63 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{compile, find_func, func_cmds, i32_params, run_lir, run_ssa};
+
+/// A module with one page of memory and a maximum of three, exporting:
+///
+/// (func $grow (param $pages i32) (param $probe i32) (result i32)
+///   (i32.add
+///     (memory.grow (local.get $pages))
+///     (i32.load8_u (local.get $probe))))
+const GROW_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x07, 0x01, 0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Memory section
+	0x05, 0x04, 0x01, 0x01, 0x01, 0x03,
+	// Export section
+	0x07, 0x08, 0x01, 0x04, b'g', b'r', b'o', b'w', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0E, 0x01, 0x0C, 0x00,
+	0x20, 0x00, 0x40, 0x00,
+	0x20, 0x01, 0x2D, 0x00, 0x00,
+	0x6A,
+	0x0B,
+];
+
+#[test]
+fn memory_grow_matches() {
+	let cases = [
+		// pages, probe, expected
+		(0, 0, 1),
+		(1, 0, 1),
+		// The new pages can be accessed right away
+		(1, 65536, 1),
+		(2, 3 * 65536 - 1, 1),
+		// Growing past the maximum fails
+		(3, 0, -1),
+		// The page count is unsigned
+		(-1, 0, -1),
+		(i32::MIN, 0, -1),
+	];
+
+	let ctx = CompileContext::new_from_opt(1);
+	for (pages, probe, expected) in cases {
+		let params = i32_params(&[pages, probe]);
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, GROW_MODULE, "grow", &params), expected, "SSA, grow({pages}), probe {probe}");
+		assert_eq!(run_lir(&ctx, GROW_MODULE, "grow", &params), expected, "LIR, grow({pages}), probe {probe}");
+	}
+}
+
+#[test]
+fn memory_grow_checks_maximum() {
+	let funcs = func_cmds(&compile(GROW_MODULE, &["-O1"]));
+
+	assert!(funcs.iter().any(|(_, cmds)| cmds.iter().any(|cmd| cmd == "function wasmrunner:__memory_grow")));
+
+	let grow = find_func(&funcs, "wasmrunner:__memory_grow");
+	assert!(grow.contains(&"execute unless score %param0%0 reg matches 0..3 run scoreboard players set %return%0 reg -1".to_string()), "{grow:?}");
+}
@@ -0,0 +1,64 @@
+use wasmcraft::{wasm_file::WasmFile, ssa::interp::{SsaInterpreter, TypedValue}, lir::interp::LirInterpreter};
+
+mod test_common;
+use test_common::{call_datapack, context, i32_params, start_datapack};
+
+/// A module with one page of memory and a maximum of three, exporting:
+///
+/// (func $size (result i32)
+///   (memory.size))
+///
+/// (func $grow (param $pages i32) (result i32)
+///   (memory.grow (local.get $pages)))
+const SIZE_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0A, 0x02,
+	0x60, 0x00, 0x01, 0x7F,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x01,
+	// Memory section
+	0x05, 0x04, 0x01, 0x01, 0x01, 0x03,
+	// Export section
+	0x07, 0x0F, 0x02,
+	0x04, b's', b'i', b'z', b'e', 0x00, 0x00,
+	0x04, b'g', b'r', b'o', b'w', 0x00, 0x01,
+	// Code section
+	0x0A, 0x0D, 0x02,
+	0x04, 0x00, 0x3F, 0x00, 0x0B,
+	0x06, 0x00, 0x20, 0x00, 0x40, 0x00, 0x0B,
+];
+
+/// Calls made one after another, with the pages each should return
+const CALLS: &[(&str, &[i32], i32)] = &[
+	("size", &[], 1),
+	("grow", &[1], 1),
+	("size", &[], 2),
+	// Growing past the maximum fails and leaves the size alone
+	("grow", &[5], -1),
+	("size", &[], 2),
+];
+
+#[test]
+fn memory_size_follows_growth() {
+	let ctx = context(&["-O1"]);
+	let file = WasmFile::from(SIZE_MODULE);
+
+	let mut interp = SsaInterpreter::new(ctx.compute_ssa(&file));
+	for &(name, params, expected) in CALLS {
+		interp.call(file.find_func(name).unwrap(), i32_params(params));
+		assert_eq!(interp.run_until_halted(), vec![TypedValue::I32(expected)], "SSA, {name}{params:?}");
+	}
+
+	let mut interp = LirInterpreter::new(ctx.compute_lir(ctx.compute_ssa(&file)));
+	for &(name, params, expected) in CALLS {
+		interp.call(file.find_func(name).unwrap(), &i32_params(params));
+		assert_eq!(interp.run_until_halted(), vec![TypedValue::I32(expected)], "LIR, {name}{params:?}");
+	}
+
+	let mut interp = start_datapack(SIZE_MODULE, &["-O1"]);
+	for &(name, params, expected) in CALLS {
+		assert_eq!(call_datapack(&mut interp, name, &i32_params(params), false), TypedValue::I32(expected), "datapack, {name}{params:?}");
+	}
+}
@@ -0,0 +1,86 @@
+use wasmcraft::ssa::interp::TypedValue;
+
+mod test_common;
+use test_common::{call_datapack, context, i32_params, run_lir, run_ssa, start_datapack};
+
+/// (import "env" "memory_snapshot" (func $snapshot (param i32)))
+/// (import "env" "memory_restore" (func $restore (param i32)))
+/// (memory 1 2)
+/// (func (export "run") (param i32) (result i32)
+///   (i32.store (i32.const 16) (i32.const 111))
+///   (call $snapshot (local.get 0))
+///   (i32.store (i32.const 16) (i32.const 222))
+///   (call $restore (local.get 0))
+///   (i32.load (i32.const 16)))
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0B, 0x02,
+	0x60, 0x01, 0x7F, 0x00,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Import section
+	0x02, 0x2C, 0x02,
+	0x03, b'e', b'n', b'v',
+	0x0F, b'm', b'e', b'm', b'o', b'r', b'y', b'_', b's', b'n', b'a', b'p', b's', b'h', b'o', b't', 0x00, 0x00,
+	0x03, b'e', b'n', b'v',
+	0x0E, b'm', b'e', b'm', b'o', b'r', b'y', b'_', b'r', b'e', b's', b't', b'o', b'r', b'e', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Memory section
+	0x05, 0x04, 0x01, 0x01, 0x01, 0x02,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x02,
+	// Code section
+	0x0A, 0x21, 0x01,
+	0x1F, 0x00,
+	0x41, 0x10, 0x41, 0xEF, 0x00, 0x36, 0x02, 0x00,
+	0x20, 0x00, 0x10, 0x00,
+	0x41, 0x10, 0x41, 0xDE, 0x01, 0x36, 0x02, 0x00,
+	0x20, 0x00, 0x10, 0x01,
+	0x41, 0x10, 0x28, 0x02, 0x00,
+	0x0B,
+];
+
+/// Region 0 covers the stored word, region 1 doesn't, and there is no region 2
+const REGIONS: &[&str] = &["-O1", "--snapshot-region", "16:8", "--snapshot-region", "32:4"];
+
+const CASES: [(i32, i32); 3] = [(0, 111), (1, 222), (2, 222)];
+
+#[test]
+fn restore_in_interpreters() {
+	let ctx = context(REGIONS);
+	for (region, expected) in CASES {
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, MODULE, "run", &i32_params(&[region])), expected, "SSA, region {region}");
+		assert_eq!(run_lir(&ctx, MODULE, "run", &i32_params(&[region])), expected, "LIR, region {region}");
+	}
+}
+
+#[test]
+fn restore_in_datapack() {
+	let mut interp = start_datapack(MODULE, REGIONS);
+	for (region, expected) in CASES {
+		let actual = call_datapack(&mut interp, "run", &[TypedValue::I32(region)], false);
+		assert_eq!(actual, TypedValue::I32(expected), "region {region}");
+	}
+}
+
+#[test]
+#[should_panic(expected = "does not fit in memory")]
+fn region_past_maximum_memory() {
+	// The memory can't grow past 2 pages
+	run_ssa(&context(&["--snapshot-region", "131068:8"]), MODULE, "run", &i32_params(&[0]));
+}
+
+#[test]
+#[should_panic(expected = "goes past the end of the 32-bit address space")]
+fn region_past_address_space() {
+	context(&["--snapshot-region", "4294967292:8"]);
+}
+
+#[test]
+#[should_panic(expected = "is empty")]
+fn empty_region() {
+	context(&["--snapshot-region", "16:0"]);
+}
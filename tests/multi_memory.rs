@@ -0,0 +1,116 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{all_cmds, compile, i32_params, run_lir, run_ssa};
+
+/// A module with two memories of one page each, the first starting with the byte 1
+/// and the second starting with the byte 5, exporting:
+///
+/// (func $run (param $addr i32) (param $value i32) (param $probe i32) (result i32)
+///   (i32.store8 $b (local.get $addr) (local.get $value))
+///   (i32.add
+///     (i32.mul (i32.load8_u $a (local.get $probe)) (i32.const 100))
+///     (i32.load8_u $b (local.get $probe))))
+///
+/// (func $size (result i32)
+///   (i32.add
+///     (i32.add
+///       (i32.mul (memory.grow $b (i32.const 2)) (i32.const 100))
+///       (i32.mul (memory.size $a) (i32.const 10)))
+///     (memory.size $b)))
+const MULTI_MEMORY_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0C, 0x02,
+	0x60, 0x03, 0x7F, 0x7F, 0x7F, 0x01, 0x7F,
+	0x60, 0x00, 0x01, 0x7F,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x01,
+	// Memory section
+	0x05, 0x05, 0x02, 0x00, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x0E, 0x02,
+	0x03, b'r', b'u', b'n', 0x00, 0x00,
+	0x04, b's', b'i', b'z', b'e', 0x00, 0x01,
+	// Code section
+	0x0A, 0x30, 0x02,
+	0x1A, 0x00,
+	0x20, 0x00, 0x20, 0x01, 0x3A, 0x40, 0x01, 0x00,
+	0x20, 0x02, 0x2D, 0x00, 0x00, 0x41, 0xE4, 0x00, 0x6C,
+	0x20, 0x02, 0x2D, 0x40, 0x01, 0x00,
+	0x6A,
+	0x0B,
+	0x13, 0x00,
+	0x41, 0x02, 0x40, 0x01, 0x41, 0xE4, 0x00, 0x6C,
+	0x3F, 0x00, 0x41, 0x0A, 0x6C, 0x6A,
+	0x3F, 0x01, 0x6A,
+	0x0B,
+	// Data section
+	0x0B, 0x0E, 0x02,
+	0x00, 0x41, 0x00, 0x0B, 0x01, 0x01,
+	0x02, 0x01, 0x41, 0x00, 0x0B, 0x01, 0x05,
+];
+
+#[test]
+fn memories_are_separate() {
+	let ctx = CompileContext::new_from_opt(1);
+	let cases = [
+		// addr, value, probe, expected
+		(4, 7, 0, 105),
+		(4, 7, 4, 7),
+		// Storing to the second memory leaves the first one alone
+		(0, 9, 0, 109),
+		(65535, 3, 65535, 3),
+	];
+
+	for (addr, value, probe, expected) in cases {
+		let args = [addr, value, probe];
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, MULTI_MEMORY_MODULE, "run", &i32_params(&args)), expected, "SSA, run{args:?}");
+		assert_eq!(run_lir(&ctx, MULTI_MEMORY_MODULE, "run", &i32_params(&args)), expected, "LIR, run{args:?}");
+	}
+}
+
+#[test]
+fn memories_grow_separately() {
+	let ctx = CompileContext::new_from_opt(1);
+	let expected = vec![TypedValue::I32(113)];
+	assert_eq!(run_ssa(&ctx, MULTI_MEMORY_MODULE, "size", &i32_params(&[])), expected);
+	assert_eq!(run_lir(&ctx, MULTI_MEMORY_MODULE, "size", &i32_params(&[])), expected);
+}
+
+#[test]
+fn memory_grow_calls_its_own_memory() {
+	let cmds = all_cmds(&compile(MULTI_MEMORY_MODULE, &["-O1"]));
+	assert!(cmds.iter().any(|cmd| cmd == "function wasmrunner:__memory_1_grow"), "{cmds:?}");
+	assert!(!cmds.iter().any(|cmd| cmd == "function wasmrunner:__memory_grow"), "{cmds:?}");
+}
+
+/// A module with two memories of one page each, exporting:
+///
+/// (func $copy (param i32 i32 i32)
+///   (memory.copy $b $a (local.get 0) (local.get 1) (local.get 2)))
+const CROSS_COPY_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x07, 0x01,
+	0x60, 0x03, 0x7F, 0x7F, 0x7F, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Memory section
+	0x05, 0x05, 0x02, 0x00, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'c', b'o', b'p', b'y', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0E, 0x01,
+	0x0C, 0x00,
+	0x20, 0x00, 0x20, 0x01, 0x20, 0x02, 0xFC, 0x0A, 0x01, 0x00,
+	0x0B,
+];
+
+#[test]
+#[should_panic(expected = "memory.copy from memory 0 to memory 1 is not supported")]
+fn copies_between_memories_are_rejected() {
+	compile(CROSS_COPY_MODULE, &["-O1"]);
+}
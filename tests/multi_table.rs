@@ -0,0 +1,97 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{all_cmds, compile, i32_params, run_lir, run_ssa};
+
+/// A module with two tables, the first holding `$a $b` and the second holding `$b $a`, exporting:
+///
+/// (func $a (result i32) (i32.const 1))
+/// (func $b (result i32) (i32.const 2))
+///
+/// (func $run (param $first i32) (param $second i32) (result i32)
+///   (i32.add
+///     (i32.mul (call_indirect $first_table (result i32) (local.get $first)) (i32.const 10))
+///     (call_indirect $second_table (result i32) (local.get $second))))
+///
+/// (func $set (param $call i32) (result i32)
+///   (table.set $second_table (i32.const 1) (ref.func $b))
+///   (i32.add
+///     (i32.mul (call_indirect $first_table (result i32) (local.get $call)) (i32.const 10))
+///     (call_indirect $second_table (result i32) (local.get $call))))
+const MULTI_TABLE_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x10, 0x03,
+	0x60, 0x00, 0x01, 0x7F,
+	0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x05, 0x04, 0x00, 0x00, 0x01, 0x02,
+	// Table section
+	0x04, 0x07, 0x02, 0x70, 0x00, 0x02, 0x70, 0x00, 0x02,
+	// Export section
+	0x07, 0x0D, 0x02,
+	0x03, b'r', b'u', b'n', 0x00, 0x02,
+	0x03, b's', b'e', b't', 0x00, 0x03,
+	// Element section
+	0x09, 0x11, 0x02,
+	0x00, 0x41, 0x00, 0x0B, 0x02, 0x00, 0x01,
+	0x02, 0x01, 0x41, 0x00, 0x0B, 0x00, 0x02, 0x01, 0x00,
+	// Code section
+	0x0A, 0x33, 0x04,
+	0x04, 0x00, 0x41, 0x01, 0x0B,
+	0x04, 0x00, 0x41, 0x02, 0x0B,
+	0x10, 0x00,
+	0x20, 0x00, 0x11, 0x00, 0x00, 0x41, 0x0A, 0x6C,
+	0x20, 0x01, 0x11, 0x00, 0x01,
+	0x6A,
+	0x0B,
+	0x16, 0x00,
+	0x41, 0x01, 0xD2, 0x01, 0x26, 0x01,
+	0x20, 0x00, 0x11, 0x00, 0x00, 0x41, 0x0A, 0x6C,
+	0x20, 0x00, 0x11, 0x00, 0x01,
+	0x6A,
+	0x0B,
+];
+
+#[test]
+fn call_indirect_uses_its_table() {
+	let ctx = CompileContext::new_from_opt(1);
+	let cases = [
+		// first, second, expected
+		(0, 0, 12),
+		(0, 1, 11),
+		(1, 0, 22),
+		(1, 1, 21),
+	];
+
+	for (first, second, expected) in cases {
+		let args = [first, second];
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, MULTI_TABLE_MODULE, "run", &i32_params(&args)), expected, "SSA, run{args:?}");
+		assert_eq!(run_lir(&ctx, MULTI_TABLE_MODULE, "run", &i32_params(&args)), expected, "LIR, run{args:?}");
+	}
+}
+
+#[test]
+fn table_set_only_changes_its_table() {
+	let ctx = CompileContext::new_from_opt(1);
+	let cases = [
+		// call, expected
+		(0, 12),
+		(1, 22),
+	];
+
+	for (call, expected) in cases {
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, MULTI_TABLE_MODULE, "set", &i32_params(&[call])), expected, "SSA, set({call})");
+		assert_eq!(run_lir(&ctx, MULTI_TABLE_MODULE, "set", &i32_params(&[call])), expected, "LIR, set({call})");
+	}
+}
+
+#[test]
+fn table_set_writes_its_table() {
+	let cmds = all_cmds(&compile(MULTI_TABLE_MODULE, &["-O1"]));
+	assert!(cmds.iter().any(|cmd| cmd.contains(" run scoreboard players operation %%table_1_1 reg = ")), "{cmds:?}");
+	assert!(!cmds.iter().any(|cmd| cmd.contains(" run scoreboard players operation %%table_0_")), "{cmds:?}");
+}
@@ -0,0 +1,84 @@
+use wasmcraft::CompileContext;
+
+mod test_common;
+use test_common::{call_datapack, i32_params, run_lir, run_ssa, start_datapack};
+
+/// Branches inside branches, some of whose targets take parameters and some of which don't,
+/// so the branches that skip resetting `cond_taken` run right after ones that set it.
+///
+/// (func (export "classify") (param i32 i32) (result i32) (local i32)
+///   (if (local.get 0)
+///     (then
+///       (if (local.get 1)
+///         (then (local.set 2 (if (result i32) (i32.gt_s (local.get 1) (i32.const 5)) (then (i32.const 5)) (else (i32.const 1)))))
+///         (else (local.set 2 (i32.const 2)))))
+///     (else
+///       (local.set 2 (i32.const 4))
+///       (block
+///         (block (br_table 0 1 (local.get 1)))
+///         (local.set 2 (i32.const 3)))))
+///   (i32.add (local.get 2) (if (result i32) (local.get 1) (then (i32.const 10)) (else (i32.const 20)))))
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x07, 0x01,
+	0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x0C, 0x01,
+	0x08, b'c', b'l', b'a', b's', b's', b'i', b'f', b'y', 0x00, 0x00,
+	// Code section
+	0x0A, 0x46, 0x01,
+	0x44, 0x01, 0x01, 0x7F,
+	0x20, 0x00, 0x04, 0x40,
+	0x20, 0x01, 0x04, 0x40,
+	0x20, 0x01, 0x41, 0x05, 0x4A, 0x04, 0x7F, 0x41, 0x05, 0x05, 0x41, 0x01, 0x0B, 0x21, 0x02,
+	0x05,
+	0x41, 0x02, 0x21, 0x02,
+	0x0B,
+	0x05,
+	0x41, 0x04, 0x21, 0x02,
+	0x02, 0x40,
+	0x02, 0x40, 0x20, 0x01, 0x0E, 0x01, 0x00, 0x01, 0x0B,
+	0x41, 0x03, 0x21, 0x02,
+	0x0B,
+	0x0B,
+	0x20, 0x02,
+	0x20, 0x01, 0x04, 0x7F, 0x41, 0x0A, 0x05, 0x41, 0x14, 0x0B,
+	0x6A,
+	0x0B,
+];
+
+/// The parameters of each call checked, and what `classify` returns for them
+const CASES: &[(i32, i32, i32)] = &[
+	(1, 7, 15),
+	(1, 3, 11),
+	(1, 0, 22),
+	(0, 0, 23),
+	(0, 1, 14),
+	(0, -1, 14),
+	(-1, 6, 15),
+];
+
+#[test]
+fn nested_branches_interpreters() {
+	let ctx = CompileContext::new_from_opt(1);
+	for &(a, b, expected) in CASES {
+		let params = i32_params(&[a, b]);
+		assert_eq!(run_ssa(&ctx, MODULE, "classify", &params), i32_params(&[expected]), "SSA, classify({a}, {b})");
+		assert_eq!(run_lir(&ctx, MODULE, "classify", &params), i32_params(&[expected]), "LIR, classify({a}, {b})");
+	}
+}
+
+#[test]
+fn nested_branches_datapack() {
+	for args in [&["-O0"][..], &["-O1"], &["-O1", "--jump-mode", "bounce"]] {
+		let mut interp = start_datapack(MODULE, args);
+		// Calls that take different paths back to back, so a stale `cond_taken` from one would show up in the next
+		for &(a, b, expected) in CASES {
+			let actual = call_datapack(&mut interp, "classify", &i32_params(&[a, b]), false);
+			assert_eq!(actual, i32_params(&[expected])[0], "{args:?}, classify({a}, {b})");
+		}
+	}
+}
@@ -0,0 +1,69 @@
+use wasmcraft::ssa::{interp::TypedValue, TrapKind};
+
+mod test_common;
+use test_common::{all_cmds, call_datapack, compile, get_trap, i32_params, start_datapack};
+
+/// (func (export "add") (param i32 i32) (result i32)
+///   (i32.add (local.get 0) (local.get 1)))
+///
+/// and likewise `sub` and `mul`
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x07, 0x01, 0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x04, 0x03, 0x00, 0x00, 0x00,
+	// Export section
+	0x07, 0x13, 0x03,
+	0x03, b'a', b'd', b'd', 0x00, 0x00,
+	0x03, b's', b'u', b'b', 0x00, 0x01,
+	0x03, b'm', b'u', b'l', 0x00, 0x02,
+	// Code section
+	0x0A, 0x19, 0x03,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6A, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6B, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6C, 0x0B,
+];
+
+#[test]
+fn overflow_traps() {
+	let mut interp = start_datapack(MODULE, &["--overflow-checks"]);
+
+	let cases = [
+		// name, lhs, rhs, result if it doesn't overflow
+		("add", 1, 2, Some(3)),
+		("add", -5, -7, Some(-12)),
+		("add", i32::MAX, 1, None),
+		("add", i32::MIN, -1, None),
+		("sub", 5, 7, Some(-2)),
+		("sub", i32::MIN, 1, None),
+		("sub", 0, i32::MIN, None),
+		("mul", 46340, 46340, Some(2147395600)),
+		("mul", -3, 7, Some(-21)),
+		("mul", 0, i32::MIN, Some(0)),
+		("mul", 65536, 65536, None),
+		("mul", i32::MIN, -1, None),
+		("mul", -1, i32::MIN, None),
+	];
+
+	for (name, lhs, rhs, expected) in cases {
+		let actual = call_datapack(&mut interp, name, &i32_params(&[lhs, rhs]), false);
+		match expected {
+			Some(expected) => {
+				assert_eq!(get_trap(&mut interp), Some(0), "{name}({lhs}, {rhs})");
+				assert_eq!(actual, TypedValue::I32(expected), "{name}({lhs}, {rhs})");
+			}
+			None => assert_eq!(get_trap(&mut interp), Some(TrapKind::IntegerOverflow.code()), "{name}({lhs}, {rhs})"),
+		}
+	}
+}
+
+#[test]
+fn trap_gives_code_offsets() {
+	let cmds = all_cmds(&compile(MODULE, &["--overflow-checks"]));
+	let is_mul_trap = |cmd: &String| cmd.contains(r#"{"text":"Trap: integer overflow in i32.mul (wasm function 2, block "#) && cmd.contains(", code offsets 0x");
+	assert!(cmds.iter().any(is_mul_trap), "{cmds:?}");
+
+	let unchecked = all_cmds(&compile(MODULE, &[]));
+	assert!(!unchecked.iter().any(|cmd| cmd.contains("integer overflow")));
+}
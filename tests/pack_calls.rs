@@ -0,0 +1,108 @@
+use clap::Parser;
+use wasmcraft::{Args, CompileContext, wasm_file::WasmFile, abi_doc::{self, AbiDocFormat}, pack_emitter::PackBackend};
+
+mod test_common;
+use test_common::compile_with;
+
+/// A module calling an export of another datapack:
+///
+/// (import "mathlib" "add" (func $add (param i32 i32) (result i32)))
+///
+/// (func $run (param i32) (result i32)
+///   (call $add (local.get 0) (i32.const 1)))
+const CALLER_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0C, 0x02,
+	0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Import section
+	0x02, 0x0F, 0x01,
+	0x07, b'm', b'a', b't', b'h', b'l', b'i', b'b', 0x03, b'a', b'd', b'd', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x01,
+	// Code section
+	0x0A, 0x0A, 0x01,
+	0x08, 0x00, 0x20, 0x00, 0x41, 0x01, 0x10, 0x00, 0x0B,
+];
+
+fn context(extra_args: &[&str]) -> CompileContext {
+	test_common::context(&[&["-O1", "--import-pack", "mathlib=mathlib"], extra_args].concat())
+}
+
+/// The written files of the datapack, with each function's contents split into lines.
+fn compile(extra_args: &[&str]) -> Vec<(String, Vec<String>)> {
+	let ctx = context(extra_args);
+	let datapack = compile_with(&ctx, CALLER_MODULE);
+
+	let (files, _) = ctx.datapack().files(&datapack);
+	files.into_iter().map(|(path, contents)| (path, contents.lines().map(str::to_string).collect())).collect()
+}
+
+fn find_file<'a>(files: &'a [(String, Vec<String>)], path: &str) -> &'a [String] {
+	&files.iter().find(|(name, _)| name == path).unwrap_or_else(|| panic!("missing {path}")).1
+}
+
+#[test]
+fn imports_call_stub_of_other_datapack() {
+	let files = compile(&[]);
+
+	let stub = find_file(&files, "data/wasmrunner/functions/__pack_call/mathlib/add.mcfunction");
+	assert!(stub.iter().any(|cmd| cmd == "scoreboard players operation arg0 wasm_call = %param%0%lo reg"), "{stub:?}");
+	assert!(stub.iter().any(|cmd| cmd == "scoreboard players operation arg1 wasm_call = %param%1%lo reg"), "{stub:?}");
+	assert!(stub.iter().any(|cmd| cmd == "function mathlib:call/add"), "{stub:?}");
+	assert_eq!(stub.last().unwrap(), "scoreboard players operation %return%0%lo reg = ret0 wasm_call");
+
+	// Only the stub names the other datapack's functions
+	let callers = files.iter().filter(|(_, lines)| lines.iter().any(|cmd| cmd.ends_with("function wasmrunner:__pack_call/mathlib/add"))).count();
+	assert!(callers > 0);
+	let direct = files.iter().filter(|(_, lines)| lines.iter().any(|cmd| cmd.contains("mathlib:call/add"))).count();
+	assert_eq!(direct, 1);
+}
+
+#[test]
+fn namespace_moves_functions_storage_and_objectives() {
+	let files = compile(&["--namespace", "app"]);
+
+	assert!(files.iter().all(|(path, _)| !path.starts_with("data/wasmrunner/") && !path.starts_with("data/intrinsic/")));
+	assert!(files.iter().any(|(path, _)| path.starts_with("data/app_intrinsic/functions/")));
+
+	let init = find_file(&files, "data/app/functions/init.mcfunction");
+	assert!(init.iter().any(|cmd| cmd == "scoreboard objectives add app dummy"), "{init:?}");
+	assert!(init.iter().any(|cmd| cmd == "scoreboard objectives add wasm_call dummy"), "{init:?}");
+	assert!(init.iter().any(|cmd| cmd.starts_with("data modify storage app:datastack ")), "{init:?}");
+	assert!(init.iter().all(|cmd| !cmd.contains(" reg") && !cmd.contains("wasm:") && !cmd.contains("wasmrunner:")), "{init:?}");
+
+	// Other datapacks call the exports the same way, under the new namespace
+	let call = find_file(&files, "data/app/functions/call/run.mcfunction");
+	assert!(call.iter().any(|cmd| cmd == "scoreboard players operation %param%0%lo app = arg0 wasm_call"), "{call:?}");
+	assert!(call.iter().any(|cmd| cmd == "function app:run"), "{call:?}");
+
+	let stub = find_file(&files, "data/app/functions/__pack_call/mathlib/add.mcfunction");
+	assert!(stub.iter().any(|cmd| cmd == "function mathlib:call/add"), "{stub:?}");
+}
+
+#[test]
+fn abi_doc_uses_namespace_and_lists_pack_calls() {
+	let ctx = context(&["--namespace", "app"]);
+	let file = WasmFile::from(CALLER_MODULE);
+	let program = ctx.compute_ssa(&file);
+
+	let doc = abi_doc::generate(&ctx, &file, &program, AbiDocFormat::Markdown);
+	assert!(doc.contains("Call function: `app:call/run`"), "{doc}");
+	assert!(doc.contains("- `mathlib.add` (i32, i32) -> (i32), calls `mathlib:call/add` in another datapack"), "{doc}");
+	assert!(doc.contains("scores in the `app` objective"), "{doc}");
+
+	let doc = abi_doc::generate(&ctx, &file, &program, AbiDocFormat::Json);
+	assert!(doc.contains(r#""pack_call":"mathlib:call/add""#), "{doc}");
+}
+
+#[test]
+fn called_datapacks_need_own_namespace() {
+	assert!(Args::try_parse_from(["wasmcraft", "caller.wasm", "-o", "out", "--import-pack", "mathlib=wasmrunner"]).is_err());
+	assert!(Args::try_parse_from(["wasmcraft", "caller.wasm", "-o", "out", "--namespace", "wasm"]).is_err());
+	assert!(Args::try_parse_from(["wasmcraft", "caller.wasm", "-o", "out", "--namespace", "My-Pack"]).is_err());
+}
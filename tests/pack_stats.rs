@@ -0,0 +1,71 @@
+use wasmcraft::{wasm_file::WasmFile, mc_version::McVersion, pack_stats};
+
+mod test_common;
+use test_common::context;
+
+/// A module exporting:
+///
+/// (func $calc (param i32) (result i32)
+///   (i32.mul
+///     (i32.add (local.get 0) (i32.const 12345))
+///     (i32.const 7)))
+const CALC_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'c', b'a', b'l', b'c', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0E, 0x01,
+	0x0C, 0x00,
+	0x20, 0x00, 0x41, 0xB9, 0xE0, 0x00, 0x6A,
+	0x41, 0x07, 0x6C,
+	0x0B,
+];
+
+fn compute() -> pack_stats::PackStats {
+	let ctx = context(&["-O1"]);
+	let file = WasmFile::from(CALC_MODULE);
+	let lir_program = ctx.compute_lir(ctx.compute_ssa(&file));
+	let datapack = ctx.compute_datapack(&lir_program);
+	pack_stats::compute(&datapack, &lir_program.func_names, McVersion::new(19, 4))
+}
+
+#[test]
+fn commands_are_counted_per_function() {
+	let stats = compute();
+
+	assert!(stats.functions.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+	assert!(stats.functions.iter().any(|(id, _)| id == "wasmrunner:init"));
+
+	let calc = stats.wasm_functions.iter().find(|func| func.index == 0).unwrap();
+	assert!(calc.blocks >= 1);
+	let calc_commands = stats.functions.iter()
+		.filter(|(id, _)| id.starts_with("wasmrunner:wasm_0_"))
+		.map(|(_, commands)| commands)
+		.sum::<usize>();
+	assert_eq!(calc.commands, calc_commands);
+}
+
+#[test]
+fn score_holders_are_counted_per_objective() {
+	let stats = compute();
+
+	assert!(stats.objectives["reg"].contains("%%max_commands"), "{:?}", stats.objectives["reg"]);
+	assert!(stats.objectives["wasm_call"].contains("arg0"), "{:?}", stats.objectives["wasm_call"]);
+	assert!(stats.objectives["wasm_call"].contains("ret0"), "{:?}", stats.objectives["wasm_call"]);
+	assert!(!stats.objectives["reg"].contains("add"));
+}
+
+#[test]
+fn json_lists_every_function() {
+	let stats = compute();
+	let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+
+	assert_eq!(json["functions"].as_array().unwrap().len(), stats.functions.len());
+	assert_eq!(json["commands"], stats.total_commands());
+}
@@ -0,0 +1,96 @@
+use clap::Parser;
+use wasmcraft::{Args, CompileContext, JumpMode, cost_model::CostModel, lir::LirTerminator, pack_emitter::{PackBackend, estimate_term_cost}, ssa::BlockId};
+
+mod test_common;
+use test_common::compile_with;
+
+/// (func (export "run") (param i32) (result i32)
+///   (local.get 0))
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x00,
+	// Code section
+	0x0A, 0x06, 0x01,
+	0x04, 0x00, 0x20, 0x00, 0x0B,
+];
+
+fn args(extra_args: &[&str]) -> impl Iterator<Item=&str> {
+	["wasmcraft", "run.wasm", "-o", "out", "-O1", "--persist-state"].into_iter().chain(extra_args.iter().copied())
+}
+
+/// The written files of the datapack, with each function's contents split into lines.
+fn compile(extra_args: &[&str]) -> Vec<(String, Vec<String>)> {
+	let ctx = CompileContext::new(Args::parse_from(args(extra_args)));
+	let datapack = compile_with(&ctx, MODULE);
+
+	let (files, _) = ctx.datapack().files(&datapack);
+	files.into_iter().map(|(path, contents)| (path, contents.lines().map(str::to_string).collect())).collect()
+}
+
+fn find_file<'a>(files: &'a [(String, Vec<String>)], path: &str) -> &'a [String] {
+	&files.iter().find(|(name, _)| name == path).unwrap_or_else(|| panic!("missing {path}")).1
+}
+
+#[test]
+fn yields_checkpoint_scores_and_loading_restores_them() {
+	let files = compile(&["--jump-mode", "bounce"]);
+
+	let bounce = find_file(&files, "data/wasmrunner/functions/__bounce.mcfunction");
+	assert!(bounce.iter().any(|cmd| cmd == "execute if score %sleepneeded reg matches 1 run function wasmrunner:__checkpoint"), "{bounce:?}");
+
+	// The block a paused program continues at is one of the checkpointed scores
+	let checkpoint = find_file(&files, "data/wasmrunner/functions/__checkpoint.mcfunction");
+	assert!(checkpoint.iter().any(|cmd| cmd == "execute store result storage wasm:checkpoint scores.%%bounce_resume int 1 run scoreboard players get %%bounce_resume reg"), "{checkpoint:?}");
+	assert!(checkpoint.iter().all(|cmd| !cmd.contains("%const%")), "{checkpoint:?}");
+
+	let restore = find_file(&files, "data/wasmrunner/functions/__restore_scores.mcfunction");
+	assert!(restore.iter().any(|cmd| cmd == "execute store result score %%bounce_resume reg run data get storage wasm:checkpoint scores.%%bounce_resume"), "{restore:?}");
+	// Every checkpointed score is restored
+	let saved = checkpoint.iter().filter(|cmd| cmd.starts_with("execute store result storage wasm:checkpoint scores.")).count();
+	let loaded = restore.iter().filter(|cmd| cmd.starts_with("execute store result score ")).count();
+	assert_eq!(saved, loaded);
+
+	// Continuing the program removes the checkpoint, so there only is one while it's paused
+	let resume = find_file(&files, "data/wasmrunner/functions/__bounce_resume.mcfunction");
+	assert!(resume.iter().any(|cmd| cmd == "data remove storage wasm:checkpoint scores"), "{resume:?}");
+
+	let load = find_file(&files, "data/minecraft/tags/functions/load.json");
+	assert_eq!(load, [r#"{"values":["wasmrunner:__restore_state"]}"#]);
+}
+
+#[test]
+fn checkpoint_holds_program_registers() {
+	let files = compile(&["--jump-mode", "bounce"]);
+
+	let checkpoint = find_file(&files, "data/wasmrunner/functions/__checkpoint.mcfunction");
+	for holder in ["%param%0%lo", "%return%0%lo", "%%call_depth"] {
+		let cmd = format!("execute store result storage wasm:checkpoint scores.{holder} int 1 run scoreboard players get {holder} reg");
+		assert!(checkpoint.contains(&cmd), "{holder} in {checkpoint:?}");
+	}
+	// The temporaries of the intrinsics never live across a yield, and restoring sets the return addresses up again
+	assert!(checkpoint.iter().all(|cmd| !cmd.contains("scores.%ptr ") && !cmd.contains("%%returnaddr")), "{checkpoint:?}");
+
+	let restore = find_file(&files, "data/wasmrunner/functions/__restore_scores.mcfunction");
+	assert!(restore.iter().any(|cmd| cmd.starts_with("scoreboard players set %%returnaddr_wasm_")), "{restore:?}");
+}
+
+#[test]
+fn checkpoints_count_towards_yield_cost() {
+	let term = LirTerminator::ScheduleJump(BlockId { func: 0, block: 1 }, 1);
+	let cost_model = CostModel::default();
+	let without = estimate_term_cost(&term, JumpMode::Bounce, None, &cost_model);
+	let with = estimate_term_cost(&term, JumpMode::Bounce, Some(100), &cost_model);
+	assert!(with >= without + 100, "{without} without checkpoints, {with} with");
+}
+
+#[test]
+#[should_panic(expected = "--persist-state needs --jump-mode bounce")]
+fn persisting_state_needs_bounce_mode() {
+	CompileContext::new(Args::parse_from(args(&[])));
+}
@@ -0,0 +1,45 @@
+mod test_common;
+use test_common::{compile, find_func, func_cmds};
+
+/// A module exporting:
+///
+/// (func $on_player_use (param $player i32))
+///
+/// (func $on_player_join (param $player i32))
+const PLAYER_EVENTS_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x05, 0x01,
+	0x60, 0x01, 0x7F, 0x00,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x00,
+	// Export section
+	0x07, 0x22, 0x02,
+	0x0D, b'o', b'n', b'_', b'p', b'l', b'a', b'y', b'e', b'r', b'_', b'u', b's', b'e', 0x00, 0x00,
+	0x0E, b'o', b'n', b'_', b'p', b'l', b'a', b'y', b'e', b'r', b'_', b'j', b'o', b'i', b'n', 0x00, 0x01,
+	// Code section
+	0x0A, 0x07, 0x02,
+	0x02, 0x00, 0x0B,
+	0x02, 0x00, 0x0B,
+];
+
+#[test]
+fn player_event_exports_are_called() {
+	let funcs = func_cmds(&compile(PLAYER_EVENTS_MODULE, &["-O1"]));
+
+	let events = find_func(&funcs, "wasmrunner:__player_events");
+	assert!(events.iter().any(|cmd| cmd.ends_with("run function wasmrunner:__on_player_use")), "{events:?}");
+	assert!(events.iter().any(|cmd| cmd.ends_with("run function wasmrunner:__on_player_join")), "{events:?}");
+	assert!(!events.iter().any(|cmd| cmd.contains("on_player_jump")), "{events:?}");
+
+	let use_event = find_func(&funcs, "wasmrunner:__on_player_use");
+	assert!(use_event.iter().any(|cmd| cmd.ends_with("= @s wasm_player_id")), "{use_event:?}");
+	assert_eq!(use_event.last().unwrap(), "function wasmrunner:on_player_use");
+
+	// New players count as joining
+	let new_player = find_func(&funcs, "wasmrunner:__new_player");
+	assert!(new_player.iter().any(|cmd| cmd == "scoreboard players set @s wasm_join 1"), "{new_player:?}");
+
+	let init = find_func(&funcs, "wasmrunner:init");
+	assert!(init.iter().any(|cmd| cmd == "schedule function wasmrunner:__player_events 1"), "{init:?}");
+}
@@ -0,0 +1,47 @@
+use wasmcraft::ssa::format_float;
+
+#[test]
+fn fixed_point() {
+	let cases = [
+		(0.0, "0.000000"),
+		(-0.0, "-0.000000"),
+		(1.5, "1.500000"),
+		(-42.25, "-42.250000"),
+		// Rounded towards zero
+		(0.1, "0.100000"),
+		(2.0 / 3.0, "0.666666"),
+		(1e-4, "0.000100"),
+		(123456789012.0, "123456789012.000000"),
+	];
+
+	for (value, expected) in cases {
+		assert_eq!(format_float(value), expected, "{value}");
+	}
+}
+
+#[test]
+fn scientific_notation() {
+	let cases = [
+		(1.5e20, "1.500000e20"),
+		(f64::MAX, "1.797693e308"),
+		(-1e-5, "-1.000000e-5"),
+		(f64::from_bits(1), "4.940656e-324"),
+	];
+
+	for (value, expected) in cases {
+		assert_eq!(format_float(value), expected, "{value}");
+	}
+}
+
+#[test]
+fn floats_are_widened_exactly() {
+	assert_eq!(format_float(0.1f32 as f64), "0.100000");
+	assert_eq!(format_float(f32::from_bits(1) as f64), "1.401298e-45");
+}
+
+#[test]
+fn special_values() {
+	assert_eq!(format_float(f64::INFINITY), "inf");
+	assert_eq!(format_float(f64::NEG_INFINITY), "-inf");
+	assert_eq!(format_float(f64::NAN), "nan");
+}
@@ -0,0 +1,42 @@
+use wasmcraft::ssa::Memory;
+
+/// Formats `fmt` with the given argument words, with any strings the arguments point to placed at 256.
+fn format(fmt: &str, args: &[u32], strings: &[u8]) -> String {
+	let mut memory = Memory::new(1, None);
+	memory.store(0, fmt.as_bytes());
+	for (idx, arg) in args.iter().enumerate() {
+		memory.store(128 + 4 * idx, &arg.to_le_bytes());
+	}
+	memory.store(256, strings);
+
+	String::from_utf8(memory.print_fmt(0, 128)).unwrap()
+}
+
+#[test]
+fn integer_conversions() {
+	let cases: [(&str, &[u32], &str); 7] = [
+		("%d", &[42], "42"),
+		("%d", &[-7_i32 as u32], "-7"),
+		("%d", &[i32::MIN as u32], "-2147483648"),
+		("%u", &[u32::MAX], "4294967295"),
+		("%x", &[0xDEAD_BEEF], "deadbeef"),
+		("%x %u", &[0, 0], "0 0"),
+		("a=%d, b=%u%%", &[1, 2], "a=1, b=2%"),
+	];
+
+	for (fmt, args, expected) in cases {
+		assert_eq!(format(fmt, args, &[]), expected, "{fmt} {args:?}");
+	}
+}
+
+#[test]
+fn character_and_string_conversions() {
+	assert_eq!(format("<%c>", &[b'x' as u32], &[]), "<x>");
+	assert_eq!(format("hello, %s!", &[256], b"world\0ignored"), "hello, world!");
+}
+
+#[test]
+fn unsupported_conversions_are_printed_as_is() {
+	assert_eq!(format("%f %d", &[5], &[]), "%f 5");
+	assert_eq!(format("trailing %", &[], &[]), "trailing ");
+}
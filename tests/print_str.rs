@@ -0,0 +1,47 @@
+mod test_common;
+use test_common::{all_cmds, compile, context, run_lir, run_ssa};
+use wasmcraft::ssa::interp::TypedValue;
+
+/// A module with one page of memory holding the string `hi` at 16, exporting:
+///
+/// (import "env" "print_str" (func $print_str (param i32 i32)))
+///
+/// (func $run (result i32)
+///   (call $print_str (i32.const 16) (i32.const 2))
+///   (i32.const 7))
+const PRINT_STR_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0A, 0x02,
+	0x60, 0x02, 0x7F, 0x7F, 0x00,
+	0x60, 0x00, 0x01, 0x7F,
+	// Import section
+	0x02, 0x11, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x09, b'p', b'r', b'i', b'n', b't', b'_', b's', b't', b'r', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x01,
+	// Code section
+	0x0A, 0x0C, 0x01,
+	0x0A, 0x00, 0x41, 0x10, 0x41, 0x02, 0x10, 0x00, 0x41, 0x07, 0x0B,
+	// Data section
+	0x0B, 0x08, 0x01, 0x00, 0x41, 0x10, 0x0B, 0x02, b'h', b'i',
+];
+
+#[test]
+fn print_str_runs() {
+	let ctx = context(&["-O1"]);
+	assert_eq!(run_ssa(&ctx, PRINT_STR_MODULE, "run", &[]), vec![TypedValue::I32(7)], "SSA");
+	assert_eq!(run_lir(&ctx, PRINT_STR_MODULE, "run", &[]), vec![TypedValue::I32(7)], "LIR");
+}
+
+#[test]
+fn print_str_uses_the_runtime_routine() {
+	let cmds = all_cmds(&compile(PRINT_STR_MODULE, &["-O1"]));
+	assert!(cmds.iter().any(|cmd| cmd == "function intrinsic:print_str"), "{cmds:?}");
+}
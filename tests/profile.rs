@@ -0,0 +1,57 @@
+use datapack_vm::Interpreter;
+use wasmcraft::{lir::Register, pack_emitter};
+use wasmcraft::ssa::BlockId;
+
+mod test_common;
+use test_common::{compile, get_reg, get_var, run_function, set_reg};
+
+/// (func $double (param i32) (result i32)
+///   (i32.mul (local.get 0) (i32.const 2)))
+///
+/// (func (export "run") (param i32) (result i32)
+///   (call $double (call $double (local.get 0))))
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x00,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x01,
+	// Code section
+	0x0A, 0x12, 0x02,
+	0x07, 0x00, 0x20, 0x00, 0x41, 0x02, 0x6C, 0x0B,
+	0x08, 0x00, 0x20, 0x00, 0x10, 0x00, 0x10, 0x00, 0x0B,
+];
+
+#[test]
+fn counts_calls_to_each_function() {
+	let datapack = compile(MODULE, &["--profile"]);
+
+	let dump = datapack.iter().find(|func| func.id.to_string() == "wasmrunner:__profile_dump_round").unwrap();
+	assert!(dump.cmds.iter().any(|cmd| cmd.to_string().contains(r#"{"text":"wasmrunner:wasm_0_0: "}"#)));
+
+	let mut interp = Interpreter::new(datapack, 0);
+	run_function(&mut interp, "wasmrunner:init");
+	for _ in 0..3 {
+		set_reg(&mut interp, Register::param_lo(0), 5);
+		run_function(&mut interp, "wasmrunner:run");
+	}
+
+	assert_eq!(get_reg(&interp, Register::return_lo(0)), Some(20));
+
+	assert_eq!(get_var(&interp, &pack_emitter::get_profile_var(BlockId { func: 1, block: 0 })), Some(3));
+	assert_eq!(get_var(&interp, &pack_emitter::get_profile_var(BlockId { func: 0, block: 0 })), Some(6));
+
+	run_function(&mut interp, "wasmrunner:profile_reset");
+	assert_eq!(get_var(&interp, &pack_emitter::get_profile_var(BlockId { func: 0, block: 0 })), Some(0));
+}
+
+#[test]
+fn only_counts_when_profiling() {
+	let datapack = compile(MODULE, &[]);
+
+	assert!(datapack.iter().all(|func| !func.id.to_string().contains("profile")));
+	assert!(datapack.iter().flat_map(|func| func.cmds.iter()).all(|cmd| !cmd.to_string().contains("%profile%")));
+}
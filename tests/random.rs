@@ -0,0 +1,52 @@
+mod test_common;
+use test_common::{all_cmds, compile, context, run_ssa, ssa_instrs};
+use wasmcraft::ssa::{SsaInstr, interp::TypedValue};
+
+/// A module exporting:
+///
+/// (import "env" "mc_random" (func $mc_random (param i32) (result i32)))
+///
+/// (func $roll (result i32)
+///   (i32.add (call $mc_random (i32.const 6)) (i32.const 1)))
+const RANDOM_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0A, 0x02,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	0x60, 0x00, 0x01, 0x7F,
+	// Import section
+	0x02, 0x11, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x09, b'm', b'c', b'_', b'r', b'a', b'n', b'd', b'o', b'm', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'r', b'o', b'l', b'l', 0x00, 0x01,
+	// Code section
+	0x0A, 0x0B, 0x01,
+	0x09, 0x00, 0x41, 0x06, 0x10, 0x00, 0x41, 0x01, 0x6A, 0x0B,
+];
+
+#[test]
+fn random_is_lowered() {
+	let ctx = context(&["-O1"]);
+	let count = ssa_instrs(&ctx, RANDOM_MODULE).iter()
+		.filter(|instr| matches!(instr, SsaInstr::Random { .. }))
+		.count();
+	assert_eq!(count, 1);
+
+	// The interpreter always rolls the lowest number
+	assert_eq!(run_ssa(&ctx, RANDOM_MODULE, "roll", &[]), vec![TypedValue::I32(1)]);
+}
+
+#[test]
+fn random_is_seeded_when_used() {
+	let datapack = compile(RANDOM_MODULE, &["-O1"]);
+
+	let cmds = all_cmds(&datapack);
+	assert!(cmds.iter().any(|cmd| cmd == "function intrinsic:random"), "{cmds:?}");
+
+	let init = datapack.iter().find(|func| func.id.to_string() == "wasmrunner:init").unwrap();
+	assert!(init.cmds.iter().any(|cmd| cmd.to_string().starts_with("execute store result score %%rng_state reg")));
+}
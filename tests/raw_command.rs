@@ -0,0 +1,97 @@
+mod test_common;
+use test_common::{context, run_ssa, ssa_instrs};
+use wasmcraft::ssa::{SsaInstr, interp::TypedValue};
+
+/// A module with one page of memory holding the string `/tellraw @a "hi"` at 16, exporting:
+///
+/// (import "env" "mc_command" (func $mc_command (param i32 i32)))
+///
+/// (func $run
+///   (call $mc_command (i32.const 16) (i32.const 16)))
+const RAW_COMMAND_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x09, 0x02,
+	0x60, 0x02, 0x7F, 0x7F, 0x00,
+	0x60, 0x00, 0x00,
+	// Import section
+	0x02, 0x12, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x0A, b'm', b'c', b'_', b'c', b'o', b'm', b'm', b'a', b'n', b'd', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x01,
+	// Code section
+	0x0A, 0x0A, 0x01,
+	0x08, 0x00, 0x41, 0x10, 0x41, 0x10, 0x10, 0x00, 0x0B,
+	// Data section
+	0x0B, 0x17, 0x01, 0x00, 0x41, 0x10, 0x0B, 0x11,
+	b'/', b't', b'e', b'l', b'l', b'r', b'a', b'w', b' ', b'@', b'a', b' ', b'"', b'h', b'i', b'"', 0x00,
+];
+
+/// A module with one page of memory holding the string `time query daytime` at 16, exporting:
+///
+/// (import "env" "mc_command_success" (func $mc_command_success (param i32 i32) (result i32)))
+///
+/// (func $run (result i32)
+///   (call $mc_command_success (i32.const 16) (i32.const 18)))
+const COMMAND_SUCCESS_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0B, 0x02,
+	0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	0x60, 0x00, 0x01, 0x7F,
+	// Import section
+	0x02, 0x1A, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x12, b'm', b'c', b'_', b'c', b'o', b'm', b'm', b'a', b'n', b'd', b'_', b's', b'u', b'c', b'c', b'e', b's', b's', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x01,
+	// Code section
+	0x0A, 0x0A, 0x01,
+	0x08, 0x00, 0x41, 0x10, 0x41, 0x12, 0x10, 0x00, 0x0B,
+	// Data section
+	0x0B, 0x18, 0x01, 0x00, 0x41, 0x10, 0x0B, 0x12,
+	b't', b'i', b'm', b'e', b' ', b'q', b'u', b'e', b'r', b'y', b' ', b'd', b'a', b'y', b't', b'i', b'm', b'e',
+];
+
+#[test]
+fn constant_command_is_copied() {
+	for opt in [0, 1] {
+		let ctx = context(&[&format!("-O{opt}")]);
+		let instrs = ssa_instrs(&ctx, RAW_COMMAND_MODULE);
+		let cmds = instrs.iter()
+			.filter_map(|instr| match instr {
+				SsaInstr::RawCommand(cmd) => Some(cmd.as_str()),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+
+		assert_eq!(cmds, ["tellraw @a \"hi\""], "-O{opt}");
+	}
+}
+
+#[test]
+fn command_success_is_stored() {
+	let ctx = context(&["-O1"]);
+	let instrs = ssa_instrs(&ctx, COMMAND_SUCCESS_MODULE);
+	let stores = instrs.iter()
+		.filter_map(|instr| match instr {
+			SsaInstr::RawCommandStore { cmd, success, .. } => Some((cmd.as_str(), *success)),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(stores, [("time query daytime", true)]);
+
+	// There is no game to run the command in, so the interpreter treats it as failing
+	assert_eq!(run_ssa(&ctx, COMMAND_SUCCESS_MODULE, "run", &[]), vec![TypedValue::I32(0)]);
+}
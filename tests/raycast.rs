@@ -0,0 +1,44 @@
+mod test_common;
+use test_common::{context, run_ssa, ssa_instrs};
+use wasmcraft::ssa::{SsaInstr, interp::TypedValue};
+
+/// A module exporting:
+///
+/// (import "env" "turtle_face" (func $turtle_face (param i32 i32)))
+/// (import "env" "turtle_raycast" (func $turtle_raycast (param i32) (result i32)))
+///
+/// (func $look (param $dist i32) (result i32)
+///   (call $turtle_face (i32.const 90) (i32.const 0))
+///   (call $turtle_raycast (local.get $dist)))
+const RAYCAST_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0B, 0x02,
+	0x60, 0x02, 0x7F, 0x7F, 0x00,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Import section
+	0x02, 0x28, 0x02,
+	0x03, b'e', b'n', b'v',
+	0x0B, b't', b'u', b'r', b't', b'l', b'e', b'_', b'f', b'a', b'c', b'e', 0x00, 0x00,
+	0x03, b'e', b'n', b'v',
+	0x0E, b't', b'u', b'r', b't', b'l', b'e', b'_', b'r', b'a', b'y', b'c', b'a', b's', b't', 0x00, 0x01,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'l', b'o', b'o', b'k', 0x00, 0x02,
+	// Code section
+	0x0A, 0x0F, 0x01,
+	0x0D, 0x00, 0x41, 0xDA, 0x00, 0x41, 0x00, 0x10, 0x00, 0x20, 0x00, 0x10, 0x01, 0x0B,
+];
+
+#[test]
+fn raycast_is_lowered() {
+	let ctx = context(&["-O1"]);
+	let instrs = ssa_instrs(&ctx, RAYCAST_MODULE);
+	assert_eq!(instrs.iter().filter(|instr| matches!(instr, SsaInstr::TurtleFace { .. })).count(), 1);
+	assert_eq!(instrs.iter().filter(|instr| matches!(instr, SsaInstr::TurtleRaycast { .. })).count(), 1);
+
+	// There is no world to cast the ray in, so the interpreter always finds air
+	assert_eq!(run_ssa(&ctx, RAYCAST_MODULE, "look", &[TypedValue::I32(16)]), vec![TypedValue::I32(0)]);
+}
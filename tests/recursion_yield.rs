@@ -0,0 +1,47 @@
+use wasmcraft::ssa::interp::TypedValue;
+
+mod test_common;
+use test_common::{call_datapack, i32_params, start_datapack};
+
+/// A module exporting:
+///
+/// (func $sum (param i32) (result i32)
+///   (if (result i32) (i32.eqz (local.get 0))
+///     (then (i32.const 0))
+///     (else (i32.add (local.get 0) (call $sum (i32.sub (local.get 0) (i32.const 1)))))))
+const RECURSIVE_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01, 0x03, b's', b'u', b'm', 0x00, 0x00,
+	// Code section
+	0x0A, 0x17, 0x01,
+	0x15, 0x00,
+	0x20, 0x00, 0x45,
+	0x04, 0x7F,
+	0x41, 0x00,
+	0x05,
+	0x20, 0x00, 0x20, 0x00, 0x41, 0x01, 0x6B, 0x10, 0x00, 0x6A,
+	0x0B,
+	0x0B,
+];
+
+/// Sums 1 to 50 in the datapack, returning how many ticks it took
+fn sum_ticks(args: &[&str]) -> u64 {
+	let mut interp = start_datapack(RECURSIVE_MODULE, &[&["-O1"], args].concat());
+	let start = interp.tick;
+	assert_eq!(call_datapack(&mut interp, "sum", &i32_params(&[50]), false), TypedValue::I32(1275));
+	(interp.tick - start) as u64
+}
+
+#[test]
+fn deep_recursion_yields_and_finishes() {
+	assert_eq!(sum_ticks(&[]), 0);
+
+	// 51 calls yield after every 10th
+	let ticks = sum_ticks(&["--recursion-yield-depth", "10"]);
+	assert!(ticks >= 5, "{ticks}");
+}
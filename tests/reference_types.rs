@@ -0,0 +1,131 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{all_cmds, compile, i32_params, run_lir, run_ssa};
+
+/// A module with a function table holding `$a` and a null reference, given as element expressions,
+/// and a table of two external references, exporting:
+///
+/// (func $a (result i32) (i32.const 1))
+///
+/// (func $call (param $idx i32) (result i32)
+///   (call_indirect $funcs (result i32) (local.get $idx)))
+///
+/// (func $null (param $idx i32) (result i32)
+///   (ref.is_null (table.get $funcs (local.get $idx))))
+///
+/// (func $ext (param $ref externref) (param $idx i32) (result externref)
+///   (table.set $externs (local.get $idx) (local.get $ref))
+///   (table.get $externs (i32.const 1)))
+const REF_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x10, 0x03,
+	0x60, 0x00, 0x01, 0x7F,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	0x60, 0x02, 0x6F, 0x7F, 0x01, 0x6F,
+	// Function section
+	0x03, 0x05, 0x04, 0x00, 0x01, 0x01, 0x02,
+	// Table section
+	0x04, 0x07, 0x02, 0x70, 0x00, 0x02, 0x6F, 0x00, 0x02,
+	// Export section
+	0x07, 0x15, 0x03,
+	0x04, b'c', b'a', b'l', b'l', 0x00, 0x01,
+	0x04, b'n', b'u', b'l', b'l', 0x00, 0x02,
+	0x03, b'e', b'x', b't', 0x00, 0x03,
+	// Element section
+	0x09, 0x0C, 0x01,
+	0x04, 0x41, 0x00, 0x0B, 0x02, 0xD2, 0x00, 0x0B, 0xD0, 0x70, 0x0B,
+	// Code section
+	0x0A, 0x23, 0x04,
+	0x04, 0x00, 0x41, 0x01, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x11, 0x00, 0x00, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x25, 0x00, 0xD1, 0x0B,
+	0x0C, 0x00, 0x20, 0x01, 0x20, 0x00, 0x26, 0x01, 0x41, 0x01, 0x25, 0x01, 0x0B,
+];
+
+#[test]
+fn element_expressions() {
+	let ctx = CompileContext::new_from_opt(1);
+	let cases = [
+		// name, args, expected
+		("call", 0, 1),
+		("null", 0, 0),
+		("null", 1, 1),
+	];
+
+	for (name, arg, expected) in cases {
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, REF_MODULE, name, &i32_params(&[arg])), expected, "SSA, {name}({arg})");
+		assert_eq!(run_lir(&ctx, REF_MODULE, name, &i32_params(&[arg])), expected, "LIR, {name}({arg})");
+	}
+}
+
+#[test]
+fn only_written_tables_get_holders() {
+	let cmds = all_cmds(&compile(REF_MODULE, &["-O1"]));
+	// `$funcs` is never written, so `table.get` looks its entries up like `call_indirect` does
+	assert!(!cmds.iter().any(|cmd| cmd.contains("%%table_0_0 reg") || cmd.contains("%%table_0_1 reg")), "{cmds:?}");
+	// Null references are stored as 0
+	for idx in 0..2 {
+		let init = format!("scoreboard players set %%table_1_{idx} reg 0");
+		assert!(cmds.contains(&init), "{idx}: {cmds:?}");
+	}
+}
+
+#[test]
+fn externref_handles_round_trip() {
+	let ctx = CompileContext::new_from_opt(1);
+	let cases = [
+		// ref, idx, expected
+		(42, 1, 42),
+		(42, 0, 0),
+	];
+
+	for (handle, idx, expected) in cases {
+		let args = [handle, idx];
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, REF_MODULE, "ext", &i32_params(&args)), expected, "SSA, ext{args:?}");
+		assert_eq!(run_lir(&ctx, REF_MODULE, "ext", &i32_params(&args)), expected, "LIR, ext{args:?}");
+	}
+}
+
+/// A module with a global holding a reference to `$a` and one holding a null reference, exporting:
+///
+/// (global $a_ref funcref (ref.func $a))
+/// (global $null_ref funcref (ref.null func))
+///
+/// (func $a (result i32) (i32.const 7))
+///
+/// (func $first_null (result i32)
+///   (ref.is_null (global.get $a_ref)))
+///
+/// (func $second_null (result i32)
+///   (ref.is_null (global.get $null_ref)))
+const REF_GLOBALS_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x05, 0x01, 0x60, 0x00, 0x01, 0x7F,
+	// Function section
+	0x03, 0x04, 0x03, 0x00, 0x00, 0x00,
+	// Global section
+	0x06, 0x0B, 0x02,
+	0x70, 0x00, 0xD2, 0x00, 0x0B,
+	0x70, 0x00, 0xD0, 0x70, 0x0B,
+	// Export section
+	0x07, 0x1C, 0x02,
+	0x0A, b'f', b'i', b'r', b's', b't', b'_', b'n', b'u', b'l', b'l', 0x00, 0x01,
+	0x0B, b's', b'e', b'c', b'o', b'n', b'd', b'_', b'n', b'u', b'l', b'l', 0x00, 0x02,
+	// Code section
+	0x0A, 0x12, 0x03,
+	0x04, 0x00, 0x41, 0x07, 0x0B,
+	0x05, 0x00, 0x23, 0x00, 0xD1, 0x0B,
+	0x05, 0x00, 0x23, 0x01, 0xD1, 0x0B,
+];
+
+#[test]
+fn reference_globals_are_initialized() {
+	let ctx = CompileContext::new_from_opt(1);
+	assert_eq!(run_lir(&ctx, REF_GLOBALS_MODULE, "first_null", &[]), vec![TypedValue::I32(0)]);
+	assert_eq!(run_lir(&ctx, REF_GLOBALS_MODULE, "second_null", &[]), vec![TypedValue::I32(1)]);
+}
@@ -0,0 +1,57 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{all_cmds, call_datapack, compile, run_lir, run_ssa, start_datapack};
+
+/// A module passing bits through floats and back, exporting:
+///
+/// (func $round32 (param i32) (result i32)
+///   (i32.reinterpret_f32 (f32.reinterpret_i32 (local.get 0))))
+///
+/// (func $round64 (param i64) (result i64)
+///   (i64.reinterpret_f64 (f64.reinterpret_i64 (local.get 0))))
+const REINTERPRET_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0B, 0x02,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	0x60, 0x01, 0x7E, 0x01, 0x7E,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x01,
+	// Export section
+	0x07, 0x15, 0x02,
+	0x07, b'r', b'o', b'u', b'n', b'd', b'3', b'2', 0x00, 0x00,
+	0x07, b'r', b'o', b'u', b'n', b'd', b'6', b'4', 0x00, 0x01,
+	// Code section
+	0x0A, 0x0F, 0x02,
+	0x06, 0x00, 0x20, 0x00, 0xBE, 0xBC, 0x0B,
+	0x06, 0x00, 0x20, 0x00, 0xBF, 0xBD, 0x0B,
+];
+
+#[test]
+fn bits_are_kept_exactly() {
+	let cases = [
+		// name, value
+		("round32", TypedValue::I32(0x3F80_0000)),
+		("round32", TypedValue::I32(i32::MIN)),
+		// A NaN with a payload, which arithmetic would canonicalize
+		("round32", TypedValue::I32(0x7FC0_0001)),
+		("round64", TypedValue::I64(-5)),
+		("round64", TypedValue::I64(0x7FF8_0000_0000_0001)),
+	];
+
+	let ctx = CompileContext::new_from_opt(1);
+	let mut interp = start_datapack(REINTERPRET_MODULE, &["-O1"]);
+	for (name, value) in cases {
+		let is_64 = matches!(value, TypedValue::I64(_));
+		assert_eq!(run_ssa(&ctx, REINTERPRET_MODULE, name, &[value]), vec![value], "SSA, {name}({value:?})");
+		assert_eq!(run_lir(&ctx, REINTERPRET_MODULE, name, &[value]), vec![value], "LIR, {name}({value:?})");
+		assert_eq!(call_datapack(&mut interp, name, &[value], is_64), value, "datapack, {name}({value:?})");
+	}
+}
+
+#[test]
+fn reinterpreting_calls_no_float_intrinsics() {
+	let cmds = all_cmds(&compile(REINTERPRET_MODULE, &["-O1"]));
+	assert!(!cmds.iter().any(|cmd| cmd.contains("intrinsic:f32") || cmd.contains("intrinsic:f64")), "{cmds:?}");
+}
@@ -0,0 +1,103 @@
+use wasmcraft::{CompileContext, remarks::{Remark, Remarks}, wasm_file::WasmFile};
+
+mod test_common;
+use test_common::context;
+
+/// A module exporting:
+///
+/// (func $sum (param i32) (result i32) (local i32)
+///   (block
+///     (loop
+///       (br_if 1 (i32.eqz (local.get 0)))
+///       (local.set 1 (i32.add (local.get 1) (local.get 0)))
+///       (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+///       (br 0)))
+///   (local.get 1))
+///
+/// (func $twice (param i32) (result i32)
+///   (i32.add (call $sum (local.get 0)) (call $sum (local.get 0))))
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x00,
+	// Export section
+	0x07, 0x0F, 0x02,
+	0x03, b's', b'u', b'm', 0x00, 0x00,
+	0x05, b't', b'w', b'i', b'c', b'e', 0x00, 0x01,
+	// Code section
+	0x0A, 0x2F, 0x02,
+	0x21, 0x01, 0x01, 0x7F,
+	0x02, 0x40,
+	0x03, 0x40,
+	0x20, 0x00, 0x45, 0x0D, 0x01,
+	0x20, 0x01, 0x20, 0x00, 0x6A, 0x21, 0x01,
+	0x20, 0x00, 0x41, 0x01, 0x6B, 0x21, 0x00,
+	0x0C, 0x00,
+	0x0B,
+	0x0B,
+	0x20, 0x01,
+	0x0B,
+	0x0B, 0x00, 0x20, 0x00, 0x10, 0x00, 0x20, 0x00, 0x10, 0x00, 0x6A, 0x0B,
+];
+
+fn compile_remarks(ctx: &CompileContext) -> Vec<Remark> {
+	ctx.compute_lir(ctx.compute_ssa(&WasmFile::from(MODULE)));
+	ctx.remarks().take()
+}
+
+fn remarks_path(name: &str) -> String {
+	std::env::temp_dir().join(format!("wasmcraft_{name}_remarks.txt")).to_str().unwrap().to_string()
+}
+
+#[test]
+fn remarks_are_kept_per_context() {
+	let path = remarks_path("per_context");
+	let with = context(&["-O1", "--remarks", &path]);
+	let without = context(&["-O1"]);
+
+	let remarks = compile_remarks(&with);
+	assert!(remarks.iter().any(|remark| remark.pass == "regalloc" && remark.block.is_none()), "{remarks:?}");
+	assert!(remarks.iter().any(|remark| remark.pass == "lir" && remark.func == 0 && remark.block.is_some()), "{remarks:?}");
+
+	// Compiling with another context in between doesn't mix their remarks
+	assert!(compile_remarks(&without).is_empty());
+	assert!(with.remarks().take().is_empty());
+}
+
+#[test]
+fn remarks_are_sorted_by_function_then_block() {
+	let path = remarks_path("sorted");
+	let remarks = compile_remarks(&context(&["-O1", "--remarks", &path]));
+
+	let keys = remarks.iter().map(|remark| (remark.func, remark.block)).collect::<Vec<_>>();
+	let mut sorted = keys.clone();
+	sorted.sort();
+	assert_eq!(keys, sorted);
+	assert!(keys.iter().any(|&(func, _)| func == 1), "{remarks:?}");
+}
+
+#[test]
+fn remarks_about_one_block_keep_their_order() {
+	let remarks = Remarks::new(true);
+	remarks.emit("lir", 1, Some(0), || "second".to_string());
+	remarks.emit("dce", 0, Some(2), || "third".to_string());
+	remarks.emit("regalloc", 0, None, || "first".to_string());
+	remarks.emit("dce", 1, Some(0), || "fourth".to_string());
+
+	let order = remarks.take().into_iter().map(|remark| (remark.func, remark.block, remark.message)).collect::<Vec<_>>();
+	assert_eq!(order, [
+		(0, None, "first".to_string()),
+		(0, Some(2), "third".to_string()),
+		(1, Some(0), "second".to_string()),
+		(1, Some(0), "fourth".to_string()),
+	]);
+}
+
+#[test]
+fn disabled_remarks_are_not_built() {
+	let remarks = Remarks::default();
+	remarks.emit("dce", 0, None, || panic!("the message was built"));
+	assert!(remarks.take().is_empty());
+}
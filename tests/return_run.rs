@@ -0,0 +1,61 @@
+mod test_common;
+use test_common::compile;
+use wasmcraft::{mc_version::McVersion, pack_emitter};
+
+/// A module exporting:
+///
+/// (func $choose (param i32) (result i32)
+///   (if (result i32) (local.get 0)
+///     (then (i32.const 10))
+///     (else (i32.const 20))))
+const BRANCH_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x0A, 0x01,
+	0x06, b'c', b'h', b'o', b'o', b's', b'e', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0E, 0x01,
+	0x0C, 0x00,
+	0x20, 0x00, 0x04, 0x7F,
+	0x41, 0x0A,
+	0x05,
+	0x41, 0x14,
+	0x0B, 0x0B,
+];
+
+/// Compiles the module and writes it for the given version, returning the contents of every function in the datapack.
+fn persist(version: &str) -> Vec<String> {
+	let datapack = compile(BRANCH_MODULE, &["-O1", "--mc-version", version]);
+
+	let folder = std::env::temp_dir().join(format!("wasmcraft_return_run_{version}"));
+	let _ = std::fs::remove_dir_all(&folder);
+	pack_emitter::persist_program(&folder, &datapack, McVersion::parse(version).unwrap());
+
+	let functions = folder.join("data/wasmrunner/functions");
+	std::fs::read_dir(&functions).unwrap()
+		.map(|entry| entry.unwrap().path())
+		.filter(|path| path.extension().map_or(false, |ext| ext == "mcfunction"))
+		.map(|path| std::fs::read_to_string(path).unwrap())
+		.collect()
+}
+
+#[test]
+fn branches_use_return_run_when_available() {
+	let funcs = persist("1.20.2");
+
+	assert!(funcs.iter().any(|func| func.contains("matches 0 run return run function wasmrunner:")), "{funcs:#?}");
+	assert!(funcs.iter().all(|func| !func.contains("# !RETURN")), "{funcs:#?}");
+}
+
+#[test]
+fn branches_check_cond_taken_without_return_run() {
+	let funcs = persist("1.20.1");
+
+	assert!(funcs.iter().any(|func| func.contains("execute unless score") && func.contains("matches 0 run function wasmrunner:")), "{funcs:#?}");
+	assert!(funcs.iter().all(|func| !func.contains("return run") && !func.contains("return 1")), "{funcs:#?}");
+}
@@ -0,0 +1,51 @@
+use wasmcraft::{CompileContext, find_run_entry, wasm_file::WasmFile, ssa::BlockId};
+
+mod test_common;
+
+/// (func (export "main"))
+const MAIN_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01, 0x04, b'm', b'a', b'i', b'n', 0x00, 0x00,
+	// Code section
+	0x0A, 0x04, 0x01, 0x02, 0x00, 0x0B,
+];
+
+/// (func) (func (export "_start"))
+const START_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x00,
+	// Export section
+	0x07, 0x0A, 0x01, 0x06, b'_', b's', b't', b'a', b'r', b't', 0x00, 0x01,
+	// Code section
+	0x0A, 0x07, 0x02, 0x02, 0x00, 0x0B, 0x02, 0x00, 0x0B,
+];
+
+#[test]
+fn run_entry_is_start() {
+	let ctx = CompileContext::new_from_opt(1);
+	let program = ctx.compute_ssa(&WasmFile::from(START_MODULE));
+	assert_eq!(find_run_entry(&program.exports), Ok(BlockId { func: 1, block: 0 }));
+}
+
+#[test]
+fn missing_start_names_it() {
+	let ctx = CompileContext::new_from_opt(1);
+	let program = ctx.compute_ssa(&WasmFile::from(MAIN_MODULE));
+	let err = find_run_entry(&program.exports).unwrap_err();
+	assert!(err.contains("`_start`"), "{err}");
+}
+
+#[test]
+fn tick_limit_follows_the_cost_model() {
+	assert_eq!(test_common::context(&["--max-command-chain-length", "1000"]).run_output_tick_limit(), 30_000);
+	assert_eq!(test_common::context(&["--cost-model", "vanilla", "--max-command-chain-length", "1000"]).run_output_tick_limit(), 60_000);
+	assert_eq!(test_common::context(&["--tick-budget", "500", "--max-command-chain-length", "1000"]).run_output_tick_limit(), 1000);
+}
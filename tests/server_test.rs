@@ -109,7 +109,7 @@ async fn load_state_async(wasm_data: &[u8]) -> TestState {
 	let program = ctx.compute_lir(program);
 	let program = ctx.compute_datapack(&program);
 
-	pack_emitter::persist_program(std::path::Path::new("./tests/server_test/world/datapacks/out"), &program);
+	pack_emitter::persist_program(std::path::Path::new("./tests/server_test/world/datapacks/out"), &program, Default::default());
 
 	let mut server = connect_to_server().await;
 
@@ -154,7 +154,7 @@ async fn eval(expr: &SExpr, test_state: Option<&mut TestState<'_>>) -> Vec<Typed
 
 			let func_idx = test_state.wasm_file.find_func(func_name).unwrap();
 			let return_tys = &test_state.wasm_file.func_type(func_idx).returns;
-			let func_name = get_mc_id(BlockId { func: func_idx, block: 0 });
+			let func_name = get_mc_id(&test_state.wasm_file.func_names, BlockId { func: func_idx, block: 0 });
 
 			println!("Calling func {func_name} with params {:?}", func_params);
 
@@ -0,0 +1,66 @@
+use std::path::Path;
+
+use wasmcraft::pack_emitter::{shards, PackBackend};
+
+mod test_common;
+use test_common::{compile_with, context};
+
+/// (func (export "run") (param i32) (result i32)
+///   (local.get 0))
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x00,
+	// Code section
+	0x0A, 0x06, 0x01,
+	0x04, 0x00, 0x20, 0x00, 0x0B,
+];
+
+fn is_function(path: &str) -> bool {
+	path.ends_with(".mcfunction")
+}
+
+#[test]
+fn shards_have_at_most_max_functions() {
+	let ctx = context(&["-O1", "--refresh-markers"]);
+	let datapack = compile_with(&ctx, MODULE);
+	let (files, _) = ctx.datapack().files(&datapack);
+
+	let split = shards::split_files(files.clone(), 50);
+	assert!(split.len() > 1);
+	for shard in split.iter() {
+		assert!(shard.iter().filter(|(path, _)| is_function(path)).count() <= 50);
+		assert!(shard.iter().any(|(path, _)| path == "pack.mcmeta"));
+	}
+
+	// Every file is written once, besides the metadata every shard has
+	let mut written = split.iter().flatten().filter(|(path, _)| path != "pack.mcmeta").collect::<Vec<_>>();
+	let mut expected = files.iter().filter(|(path, _)| path != "pack.mcmeta").collect::<Vec<_>>();
+	written.sort();
+	expected.sort();
+	assert_eq!(written, expected);
+
+	assert!(split[0].iter().any(|(path, _)| path == "data/minecraft/tags/functions/load.json"));
+	assert!(split[1..].iter().flatten().all(|(path, _)| is_function(path) || path == "pack.mcmeta"));
+}
+
+#[test]
+fn shards_are_written_next_to_output() {
+	assert_eq!(shards::shard_path(Path::new("../packs/foo"), 0), Path::new("../packs/foo"));
+	assert_eq!(shards::shard_path(Path::new("../packs/foo"), 1), Path::new("../packs/foo_2"));
+	assert_eq!(shards::shard_path(Path::new("../packs/foo.zip"), 2), Path::new("../packs/foo_3.zip"));
+}
+
+#[test]
+fn small_packs_are_not_split() {
+	let files = vec![
+		("pack.mcmeta".to_string(), String::new()),
+		("data/wasmrunner/functions/init.mcfunction".to_string(), String::new()),
+	];
+	assert_eq!(shards::split_files(files.clone(), 1), vec![files]);
+}
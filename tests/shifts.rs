@@ -0,0 +1,140 @@
+use wasmcraft::ssa::interp::TypedValue;
+
+mod test_common;
+use test_common::{call_datapack, context, run_ssa, start_datapack};
+
+/// (func (export "shl") (param i32 i32) (result i32)
+///   (i32.shl (local.get 0) (local.get 1)))
+///
+/// and likewise `shr_s`, `shr_u`, `rotl` and `rotr`, followed by the same for i64 named `shl64` and so on.
+///
+/// (func (export "shl_33") (param i32) (result i32)
+///   (i32.shl (local.get 0) (i32.const 33)))
+///
+/// and likewise each i32 shift and rotate by 33, followed by each i64 one by 7, 40 and 100, named like `rotr64_100`.
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x17, 0x04,
+	0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	0x60, 0x02, 0x7E, 0x7E, 0x01, 0x7E,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	0x60, 0x01, 0x7E, 0x01, 0x7E,
+	// Function section
+	0x03, 0x1F, 0x1E,
+	0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x01, 0x01, 0x01, 0x02, 0x02, 0x02, 0x02, 0x02, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03, 0x03,
+	// Export section
+	0x07, 0xBD, 0x02, 0x1E,
+	0x03, b's', b'h', b'l', 0x00, 0x00,
+	0x05, b's', b'h', b'r', b'_', b's', 0x00, 0x01,
+	0x05, b's', b'h', b'r', b'_', b'u', 0x00, 0x02,
+	0x04, b'r', b'o', b't', b'l', 0x00, 0x03,
+	0x04, b'r', b'o', b't', b'r', 0x00, 0x04,
+	0x05, b's', b'h', b'l', b'6', b'4', 0x00, 0x05,
+	0x07, b's', b'h', b'r', b'_', b's', b'6', b'4', 0x00, 0x06,
+	0x07, b's', b'h', b'r', b'_', b'u', b'6', b'4', 0x00, 0x07,
+	0x06, b'r', b'o', b't', b'l', b'6', b'4', 0x00, 0x08,
+	0x06, b'r', b'o', b't', b'r', b'6', b'4', 0x00, 0x09,
+	0x06, b's', b'h', b'l', b'_', b'3', b'3', 0x00, 0x0A,
+	0x08, b's', b'h', b'r', b'_', b's', b'_', b'3', b'3', 0x00, 0x0B,
+	0x08, b's', b'h', b'r', b'_', b'u', b'_', b'3', b'3', 0x00, 0x0C,
+	0x07, b'r', b'o', b't', b'l', b'_', b'3', b'3', 0x00, 0x0D,
+	0x07, b'r', b'o', b't', b'r', b'_', b'3', b'3', 0x00, 0x0E,
+	0x07, b's', b'h', b'l', b'6', b'4', b'_', b'7', 0x00, 0x0F,
+	0x08, b's', b'h', b'l', b'6', b'4', b'_', b'4', b'0', 0x00, 0x10,
+	0x09, b's', b'h', b'l', b'6', b'4', b'_', b'1', b'0', b'0', 0x00, 0x11,
+	0x09, b's', b'h', b'r', b'_', b's', b'6', b'4', b'_', b'7', 0x00, 0x12,
+	0x0A, b's', b'h', b'r', b'_', b's', b'6', b'4', b'_', b'4', b'0', 0x00, 0x13,
+	0x0B, b's', b'h', b'r', b'_', b's', b'6', b'4', b'_', b'1', b'0', b'0', 0x00, 0x14,
+	0x09, b's', b'h', b'r', b'_', b'u', b'6', b'4', b'_', b'7', 0x00, 0x15,
+	0x0A, b's', b'h', b'r', b'_', b'u', b'6', b'4', b'_', b'4', b'0', 0x00, 0x16,
+	0x0B, b's', b'h', b'r', b'_', b'u', b'6', b'4', b'_', b'1', b'0', b'0', 0x00, 0x17,
+	0x08, b'r', b'o', b't', b'l', b'6', b'4', b'_', b'7', 0x00, 0x18,
+	0x09, b'r', b'o', b't', b'l', b'6', b'4', b'_', b'4', b'0', 0x00, 0x19,
+	0x0A, b'r', b'o', b't', b'l', b'6', b'4', b'_', b'1', b'0', b'0', 0x00, 0x1A,
+	0x08, b'r', b'o', b't', b'r', b'6', b'4', b'_', b'7', 0x00, 0x1B,
+	0x09, b'r', b'o', b't', b'r', b'6', b'4', b'_', b'4', b'0', 0x00, 0x1C,
+	0x0A, b'r', b'o', b't', b'r', b'6', b'4', b'_', b'1', b'0', b'0', 0x00, 0x1D,
+	// Code section
+	0x0A, 0xF6, 0x01, 0x1E,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x74, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x75, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x76, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x77, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x78, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x86, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x87, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x88, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x89, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x8A, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x41, 0x21, 0x74, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x41, 0x21, 0x75, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x41, 0x21, 0x76, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x41, 0x21, 0x77, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x41, 0x21, 0x78, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x42, 0x07, 0x86, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x42, 0x28, 0x86, 0x0B,
+	0x08, 0x00, 0x20, 0x00, 0x42, 0xE4, 0x00, 0x86, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x42, 0x07, 0x87, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x42, 0x28, 0x87, 0x0B,
+	0x08, 0x00, 0x20, 0x00, 0x42, 0xE4, 0x00, 0x87, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x42, 0x07, 0x88, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x42, 0x28, 0x88, 0x0B,
+	0x08, 0x00, 0x20, 0x00, 0x42, 0xE4, 0x00, 0x88, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x42, 0x07, 0x89, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x42, 0x28, 0x89, 0x0B,
+	0x08, 0x00, 0x20, 0x00, 0x42, 0xE4, 0x00, 0x89, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x42, 0x07, 0x8A, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0x42, 0x28, 0x8A, 0x0B,
+	0x08, 0x00, 0x20, 0x00, 0x42, 0xE4, 0x00, 0x8A, 0x0B,
+];
+
+const OPS: [&str; 5] = ["shl", "shr_s", "shr_u", "rotl", "rotr"];
+
+const I32_VALUES: [i32; 4] = [0x1234_5678, -0x1234_5678, -1, i32::MIN];
+const I64_VALUES: [i64; 4] = [0x1234_5678_9ABC_DEF0, -0x1234_5678_9ABC_DEF0, -1, i64::MIN];
+
+const I32_COUNTS: [i32; 9] = [0, 1, 31, 32, 33, 63, 64, 100, -1];
+const I64_COUNTS: [i64; 10] = [0, 1, 31, 32, 33, 63, 64, 65, 200, -1];
+
+fn cases() -> Vec<(String, Vec<TypedValue>)> {
+	let mut cases = Vec::new();
+	for op in OPS {
+		for lhs in I32_VALUES {
+			for count in I32_COUNTS {
+				cases.push((op.to_string(), vec![TypedValue::I32(lhs), TypedValue::I32(count)]));
+			}
+			cases.push((format!("{op}_33"), vec![TypedValue::I32(lhs)]));
+		}
+		for lhs in I64_VALUES {
+			for count in I64_COUNTS {
+				cases.push((format!("{op}64"), vec![TypedValue::I64(lhs), TypedValue::I64(count)]));
+			}
+			for count in [7, 40, 100] {
+				cases.push((format!("{op}64_{count}"), vec![TypedValue::I64(lhs)]));
+			}
+		}
+	}
+	cases
+}
+
+fn check_against_interpreter(opt: &str) {
+	let ctx = context(&["-O0"]);
+	let mut interp = start_datapack(MODULE, &[opt]);
+
+	for (name, params) in cases() {
+		let expected = run_ssa(&ctx, MODULE, &name, &params)[0];
+		let actual = call_datapack(&mut interp, &name, &params, matches!(expected, TypedValue::I64(_)));
+		assert_eq!(actual, expected, "{name} {params:?} with {opt}");
+	}
+}
+
+#[test]
+fn shifts_mask_count() {
+	check_against_interpreter("-O0");
+}
+
+#[test]
+fn constant_shifts_mask_count() {
+	check_against_interpreter("-O1");
+}
@@ -0,0 +1,93 @@
+mod test_common;
+use test_common::compile;
+use wasmcraft::{mc_version::McVersion, pack_emitter};
+
+/// A module exporting:
+///
+/// (func $calc (param i32) (result i32)
+///   (i32.mul
+///     (i32.add (local.get 0) (i32.const 12345))
+///     (i32.const 7)))
+const CALC_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'c', b'a', b'l', b'c', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0E, 0x01,
+	0x0C, 0x00,
+	0x20, 0x00, 0x41, 0xB9, 0xE0, 0x00, 0x6A,
+	0x41, 0x07, 0x6C,
+	0x0B,
+];
+
+/// Reads every function in a written datapack into one string.
+fn read_functions(folder: &std::path::Path) -> String {
+	let mut contents = String::new();
+	let mut dirs = vec![folder.join("data")];
+	while let Some(dir) = dirs.pop() {
+		for entry in std::fs::read_dir(dir).unwrap() {
+			let path = entry.unwrap().path();
+			if path.is_dir() {
+				dirs.push(path);
+			} else if path.extension() == Some(std::ffi::OsStr::new("mcfunction")) {
+				contents.push_str(&std::fs::read_to_string(path).unwrap());
+				contents.push('\n');
+			}
+		}
+	}
+	contents
+}
+
+/// Writes the module with or without short names, returning its functions and the mapping of short names.
+fn write(short_names: bool) -> (String, serde_json::Value) {
+	let datapack = compile(CALC_MODULE, &["-O1"]);
+
+	let mut pack = pack_emitter::Datapack::new(McVersion::new(19, 4));
+	pack.short_names = short_names;
+
+	let folder = std::env::temp_dir().join(format!("wasmcraft_short_names_{short_names}"));
+	let _ = std::fs::remove_dir_all(&folder);
+	let names = pack_emitter::write_datapack(&pack, &folder, &datapack);
+	(read_functions(&folder), serde_json::from_str(&names.to_json()).unwrap())
+}
+
+fn tokens(contents: &str) -> Vec<&str> {
+	contents.split(|c: char| c.is_whitespace() || c == '"').filter(|token| !token.is_empty()).collect()
+}
+
+#[test]
+fn internal_holders_are_shortened() {
+	let (long, _) = write(false);
+	let (short, names) = write(true);
+	assert!(short.len() < long.len());
+
+	let holders = names["holders"].as_object().unwrap();
+	assert!(holders.values().any(|name| name == "%param0%0"), "{holders:?}");
+
+	let short_tokens = tokens(&short);
+	for (short_name, long_name) in holders {
+		assert!(short_name.len() < long_name.as_str().unwrap().len(), "{short_name} {long_name}");
+		assert!(short_tokens.contains(&short_name.as_str()), "{short_name}");
+		assert!(!short_tokens.contains(&long_name.as_str().unwrap()), "{long_name}");
+		assert!(!tokens(&long).contains(&short_name.as_str()), "{short_name} was already used");
+	}
+
+	// The same commands are written, only with other names
+	assert_eq!(tokens(&long).len(), short_tokens.len());
+}
+
+#[test]
+fn documented_holders_keep_their_names() {
+	let (long, _) = write(false);
+	let (short, _) = write(true);
+
+	assert!(tokens(&long).contains(&"%%max_commands"));
+	assert!(tokens(&short).contains(&"%%max_commands"));
+	assert!(tokens(&short).contains(&"wasm_call"));
+}
@@ -0,0 +1,49 @@
+use wasmcraft::{wasm_file::WasmFile, ssa::{SsaTerminator, interp::TypedValue}};
+
+mod test_common;
+use test_common::{context, run_lir, run_ssa};
+
+/// A module exporting:
+///
+/// (import "env" "mc_sleep_ticks" (func $mc_sleep_ticks (param i32)))
+///
+/// (func $wait (result i32)
+///   (call $mc_sleep_ticks (i32.const 20))
+///   (i32.const 3))
+const SLEEP_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x09, 0x02,
+	0x60, 0x01, 0x7F, 0x00,
+	0x60, 0x00, 0x01, 0x7F,
+	// Import section
+	0x02, 0x16, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x0E, b'm', b'c', b'_', b's', b'l', b'e', b'e', b'p', b'_', b't', b'i', b'c', b'k', b's', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'w', b'a', b'i', b't', 0x00, 0x01,
+	// Code section
+	0x0A, 0x0A, 0x01,
+	0x08, 0x00, 0x41, 0x14, 0x10, 0x00, 0x41, 0x03, 0x0B,
+];
+
+#[test]
+fn sleep_ticks_schedules_the_rest() {
+	let ctx = context(&["-O1"]);
+	let program = ctx.compute_ssa(&WasmFile::from(SLEEP_MODULE));
+
+	let delays = program.code.iter()
+		.flat_map(|func| func.iter())
+		.filter_map(|(_, block)| match &block.term {
+			SsaTerminator::ScheduleJump(_, delay) => Some(*delay),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(delays, [20]);
+
+	assert_eq!(run_ssa(&ctx, SLEEP_MODULE, "wait", &[]), vec![TypedValue::I32(3)], "SSA");
+	assert_eq!(run_lir(&ctx, SLEEP_MODULE, "wait", &[]), vec![TypedValue::I32(3)], "LIR");
+}
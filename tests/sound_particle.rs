@@ -0,0 +1,96 @@
+mod test_common;
+use test_common::{context, ssa_instrs};
+use wasmcraft::ssa::SsaInstr;
+
+/// A module exporting:
+///
+/// (import "env" "mc_playsound" (func $mc_playsound (param i32 i32 i32)))
+/// (import "env" "mc_particle" (func $mc_particle (param i32 i32)))
+///
+/// (func $effects (param $particle i32)
+///   (call $mc_playsound (i32.const 4) (i32.const 100) (i32.const 150))
+///   (call $mc_particle (local.get $particle) (i32.const 8)))
+const EFFECTS_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x10, 0x03,
+	0x60, 0x03, 0x7F, 0x7F, 0x7F, 0x00,
+	0x60, 0x02, 0x7F, 0x7F, 0x00,
+	0x60, 0x01, 0x7F, 0x00,
+	// Import section
+	0x02, 0x26, 0x02,
+	0x03, b'e', b'n', b'v',
+	0x0C, b'm', b'c', b'_', b'p', b'l', b'a', b'y', b's', b'o', b'u', b'n', b'd', 0x00, 0x00,
+	0x03, b'e', b'n', b'v',
+	0x0B, b'm', b'c', b'_', b'p', b'a', b'r', b't', b'i', b'c', b'l', b'e', 0x00, 0x01,
+	// Function section
+	0x03, 0x02, 0x01, 0x02,
+	// Export section
+	0x07, 0x0B, 0x01,
+	0x07, b'e', b'f', b'f', b'e', b'c', b't', b's', 0x00, 0x02,
+	// Code section
+	0x0A, 0x14, 0x01,
+	0x12, 0x00,
+	0x41, 0x04, 0x41, 0xE4, 0x00, 0x41, 0x96, 0x01, 0x10, 0x00,
+	0x20, 0x00, 0x41, 0x08, 0x10, 0x01,
+	0x0B,
+];
+
+/// The same imports as [`EFFECTS_MODULE`], exporting:
+///
+/// (func $bad (param $pitch i32)
+///   (call $mc_playsound (i32.const 0) (i32.const 100) (local.get $pitch)))
+const NON_CONSTANT_PITCH_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x10, 0x03,
+	0x60, 0x03, 0x7F, 0x7F, 0x7F, 0x00,
+	0x60, 0x02, 0x7F, 0x7F, 0x00,
+	0x60, 0x01, 0x7F, 0x00,
+	// Import section
+	0x02, 0x26, 0x02,
+	0x03, b'e', b'n', b'v',
+	0x0C, b'm', b'c', b'_', b'p', b'l', b'a', b'y', b's', b'o', b'u', b'n', b'd', 0x00, 0x00,
+	0x03, b'e', b'n', b'v',
+	0x0B, b'm', b'c', b'_', b'p', b'a', b'r', b't', b'i', b'c', b'l', b'e', 0x00, 0x01,
+	// Function section
+	0x03, 0x02, 0x01, 0x02,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'b', b'a', b'd', 0x00, 0x02,
+	// Code section
+	0x0A, 0x0D, 0x01,
+	0x0B, 0x00,
+	0x41, 0x00, 0x41, 0xE4, 0x00, 0x20, 0x00, 0x10, 0x00,
+	0x0B,
+];
+
+#[test]
+fn constant_parameters_are_kept() {
+	for opt in [0, 1] {
+		let ctx = context(&[&format!("-O{opt}")]);
+		let instrs = ssa_instrs(&ctx, EFFECTS_MODULE);
+
+		let sounds = instrs.iter()
+			.filter_map(|instr| match instr {
+				&SsaInstr::PlaySound { volume, pitch, .. } => Some((volume, pitch)),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(sounds, [(100, 150)], "-O{opt}");
+
+		let particles = instrs.iter()
+			.filter_map(|instr| match instr {
+				&SsaInstr::Particle { count, .. } => Some(count),
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+		assert_eq!(particles, [8], "-O{opt}");
+	}
+}
+
+#[test]
+#[should_panic(expected = "the pitch passed to `mc_playsound` must be a constant")]
+fn non_constant_pitch_is_rejected() {
+	ssa_instrs(&context(&["-O1"]), NON_CONSTANT_PITCH_MODULE);
+}
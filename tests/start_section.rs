@@ -0,0 +1,90 @@
+use wasmcraft::{wasm_file::WasmFile, ssa::{BlockId, interp::{SsaInterpreter, TypedValue}}, lir::interp::LirInterpreter};
+
+mod test_common;
+use test_common::context;
+
+/// A module whose start function sets a global, exporting:
+///
+/// (global $g (mut i32) (i32.const 0))
+///
+/// (func $get (result i32) (global.get $g))
+///
+/// (func $init (global.set $g (i32.const 42)))
+///
+/// (start $init)
+const START_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x08, 0x02, 0x60, 0x00, 0x01, 0x7F, 0x60, 0x00, 0x00,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x01,
+	// Global section
+	0x06, 0x06, 0x01, 0x7F, 0x01, 0x41, 0x00, 0x0B,
+	// Export section
+	0x07, 0x07, 0x01, 0x03, b'g', b'e', b't', 0x00, 0x00,
+	// Start section
+	0x08, 0x01, 0x01,
+	// Code section
+	0x0A, 0x0D, 0x02,
+	0x04, 0x00, 0x23, 0x00, 0x0B,
+	0x06, 0x00, 0x41, 0x2A, 0x24, 0x00, 0x0B,
+];
+
+#[test]
+fn start_function_is_recorded() {
+	let ctx = context(&["-O1"]);
+	let file = WasmFile::from(START_MODULE);
+	assert_eq!(file.start_func, Some(1));
+
+	let program = ctx.compute_ssa(&file);
+	assert_eq!(program.start, Some(BlockId { func: 1, block: 0 }));
+
+	let program = ctx.compute_lir(program);
+	assert_eq!(program.start, Some(BlockId { func: 1, block: 0 }));
+}
+
+#[test]
+fn start_function_runs_before_exports() {
+	let ctx = context(&["-O1"]);
+	let file = WasmFile::from(START_MODULE);
+	let get = file.find_func("get").unwrap();
+	let expected = vec![TypedValue::I32(42)];
+
+	let program = ctx.compute_ssa(&file);
+	let start = program.start.unwrap();
+	let mut interp = SsaInterpreter::new(program);
+	interp.call(start.func, Vec::new());
+	interp.run_until_halted();
+	interp.call(get, Vec::new());
+	assert_eq!(interp.run_until_halted(), expected, "SSA");
+
+	let program = ctx.compute_lir(ctx.compute_ssa(&file));
+	let start = program.start.unwrap();
+	let mut interp = LirInterpreter::new(program);
+	interp.call(start.func, &[]);
+	interp.run_until_halted();
+	interp.call(get, &[]);
+	assert_eq!(interp.run_until_halted(), expected, "LIR");
+}
+
+/// A module whose start function is imported:
+///
+/// (import "env" "setup" (func $setup))
+///
+/// (start $setup)
+const IMPORTED_START_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x04, 0x01, 0x60, 0x00, 0x00,
+	// Import section
+	0x02, 0x0D, 0x01,
+	0x03, b'e', b'n', b'v', 0x05, b's', b'e', b't', b'u', b'p', 0x00, 0x00,
+	// Start section
+	0x08, 0x01, 0x00,
+];
+
+#[test]
+#[should_panic(expected = "start function 0 is the import `env.setup`, but only functions defined by the module or linked from another one can be start functions")]
+fn imported_start_function_is_rejected() {
+	context(&["-O1"]).compute_ssa(&WasmFile::from(IMPORTED_START_MODULE));
+}
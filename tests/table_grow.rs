@@ -0,0 +1,87 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{all_cmds, compile, i32_params, run_lir, run_ssa};
+
+/// A module with a table holding `$a` that can grow to three entries, exporting:
+///
+/// (func $a (result i32) (i32.const 1))
+/// (func $b (result i32) (i32.const 2))
+/// (elem declare func $b)
+///
+/// (func $grow (param $count i32) (param $call i32) (result i32)
+///   (i32.add
+///     (i32.add
+///       (table.grow (ref.func $b) (local.get $count))
+///       (call_indirect (result i32) (local.get $call)))
+///     (i32.mul (table.size) (i32.const 100))))
+const GROW_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0B, 0x02, 0x60, 0x00, 0x01, 0x7F, 0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x04, 0x03, 0x00, 0x00, 0x01,
+	// Table section
+	0x04, 0x05, 0x01, 0x70, 0x01, 0x01, 0x03,
+	// Export section
+	0x07, 0x08, 0x01, 0x04, b'g', b'r', b'o', b'w', 0x00, 0x02,
+	// Element section
+	0x09, 0x0B, 0x02,
+	0x00, 0x41, 0x00, 0x0B, 0x01, 0x00,
+	0x03, 0x00, 0x01, 0x01,
+	// Code section
+	0x0A, 0x23, 0x03,
+	0x04, 0x00, 0x41, 0x01, 0x0B,
+	0x04, 0x00, 0x41, 0x02, 0x0B,
+	0x17, 0x00,
+	0xD2, 0x01, 0x20, 0x00, 0xFC, 0x0F, 0x00,
+	0x20, 0x01, 0x11, 0x00, 0x00,
+	0x6A,
+	0xFC, 0x10, 0x00, 0x41, 0xE4, 0x00, 0x6C,
+	0x6A,
+	0x0B,
+];
+
+#[test]
+fn table_grow_matches() {
+	let cases = [
+		// count, call, expected
+		(0, 0, 102),
+		// The new entries can be called right away
+		(1, 1, 203),
+		(2, 2, 303),
+		(2, 0, 302),
+		// Growing past the maximum fails
+		(3, 0, 100),
+		// The count is unsigned
+		(-1, 0, 100),
+	];
+
+	let ctx = CompileContext::new_from_opt(1);
+	for (count, call, expected) in cases {
+		let params = i32_params(&[count, call]);
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, GROW_MODULE, "grow", &params), expected, "SSA, grow({count}, {call})");
+		assert_eq!(run_lir(&ctx, GROW_MODULE, "grow", &params), expected, "LIR, grow({count}, {call})");
+	}
+}
+
+#[test]
+fn table_grow_checks_capacity() {
+	let cmds = all_cmds(&compile(GROW_MODULE, &["-O1"]));
+
+	assert!(cmds.iter().any(|cmd| cmd.starts_with("execute store success score %%table_grow_ok reg if score ") && cmd.ends_with(" matches 0..3")), "{cmds:?}");
+	assert!(cmds.contains(&"execute if score %%table_grow_end reg matches 4.. run scoreboard players set %%table_grow_ok reg 0".to_string()), "{cmds:?}");
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn table_grow_call_past_size_ssa() {
+	run_ssa(&CompileContext::new_from_opt(1), GROW_MODULE, "grow", &i32_params(&[1, 2]));
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn table_grow_call_past_size_lir() {
+	run_lir(&CompileContext::new_from_opt(1), GROW_MODULE, "grow", &i32_params(&[1, 2]));
+}
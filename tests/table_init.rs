@@ -0,0 +1,115 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{all_cmds, compile, i32_params, run_lir, run_ssa};
+
+/// A module with a table of three entries, where only the first starts out holding `$a`, exporting:
+///
+/// (func $a (result i32) (i32.const 1))
+/// (func $b (result i32) (i32.const 2))
+/// (func $c (result i32) (i32.const 3))
+/// (elem $passive func $b $c)
+///
+/// (func $run (param $dst i32) (param $src i32) (param $len i32) (param $call i32) (result i32)
+///   (table.init $passive (local.get $dst) (local.get $src) (local.get $len))
+///   (call_indirect (result i32) (local.get $call)))
+///
+/// (func $drop (param $len i32) (result i32)
+///   (elem.drop $passive)
+///   (table.init $passive (i32.const 0) (i32.const 0) (local.get $len))
+///   (table.size))
+const INIT_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x12, 0x03,
+	0x60, 0x00, 0x01, 0x7F,
+	0x60, 0x04, 0x7F, 0x7F, 0x7F, 0x7F, 0x01, 0x7F,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x06, 0x05, 0x00, 0x00, 0x00, 0x01, 0x02,
+	// Table section
+	0x04, 0x04, 0x01, 0x70, 0x00, 0x03,
+	// Export section
+	0x07, 0x0E, 0x02,
+	0x03, b'r', b'u', b'n', 0x00, 0x03,
+	0x04, b'd', b'r', b'o', b'p', 0x00, 0x04,
+	// Element section
+	0x09, 0x0C, 0x02,
+	0x00, 0x41, 0x00, 0x0B, 0x01, 0x00,
+	0x01, 0x00, 0x02, 0x01, 0x02,
+	// Code section
+	0x0A, 0x35, 0x05,
+	0x04, 0x00, 0x41, 0x01, 0x0B,
+	0x04, 0x00, 0x41, 0x02, 0x0B,
+	0x04, 0x00, 0x41, 0x03, 0x0B,
+	0x11, 0x00,
+	0x20, 0x00, 0x20, 0x01, 0x20, 0x02, 0xFC, 0x0C, 0x01, 0x00,
+	0x20, 0x03, 0x11, 0x00, 0x00,
+	0x0B,
+	0x12, 0x00,
+	0xFC, 0x0D, 0x01,
+	0x41, 0x00, 0x41, 0x00, 0x20, 0x00, 0xFC, 0x0C, 0x01, 0x00,
+	0xFC, 0x10, 0x00,
+	0x0B,
+];
+
+#[test]
+fn table_init_matches() {
+	let ctx = CompileContext::new_from_opt(1);
+	let cases = [
+		// dst, src, len, call, expected
+		(1, 0, 2, 1, 2),
+		(1, 0, 2, 2, 3),
+		(0, 1, 1, 0, 3),
+		(2, 0, 1, 0, 1),
+		// Zero-length copies are allowed right up to the ends
+		(0, 0, 0, 0, 1),
+		(3, 2, 0, 0, 1),
+	];
+
+	for (dst, src, len, call, expected) in cases {
+		let args = [dst, src, len, call];
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, INIT_MODULE, "run", &i32_params(&args)), expected, "SSA, run{args:?}");
+		assert_eq!(run_lir(&ctx, INIT_MODULE, "run", &i32_params(&args)), expected, "LIR, run{args:?}");
+	}
+}
+
+#[test]
+fn table_init_calls_segment_copy() {
+	let cmds = all_cmds(&compile(INIT_MODULE, &["-O1"]));
+	assert!(cmds.iter().any(|cmd| cmd == "function wasmrunner:__table_init_0_1"), "{cmds:?}");
+	assert!(cmds.iter().any(|cmd| cmd == "scoreboard players set %%elem_1_len reg 0"), "{cmds:?}");
+}
+
+#[test]
+fn elem_drop_empties_segment() {
+	let ctx = CompileContext::new_from_opt(1);
+	let expected = vec![TypedValue::I32(3)];
+	assert_eq!(run_ssa(&ctx, INIT_MODULE, "drop", &i32_params(&[0])), expected);
+	assert_eq!(run_lir(&ctx, INIT_MODULE, "drop", &i32_params(&[0])), expected);
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn table_init_out_of_bounds_ssa() {
+	run_ssa(&CompileContext::new_from_opt(1), INIT_MODULE, "run", &i32_params(&[2, 0, 2, 0]));
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn table_init_out_of_bounds_lir() {
+	run_lir(&CompileContext::new_from_opt(1), INIT_MODULE, "run", &i32_params(&[0, 1, 2, 0]));
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn table_init_after_drop_ssa() {
+	run_ssa(&CompileContext::new_from_opt(1), INIT_MODULE, "drop", &i32_params(&[1]));
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn table_init_after_drop_lir() {
+	run_lir(&CompileContext::new_from_opt(1), INIT_MODULE, "drop", &i32_params(&[1]));
+}
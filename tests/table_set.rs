@@ -0,0 +1,78 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{all_cmds, compile, i32_params, run_lir, run_ssa};
+
+/// A module with a table of two entries, where only the first starts out holding `$a`, exporting:
+///
+/// (func $a (result i32) (i32.const 1))
+/// (func $b (result i32) (i32.const 2))
+/// (elem declare func $b)
+///
+/// (func $run (param $set i32) (param $call i32) (result i32)
+///   (table.set (local.get $set) (ref.func $b))
+///   (call_indirect (result i32) (local.get $call)))
+const TABLE_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0B, 0x02, 0x60, 0x00, 0x01, 0x7F, 0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x04, 0x03, 0x00, 0x00, 0x01,
+	// Table section
+	0x04, 0x04, 0x01, 0x70, 0x00, 0x02,
+	// Export section
+	0x07, 0x07, 0x01, 0x03, b'r', b'u', b'n', 0x00, 0x02,
+	// Element section
+	0x09, 0x0B, 0x02,
+	0x00, 0x41, 0x00, 0x0B, 0x01, 0x00,
+	0x03, 0x00, 0x01, 0x01,
+	// Code section
+	0x0A, 0x19, 0x03,
+	0x04, 0x00, 0x41, 0x01, 0x0B,
+	0x04, 0x00, 0x41, 0x02, 0x0B,
+	0x0D, 0x00,
+	0x20, 0x00, 0xD2, 0x01, 0x26, 0x00,
+	0x20, 0x01, 0x11, 0x00, 0x00,
+	0x0B,
+];
+
+#[test]
+fn table_set_matches() {
+	let cases = [
+		// set, call, expected
+		(1, 1, 2),
+		(1, 0, 1),
+		// Overwriting an initial element
+		(0, 0, 2),
+	];
+
+	let ctx = CompileContext::new_from_opt(1);
+	for (set, call, expected) in cases {
+		let params = i32_params(&[set, call]);
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, TABLE_MODULE, "run", &params), expected, "SSA, run({set}, {call})");
+		assert_eq!(run_lir(&ctx, TABLE_MODULE, "run", &params), expected, "LIR, run({set}, {call})");
+	}
+}
+
+#[test]
+fn table_set_writes_each_entry() {
+	let cmds = all_cmds(&compile(TABLE_MODULE, &["-O1"]));
+
+	for idx in 0..2 {
+		let write = format!(" matches {idx} run scoreboard players operation %%table_0_{idx} reg = ");
+		assert!(cmds.iter().any(|cmd| cmd.starts_with("execute if score ") && cmd.contains(&write)), "{idx}: {cmds:?}");
+	}
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn table_set_out_of_bounds_ssa() {
+	run_ssa(&CompileContext::new_from_opt(1), TABLE_MODULE, "run", &i32_params(&[2, 0]));
+}
+
+#[test]
+#[should_panic(expected = "out of bounds")]
+fn table_set_out_of_bounds_lir() {
+	run_lir(&CompileContext::new_from_opt(1), TABLE_MODULE, "run", &i32_params(&[2, 0]));
+}
@@ -1,6 +1,144 @@
+//! Helpers shared by the integration tests, which include this file with `mod test_common;`
+#![allow(dead_code)]
+
 #[path="sexpr.rs"]
 mod sexpr;
 
+use clap::Parser;
+use command_parser::CommandParse;
+use datapack_common::functions::{Function, command_components::{FunctionIdent, Objective, ScoreHolder}};
+use datapack_vm::Interpreter;
+use wasmcraft::{Args, CompileContext, wasm_file::WasmFile, lir::{DoubleRegister, Register, interp::LirInterpreter}, pack_emitter::TRAP_VAR, ssa::{SsaInstr, interp::{SsaInterpreter, TypedValue}}};
+
+/// Parses the options that come after `wasmcraft test.wasm -o out`
+pub fn context(args: &[&str]) -> CompileContext {
+	let args = ["wasmcraft", "test.wasm", "-o", "out"].iter().chain(args.iter()).copied();
+	CompileContext::new(Args::parse_from(args))
+}
+
+/// Compiles a module all the way to a datapack
+pub fn compile(module: &[u8], args: &[&str]) -> Vec<Function> {
+	compile_with(&context(args), module)
+}
+
+pub fn compile_with(ctx: &CompileContext, module: &[u8]) -> Vec<Function> {
+	let file = WasmFile::from(module);
+	ctx.compute_datapack(&ctx.compute_lir(ctx.compute_ssa(&file)))
+}
+
+/// The ID and commands of every function in a datapack
+pub fn func_cmds(datapack: &[Function]) -> Vec<(String, Vec<String>)> {
+	datapack.iter().map(|func| (func.id.to_string(), func.cmds.iter().map(|cmd| cmd.to_string()).collect())).collect()
+}
+
+/// Every command in a datapack, in order
+pub fn all_cmds(datapack: &[Function]) -> Vec<String> {
+	datapack.iter().flat_map(|func| func.cmds.iter().map(|cmd| cmd.to_string())).collect()
+}
+
+pub fn find_func<'a>(funcs: &'a [(String, Vec<String>)], id: &str) -> &'a [String] {
+	&funcs.iter().find(|(name, _)| name == id).unwrap_or_else(|| panic!("missing {id}")).1
+}
+
+/// Every instruction in the SSA of a module, in block order
+pub fn ssa_instrs(ctx: &CompileContext, module: &[u8]) -> Vec<SsaInstr> {
+	let file = WasmFile::from(module);
+	let program = ctx.compute_ssa(&file);
+	program.code.iter()
+		.flat_map(|func| func.iter())
+		.flat_map(|(_, block)| block.body.iter().cloned())
+		.collect()
+}
+
+/// How many instructions in the SSA of a module match `pred`
+pub fn count_ssa_instrs(ctx: &CompileContext, module: &[u8], pred: impl Fn(&SsaInstr) -> bool) -> usize {
+	ssa_instrs(ctx, module).iter().filter(|instr| pred(instr)).count()
+}
+
+pub fn i32_params(args: &[i32]) -> Vec<TypedValue> {
+	args.iter().copied().map(TypedValue::I32).collect()
+}
+
+/// Calls an export with the SSA interpreter, returning its results
+pub fn run_ssa(ctx: &CompileContext, module: &[u8], name: &str, params: &[TypedValue]) -> Vec<TypedValue> {
+	let file = WasmFile::from(module);
+	let func = file.find_func(name).unwrap_or_else(|| panic!("missing export {name}"));
+	let program = ctx.compute_ssa(&file);
+
+	let mut interp = SsaInterpreter::new(program);
+	interp.call(func, params.to_vec());
+	interp.run_until_halted()
+}
+
+/// Calls an export with the LIR interpreter, returning its results
+pub fn run_lir(ctx: &CompileContext, module: &[u8], name: &str, params: &[TypedValue]) -> Vec<TypedValue> {
+	let file = WasmFile::from(module);
+	let func = file.find_func(name).unwrap_or_else(|| panic!("missing export {name}"));
+	let program = ctx.compute_lir(ctx.compute_ssa(&file));
+
+	let mut interp = LirInterpreter::new(program);
+	interp.call(func, params);
+	interp.run_until_halted()
+}
+
+pub fn run_function(interp: &mut Interpreter, name: &str) {
+	let (_, id) = FunctionIdent::parse_from_command(name).unwrap();
+	let idx = interp.get_func_idx(&id);
+	interp.set_pos(idx);
+	interp.run_to_end().unwrap();
+}
+
+/// Compiles a module and loads it into datapack_vm, running `wasmrunner:init`
+pub fn start_datapack(module: &[u8], args: &[&str]) -> Interpreter {
+	let mut interp = Interpreter::new(compile(module, args), 0);
+	run_function(&mut interp, "wasmrunner:init");
+	interp
+}
+
+pub fn set_reg(interp: &mut Interpreter, reg: Register, value: i32) {
+	let (holder, objective) = reg.scoreboard_pair();
+	interp.set_named_score(&holder, &objective, value);
+}
+
+pub fn get_reg(interp: &Interpreter, reg: Register) -> Option<i32> {
+	let (holder, objective) = reg.scoreboard_pair();
+	interp.get_named_score(&holder, &objective)
+}
+
+/// Calls an export in a datapack, returning its first result as an `I64` if `is_64` is set
+pub fn call_datapack(interp: &mut Interpreter, name: &str, params: &[TypedValue], is_64: bool) -> TypedValue {
+	for (idx, param) in params.iter().enumerate() {
+		let param_reg = DoubleRegister::param(idx as u32);
+		match *param {
+			TypedValue::I32(value) => set_reg(interp, param_reg.lo(), value),
+			TypedValue::I64(value) => {
+				set_reg(interp, param_reg.lo(), value as i32);
+				set_reg(interp, param_reg.hi(), (value >> 32) as i32);
+			}
+		}
+	}
+	run_function(interp, &format!("wasmrunner:{name}"));
+
+	let ret = DoubleRegister::return_reg(0);
+	let lo = get_reg(interp, ret.lo()).expect("nothing was returned");
+	if is_64 {
+		let hi = get_reg(interp, ret.hi()).expect("nothing was returned");
+		TypedValue::I64((lo as u32 as i64) | ((hi as i64) << 32))
+	} else {
+		TypedValue::I32(lo)
+	}
+}
+
+/// Reads a score given as `holder objective`, like the variables in `pack_emitter`
+pub fn get_var(interp: &Interpreter, var: &str) -> Option<i32> {
+	let (holder, objective) = var.split_once(' ').unwrap();
+	interp.get_named_score(&ScoreHolder::new(holder.to_string()).unwrap(), &Objective::new(objective.to_string()).unwrap())
+}
+
+pub fn get_trap(interp: &mut Interpreter) -> Option<i32> {
+	get_var(interp, TRAP_VAR)
+}
+
 /*
 pub mod wasm_suite_prelude {
 	use wasmcraft::{wasm_file::WasmFile, ssa::{interp::{SsaInterpreter, TypedValue}, lir_emitter}, validator::wasm_to_ssa, lir::interp::LirInterpreter};
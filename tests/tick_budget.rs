@@ -0,0 +1,76 @@
+mod test_common;
+use test_common::{func_cmds};
+
+/// A module exporting:
+///
+/// (func $sum (param i32) (result i32) (local i32)
+///   (block
+///     (loop
+///       (br_if 1 (i32.eqz (local.get 0)))
+///       (local.set 1 (i32.add (local.get 1) (local.get 0)))
+///       (local.set 0 (i32.sub (local.get 0) (i32.const 1)))
+///       (br 0)))
+///   (local.get 1))
+const SUM_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b's', b'u', b'm', 0x00, 0x00,
+	// Code section
+	0x0A, 0x23, 0x01,
+	0x21, 0x01, 0x01, 0x7F,
+	0x02, 0x40,
+	0x03, 0x40,
+	0x20, 0x00, 0x45, 0x0D, 0x01,
+	0x20, 0x01, 0x20, 0x00, 0x6A, 0x21, 0x01,
+	0x20, 0x00, 0x41, 0x01, 0x6B, 0x21, 0x00,
+	0x0C, 0x00,
+	0x0B,
+	0x0B,
+	0x20, 0x01,
+	0x0B,
+];
+
+const CHECK: &str = "if score %%commands_run reg >= %%max_commands reg";
+
+fn compile(extra_args: &[&str]) -> Vec<(String, Vec<String>)> {
+	func_cmds(&test_common::compile(SUM_MODULE, &[&["-O1"], extra_args].concat()))
+}
+
+fn loop_checks(funcs: &[(String, Vec<String>)]) -> usize {
+	funcs.iter()
+		.filter(|(id, _)| id.starts_with("wasmrunner:wasm_0"))
+		.flat_map(|(_, cmds)| cmds.iter())
+		.filter(|cmd| cmd.contains(CHECK))
+		.count()
+}
+
+fn init(funcs: &[(String, Vec<String>)]) -> &[String] {
+	&funcs.iter().find(|(id, _)| id == "wasmrunner:init").unwrap().1
+}
+
+#[test]
+fn budget_sets_max_commands() {
+	let funcs = compile(&["--tick-budget", "500"]);
+	assert!(init(&funcs).contains(&"scoreboard players set %%max_commands reg 500".to_string()));
+	assert!(loop_checks(&funcs) > 0);
+}
+
+#[test]
+fn default_budget_comes_from_cost_model() {
+	let funcs = compile(&["--cost-model", "server"]);
+	assert!(init(&funcs).contains(&"scoreboard players set %%max_commands reg 10000".to_string()));
+	assert!(loop_checks(&funcs) > 0);
+}
+
+#[test]
+fn zero_budget_never_yields_in_loops() {
+	let funcs = compile(&["--tick-budget", "0"]);
+	assert!(init(&funcs).contains(&"scoreboard players set %%max_commands reg 2147483647".to_string()));
+	assert_eq!(loop_checks(&funcs), 0, "{funcs:?}");
+}
@@ -0,0 +1,89 @@
+use clap::Parser;
+use wasmcraft::Args;
+
+mod test_common;
+use test_common::{find_func, func_cmds};
+
+/// A module exporting:
+///
+/// (func $tick)
+/// (func $update)
+/// (func $tick_5 (param i32))
+const TICK_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x08, 0x02,
+	0x60, 0x00, 0x00,
+	0x60, 0x01, 0x7F, 0x00,
+	// Function section
+	0x03, 0x04, 0x03, 0x00, 0x00, 0x01,
+	// Export section
+	0x07, 0x1A, 0x03,
+	0x04, b't', b'i', b'c', b'k', 0x00, 0x00,
+	0x06, b'u', b'p', b'd', b'a', b't', b'e', 0x00, 0x01,
+	0x06, b't', b'i', b'c', b'k', b'_', b'5', 0x00, 0x02,
+	// Code section
+	0x0A, 0x0A, 0x03,
+	0x02, 0x00, 0x0B,
+	0x02, 0x00, 0x0B,
+	0x02, 0x00, 0x0B,
+];
+
+fn compile(extra_args: &[&str]) -> Vec<(String, Vec<String>)> {
+	func_cmds(&test_common::compile(TICK_MODULE, &[&["-O1"], extra_args].concat()))
+}
+
+#[test]
+fn tick_export_is_found_by_name() {
+	let funcs = compile(&[]);
+
+	let init = find_func(&funcs, "wasmrunner:init");
+	assert!(init.iter().any(|cmd| cmd == "schedule function wasmrunner:__tick_tick 1"), "{init:?}");
+
+	let tick = find_func(&funcs, "wasmrunner:__tick_tick");
+	assert_eq!(tick, ["schedule function wasmrunner:__tick_tick 1", "function wasmrunner:tick"]);
+
+	// Exports with parameters aren't called on a loop unless asked to
+	assert!(!funcs.iter().any(|(name, _)| name == "wasmrunner:__tick_tick_5"));
+	assert!(!funcs.iter().any(|(name, _)| name == "wasmrunner:__tick_update"));
+}
+
+#[test]
+fn tick_interval_can_be_given() {
+	let funcs = compile(&["--tick", "update=20", "--tick", "tick=4"]);
+
+	let init = find_func(&funcs, "wasmrunner:init");
+	assert!(init.iter().any(|cmd| cmd == "schedule function wasmrunner:__tick_update 1"), "{init:?}");
+
+	let update = find_func(&funcs, "wasmrunner:__tick_update");
+	assert_eq!(update[0], "schedule function wasmrunner:__tick_update 20");
+
+	let tick = find_func(&funcs, "wasmrunner:__tick_tick");
+	assert_eq!(tick[0], "schedule function wasmrunner:__tick_tick 4");
+}
+
+#[test]
+fn stop_clears_tick_loops() {
+	let funcs = compile(&["--controls"]);
+
+	let stop = find_func(&funcs, "wasmrunner:__stop");
+	assert!(stop.iter().any(|cmd| cmd == "schedule clear wasmrunner:__tick_tick"), "{stop:?}");
+}
+
+#[test]
+#[should_panic(expected = "not an export")]
+fn tick_needs_export() {
+	compile(&["--tick", "missing"]);
+}
+
+#[test]
+#[should_panic(expected = "takes parameters")]
+fn tick_export_has_no_parameters() {
+	compile(&["--tick", "tick_5"]);
+}
+
+#[test]
+fn tick_interval_is_checked() {
+	assert!(Args::try_parse_from(["wasmcraft", "tick.wasm", "-o", "out", "--tick", "update=0"]).is_err());
+	assert!(Args::try_parse_from(["wasmcraft", "tick.wasm", "-o", "out", "--tick", "update=often"]).is_err());
+}
@@ -0,0 +1,63 @@
+use wasmcraft::{pack_emitter::{FRAMES_DROPPED_VAR, TIME_SLICE_BUSY_VAR}, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{call_datapack, get_var, run_function, start_datapack};
+
+/// A module exporting:
+///
+/// (global $total (mut i32) (i32.const 0))
+///
+/// (func $draw (local i32)
+///   (loop
+///     (global.set $total (i32.add (global.get $total) (local.get 0)))
+///     (local.set 0 (i32.add (local.get 0) (i32.const 1)))
+///     (br_if 0 (i32.ne (local.get 0) (i32.const 200)))))
+///
+/// (func $total (result i32)
+///   (global.get $total))
+const FRAME_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x08, 0x02,
+	0x60, 0x00, 0x00,
+	0x60, 0x00, 0x01, 0x7F,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x01,
+	// Global section
+	0x06, 0x06, 0x01, 0x7F, 0x01, 0x41, 0x00, 0x0B,
+	// Export section
+	0x07, 0x10, 0x02,
+	0x04, b'd', b'r', b'a', b'w', 0x00, 0x00,
+	0x05, b't', b'o', b't', b'a', b'l', 0x00, 0x01,
+	// Code section
+	0x0A, 0x24, 0x02,
+	0x1D, 0x01, 0x01, 0x7F,
+	0x03, 0x40,
+	0x23, 0x00, 0x20, 0x00, 0x6A, 0x24, 0x00,
+	0x20, 0x00, 0x41, 0x01, 0x6A, 0x21, 0x00,
+	0x20, 0x00, 0x41, 0xC8, 0x01, 0x47, 0x0D, 0x00,
+	0x0B,
+	0x0B,
+	0x04, 0x00, 0x23, 0x00, 0x0B,
+];
+
+/// Runs a frame to the end, returning how many ticks it took
+fn draw_frame(args: &[&str]) -> u64 {
+	let mut interp = start_datapack(FRAME_MODULE, &[&["-O1"], args].concat());
+	let start = interp.tick;
+	run_function(&mut interp, "wasmrunner:draw");
+
+	assert_eq!(call_datapack(&mut interp, "total", &[], false), TypedValue::I32(19900));
+	assert_eq!(get_var(&interp, TIME_SLICE_BUSY_VAR), Some(0));
+	assert_eq!(get_var(&interp, FRAMES_DROPPED_VAR), Some(0));
+	(interp.tick - start) as u64
+}
+
+#[test]
+fn time_sliced_frame_spans_several_ticks_and_finishes() {
+	assert_eq!(draw_frame(&[]), 0);
+
+	// A thousandth of the usual budget isn't enough for the whole loop
+	let ticks = draw_frame(&["--time-slice", "draw=1000"]);
+	assert!(ticks > 1, "{ticks}");
+}
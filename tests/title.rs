@@ -0,0 +1,41 @@
+mod test_common;
+use test_common::{context, run_lir, run_ssa};
+use wasmcraft::ssa::interp::TypedValue;
+
+/// A module with one page of memory holding the string `hi` at 16, exporting:
+///
+/// (import "env" "mc_actionbar" (func $mc_actionbar (param i32 i32)))
+///
+/// (func $run (result i32)
+///   (call $mc_actionbar (i32.const 16) (i32.const 2))
+///   (i32.const 7))
+const ACTIONBAR_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0A, 0x02,
+	0x60, 0x02, 0x7F, 0x7F, 0x00,
+	0x60, 0x00, 0x01, 0x7F,
+	// Import section
+	0x02, 0x14, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x0C, b'm', b'c', b'_', b'a', b'c', b't', b'i', b'o', b'n', b'b', b'a', b'r', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x01,
+	// Code section
+	0x0A, 0x0C, 0x01,
+	0x0A, 0x00, 0x41, 0x10, 0x41, 0x02, 0x10, 0x00, 0x41, 0x07, 0x0B,
+	// Data section
+	0x0B, 0x08, 0x01, 0x00, 0x41, 0x10, 0x0B, 0x02, b'h', b'i',
+];
+
+#[test]
+fn actionbar_runs() {
+	let ctx = context(&["-O1"]);
+	assert_eq!(run_ssa(&ctx, ACTIONBAR_MODULE, "run", &[]), vec![TypedValue::I32(7)], "SSA");
+	assert_eq!(run_lir(&ctx, ACTIONBAR_MODULE, "run", &[]), vec![TypedValue::I32(7)], "LIR");
+}
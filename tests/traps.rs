@@ -0,0 +1,118 @@
+use datapack_vm::Interpreter;
+use wasmcraft::{CompileContext, lir::Register, mc_version::McVersion, pack_emitter::{self, TRAP_VAR}, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{all_cmds, call_datapack, compile, find_func, func_cmds, get_reg, get_trap, i32_params, run_function, run_ssa, start_datapack};
+
+/// (func $check (param i32) (result i32)
+///   (if (i32.eqz (local.get 0)) (then unreachable))
+///   (local.get 0))
+///
+/// (func (export "run") (param i32) (result i32)
+///   (call $check (local.get 0)))
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x00,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x01,
+	// Code section
+	0x0A, 0x14, 0x02,
+	0x0B, 0x00, 0x20, 0x00, 0x45, 0x04, 0x40, 0x00, 0x0B, 0x20, 0x00, 0x0B,
+	0x06, 0x00, 0x20, 0x00, 0x10, 0x00, 0x0B,
+];
+
+/// (global $count (mut i32) (i32.const 0))
+///
+/// (func $check (param i32) (result i32)
+///   (if (i32.eqz (local.get 0)) (then unreachable))
+///   (local.get 0))
+///
+/// (func (export "run") (param i32) (result i32)
+///   (drop (call $check (local.get 0)))
+///   (global.set $count (i32.add (global.get $count) (i32.const 1)))
+///   (global.get $count))
+const COUNT_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01, 0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Function section
+	0x03, 0x03, 0x02, 0x00, 0x00,
+	// Global section
+	0x06, 0x06, 0x01, 0x7F, 0x01, 0x41, 0x00, 0x0B,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'r', b'u', b'n', 0x00, 0x01,
+	// Code section
+	0x0A, 0x1E, 0x02,
+	0x0B, 0x00, 0x20, 0x00, 0x45, 0x04, 0x40, 0x00, 0x0B, 0x20, 0x00, 0x0B,
+	0x10, 0x00, 0x20, 0x00, 0x10, 0x00, 0x1A, 0x23, 0x00, 0x41, 0x01, 0x6A, 0x24, 0x00, 0x23, 0x00, 0x0B,
+];
+
+#[test]
+fn unreachable_reports_trap() {
+	let datapack = compile(MODULE, &[]);
+
+	let cmds = all_cmds(&datapack);
+	assert!(cmds.iter().any(|cmd| cmd.starts_with(r#"tellraw @a [{"text":"Trap: unreachable (wasm function 0, block "#)), "{cmds:?}");
+
+	let mut interp = Interpreter::new(datapack, 0);
+	run_function(&mut interp, "wasmrunner:init");
+	assert_eq!(get_trap(&mut interp), Some(0));
+
+	let (holder, objective) = Register::param_lo(0).scoreboard_pair();
+	interp.set_named_score(&holder, &objective, 0);
+	run_function(&mut interp, "wasmrunner:run");
+	assert_eq!(get_trap(&mut interp), Some(1));
+
+	// Calling an export again starts over
+	interp.set_named_score(&holder, &objective, 5);
+	run_function(&mut interp, "wasmrunner:run");
+	assert_eq!(get_trap(&mut interp), Some(0));
+
+	let (holder, objective) = Register::return_lo(0).scoreboard_pair();
+	assert_eq!(interp.get_named_score(&holder, &objective), Some(5));
+}
+
+#[test]
+fn callers_stop_after_trap() {
+	let mut interp = start_datapack(COUNT_MODULE, &[]);
+
+	assert_eq!(call_datapack(&mut interp, "run", &i32_params(&[5]), false), TypedValue::I32(1));
+	call_datapack(&mut interp, "run", &i32_params(&[0]), false);
+	assert_eq!(get_trap(&mut interp), Some(1));
+
+	// Nothing after the call that trapped ran, so the count is the same
+	assert_eq!(get_reg(&interp, Register::global_lo(0)), Some(1));
+
+	assert_eq!(call_datapack(&mut interp, "run", &i32_params(&[5]), false), TypedValue::I32(2));
+}
+
+#[test]
+fn callers_return_after_trap_with_return_run() {
+	let datapack = compile(COUNT_MODULE, &[]);
+	let funcs = func_cmds(&datapack);
+
+	let cmds = find_func(&funcs, "wasmrunner:wasm_1_0");
+	let call = cmds.iter().position(|cmd| cmd.ends_with("function wasmrunner:wasm_0_0")).unwrap();
+	assert_eq!(cmds[call + 1], format!("# !RETURN: execute if score {TRAP_VAR} matches 1.. run return 0"));
+
+	// Older versions gate the commands after the call instead
+	let gate = format!("execute unless score {TRAP_VAR} matches 1.. run ");
+	assert!(cmds[call + 2..].iter().all(|cmd| cmd.starts_with('#') || cmd.starts_with(&gate)), "{cmds:?}");
+
+	let func = datapack.iter().find(|func| func.id.to_string() == "wasmrunner:wasm_1_0").unwrap();
+	let lines = pack_emitter::written_lines(func, McVersion::new(20, 2)).unwrap();
+	assert!(lines.iter().all(|line| !line.starts_with(&gate)), "{lines:?}");
+	let call = lines.iter().position(|line| line == "function wasmrunner:wasm_0_0").unwrap();
+	assert_eq!(lines[call + 1], format!("execute if score {TRAP_VAR} matches 1.. run return 0"));
+}
+
+#[test]
+#[should_panic(expected = "trap: unreachable")]
+fn interpreter_panics_on_trap() {
+	run_ssa(&CompileContext::new_from_opt(1), MODULE, "run", &i32_params(&[0]));
+}
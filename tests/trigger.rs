@@ -0,0 +1,56 @@
+use clap::Parser;
+use wasmcraft::Args;
+
+mod test_common;
+use test_common::{find_func, func_cmds};
+
+/// A module exporting:
+///
+/// (func $jump_to (param $level i32) (param $player i32))
+const TRIGGER_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x06, 0x01,
+	0x60, 0x02, 0x7F, 0x7F, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x0B, 0x01,
+	0x07, b'j', b'u', b'm', b'p', b'_', b't', b'o', 0x00, 0x00,
+	// Code section
+	0x0A, 0x04, 0x01,
+	0x02, 0x00, 0x0B,
+];
+
+fn compile(trigger: &str) -> Vec<(String, Vec<String>)> {
+	func_cmds(&test_common::compile(TRIGGER_MODULE, &["-O1", "--trigger", trigger]))
+}
+
+#[test]
+fn trigger_calls_export() {
+	let funcs = compile("jump_to=level");
+
+	let init = find_func(&funcs, "wasmrunner:init");
+	assert!(init.iter().any(|cmd| cmd == "scoreboard objectives add level trigger"), "{init:?}");
+
+	let events = find_func(&funcs, "wasmrunner:__player_events");
+	assert!(events.iter().any(|cmd| cmd == "scoreboard players enable @a level"), "{events:?}");
+	assert!(events.iter().any(|cmd| cmd.ends_with("run function wasmrunner:__trigger_jump_to")), "{events:?}");
+
+	let call = find_func(&funcs, "wasmrunner:__trigger_jump_to");
+	assert!(call.iter().any(|cmd| cmd.ends_with("= @s level")), "{call:?}");
+	assert!(call.iter().any(|cmd| cmd.ends_with("= @s wasm_player_id")), "{call:?}");
+	assert_eq!(call.last().unwrap(), "function wasmrunner:jump_to");
+}
+
+#[test]
+#[should_panic(expected = "not an export")]
+fn trigger_needs_export() {
+	compile("missing");
+}
+
+#[test]
+fn trigger_objective_is_checked() {
+	assert!(Args::try_parse_from(["wasmcraft", "trigger.wasm", "-o", "out", "--trigger", "jump_to=a_very_long_objective"]).is_err());
+	assert!(Args::try_parse_from(["wasmcraft", "trigger.wasm", "-o", "out", "--trigger", "jump_to=no spaces"]).is_err());
+}
@@ -0,0 +1,105 @@
+use wasmcraft::{CompileContext, ssa::interp::TypedValue};
+
+mod test_common;
+use test_common::{call_datapack, run_lir, run_ssa, start_datapack};
+
+/// (func (export "i32_f32_s") (param i32) (result i32)
+///   (i32.trunc_sat_f32_s (f32.reinterpret_i32 (local.get 0))))
+///
+/// and likewise `i32_f32_u`, `i32_f64_s` and `i32_f64_u`, taking an i64 for the f64 ones,
+/// followed by the same for i64 named `i64_f32_s` and so on.
+const MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x15, 0x04,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	0x60, 0x01, 0x7E, 0x01, 0x7F,
+	0x60, 0x01, 0x7F, 0x01, 0x7E,
+	0x60, 0x01, 0x7E, 0x01, 0x7E,
+	// Function section
+	0x03, 0x09, 0x08, 0x00, 0x00, 0x01, 0x01, 0x02, 0x02, 0x03, 0x03,
+	// Export section
+	0x07, 0x61, 0x08,
+	0x09, b'i', b'3', b'2', b'_', b'f', b'3', b'2', b'_', b's', 0x00, 0x00,
+	0x09, b'i', b'3', b'2', b'_', b'f', b'3', b'2', b'_', b'u', 0x00, 0x01,
+	0x09, b'i', b'3', b'2', b'_', b'f', b'6', b'4', b'_', b's', 0x00, 0x02,
+	0x09, b'i', b'3', b'2', b'_', b'f', b'6', b'4', b'_', b'u', 0x00, 0x03,
+	0x09, b'i', b'6', b'4', b'_', b'f', b'3', b'2', b'_', b's', 0x00, 0x04,
+	0x09, b'i', b'6', b'4', b'_', b'f', b'3', b'2', b'_', b'u', 0x00, 0x05,
+	0x09, b'i', b'6', b'4', b'_', b'f', b'6', b'4', b'_', b's', 0x00, 0x06,
+	0x09, b'i', b'6', b'4', b'_', b'f', b'6', b'4', b'_', b'u', 0x00, 0x07,
+	// Code section
+	0x0A, 0x41, 0x08,
+	0x07, 0x00, 0x20, 0x00, 0xBE, 0xFC, 0x00, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0xBE, 0xFC, 0x01, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0xBF, 0xFC, 0x02, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0xBF, 0xFC, 0x03, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0xBE, 0xFC, 0x04, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0xBE, 0xFC, 0x05, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0xBF, 0xFC, 0x06, 0x0B,
+	0x07, 0x00, 0x20, 0x00, 0xBF, 0xFC, 0x07, 0x0B,
+];
+
+/// ±0, NaNs, ±inf, the limits of each integer type and the values next to them, and the largest finite values
+const F32_VALUES: [u32; 20] = [
+	0x0000_0000, 0x8000_0000,
+	0x7FC0_0000, 0xFFC0_0000,
+	0x7F80_0000, 0xFF80_0000,
+	0x3FC0_0000, 0xBFC0_0000, 0xBF00_0000,
+	0x4EFF_FFFF, 0x4F00_0000, 0xCF00_0000, 0xCF00_0001,
+	0x4F7F_FFFF, 0x4F80_0000,
+	0x5EFF_FFFF, 0x5F00_0000, 0xDF00_0000, 0x5F80_0000,
+	0x7F7F_FFFF,
+];
+
+/// The same kinds of values as `F32_VALUES`, and NaNs whose high word is the same as infinity's
+const F64_VALUES: [u64; 23] = [
+	0x0000_0000_0000_0000, 0x8000_0000_0000_0000,
+	0x7FF8_0000_0000_0000, 0xFFF8_0000_0000_0000, 0x7FF0_0000_0000_0001, 0xFFF0_0000_0000_0001,
+	0x7FF0_0000_0000_0000, 0xFFF0_0000_0000_0000,
+	0x3FF8_0000_0000_0000, 0xBFF8_0000_0000_0000, 0xBFE0_0000_0000_0000,
+	0x41DF_FFFF_FFE0_0000, 0x41E0_0000_0000_0000, 0xC1E0_0000_0010_0000, 0xC1E0_0000_0020_0000,
+	0x41EF_FFFF_FFE0_0000, 0x41F0_0000_0000_0000,
+	0x43DF_FFFF_FFFF_FFFF, 0x43E0_0000_0000_0000, 0xC3E0_0000_0000_0000, 0xC3E0_0000_0000_0001, 0x43F0_0000_0000_0000,
+	0x7FEF_FFFF_FFFF_FFFF,
+];
+
+/// Every call checked: the name of the export, its parameter, and the result, which Rust's saturating casts give
+fn cases() -> Vec<(&'static str, TypedValue, TypedValue)> {
+	let mut cases = Vec::new();
+	for bits in F32_VALUES {
+		let val = f32::from_bits(bits);
+		let param = TypedValue::I32(bits as i32);
+		cases.push(("i32_f32_s", param, TypedValue::I32(val as i32)));
+		cases.push(("i32_f32_u", param, TypedValue::I32(val as u32 as i32)));
+		cases.push(("i64_f32_s", param, TypedValue::I64(val as i64)));
+		cases.push(("i64_f32_u", param, TypedValue::I64(val as u64 as i64)));
+	}
+	for bits in F64_VALUES {
+		let val = f64::from_bits(bits);
+		let param = TypedValue::I64(bits as i64);
+		cases.push(("i32_f64_s", param, TypedValue::I32(val as i32)));
+		cases.push(("i32_f64_u", param, TypedValue::I32(val as u32 as i32)));
+		cases.push(("i64_f64_s", param, TypedValue::I64(val as i64)));
+		cases.push(("i64_f64_u", param, TypedValue::I64(val as u64 as i64)));
+	}
+	cases
+}
+
+#[test]
+fn trunc_sat_interpreters() {
+	let ctx = CompileContext::new_from_opt(1);
+	for (name, param, expected) in cases() {
+		assert_eq!(run_ssa(&ctx, MODULE, name, &[param]), vec![expected], "SSA, {name}({param:?})");
+		assert_eq!(run_lir(&ctx, MODULE, name, &[param]), vec![expected], "LIR, {name}({param:?})");
+	}
+}
+
+#[test]
+fn trunc_sat_datapack() {
+	let mut interp = start_datapack(MODULE, &["-O1"]);
+	for (name, param, expected) in cases() {
+		let actual = call_datapack(&mut interp, name, &[param], matches!(expected, TypedValue::I64(_)));
+		assert_eq!(actual, expected, "{name}({param:?})");
+	}
+}
@@ -0,0 +1,55 @@
+mod test_common;
+use test_common::{context, run_ssa, ssa_instrs};
+use wasmcraft::ssa::{SsaInstr, interp::TypedValue};
+
+/// A module exporting:
+///
+/// (import "env" "turtle_turn_right" (func $turtle_turn_right))
+/// (import "env" "turtle_turn_left" (func $turtle_turn_left))
+/// (import "env" "turtle_forward" (func $turtle_forward (param i32)))
+///
+/// (func $walk (param $blocks i32)
+///   (call $turtle_turn_right)
+///   (call $turtle_turn_right)
+///   (call $turtle_turn_left)
+///   (call $turtle_forward (local.get $blocks)))
+const TURTLE_MOVEMENT_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x08, 0x02,
+	0x60, 0x00, 0x00,
+	0x60, 0x01, 0x7F, 0x00,
+	// Import section
+	0x02, 0x45, 0x03,
+	0x03, b'e', b'n', b'v',
+	0x11, b't', b'u', b'r', b't', b'l', b'e', b'_', b't', b'u', b'r', b'n', b'_', b'r', b'i', b'g', b'h', b't', 0x00, 0x00,
+	0x03, b'e', b'n', b'v',
+	0x10, b't', b'u', b'r', b't', b'l', b'e', b'_', b't', b'u', b'r', b'n', b'_', b'l', b'e', b'f', b't', 0x00, 0x00,
+	0x03, b'e', b'n', b'v',
+	0x0E, b't', b'u', b'r', b't', b'l', b'e', b'_', b'f', b'o', b'r', b'w', b'a', b'r', b'd', 0x00, 0x01,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Export section
+	0x07, 0x08, 0x01,
+	0x04, b'w', b'a', b'l', b'k', 0x00, 0x03,
+	// Code section
+	0x0A, 0x0E, 0x01,
+	0x0C, 0x00, 0x10, 0x00, 0x10, 0x00, 0x10, 0x01, 0x20, 0x00, 0x10, 0x02, 0x0B,
+];
+
+#[test]
+fn turtle_movement_is_lowered() {
+	let ctx = context(&["-O1"]);
+	let instrs = ssa_instrs(&ctx, TURTLE_MOVEMENT_MODULE);
+
+	let turns = instrs.iter()
+		.filter_map(|instr| match instr {
+			SsaInstr::TurtleTurn { degrees } => Some(*degrees),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+	assert_eq!(turns, [90, 90, -90]);
+	assert_eq!(instrs.iter().filter(|instr| matches!(instr, SsaInstr::TurtleForward(_))).count(), 1);
+
+	assert_eq!(run_ssa(&ctx, TURTLE_MOVEMENT_MODULE, "walk", &[TypedValue::I32(3)]), Vec::new());
+}
@@ -0,0 +1,56 @@
+mod test_common;
+use test_common::{all_cmds, compile, context, ssa_instrs};
+use wasmcraft::ssa::SsaInstr;
+
+/// A module with one page of memory holding the string `{Items:[]}` at 16, exporting:
+///
+/// (import "env" "turtle_set_block_nbt" (func $turtle_set_block_nbt (param i32 i32 i32)))
+///
+/// (func $place
+///   (call $turtle_set_block_nbt (i32.const 1) (i32.const 16) (i32.const 10)))
+const TURTLE_NBT_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0A, 0x02,
+	0x60, 0x03, 0x7F, 0x7F, 0x7F, 0x00,
+	0x60, 0x00, 0x00,
+	// Import section
+	0x02, 0x1C, 0x01,
+	0x03, b'e', b'n', b'v',
+	0x14, b't', b'u', b'r', b't', b'l', b'e', b'_', b's', b'e', b't', b'_', b'b', b'l', b'o', b'c', b'k', b'_', b'n', b'b', b't', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x01,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x09, 0x01,
+	0x05, b'p', b'l', b'a', b'c', b'e', 0x00, 0x01,
+	// Code section
+	0x0A, 0x0C, 0x01,
+	0x0A, 0x00, 0x41, 0x01, 0x41, 0x10, 0x41, 0x0A, 0x10, 0x00, 0x0B,
+	// Data section
+	0x0B, 0x10, 0x01, 0x00, 0x41, 0x10, 0x0B, 0x0A,
+	b'{', b'I', b't', b'e', b'm', b's', b':', b'[', b']', b'}',
+];
+
+#[test]
+fn constant_nbt_is_copied() {
+	let ctx = context(&["-O1"]);
+	let instrs = ssa_instrs(&ctx, TURTLE_NBT_MODULE);
+	let nbts = instrs.iter()
+		.filter_map(|instr| match instr {
+			SsaInstr::TurtleSetBlockNbt { nbt, .. } => Some(nbt.as_str()),
+			_ => None,
+		})
+		.collect::<Vec<_>>();
+
+	assert_eq!(nbts, ["{Items:[]}"]);
+}
+
+#[test]
+fn setblock_includes_nbt() {
+	let datapack = compile(TURTLE_NBT_MODULE, &["-O1"]);
+
+	let cmds = all_cmds(&datapack);
+	assert!(cmds.iter().any(|cmd| cmd.contains("setblock ~ ~ ~ ") && cmd.contains("{Items:[]}")), "{cmds:?}");
+}
@@ -0,0 +1,49 @@
+mod test_common;
+use test_common::{all_cmds, compile, context, ssa_instrs};
+use wasmcraft::ssa::SsaInstr;
+
+/// A module exporting:
+///
+/// (import "env" "turtle_light_level" (func $turtle_light_level (result i32)))
+/// (import "env" "turtle_is_solid" (func $turtle_is_solid (result i32)))
+///
+/// (func $check (result i32)
+///   (i32.add (call $turtle_light_level) (call $turtle_is_solid)))
+const TURTLE_QUERY_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x05, 0x01,
+	0x60, 0x00, 0x01, 0x7F,
+	// Import section
+	0x02, 0x30, 0x02,
+	0x03, b'e', b'n', b'v',
+	0x12, b't', b'u', b'r', b't', b'l', b'e', b'_', b'l', b'i', b'g', b'h', b't', b'_', b'l', b'e', b'v', b'e', b'l', 0x00, 0x00,
+	0x03, b'e', b'n', b'v',
+	0x0F, b't', b'u', b'r', b't', b'l', b'e', b'_', b'i', b's', b'_', b's', b'o', b'l', b'i', b'd', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x09, 0x01,
+	0x05, b'c', b'h', b'e', b'c', b'k', 0x00, 0x02,
+	// Code section
+	0x0A, 0x09, 0x01,
+	0x07, 0x00, 0x10, 0x00, 0x10, 0x01, 0x6A, 0x0B,
+];
+
+#[test]
+fn queries_are_lowered() {
+	let ctx = context(&["-O1"]);
+	let instrs = ssa_instrs(&ctx, TURTLE_QUERY_MODULE);
+
+	assert!(instrs.iter().any(|instr| matches!(instr, SsaInstr::TurtleLightLevel(_))));
+	assert!(instrs.iter().any(|instr| matches!(instr, SsaInstr::TurtleCheckBlock { solid: true, .. })));
+}
+
+#[test]
+fn queries_check_the_turtle_position() {
+	let datapack = compile(TURTLE_QUERY_MODULE, &["-O1"]);
+
+	let cmds = all_cmds(&datapack);
+	assert!(cmds.iter().any(|cmd| cmd.contains("if predicate wasmrunner:light/15")), "{cmds:?}");
+	assert!(cmds.iter().any(|cmd| cmd.contains("if block ~ ~ ~ minecraft:lava")), "{cmds:?}");
+}
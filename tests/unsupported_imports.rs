@@ -0,0 +1,79 @@
+use wasmcraft::wasm_file::WasmFile;
+
+mod test_common;
+use test_common::{all_cmds, compile, context};
+
+/// A module importing functions that aren't intrinsics, exporting:
+///
+/// (import "env" "prnt" (func $prnt (param i32)))
+/// (import "game" "spawn" (func $spawn (param i32)))
+/// (import "wasi_snapshot_preview1" "random_get" (func $random_get (param i32)))
+/// (import "env" "unused" (func $unused (param i32)))
+///
+/// (func $run
+///   (call $prnt (i32.const 0))
+///   (call $spawn (i32.const 0))
+///   (call $random_get (i32.const 0)))
+const UNSUPPORTED_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x05, 0x01, 0x60, 0x01, 0x7F, 0x00,
+	// Import section
+	0x02, 0x4A, 0x04,
+	0x03, b'e', b'n', b'v', 0x04, b'p', b'r', b'n', b't', 0x00, 0x00,
+	0x04, b'g', b'a', b'm', b'e', 0x05, b's', b'p', b'a', b'w', b'n', 0x00, 0x00,
+	0x16, b'w', b'a', b's', b'i', b'_', b's', b'n', b'a', b'p', b's', b'h', b'o', b't', b'_', b'p', b'r', b'e', b'v', b'i', b'e', b'w', b'1',
+	0x0A, b'r', b'a', b'n', b'd', b'o', b'm', b'_', b'g', b'e', b't', 0x00, 0x00,
+	0x03, b'e', b'n', b'v', 0x06, b'u', b'n', b'u', b's', b'e', b'd', 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01, 0x03, b'r', b'u', b'n', 0x00, 0x04,
+	// Code section
+	0x0A, 0x10, 0x01,
+	0x0E, 0x00,
+	0x41, 0x00, 0x10, 0x00,
+	0x41, 0x00, 0x10, 0x01,
+	0x41, 0x00, 0x10, 0x02,
+	0x0B,
+];
+
+fn compile_ssa(args: &[&str]) {
+	context(args).compute_ssa(&WasmFile::from(UNSUPPORTED_MODULE));
+}
+
+#[test]
+#[should_panic(expected = "unsupported import `env.prnt` [I32] -> []\n  called by function(s): 4\n  help: did you mean the intrinsic `env.print`? Rename the import or pass `--import-map env.prnt=env.print`")]
+fn typos_suggest_the_nearest_intrinsic() {
+	compile_ssa(&["-O1"]);
+}
+
+#[test]
+#[should_panic(expected = "unsupported import `game.spawn` [I32] -> []\n  called by function(s): 4\n  help: use --import-map or --import-map-file to redirect it to a supported import")]
+fn unknown_imports_suggest_an_import_map() {
+	compile_ssa(&["-O1"]);
+}
+
+#[test]
+#[should_panic(expected = "unsupported import `wasi_snapshot_preview1.random_get` [I32] -> []\n  called by function(s): 4\n  help: implement it in the program as an exported function named `__wasi_impl_random_get`")]
+fn wasi_imports_suggest_implementing_them() {
+	compile_ssa(&["-O1"]);
+}
+
+#[test]
+#[should_panic(expected = "unsupported import `game.spawn` [I32] -> [] (mapped to `env.spwn`)")]
+fn mapped_imports_name_both() {
+	compile_ssa(&["-O1", "--import-map", "game.spawn=env.spwn"]);
+}
+
+/// `env.unused` isn't an intrinsic either, but nothing calls it
+#[test]
+fn uncalled_imports_are_allowed() {
+	let cmds = all_cmds(&compile(UNSUPPORTED_MODULE, &[
+		"-O1",
+		"--import-map", "env.prnt=env.print",
+		"--import-map", "game.spawn=env.print",
+		"--import-map", "wasi_snapshot_preview1.random_get=env.print",
+	]));
+	assert!(cmds.iter().any(|cmd| cmd.starts_with(r#"tellraw @a [{"text":"Printed "}"#)), "{cmds:?}");
+}
@@ -0,0 +1,103 @@
+use wasmcraft::ssa::interp::TypedValue;
+
+mod test_common;
+use test_common::{all_cmds, compile, context, i32_params, run_lir, run_ssa};
+
+/// A module with one page of memory, importing `args_sizes_get`, `args_get`, `environ_sizes_get`, and `environ_get`
+/// from `wasi_snapshot_preview1`, exporting:
+///
+/// (func $args (param $byte i32) (result i32)
+///   (drop (call $args_sizes_get (i32.const 0) (i32.const 4)))
+///   (drop (call $args_get (i32.const 16) (i32.const 64)))
+///   (i32.add
+///     (i32.add
+///       (i32.mul (i32.load (i32.const 0)) (i32.const 10000))
+///       (i32.mul (i32.load (i32.const 4)) (i32.const 100)))
+///     (i32.load8_u (i32.add (i32.load (i32.const 20)) (local.get $byte)))))
+///
+/// (func $env (param $byte i32) (result i32)
+///   The same as `$args`, but calling `$environ_sizes_get` and `$environ_get`)
+const WASI_ARGS_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x0C, 0x02,
+	0x60, 0x02, 0x7F, 0x7F, 0x01, 0x7F,
+	0x60, 0x01, 0x7F, 0x01, 0x7F,
+	// Import section
+	0x02, 0x9B, 0x01, 0x04,
+	0x16, b'w', b'a', b's', b'i', b'_', b's', b'n', b'a', b'p', b's', b'h', b'o', b't', b'_', b'p', b'r', b'e', b'v', b'i', b'e', b'w', b'1',
+	0x0E, b'a', b'r', b'g', b's', b'_', b's', b'i', b'z', b'e', b's', b'_', b'g', b'e', b't', 0x00, 0x00,
+	0x16, b'w', b'a', b's', b'i', b'_', b's', b'n', b'a', b'p', b's', b'h', b'o', b't', b'_', b'p', b'r', b'e', b'v', b'i', b'e', b'w', b'1',
+	0x08, b'a', b'r', b'g', b's', b'_', b'g', b'e', b't', 0x00, 0x00,
+	0x16, b'w', b'a', b's', b'i', b'_', b's', b'n', b'a', b'p', b's', b'h', b'o', b't', b'_', b'p', b'r', b'e', b'v', b'i', b'e', b'w', b'1',
+	0x11, b'e', b'n', b'v', b'i', b'r', b'o', b'n', b'_', b's', b'i', b'z', b'e', b's', b'_', b'g', b'e', b't', 0x00, 0x00,
+	0x16, b'w', b'a', b's', b'i', b'_', b's', b'n', b'a', b'p', b's', b'h', b'o', b't', b'_', b'p', b'r', b'e', b'v', b'i', b'e', b'w', b'1',
+	0x0B, b'e', b'n', b'v', b'i', b'r', b'o', b'n', b'_', b'g', b'e', b't', 0x00, 0x00,
+	// Function section
+	0x03, 0x03, 0x02, 0x01, 0x01,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x0E, 0x02,
+	0x04, b'a', b'r', b'g', b's', 0x00, 0x04,
+	0x03, b'e', b'n', b'v', 0x00, 0x05,
+	// Code section
+	0x0A, 0x65, 0x02,
+	0x31, 0x00,
+	0x41, 0x00, 0x41, 0x04, 0x10, 0x00, 0x1A,
+	0x41, 0x10, 0x41, 0xC0, 0x00, 0x10, 0x01, 0x1A,
+	0x41, 0x00, 0x28, 0x02, 0x00, 0x41, 0x90, 0xCE, 0x00, 0x6C,
+	0x41, 0x04, 0x28, 0x02, 0x00, 0x41, 0xE4, 0x00, 0x6C,
+	0x6A,
+	0x41, 0x14, 0x28, 0x02, 0x00, 0x20, 0x00, 0x6A, 0x2D, 0x00, 0x00,
+	0x6A,
+	0x0B,
+	0x31, 0x00,
+	0x41, 0x00, 0x41, 0x04, 0x10, 0x02, 0x1A,
+	0x41, 0x10, 0x41, 0xC0, 0x00, 0x10, 0x03, 0x1A,
+	0x41, 0x00, 0x28, 0x02, 0x00, 0x41, 0x90, 0xCE, 0x00, 0x6C,
+	0x41, 0x04, 0x28, 0x02, 0x00, 0x41, 0xE4, 0x00, 0x6C,
+	0x6A,
+	0x41, 0x14, 0x28, 0x02, 0x00, 0x20, 0x00, 0x6A, 0x2D, 0x00, 0x00,
+	0x6A,
+	0x0B,
+];
+
+const WASI_ARGS: &[&str] = &[
+	"-O1",
+	"--wasi-arg", "prog", "--wasi-arg", "hello",
+	"--wasi-env", "A=x", "--wasi-env", "B=yz", "--wasi-env", "A=b",
+];
+
+#[test]
+fn args_and_environ_are_baked_in() {
+	let cases = [
+		// name, byte, expected
+		// Two arguments, 11 bytes including null terminators, reading from "hello"
+		("args", 0, 21_204),
+		("args", 5, 21_100),
+		// The later `A=b` replaces `A=x`, reading from "B=yz"
+		("env", 0, 20_966),
+		("env", 2, 21_021),
+	];
+
+	let ctx = context(WASI_ARGS);
+	for (name, byte, expected) in cases {
+		let expected = vec![TypedValue::I32(expected)];
+		assert_eq!(run_ssa(&ctx, WASI_ARGS_MODULE, name, &i32_params(&[byte])), expected, "SSA, {name}({byte})");
+		assert_eq!(run_lir(&ctx, WASI_ARGS_MODULE, name, &i32_params(&[byte])), expected, "LIR, {name}({byte})");
+	}
+}
+
+#[test]
+fn strings_are_stored_a_word_at_a_time() {
+	let cmds = all_cmds(&compile(WASI_ARGS_MODULE, WASI_ARGS));
+	let stores = |word: &[u8; 4]| {
+		let word = i32::from_le_bytes(*word).to_string();
+		cmds.iter().any(|cmd| cmd.split([' ', '%']).any(|part| part == word))
+	};
+
+	assert!(stores(b"prog"), "{cmds:?}");
+	assert!(stores(b"A=b\0"), "{cmds:?}");
+	assert!(!stores(b"A=x\0"), "{cmds:?}");
+}
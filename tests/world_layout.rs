@@ -0,0 +1,42 @@
+mod test_common;
+use test_common::{all_cmds, compile};
+
+/// A module with one page of memory, exporting:
+///
+/// (func $store
+///   (i32.store (i32.const 8) (i32.const 5)))
+const STORE_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x04, 0x01,
+	0x60, 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Memory section
+	0x05, 0x03, 0x01, 0x00, 0x01,
+	// Export section
+	0x07, 0x09, 0x01,
+	0x05, b's', b't', b'o', b'r', b'e', 0x00, 0x00,
+	// Code section
+	0x0A, 0x0B, 0x01,
+	0x09, 0x00, 0x41, 0x08, 0x41, 0x05, 0x36, 0x02, 0x00, 0x0B,
+];
+
+fn compile_with_args(args: &[&str]) -> Vec<String> {
+	all_cmds(&compile(STORE_MODULE, &[&["-O1"], args].concat()))
+}
+
+#[test]
+fn memory_is_placed_at_origin() {
+	let cmds = compile_with_args(&["--world-height", "-64..320", "--memory-origin", "100,-64,200", "--turtle-origin", "90,70,190"]);
+
+	assert!(cmds.iter().any(|cmd| cmd.starts_with("fill 100 -64 200 101 191 231 ")), "{cmds:?}");
+	assert!(cmds.iter().any(|cmd| cmd.contains("block 100 -64 202 ")), "{cmds:?}");
+	assert!(cmds.iter().any(|cmd| cmd.starts_with("summon minecraft:marker 90 70 190 ")), "{cmds:?}");
+}
+
+#[test]
+#[should_panic(expected = "outside of the world height range")]
+fn memory_must_fit_in_world() {
+	compile_with_args(&["--world-height", "-64..320", "--memory-origin", "0,100,0"]);
+}
@@ -0,0 +1,66 @@
+use std::io::Read;
+
+use datapack_common::functions::Function;
+use wasmcraft::{mc_version::McVersion, pack_emitter};
+
+mod test_common;
+
+/// A module exporting:
+///
+/// (func $nop)
+const NOP_MODULE: &[u8] = &[
+	0x00, 0x61, 0x73, 0x6D, 0x01, 0x00, 0x00, 0x00,
+	// Type section
+	0x01, 0x04, 0x01,
+	0x60, 0x00, 0x00,
+	// Function section
+	0x03, 0x02, 0x01, 0x00,
+	// Export section
+	0x07, 0x07, 0x01,
+	0x03, b'n', b'o', b'p', 0x00, 0x00,
+	// Code section
+	0x0A, 0x04, 0x01,
+	0x02, 0x00, 0x0B,
+];
+
+fn compile() -> Vec<Function> {
+	test_common::compile(NOP_MODULE, &["-O1", "--zip"])
+}
+
+fn read_entry(archive: &mut zip::ZipArchive<std::fs::File>, path: &str) -> String {
+	let mut contents = String::new();
+	archive.by_name(path).unwrap_or_else(|err| panic!("missing {path}: {err}")).read_to_string(&mut contents).unwrap();
+	contents
+}
+
+#[test]
+fn zip_has_same_files_as_folder() {
+	let datapack = compile();
+
+	let folder = std::env::temp_dir().join("wasmcraft_zip_output");
+	let _ = std::fs::remove_dir_all(&folder);
+	pack_emitter::persist_program(&folder, &datapack, McVersion::default());
+
+	let zip_path = std::env::temp_dir().join("wasmcraft_zip_output.zip");
+	pack_emitter::persist_program_zip(&zip_path, &datapack, McVersion::default());
+
+	let mut archive = zip::ZipArchive::new(std::fs::File::open(&zip_path).unwrap()).unwrap();
+	assert!(read_entry(&mut archive, "pack.mcmeta").contains(r#""pack_format": 12"#));
+
+	for path in ["data/wasmrunner/functions/init.mcfunction", "data/wasmrunner/functions/nop.mcfunction"] {
+		assert_eq!(read_entry(&mut archive, path), std::fs::read_to_string(folder.join(path)).unwrap(), "{path}");
+	}
+
+}
+
+#[test]
+fn zip_uses_folders_of_version() {
+	let datapack = compile();
+
+	let zip_path = std::env::temp_dir().join("wasmcraft_zip_output_1_21.zip");
+	pack_emitter::persist_program_zip(&zip_path, &datapack, McVersion::new(21, 0));
+
+	let archive = zip::ZipArchive::new(std::fs::File::open(&zip_path).unwrap()).unwrap();
+	assert!(archive.file_names().any(|name| name == "data/wasmrunner/function/init.mcfunction"));
+	assert!(archive.file_names().all(|name| !name.starts_with("data/wasmrunner/functions/")));
+}